@@ -0,0 +1,193 @@
+//! Telling videos apart by their content instead of their extension.
+//!
+//! Folder scans in probe mode read the first bytes of every file and
+//! recognise the common containers by their signature. Files named like a
+//! video that match none are asked of ffprobe, so a misnamed video is still
+//! found and a text file called `.mkv` is not. Verdicts are cached by path,
+//! size and modification time so rescanning a library stays fast.
+
+use crate::config::{InputConfig, VideoDetection};
+use crate::error::AppError;
+use crate::queue::is_video_file;
+use crate::utils::{data_dir, tools};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+use tracing::warn;
+
+/// Bytes read to recognise a container; MPEG-TS needs a second packet
+const HEADER_LEN: usize = 4096;
+
+/// Transport stream packet sizes: plain, and Blu-ray/AVCHD with a 4-byte timestamp
+const TS_PACKET: usize = 188;
+const M2TS_PACKET: usize = 192;
+
+/// Container a file's first bytes belong to, if they look like a video
+pub fn sniff_container(header: &[u8]) -> Option<&'static str> {
+    let at = |offset: usize, magic: &[u8]| header.get(offset..offset + magic.len()) == Some(magic);
+
+    if at(0, &[0x1A, 0x45, 0xDF, 0xA3]) {
+        return Some("matroska");
+    }
+    if at(4, b"ftyp") {
+        // Audio-only and still-image brands of the ISO family
+        let brand = header.get(8..12).unwrap_or_default();
+        let audio_or_image = [b"M4A ", b"M4B ", b"M4P ", b"heic", b"avif", b"mif1"]
+            .iter()
+            .any(|b| brand == *b);
+        return (!audio_or_image).then_some("mp4");
+    }
+    if at(4, b"moov") || at(4, b"mdat") || at(4, b"wide") || at(4, b"free") {
+        return Some("mov");
+    }
+    if at(0, b"RIFF") && at(8, b"AVI ") {
+        return Some("avi");
+    }
+    if at(0, &[0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11]) {
+        return Some("asf");
+    }
+    if at(0, b"FLV") {
+        return Some("flv");
+    }
+    if at(0, &[0x00, 0x00, 0x01, 0xBA]) {
+        return Some("mpeg");
+    }
+    if at(0, b"OggS") {
+        // Only Theora is video; Vorbis, Opus and FLAC streams are audio
+        return header.windows(6).any(|w| w == b"theora").then_some("ogg");
+    }
+    let synced = |offset: usize, packet: usize| {
+        (0..3).all(|i| header.get(offset + i * packet) == Some(&0x47))
+    };
+    if synced(0, TS_PACKET) {
+        return Some("mpegts");
+    }
+    if synced(4, M2TS_PACKET) {
+        return Some("m2ts");
+    }
+    None
+}
+
+/// Check if ffprobe finds a video stream in `path`
+fn probe_has_video(path: &Path) -> bool {
+    Command::new(tools::ffprobe())
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=codec_type",
+            "-of",
+            "csv=p=0",
+        ])
+        .arg(path)
+        .output()
+        .is_ok_and(|out| out.status.success() && !out.stdout.trim_ascii().is_empty())
+}
+
+/// Verdict for a file as it was when checked
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct CachedVerdict {
+    size: u64,
+    /// Modification time in seconds since the Unix epoch
+    modified: u64,
+    video: bool,
+}
+
+/// Location of the detection cache
+pub fn detection_cache_path() -> PathBuf {
+    data_dir().join("video_detection.json")
+}
+
+/// Decides which files of a folder scan are videos
+pub struct VideoDetector {
+    mode: VideoDetection,
+    cache: HashMap<PathBuf, CachedVerdict>,
+    changed: bool,
+}
+
+impl VideoDetector {
+    /// Detector for the configured mode; probe mode loads the cache
+    pub fn new(input: &InputConfig) -> Self {
+        let cache = match input.detection {
+            VideoDetection::Extension => HashMap::new(),
+            VideoDetection::Probe => std::fs::read_to_string(detection_cache_path())
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default(),
+        };
+        Self {
+            mode: input.detection,
+            cache,
+            changed: false,
+        }
+    }
+
+    /// Check if `path` is a video
+    pub fn is_video(&mut self, path: &Path) -> bool {
+        if self.mode == VideoDetection::Extension {
+            return is_video_file(path);
+        }
+        let Ok(meta) = std::fs::metadata(path) else {
+            return false;
+        };
+        let size = meta.len();
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        if let Some(cached) = self.cache.get(path)
+            && cached.size == size
+            && cached.modified == modified
+        {
+            return cached.video;
+        }
+
+        let video = content_is_video(path);
+        self.cache.insert(
+            path.to_path_buf(),
+            CachedVerdict {
+                size,
+                modified,
+                video,
+            },
+        );
+        self.changed = true;
+        video
+    }
+
+    /// Write new verdicts to the cache file
+    pub fn save(&self) {
+        if !self.changed {
+            return;
+        }
+        if let Err(e) = self.write_cache() {
+            warn!("Failed to save the video detection cache: {}", e);
+        }
+    }
+
+    fn write_cache(&self) -> Result<(), AppError> {
+        let path = detection_cache_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string(&self.cache)?)?;
+        Ok(())
+    }
+}
+
+/// Recognise the container, asking ffprobe only about files named like videos
+fn content_is_video(path: &Path) -> bool {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    let read = std::fs::File::open(path)
+        .and_then(|file| file.take(HEADER_LEN as u64).read_to_end(&mut header));
+    if read.is_err() {
+        return false;
+    }
+    sniff_container(&header).is_some() || (is_video_file(path) && probe_has_video(path))
+}
@@ -0,0 +1,234 @@
+use crate::error::AppError;
+use crate::utils::tools;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Titles probed for their duration; the rest are clips and menus
+const MAX_PROBED_TITLES: usize = 20;
+
+/// Kind of disc folder structure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscKind {
+    BluRay,
+    Dvd,
+}
+
+impl DiscKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DiscKind::BluRay => "Blu-ray",
+            DiscKind::Dvd => "DVD",
+        }
+    }
+}
+
+/// One playable title of a disc
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscTitle {
+    /// Disc root folder, the one containing BDMV or VIDEO_TS
+    pub disc: PathBuf,
+    pub kind: DiscKind,
+    /// Title name, e.g. "00800" for a Blu-ray stream or "title 01" for a DVD
+    pub name: String,
+    /// Files holding the title, in playback order
+    pub parts: Vec<PathBuf>,
+    pub size: u64,
+    pub duration_secs: Option<f64>,
+}
+
+impl DiscTitle {
+    /// Input for ffmpeg and ffprobe; multi-part DVD titles use the concat protocol
+    pub fn input(&self) -> String {
+        if self.parts.len() == 1 {
+            return self.parts[0].to_string_lossy().to_string();
+        }
+        let parts: Vec<String> = self
+            .parts
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        format!("concat:{}", parts.join("|"))
+    }
+
+    /// Name used for the job and its output, e.g. "Movie_title-01.vob"
+    pub fn source_name(&self) -> String {
+        let disc = self
+            .disc
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "disc".to_string());
+        let extension = match self.kind {
+            DiscKind::BluRay => "m2ts",
+            DiscKind::Dvd => "vob",
+        };
+        format!("{}_{}.{}", disc, self.name.replace(' ', "-"), extension)
+    }
+
+    /// Path the output name is derived from, next to the disc folder
+    pub fn output_base(&self) -> PathBuf {
+        self.disc
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(self.source_name())
+    }
+}
+
+/// Detect a Blu-ray (BDMV) or DVD (VIDEO_TS) folder structure
+///
+/// Accepts both the disc root and the BDMV/VIDEO_TS folder itself.
+pub fn detect_disc(dir: &Path) -> Option<DiscKind> {
+    if !dir.is_dir() {
+        return None;
+    }
+    let root = disc_root(dir);
+    if root.join("BDMV").join("STREAM").is_dir() {
+        Some(DiscKind::BluRay)
+    } else if root.join("VIDEO_TS").is_dir() {
+        Some(DiscKind::Dvd)
+    } else {
+        None
+    }
+}
+
+/// The folder containing BDMV or VIDEO_TS
+pub fn disc_root(dir: &Path) -> PathBuf {
+    let is_named = |name: &str| {
+        dir.file_name()
+            .is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(name))
+    };
+    if is_named("BDMV") || is_named("VIDEO_TS") {
+        dir.parent().unwrap_or(dir).to_path_buf()
+    } else {
+        dir.to_path_buf()
+    }
+}
+
+/// List a disc's titles, longest first
+///
+/// The largest titles are probed for their duration; the main feature is
+/// normally the first entry.
+pub fn list_titles(dir: &Path) -> Result<Vec<DiscTitle>, AppError> {
+    let kind = detect_disc(dir)
+        .ok_or_else(|| AppError::Analysis(format!("{} is not a disc folder", dir.display())))?;
+    let root = disc_root(dir);
+    let mut titles = match kind {
+        DiscKind::BluRay => bluray_titles(&root)?,
+        DiscKind::Dvd => dvd_titles(&root)?,
+    };
+
+    titles.sort_by_key(|t| std::cmp::Reverse(t.size));
+    titles.truncate(MAX_PROBED_TITLES);
+    for title in &mut titles {
+        title.duration_secs = probe_duration(&title.input());
+    }
+    titles.sort_by(|a, b| {
+        b.duration_secs
+            .unwrap_or(0.0)
+            .total_cmp(&a.duration_secs.unwrap_or(0.0))
+            .then(b.size.cmp(&a.size))
+    });
+    Ok(titles)
+}
+
+/// Each .m2ts stream of BDMV/STREAM is a title
+fn bluray_titles(root: &Path) -> Result<Vec<DiscTitle>, AppError> {
+    let stream_dir = root.join("BDMV").join("STREAM");
+    Ok(read_files(&stream_dir)?
+        .into_iter()
+        .filter(|(path, _)| has_extension(path, "m2ts"))
+        .map(|(path, size)| DiscTitle {
+            disc: root.to_path_buf(),
+            kind: DiscKind::BluRay,
+            name: path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            parts: vec![path],
+            size,
+            duration_secs: None,
+        })
+        .collect())
+}
+
+/// VTS_NN_1.VOB, VTS_NN_2.VOB, ... form title NN; VTS_NN_0.VOB is its menu
+fn dvd_titles(root: &Path) -> Result<Vec<DiscTitle>, AppError> {
+    let mut sets: BTreeMap<String, Vec<(u32, PathBuf, u64)>> = BTreeMap::new();
+    for (path, size) in read_files(&root.join("VIDEO_TS"))? {
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_uppercase())
+            .unwrap_or_default();
+        let mut fields = stem.split('_');
+        let (Some("VTS"), Some(set), Some(part), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        match part.parse::<u32>() {
+            Ok(part) if part > 0 && has_extension(&path, "vob") => {
+                sets.entry(set.to_string())
+                    .or_default()
+                    .push((part, path, size));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(sets
+        .into_iter()
+        .map(|(set, mut parts)| {
+            parts.sort_by_key(|(part, _, _)| *part);
+            DiscTitle {
+                disc: root.to_path_buf(),
+                kind: DiscKind::Dvd,
+                name: format!("title {}", set),
+                size: parts.iter().map(|(_, _, size)| size).sum(),
+                parts: parts.into_iter().map(|(_, path, _)| path).collect(),
+                duration_secs: None,
+            }
+        })
+        .collect())
+}
+
+fn read_files(dir: &Path) -> Result<Vec<(PathBuf, u64)>, AppError> {
+    let entries = std::fs::read_dir(dir).map_err(|e| AppError::Io {
+        path: dir.to_path_buf(),
+        operation: "list disc folder",
+        message: e.to_string(),
+    })?;
+    Ok(entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            metadata.is_file().then(|| (e.path(), metadata.len()))
+        })
+        .collect())
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+}
+
+/// Container duration, or None when the title cannot be probed
+fn probe_duration(input: &str) -> Option<f64> {
+    let output = Command::new(tools::ffprobe())
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+            input,
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
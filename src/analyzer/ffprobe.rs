@@ -1,6 +1,8 @@
+use crate::analyzer::input::UnsupportedInput;
 use crate::analyzer::metadata::{HdrType, VideoMetadata};
 use crate::error::AppError;
-use crate::tracks::{AudioTrack, SubtitleTrack};
+use crate::tracks::{AudioTrack, SubtitleTrack, TrackDisposition};
+use crate::utils::tools;
 use serde::Deserialize;
 use serde_json::Value;
 use std::process::Command;
@@ -14,8 +16,15 @@ pub struct AnalysisResult {
 }
 
 /// Analyze a video file using ffprobe
+///
+/// Images, audio-only files and raw video streams are rejected with
+/// [`AppError::Unsupported`].
 pub fn analyze(input_path: &str) -> Result<AnalysisResult, AppError> {
-    let metadata = analyze_video_stream(input_path)?;
+    let metadata = match analyze_video_stream(input_path, None) {
+        Ok(metadata) => metadata,
+        Err(VideoProbeError::NoVideo) => return Err(no_video_error(input_path)),
+        Err(VideoProbeError::Failed(e)) => return Err(e),
+    };
     let (audio_tracks, subtitle_tracks) = analyze_tracks(input_path)?;
 
     Ok(AnalysisResult {
@@ -25,31 +34,127 @@ pub fn analyze(input_path: &str) -> Result<AnalysisResult, AppError> {
     })
 }
 
+/// Analyze a raw video stream, reading it at an assumed frame rate
+///
+/// The stream has no timestamps, so the duration comes from counting frames.
+pub fn analyze_elementary(
+    input_path: &str,
+    frame_rate: (u32, u32),
+) -> Result<AnalysisResult, AppError> {
+    let mut metadata = match analyze_video_stream(input_path, Some(frame_rate)) {
+        Ok(metadata) => metadata,
+        Err(VideoProbeError::NoVideo) => return Err(no_video_error(input_path)),
+        Err(VideoProbeError::Failed(e)) => return Err(e),
+    };
+    metadata.frame_rate_num = frame_rate.0;
+    metadata.frame_rate_den = frame_rate.1;
+    if let Some(frames) = count_frames(input_path, frame_rate) {
+        metadata.duration_secs = frames as f64 * frame_rate.1 as f64 / frame_rate.0 as f64;
+    }
+
+    Ok(AnalysisResult {
+        metadata,
+        audio_tracks: Vec::new(),
+        subtitle_tracks: Vec::new(),
+    })
+}
+
+/// Why the video stream could not be analyzed
+enum VideoProbeError {
+    /// The input has no video stream besides cover art
+    NoVideo,
+    Failed(AppError),
+}
+
+impl From<AppError> for VideoProbeError {
+    fn from(e: AppError) -> Self {
+        VideoProbeError::Failed(e)
+    }
+}
+
+/// Audio-only when there is audio, a generic analysis error otherwise
+fn no_video_error(input_path: &str) -> AppError {
+    match analyze_tracks(input_path) {
+        Ok((audio, _)) if !audio.is_empty() => AppError::Unsupported(UnsupportedInput::AudioOnly),
+        _ => AppError::Analysis("No video stream found".to_string()),
+    }
+}
+
+/// `-framerate` input option for reading a raw stream, empty otherwise
+fn frame_rate_args(frame_rate: Option<(u32, u32)>) -> Vec<String> {
+    frame_rate
+        .map(|(num, den)| vec!["-framerate".to_string(), format!("{}/{}", num, den)])
+        .unwrap_or_default()
+}
+
+/// Count the frames of a raw stream; it is demuxed in full but not decoded
+fn count_frames(input_path: &str, frame_rate: (u32, u32)) -> Option<u64> {
+    let mut args = frame_rate_args(Some(frame_rate));
+    args.extend(
+        [
+            "-v",
+            "error",
+            "-count_packets",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=nb_read_packets",
+            "-of",
+            "csv=p=0",
+            input_path,
+        ]
+        .map(String::from),
+    );
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run_ffprobe(&args).ok()?.trim().parse().ok()
+}
+
 /// Analyze the primary video stream
-fn analyze_video_stream(input_path: &str) -> Result<VideoMetadata, AppError> {
-    let args = [
-        "-v",
-        "error",
-        "-select_streams",
-        "v:0",
-        "-show_entries",
-        "stream=width,height,pix_fmt,color_primaries,color_transfer,color_space,codec_name,r_frame_rate,avg_frame_rate,bit_rate,side_data_list",
-        "-show_entries",
-        "format=duration,bit_rate",
-        "-of",
-        "json",
-        input_path,
-    ];
+fn analyze_video_stream(
+    input_path: &str,
+    assumed_frame_rate: Option<(u32, u32)>,
+) -> Result<VideoMetadata, VideoProbeError> {
+    let mut args = frame_rate_args(assumed_frame_rate);
+    args.extend(
+        [
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=width,height,pix_fmt,color_primaries,color_transfer,color_space,codec_name,r_frame_rate,avg_frame_rate,bit_rate,side_data_list:stream_disposition=attached_pic",
+            "-show_entries",
+            "format=duration,bit_rate,format_name",
+            "-of",
+            "json",
+            input_path,
+        ]
+        .map(String::from),
+    );
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
 
     let output = run_ffprobe(&args)?;
     let data: FfprobeOutput = serde_json::from_str(&output)
         .map_err(|e| AppError::Analysis(format!("Failed to parse ffprobe output: {}", e)))?;
 
+    // Cover art of an audio file shows up as a video stream
     let stream = data
         .streams
         .into_iter()
-        .next()
-        .ok_or_else(|| AppError::Analysis("No video stream found".to_string()))?;
+        .find(|s| s.disposition.as_ref().is_none_or(|d| d.attached_pic != 1))
+        .ok_or(VideoProbeError::NoVideo)?;
+
+    let format_name = data
+        .format
+        .as_ref()
+        .and_then(|f| f.format_name.as_deref())
+        .unwrap_or("");
+    let codec_name = stream.codec_name.as_deref().unwrap_or("unknown");
+    match UnsupportedInput::classify(format_name, codec_name) {
+        Some(UnsupportedInput::ElementaryStream { .. }) if assumed_frame_rate.is_some() => {}
+        Some(unsupported) => return Err(AppError::Unsupported(unsupported).into()),
+        None => {}
+    }
 
     // Check for Dolby Vision
     let is_dolby_vision = stream
@@ -134,7 +239,7 @@ fn analyze_tracks(input_path: &str) -> Result<(Vec<AudioTrack>, Vec<SubtitleTrac
         "-v",
         "error",
         "-show_entries",
-        "stream=index,codec_type,codec_name,channels,bit_rate,sample_rate:stream_tags=language,title",
+        "stream=index,codec_type,codec_name,channels,bit_rate,sample_rate:stream_tags=language,title,BPS,BPS-eng:stream_disposition=default,forced,hearing_impaired,visual_impaired,comment",
         "-select_streams",
         "a",
         "-of",
@@ -150,7 +255,7 @@ fn analyze_tracks(input_path: &str) -> Result<(Vec<AudioTrack>, Vec<SubtitleTrac
         "-v",
         "error",
         "-show_entries",
-        "stream=index,codec_type,codec_name:stream_tags=language,title",
+        "stream=index,codec_type,codec_name,bit_rate:stream_tags=language,title,BPS,BPS-eng:stream_disposition=default,forced,hearing_impaired,visual_impaired,comment",
         "-select_streams",
         "s",
         "-of",
@@ -167,24 +272,30 @@ fn analyze_tracks(input_path: &str) -> Result<(Vec<AudioTrack>, Vec<SubtitleTrac
     let mut subtitle_tracks = Vec::new();
 
     for (audio_index, stream) in audio_data.streams.into_iter().enumerate() {
+        let bitrate = stream.bitrate();
+        let disposition = stream.track_disposition();
         audio_tracks.push(AudioTrack {
             index: audio_index,
             language: stream.tags.as_ref().and_then(|t| t.language.clone()),
             codec: stream.codec_name.unwrap_or_else(|| "unknown".to_string()),
             channels: stream.channels.unwrap_or(2),
             title: stream.tags.as_ref().and_then(|t| t.title.clone()),
-            bitrate: stream.bit_rate.and_then(|b| b.parse::<u64>().ok()),
+            bitrate,
             sample_rate: stream.sample_rate.and_then(|s| s.parse::<u32>().ok()),
+            disposition,
         });
     }
 
     for (subtitle_index, stream) in sub_data.streams.into_iter().enumerate() {
+        let bitrate = stream.bitrate();
+        let disposition = stream.track_disposition();
         subtitle_tracks.push(SubtitleTrack {
             index: subtitle_index,
             language: stream.tags.as_ref().and_then(|t| t.language.clone()),
             codec: stream.codec_name.unwrap_or_else(|| "unknown".to_string()),
             title: stream.tags.as_ref().and_then(|t| t.title.clone()),
-            forced: false,
+            disposition,
+            bitrate,
         });
     }
 
@@ -193,7 +304,7 @@ fn analyze_tracks(input_path: &str) -> Result<(Vec<AudioTrack>, Vec<SubtitleTrac
 
 /// Run ffprobe with arguments
 fn run_ffprobe(args: &[&str]) -> Result<String, AppError> {
-    let output = Command::new("ffprobe")
+    let output = Command::new(tools::ffprobe())
         .args(args)
         .output()
         .map_err(|e| AppError::Analysis(format!("Failed to execute ffprobe: {}", e)))?;
@@ -218,12 +329,15 @@ struct FfprobeOutput {
 struct FormatInfo {
     duration: Option<String>,
     bit_rate: Option<String>,
+    format_name: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 #[allow(unused)]
 struct VideoStream {
+    #[serde(default)]
     width: u32,
+    #[serde(default)]
     height: u32,
     codec_name: Option<String>,
     pix_fmt: Option<String>,
@@ -234,6 +348,23 @@ struct VideoStream {
     avg_frame_rate: Option<String>,
     bit_rate: Option<String>,
     side_data_list: Option<Vec<Value>>,
+    disposition: Option<Disposition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Disposition {
+    #[serde(default)]
+    attached_pic: u8,
+    #[serde(default)]
+    default: u8,
+    #[serde(default)]
+    forced: u8,
+    #[serde(default)]
+    hearing_impaired: u8,
+    #[serde(default)]
+    visual_impaired: u8,
+    #[serde(default)]
+    comment: u8,
 }
 
 #[derive(Debug, Deserialize)]
@@ -251,10 +382,38 @@ struct RawStream {
     bit_rate: Option<String>,
     sample_rate: Option<String>,
     tags: Option<StreamTags>,
+    disposition: Option<Disposition>,
+}
+
+impl RawStream {
+    /// Stream bitrate, or the average Matroska files record in their `BPS` tag
+    fn bitrate(&self) -> Option<u64> {
+        let parse = |b: &String| b.parse::<u64>().ok();
+        self.bit_rate
+            .as_ref()
+            .and_then(parse)
+            .or_else(|| self.tags.as_ref()?.bps.as_ref().and_then(parse))
+    }
+
+    /// Disposition flags, all unset when ffprobe reported none
+    fn track_disposition(&self) -> TrackDisposition {
+        let Some(ref d) = self.disposition else {
+            return TrackDisposition::default();
+        };
+        TrackDisposition {
+            default: d.default == 1,
+            forced: d.forced == 1,
+            hearing_impaired: d.hearing_impaired == 1,
+            visual_impaired: d.visual_impaired == 1,
+            comment: d.comment == 1,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct StreamTags {
     language: Option<String>,
     title: Option<String>,
+    #[serde(rename = "BPS", alias = "BPS-eng")]
+    bps: Option<String>,
 }
@@ -0,0 +1,231 @@
+//! Film grain parameters signalled in AV1 sources.
+//!
+//! An AV1 file usually carries its grain as synthesis parameters rather than in
+//! the picture. When such a file is re-encoded (e.g. to fit a device profile),
+//! decoding without the grain and handing the same parameters to SVT-AV1 keeps
+//! the original look, where re-estimating would denoise and guess again.
+
+use crate::error::AppError;
+use crate::utils::tools;
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+/// AV1 film grain synthesis parameters, as coded in the bitstream
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FilmGrainParams {
+    pub seed: u16,
+    pub ar_coeff_lag: u8,
+    /// 6-9
+    pub ar_coeff_shift: u8,
+    pub grain_scale_shift: u8,
+    /// 8-11
+    pub scaling_shift: u8,
+    pub chroma_scaling_from_luma: bool,
+    pub overlap: bool,
+    /// Piecewise-linear scaling function points (value, scaling)
+    pub y_points: Vec<(u8, u8)>,
+    pub cb_points: Vec<(u8, u8)>,
+    pub cr_points: Vec<(u8, u8)>,
+    pub ar_coeffs_y: Vec<i8>,
+    pub ar_coeffs_cb: Vec<i8>,
+    pub ar_coeffs_cr: Vec<i8>,
+    /// Chroma multipliers and offsets, centred on zero
+    pub cb_mult: i16,
+    pub cb_luma_mult: i16,
+    pub cb_offset: i16,
+    pub cr_mult: i16,
+    pub cr_luma_mult: i16,
+    pub cr_offset: i16,
+}
+
+impl FilmGrainParams {
+    /// Grain table in the libaom format read by SVT-AV1's `fgs-table`
+    ///
+    /// One entry covers the whole file, so the grain of the first frame is
+    /// applied throughout.
+    pub fn to_table(&self) -> String {
+        let points = |points: &[(u8, u8)]| {
+            let mut line = points.len().to_string();
+            for (value, scaling) in points {
+                line.push_str(&format!(" {} {}", value, scaling));
+            }
+            line
+        };
+        let coeffs = |coeffs: &[i8], count: usize| {
+            (0..count)
+                .map(|i| format!(" {}", coeffs.get(i).copied().unwrap_or(0)))
+                .collect::<String>()
+        };
+        let luma_coeffs = 2 * self.ar_coeff_lag as usize * (self.ar_coeff_lag as usize + 1);
+
+        format!(
+            "filmgrn1\n\
+             E 0 {} 1 {} 1\n\
+             \tp {} {} {} {} {} {} {} {} {} {} {} {}\n\
+             \tsY {}\n\
+             \tsCb {}\n\
+             \tsCr {}\n\
+             \tcY{}\n\
+             \tcCb{}\n\
+             \tcCr{}\n",
+            i64::MAX,
+            self.seed,
+            self.ar_coeff_lag,
+            self.ar_coeff_shift,
+            self.grain_scale_shift,
+            self.scaling_shift,
+            self.chroma_scaling_from_luma as u8,
+            self.overlap as u8,
+            self.cb_mult + 128,
+            self.cb_luma_mult + 128,
+            self.cb_offset + 256,
+            self.cr_mult + 128,
+            self.cr_luma_mult + 128,
+            self.cr_offset + 256,
+            points(&self.y_points),
+            points(&self.cb_points),
+            points(&self.cr_points),
+            coeffs(&self.ar_coeffs_y, luma_coeffs),
+            coeffs(&self.ar_coeffs_cb, luma_coeffs + 1),
+            coeffs(&self.ar_coeffs_cr, luma_coeffs + 1),
+        )
+    }
+
+    /// Write the grain table to `path`
+    pub fn write_table(&self, path: &Path) -> Result<(), AppError> {
+        std::fs::write(path, self.to_table()).map_err(|e| AppError::Io {
+            path: path.to_path_buf(),
+            operation: "write film grain table",
+            message: e.to_string(),
+        })
+    }
+}
+
+/// Read the film grain parameters of the first frame of an AV1 source
+///
+/// `None` when the stream signals no grain.
+pub fn probe_film_grain(input_path: &str) -> Result<Option<FilmGrainParams>, AppError> {
+    let output = Command::new(tools::ffprobe())
+        .args([
+            "-v",
+            "error",
+            "-export_side_data",
+            "film_grain",
+            "-select_streams",
+            "v:0",
+            "-read_intervals",
+            "%+#1",
+            "-show_frames",
+            "-of",
+            "json",
+            input_path,
+        ])
+        .output()
+        .map_err(|e| AppError::Analysis(format!("Failed to execute ffprobe: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::Analysis(format!(
+            "Film grain probe failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(parse_film_grain(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Extract AV1 film grain parameters from ffprobe's `-show_frames` JSON
+///
+/// Chroma components are listed only when they carry grain; a single one is
+/// taken as Cb.
+pub fn parse_film_grain(json: &str) -> Option<FilmGrainParams> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    let side_data = value
+        .get("frames")?
+        .as_array()?
+        .iter()
+        .filter_map(|frame| frame.get("side_data_list")?.as_array())
+        .flatten()
+        .find(|data| {
+            data.get("side_data_type").and_then(Value::as_str) == Some("Film grain parameters")
+                && data.get("type").and_then(Value::as_str) == Some("av1")
+        })?;
+
+    let int = |data: &Value, key: &str| data.get(key).and_then(Value::as_i64).unwrap_or(0);
+    let mut params = FilmGrainParams {
+        seed: int(side_data, "seed") as u16,
+        ar_coeff_lag: int(side_data, "ar_coeff_lag") as u8,
+        ar_coeff_shift: int(side_data, "ar_coeff_shift") as u8,
+        grain_scale_shift: int(side_data, "grain_scale_shift") as u8,
+        scaling_shift: int(side_data, "scaling_shift") as u8,
+        chroma_scaling_from_luma: int(side_data, "chroma_scaling_from_luma") != 0,
+        overlap: int(side_data, "overlap_flag") != 0,
+        ..Default::default()
+    };
+
+    let components = side_data
+        .get("components")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let mut chroma = 0;
+    for component in components {
+        if component.get("y_points_value").is_some() {
+            params.y_points = point_list(component, "y_points_value", "y_points_scaling");
+            params.ar_coeffs_y = number_list(component, "ar_coeffs_y");
+            continue;
+        }
+        let points = point_list(component, "uv_points_value", "uv_points_scaling");
+        let coeffs = number_list(component, "ar_coeffs_uv");
+        let (mult, luma_mult, offset) = (
+            int(component, "uv_mult") as i16,
+            int(component, "uv_mult_luma") as i16,
+            int(component, "uv_offset") as i16,
+        );
+        match chroma {
+            0 => {
+                params.cb_points = points;
+                params.ar_coeffs_cb = coeffs;
+                (params.cb_mult, params.cb_luma_mult, params.cb_offset) = (mult, luma_mult, offset);
+            }
+            _ => {
+                params.cr_points = points;
+                params.ar_coeffs_cr = coeffs;
+                (params.cr_mult, params.cr_luma_mult, params.cr_offset) = (mult, luma_mult, offset);
+            }
+        }
+        chroma += 1;
+    }
+
+    if params.y_points.is_empty()
+        && params.cb_points.is_empty()
+        && params.cr_points.is_empty()
+        && !params.chroma_scaling_from_luma
+    {
+        return None;
+    }
+    Some(params)
+}
+
+/// Numbers printed as a space-separated string (or a JSON array)
+fn number_list<T: TryFrom<i64>>(data: &Value, key: &str) -> Vec<T> {
+    let numbers: Vec<i64> = match data.get(key) {
+        Some(Value::String(list)) => list
+            .split_whitespace()
+            .filter_map(|n| n.parse().ok())
+            .collect(),
+        Some(Value::Array(list)) => list.iter().filter_map(Value::as_i64).collect(),
+        Some(Value::Number(n)) => n.as_i64().into_iter().collect(),
+        _ => Vec::new(),
+    };
+    numbers
+        .into_iter()
+        .filter_map(|n| T::try_from(n).ok())
+        .collect()
+}
+
+fn point_list(data: &Value, values: &str, scalings: &str) -> Vec<(u8, u8)> {
+    number_list::<u8>(data, values)
+        .into_iter()
+        .zip(number_list::<u8>(data, scalings))
+        .collect()
+}
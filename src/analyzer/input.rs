@@ -0,0 +1,46 @@
+/// Inputs ffprobe can read but that can't be converted as a video
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnsupportedInput {
+    /// A numbered image sequence or a single picture
+    ImageSequence { codec: String },
+    /// Audio without a video stream, or with only embedded cover art
+    AudioOnly,
+    /// A raw video bitstream without a container or timestamps
+    ElementaryStream { codec: String },
+}
+
+/// Demuxers that read raw video bitstreams
+const ELEMENTARY_FORMATS: [&str; 7] = ["h264", "hevc", "mpegvideo", "m4v", "vc1", "obu", "ivf"];
+
+impl UnsupportedInput {
+    /// Classify an input from ffprobe's container format name and video codec
+    pub fn classify(format_name: &str, codec: &str) -> Option<Self> {
+        let codec = codec.to_string();
+        if format_name == "image2" || format_name.ends_with("_pipe") {
+            Some(UnsupportedInput::ImageSequence { codec })
+        } else if format_name
+            .split(',')
+            .any(|f| ELEMENTARY_FORMATS.contains(&f))
+        {
+            Some(UnsupportedInput::ElementaryStream { codec })
+        } else {
+            None
+        }
+    }
+
+    /// Why the input can't be converted as-is
+    pub fn reason(&self) -> String {
+        match self {
+            UnsupportedInput::ImageSequence { codec } => {
+                format!("{} image or image sequence, not a video", codec)
+            }
+            UnsupportedInput::AudioOnly => {
+                "audio-only file, there is no video to encode".to_string()
+            }
+            UnsupportedInput::ElementaryStream { codec } => format!(
+                "raw {} stream without a container, frame rate and duration are unknown",
+                codec
+            ),
+        }
+    }
+}
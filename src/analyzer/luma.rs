@@ -0,0 +1,98 @@
+use crate::analyzer::HdrType;
+use crate::error::AppError;
+use crate::utils::tools;
+use std::process::Command;
+
+/// Average 8-bit luma below which an SDR scene counts as dark (16 is black)
+const DARK_LUMA_SDR: f64 = 40.0;
+
+/// Same for PQ and HLG, whose code values for shadows sit higher (~5 nits)
+const DARK_LUMA_HDR: f64 = 64.0;
+
+/// Share of dark scenes that makes the whole file dark content
+const DARK_SHARE_THRESHOLD: f64 = 0.3;
+
+/// Average luma of the scenes of a video, sampled at its keyframes
+#[derive(Debug, Clone)]
+pub struct LumaAnalysis {
+    /// Average 8-bit luma (16-235) per sampled keyframe
+    pub scene_luma: Vec<f64>,
+    /// Level below which a scene counts as dark
+    pub dark_level: f64,
+}
+
+impl LumaAnalysis {
+    /// Fraction of the sampled scenes that are dark (0.0-1.0)
+    pub fn dark_ratio(&self) -> f64 {
+        if self.scene_luma.is_empty() {
+            return 0.0;
+        }
+        let dark = self
+            .scene_luma
+            .iter()
+            .filter(|&&luma| luma < self.dark_level)
+            .count();
+        dark as f64 / self.scene_luma.len() as f64
+    }
+
+    /// Check if enough of the video is dark for shadows to need extra bits
+    pub fn is_dark(&self) -> bool {
+        self.dark_ratio() >= DARK_SHARE_THRESHOLD
+    }
+}
+
+/// Measure the average luma of each keyframe using ffmpeg's signalstats
+///
+/// Only keyframes are decoded, which keeps this fast and roughly samples one
+/// frame per scene since encoders place keyframes at scene cuts.
+pub fn analyze_luma(input_path: &str, hdr_type: HdrType) -> Result<LumaAnalysis, AppError> {
+    let output = Command::new(tools::ffmpeg())
+        .args([
+            "-hide_banner",
+            "-nostdin",
+            "-skip_frame",
+            "nokey",
+            "-i",
+            input_path,
+            "-map",
+            "0:v:0",
+            "-vf",
+            "scale=160:-2,format=yuv420p,signalstats,metadata=print:key=lavfi.signalstats.YAVG",
+            "-fps_mode",
+            "passthrough",
+            "-an",
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .map_err(|e| AppError::Analysis(format!("Failed to run luma analysis: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::Analysis(format!(
+            "Luma analysis failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let scene_luma = stderr
+        .lines()
+        .filter_map(|line| {
+            let pos = line.find("lavfi.signalstats.YAVG=")?;
+            line[pos + "lavfi.signalstats.YAVG=".len()..]
+                .trim()
+                .parse::<f64>()
+                .ok()
+        })
+        .collect();
+
+    Ok(LumaAnalysis {
+        scene_luma,
+        dark_level: if hdr_type.is_hdr() {
+            DARK_LUMA_HDR
+        } else {
+            DARK_LUMA_SDR
+        },
+    })
+}
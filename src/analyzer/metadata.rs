@@ -54,4 +54,23 @@ impl VideoMetadata {
     pub fn hdr_string(&self) -> &'static str {
         self.hdr_type.display_string()
     }
+
+    /// Bits per sample from the pixel format, e.g. 10 for `yuv420p10le` (8 when unknown)
+    pub fn bit_depth(&self) -> u8 {
+        let format = self.pixel_format.as_deref().unwrap_or("");
+        [16, 12, 10]
+            .into_iter()
+            .find(|depth| format.contains(&depth.to_string()))
+            .unwrap_or(8)
+    }
+
+    /// Bits per pixel and frame at `bitrate` bits per second
+    pub fn bits_per_pixel(&self, bitrate: f64) -> Option<f64> {
+        if self.frame_rate_den == 0 {
+            return None;
+        }
+        let fps = self.frame_rate_num as f64 / self.frame_rate_den as f64;
+        let pixels_per_sec = self.width as f64 * self.height as f64 * fps;
+        (pixels_per_sec > 0.0).then(|| bitrate / pixels_per_sec)
+    }
 }
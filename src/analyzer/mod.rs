@@ -1,7 +1,21 @@
 pub mod classifier;
+pub mod detect;
+pub mod disc;
 pub mod ffprobe;
+pub mod grain;
+pub mod input;
+pub mod luma;
 pub mod metadata;
+pub mod motion;
+pub mod noise;
 
 pub use classifier::{ResolutionTier, is_av1_codec};
-pub use ffprobe::analyze;
+pub use detect::VideoDetector;
+pub use disc::{DiscKind, DiscTitle, detect_disc, list_titles};
+pub use ffprobe::{analyze, analyze_elementary};
+pub use grain::{FilmGrainParams, probe_film_grain};
+pub use input::UnsupportedInput;
+pub use luma::{LumaAnalysis, analyze_luma};
 pub use metadata::{HdrType, VideoMetadata};
+pub use motion::{MotionAnalysis, analyze_motion};
+pub use noise::{NoiseAnalysis, adaptive_film_grain, analyze_noise};
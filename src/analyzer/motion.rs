@@ -0,0 +1,112 @@
+use crate::error::AppError;
+use crate::utils::tools;
+use std::process::Command;
+
+/// Share of the sampled window that must be frozen to count as low-motion content
+const LOW_MOTION_THRESHOLD: f64 = 0.9;
+
+/// Maximum length of the sampled window in seconds
+const SAMPLE_WINDOW_SECS: f64 = 60.0;
+
+/// Result of a quick motion analysis over a sample window
+#[derive(Debug, Clone, Copy)]
+pub struct MotionAnalysis {
+    /// Length of the sampled window in seconds
+    pub sampled_secs: f64,
+    /// Seconds detected as frozen within the window
+    pub frozen_secs: f64,
+}
+
+impl MotionAnalysis {
+    /// Fraction of the sample without visible motion (0.0-1.0)
+    pub fn static_ratio(&self) -> f64 {
+        if self.sampled_secs <= 0.0 {
+            return 0.0;
+        }
+        (self.frozen_secs / self.sampled_secs).clamp(0.0, 1.0)
+    }
+
+    /// Check if the content is a slideshow or still image
+    pub fn is_low_motion(&self) -> bool {
+        self.static_ratio() >= LOW_MOTION_THRESHOLD
+    }
+}
+
+/// Measure how static a video is using ffmpeg's freezedetect on a downscaled sample
+pub fn analyze_motion(input_path: &str, duration_secs: f64) -> Result<MotionAnalysis, AppError> {
+    let window = if duration_secs > 0.0 {
+        duration_secs.min(SAMPLE_WINDOW_SECS)
+    } else {
+        SAMPLE_WINDOW_SECS
+    };
+    // Skip intros on longer files
+    let start = if duration_secs > window * 2.0 {
+        duration_secs * 0.1
+    } else {
+        0.0
+    };
+
+    let output = Command::new(tools::ffmpeg())
+        .args([
+            "-hide_banner",
+            "-nostdin",
+            "-ss",
+            &format!("{:.3}", start),
+            "-t",
+            &format!("{:.3}", window),
+            "-i",
+            input_path,
+            "-map",
+            "0:v:0",
+            "-vf",
+            "scale=320:-2,freezedetect=n=0.003:d=1",
+            "-an",
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .map_err(|e| AppError::Analysis(format!("Failed to run motion analysis: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::Analysis(format!(
+            "Motion analysis failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let frozen_secs = parse_frozen_duration(&stderr, window);
+
+    Ok(MotionAnalysis {
+        sampled_secs: window,
+        frozen_secs,
+    })
+}
+
+/// Sum freeze durations reported by freezedetect, closing a freeze still open at the end
+fn parse_frozen_duration(stderr: &str, window: f64) -> f64 {
+    let mut total = 0.0;
+    let mut open_start: Option<f64> = None;
+
+    for line in stderr.lines() {
+        if let Some(value) = field_value(line, "lavfi.freezedetect.freeze_start:") {
+            open_start = Some(value);
+        } else if let Some(value) = field_value(line, "lavfi.freezedetect.freeze_duration:") {
+            total += value;
+        } else if line.contains("lavfi.freezedetect.freeze_end:") {
+            open_start = None;
+        }
+    }
+
+    if let Some(start) = open_start {
+        total += (window - start).max(0.0);
+    }
+
+    total
+}
+
+fn field_value(line: &str, key: &str) -> Option<f64> {
+    let pos = line.find(key)?;
+    line[pos + key.len()..].trim().parse::<f64>().ok()
+}
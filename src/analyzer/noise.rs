@@ -0,0 +1,114 @@
+use crate::analyzer::ResolutionTier;
+use crate::config::AdaptiveGrainConfig;
+use crate::error::AppError;
+use crate::utils::tools;
+use std::process::Command;
+
+/// Keyframes compared with their denoised selves
+const NOISE_SAMPLE_FRAMES: &str = "40";
+
+/// Share of the grain score coming from the measured noise when the bitrate
+/// is known too; noise tells grain apart from detail, the bitrate only hints
+const NOISE_WEIGHT: f64 = 0.75;
+
+/// Grain measured in a source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseAnalysis {
+    /// Luma PSNR of the sampled frames against a denoised copy, in dB; the
+    /// lower, the more grain the denoiser took out
+    pub denoised_psnr: f64,
+}
+
+impl NoiseAnalysis {
+    /// How grainy the source is, from 0 (clean digital) to 1 (heavy film grain)
+    ///
+    /// Blends the measured noise with the bits per pixel the source spends,
+    /// since grain is expensive to keep.
+    pub fn grain_score(&self, bits_per_pixel: Option<f64>, config: &AdaptiveGrainConfig) -> f64 {
+        let noise = ramp(
+            config.clean_noise_db - self.denoised_psnr,
+            config.clean_noise_db - config.grainy_noise_db,
+        );
+        match bits_per_pixel {
+            Some(bpp) => {
+                let bitrate = ramp(bpp - config.low_bpp, config.high_bpp - config.low_bpp);
+                NOISE_WEIGHT * noise + (1.0 - NOISE_WEIGHT) * bitrate
+            }
+            None => noise,
+        }
+    }
+}
+
+/// `value` over `span`, clamped to 0-1
+fn ramp(value: f64, span: f64) -> f64 {
+    if span <= 0.0 {
+        return 0.0;
+    }
+    (value / span).clamp(0.0, 1.0)
+}
+
+/// Film grain for a source of `tier` with a grain `score` (0-1)
+///
+/// Smaller pictures get less, since the same synthesized grain covers more
+/// of each frame there.
+pub fn adaptive_film_grain(score: f64, tier: ResolutionTier, config: &AdaptiveGrainConfig) -> u8 {
+    let tier_weight = match tier {
+        ResolutionTier::SD => 0.5,
+        ResolutionTier::HD => 0.75,
+        ResolutionTier::FullHD | ResolutionTier::Uhd | ResolutionTier::Above4K => 1.0,
+    };
+    (score.clamp(0.0, 1.0) * tier_weight * config.max_film_grain as f64).round() as u8
+}
+
+/// Measure grain by comparing sampled keyframes with a denoised copy
+///
+/// Only keyframes are decoded, so the denoiser is spatial only; grain is
+/// what it removes from an otherwise clean picture.
+pub fn analyze_noise(input_path: &str) -> Result<NoiseAnalysis, AppError> {
+    let output = Command::new(tools::ffmpeg())
+        .args([
+            "-hide_banner",
+            "-nostdin",
+            "-skip_frame",
+            "nokey",
+            "-i",
+            input_path,
+            "-map",
+            "0:v:0",
+            "-frames:v",
+            NOISE_SAMPLE_FRAMES,
+            "-vf",
+            "format=yuv420p,split[a][b];[b]hqdn3d=4:3:0:0[d];[a][d]psnr",
+            "-fps_mode",
+            "passthrough",
+            "-an",
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .map_err(|e| AppError::Analysis(format!("Failed to run noise analysis: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::Analysis(format!(
+            "Noise analysis failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    parse_psnr_y(&String::from_utf8_lossy(&output.stderr))
+        .map(|denoised_psnr| NoiseAnalysis { denoised_psnr })
+        .ok_or_else(|| AppError::Analysis("Noise analysis printed no PSNR".to_string()))
+}
+
+/// Luma PSNR of the psnr filter's summary line, e.g. `PSNR y:41.23 u:…`
+///
+/// Identical frames print `inf`, which counts as perfectly clean.
+pub fn parse_psnr_y(stderr: &str) -> Option<f64> {
+    let line = stderr.lines().rev().find(|l| l.contains("PSNR y:"))?;
+    let value = line.split("PSNR y:").nth(1)?.split_whitespace().next()?;
+    match value {
+        "inf" => Some(f64::INFINITY),
+        value => value.parse().ok(),
+    }
+}
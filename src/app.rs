@@ -1,27 +1,62 @@
-use crate::analyzer::{self, is_av1_codec};
-use crate::config::AppConfig;
+use crate::analyzer::ffprobe::AnalysisResult;
+use crate::analyzer::{
+    self, DiscTitle, FilmGrainParams, LumaAnalysis, MotionAnalysis, NoiseAnalysis, ResolutionTier,
+    VideoDetector, detect_disc, is_av1_codec,
+};
+use crate::audit::{self, AuditEntry, AuditReport, CodecFamily};
+use crate::cleanup::{self, Leftover};
+use crate::config::settings::{self, config_rows};
+use crate::config::{
+    AppConfig, ConfigDifference, ConfigRow, Cycle, DeviceProfile, Encoder, EncodingPresetsConfig,
+    LockScope, LossyAudio, PresetField, SettingsSection, SharedConfig, SpaceCheck, StatsMode,
+};
+use crate::encoder::{JobMode, Stage, ffmpeg_command, preset_quality, timed};
+use crate::error::{AppError, ErrorKind};
+use crate::history::{self, HistoryRecord, Pacing, Recommendation, StatsRecord, stats};
+use crate::notify;
+use crate::palette::{self, PaletteAction, PaletteState};
+use crate::queue::preflight::{self, VolumeNeed};
 use crate::queue::{
-    EncodingJob, JobStatus, QueueState, WorkerJob, WorkerMessage, is_video_file, run_worker,
+    DarkSceneHint, EncodingJob, EtaEstimator, GrainHint, JobStatus, QueuePublisher, QueueState,
+    RunSummary, SavedQueue, SkipKind, StillImageHint, TaskGraph, WorkerJob, WorkerMessage,
+    configure_video_extensions, is_video_file, post_actions, run_streamed_worker,
+};
+use crate::utils::deps::{Feature, Version};
+use crate::utils::{
+    DependencyStatus, InstanceLock, SessionState, copy_to_clipboard, crash, format_file_size,
+    humanize, open_in_player, reveal_in_file_manager, suspend, tools, update,
 };
-use crate::utils::DependencyStatus;
 use ratatui::widgets::ListState;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{self, Receiver};
-use std::thread;
-use tracing::info;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, info_span, warn};
 
 /// Application screens
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Screen {
     Home,
-    FileExplorer { select_folder: bool },
+    FileExplorer {
+        select_folder: bool,
+    },
     FileConfirm,
+    /// Pick a title of a Blu-ray or DVD folder
+    DiscTitles,
+    /// Codec breakdown and estimated savings of a folder tree
+    Audit,
+    /// Review leftovers of earlier runs before deleting them
+    Cleanup,
     TrackConfig,
     Queue,
     Finish,
     Configuration,
+    /// Per-tier encoding presets, opened from Configuration
+    Presets,
 }
 
 /// File selection mode
@@ -31,6 +66,16 @@ pub enum SelectionMode {
     Folder,
 }
 
+/// What the folder picked in the explorer is used for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FolderTask {
+    Encode,
+    /// Report what the folder holds without encoding
+    Audit,
+    /// Scan for leftovers of earlier runs
+    Cleanup,
+}
+
 /// Track configuration focus
 #[derive(Debug, Clone, PartialEq)]
 pub enum TrackFocus {
@@ -40,16 +85,40 @@ pub enum TrackFocus {
 }
 
 /// Confirmation dialog action
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ConfirmAction {
     CancelEncoding,
     ExitApp,
+    DeleteLeftovers,
+    /// Put every setting back at its built-in default
+    ResetConfig,
+    /// Convert queued sources that are already AV1 or VP9
+    ConvertEfficientSources,
+    /// Start a queue whose outputs likely won't fit their volume
+    StartWithoutSpace,
+    /// Continue the queue the last run left unfinished
+    RestoreQueue,
+}
+
+impl ConfirmAction {
+    /// Whether a yes can be kept for the rest of the session
+    ///
+    /// Only questions asked again for every batch; a one-off or destructive
+    /// action is always confirmed.
+    pub fn can_apply_to_all(&self) -> bool {
+        matches!(
+            self,
+            ConfirmAction::ConvertEfficientSources | ConfirmAction::StartWithoutSpace
+        )
+    }
 }
 
 pub const HOME_MENU: &[&str] = &[
     "Open Video File",
     "Open Folder",
     "Open Folder (Recursive)",
+    "Audit Folder",
+    "Clean Up Folder",
     "Configuration",
     "Quit",
 ];
@@ -66,6 +135,7 @@ pub struct App {
     pub explorer_index: usize,
     pub explorer_list_state: ListState,
     pub recursive_scan: bool,
+    pub folder_task: FolderTask,
 
     // Queue state (replaces Vec<VideoFile>)
     pub queue: QueueState,
@@ -76,6 +146,10 @@ pub struct App {
     pub subtitle_cursor: usize,
     pub audio_list_state: ListState,
     pub subtitle_list_state: ListState,
+    /// Text being typed for the job's extra FFmpeg arguments
+    pub extra_args_input: Option<String>,
+    /// FFmpeg command shown for the job being configured
+    pub command_preview: Option<String>,
 
     // Home menu
     pub home_index: usize,
@@ -83,23 +157,104 @@ pub struct App {
     // Multi-file selection
     pub selected_files: Vec<PathBuf>,
     pub file_confirm_scroll: usize,
+    /// Probes behind the "worth converting" verdicts, by job index; missing while probing
+    pub file_probes: HashMap<usize, Option<AuditEntry>>,
+    pub probe_receiver: Option<UnboundedReceiver<(usize, Option<AuditEntry>)>>,
+    /// Publishes the queue for viewers attached from other processes, when
+    /// this instance holds the global lock
+    pub publisher: Option<QueuePublisher>,
+    /// Publishes the queue to `output.status_file`
+    pub status_file: Option<QueuePublisher>,
+    /// Space the queued outputs need on each volume, checked on the confirmation screen
+    pub space_needs: Vec<VolumeNeed>,
+    /// Indices into `config.profiles` given to every file of the queue run
+    pub active_profiles: Vec<usize>,
+
+    // Disc title selection
+    pub disc_titles: Vec<DiscTitle>,
+    pub disc_title_index: usize,
+
+    // Audit
+    pub audit: Option<AuditReport>,
+    pub audit_index: usize,
+    pub audit_list_state: ListState,
+
+    // Cleanup
+    pub cleanup_dir: PathBuf,
+    pub leftovers: Vec<Leftover>,
+    pub leftover_index: usize,
+    pub leftover_list_state: ListState,
 
     // Encoding
     pub encoding_active: bool,
-    pub progress_receiver: Option<Receiver<WorkerMessage>>,
-    pub cancel_flag: Arc<AtomicBool>,
+    pub progress_receiver: Option<UnboundedReceiver<WorkerMessage>>,
+    /// Feeds configured jobs to the running worker
+    pub job_sender: Option<UnboundedSender<WorkerJob>>,
+    /// Analysis results of the files being added, as each one finishes
+    pub analysis_receiver: Option<UnboundedReceiver<(usize, Result<SourceAnalysis, AppError>)>>,
+    /// Queue files as soon as they are analyzed, skipping track configuration
+    pub skip_track_config: bool,
+    /// Work out every encode without running it, for a report of what would happen
+    pub simulate: bool,
+    /// Queue left unfinished by the last run, offered for restoring at startup
+    pub restorable_queue: Option<SavedQueue>,
+    /// Unfinished jobs as last saved, so unchanged ones aren't written again
+    pub saved_queue: Option<SavedQueue>,
+    /// Output directories locked against other instances while the queue runs
+    pub output_locks: HashMap<PathBuf, InstanceLock>,
+    pub cancel_token: CancellationToken,
+    /// Per-job children of `cancel_token`, by job index
+    pub job_cancel_tokens: HashMap<usize, CancellationToken>,
+    /// Configuration read by the running queue
+    pub live_config: SharedConfig,
+    /// Statistics of this batch not yet submitted
+    pub stats_batch: Vec<StatsRecord>,
+    /// Email and statistics still being sent for finished batches
+    pub batch_end_tasks: Vec<JoinHandle<()>>,
     // Configuration
     pub config: AppConfig,
-    pub deps: bool,
+    /// Installed tools and their versions
+    pub deps: DependencyStatus,
+    /// Newer release found by the startup check
+    pub available_update: Option<Version>,
+    pub update_receiver: Option<UnboundedReceiver<Version>>,
 
     // UI state
     pub message: Option<String>,
     pub confirm_dialog: Option<ConfirmAction>,
     pub confirm_selection: bool,
+    /// "Apply to all" ticked in the open dialog
+    pub confirm_apply_all: bool,
+    /// Questions answered yes for the rest of the session
+    pub session_answers: HashSet<ConfirmAction>,
+    /// Open command palette (Ctrl+P)
+    pub palette: Option<PaletteState>,
 
     // Config screen state
-    pub config_scroll: usize,
+    pub config_list_state: ListState,
     pub config_selected: usize,
+    /// Search text typed after `/`, matched against labels and config keys
+    pub config_filter: String,
+    /// Whether keys go to the search box
+    pub config_searching: bool,
+    pub config_collapsed: Vec<SettingsSection>,
+    /// Show only the values that differ from the defaults
+    pub config_show_diff: bool,
+    pub config_diff_selected: usize,
+
+    // Presets editor state
+    pub preset_row: usize,
+    pub preset_column: usize,
+    /// Number typed into the highlighted cell, applied with Enter
+    pub preset_input: Option<String>,
+    /// Preset advice from past results, refreshed when the config screen opens
+    pub recommendations: Vec<Recommendation>,
+
+    // Finish screen state
+    pub queue_index: usize,
+    pub queue_list_state: ListState,
+    pub finish_index: usize,
+    pub finish_list_state: ListState,
 }
 
 impl Default for App {
@@ -119,7 +274,10 @@ impl App {
         subtitle_list_state.select(Some(0));
 
         let config = AppConfig::load();
-        let deps = DependencyStatus::check().unwrap_or(false);
+        tools::configure(&config.tools);
+        humanize::configure(&config.ui);
+        configure_video_extensions(&config.input);
+        let deps = DependencyStatus::detect();
 
         info!("Using encoder: {}", config.encoder);
 
@@ -132,25 +290,72 @@ impl App {
             explorer_index: 0,
             explorer_list_state: list_state,
             recursive_scan: false,
+            folder_task: FolderTask::Encode,
             queue: QueueState::new(),
             track_focus: TrackFocus::Audio,
             audio_cursor: 0,
             subtitle_cursor: 0,
             audio_list_state,
             subtitle_list_state,
+            extra_args_input: None,
+            command_preview: None,
             home_index: 0,
             selected_files: Vec::new(),
             file_confirm_scroll: 0,
+            file_probes: HashMap::new(),
+            probe_receiver: None,
+            publisher: None,
+            status_file: status_file(&config),
+            space_needs: Vec::new(),
+            active_profiles: Vec::new(),
+            disc_titles: Vec::new(),
+            disc_title_index: 0,
+            audit: None,
+            audit_index: 0,
+            audit_list_state: ListState::default(),
+            cleanup_dir: PathBuf::new(),
+            leftovers: Vec::new(),
+            leftover_index: 0,
+            leftover_list_state: ListState::default(),
             encoding_active: false,
             progress_receiver: None,
-            cancel_flag: Arc::new(AtomicBool::new(false)),
+            job_sender: None,
+            analysis_receiver: None,
+            skip_track_config: false,
+            simulate: false,
+            restorable_queue: None,
+            saved_queue: None,
+            output_locks: HashMap::new(),
+            cancel_token: CancellationToken::new(),
+            live_config: SharedConfig::default(),
+            job_cancel_tokens: HashMap::new(),
             config,
             deps,
+            available_update: None,
+            stats_batch: Vec::new(),
+            batch_end_tasks: Vec::new(),
+            update_receiver: None,
             message: None,
             confirm_dialog: None,
             confirm_selection: false,
-            config_scroll: 0,
+            confirm_apply_all: false,
+            session_answers: HashSet::new(),
+            palette: None,
+            config_list_state: ListState::default(),
             config_selected: 0,
+            config_filter: String::new(),
+            config_searching: false,
+            config_collapsed: Vec::new(),
+            config_show_diff: false,
+            config_diff_selected: 0,
+            preset_row: 0,
+            preset_column: 0,
+            preset_input: None,
+            recommendations: Vec::new(),
+            queue_index: 0,
+            queue_list_state: ListState::default(),
+            finish_index: 0,
+            finish_list_state: ListState::default(),
         }
     }
 
@@ -160,15 +365,152 @@ impl App {
         self.message = Some(msg.to_string());
     }
 
+    /// Close the open dialog with yes, returning the action to run
+    ///
+    /// With "apply to all" ticked the answer is kept for the session.
+    pub fn accept_confirm(&mut self) -> Option<ConfirmAction> {
+        let action = self.confirm_dialog.take()?;
+        if std::mem::take(&mut self.confirm_apply_all) && action.can_apply_to_all() {
+            self.session_answers.insert(action.clone());
+        }
+        Some(action)
+    }
+
+    pub fn dismiss_confirm(&mut self) {
+        // Declining to restore the last queue discards it at the next save
+        if self.confirm_dialog.take() == Some(ConfirmAction::RestoreQueue) {
+            self.saved_queue = self.restorable_queue.take();
+        }
+        self.confirm_apply_all = false;
+    }
+
     pub fn clear_message(&mut self) {
         self.message = None;
     }
 
+    /// Check if the screen shows timers that move without input, such as the encode ETA
+    pub fn ticking(&self) -> bool {
+        self.encoding_active
+    }
+
+    /// How long to wait for input or background updates before the next tick
+    pub fn poll_interval(&self) -> Duration {
+        let ui = &self.config.ui;
+        let ms = if self.ticking() {
+            ui.poll_interval_ms
+        } else {
+            ui.idle_poll_interval_ms.max(ui.poll_interval_ms)
+        };
+        Duration::from_millis(ms.max(10))
+    }
+
+    // Command palette
+
+    pub fn open_palette(&mut self) {
+        self.palette = Some(PaletteState::default());
+    }
+
+    pub fn close_palette(&mut self) {
+        self.palette = None;
+    }
+
+    /// Actions that make sense on the current screen
+    pub fn palette_actions(&self) -> Vec<PaletteAction> {
+        let encoding = self.encoding_active;
+        PaletteAction::ALL
+            .into_iter()
+            .filter(|action| match action {
+                PaletteAction::OpenFile
+                | PaletteAction::OpenFolder
+                | PaletteAction::OpenFolderRecursive
+                | PaletteAction::AuditFolder
+                | PaletteAction::CleanUpFolder
+                | PaletteAction::SwitchEncoder
+                | PaletteAction::OpenConfiguration => !encoding,
+                PaletteAction::StartQueue => matches!(
+                    self.current_screen,
+                    Screen::FileConfirm | Screen::TrackConfig
+                ),
+                PaletteAction::PauseQueue | PaletteAction::CancelEncoding => encoding,
+                PaletteAction::GoHome => !encoding && self.current_screen != Screen::Home,
+                PaletteAction::ToggleVmaf
+                | PaletteAction::SaveConfiguration
+                | PaletteAction::Quit => true,
+            })
+            .collect()
+    }
+
+    /// Available actions matching the typed query, best match first
+    pub fn palette_matches(&self) -> Vec<PaletteAction> {
+        let query = self.palette.as_ref().map_or("", |p| p.query.as_str());
+        palette::filter(query, &self.palette_actions())
+    }
+
+    /// Run the highlighted palette entry and close the palette
+    pub fn run_palette_selection(&mut self) {
+        let selected = self.palette.as_ref().map_or(0, |p| p.selected);
+        let action = self.palette_matches().get(selected).copied();
+        self.close_palette();
+        if let Some(action) = action {
+            self.run_palette_action(action);
+        }
+    }
+
+    pub fn run_palette_action(&mut self, action: PaletteAction) {
+        match action {
+            PaletteAction::OpenFile => self.navigate_to_explorer(false, false),
+            PaletteAction::OpenFolder => self.navigate_to_explorer(true, false),
+            PaletteAction::OpenFolderRecursive => self.navigate_to_explorer(true, true),
+            PaletteAction::AuditFolder => self.navigate_to_folder_task(FolderTask::Audit),
+            PaletteAction::CleanUpFolder => self.navigate_to_folder_task(FolderTask::Cleanup),
+            PaletteAction::StartQueue => self.start_queue(),
+            PaletteAction::ToggleVmaf => {
+                if let Err(reason) = self.toggle_vmaf() {
+                    let message = format!("VMAF verification unavailable: {}", reason);
+                    self.set_message(&message);
+                    return;
+                }
+                let message = if self.config.quality.vmaf_enabled {
+                    "VMAF verification on"
+                } else {
+                    "VMAF verification off"
+                };
+                if self.encoding_active {
+                    self.live_config.set(self.config.clone());
+                }
+                self.set_message(message);
+            }
+            PaletteAction::SwitchEncoder => {
+                self.cycle_encoder(true);
+                let message = format!("Encoder: {}", self.config.encoder);
+                self.set_message(&message);
+            }
+            PaletteAction::OpenConfiguration => self.navigate_to_configuration(),
+            PaletteAction::SaveConfiguration => match self.config.save() {
+                Ok(()) => self.set_message("Configuration saved"),
+                Err(e) => {
+                    let message = format!("Could not save configuration: {}", e);
+                    self.set_message(&message);
+                }
+            },
+            PaletteAction::PauseQueue => self.toggle_pause(),
+            PaletteAction::CancelEncoding => {
+                self.confirm_dialog = Some(ConfirmAction::CancelEncoding);
+                self.confirm_selection = false;
+            }
+            PaletteAction::GoHome => self.navigate_to_home(),
+            PaletteAction::Quit => {
+                self.confirm_dialog = Some(ConfirmAction::ExitApp);
+                self.confirm_selection = false;
+            }
+        }
+    }
+
     // Navigation
 
     pub fn navigate_to_home(&mut self) {
         self.current_screen = Screen::Home;
-        self.home_index = 0;
+        self.home_index = self.last_home_entry();
         self.selected_files.clear();
     }
 
@@ -179,18 +521,29 @@ impl App {
             SelectionMode::File
         };
         self.recursive_scan = recursive;
+        self.folder_task = FolderTask::Encode;
         self.refresh_dir_entries();
         self.current_screen = Screen::FileExplorer { select_folder };
     }
 
+    /// Pick a folder to audit or clean up
+    pub fn navigate_to_folder_task(&mut self, task: FolderTask) {
+        self.navigate_to_explorer(true, true);
+        self.folder_task = task;
+    }
+
     pub fn navigate_to_track_config(&mut self) {
         self.track_focus = TrackFocus::Audio;
         self.audio_cursor = 0;
         self.subtitle_cursor = 0;
+        self.extra_args_input = None;
+        self.command_preview = None;
         self.current_screen = Screen::TrackConfig;
     }
 
     pub fn navigate_to_queue(&mut self) {
+        self.clear_message();
+        self.queue_select(0);
         self.current_screen = Screen::Queue;
     }
 
@@ -205,20 +558,402 @@ impl App {
                 job.output_size = std::fs::metadata(output_path).ok().map(|m| m.len());
             }
         }
+        self.finish_select(0);
+        self.clear_message();
         self.current_screen = Screen::Finish;
     }
 
     pub fn navigate_to_configuration(&mut self) {
-        self.config_scroll = 0;
         self.config_selected = 0;
+        self.config_filter.clear();
+        self.config_searching = false;
+        self.config_show_diff = false;
+        self.recommendations = history::recommend(&history::load(), &self.config);
         self.current_screen = Screen::Configuration;
     }
 
+    /// Section headers and settings shown on the Configuration screen
+    pub fn config_rows(&self) -> Vec<ConfigRow> {
+        config_rows(&self.config_filter, &self.config_collapsed)
+    }
+
+    /// Row under the cursor on the Configuration screen
+    pub fn selected_config_row(&self) -> Option<ConfigRow> {
+        self.config_rows().get(self.config_selected).copied()
+    }
+
+    /// Move the Configuration cursor, staying on the visible rows
+    pub fn move_config_cursor(&mut self, down: bool) {
+        let last = self.config_rows().len().saturating_sub(1);
+        self.config_selected = if down {
+            (self.config_selected + 1).min(last)
+        } else {
+            self.config_selected.saturating_sub(1).min(last)
+        };
+    }
+
+    /// Collapse or expand a section of the Configuration screen
+    pub fn set_config_section_collapsed(&mut self, section: SettingsSection, collapsed: bool) {
+        self.config_collapsed.retain(|s| *s != section);
+        if collapsed {
+            self.config_collapsed.push(section);
+        }
+        // Keep the cursor on the header
+        if let Some(pos) = self
+            .config_rows()
+            .iter()
+            .position(|row| *row == ConfigRow::Section(section))
+        {
+            self.config_selected = pos;
+        }
+    }
+
+    /// Values that differ from the built-in defaults
+    pub fn config_differences(&self) -> Vec<ConfigDifference> {
+        settings::diff_from_defaults(&self.config)
+    }
+
+    /// Put one setting (and everything below its key) back at its default
+    pub fn reset_setting(&mut self, key: &str) {
+        match settings::reset_key(&self.config, key) {
+            Ok(config) => {
+                self.apply_config(config);
+                self.set_message(&format!("Reset {} to its default", key));
+            }
+            Err(e) => self.set_message(&format!("Could not reset {}: {}", key, e)),
+        }
+    }
+
+    /// Put every setting back at its built-in default; saved with `s`
+    pub fn reset_config(&mut self) {
+        self.apply_config(settings::defaults().clone());
+        self.config_diff_selected = 0;
+        self.set_message("All settings reset to defaults, press s to save");
+    }
+
+    fn apply_config(&mut self, config: AppConfig) {
+        self.config = config;
+        tools::configure(&self.config.tools);
+        humanize::configure(&self.config.ui);
+        configure_video_extensions(&self.config.input);
+        if self.status_file.as_ref().map(|p| p.path())
+            != self.config.output.status_file.as_deref().map(Path::new)
+        {
+            self.status_file = status_file(&self.config);
+        }
+        let count = self.config_differences().len();
+        self.config_diff_selected = self.config_diff_selected.min(count.saturating_sub(1));
+    }
+
+    /// Open the presets editor, on the tier `name` when given
+    pub fn navigate_to_presets(&mut self, name: Option<&str>) {
+        if let Some(row) =
+            name.and_then(|n| EncodingPresetsConfig::NAMES.iter().position(|&p| p == n))
+        {
+            self.preset_row = row;
+        }
+        self.preset_input = None;
+        self.clear_message();
+        self.current_screen = Screen::Presets;
+    }
+
+    /// Tier and field of the highlighted presets cell
+    pub fn selected_preset_cell(&self) -> (&'static str, PresetField) {
+        (
+            EncodingPresetsConfig::NAMES
+                [self.preset_row.min(EncodingPresetsConfig::NAMES.len() - 1)],
+            PresetField::ALL[self.preset_column.min(PresetField::ALL.len() - 1)],
+        )
+    }
+
+    /// Step the highlighted preset value, staying within its range
+    pub fn adjust_preset(&mut self, increase: bool) {
+        let (name, field) = self.selected_preset_cell();
+        let Some(preset) = self.config.presets.get_mut(name) else {
+            return;
+        };
+        let current = preset.field(field);
+        let value = if increase {
+            current.saturating_add(1)
+        } else {
+            current.saturating_sub(1)
+        };
+        // Out of range only at the ends, where the value stays put
+        let _ = preset.set_field(field, value);
+    }
+
+    /// Apply the typed value to the highlighted cell
+    ///
+    /// An invalid value stays in the input with the reason shown, so it can
+    /// be corrected.
+    pub fn commit_preset_input(&mut self) {
+        let Some(input) = self.preset_input.clone() else {
+            return;
+        };
+        let (name, field) = self.selected_preset_cell();
+        let result = match input.parse::<u8>() {
+            Ok(value) => match self.config.presets.get_mut(name) {
+                Some(preset) => preset.set_field(field, value).map_err(|e| e.to_string()),
+                None => Ok(()),
+            },
+            Err(_) => {
+                let range = field.range();
+                Err(format!(
+                    "{} must be a number between {} and {}",
+                    field.label(),
+                    range.start(),
+                    range.end()
+                ))
+            }
+        };
+        match result {
+            Ok(()) => {
+                self.preset_input = None;
+                self.clear_message();
+            }
+            Err(message) => self.set_message(&message),
+        }
+    }
+
+    /// Change the search text, putting the cursor on the first match
+    pub fn set_config_filter(&mut self, filter: String) {
+        self.config_filter = filter;
+        let rows = self.config_rows();
+        self.config_selected = rows
+            .iter()
+            .position(|row| matches!(row, ConfigRow::Setting(_)))
+            .unwrap_or(0);
+    }
+
+    /// Switch to the next (or previous) encoder the installed FFmpeg can drive
+    pub fn cycle_encoder(&mut self, forward: bool) {
+        let mut encoder = self.config.encoder;
+        for _ in 0..Encoder::ALL.len() {
+            encoder = encoder.cycle(forward);
+            if self.deps.encoder_usable(encoder.ffmpeg_name()) {
+                self.config.encoder = encoder;
+                return;
+            }
+        }
+    }
+
+    /// Turn VMAF verification on or off; it stays off when libvmaf can't run it
+    pub fn toggle_vmaf(&mut self) -> Result<(), String> {
+        let quality = &mut self.config.quality;
+        if !quality.vmaf_enabled
+            && let Some(reason) = self.deps.unsupported_reason(Feature::VmafVerification)
+        {
+            return Err(reason);
+        }
+        quality.vmaf_enabled = !quality.vmaf_enabled;
+        Ok(())
+    }
+
     pub fn navigate_to_file_confirm(&mut self) {
         self.file_confirm_scroll = 0;
+        self.probe_for_verdicts();
+        self.check_space();
         self.current_screen = Screen::FileConfirm;
     }
 
+    /// Compare the space the queued outputs likely need with what their
+    /// volumes have free
+    ///
+    /// Probed files are estimated by their codec, the rest by their size.
+    pub fn check_space(&mut self) {
+        self.space_needs = if self.config.output.space_check == SpaceCheck::Off {
+            Vec::new()
+        } else {
+            preflight::space_needs(&self.queue.jobs, &self.config.output, |i, _| {
+                Some(self.file_probes.get(&i)?.as_ref()?.estimated_size)
+            })
+        };
+    }
+
+    /// Volumes the queued outputs likely won't fit on
+    pub fn space_shortfalls(&self) -> Vec<&VolumeNeed> {
+        self.space_needs.iter().filter(|n| n.is_short()).collect()
+    }
+
+    /// Probe the queued files in the background for their verdicts
+    fn probe_for_verdicts(&mut self) {
+        self.file_probes.clear();
+        // Disc titles are read through their own demuxers, never judged
+        let paths: Vec<(usize, PathBuf)> = self
+            .queue
+            .jobs
+            .iter()
+            .enumerate()
+            .filter(|(_, j)| j.disc_title.is_none())
+            .map(|(i, j)| (i, j.path.clone()))
+            .collect();
+        if paths.is_empty() {
+            self.probe_receiver = None;
+            return;
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.probe_receiver = Some(rx);
+        let workers = std::thread::available_parallelism()
+            .map_or(4, |n| n.get())
+            .min(paths.len());
+        let next = AtomicUsize::new(0);
+
+        // Top of the list first, since that is what the screen shows
+        std::thread::spawn(move || {
+            std::thread::scope(|s| {
+                for _ in 0..workers {
+                    s.spawn(|| {
+                        while let Some((index, path)) =
+                            paths.get(next.fetch_add(1, Ordering::Relaxed))
+                        {
+                            if tx.is_closed() {
+                                break;
+                            }
+                            let entry =
+                                std::panic::catch_unwind(|| audit::probe(path)).unwrap_or(None);
+                            let _ = tx.send((*index, entry));
+                        }
+                    });
+                }
+            });
+        });
+    }
+
+    /// Pick up where the last session left off: the explorer's folder, the
+    /// way files were opened and the device profiles of the queue run
+    pub fn restore_session(&mut self) {
+        let session = SessionState::load();
+        if let Some(dir) = session.last_dir.filter(|d| d.is_dir()) {
+            self.current_dir = dir;
+        }
+        self.selection_mode = if session.select_folder {
+            SelectionMode::Folder
+        } else {
+            SelectionMode::File
+        };
+        self.recursive_scan = session.select_folder && session.recursive;
+        self.active_profiles = session
+            .profiles
+            .iter()
+            .filter_map(|name| self.config.profiles.iter().position(|p| &p.name == name))
+            .collect();
+        self.active_profiles.sort();
+        self.active_profiles.dedup();
+        self.home_index = self.last_home_entry();
+    }
+
+    /// Remember the explorer's folder, how files were opened and the device
+    /// profiles for the next start
+    pub fn save_session(&self) {
+        let session = SessionState {
+            last_dir: Some(self.current_dir.clone()),
+            select_folder: self.selection_mode == SelectionMode::Folder,
+            recursive: self.recursive_scan,
+            profiles: self
+                .device_profiles()
+                .iter()
+                .map(|p| p.name.clone())
+                .collect(),
+        };
+        if let Err(e) = session.save() {
+            warn!("Failed to save the session: {}", e);
+        }
+    }
+
+    /// Ask whether to continue the queue the last run left unfinished
+    pub fn offer_saved_queue(&mut self) {
+        let Some(saved) = SavedQueue::load() else {
+            return;
+        };
+        self.restorable_queue = Some(saved);
+        self.confirm_dialog = Some(ConfirmAction::RestoreQueue);
+        self.confirm_selection = true;
+    }
+
+    /// Queue the saved jobs again and analyze them; files confirmed before
+    /// encode without being configured again
+    pub fn restore_queue(&mut self) {
+        let Some(saved) = self.restorable_queue.take() else {
+            return;
+        };
+        let total = saved.jobs.len();
+        self.queue.reset();
+        self.queue.jobs = saved
+            .jobs
+            .iter()
+            .filter(|job| job.source_exists())
+            .map(|job| job.to_job())
+            .collect();
+        let missing = total - self.queue.jobs.len();
+        if self.queue.jobs.is_empty() {
+            self.set_message("None of the saved files exist anymore");
+            return;
+        }
+        self.analyze_jobs();
+        let message = match missing {
+            0 => format!("Restored {} files", total),
+            _ => format!(
+                "Restored {} files, {} no longer exist",
+                self.queue.jobs.len(),
+                missing
+            ),
+        };
+        self.set_message(&message);
+    }
+
+    /// Save the unfinished jobs when they changed, so quitting or a crash
+    /// doesn't lose the queue
+    ///
+    /// A queue saved by an earlier run stays until this one has jobs of its
+    /// own to save.
+    pub fn save_queue(&mut self) {
+        if self.simulate {
+            return;
+        }
+        let saved = SavedQueue::of(&self.queue);
+        let unchanged = match self.saved_queue {
+            Some(ref last) => *last == saved,
+            None => saved.jobs.is_empty(),
+        };
+        if unchanged {
+            return;
+        }
+        if let Err(e) = saved.save() {
+            warn!("Failed to save the queue: {}", e);
+        }
+        self.saved_queue = Some(saved);
+    }
+
+    /// Home menu entry of the last way files were opened
+    fn last_home_entry(&self) -> usize {
+        match (self.folder_task, &self.selection_mode, self.recursive_scan) {
+            (FolderTask::Audit, ..) => 3,
+            (FolderTask::Cleanup, ..) => 4,
+            (FolderTask::Encode, SelectionMode::File, _) => 0,
+            (FolderTask::Encode, SelectionMode::Folder, false) => 1,
+            (FolderTask::Encode, SelectionMode::Folder, true) => 2,
+        }
+    }
+
+    /// Look for a newer release in the background, when enabled
+    pub fn start_update_check(&mut self) {
+        if !self.config.ui.check_for_updates {
+            return;
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.update_receiver = Some(rx);
+        std::thread::spawn(move || {
+            if let Some(version) = update::available_update() {
+                let _ = tx.send(version);
+            }
+        });
+    }
+
+    pub fn process_probe_results(&mut self, results: Vec<(usize, Option<AuditEntry>)>) {
+        self.file_probes.extend(results);
+    }
+
     // File explorer
 
     pub fn refresh_dir_entries(&mut self) {
@@ -267,6 +1002,151 @@ impl App {
         }
     }
 
+    /// Move the Queue selection, clamped to the list
+    pub fn queue_select(&mut self, index: usize) {
+        self.queue_index = index.min(self.queue.jobs.len().saturating_sub(1));
+        self.queue_list_state.select(Some(self.queue_index));
+    }
+
+    /// Move the Finish results selection, clamped to the list
+    pub fn finish_select(&mut self, index: usize) {
+        self.finish_index = index.min(self.queue.jobs.len().saturating_sub(1));
+        self.finish_list_state.select(Some(self.finish_index));
+    }
+
+    /// Re-encode the selected Finish result if its failure is retryable
+    pub fn retry_selected_job(&mut self) {
+        let Some(job) = self.queue.jobs.get_mut(self.finish_index) else {
+            return;
+        };
+        let JobStatus::Error { kind, .. } = job.status else {
+            return;
+        };
+        if kind == ErrorKind::ElementaryStream {
+            self.encode_elementary_stream();
+            return;
+        }
+        if !kind.is_retryable() || job.metadata.is_none() {
+            self.set_message(&format!(
+                "{} cannot be retried: {}",
+                kind.label(),
+                kind.hint()
+            ));
+            return;
+        }
+
+        job.status = JobStatus::Ready;
+        self.queue.error_count = self.queue.error_count.saturating_sub(1);
+        self.start_encoding();
+    }
+
+    /// Encode the selected raw video stream, reading it at the configured frame rate
+    fn encode_elementary_stream(&mut self) {
+        let Some(frame_rate) = self.config.ffmpeg.assumed_frame_rate() else {
+            self.set_message(&format!(
+                "Invalid ffmpeg.elementary_frame_rate \"{}\"",
+                self.config.ffmpeg.elementary_frame_rate
+            ));
+            return;
+        };
+        let output = self.config.output.clone();
+        let profiles: Vec<DeviceProfile> = self.device_profiles().into_iter().cloned().collect();
+        let Some(job) = self.queue.jobs.get_mut(self.finish_index) else {
+            return;
+        };
+
+        let analysis = match analyzer::analyze_elementary(&job.path.to_string_lossy(), frame_rate) {
+            Ok(analysis) => analysis,
+            Err(e) => {
+                self.set_message(&e.to_string());
+                return;
+            }
+        };
+        info!(
+            "Encoding raw stream {} at an assumed {}/{} fps",
+            job.filename(),
+            frame_rate.0,
+            frame_rate.1
+        );
+        job.metadata = Some(analysis.metadata);
+        job.audio_tracks = analysis.audio_tracks;
+        job.subtitle_tracks = analysis.subtitle_tracks;
+        job.select_all_tracks();
+        job.assumed_frame_rate = Some(frame_rate);
+        job.profiles = profiles;
+        job.generate_output_path(&output);
+        job.status = JobStatus::Ready;
+        self.queue.error_count = self.queue.error_count.saturating_sub(1);
+        self.start_encoding();
+    }
+
+    /// Output file of the selected Finish result, if it was written
+    fn selected_finish_output(&self) -> Option<PathBuf> {
+        self.queue
+            .jobs
+            .get(self.finish_index)
+            .and_then(|j| j.output_path.clone())
+            .filter(|p| p.exists())
+    }
+
+    /// Output of the most recently completed job in the queue
+    pub fn last_finished_output(&self) -> Option<PathBuf> {
+        self.queue
+            .jobs
+            .iter()
+            .rev()
+            .filter(|j| {
+                matches!(
+                    j.status,
+                    JobStatus::Done
+                        | JobStatus::DoneWithVmaf { .. }
+                        | JobStatus::QualityWarning { .. }
+                )
+            })
+            .find_map(|j| j.output_path.clone().filter(|p| p.exists()))
+    }
+
+    /// Play an output file in the configured or default player
+    pub fn play_output(&mut self, path: Option<PathBuf>) {
+        let Some(path) = path else {
+            self.set_message("No output file to play");
+            return;
+        };
+        match open_in_player(&path, self.config.output.player.as_deref()) {
+            Ok(()) => self.clear_message(),
+            Err(e) => self.set_message(&e.to_string()),
+        }
+    }
+
+    /// Play the selected Finish result
+    pub fn play_selected_output(&mut self) {
+        self.play_output(self.selected_finish_output());
+    }
+
+    /// Copy the selected result's output path to the clipboard
+    pub fn copy_selected_output_path(&mut self) {
+        let Some(path) = self.selected_finish_output() else {
+            self.set_message("No output file for this entry");
+            return;
+        };
+        match copy_to_clipboard(&path.to_string_lossy()) {
+            Ok(()) => self.set_message("Output path copied to clipboard"),
+            Err(e) => self.set_message(&e.to_string()),
+        }
+    }
+
+    /// Open the folder containing the selected result's output
+    pub fn open_selected_output_folder(&mut self) {
+        let Some(path) = self.selected_finish_output() else {
+            self.set_message("No output file for this entry");
+            return;
+        };
+        match reveal_in_file_manager(&path) {
+            Ok(()) => self.clear_message(),
+            Err(e) => self.set_message(&e.to_string()),
+        }
+    }
+
     /// Toggle a file in the multi-select list
     pub fn toggle_file_selection(&mut self) {
         if self.dir_entries.is_empty() {
@@ -285,14 +1165,73 @@ impl App {
         }
     }
 
+    /// Probed queue files already in an efficient codec that would be encoded
+    ///
+    /// AV1 sources only count when re-encoding them is enabled, since they
+    /// are skipped after analysis otherwise.
+    pub fn efficient_sources(&self) -> Vec<&AuditEntry> {
+        let reencode_av1 = self.config.quality.reencode_av1;
+        self.file_probes
+            .values()
+            .flatten()
+            .filter(|e| e.family.is_efficient())
+            .filter(|e| e.family != CodecFamily::Av1 || reencode_av1)
+            .collect()
+    }
+
+    /// Proceed from the confirmation screen, checking free space first
+    pub fn proceed_from_file_confirm(&mut self) {
+        self.check_space();
+        if let Some(short) = self.space_shortfalls().first() {
+            if self.config.output.space_check == SpaceCheck::Block {
+                let message = format!("Not enough space on {}", short.describe());
+                self.set_message(&message);
+                return;
+            }
+            let answered = self
+                .session_answers
+                .contains(&ConfirmAction::StartWithoutSpace);
+            if !answered {
+                self.confirm_dialog = Some(ConfirmAction::StartWithoutSpace);
+                self.confirm_selection = false;
+                return;
+            }
+        }
+        self.proceed_past_space_check();
+    }
+
+    /// Proceed once free space is settled, asking first about AV1 and VP9 sources
+    pub fn proceed_past_space_check(&mut self) {
+        let answered = self
+            .session_answers
+            .contains(&ConfirmAction::ConvertEfficientSources);
+        if self.efficient_sources().is_empty() || answered {
+            self.confirm_queued_files();
+        } else {
+            self.confirm_dialog = Some(ConfirmAction::ConvertEfficientSources);
+            self.confirm_selection = false;
+        }
+    }
+
     /// Confirm the queued files from the confirmation screen and start analysis
     pub fn confirm_queued_files(&mut self) {
         self.selected_files.clear();
+        self.probe_receiver = None;
+        // Probes are keyed by position, which the ordering changes
+        let durations: HashMap<usize, f64> = self
+            .file_probes
+            .drain()
+            .filter_map(|(i, probe)| Some((i, probe?.duration_secs)))
+            .filter(|&(_, duration)| duration > 0.0)
+            .collect();
+        self.queue
+            .order_jobs(self.config.performance.queue_order, &durations);
         self.analyze_jobs();
     }
 
     /// Navigate back from file confirm to the explorer
     pub fn cancel_file_confirm(&mut self) {
+        self.probe_receiver = None;
         if self.selection_mode == SelectionMode::File {
             self.selected_files = self.queue.jobs.iter().map(|j| j.path.clone()).collect();
         }
@@ -326,9 +1265,32 @@ impl App {
 
         let selected = self.dir_entries[self.explorer_index].clone();
 
+        if self.folder_task != FolderTask::Encode {
+            if selected == Path::new("..") || !selected.is_dir() {
+                self.enter_directory();
+            } else if self.folder_task == FolderTask::Audit {
+                self.run_audit(&selected);
+            } else {
+                self.scan_leftovers(&selected);
+            }
+            return;
+        }
+
+        if selected != Path::new("..") && detect_disc(&selected).is_some() {
+            self.open_disc(&selected);
+            return;
+        }
+
         match self.selection_mode {
             SelectionMode::File => {
-                if selected == Path::new("..") || selected.is_dir() {
+                if selected
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("iso"))
+                {
+                    self.set_message(
+                        "Disc images can't be read directly; mount it and open its BDMV or VIDEO_TS folder",
+                    );
+                } else if selected == Path::new("..") || selected.is_dir() {
                     self.enter_directory();
                 } else if is_video_file(&selected) {
                     if self.selected_files.is_empty() {
@@ -368,22 +1330,181 @@ impl App {
         }
     }
 
-    pub fn scan_folder(&mut self, folder: &PathBuf, recursive: bool) {
-        self.queue.jobs.clear();
+    // Disc titles
 
-        if recursive {
-            let mut paths: Vec<PathBuf> = Vec::new();
-            collect_video_files(folder, &mut paths);
-            paths.sort();
-            for path in paths {
-                self.queue.jobs.push(EncodingJob::new(path));
+    /// List the titles of a Blu-ray or DVD folder and let the user pick one
+    pub fn open_disc(&mut self, dir: &Path) {
+        match analyzer::list_titles(dir) {
+            Ok(titles) if !titles.is_empty() => {
+                self.disc_titles = titles;
+                self.disc_title_index = 0;
+                self.clear_message();
+                self.current_screen = Screen::DiscTitles;
             }
-        } else if let Ok(entries) = std::fs::read_dir(folder) {
-            let mut paths: Vec<PathBuf> = entries
-                .filter_map(|e| e.ok())
-                .map(|e| e.path())
-                .filter(|p| is_video_file(p))
-                .collect();
+            Ok(_) => self.set_message("No titles found on this disc"),
+            Err(e) => self.set_message(&e.to_string()),
+        }
+    }
+
+    /// Queue the highlighted disc title and analyze it
+    pub fn select_disc_title(&mut self) {
+        let Some(title) = self.disc_titles.get(self.disc_title_index).cloned() else {
+            return;
+        };
+        self.queue.jobs.clear();
+        self.queue.jobs.push(EncodingJob::from_disc_title(title));
+        self.analyze_jobs();
+    }
+
+    pub fn cancel_disc_titles(&mut self) {
+        self.disc_titles.clear();
+        let select_folder = self.selection_mode == SelectionMode::Folder;
+        self.current_screen = Screen::FileExplorer { select_folder };
+    }
+
+    // Audit
+
+    /// Probe every video under a folder and show what converting it would save
+    pub fn run_audit(&mut self, dir: &Path) {
+        let report = audit::audit(dir, &self.config.input);
+        if report.entries.is_empty() && report.unreadable.is_empty() {
+            self.set_message("No video files found in this folder");
+            return;
+        }
+        self.audit = Some(report);
+        self.audit_select(0);
+        self.clear_message();
+        self.current_screen = Screen::Audit;
+    }
+
+    pub fn audit_select(&mut self, index: usize) {
+        let len = self.audit.as_ref().map_or(0, |r| r.entries.len());
+        self.audit_index = index.min(len.saturating_sub(1));
+        self.audit_list_state.select(Some(self.audit_index));
+    }
+
+    /// Save the audit as CSV in the data directory
+    pub fn export_audit(&mut self) {
+        let Some(ref report) = self.audit else {
+            return;
+        };
+        match report.export_csv() {
+            Ok(path) => self.message = Some(format!("Saved {}", path.display())),
+            Err(e) => self.message = Some(format!("Export failed: {}", e)),
+        }
+    }
+
+    pub fn close_audit(&mut self) {
+        self.audit = None;
+        self.current_screen = Screen::FileExplorer {
+            select_folder: true,
+        };
+    }
+
+    // Cleanup
+
+    /// Scan a folder tree for leftovers and list them for review
+    pub fn scan_leftovers(&mut self, dir: &Path) {
+        let leftovers = cleanup::scan(dir, &self.config.output.suffix);
+        if leftovers.is_empty() {
+            self.set_message("Nothing to clean up in this folder");
+            return;
+        }
+        self.cleanup_dir = dir.to_path_buf();
+        self.leftovers = leftovers;
+        self.leftover_select(0);
+        self.clear_message();
+        self.current_screen = Screen::Cleanup;
+    }
+
+    pub fn leftover_select(&mut self, index: usize) {
+        self.leftover_index = index.min(self.leftovers.len().saturating_sub(1));
+        self.leftover_list_state.select(Some(self.leftover_index));
+    }
+
+    /// Mark or unmark the highlighted leftover for deletion
+    pub fn toggle_leftover(&mut self) {
+        if let Some(item) = self.leftovers.get_mut(self.leftover_index) {
+            if item.kind.removable() {
+                item.selected = !item.selected;
+            } else {
+                self.message = Some(format!(
+                    "{} files are only listed, never deleted",
+                    item.kind.label()
+                ));
+            }
+        }
+    }
+
+    /// Mark every removable leftover, or unmark all when all are marked
+    pub fn toggle_all_leftovers(&mut self) {
+        let all = self
+            .leftovers
+            .iter()
+            .filter(|l| l.kind.removable())
+            .all(|l| l.selected);
+        for item in self.leftovers.iter_mut().filter(|l| l.kind.removable()) {
+            item.selected = !all;
+        }
+    }
+
+    /// Files marked for deletion and their total size
+    pub fn selected_leftovers(&self) -> (usize, u64) {
+        self.leftovers
+            .iter()
+            .filter(|l| l.selected && l.kind.removable())
+            .fold((0, 0), |(count, size), l| (count + 1, size + l.size))
+    }
+
+    /// Ask for confirmation before deleting the marked leftovers
+    pub fn request_cleanup(&mut self) {
+        if self.selected_leftovers().0 == 0 {
+            self.set_message("Nothing selected; press Space to mark files");
+            return;
+        }
+        self.confirm_dialog = Some(ConfirmAction::DeleteLeftovers);
+        self.confirm_selection = false;
+    }
+
+    /// Delete the marked leftovers and rescan the folder
+    pub fn delete_leftovers(&mut self) {
+        let result = cleanup::remove(&self.leftovers);
+        for (path, error) in &result.failed {
+            warn!("Failed to delete {}: {}", path.display(), error);
+        }
+        let mut message = format!(
+            "Deleted {} files, freed {}",
+            result.removed,
+            format_file_size(result.freed)
+        );
+        if !result.failed.is_empty() {
+            message.push_str(&format!(" ({} could not be deleted)", result.failed.len()));
+        }
+
+        self.leftovers = cleanup::scan(&self.cleanup_dir, &self.config.output.suffix);
+        self.leftover_select(self.leftover_index);
+        self.message = Some(message);
+    }
+
+    pub fn close_cleanup(&mut self) {
+        self.leftovers.clear();
+        self.current_screen = Screen::FileExplorer {
+            select_folder: true,
+        };
+    }
+
+    pub fn scan_folder(&mut self, folder: &PathBuf, recursive: bool) {
+        self.queue.jobs.clear();
+        let mut detector = VideoDetector::new(&self.config.input);
+
+        if recursive {
+            self.queue.jobs = folder_jobs(folder, &mut detector);
+        } else if let Ok(entries) = std::fs::read_dir(folder) {
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && detector.is_video(p))
+                .collect();
 
             paths.sort();
 
@@ -391,15 +1512,146 @@ impl App {
                 self.queue.jobs.push(EncodingJob::new(path));
             }
         }
+
+        for job in &mut self.queue.jobs {
+            job.source_root = Some(folder.clone());
+        }
+        detector.save();
+    }
+
+    /// Queue files and, recursively, the videos in folders given on the command line
+    pub fn queue_paths(&mut self, paths: &[PathBuf]) {
+        self.queue.jobs.clear();
+        let mut detector = VideoDetector::new(&self.config.input);
+        for path in paths {
+            if path.is_dir() {
+                let mut jobs = folder_jobs(path, &mut detector);
+                for job in &mut jobs {
+                    job.source_root = Some(path.clone());
+                }
+                self.queue.jobs.extend(jobs);
+            } else {
+                self.queue.jobs.push(EncodingJob::new(path.clone()));
+            }
+        }
+        detector.save();
+    }
+
+    /// Queue files and folders given on the command line and show them for
+    /// confirmation, as if picked in the explorer
+    pub fn open_paths(&mut self, paths: &[PathBuf]) {
+        self.queue_paths(paths);
+        if self.queue.jobs.is_empty() {
+            self.set_message("No video files found");
+        } else {
+            self.navigate_to_file_confirm();
+        }
+    }
+
+    // Device profiles
+
+    /// Device profiles selected for this queue run
+    pub fn device_profiles(&self) -> Vec<&DeviceProfile> {
+        self.active_profiles
+            .iter()
+            .filter_map(|&i| self.config.profiles.get(i))
+            .collect()
+    }
+
+    /// Switch the queue run to the next single device profile, wrapping back to none
+    pub fn cycle_profile(&mut self) {
+        if self.config.profiles.is_empty() {
+            self.set_message("No device profiles configured");
+            return;
+        }
+        self.active_profiles = match self.active_profiles.as_slice() {
+            [] => vec![0],
+            [i] if i + 1 < self.config.profiles.len() => vec![i + 1],
+            _ => Vec::new(),
+        };
+        self.apply_profiles_to_queue();
+    }
+
+    /// Switch between encoding and simulating the queue run
+    pub fn toggle_simulate(&mut self) {
+        if self.encoding_active {
+            self.set_message("Can't switch simulation while the queue runs");
+            return;
+        }
+        self.simulate = !self.simulate;
+    }
+
+    /// Add or remove a device profile (by position in the config) for the queue run
+    pub fn toggle_profile(&mut self, index: usize) {
+        if index >= self.config.profiles.len() {
+            return;
+        }
+        if let Some(pos) = self.active_profiles.iter().position(|&i| i == index) {
+            self.active_profiles.remove(pos);
+        } else {
+            self.active_profiles.push(index);
+            self.active_profiles.sort();
+        }
+        self.apply_profiles_to_queue();
+    }
+
+    /// Add or remove a device profile for the file being configured only
+    pub fn toggle_job_profile(&mut self, index: usize) {
+        let Some(profile) = self.config.profiles.get(index).cloned() else {
+            return;
+        };
+        let output = self.config.output.clone();
+        if let Some(job) = self.current_config_job_mut() {
+            if let Some(pos) = job.profiles.iter().position(|p| p.name == profile.name) {
+                job.profiles.remove(pos);
+            } else {
+                job.profiles.push(profile);
+            }
+            job.generate_output_path(&output);
+        }
     }
 
+    /// Give jobs not yet encoding the run's profiles; outputs already named follow the container
+    fn apply_profiles_to_queue(&mut self) {
+        let profiles: Vec<DeviceProfile> = self.device_profiles().into_iter().cloned().collect();
+        let output = self.config.output.clone();
+        for job in &mut self.queue.jobs {
+            if matches!(
+                job.status,
+                JobStatus::Pending | JobStatus::AwaitingConfig | JobStatus::Ready
+            ) {
+                job.profiles = profiles.clone();
+                if job.output_path.is_some() {
+                    job.generate_output_path(&output);
+                }
+            }
+        }
+        if self.current_screen == Screen::FileConfirm {
+            self.check_space();
+        }
+    }
+
+    /// Analyze the queued files in the background
+    ///
+    /// Results arrive through `recv_analysis_results` as each file finishes, so
+    /// the first files can be configured (and encoded) while the rest are read.
     fn analyze_jobs(&mut self) {
-        let suffix = self.config.output.suffix.clone();
-        let container = self.config.output.container.clone();
+        let settings = AnalysisSettings {
+            detect_motion: self.config.quality.still_image_detection,
+            detect_dark: self.config.quality.dark_scene_detection,
+            // Only SVT-AV1 synthesizes grain
+            detect_grain: self.config.quality.adaptive_grain.enabled
+                && self.config.encoder == Encoder::SvtAv1,
+            grain_passthrough: self.config.quality.reencode_av1
+                && self.config.quality.film_grain_passthrough,
+        };
 
         for job in &mut self.queue.jobs {
             job.status = JobStatus::Analyzing;
         }
+        self.queue.config_job_index = 0;
+        self.queue.previous_config_job = None;
+        self.skip_track_config = false;
 
         let paths: Vec<String> = self
             .queue
@@ -408,179 +1660,686 @@ impl App {
             .map(|j| j.path.to_str().unwrap_or("").to_string())
             .collect();
 
-        // Analyze all files
-        let results: Vec<_> = std::thread::scope(|s| {
-            let handles: Vec<_> = paths
-                .iter()
-                .map(|p| s.spawn(|| analyzer::analyze(p.as_str())))
-                .collect();
-            handles
-                .into_iter()
-                .map(|h| {
-                    h.join().unwrap_or_else(|_| {
-                        Err(crate::error::AppError::Analysis(
-                            "Analysis thread panicked".to_string(),
-                        ))
-                    })
-                })
-                .collect()
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.analysis_receiver = Some(rx);
+        let workers = std::thread::available_parallelism()
+            .map_or(4, |n| n.get())
+            .min(paths.len());
+        let next = AtomicUsize::new(0);
+
+        // Files are handed out in queue order, so the first ones finish first
+        std::thread::spawn(move || {
+            std::thread::scope(|s| {
+                for _ in 0..workers {
+                    s.spawn(|| {
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(path) = paths.get(index) else {
+                                break;
+                            };
+                            if tx.is_closed() {
+                                break;
+                            }
+                            let result =
+                                std::panic::catch_unwind(|| analyze_source(path, settings))
+                                    .unwrap_or_else(|_| {
+                                        Err(AppError::Analysis(
+                                            "Analysis thread panicked".to_string(),
+                                        ))
+                                    });
+                            let _ = tx.send((index, result));
+                        }
+                    });
+                }
+            });
         });
 
-        for (job, result) in self.queue.jobs.iter_mut().zip(results) {
+        self.navigate_to_track_config();
+    }
+
+    pub fn process_analysis_results(
+        &mut self,
+        results: Vec<(usize, Result<SourceAnalysis, AppError>)>,
+    ) {
+        let output = self.config.output.clone();
+        let profiles: Vec<DeviceProfile> = self.device_profiles().into_iter().cloned().collect();
+        let reencode_av1 = self.config.quality.reencode_av1;
+        let remux_av1 = self.config.quality.remux_av1;
+
+        for (index, result) in results {
+            let Some(job) = self.queue.jobs.get_mut(index) else {
+                continue;
+            };
+            if !matches!(job.status, JobStatus::Analyzing) {
+                continue;
+            }
             match result {
-                Ok(analysis) => {
-                    // Check if already AV1 - skip
-                    if is_av1_codec(&analysis.metadata.codec_name) {
-                        job.status = JobStatus::Skipped {
-                            reason: "Already AV1".to_string(),
-                        };
-                        self.queue.skipped_count += 1;
+                Ok(source) => {
+                    job.stage_timings.record(Stage::Analyze, source.elapsed);
+                    let analysis = source.analysis;
+                    // Check if already AV1 - skip unless re-encoding or remuxing is asked for
+                    let av1 = is_av1_codec(&analysis.metadata.codec_name);
+                    if av1 && !reencode_av1 && !remux_av1 {
+                        self.queue.skip(index, SkipKind::Rule, "Already AV1");
                     } else {
+                        if av1 && !reencode_av1 {
+                            job.mode = JobMode::Remux;
+                        }
+                        job.still_image = source.motion.and_then(|m| {
+                            StillImageHint::from_motion(&m, &analysis.metadata, &self.config)
+                        });
+                        job.dark_scenes = source
+                            .luma
+                            .and_then(|l| DarkSceneHint::from_luma(&l, &self.config));
+                        job.adaptive_grain = source.noise.and_then(|n| {
+                            GrainHint::from_noise(&n, &analysis.metadata, &self.config)
+                        });
+                        job.source_grain = source.grain;
                         job.metadata = Some(analysis.metadata);
                         job.audio_tracks = analysis.audio_tracks;
                         job.subtitle_tracks = analysis.subtitle_tracks;
                         job.select_all_tracks();
-                        job.generate_output_path(&suffix, &container);
-                        job.status = JobStatus::AwaitingConfig;
+                        if self.config.tracks.exclude_commentary {
+                            job.exclude_commentary();
+                        }
+                        job.apply_subtitle_rules(&self.config.tracks);
+                        job.profiles = profiles.clone();
+                        job.generate_output_path(&output);
+                        job.status = if self.skip_track_config {
+                            JobStatus::Ready
+                        } else {
+                            JobStatus::AwaitingConfig
+                        };
+                        if let Some(saved) = job.restored.take() {
+                            saved.apply(job);
+                        }
                     }
                 }
                 Err(e) => {
                     job.status = JobStatus::Error {
                         message: e.to_string(),
+                        kind: e.kind(),
                     };
                     self.queue.error_count += 1;
                 }
             }
         }
 
-        // Find first job awaiting config
-        self.queue.config_job_index = self
-            .queue
-            .jobs
-            .iter()
-            .position(|j| matches!(j.status, JobStatus::AwaitingConfig))
-            .unwrap_or(0);
-
-        if self
-            .queue
-            .jobs
-            .iter()
-            .any(|j| matches!(j.status, JobStatus::AwaitingConfig))
-        {
-            self.navigate_to_track_config();
+        // Move on when the file on screen was skipped or failed
+        if self.current_config_job().is_none() {
+            self.next_config_job();
         } else {
-            self.navigate_to_finish();
+            self.advance_queue();
         }
     }
 
     // Track configuration
 
+    /// File whose tracks are being configured, `None` while it is still analyzed
     pub fn current_config_job(&self) -> Option<&EncodingJob> {
-        self.queue.jobs.get(self.queue.config_job_index)
+        self.queue
+            .jobs
+            .get(self.queue.config_job_index)
+            .filter(|j| matches!(j.status, JobStatus::AwaitingConfig))
     }
 
     pub fn current_config_job_mut(&mut self) -> Option<&mut EncodingJob> {
-        self.queue.jobs.get_mut(self.queue.config_job_index)
+        self.queue
+            .jobs
+            .get_mut(self.queue.config_job_index)
+            .filter(|j| matches!(j.status, JobStatus::AwaitingConfig))
     }
 
-    pub fn confirm_track_config(&mut self) {
-        if let Some(job) = self.queue.jobs.get_mut(self.queue.config_job_index) {
-            job.status = JobStatus::Ready;
+    /// Switch the configured file between encoding and only rewriting its
+    /// audio and subtitles, for sources already in AV1
+    pub fn cycle_job_mode(&mut self) {
+        let Some(job) = self.current_config_job_mut() else {
+            return;
+        };
+        if !job.cycle_mode() {
+            self.set_message("Only AV1 sources can keep their video");
+            return;
         }
+        let message = format!("Mode: {}", job.mode.display_name());
+        self.set_message(&message);
+    }
 
-        // Find next job awaiting config
-        let next_index = self
-            .queue
+    /// Show the FFmpeg command the configured job would run, without running it
+    pub fn preview_command(&mut self) {
+        let Some(job) = self.current_config_job() else {
+            return;
+        };
+        let Some(metadata) = job.metadata.as_ref() else {
+            return;
+        };
+        let output = job.output_path.as_ref().unwrap_or(&job.path);
+        let command = ffmpeg_command(
+            &job.path.to_string_lossy(),
+            &output.to_string_lossy(),
+            metadata,
+            job.track_selection.clone(),
+            &job.overrides(),
+            &self.config,
+        );
+        self.command_preview = Some(command);
+    }
+
+    /// Copy the previewed command to the clipboard
+    pub fn copy_command_preview(&mut self) {
+        let Some(command) = self.command_preview.take() else {
+            return;
+        };
+        match copy_to_clipboard(&command) {
+            Ok(()) => self.set_message("Command copied to clipboard"),
+            Err(e) => self.set_message(&e.to_string()),
+        }
+    }
+
+    /// Files of the batch not analyzed yet
+    pub fn files_analyzing(&self) -> usize {
+        self.queue
             .jobs
             .iter()
-            .skip(self.queue.config_job_index + 1)
-            .position(|j| matches!(j.status, JobStatus::AwaitingConfig))
-            .map(|i| i + self.queue.config_job_index + 1);
+            .filter(|j| matches!(j.status, JobStatus::Analyzing))
+            .count()
+    }
 
-        if let Some(idx) = next_index {
+    pub fn confirm_track_config(&mut self) {
+        if let Some(job) = self.current_config_job_mut() {
+            job.status = JobStatus::Ready;
+            self.queue.previous_config_job = Some(job.clone());
+        }
+        self.next_config_job();
+    }
+
+    /// Show the next file to configure, or wait for one still analyzed
+    fn next_config_job(&mut self) {
+        let position =
+            |status: fn(&JobStatus) -> bool| self.queue.jobs.iter().position(|j| status(&j.status));
+        let next_index = position(|s| matches!(s, JobStatus::AwaitingConfig))
+            .or_else(|| position(|s| matches!(s, JobStatus::Analyzing)));
+        if let Some(idx) = next_index
+            && idx != self.queue.config_job_index
+        {
             self.queue.config_job_index = idx;
             self.track_focus = TrackFocus::Audio;
             self.audio_cursor = 0;
             self.subtitle_cursor = 0;
-        } else {
-            self.start_encoding();
         }
+        self.advance_queue();
+    }
+
+    /// Leave track configuration, asking first if files are already encoding
+    pub fn leave_track_config(&mut self) {
+        if self.encoding_active {
+            self.confirm_dialog = Some(ConfirmAction::CancelEncoding);
+            self.confirm_selection = false;
+            return;
+        }
+        self.analysis_receiver = None;
+        self.navigate_to_home();
+    }
+
+    /// Hand configured files to the worker and leave track configuration once
+    /// nothing is left to configure
+    ///
+    /// With `performance.overlap_encoding` the first file starts encoding as
+    /// soon as it is configured; otherwise the batch waits for the last one.
+    fn advance_queue(&mut self) {
+        let configuring = self.queue.configuring();
+        if self.config.performance.overlap_encoding || !configuring {
+            self.dispatch_ready_jobs();
+        }
+        if !configuring {
+            // Lets the worker stop after the last job
+            self.job_sender = None;
+            if self.current_screen == Screen::TrackConfig {
+                if self.encoding_active {
+                    self.navigate_to_queue();
+                } else {
+                    self.navigate_to_finish();
+                }
+            }
+        }
+    }
+
+    /// Encode now, keeping the current track selection of files not yet configured
+    pub fn start_queue(&mut self) {
+        match self.current_screen {
+            Screen::FileConfirm => {
+                self.confirm_queued_files();
+                if self.current_screen != Screen::TrackConfig {
+                    return;
+                }
+            }
+            Screen::TrackConfig => {}
+            _ => return,
+        }
+        for job in &mut self.queue.jobs {
+            if matches!(job.status, JobStatus::AwaitingConfig) {
+                job.status = JobStatus::Ready;
+            }
+        }
+        // Files still being analyzed follow as soon as they are read
+        self.skip_track_config = true;
+        self.dispatch_ready_jobs();
+        self.navigate_to_queue();
+        self.advance_queue();
     }
 
     // Encoding
 
+    /// Encode the ready jobs, e.g. a retried one, once the queue has finished
     pub fn start_encoding(&mut self) {
-        info!("Starting encoding process");
+        self.dispatch_ready_jobs();
         self.navigate_to_queue();
-        self.encoding_active = true;
-        self.queue.current_job_index = 0;
-        self.cancel_flag = Arc::new(AtomicBool::new(false));
-
-        let (tx, rx) = mpsc::channel();
-        self.progress_receiver = Some(rx);
+        self.advance_queue();
+    }
 
-        // Collect jobs to encode
-        let worker_jobs: Vec<WorkerJob> = self
+    /// Send the ready jobs to the worker, starting one if none is running
+    fn dispatch_ready_jobs(&mut self) {
+        let ready: Vec<usize> = self
             .queue
             .jobs
             .iter()
             .enumerate()
-            .filter(|(_, j)| matches!(j.status, JobStatus::Ready))
-            .filter_map(|(i, j)| {
-                let metadata = j.metadata.clone()?;
-                Some(WorkerJob {
+            .filter(|(_, j)| matches!(j.status, JobStatus::Ready) && j.metadata.is_some())
+            .map(|(i, _)| i)
+            .collect();
+        if ready.is_empty() {
+            return;
+        }
+
+        if self.job_sender.is_none() {
+            self.start_worker();
+        }
+
+        let mut sent = 0;
+        for index in ready {
+            let outputs = self.queue.split_job(index, &self.config.output);
+            let last = outputs.len().saturating_sub(1);
+            for (position, i) in outputs.into_iter().enumerate() {
+                let Some(metadata) = self.queue.jobs[i].metadata.clone() else {
+                    continue;
+                };
+                if let Some(other) = self.queue.output_taken_by(i) {
+                    let message = format!("Same output as {}", other.filename());
+                    let j = &mut self.queue.jobs[i];
+                    warn!("{} not queued: {}", j.filename(), message);
+                    j.status = JobStatus::Error {
+                        message,
+                        kind: ErrorKind::Other,
+                    };
+                    self.queue.error_count += 1;
+                    continue;
+                }
+                let j = &mut self.queue.jobs[i];
+                let output = j.output_path.clone().unwrap_or_else(|| j.path.clone());
+                if let Err(e) = lock_output_dir(&mut self.output_locks, &output, &self.config) {
+                    warn!("{} not queued: {}", j.filename(), e);
+                    j.status = JobStatus::Error {
+                        message: e.to_string(),
+                        kind: e.kind(),
+                    };
+                    self.queue.error_count += 1;
+                    continue;
+                }
+                let mut overrides = j.overrides();
+                // With several outputs per source, only the last one may delete it
+                overrides.keep_source |= position < last;
+                overrides.simulate = self.simulate;
+                if let Some(profile) = overrides.profile.clone() {
+                    let bitrates = &self.config.tracks.audio_bitrates;
+                    for (audio, track) in j.pointless_audio_reencodes(&profile, bitrates) {
+                        let fits = profile
+                            .audio_channels
+                            .is_some_and(|c| track.channels <= u16::from(c));
+                        if self.config.tracks.lossy_audio == LossyAudio::Copy && fits {
+                            info!(
+                                "{}: copying lossy audio track {}",
+                                j.filename(),
+                                track.index
+                            );
+                            overrides.copy_audio.push(audio);
+                        } else {
+                            warn!(
+                                "{}: audio track {} is already {} at {}, re-encoding to {} {}k saves little",
+                                j.filename(),
+                                track.index,
+                                track.codec.to_uppercase(),
+                                track.bitrate_string(),
+                                profile.audio_codec.display_name(),
+                                profile.audio_bitrate(track.channels, bitrates)
+                            );
+                        }
+                    }
+                }
+                let after = post_actions(&self.config.output, &j.path, j.source_root.as_deref());
+                j.steps = TaskGraph::new(after.clone());
+                let worker_job = WorkerJob {
                     index: i,
                     input: j.path.clone(),
-                    output: j.output_path.clone().unwrap_or_else(|| j.path.clone()),
+                    output,
                     metadata,
                     tracks: j.track_selection.clone(),
-                })
-            })
-            .collect();
+                    overrides,
+                    after,
+                    cancel: self.cancel_token.child_token(),
+                };
+                j.status = JobStatus::Pending;
+                self.job_cancel_tokens.insert(i, worker_job.cancel.clone());
+                if let Some(ref sender) = self.job_sender {
+                    let _ = sender.send(worker_job);
+                }
+                sent += 1;
+            }
+        }
 
-        info!("Jobs to encode: {}", worker_jobs.len());
+        info!("Jobs to encode: {} more", sent);
+        self.queue.total_jobs_to_encode += sent;
+    }
 
+    fn start_worker(&mut self) {
+        info!("Starting encoding process");
+        self.encoding_active = true;
+        self.queue.current_job_index = 0;
+        self.queue.encoding_progress_done = 0;
+        self.queue.total_jobs_to_encode = 0;
+        self.queue.eta = EtaEstimator::default();
+        self.queue.pacing = Pacing::from_history(&history::load(), self.config.encoder);
         self.queue.start_time = Some(std::time::Instant::now());
-        self.queue.total_jobs_to_encode = worker_jobs.len();
+        self.queue.end_time = None;
+        self.cancel_token = CancellationToken::new();
+        self.job_cancel_tokens.clear();
 
-        // Mark jobs as pending
-        for wj in &worker_jobs {
-            if let Some(j) = self.queue.jobs.get_mut(wj.index) {
-                j.status = JobStatus::Pending;
-            }
-        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.progress_receiver = Some(rx);
+        let (job_tx, job_rx) = mpsc::unbounded_channel();
+        self.job_sender = Some(job_tx);
+        self.live_config = SharedConfig::new(self.config.clone());
+
+        tokio::spawn(run_streamed_worker(
+            job_rx,
+            self.live_config.clone(),
+            self.cancel_token.clone(),
+            tx,
+        ));
+    }
 
-        let cancel_flag = self.cancel_flag.clone();
-        let config = self.config.clone();
+    /// Change the VMAF threshold for the files of the running queue not yet verified
+    pub fn adjust_live_vmaf_threshold(&mut self, delta: f64) {
+        let quality = &mut self.config.quality;
+        quality.vmaf_threshold = (quality.vmaf_threshold + delta).clamp(0.0, 100.0);
+        let message = format!(
+            "VMAF threshold {:.0} for files not yet verified",
+            quality.vmaf_threshold
+        );
+        self.live_config.set(self.config.clone());
+        self.set_message(&message);
+    }
 
-        thread::spawn(move || {
-            run_worker(worker_jobs, config, cancel_flag, tx);
-        });
+    /// Turn deleting passing sources on or off for the rest of the running queue
+    pub fn toggle_live_delete_source(&mut self) {
+        let quality = &mut self.config.quality;
+        quality.delete_source = !quality.delete_source;
+        let message = if quality.delete_source {
+            "Sources of files passing VMAF will be deleted"
+        } else {
+            "Sources will be kept for the rest of the queue"
+        };
+        self.live_config.set(self.config.clone());
+        self.set_message(message);
+    }
+
+    /// Pause the running queue or resume it
+    ///
+    /// A paused queue starts no new file and its running encodes and VMAF
+    /// checks are suspended, so they pick up where they were on resume.
+    pub fn toggle_pause(&mut self) {
+        if self.queue.is_paused() {
+            suspend::set_paused(false);
+            self.queue.resume();
+            self.set_message("Resumed");
+        } else {
+            suspend::set_paused(true);
+            self.queue.pause();
+            self.set_message("Paused: press p to resume");
+        }
+        self.publish_queue(true);
     }
 
     pub fn cancel_encoding(&mut self) {
-        self.cancel_flag.store(true, Ordering::Relaxed);
+        self.cancel_token.cancel();
     }
 
-    pub fn process_progress_messages(&mut self) {
-        let messages: Vec<WorkerMessage> = if let Some(ref rx) = self.progress_receiver {
-            let mut msgs = Vec::new();
-            while let Ok(msg) = rx.try_recv() {
-                msgs.push(msg);
+    /// Cancel a single job, leaving the rest of the queue running
+    pub fn cancel_job(&mut self, index: usize) -> bool {
+        match self.job_cancel_tokens.get(&index) {
+            Some(token) if !token.is_cancelled() => {
+                token.cancel();
+                true
             }
-            msgs
+            _ => false,
+        }
+    }
+
+    /// Stop the job that is currently encoding and move on to the next one
+    pub fn cancel_current_job(&mut self) {
+        let encoding = |i: usize| {
+            self.queue
+                .jobs
+                .get(i)
+                .is_some_and(|j| matches!(j.status, JobStatus::Encoding { .. }))
+        };
+        // With several encodes running, the selected one is meant
+        let index = if encoding(self.queue_index) {
+            self.queue_index
         } else {
+            self.queue.current_job_index
+        };
+        let Some(job) = self.queue.jobs.get(index) else {
+            return;
+        };
+        if !matches!(job.status, JobStatus::Encoding { .. }) {
+            return;
+        }
+        let name = job.filename();
+        if self.cancel_job(index) {
+            self.set_message(&format!("Cancelling {}", name));
+        }
+    }
+
+    /// Mark the selected pending job as skipped before the worker reaches it
+    pub fn skip_selected_job(&mut self) {
+        let index = self.queue_index;
+        let Some(job) = self.queue.jobs.get(index) else {
             return;
         };
+        if !matches!(job.status, JobStatus::Pending) {
+            self.set_message("Only pending files can be skipped");
+            return;
+        }
+        if !self.cancel_job(index) {
+            return;
+        }
+
+        let message = format!("Skipped {}", self.queue.jobs[index].filename());
+        self.queue.skip(index, SkipKind::User, "Skipped");
+        self.queue.encoding_progress_done += 1;
+        self.set_message(&message);
+    }
+
+    /// Append a finished job to the results history used for preset recommendations
+    fn record_history(&mut self, idx: usize, vmaf: Option<f64>) {
+        let Some(job) = self.queue.jobs.get(idx) else {
+            return;
+        };
+        let Some(ref metadata) = job.metadata else {
+            return;
+        };
+        // Nothing was encoded, so there is nothing to learn from
+        if job.mode.copies_video() {
+            return;
+        }
 
+        let tier = ResolutionTier::from_dimensions(metadata.width, metadata.height);
+        let record = HistoryRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            file: job.filename(),
+            encoder: self.config.encoder,
+            preset: AppConfig::preset_name(&tier, metadata.hdr_type).to_string(),
+            quality: job
+                .crf
+                .unwrap_or_else(|| preset_quality(metadata, &self.config)),
+            vmaf,
+            source_size: job.source_size,
+            output_size: job
+                .output_path
+                .as_ref()
+                .and_then(|p| std::fs::metadata(p).ok())
+                .map(|m| m.len()),
+            encode_secs: job
+                .stage_timings
+                .get(Stage::Encode)
+                .map(|d| d.as_secs_f64()),
+            before_encode_secs: Some(job.stage_timings.before_encode().as_secs_f64()),
+            after_encode_secs: Some(job.stage_timings.after_encode().as_secs_f64()),
+        };
+        if let Err(e) = history::append(&record) {
+            warn!("Failed to record history: {}", e);
+        }
+
+        if self.config.stats.mode == StatsMode::Off {
+            return;
+        }
+        let record = StatsRecord::from_history(&record, metadata.duration_secs);
+        if let Err(e) = stats::append(&record) {
+            warn!("Failed to record statistics: {}", e);
+        }
+        if self.config.stats.mode == StatsMode::Submit {
+            self.stats_batch.push(record);
+        }
+    }
+
+    /// Wait for the next batch of worker messages or analysis results
+    ///
+    /// Never resolves while nothing runs in the background, so it can sit in a
+    /// `select!` with input.
+    pub async fn recv_background(&mut self) -> Background {
+        tokio::select! {
+            batch = recv_batch(&mut self.progress_receiver) => Background::Worker(batch),
+            batch = recv_batch(&mut self.analysis_receiver) => Background::Analysis(batch),
+            batch = recv_batch(&mut self.probe_receiver) => Background::Probes(batch),
+            batch = recv_batch(&mut self.update_receiver) => Background::Update(batch),
+        }
+    }
+
+    pub fn process_background(&mut self, update: Background) {
+        let worker_stopped = matches!(update, Background::Worker(None));
+        self.process_update(update);
+        self.publish_queue(worker_stopped);
+    }
+
+    /// Publish the queue for attached viewers and the status file, throttled
+    /// unless `force` is set
+    pub fn publish_queue(&mut self, force: bool) {
+        for publisher in [self.publisher.as_mut(), self.status_file.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            if let Err(e) = publisher.publish(&self.queue, force) {
+                warn!(
+                    "Failed to publish the queue to {}: {}",
+                    publisher.path().display(),
+                    e
+                );
+            }
+        }
+    }
+
+    fn process_update(&mut self, update: Background) {
+        match update {
+            Background::Worker(Some(messages)) => self.process_progress_messages(messages),
+            Background::Worker(None) => {
+                let messages = self.worker_stopped();
+                self.process_progress_messages(messages);
+            }
+            Background::Analysis(Some(results)) => self.process_analysis_results(results),
+            Background::Analysis(None) => self.analysis_receiver = None,
+            Background::Probes(Some(results)) => self.process_probe_results(results),
+            Background::Probes(None) => {
+                self.probe_receiver = None;
+                if self.current_screen == Screen::FileConfirm {
+                    self.check_space();
+                }
+            }
+            Background::Update(Some(versions)) => self.available_update = versions.last().copied(),
+            Background::Update(None) => self.update_receiver = None,
+        }
+    }
+
+    /// Messages closing the queue when the worker dropped its sender
+    fn worker_stopped(&mut self) -> Vec<WorkerMessage> {
+        self.progress_receiver = None;
+        if !self.encoding_active {
+            return Vec::new();
+        }
+
+        // Still encoding means the worker died, e.g. from a panic
+        warn!("Encoding worker stopped unexpectedly");
+        let mut messages: Vec<WorkerMessage> = self
+            .queue
+            .jobs
+            .iter()
+            .position(|j| matches!(j.status, JobStatus::Encoding { .. }))
+            .map(|idx| {
+                let report = crash::last_bundle()
+                    .map(|path| format!(" (crash report: {})", path.display()))
+                    .unwrap_or_default();
+                WorkerMessage::Error(
+                    idx,
+                    AppError::CommandExecution(format!(
+                        "Encoding worker stopped unexpectedly{}",
+                        report
+                    )),
+                )
+            })
+            .into_iter()
+            .collect();
+        messages.push(WorkerMessage::Cancelled);
+        messages
+    }
+
+    pub fn process_progress_messages(&mut self, messages: Vec<WorkerMessage>) {
         let mut should_finish = false;
+        let mut cancelled = false;
 
         for msg in messages {
             match msg {
                 WorkerMessage::Progress(idx, progress) => {
                     if let Some(job) = self.queue.jobs.get_mut(idx) {
-                        job.status = JobStatus::Encoding { progress };
-                        self.queue.current_job_index = idx;
+                        if !matches!(job.status, JobStatus::Encoding { .. }) {
+                            job.phase_started = Some(std::time::Instant::now());
+                        }
+                        job.status = JobStatus::Encoding {
+                            progress: progress.percent,
+                        };
+                        // With several encodes running, the current job is
+                        // the oldest one still going, and the graphs follow it
+                        let current = self.queue.current_job_index;
+                        let current_running =
+                            current != idx
+                                && self.queue.jobs.get(current).is_some_and(|j| {
+                                    matches!(j.status, JobStatus::Encoding { .. })
+                                });
+                        if !current_running {
+                            self.queue.current_job_index = idx;
+                            self.queue.stats.record(idx, &progress);
+                        }
+                        self.queue.record_eta_sample();
                     }
                 }
                 WorkerMessage::Done(idx) => {
@@ -589,6 +2348,7 @@ impl App {
                         self.queue.converted_count += 1;
                         self.queue.encoding_progress_done += 1;
                     }
+                    self.record_history(idx, None);
                     if self.queue.all_completed() {
                         self.encoding_active = false;
                         should_finish = true;
@@ -600,14 +2360,33 @@ impl App {
                         self.queue.converted_count += 1;
                         self.queue.encoding_progress_done += 1;
                     }
+                    self.record_history(idx, Some(score));
                     if self.queue.all_completed() {
                         self.encoding_active = false;
                         should_finish = true;
                     }
                 }
-                WorkerMessage::Error(idx, msg) => {
+                WorkerMessage::Simulated(idx, simulated) => {
+                    // Simulated runs stay out of the history so they don't skew estimates
                     if let Some(job) = self.queue.jobs.get_mut(idx) {
-                        job.status = JobStatus::Error { message: msg };
+                        job.status = JobStatus::Done;
+                        job.output_size = simulated.estimated_size;
+                        job.simulation = Some(simulated);
+                        self.queue.converted_count += 1;
+                        self.queue.encoding_progress_done += 1;
+                    }
+                    if self.queue.all_completed() {
+                        self.encoding_active = false;
+                        should_finish = true;
+                    }
+                }
+                WorkerMessage::Error(idx, err) => {
+                    if let Some(job) = self.queue.jobs.get_mut(idx) {
+                        warn!("{} failed: {}", job.filename(), err);
+                        job.status = JobStatus::Error {
+                            message: err.to_string(),
+                            kind: err.kind(),
+                        };
                         self.queue.error_count += 1;
                         self.queue.encoding_progress_done += 1;
                     }
@@ -622,11 +2401,41 @@ impl App {
                         self.queue.converted_count += 1;
                         self.queue.encoding_progress_done += 1;
                     }
+                    self.record_history(idx, Some(vmaf));
                     if self.queue.all_completed() {
                         self.encoding_active = false;
                         should_finish = true;
                     }
                 }
+                WorkerMessage::StageTime(idx, stage, elapsed) => {
+                    if let Some(job) = self.queue.jobs.get_mut(idx) {
+                        job.stage_timings.record(stage, elapsed);
+                        if matches!(stage, Stage::Prepare | Stage::Encode) {
+                            job.phase_started = Some(std::time::Instant::now());
+                        }
+                        self.queue.record_eta_sample();
+                    }
+                }
+                WorkerMessage::VmafWorstSegments(idx, segments) => {
+                    if let Some(job) = self.queue.jobs.get_mut(idx) {
+                        job.vmaf_worst_segments = segments;
+                    }
+                }
+                WorkerMessage::ValidationIssues(idx, issues) => {
+                    if let Some(job) = self.queue.jobs.get_mut(idx) {
+                        job.validation_issues = issues;
+                    }
+                }
+                WorkerMessage::EncoderFallback(idx, encoder, reason) => {
+                    if let Some(job) = self.queue.jobs.get_mut(idx) {
+                        job.encoder_fallback = Some((encoder, reason));
+                    }
+                }
+                WorkerMessage::Provenance(idx, provenance) => {
+                    if let Some(job) = self.queue.jobs.get_mut(idx) {
+                        job.provenance = Some(provenance);
+                    }
+                }
                 WorkerMessage::SourceDeleted(idx) => {
                     if let Some(job) = self.queue.jobs.get_mut(idx) {
                         job.source_deleted = true;
@@ -637,23 +2446,151 @@ impl App {
                         job.source_kept_vmaf = Some(vmaf);
                     }
                 }
+                WorkerMessage::SourceKept(idx, reason) => {
+                    if let Some(job) = self.queue.jobs.get_mut(idx) {
+                        job.source_kept_reason = Some(reason);
+                    }
+                }
+                WorkerMessage::Remuxed(idx, encoded_size, remuxed_size) => {
+                    if let Some(job) = self.queue.jobs.get_mut(idx) {
+                        job.encoded_size = Some(encoded_size);
+                        job.output_size = Some(remuxed_size);
+                    }
+                }
+                WorkerMessage::JobCancelled(idx) => {
+                    // Jobs skipped before they started are already counted
+                    if self
+                        .queue
+                        .jobs
+                        .get(idx)
+                        .is_some_and(|job| !matches!(job.status, JobStatus::Skipped { .. }))
+                    {
+                        self.queue.skip(idx, SkipKind::Cancelled, "Cancelled");
+                        self.queue.encoding_progress_done += 1;
+                    }
+                    if self.queue.all_completed() {
+                        self.encoding_active = false;
+                        should_finish = true;
+                    }
+                }
+                WorkerMessage::Step(idx, position, status) => {
+                    if let Some(job) = self.queue.jobs.get_mut(idx) {
+                        job.steps.set_status(position, status);
+                    }
+                }
+                WorkerMessage::OutputMoved(idx, path) => {
+                    if let Some(job) = self.queue.jobs.get_mut(idx) {
+                        job.output_path = Some(path);
+                    }
+                }
+                WorkerMessage::WaitingForSchedule(window) => {
+                    self.queue.waiting_for_schedule = window;
+                }
+                WorkerMessage::Claimed(idx, holder) => {
+                    if idx < self.queue.jobs.len() {
+                        self.queue
+                            .skip(idx, SkipKind::Rule, format!("Claimed by {}", holder));
+                        self.queue.encoding_progress_done += 1;
+                    }
+                    if self.queue.all_completed() {
+                        self.encoding_active = false;
+                        should_finish = true;
+                    }
+                }
                 WorkerMessage::Cancelled => {
-                    for job in &mut self.queue.jobs {
-                        if matches!(job.status, JobStatus::Encoding { .. }) {
-                            job.status = JobStatus::Skipped {
-                                reason: "Cancelled".to_string(),
-                            };
+                    self.queue.waiting_for_schedule = None;
+                    for idx in 0..self.queue.jobs.len() {
+                        if matches!(
+                            self.queue.jobs[idx].status,
+                            JobStatus::Encoding { .. }
+                                | JobStatus::Analyzing
+                                | JobStatus::AwaitingConfig
+                        ) {
+                            self.queue.skip(idx, SkipKind::Cancelled, "Cancelled");
                         }
                     }
+                    self.job_sender = None;
+                    self.analysis_receiver = None;
                     self.encoding_active = false;
                     should_finish = true;
+                    cancelled = true;
                 }
             }
         }
 
         if should_finish {
+            if self.queue.is_paused() {
+                suspend::set_paused(false);
+                self.queue.resume();
+            }
+            self.output_locks.clear();
             self.queue.end_time = Some(std::time::Instant::now());
             self.navigate_to_finish();
+            self.write_summary();
+            if !cancelled {
+                self.send_completion_email();
+            }
+            self.submit_stats();
+        }
+    }
+
+    /// Write the batch's JSON summary for scripts; simulated runs leave the
+    /// last real one in place
+    fn write_summary(&self) {
+        if self.simulate {
+            return;
+        }
+        let path = self.config.output.summary_file.as_deref().map(Path::new);
+        match RunSummary::of(&self.queue).write(path) {
+            Ok(path) => info!("Run summary written to {}", path.display()),
+            Err(e) => warn!("Failed to write the run summary: {}", e),
+        }
+    }
+
+    /// Post this batch's statistics in the background if submitting is opted in
+    fn submit_stats(&mut self) {
+        let records = std::mem::take(&mut self.stats_batch);
+        if records.is_empty() {
+            return;
+        }
+        let Some(endpoint) = self.config.stats.endpoint.clone().filter(|e| !e.is_empty()) else {
+            warn!("Statistics are kept locally: stats.endpoint is not set");
+            return;
+        };
+        let task = tokio::task::spawn_blocking(move || match stats::submit(&endpoint, &records) {
+            Ok(()) => info!("Submitted statistics of {} files", records.len()),
+            Err(e) => warn!("Failed to submit statistics: {}", e),
+        });
+        self.batch_end_tasks.push(task);
+    }
+
+    /// Email the batch report in the background if notifications are enabled
+    fn send_completion_email(&mut self) {
+        let notifications = &self.config.notifications;
+        if !notifications.email_enabled {
+            return;
+        }
+
+        let config = notifications.clone();
+        let subject = notify::batch_subject(&self.queue);
+        let body = notify::batch_report(&self.queue);
+        let task = tokio::task::spawn_blocking(move || {
+            if let Err(e) = notify::send_email(&config, &subject, &body) {
+                warn!("Failed to send email notification: {}", e);
+            }
+        });
+        self.batch_end_tasks.push(task);
+    }
+
+    /// Wait up to `limit` for the email and statistics of finished batches,
+    /// which exiting the process would otherwise cut off
+    pub async fn finish_batch_end_tasks(&mut self, limit: Duration) {
+        let deadline = tokio::time::Instant::now() + limit;
+        for task in std::mem::take(&mut self.batch_end_tasks) {
+            if tokio::time::timeout_at(deadline, task).await.is_err() {
+                warn!("Gave up waiting for the batch email and statistics");
+                return;
+            }
         }
     }
 
@@ -662,19 +2599,178 @@ impl App {
         self.encoding_active = false;
         self.selected_files.clear();
         self.progress_receiver = None;
+        self.job_sender = None;
+        self.analysis_receiver = None;
+        self.output_locks.clear();
         self.navigate_to_home();
     }
 }
 
-fn collect_video_files(dir: &PathBuf, paths: &mut Vec<PathBuf>) {
+/// Publisher of the configured status file, if any
+fn status_file(config: &AppConfig) -> Option<QueuePublisher> {
+    config
+        .output
+        .status_file
+        .as_ref()
+        .map(|path| QueuePublisher::status_file(PathBuf::from(path)))
+}
+
+/// Lock the directory `output` is written to, unless this queue already holds it
+///
+/// Only with `instance_lock = "output_directory"`; the global lock is taken at startup.
+fn lock_output_dir(
+    locks: &mut HashMap<PathBuf, InstanceLock>,
+    output: &Path,
+    config: &AppConfig,
+) -> Result<(), AppError> {
+    if config.output.instance_lock != LockScope::OutputDirectory {
+        return Ok(());
+    }
+    let dir = match output.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    if let Entry::Vacant(entry) = locks.entry(dir) {
+        let lock = InstanceLock::output_dir(entry.key())?;
+        entry.insert(lock);
+    }
+    Ok(())
+}
+
+/// Output of a background task, see `App::recv_background`
+pub enum Background {
+    /// Messages from the encoding worker, `None` once it stopped
+    Worker(Option<Vec<WorkerMessage>>),
+    /// Analyzed files, `None` once all are done
+    Analysis(Option<Vec<(usize, Result<SourceAnalysis, AppError>)>>),
+    /// Probed files for the verdicts on the review screen, `None` once all are done
+    Probes(Option<Vec<(usize, Option<AuditEntry>)>>),
+    /// Newer release than the running one, `None` once the check is done
+    Update(Option<Vec<Version>>),
+}
+
+/// Everything waiting in a channel, or `None` once it closed
+///
+/// Pends forever without a channel.
+async fn recv_batch<T>(rx: &mut Option<UnboundedReceiver<T>>) -> Option<Vec<T>> {
+    let Some(rx) = rx else {
+        return std::future::pending().await;
+    };
+    let first = rx.recv().await?;
+    let mut batch = vec![first];
+    while let Ok(item) = rx.try_recv() {
+        batch.push(item);
+    }
+    Some(batch)
+}
+
+/// Optional analyses to run besides ffprobe
+#[derive(Debug, Clone, Copy)]
+struct AnalysisSettings {
+    detect_motion: bool,
+    detect_dark: bool,
+    detect_grain: bool,
+    grain_passthrough: bool,
+}
+
+/// Everything learned about a source before its tracks are configured
+pub struct SourceAnalysis {
+    pub analysis: AnalysisResult,
+    pub motion: Option<MotionAnalysis>,
+    pub luma: Option<LumaAnalysis>,
+    pub noise: Option<NoiseAnalysis>,
+    pub grain: Option<FilmGrainParams>,
+    pub elapsed: Duration,
+}
+
+/// Probe one source, with the optional analyses that apply to it
+fn analyze_source(p: &str, settings: AnalysisSettings) -> Result<SourceAnalysis, AppError> {
+    let span = info_span!("job", file = %p);
+    let _guard = span.enter();
+    let (result, elapsed) = timed(Stage::Analyze, || {
+        let analysis = analyzer::analyze(p)?;
+        let av1 = is_av1_codec(&analysis.metadata.codec_name);
+        let motion = if settings.detect_motion && !av1 {
+            analyzer::analyze_motion(p, analysis.metadata.duration_secs)
+                .inspect_err(|e| warn!("Motion analysis failed for {}: {}", p, e))
+                .ok()
+        } else {
+            None
+        };
+        let luma = if settings.detect_dark && !av1 {
+            analyzer::analyze_luma(p, analysis.metadata.hdr_type)
+                .inspect_err(|e| warn!("Luma analysis failed for {}: {}", p, e))
+                .ok()
+        } else {
+            None
+        };
+        let noise = if settings.detect_grain && !av1 {
+            analyzer::analyze_noise(p)
+                .inspect_err(|e| warn!("Noise analysis failed for {}: {}", p, e))
+                .ok()
+        } else {
+            None
+        };
+        let grain = if settings.grain_passthrough && av1 {
+            analyzer::probe_film_grain(p)
+                .inspect_err(|e| warn!("Film grain probe failed for {}: {}", p, e))
+                .ok()
+                .flatten()
+        } else {
+            None
+        };
+        Ok::<_, AppError>((analysis, motion, luma, noise, grain))
+    });
+    result.map(|(analysis, motion, luma, noise, grain)| SourceAnalysis {
+        analysis,
+        motion,
+        luma,
+        noise,
+        grain,
+        elapsed,
+    })
+}
+
+/// Videos below `folder`, then the main title of each disc found there
+fn folder_jobs(folder: &PathBuf, detector: &mut VideoDetector) -> Vec<EncodingJob> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut discs: Vec<PathBuf> = Vec::new();
+    collect_video_files(folder, detector, &mut paths, &mut discs);
+    paths.sort();
+    discs.sort();
+    let mut jobs: Vec<EncodingJob> = paths.into_iter().map(EncodingJob::new).collect();
+    // The longest title of each disc, normally the main feature
+    for disc in discs {
+        match analyzer::list_titles(&disc) {
+            Ok(titles) => {
+                if let Some(title) = titles.into_iter().next() {
+                    jobs.push(EncodingJob::from_disc_title(title));
+                }
+            }
+            Err(e) => warn!("Skipping disc {}: {}", disc.display(), e),
+        }
+    }
+    jobs
+}
+
+fn collect_video_files(
+    dir: &PathBuf,
+    detector: &mut VideoDetector,
+    paths: &mut Vec<PathBuf>,
+    discs: &mut Vec<PathBuf>,
+) {
+    if detect_disc(dir).is_some() {
+        discs.push(dir.clone());
+        return;
+    }
     let Ok(entries) = std::fs::read_dir(dir) else {
         return;
     };
     for entry in entries.filter_map(|e| e.ok()) {
         let path = entry.path();
         if path.is_dir() {
-            collect_video_files(&path, paths);
-        } else if is_video_file(&path) {
+            collect_video_files(&path, detector, paths, discs);
+        } else if detector.is_video(&path) {
             paths.push(path);
         }
     }
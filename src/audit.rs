@@ -0,0 +1,316 @@
+//! Library audit: what a folder tree holds and how much converting it would save.
+
+use crate::analyzer::{self, VideoDetector, VideoMetadata, detect_disc, is_av1_codec};
+use crate::config::{InputConfig, TriageConfig, TriageThresholds};
+use crate::error::AppError;
+use crate::utils::data_dir;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Files probed at the same time
+const PROBE_PARALLELISM: usize = 8;
+
+/// Source codec groups, each with a typical AV1 size ratio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CodecFamily {
+    H264,
+    Hevc,
+    Vp9,
+    Av1,
+    /// MPEG-2, MPEG-4 Part 2, VC-1 and anything else
+    Other,
+}
+
+impl CodecFamily {
+    pub const ALL: [CodecFamily; 5] = [
+        CodecFamily::H264,
+        CodecFamily::Hevc,
+        CodecFamily::Vp9,
+        CodecFamily::Av1,
+        CodecFamily::Other,
+    ];
+
+    pub fn from_codec(codec_name: &str) -> Self {
+        if is_av1_codec(codec_name) {
+            return CodecFamily::Av1;
+        }
+        match codec_name.to_lowercase().as_str() {
+            "h264" | "avc" | "avc1" => CodecFamily::H264,
+            "hevc" | "h265" | "hvc1" | "hev1" => CodecFamily::Hevc,
+            "vp9" => CodecFamily::Vp9,
+            _ => CodecFamily::Other,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CodecFamily::H264 => "H.264",
+            CodecFamily::Hevc => "HEVC",
+            CodecFamily::Vp9 => "VP9",
+            CodecFamily::Av1 => "AV1",
+            CodecFamily::Other => "Other",
+        }
+    }
+
+    /// Whether the codec is already as efficient as AV1 or close to it
+    pub fn is_efficient(&self) -> bool {
+        matches!(self, CodecFamily::Vp9 | CodecFamily::Av1)
+    }
+
+    /// Typical AV1 output size relative to the source at transparent quality
+    pub fn size_ratio(&self) -> f64 {
+        match self {
+            CodecFamily::H264 => 0.5,
+            CodecFamily::Hevc => 0.8,
+            CodecFamily::Vp9 => 0.85,
+            CodecFamily::Av1 => 1.0,
+            CodecFamily::Other => 0.3,
+        }
+    }
+}
+
+/// One probed file of the audited tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub path: PathBuf,
+    pub codec: String,
+    pub family: CodecFamily,
+    pub width: u32,
+    pub height: u32,
+    pub duration_secs: f64,
+    pub size: u64,
+    pub estimated_size: u64,
+    /// Bits spent per pixel and frame, when bitrate and frame rate are known
+    pub bits_per_pixel: Option<f64>,
+}
+
+impl AuditEntry {
+    /// Bytes converting this file is expected to save
+    pub fn savings(&self) -> u64 {
+        self.size.saturating_sub(self.estimated_size)
+    }
+
+    /// Whether converting this file is worth it, by codec and bits per pixel
+    pub fn verdict(&self, triage: &TriageConfig) -> Verdict {
+        let thresholds = match self.family {
+            CodecFamily::Av1 => return Verdict::Skip,
+            CodecFamily::H264 => triage.h264,
+            CodecFamily::Hevc => triage.hevc,
+            CodecFamily::Vp9 => triage.vp9,
+            CodecFamily::Other => triage.other,
+        };
+        match self.bits_per_pixel {
+            Some(bpp) => Verdict::from_bpp(bpp, thresholds),
+            None => Verdict::Marginal,
+        }
+    }
+}
+
+/// Triage verdict for a source on the file review screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Enough bits to expect real savings
+    Convert,
+    /// Savings likely small, or the bitrate is unknown
+    Marginal,
+    /// Already AV1 or lean enough that converting costs quality for little gain
+    Skip,
+}
+
+impl Verdict {
+    fn from_bpp(bpp: f64, thresholds: TriageThresholds) -> Self {
+        if bpp >= thresholds.convert_bpp {
+            Verdict::Convert
+        } else if bpp >= thresholds.skip_bpp {
+            Verdict::Marginal
+        } else {
+            Verdict::Skip
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Verdict::Convert => "Convert",
+            Verdict::Marginal => "Marginal",
+            Verdict::Skip => "Skip",
+        }
+    }
+}
+
+/// Files, size and estimated size of one codec family
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FamilySummary {
+    pub family: CodecFamily,
+    pub count: usize,
+    pub size: u64,
+    pub estimated_size: u64,
+}
+
+/// Result of auditing a folder tree
+#[derive(Debug, Clone, Default)]
+pub struct AuditReport {
+    pub root: PathBuf,
+    /// Probed files, biggest potential savings first
+    pub entries: Vec<AuditEntry>,
+    /// Video files ffprobe could not read
+    pub unreadable: Vec<PathBuf>,
+}
+
+impl AuditReport {
+    /// Per-family totals, for the families present in the tree
+    pub fn families(&self) -> Vec<FamilySummary> {
+        CodecFamily::ALL
+            .iter()
+            .map(|&family| {
+                let entries = self.entries.iter().filter(|e| e.family == family);
+                FamilySummary {
+                    family,
+                    count: entries.clone().count(),
+                    size: entries.clone().map(|e| e.size).sum(),
+                    estimated_size: entries.map(|e| e.estimated_size).sum(),
+                }
+            })
+            .filter(|s| s.count > 0)
+            .collect()
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+
+    pub fn total_savings(&self) -> u64 {
+        self.entries.iter().map(|e| e.savings()).sum()
+    }
+
+    /// The report as CSV, one row per file in ranking order
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "path,codec,resolution,duration_secs,size_bytes,estimated_bytes,savings_bytes\n",
+        );
+        for e in &self.entries {
+            csv.push_str(&format!(
+                "{},{},{}x{},{:.1},{},{},{}\n",
+                csv_field(&e.path.to_string_lossy()),
+                csv_field(&e.codec),
+                e.width,
+                e.height,
+                e.duration_secs,
+                e.size,
+                e.estimated_size,
+                e.savings()
+            ));
+        }
+        csv
+    }
+
+    /// Write the CSV into the data directory and return its path
+    pub fn export_csv(&self) -> Result<PathBuf, AppError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let dir = data_dir().join("audits");
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("audit-{}.csv", timestamp));
+        std::fs::write(&path, self.to_csv())?;
+        Ok(path)
+    }
+}
+
+/// Probe every video file under `dir` without encoding anything
+///
+/// Disc folders, hidden folders and chunk folders of interrupted encodes are skipped.
+pub fn audit(dir: &Path, input: &InputConfig) -> AuditReport {
+    let mut detector = VideoDetector::new(input);
+    let mut paths = Vec::new();
+    collect_files(dir, &mut detector, &mut paths);
+    detector.save();
+    paths.sort();
+
+    let mut report = AuditReport {
+        root: dir.to_path_buf(),
+        ..Default::default()
+    };
+    for batch in paths.chunks(PROBE_PARALLELISM) {
+        let results: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = batch.iter().map(|p| s.spawn(|| probe(p))).collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or(None))
+                .collect()
+        });
+        for (path, entry) in batch.iter().zip(results) {
+            match entry {
+                Some(entry) => report.entries.push(entry),
+                None => report.unreadable.push(path.clone()),
+            }
+        }
+    }
+
+    report
+        .entries
+        .sort_by_key(|e| std::cmp::Reverse(e.savings()));
+    report
+}
+
+/// Probe one file, or `None` if ffprobe can't read it
+pub fn probe(path: &Path) -> Option<AuditEntry> {
+    let size = std::fs::metadata(path).ok()?.len();
+    let analysis = analyzer::analyze(&path.to_string_lossy())
+        .inspect_err(|e| warn!("Audit could not probe {}: {}", path.display(), e))
+        .ok()?;
+    let metadata = analysis.metadata;
+    let family = CodecFamily::from_codec(&metadata.codec_name);
+    Some(AuditEntry {
+        path: path.to_path_buf(),
+        family,
+        width: metadata.width,
+        height: metadata.height,
+        duration_secs: metadata.duration_secs,
+        size,
+        estimated_size: (size as f64 * family.size_ratio()) as u64,
+        bits_per_pixel: bits_per_pixel(&metadata, size),
+        codec: metadata.codec_name,
+    })
+}
+
+/// Bits per pixel and frame, from the probed bitrate or else the file size
+fn bits_per_pixel(metadata: &VideoMetadata, size: u64) -> Option<f64> {
+    let bitrate = match metadata.bitrate {
+        Some(bitrate) if bitrate > 0 => bitrate as f64,
+        _ if metadata.duration_secs > 0.0 => size as f64 * 8.0 / metadata.duration_secs,
+        _ => return None,
+    };
+    metadata.bits_per_pixel(bitrate)
+}
+
+fn collect_files(dir: &Path, detector: &mut VideoDetector, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            let chunks = path.extension().is_some_and(|e| e == "chunks");
+            if !chunks && detect_disc(&path).is_none() {
+                collect_files(&path, detector, paths);
+            }
+        } else if detector.is_video(&path) {
+            paths.push(path);
+        }
+    }
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,241 @@
+//! Library maintenance: find what earlier conversion runs left behind.
+
+use crate::analyzer::detect_disc;
+use crate::encoder::chunked::SETTINGS_FILE;
+use crate::queue::is_video_file;
+use crate::utils::claim::claim_active;
+use crate::utils::lock::{DIR_LOCK_FILE, lock_active};
+use std::path::{Path, PathBuf};
+
+/// Why a file was flagged by the cleanup scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeftoverKind {
+    /// Intermediate file of an interrupted encode
+    TempFile,
+    /// Finished chunks of an interrupted chunked encode, reused when it runs again
+    Chunks,
+    /// Output left empty by a failed encode
+    EmptyOutput,
+    /// Output whose source is gone, e.g. renamed, moved or deleted after conversion
+    OrphanOutput,
+    /// Source without an output; listed, never deleted
+    Unconverted,
+}
+
+impl LeftoverKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LeftoverKind::TempFile => "temp file",
+            LeftoverKind::Chunks => "chunks",
+            LeftoverKind::EmptyOutput => "empty output",
+            LeftoverKind::OrphanOutput => "no source",
+            LeftoverKind::Unconverted => "not converted",
+        }
+    }
+
+    /// Whether the cleanup may delete it
+    pub fn removable(&self) -> bool {
+        !matches!(self, LeftoverKind::Unconverted)
+    }
+
+    /// Preselected for deletion; orphaned outputs are often the only copy
+    /// left, and chunks save the encode they belong to from starting over
+    pub fn selected_by_default(&self) -> bool {
+        matches!(self, LeftoverKind::TempFile | LeftoverKind::EmptyOutput)
+    }
+}
+
+/// A file or folder found by the cleanup scan
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leftover {
+    pub path: PathBuf,
+    pub kind: LeftoverKind,
+    pub size: u64,
+    /// Marked for deletion in the confirmation list
+    pub selected: bool,
+}
+
+/// Outcome of deleting the selected leftovers
+#[derive(Debug, Default)]
+pub struct CleanupResult {
+    pub removed: usize,
+    pub freed: u64,
+    /// Paths that could not be deleted, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Scan a folder tree for leftovers of earlier runs
+///
+/// Outputs are recognised by `suffix` in their name; the source of
+/// `movie_av1.mkv` (or the per-profile `movie_av1_phone-720p.mp4`) is any
+/// video named `movie` in the same folder. Temporaries count only under the
+/// exact names the encode gives them next to such an output, and none are
+/// listed in a folder locked by a running instance or for a source another
+/// instance claimed, since an encode may still be writing them.
+pub fn scan(dir: &Path, suffix: &str) -> Vec<Leftover> {
+    let mut found = Vec::new();
+    if !suffix.is_empty() {
+        scan_dir(dir, suffix, &mut found);
+    }
+    found
+}
+
+fn scan_dir(dir: &Path, suffix: &str, found: &mut Vec<Leftover>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<(PathBuf, std::fs::Metadata)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| Some((e.path(), e.metadata().ok()?)))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut discs = Vec::new();
+    let mut sources = Vec::new();
+    let mut outputs = Vec::new();
+    let mut temps = Vec::new();
+    let mut empty = Vec::new();
+    let source_of = |output: &str| output.rfind(suffix).map(|end| output[..end].to_string());
+    for (path, metadata) in entries {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if name.starts_with('.') {
+            continue;
+        }
+
+        if metadata.is_dir() {
+            if let Some(source) = chunks_output(&path, &name).and_then(|o| source_of(&o)) {
+                let size = dir_size(&path);
+                temps.push((path, LeftoverKind::Chunks, source, size));
+            } else if detect_disc(&path).is_some() {
+                discs.push(name);
+            } else {
+                scan_dir(&path, suffix, found);
+            }
+        } else if let Some(source) = temp_output(&name).and_then(source_of) {
+            temps.push((path, LeftoverKind::TempFile, source, metadata.len()));
+        } else if is_video_file(&path) {
+            let stem = stem(&path);
+            match stem.rfind(suffix) {
+                Some(end) if metadata.len() == 0 => empty.push((path, stem[..end].to_string())),
+                Some(end) => outputs.push((path, stem[..end].to_string(), metadata.len())),
+                None => sources.push((path, stem, metadata.len())),
+            }
+        }
+    }
+
+    let locked = lock_active(&dir.join(DIR_LOCK_FILE));
+    let in_use = |source_stem: &str| {
+        locked
+            || sources
+                .iter()
+                .any(|(path, stem, _)| stem == source_stem && claim_active(path))
+    };
+    // An encode may still be writing into a locked folder or for a claimed source
+    for (path, kind, source_stem, size) in temps {
+        if !in_use(&source_stem) {
+            found.push(leftover(path, kind, size));
+        }
+    }
+    for (path, source_stem) in empty {
+        if !in_use(&source_stem) {
+            found.push(leftover(path, LeftoverKind::EmptyOutput, 0));
+        }
+    }
+
+    for (path, source_stem, size) in &outputs {
+        let has_source = sources.iter().any(|(_, stem, _)| stem == source_stem)
+            || discs
+                .iter()
+                .any(|disc| source_stem.starts_with(&format!("{}_", disc)));
+        if !has_source {
+            found.push(leftover(path.clone(), LeftoverKind::OrphanOutput, *size));
+        }
+    }
+    for (path, stem, size) in sources {
+        if !outputs
+            .iter()
+            .any(|(_, source_stem, _)| *source_stem == stem)
+        {
+            found.push(leftover(path, LeftoverKind::Unconverted, size));
+        }
+    }
+}
+
+/// Delete the selected, removable leftovers
+pub fn remove(leftovers: &[Leftover]) -> CleanupResult {
+    let mut result = CleanupResult::default();
+    for item in leftovers
+        .iter()
+        .filter(|l| l.selected && l.kind.removable())
+    {
+        let removed = if item.path.is_dir() {
+            std::fs::remove_dir_all(&item.path)
+        } else {
+            std::fs::remove_file(&item.path)
+        };
+        match removed {
+            Ok(()) => {
+                result.removed += 1;
+                result.freed += item.size;
+            }
+            Err(e) => result.failed.push((item.path.clone(), e.to_string())),
+        }
+    }
+    result
+}
+
+fn leftover(path: PathBuf, kind: LeftoverKind, size: u64) -> Leftover {
+    Leftover {
+        path,
+        kind,
+        size,
+        selected: kind.selected_by_default(),
+    }
+}
+
+/// Output stem of a remux or transfer temporary (`movie_av1.remux.mkv`),
+/// mkvmerge's intermediate video stream (`movie_av1.video.ivf`) or a grain
+/// table (`movie_av1.grain.tbl`)
+fn temp_output(name: &str) -> Option<&str> {
+    if let Some(stem) = name
+        .strip_suffix(".video.ivf")
+        .or_else(|| name.strip_suffix(".grain.tbl"))
+    {
+        return Some(stem);
+    }
+    let (rest, _) = name.rsplit_once('.')?;
+    let (stem, tag) = rest.rsplit_once('.')?;
+    (matches!(tag, "remux" | "transfer") && is_video_file(Path::new(name))).then_some(stem)
+}
+
+/// Output stem of a chunk folder (`movie_av1.mkv.chunks`), recognised by
+/// the settings file the chunked encode writes into it
+fn chunks_output(path: &Path, name: &str) -> Option<String> {
+    let output = Path::new(name.strip_suffix(".chunks")?);
+    (is_video_file(output) && path.join(SETTINGS_FILE).is_file()).then(|| stem(output))
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            Some(if metadata.is_dir() {
+                dir_size(&e.path())
+            } else {
+                metadata.len()
+            })
+        })
+        .sum()
+}
@@ -0,0 +1,392 @@
+//! Command line: the options, how they are read, and shell completions.
+//!
+//! [`OPTIONS`] and [`SUBCOMMANDS`] describe the command line once; [`parse`]
+//! reads the arguments by them and [`completions`] turns them into bash, zsh
+//! and fish scripts, so a new option only has to be added here.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Name of the binary the completions are for
+const BINARY: &str = "av1converter";
+
+/// What an option is followed by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    None,
+    /// A directory, also given as `--option=<dir>`
+    Dir,
+    /// A directory, unless the next argument is another option
+    OptionalDir,
+    /// A `host:port` address, unless the next argument is another option
+    OptionalAddress,
+}
+
+/// A command-line option
+#[derive(Debug, Clone, Copy)]
+pub struct CliOption {
+    pub name: &'static str,
+    pub value: Value,
+    pub help: &'static str,
+}
+
+/// Every option of the binary
+pub const OPTIONS: &[CliOption] = &[
+    CliOption {
+        name: "--config-dir",
+        value: Value::Dir,
+        help: "Keep configuration, history and logs in this directory",
+    },
+    CliOption {
+        name: "--ignore-lock",
+        value: Value::None,
+        help: "Run even when another instance holds the lock",
+    },
+    CliOption {
+        name: "--stdin",
+        value: Value::None,
+        help: "Also read files and folders from standard input, one per line",
+    },
+    CliOption {
+        name: "--json",
+        value: Value::None,
+        help: "Convert without the TUI, printing JSON events",
+    },
+    CliOption {
+        name: "--print-commands",
+        value: Value::None,
+        help: "Print the FFmpeg command of each file without encoding",
+    },
+    CliOption {
+        name: "--serve",
+        value: Value::OptionalAddress,
+        help: "Take jobs over HTTP without the TUI",
+    },
+    CliOption {
+        name: "--attach",
+        value: Value::None,
+        help: "Watch the queue of the running instance without controlling it",
+    },
+    CliOption {
+        name: "--cleanup",
+        value: Value::OptionalDir,
+        help: "List leftovers of earlier runs and delete the marked ones",
+    },
+    CliOption {
+        name: "--dump-commands",
+        value: Value::None,
+        help: "Print the FFmpeg command of every encoder and track layout",
+    },
+];
+
+/// Subcommands, given as the first argument
+pub const SUBCOMMANDS: &[(&str, &str)] = &[(
+    "completions",
+    "Print the completion script of bash, zsh or fish",
+)];
+
+/// Shell a completion script is written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub const ALL: [Shell; 3] = [Shell::Bash, Shell::Zsh, Shell::Fish];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Shell::ALL
+            .into_iter()
+            .find(|shell| shell.name() == s)
+            .ok_or_else(|| format!("unknown shell {}, expected bash, zsh or fish", s))
+    }
+}
+
+/// What the binary was started to do
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// The terminal UI, on the given paths if any
+    #[default]
+    Tui,
+    Json,
+    PrintCommands,
+    /// Serve on this address, or the configured one
+    Serve(Option<String>),
+    /// Read-only view of the queue another instance runs
+    Attach,
+    Cleanup(PathBuf),
+    DumpCommands,
+    Completions(Shell),
+}
+
+/// Parsed command line
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Cli {
+    pub mode: Mode,
+    pub config_dir: Option<PathBuf>,
+    pub ignore_lock: bool,
+    /// Read more paths from standard input
+    pub stdin: bool,
+    /// Files and folders to convert
+    pub paths: Vec<PathBuf>,
+}
+
+/// Read the arguments after the binary name
+///
+/// Errors are messages for the user, e.g. `unknown option --foo`.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+    let mut args = args.into_iter().peekable();
+    let mut cli = Cli::default();
+
+    if args.peek().map(String::as_str) == Some("completions") {
+        args.next();
+        let shell = args
+            .next()
+            .ok_or("completions needs a shell: bash, zsh or fish")?
+            .parse()?;
+        if let Some(extra) = args.next() {
+            return Err(format!("unexpected argument {}", extra));
+        }
+        cli.mode = Mode::Completions(shell);
+        return Ok(cli);
+    }
+
+    let mut mode = None;
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            cli.paths.push(PathBuf::from(arg));
+            continue;
+        }
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let option = OPTIONS
+            .iter()
+            .find(|o| o.name == name)
+            .ok_or_else(|| format!("unknown option {}", arg))?;
+        let value = match option.value {
+            Value::None if inline.is_some() => {
+                return Err(format!("{} takes no value", option.name));
+            }
+            Value::None => None,
+            Value::Dir => Some(
+                inline
+                    .or_else(|| args.next())
+                    .filter(|dir| !dir.is_empty())
+                    .ok_or_else(|| format!("{} needs a directory", option.name))?,
+            ),
+            Value::OptionalDir | Value::OptionalAddress => {
+                inline.or_else(|| args.next_if(|next| !next.starts_with("--")))
+            }
+        };
+        let chosen = match option.name {
+            "--config-dir" => {
+                cli.config_dir = value.map(PathBuf::from);
+                continue;
+            }
+            "--ignore-lock" => {
+                cli.ignore_lock = true;
+                continue;
+            }
+            "--stdin" => {
+                cli.stdin = true;
+                continue;
+            }
+            "--json" => Mode::Json,
+            "--print-commands" => Mode::PrintCommands,
+            "--serve" => Mode::Serve(value),
+            "--attach" => Mode::Attach,
+            "--cleanup" => Mode::Cleanup(PathBuf::from(value.as_deref().unwrap_or("."))),
+            "--dump-commands" => Mode::DumpCommands,
+            _ => unreachable!("every option is handled"),
+        };
+        if let Some((other, _)) = &mode {
+            return Err(format!("{} can't be combined with {}", option.name, other));
+        }
+        mode = Some((option.name, chosen));
+    }
+
+    if let Some((name, mode)) = mode {
+        let takes_paths = matches!(mode, Mode::Json | Mode::PrintCommands);
+        if !takes_paths && (!cli.paths.is_empty() || cli.stdin) {
+            return Err(format!("{} takes no files or folders", name));
+        }
+        cli.mode = mode;
+    }
+    Ok(cli)
+}
+
+/// Completion script of `shell` for every option and subcommand
+pub fn completions(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => bash(),
+        Shell::Zsh => zsh(),
+        Shell::Fish => fish(),
+    }
+}
+
+fn names_with(value: impl Fn(Value) -> bool) -> Vec<&'static str> {
+    OPTIONS
+        .iter()
+        .filter(|o| value(o.value))
+        .map(|o| o.name)
+        .collect()
+}
+
+fn shells() -> String {
+    Shell::ALL.map(|s| s.name()).join(" ")
+}
+
+fn bash() -> String {
+    let all = names_with(|_| true).join(" ");
+    let dirs = names_with(|v| matches!(v, Value::Dir | Value::OptionalDir)).join("|");
+    let subcommands: Vec<&str> = SUBCOMMANDS.iter().map(|(name, _)| *name).collect();
+    let function = format!("_{}", BINARY);
+    let mut script = String::new();
+    let _ = writeln!(script, "# bash completion for {}", BINARY);
+    let _ = writeln!(script, "{}() {{", function);
+    let _ = writeln!(script, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+    let _ = writeln!(script, "    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"");
+    let _ = writeln!(script, "    case \"$prev\" in");
+    let _ = writeln!(
+        script,
+        "        {}) COMPREPLY=($(compgen -d -- \"$cur\")); return ;;",
+        dirs
+    );
+    let _ = writeln!(
+        script,
+        "        completions) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;",
+        shells()
+    );
+    let _ = writeln!(script, "    esac");
+    let _ = writeln!(script, "    if [[ \"$cur\" == -* ]]; then");
+    let _ = writeln!(
+        script,
+        "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+        all
+    );
+    let _ = writeln!(script, "    elif [[ $COMP_CWORD -eq 1 ]]; then");
+    let _ = writeln!(
+        script,
+        "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\") $(compgen -f -- \"$cur\"))",
+        subcommands.join(" ")
+    );
+    let _ = writeln!(script, "    else");
+    let _ = writeln!(script, "        COMPREPLY=($(compgen -f -- \"$cur\"))");
+    let _ = writeln!(script, "    fi");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script, "complete -o filenames -F {} {}", function, BINARY);
+    script
+}
+
+fn zsh() -> String {
+    let function = format!("_{}", BINARY);
+    let mut script = String::new();
+    let _ = writeln!(script, "#compdef {}", BINARY);
+    let _ = writeln!(script);
+    let _ = writeln!(script, "{}() {{", function);
+    let _ = writeln!(
+        script,
+        "    if (( CURRENT == 3 )) && [[ ${{words[2]}} == completions ]]; then"
+    );
+    let _ = writeln!(script, "        _values 'shell' {}", shells());
+    let _ = writeln!(script, "        return");
+    let _ = writeln!(script, "    fi");
+    let _ = writeln!(
+        script,
+        "    if (( CURRENT == 2 )) && [[ $PREFIX != -* ]]; then"
+    );
+    let subcommands: Vec<String> = SUBCOMMANDS
+        .iter()
+        .map(|(name, help)| format!("{}\\:\"{}\"", name, help.replace('\'', "'\\''")))
+        .collect();
+    let _ = writeln!(
+        script,
+        "        _alternative 'subcommands:subcommand:(({}))' 'files:file or folder:_files'",
+        subcommands.join(" ")
+    );
+    let _ = writeln!(script, "        return");
+    let _ = writeln!(script, "    fi");
+    let _ = writeln!(script, "    _arguments \\");
+    for option in OPTIONS {
+        let help = zsh_escape(option.help);
+        let spec = match option.value {
+            Value::None => format!("'{}[{}]'", option.name, help),
+            Value::Dir => format!("'{}=[{}]:directory:_files -/'", option.name, help),
+            Value::OptionalDir => format!("'{}[{}]::directory:_files -/'", option.name, help),
+            Value::OptionalAddress => format!("'{}[{}]::address:'", option.name, help),
+        };
+        let _ = writeln!(script, "        {} \\", spec);
+    }
+    let _ = writeln!(script, "        '*:file or folder:_files'");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script);
+    let _ = writeln!(script, "{} \"$@\"", function);
+    script
+}
+
+fn fish() -> String {
+    let mut script = String::new();
+    let _ = writeln!(script, "# fish completion for {}", BINARY);
+    for option in OPTIONS {
+        let long = option.name.trim_start_matches("--");
+        let value = match option.value {
+            Value::None | Value::OptionalAddress => "",
+            Value::Dir => " -x -a '(__fish_complete_directories)'",
+            Value::OptionalDir => " -a '(__fish_complete_directories)'",
+        };
+        let _ = writeln!(
+            script,
+            "complete -c {} -l {}{} -d '{}'",
+            BINARY,
+            long,
+            value,
+            fish_escape(option.help)
+        );
+    }
+    for (name, help) in SUBCOMMANDS {
+        let _ = writeln!(
+            script,
+            "complete -c {} -n '__fish_use_subcommand' -a {} -d '{}'",
+            BINARY,
+            name,
+            fish_escape(help)
+        );
+    }
+    let _ = writeln!(
+        script,
+        "complete -c {} -n '__fish_seen_subcommand_from completions' -x -a '{}'",
+        BINARY,
+        shells()
+    );
+    script
+}
+
+/// Help text inside a single-quoted `_arguments` spec
+fn zsh_escape(help: &str) -> String {
+    help.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+/// Help text inside single quotes for fish
+fn fish_escape(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\'', "\\'")
+}
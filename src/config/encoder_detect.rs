@@ -1,10 +1,11 @@
 #[cfg(not(target_os = "macos"))]
 use std::process::Command;
 
+use super::types::Cycle;
 use serde::{Deserialize, Serialize};
 
 /// AV1 encoders
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Encoder {
     /// NVIDIA NVENC
     Nvenc,
@@ -16,6 +17,11 @@ pub enum Encoder {
     SvtAv1,
 }
 
+impl Cycle for Encoder {
+    /// In the order the configuration screen cycles through them
+    const ALL: &'static [Self] = &[Encoder::SvtAv1, Encoder::Nvenc, Encoder::Qsv, Encoder::Amf];
+}
+
 impl Encoder {
     /// FFmpeg encoder name
     pub fn ffmpeg_name(&self) -> &'static str {
@@ -36,6 +42,23 @@ impl Encoder {
             Encoder::SvtAv1 => "SVT-AV1 (Software)",
         }
     }
+
+    /// Name of the encoder's quality parameter for messages
+    pub fn quality_label(&self) -> &'static str {
+        match self {
+            Encoder::SvtAv1 => "CRF",
+            Encoder::Nvenc => "CQ",
+            Encoder::Qsv | Encoder::Amf => "quality",
+        }
+    }
+
+    /// Highest (lowest quality) value accepted by the encoder's quality parameter
+    pub fn max_quality(&self) -> u8 {
+        match self {
+            Encoder::SvtAv1 => 63,
+            Encoder::Nvenc | Encoder::Qsv | Encoder::Amf => 51,
+        }
+    }
 }
 
 impl Default for Encoder {
@@ -0,0 +1,86 @@
+use crate::error::AppError;
+use toml::{Table, Value};
+use tracing::{info, warn};
+
+/// Current version of the config file layout
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Upgrades a config table from version `index` to `index + 1`
+type Migration = fn(&mut Table);
+
+/// Migrations in order; `MIGRATIONS.len()` must equal `CONFIG_VERSION`
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [v0_lowercase_sections];
+
+/// Version a config table was written with; files without the field predate versioning
+pub fn config_version(table: &Table) -> u32 {
+    table
+        .get("config_version")
+        .and_then(Value::as_integer)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(0)
+}
+
+/// Upgrade a parsed config to the current version
+///
+/// Returns the version the file was written with. Files from a newer release are left
+/// untouched so unknown settings are not lost.
+pub fn migrate(table: &mut Table) -> u32 {
+    let version = config_version(table);
+    if version > CONFIG_VERSION {
+        warn!(
+            "Config version {} is newer than supported version {}",
+            version, CONFIG_VERSION
+        );
+        return version;
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        info!("Migrating config from version {} to {}", from, from + 1);
+        migration(table);
+    }
+    table.insert(
+        "config_version".to_string(),
+        Value::Integer(CONFIG_VERSION as i64),
+    );
+    version
+}
+
+/// Fill settings missing from `table` with the values from `defaults`
+///
+/// Nested tables are merged key by key; arrays and scalars set by the user are kept as-is.
+pub fn fill_defaults(table: &mut Table, defaults: &Table) {
+    for (key, default) in defaults {
+        match (table.get_mut(key), default) {
+            (Some(Value::Table(user)), Value::Table(default)) => fill_defaults(user, default),
+            (Some(_), _) => {}
+            (None, _) => {
+                table.insert(key.clone(), default.clone());
+            }
+        }
+    }
+}
+
+/// Parse a config file, migrating and completing it against the defaults
+pub fn parse(content: &str, defaults: &Table) -> Result<(Table, u32), AppError> {
+    let mut table: Table = content.parse()?;
+    let version = migrate(&mut table);
+    fill_defaults(&mut table, defaults);
+    Ok((table, version))
+}
+
+/// v0 → v1: early releases documented capitalized section names (`[Quality]`, …)
+fn v0_lowercase_sections(table: &mut Table) {
+    for section in ["Quality", "Performance", "Presets", "Output", "Tracks"] {
+        let Some(Value::Table(legacy)) = table.remove(section) else {
+            continue;
+        };
+        let key = section.to_lowercase();
+        match table.get_mut(&key) {
+            // Settings under the lowercase name win over the legacy ones
+            Some(Value::Table(current)) => fill_defaults(current, &legacy),
+            _ => {
+                table.insert(key, Value::Table(legacy));
+            }
+        }
+    }
+}
@@ -1,17 +1,26 @@
 pub mod encoder_detect;
+pub mod migrate;
+pub mod settings;
 pub mod types;
 
 pub use encoder_detect::Encoder;
+pub use migrate::CONFIG_VERSION;
+pub use settings::{ConfigDifference, ConfigRow, Setting, SettingsSection};
 pub use types::*;
 
 use crate::error::AppError;
+use crate::utils::paths;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 use tracing::{info, warn};
 
 /// Main application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
+    /// Layout version of the config file, upgraded on load
+    #[serde(default)]
+    pub config_version: u32,
     /// Selected encoder
     pub encoder: Encoder,
     /// Quality settings
@@ -24,18 +33,59 @@ pub struct AppConfig {
     pub output: OutputConfig,
     /// Track selection presets
     pub tracks: TrackPresetConfig,
+    /// Files treated as videos
+    #[serde(default)]
+    pub input: InputConfig,
+    /// Terminal UI settings
+    #[serde(default)]
+    pub ui: UiConfig,
+    /// Batch completion notifications
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    /// Anonymous encode statistics, off unless opted in
+    #[serde(default)]
+    pub stats: StatsConfig,
+    /// Control server of `--serve`
+    #[serde(default)]
+    pub server: ServerConfig,
+    /// "Worth converting" verdicts on the file review screen
+    #[serde(default)]
+    pub triage: TriageConfig,
+    /// Advanced FFmpeg settings
+    #[serde(default)]
+    pub ffmpeg: FfmpegConfig,
+    /// External tool locations
+    #[serde(default)]
+    pub tools: ToolsConfig,
+    /// Device output profiles
+    #[serde(default = "default_profiles")]
+    pub profiles: Vec<DeviceProfile>,
+    /// External programs run before and after the encode and verify stages
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
 }
 
 #[allow(clippy::derivable_impls)]
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            config_version: CONFIG_VERSION,
             encoder: Encoder::default(),
             quality: QualityConfig::default(),
             performance: PerformanceConfig::default(),
             presets: EncodingPresetsConfig::default(),
             output: OutputConfig::default(),
             tracks: TrackPresetConfig::default(),
+            input: InputConfig::default(),
+            ui: UiConfig::default(),
+            notifications: NotificationConfig::default(),
+            stats: StatsConfig::default(),
+            server: ServerConfig::default(),
+            triage: TriageConfig::default(),
+            ffmpeg: FfmpegConfig::default(),
+            tools: ToolsConfig::default(),
+            profiles: default_profiles(),
+            hooks: Vec::new(),
         }
     }
 }
@@ -52,7 +102,9 @@ impl AppConfig {
                     return config;
                 }
                 Err(e) => {
+                    // Leave the file alone so the user can fix it without losing settings
                     warn!("Failed to load config: {:?}. Using defaults.", e);
+                    return Self::default();
                 }
             }
         }
@@ -67,8 +119,10 @@ impl AppConfig {
 
     /// Save configuration to TOML file
     pub fn save(&self) -> Result<(), AppError> {
-        let config_path = Self::config_path();
+        self.save_to(&Self::config_path())
+    }
 
+    fn save_to(&self, config_path: &Path) -> Result<(), AppError> {
         if let Some(parent) = config_path.parent() {
             std::fs::create_dir_all(parent).map_err(|e| {
                 AppError::Config(format!("Failed to create config directory: {}", e))
@@ -76,30 +130,44 @@ impl AppConfig {
         }
 
         let toml_string = toml::to_string_pretty(self)?;
-        std::fs::write(&config_path, toml_string)
+        std::fs::write(config_path, toml_string)
             .map_err(|e| AppError::Config(format!("Failed to write config file: {}", e)))?;
 
         info!("Saved config to {}", config_path.display());
         Ok(())
     }
 
-    /// Load configuration from a specific file
-    fn load_from_file(path: &PathBuf) -> Result<Self, AppError> {
+    /// Load configuration from a specific file, upgrading older layouts in place
+    fn load_from_file(path: &Path) -> Result<Self, AppError> {
         let content = std::fs::read_to_string(path)
             .map_err(|e| AppError::Config(format!("Failed to read config file: {}", e)))?;
-        let config: AppConfig = toml::from_str(&content)?;
+
+        let defaults = match toml::Value::try_from(Self::default())? {
+            toml::Value::Table(table) => table,
+            _ => unreachable!("AppConfig serializes to a table"),
+        };
+        let (table, version) = migrate::parse(&content, &defaults)?;
+        let config: AppConfig = toml::Value::Table(table).try_into()?;
+
+        if version < CONFIG_VERSION {
+            // Keep the original next to the upgraded file
+            let backup = path.with_extension(format!("toml.v{}.bak", version));
+            std::fs::copy(path, &backup)
+                .map_err(|e| AppError::Config(format!("Failed to back up config file: {}", e)))?;
+            config.save_to(path)?;
+            info!(
+                "Upgraded config from version {} to {} (backup at {})",
+                version,
+                CONFIG_VERSION,
+                backup.display()
+            );
+        }
         Ok(config)
     }
 
-    /// Get the default configuration file path
+    /// Get the configuration file path, inside the portable directory when one is set
     pub fn config_path() -> PathBuf {
-        std::env::var_os("XDG_CONFIG_HOME")
-            .map(PathBuf::from)
-            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
-            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("av1converter")
-            .join("config.toml")
+        paths::config_dir().join("config.toml")
     }
 
     /// Validate configuration values
@@ -115,6 +183,56 @@ impl AppConfig {
                 "SVT-AV1 preset must be between 0 and 13".to_string(),
             ));
         }
+        if self.performance.parallel_jobs == 0 {
+            return Err(AppError::Config(
+                "parallel_jobs must be at least 1".to_string(),
+            ));
+        }
+        if let Some(window) = &self.performance.encode_window {
+            window.parse::<crate::queue::EncodeWindow>()?;
+        }
+        let grain = &self.quality.adaptive_grain;
+        if grain.max_film_grain > 50 {
+            return Err(AppError::Config(
+                "adaptive_grain.max_film_grain must be between 0 and 50".to_string(),
+            ));
+        }
+        if grain.grainy_noise_db >= grain.clean_noise_db || grain.low_bpp >= grain.high_bpp {
+            return Err(AppError::Config(
+                "adaptive_grain: grainy_noise_db must be below clean_noise_db and low_bpp below \
+                 high_bpp"
+                    .to_string(),
+            ));
+        }
+        let triage = &self.triage;
+        for (codec, thresholds) in [
+            ("h264", triage.h264),
+            ("hevc", triage.hevc),
+            ("vp9", triage.vp9),
+            ("other", triage.other),
+        ] {
+            if thresholds.skip_bpp < 0.0 || thresholds.skip_bpp > thresholds.convert_bpp {
+                return Err(AppError::Config(format!(
+                    "triage.{}: skip_bpp must be between 0 and convert_bpp",
+                    codec
+                )));
+            }
+        }
+        for name in EncodingPresetsConfig::NAMES {
+            let preset = self.presets.get(name).expect("preset names are known");
+            for field in PresetField::ALL {
+                let range = field.range();
+                if !range.contains(&preset.field(field)) {
+                    return Err(AppError::Config(format!(
+                        "{} {} must be between {} and {}",
+                        EncodingPresetsConfig::display_name(name),
+                        field.label(),
+                        range.start(),
+                        range.end()
+                    )));
+                }
+            }
+        }
         Ok(())
     }
 
@@ -124,20 +242,75 @@ impl AppConfig {
         tier: &crate::analyzer::ResolutionTier,
         hdr_type: crate::analyzer::HdrType,
     ) -> &EncodingPreset {
+        let name = Self::preset_name(tier, hdr_type);
+        self.presets
+            .get(name)
+            .expect("preset_name returns a known preset")
+    }
+
+    /// Name of the preset used for a resolution tier and HDR type
+    pub fn preset_name(
+        tier: &crate::analyzer::ResolutionTier,
+        hdr_type: crate::analyzer::HdrType,
+    ) -> &'static str {
         use crate::analyzer::{HdrType, ResolutionTier};
         match tier {
-            ResolutionTier::SD => &self.presets.sd,
-            ResolutionTier::HD => &self.presets.hd,
+            ResolutionTier::SD => "sd",
+            ResolutionTier::HD => "hd",
             ResolutionTier::FullHD => match hdr_type {
-                HdrType::DolbyVision => &self.presets.full_hd_dv,
-                HdrType::Sdr => &self.presets.full_hd,
-                _ => &self.presets.full_hd_hdr,
+                HdrType::DolbyVision => "full_hd_dv",
+                HdrType::Sdr => "full_hd",
+                _ => "full_hd_hdr",
             },
             ResolutionTier::Uhd | ResolutionTier::Above4K => match hdr_type {
-                HdrType::DolbyVision => &self.presets.uhd_dv,
-                HdrType::Sdr => &self.presets.uhd,
-                _ => &self.presets.uhd_hdr,
+                HdrType::DolbyVision => "uhd_dv",
+                HdrType::Sdr => "uhd",
+                _ => "uhd_hdr",
             },
         }
     }
 }
+
+/// Configuration shared with a running queue
+///
+/// The worker reads it when each file starts encoding and again before it is
+/// verified, so quality settings changed mid-queue apply to every file not yet
+/// verified.
+#[derive(Debug, Clone, Default)]
+pub struct SharedConfig(Arc<RwLock<AppConfig>>);
+
+impl SharedConfig {
+    pub fn new(config: AppConfig) -> Self {
+        Self(Arc::new(RwLock::new(config)))
+    }
+
+    /// Copy of the current configuration
+    pub fn snapshot(&self) -> AppConfig {
+        match self.0.read() {
+            Ok(config) => config.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Current quality settings
+    pub fn quality(&self) -> QualityConfig {
+        match self.0.read() {
+            Ok(config) => config.quality.clone(),
+            Err(poisoned) => poisoned.into_inner().quality.clone(),
+        }
+    }
+
+    /// Replace the configuration seen by the queue
+    pub fn set(&self, config: AppConfig) {
+        match self.0.write() {
+            Ok(mut current) => *current = config,
+            Err(poisoned) => *poisoned.into_inner() = config,
+        }
+    }
+}
+
+impl From<AppConfig> for SharedConfig {
+    fn from(config: AppConfig) -> Self {
+        Self::new(config)
+    }
+}
@@ -0,0 +1,366 @@
+//! Settings shown on the Configuration screen, grouped by section.
+
+use super::{AppConfig, EncodingPresetsConfig};
+use crate::error::AppError;
+use crate::utils::DependencyStatus;
+use crate::utils::deps::Feature;
+use std::sync::LazyLock;
+
+/// Group of settings with a collapsible header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsSection {
+    Quality,
+    Performance,
+    Output,
+    Tracks,
+    Presets,
+}
+
+impl SettingsSection {
+    pub const ALL: [SettingsSection; 5] = [
+        SettingsSection::Quality,
+        SettingsSection::Performance,
+        SettingsSection::Output,
+        SettingsSection::Tracks,
+        SettingsSection::Presets,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingsSection::Quality => "Quality",
+            SettingsSection::Performance => "Performance",
+            SettingsSection::Output => "Output",
+            SettingsSection::Tracks => "Tracks",
+            SettingsSection::Presets => "Presets",
+        }
+    }
+}
+
+/// A setting on the Configuration screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    VmafEnabled,
+    VmafThreshold,
+    Encoder,
+    SvtPreset,
+    NvencPreset,
+    AmfPreset,
+    QueueOrder,
+    IoPriority,
+    OutputSuffix,
+    OutputContainer,
+    SameDirectory,
+    OutputNaming,
+    Muxer,
+    PreferredAudioLanguages,
+    PreferredSubtitleLanguages,
+    ExcludeCommentary,
+    SubtitleRule,
+    /// Encoding preset of a tier, by its name in `[presets.<name>]`
+    Preset(&'static str),
+}
+
+impl Setting {
+    pub const ALL: [Setting; 25] = [
+        Setting::VmafEnabled,
+        Setting::VmafThreshold,
+        Setting::Encoder,
+        Setting::SvtPreset,
+        Setting::NvencPreset,
+        Setting::AmfPreset,
+        Setting::QueueOrder,
+        Setting::IoPriority,
+        Setting::OutputSuffix,
+        Setting::OutputContainer,
+        Setting::SameDirectory,
+        Setting::OutputNaming,
+        Setting::Muxer,
+        Setting::PreferredAudioLanguages,
+        Setting::PreferredSubtitleLanguages,
+        Setting::ExcludeCommentary,
+        Setting::SubtitleRule,
+        Setting::Preset(EncodingPresetsConfig::NAMES[0]),
+        Setting::Preset(EncodingPresetsConfig::NAMES[1]),
+        Setting::Preset(EncodingPresetsConfig::NAMES[2]),
+        Setting::Preset(EncodingPresetsConfig::NAMES[3]),
+        Setting::Preset(EncodingPresetsConfig::NAMES[4]),
+        Setting::Preset(EncodingPresetsConfig::NAMES[5]),
+        Setting::Preset(EncodingPresetsConfig::NAMES[6]),
+        Setting::Preset(EncodingPresetsConfig::NAMES[7]),
+    ];
+
+    pub fn section(&self) -> SettingsSection {
+        match self {
+            Setting::VmafEnabled | Setting::VmafThreshold => SettingsSection::Quality,
+            Setting::Encoder
+            | Setting::SvtPreset
+            | Setting::NvencPreset
+            | Setting::AmfPreset
+            | Setting::QueueOrder
+            | Setting::IoPriority => SettingsSection::Performance,
+            Setting::OutputSuffix
+            | Setting::OutputContainer
+            | Setting::SameDirectory
+            | Setting::OutputNaming
+            | Setting::Muxer => SettingsSection::Output,
+            Setting::PreferredAudioLanguages
+            | Setting::PreferredSubtitleLanguages
+            | Setting::ExcludeCommentary
+            | Setting::SubtitleRule => SettingsSection::Tracks,
+            Setting::Preset(_) => SettingsSection::Presets,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Setting::VmafEnabled => "VMAF Enabled",
+            Setting::VmafThreshold => "VMAF Threshold",
+            Setting::Encoder => "Encoder",
+            Setting::SvtPreset => "SVT-AV1 Preset",
+            Setting::NvencPreset => "NVENC Preset",
+            Setting::AmfPreset => "AMF Preset",
+            Setting::QueueOrder => "Queue Order",
+            Setting::IoPriority => "Disk Priority",
+            Setting::OutputSuffix => "Output Suffix",
+            Setting::OutputContainer => "Output Container",
+            Setting::SameDirectory => "Same Directory Output",
+            Setting::OutputNaming => "Output Naming",
+            Setting::Muxer => "Muxer",
+            Setting::PreferredAudioLanguages => "Preferred Audio Languages",
+            Setting::PreferredSubtitleLanguages => "Preferred Subtitle Languages",
+            Setting::ExcludeCommentary => "Exclude Commentary",
+            Setting::SubtitleRule => "Subtitle Rule",
+            Setting::Preset(name) => EncodingPresetsConfig::display_name(name),
+        }
+    }
+
+    /// Key of the setting in config.toml, e.g. `performance.svt_preset`
+    pub fn key(&self) -> String {
+        match self {
+            Setting::VmafEnabled => "quality.vmaf_enabled".to_string(),
+            Setting::VmafThreshold => "quality.vmaf_threshold".to_string(),
+            Setting::Encoder => "encoder".to_string(),
+            Setting::SvtPreset => "performance.svt_preset".to_string(),
+            Setting::NvencPreset => "performance.nvenc_preset".to_string(),
+            Setting::AmfPreset => "performance.amf_preset".to_string(),
+            Setting::QueueOrder => "performance.queue_order".to_string(),
+            Setting::IoPriority => "performance.io_priority".to_string(),
+            Setting::OutputSuffix => "output.suffix".to_string(),
+            Setting::OutputContainer => "output.container".to_string(),
+            Setting::SameDirectory => "output.same_directory".to_string(),
+            Setting::OutputNaming => "output.naming".to_string(),
+            Setting::Muxer => "output.muxer".to_string(),
+            Setting::PreferredAudioLanguages => "tracks.preferred_audio_languages".to_string(),
+            Setting::PreferredSubtitleLanguages => {
+                "tracks.preferred_subtitle_languages".to_string()
+            }
+            Setting::ExcludeCommentary => "tracks.exclude_commentary".to_string(),
+            Setting::SubtitleRule => "tracks.subtitle_rule".to_string(),
+            Setting::Preset(name) => format!("presets.{}", name),
+        }
+    }
+
+    /// Current value as shown on the screen
+    pub fn value(&self, config: &AppConfig, deps: &DependencyStatus) -> String {
+        let yes_no = |value: bool| if value { "Yes" } else { "No" }.to_string();
+        match self {
+            Setting::VmafEnabled => match deps.unsupported_reason(Feature::VmafVerification) {
+                _ if config.quality.vmaf_enabled => "Yes".to_string(),
+                Some(reason) => format!("No ({})", reason),
+                None => "No".to_string(),
+            },
+            Setting::VmafThreshold => format!("{:.0}", config.quality.vmaf_threshold),
+            Setting::Encoder => {
+                if deps.encoder_usable(config.encoder.ffmpeg_name()) {
+                    config.encoder.display_name().to_string()
+                } else {
+                    format!("{} (not in this FFmpeg)", config.encoder.display_name())
+                }
+            }
+            Setting::SvtPreset => config.performance.svt_preset.to_string(),
+            Setting::NvencPreset => config.performance.nvenc_preset.clone(),
+            Setting::AmfPreset => config.performance.amf_preset.display_name().to_string(),
+            Setting::QueueOrder => config.performance.queue_order.display_name().to_string(),
+            Setting::IoPriority => config.performance.io_priority.display_name().to_string(),
+            Setting::OutputSuffix => config.output.suffix.clone(),
+            Setting::OutputContainer => config.output.container.clone(),
+            Setting::SameDirectory => yes_no(config.output.same_directory),
+            Setting::OutputNaming => config.output.naming.display_name().to_string(),
+            Setting::Muxer => config.output.muxer.display_name().to_string(),
+            Setting::PreferredAudioLanguages => config.tracks.preferred_audio_languages.join(", "),
+            Setting::PreferredSubtitleLanguages => {
+                config.tracks.preferred_subtitle_languages.join(", ")
+            }
+            Setting::ExcludeCommentary => yes_no(config.tracks.exclude_commentary),
+            Setting::SubtitleRule => config.tracks.subtitle_rule.display_name().to_string(),
+            Setting::Preset(name) => match config.presets.get(name) {
+                Some(preset) => format!(
+                    "CRF {} · grain {} · NVENC {} · QSV {} · AMF {}",
+                    preset.crf,
+                    preset.film_grain,
+                    preset.nvenc_cq,
+                    preset.qsv_quality,
+                    preset.amf_quality
+                ),
+                None => String::new(),
+            },
+        }
+    }
+
+    /// Check if the label, config key or section contains `filter`, ignoring case
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+        filter.is_empty()
+            || self.label().to_lowercase().contains(&filter)
+            || self.key().contains(&filter)
+            || self.section().label().to_lowercase().contains(&filter)
+    }
+}
+
+/// Line of the Configuration screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigRow {
+    Section(SettingsSection),
+    Setting(Setting),
+}
+
+/// Rows to show for a search `filter` and the collapsed sections
+///
+/// Sections without a match are left out. While filtering, matches are shown
+/// even in collapsed sections.
+pub fn config_rows(filter: &str, collapsed: &[SettingsSection]) -> Vec<ConfigRow> {
+    let filtering = !filter.trim().is_empty();
+    let mut rows = Vec::new();
+    for section in SettingsSection::ALL {
+        let settings: Vec<Setting> = Setting::ALL
+            .into_iter()
+            .filter(|s| s.section() == section && s.matches(filter))
+            .collect();
+        if settings.is_empty() {
+            continue;
+        }
+        rows.push(ConfigRow::Section(section));
+        if filtering || !collapsed.contains(&section) {
+            rows.extend(settings.into_iter().map(ConfigRow::Setting));
+        }
+    }
+    rows
+}
+
+/// Built-in defaults, detected once since the default encoder probes FFmpeg
+static DEFAULTS: LazyLock<AppConfig> = LazyLock::new(AppConfig::default);
+
+/// Built-in default configuration
+pub fn defaults() -> &'static AppConfig {
+    &DEFAULTS
+}
+
+/// Value in config.toml that differs from the built-in default
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigDifference {
+    /// Dotted key, e.g. `quality.vmaf_threshold`
+    pub key: String,
+    pub value: String,
+    pub default: String,
+}
+
+/// Every value of `config` that differs from the defaults, sorted by key
+pub fn diff_from_defaults(config: &AppConfig) -> Vec<ConfigDifference> {
+    let mut differences = Vec::new();
+    if let (Some(current), Some(default)) = (to_table(config), to_table(defaults())) {
+        diff_tables("", &current, &default, &mut differences);
+    }
+    differences
+}
+
+/// Check if a setting, or any value below it, differs from its default
+pub fn is_customized(key: &str, differences: &[ConfigDifference]) -> bool {
+    differences.iter().any(|d| {
+        d.key == key
+            || d.key
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+/// Copy of `config` with `key` (and everything below it) back at its default
+pub fn reset_key(config: &AppConfig, key: &str) -> Result<AppConfig, AppError> {
+    let mut current = toml::Value::try_from(config)?;
+    let default = toml::Value::try_from(defaults())?;
+    let path: Vec<&str> = key.split('.').collect();
+    let (last, parents) = path.split_last().expect("split yields at least one part");
+
+    let mut table = current.as_table_mut();
+    let mut default_table = default.as_table();
+    for part in parents {
+        table = table
+            .and_then(|t| t.get_mut(*part))
+            .and_then(|v| v.as_table_mut());
+        default_table = default_table
+            .and_then(|t| t.get(*part))
+            .and_then(|v| v.as_table());
+    }
+    let Some(table) = table else {
+        return Err(AppError::Config(format!("Unknown setting {}", key)));
+    };
+    match default_table.and_then(|t| t.get(*last)) {
+        Some(value) => {
+            table.insert(last.to_string(), value.clone());
+        }
+        // Unset by default, e.g. an optional folder
+        None => {
+            table.remove(*last);
+        }
+    }
+    Ok(current.try_into()?)
+}
+
+fn to_table(config: &AppConfig) -> Option<toml::Table> {
+    match toml::Value::try_from(config).ok()? {
+        toml::Value::Table(table) => Some(table),
+        _ => None,
+    }
+}
+
+fn diff_tables(
+    prefix: &str,
+    current: &toml::Table,
+    default: &toml::Table,
+    differences: &mut Vec<ConfigDifference>,
+) {
+    let mut keys: Vec<&String> = current.keys().collect();
+    keys.extend(default.keys().filter(|k| !current.contains_key(*k)));
+    for key in keys {
+        // Bumped by upgrades, never a user choice
+        if prefix.is_empty() && key == "config_version" {
+            continue;
+        }
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match (current.get(key), default.get(key)) {
+            (Some(toml::Value::Table(current)), Some(toml::Value::Table(default))) => {
+                diff_tables(&path, current, default, differences);
+            }
+            (current, default) if current != default => differences.push(ConfigDifference {
+                key: path,
+                value: display_value(current),
+                default: display_value(default),
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn display_value(value: Option<&toml::Value>) -> String {
+    match value {
+        None => "unset".to_string(),
+        // Device profiles and similar lists of tables
+        Some(toml::Value::Array(items)) if items.iter().any(toml::Value::is_table) => {
+            format!("{} entries", items.len())
+        }
+        Some(value) => value.to_string(),
+    }
+}
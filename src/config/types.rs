@@ -1,4 +1,9 @@
+use super::encoder_detect::Encoder;
+use crate::error::AppError;
+use crate::utils::naming::DEFAULT_NAME_TEMPLATE;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
 
 /// Quality configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,6 +12,55 @@ pub struct QualityConfig {
     pub vmaf_threshold: f64,
     /// Whether to run VMAF after encoding
     pub vmaf_enabled: bool,
+    /// Whether to sample motion during analysis to flag slideshows and still images
+    #[serde(default = "default_true")]
+    pub still_image_detection: bool,
+    /// Whether to compare per-stream durations of source and output after encoding
+    #[serde(default = "default_true")]
+    pub sync_check: bool,
+    /// Largest allowed difference between source and output stream durations, in seconds
+    #[serde(default = "default_sync_tolerance")]
+    pub sync_tolerance_secs: f64,
+    /// Whether to check that output audio/subtitle streams match the track selection
+    #[serde(default = "default_true")]
+    pub stream_parity_check: bool,
+    /// Whether to compare sampled frame brightness of HDR sources and outputs
+    #[serde(default = "default_true")]
+    pub hdr_check: bool,
+    /// Whether to scan the output for black or frozen segments the source lacks
+    #[serde(default = "default_true")]
+    pub defect_check: bool,
+    /// Whether to measure scene brightness during analysis and protect dark content
+    #[serde(default)]
+    pub dark_scene_detection: bool,
+    /// Quality steps the preset value is lowered by for dark content
+    #[serde(default = "default_dark_scene_crf_offset")]
+    pub dark_scene_crf_offset: u8,
+    /// Whether to deband 8-bit SDR sources prone to gradient banding before encoding
+    #[serde(default)]
+    pub deband: bool,
+    /// Whether to delete the source once the output passes the VMAF threshold
+    #[serde(default = "default_true")]
+    pub delete_source: bool,
+    /// Whether a deleted source goes to the trash or is removed for good
+    #[serde(default)]
+    pub delete_mode: DeleteMode,
+    /// Smallest output, as a fraction of the size typical for its source codec, that lets the source go
+    #[serde(default = "default_delete_min_size_ratio")]
+    pub delete_min_size_ratio: f64,
+    /// Whether to re-encode sources that are already AV1 instead of skipping them
+    #[serde(default)]
+    pub reencode_av1: bool,
+    /// Whether sources that are already AV1 stay queued to remux their tracks
+    /// or re-encode their audio, when they aren't re-encoded
+    #[serde(default)]
+    pub remux_av1: bool,
+    /// Whether re-encoded AV1 sources keep their signalled film grain instead of re-estimating it
+    #[serde(default = "default_true")]
+    pub film_grain_passthrough: bool,
+    /// Film grain picked per source from its measured noise and bitrate
+    #[serde(default)]
+    pub adaptive_grain: AdaptiveGrainConfig,
 }
 
 impl Default for QualityConfig {
@@ -14,10 +68,89 @@ impl Default for QualityConfig {
         Self {
             vmaf_threshold: 90.0,
             vmaf_enabled: true,
+            still_image_detection: true,
+            sync_check: true,
+            sync_tolerance_secs: default_sync_tolerance(),
+            stream_parity_check: true,
+            hdr_check: true,
+            defect_check: true,
+            dark_scene_detection: false,
+            dark_scene_crf_offset: default_dark_scene_crf_offset(),
+            deband: false,
+            delete_source: true,
+            delete_mode: DeleteMode::default(),
+            delete_min_size_ratio: default_delete_min_size_ratio(),
+            reencode_av1: false,
+            remux_av1: false,
+            film_grain_passthrough: true,
+            adaptive_grain: AdaptiveGrainConfig::default(),
         }
     }
 }
 
+/// How the film grain of a source follows its measured grain and bitrate
+/// instead of the fixed value of its preset
+///
+/// Sources are scored from 0 (clean digital) to 1 (heavy film grain); the
+/// score scales `max_film_grain`, less for smaller resolutions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdaptiveGrainConfig {
+    /// Whether to measure grain during analysis (SVT-AV1 only)
+    pub enabled: bool,
+    /// Film grain of a 1080p or larger source scored 1
+    pub max_film_grain: u8,
+    /// Difference of frames from their denoised selves, in dB PSNR, at and
+    /// above which a source counts as clean
+    pub clean_noise_db: f64,
+    /// Same, at and below which a source counts as fully grainy
+    pub grainy_noise_db: f64,
+    /// Bits per pixel up to which the bitrate adds nothing to the score
+    pub low_bpp: f64,
+    /// Bits per pixel from which the bitrate adds fully to the score, since
+    /// grain takes many bits to keep
+    pub high_bpp: f64,
+}
+
+impl Default for AdaptiveGrainConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_film_grain: 12,
+            clean_noise_db: 46.0,
+            grainy_noise_db: 36.0,
+            low_bpp: 0.05,
+            high_bpp: 0.25,
+        }
+    }
+}
+
+fn default_dark_scene_crf_offset() -> u8 {
+    4
+}
+
+fn default_delete_min_size_ratio() -> f64 {
+    0.1
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_sync_tolerance() -> f64 {
+    0.5
+}
+
+/// How a source is removed once its output passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteMode {
+    /// Move it to the system trash; the source is kept when there is none
+    #[default]
+    Trash,
+    Permanent,
+}
+
 /// Performance configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceConfig {
@@ -25,6 +158,145 @@ pub struct PerformanceConfig {
     pub svt_preset: u8,
     /// NVENC preset name
     pub nvenc_preset: String,
+    /// Encode in resumable fixed-length chunks
+    #[serde(default)]
+    pub chunked_encoding: bool,
+    /// Chunk length in seconds for chunked encoding
+    #[serde(default = "default_chunk_duration")]
+    pub chunk_duration_secs: u32,
+    /// Encode with SVT-AV1 when a test encode shows the hardware encoder can't
+    /// handle the source's resolution or bit depth; fail the file otherwise
+    #[serde(default = "default_true")]
+    pub hardware_fallback: bool,
+    /// Start encoding each file once its tracks are configured, while later
+    /// files are still analyzed or configured; wait for the whole batch otherwise
+    #[serde(default = "default_true")]
+    pub overlap_encoding: bool,
+    /// Order the files of a queue run are analyzed and encoded in
+    #[serde(default)]
+    pub queue_order: QueueOrder,
+    /// Read sources at most this many times faster than they play (FFmpeg `-readrate`)
+    #[serde(default)]
+    pub read_rate: Option<f64>,
+    /// Disk priority of encodes, so other programs reading the same disk come first
+    #[serde(default)]
+    pub io_priority: IoPriority,
+    /// Device Quick Sync encodes on: a DRM render node such as
+    /// `/dev/dri/renderD129` on Linux, an adapter index on Windows
+    #[serde(default)]
+    pub qsv_device: Option<String>,
+    /// Encode on the low-power (VDENC) path, the only one some Intel GPUs have
+    #[serde(default)]
+    pub qsv_low_power: bool,
+    /// Frames Quick Sync works on in parallel
+    #[serde(default)]
+    pub qsv_async_depth: Option<u32>,
+    /// AMF speed/quality trade-off
+    #[serde(default)]
+    pub amf_preset: AmfPreset,
+    /// B-frames between reference frames for AMF; RDNA3 cards encode AV1 without them
+    #[serde(default)]
+    pub amf_bframes: Option<u8>,
+    /// Let AMF analyze frames ahead for rate control and adaptive quantization
+    #[serde(default)]
+    pub amf_preanalysis: bool,
+    /// Local hours encodes may start in, e.g. `23:00-07:00`; any time when unset
+    #[serde(default)]
+    pub encode_window: Option<String>,
+    /// Files encoded at the same time
+    #[serde(default = "default_parallel_jobs")]
+    pub parallel_jobs: usize,
+}
+
+fn default_parallel_jobs() -> usize {
+    1
+}
+
+/// Setting with a fixed set of values that the settings screen steps through
+pub trait Cycle: Copy + PartialEq + 'static {
+    /// Every value, in the order they are stepped through
+    const ALL: &'static [Self];
+
+    /// Next (or previous) value in `ALL`, wrapping around
+    fn cycle(self, forward: bool) -> Self {
+        let len = Self::ALL.len();
+        let pos = Self::ALL.iter().position(|&v| v == self).unwrap_or(0);
+        let next = if forward {
+            (pos + 1) % len
+        } else {
+            (pos + len - 1) % len
+        };
+        Self::ALL[next]
+    }
+}
+
+/// AMF encoder quality preset (`-quality`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AmfPreset {
+    Speed,
+    Balanced,
+    #[default]
+    Quality,
+    HighQuality,
+}
+
+impl Cycle for AmfPreset {
+    const ALL: &'static [Self] = &[
+        AmfPreset::Speed,
+        AmfPreset::Balanced,
+        AmfPreset::Quality,
+        AmfPreset::HighQuality,
+    ];
+}
+
+impl AmfPreset {
+    /// Value of FFmpeg's `-quality` option
+    pub fn ffmpeg_name(&self) -> &'static str {
+        match self {
+            AmfPreset::Speed => "speed",
+            AmfPreset::Balanced => "balanced",
+            AmfPreset::Quality => "quality",
+            AmfPreset::HighQuality => "high_quality",
+        }
+    }
+
+    /// Display name for UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            AmfPreset::Speed => "Speed",
+            AmfPreset::Balanced => "Balanced",
+            AmfPreset::Quality => "Quality",
+            AmfPreset::HighQuality => "High quality",
+        }
+    }
+}
+
+/// Disk access priority of the tools the app runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IoPriority {
+    #[default]
+    Normal,
+    /// Lowest priority of the normal class
+    Low,
+    /// Only when no other program uses the disk
+    Idle,
+}
+
+impl Cycle for IoPriority {
+    const ALL: &'static [Self] = &[IoPriority::Normal, IoPriority::Low, IoPriority::Idle];
+}
+
+impl IoPriority {
+    /// Display name for UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            IoPriority::Normal => "Normal",
+            IoPriority::Low => "Low",
+            IoPriority::Idle => "Idle",
+        }
+    }
 }
 
 impl Default for PerformanceConfig {
@@ -32,10 +304,555 @@ impl Default for PerformanceConfig {
         Self {
             svt_preset: 4,
             nvenc_preset: "p7".to_string(),
+            chunked_encoding: false,
+            chunk_duration_secs: default_chunk_duration(),
+            hardware_fallback: true,
+            overlap_encoding: true,
+            queue_order: QueueOrder::default(),
+            read_rate: None,
+            io_priority: IoPriority::default(),
+            qsv_device: None,
+            qsv_low_power: false,
+            qsv_async_depth: None,
+            amf_preset: AmfPreset::default(),
+            amf_bframes: None,
+            amf_preanalysis: false,
+            encode_window: None,
+            parallel_jobs: default_parallel_jobs(),
+        }
+    }
+}
+
+fn default_chunk_duration() -> u32 {
+    300
+}
+
+/// Order the files of a queue run are encoded in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOrder {
+    /// As selected
+    #[default]
+    AsIs,
+    SmallestFirst,
+    LargestFirst,
+    /// Shortest running time first, for files probed on the confirm screen
+    ShortestFirst,
+}
+
+impl Cycle for QueueOrder {
+    const ALL: &'static [Self] = &[
+        QueueOrder::AsIs,
+        QueueOrder::SmallestFirst,
+        QueueOrder::LargestFirst,
+        QueueOrder::ShortestFirst,
+    ];
+}
+
+impl QueueOrder {
+    /// Display name for UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            QueueOrder::AsIs => "As selected",
+            QueueOrder::SmallestFirst => "Smallest first",
+            QueueOrder::LargestFirst => "Largest first",
+            QueueOrder::ShortestFirst => "Shortest first",
+        }
+    }
+}
+
+/// Advanced FFmpeg invocation settings for cases the command builder doesn't cover
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FfmpegConfig {
+    /// Extra arguments inserted just before the output path of every encode
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// Environment variables set for the encoding process (e.g. CUDA_VISIBLE_DEVICES)
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Frame rate assumed for raw video streams, e.g. "25" or "24000/1001"
+    #[serde(default = "default_elementary_frame_rate")]
+    pub elementary_frame_rate: String,
+}
+
+impl Default for FfmpegConfig {
+    fn default() -> Self {
+        Self {
+            extra_args: Vec::new(),
+            env: BTreeMap::new(),
+            elementary_frame_rate: default_elementary_frame_rate(),
+        }
+    }
+}
+
+impl FfmpegConfig {
+    /// `elementary_frame_rate` as numerator and denominator
+    pub fn assumed_frame_rate(&self) -> Option<(u32, u32)> {
+        let rate = self.elementary_frame_rate.trim();
+        let (num, den) = rate.split_once('/').unwrap_or((rate, "1"));
+        match (num.trim().parse(), den.trim().parse()) {
+            (Ok(num), Ok(den)) if num > 0 && den > 0 => Some((num, den)),
+            _ => None,
+        }
+    }
+}
+
+fn default_elementary_frame_rate() -> String {
+    "24000/1001".to_string()
+}
+
+/// Locations of the external tools
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolsConfig {
+    /// ffmpeg executable (name on PATH or full path)
+    pub ffmpeg: String,
+    /// ffprobe executable
+    pub ffprobe: String,
+    /// mkvmerge executable
+    pub mkvmerge: String,
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            ffmpeg: "ffmpeg".to_string(),
+            ffprobe: "ffprobe".to_string(),
+            mkvmerge: "mkvmerge".to_string(),
+        }
+    }
+}
+
+/// Which files count as videos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputConfig {
+    /// File extensions, without the dot, shown in the explorer and picked up from folders
+    #[serde(default = "default_video_extensions")]
+    pub video_extensions: Vec<String>,
+    /// How folder scans decide which files are videos
+    #[serde(default)]
+    pub detection: VideoDetection,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            video_extensions: default_video_extensions(),
+            detection: VideoDetection::default(),
+        }
+    }
+}
+
+/// How folder scans recognise video files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoDetection {
+    /// By `video_extensions`
+    #[default]
+    Extension,
+    /// By the file's first bytes, asking ffprobe about files named like videos
+    /// that match no known container; results are cached
+    Probe,
+}
+
+fn default_video_extensions() -> Vec<String> {
+    [
+        "mp4", "mkv", "avi", "mov", "webm", "m4v", "ts", "m2ts", "mts", "vob", "mpg", "mpeg",
+        "wmv", "flv", "3gp", "ogv", "h264", "264", "h265", "265", "hevc",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+/// Terminal UI settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiConfig {
+    /// How long the UI waits for input before redrawing while encoding, in milliseconds
+    pub poll_interval_ms: u64,
+    /// Wait between wakeups while nothing runs, in milliseconds
+    #[serde(default = "default_idle_poll_interval_ms")]
+    pub idle_poll_interval_ms: u64,
+    /// Units file sizes and transfer speeds are shown in
+    #[serde(default)]
+    pub size_units: SizeUnits,
+    /// Decimal separator for sizes and speeds: ".", "," or "auto" to follow the locale
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: String,
+    /// Look for a newer release on GitHub at startup, at most once a day
+    #[serde(default = "default_true")]
+    pub check_for_updates: bool,
+}
+
+fn default_idle_poll_interval_ms() -> u64 {
+    1000
+}
+
+fn default_decimal_separator() -> String {
+    "auto".to_string()
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_ms: 100,
+            idle_poll_interval_ms: default_idle_poll_interval_ms(),
+            size_units: SizeUnits::default(),
+            decimal_separator: default_decimal_separator(),
+            check_for_updates: true,
+        }
+    }
+}
+
+/// How file sizes are scaled and labelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SizeUnits {
+    /// Powers of 1024: KiB, MiB, GiB
+    #[default]
+    Binary,
+    /// Powers of 1000: kB, MB, GB
+    Decimal,
+}
+
+/// Bits-per-pixel bounds of one source codec for the "worth converting" verdict
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TriageThresholds {
+    /// At or above this, converting is expected to pay off
+    pub convert_bpp: f64,
+    /// Below this the source is already lean and best skipped
+    pub skip_bpp: f64,
+}
+
+impl TriageThresholds {
+    const fn new(convert_bpp: f64, skip_bpp: f64) -> Self {
+        Self {
+            convert_bpp,
+            skip_bpp,
+        }
+    }
+}
+
+/// Verdicts on the file review screen, per source codec
+///
+/// Older codecs need far more bits for the same picture, so they stay worth
+/// converting down to lower bitrates than HEVC or VP9.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TriageConfig {
+    pub h264: TriageThresholds,
+    pub hevc: TriageThresholds,
+    pub vp9: TriageThresholds,
+    /// MPEG-2, MPEG-4 Part 2 (XviD, DivX), VC-1 and anything else
+    pub other: TriageThresholds,
+}
+
+impl Default for TriageConfig {
+    fn default() -> Self {
+        Self {
+            h264: TriageThresholds::new(0.08, 0.04),
+            hevc: TriageThresholds::new(0.10, 0.05),
+            vp9: TriageThresholds::new(0.10, 0.05),
+            other: TriageThresholds::new(0.05, 0.02),
+        }
+    }
+}
+
+/// HTTP control server started with `--serve`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Address to listen on; only this machine unless changed
+    pub address: String,
+    /// Token clients send as `Authorization: Bearer <token>`, needed to listen
+    /// on another address than loopback
+    pub token: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1:8750".to_string(),
+            token: None,
+        }
+    }
+}
+
+/// Anonymous encode statistics for tuning the default presets
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsConfig {
+    pub mode: StatsMode,
+    /// Where `submit` posts each finished batch
+    pub endpoint: Option<String>,
+}
+
+/// What happens to the anonymous statistics of each encode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsMode {
+    /// None are kept
+    #[default]
+    Off,
+    /// Written to a file in the data directory only
+    Local,
+    /// Written to the file and posted to the endpoint
+    Submit,
+}
+
+/// Notifications sent when a batch finishes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    /// Email a batch report when the queue completes
+    pub email_enabled: bool,
+    /// SMTP server URL, e.g. smtps://smtp.example.com:465
+    pub smtp_url: String,
+    /// SMTP login name
+    pub smtp_username: Option<String>,
+    /// SMTP password
+    pub smtp_password: Option<String>,
+    /// Sender address
+    pub email_from: String,
+    /// Recipient addresses
+    pub email_to: Vec<String>,
+}
+
+/// External program run at a point of the pipeline, declared as `[[hooks]]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookConfig {
+    /// Where in the pipeline it runs
+    pub at: HookPoint,
+    /// Executable, found on `PATH` unless a path is given
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Seconds before the hook is killed and the job fails
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_hook_timeout() -> u64 {
+    600
+}
+
+/// Point of the pipeline a hook runs at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookPoint {
+    /// Before the encoder starts, with the final encoding parameters
+    BeforeEncode,
+    /// Once the output is complete, after muxing
+    AfterEncode,
+    /// Right before the output is verified, only when verification is on
+    BeforeVerify,
+    /// After verification, before the source may be deleted
+    AfterVerify,
+}
+
+impl HookPoint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookPoint::BeforeEncode => "before_encode",
+            HookPoint::AfterEncode => "after_encode",
+            HookPoint::BeforeVerify => "before_verify",
+            HookPoint::AfterVerify => "after_verify",
+        }
+    }
+}
+
+/// Output constraints for a playback device, selectable per queue run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceProfile {
+    pub name: String,
+    /// Largest output width; wider sources are scaled down keeping the aspect ratio
+    #[serde(default)]
+    pub max_width: Option<u32>,
+    /// Largest output height; taller sources are scaled down keeping the aspect ratio
+    #[serde(default)]
+    pub max_height: Option<u32>,
+    /// Encode SDR sources as 8-bit for decoders without 10-bit AV1 support
+    #[serde(default)]
+    pub eight_bit: bool,
+    /// Re-encode audio, downmixing tracks with more channels to this many
+    #[serde(default)]
+    pub audio_channels: Option<u8>,
+    /// Codec audio is re-encoded to
+    #[serde(default)]
+    pub audio_codec: AudioCodec,
+    /// Bitrate of re-encoded audio; by codec and channel count if unset
+    #[serde(default)]
+    pub audio_bitrate_kbps: Option<u32>,
+    /// Output container, replacing `output.container`
+    #[serde(default)]
+    pub container: Option<String>,
+    /// Keep subtitle streams (most MP4 players cannot show copied subtitles)
+    #[serde(default = "default_true")]
+    pub subtitles: bool,
+}
+
+impl DeviceProfile {
+    /// File name tag telling outputs of the same source apart, e.g. "phone-720p"
+    pub fn slug(&self) -> String {
+        let slug: String = self
+            .name
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect();
+        slug.split('-')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// One-line description of the constraints, e.g. "Phone 720p — ≤1280x720, 8-bit, 2ch AAC 128k, mp4"
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        match (self.max_width, self.max_height) {
+            (Some(w), Some(h)) => parts.push(format!("≤{}x{}", w, h)),
+            (Some(w), None) => parts.push(format!("≤{} wide", w)),
+            (None, Some(h)) => parts.push(format!("≤{}p", h)),
+            (None, None) => {}
+        }
+        parts.push(if self.eight_bit { "8-bit" } else { "10-bit" }.to_string());
+        match self.audio_channels {
+            Some(channels) => parts.push(format!(
+                "{}ch {} {}",
+                channels,
+                self.audio_codec.display_name(),
+                self.audio_bitrate_kbps
+                    .map_or("auto".to_string(), |kbps| format!("{}k", kbps))
+            )),
+            None => parts.push("audio copied".to_string()),
+        }
+        if let Some(ref container) = self.container {
+            parts.push(container.clone());
+        }
+        if !self.subtitles {
+            parts.push("no subtitles".to_string());
+        }
+        format!("{} — {}", self.name, parts.join(", "))
+    }
+
+    /// Bitrate for a re-encoded track with `channels` source channels, after the downmix
+    pub fn audio_bitrate(&self, channels: u16, overrides: &AudioBitrates) -> u32 {
+        if let Some(kbps) = self.audio_bitrate_kbps {
+            return kbps;
+        }
+        let channels = match self.audio_channels {
+            Some(max) => channels.clamp(1, u16::from(max)),
+            None => channels.max(1),
+        };
+        audio_bitrate(self.audio_codec, channels, overrides)
+    }
+}
+
+/// Audio codec of re-encoded tracks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioCodec {
+    #[default]
+    Aac,
+    Opus,
+    Ac3,
+    Eac3,
+}
+
+impl AudioCodec {
+    /// FFmpeg encoder name
+    pub fn ffmpeg_name(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "aac",
+            AudioCodec::Opus => "libopus",
+            AudioCodec::Ac3 => "ac3",
+            AudioCodec::Eac3 => "eac3",
+        }
+    }
+
+    /// Display name for UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "AAC",
+            AudioCodec::Opus => "Opus",
+            AudioCodec::Ac3 => "AC-3",
+            AudioCodec::Eac3 => "E-AC-3",
+        }
+    }
+
+    /// Built-in bitrates in kbps by channel count
+    fn bitrates(&self) -> &'static [(u16, u32)] {
+        match self {
+            AudioCodec::Aac => &[(1, 96), (2, 160), (6, 384), (8, 512)],
+            AudioCodec::Opus => &[(1, 64), (2, 96), (6, 256), (8, 450)],
+            AudioCodec::Ac3 => &[(1, 96), (2, 192), (6, 448)],
+            AudioCodec::Eac3 => &[(1, 96), (2, 192), (6, 448), (8, 640)],
         }
     }
 }
 
+/// Audio bitrates in kbps by codec and channel count, over the built-in ones
+pub type AudioBitrates = BTreeMap<AudioCodec, BTreeMap<u8, u32>>;
+
+/// Bitrate for `channels` channels of `codec`
+///
+/// A channel count without an entry gets the per-channel bitrate of the
+/// nearest smaller count, or of the smallest one.
+pub fn audio_bitrate(codec: AudioCodec, channels: u16, overrides: &AudioBitrates) -> u32 {
+    let mut table: BTreeMap<u16, u32> = codec.bitrates().iter().copied().collect();
+    if let Some(custom) = overrides.get(&codec) {
+        table.extend(custom.iter().map(|(&c, &kbps)| (u16::from(c), kbps)));
+    }
+    table.retain(|&c, _| c > 0);
+    let nearest = table
+        .range(..=channels)
+        .next_back()
+        .or_else(|| table.iter().next());
+    match nearest {
+        Some((&c, &kbps)) if c == channels => kbps,
+        Some((&c, &kbps)) => (kbps as f64 * channels as f64 / c as f64).round() as u32,
+        None => 0,
+    }
+}
+
+/// Built-in device profiles written to new config files
+pub fn default_profiles() -> Vec<DeviceProfile> {
+    vec![
+        DeviceProfile {
+            name: "Living-room TV 4K".to_string(),
+            max_width: Some(3840),
+            max_height: Some(2160),
+            eight_bit: false,
+            audio_channels: None,
+            audio_codec: AudioCodec::Aac,
+            audio_bitrate_kbps: None,
+            container: Some("mkv".to_string()),
+            subtitles: true,
+        },
+        DeviceProfile {
+            name: "Tablet 1080p".to_string(),
+            max_width: Some(1920),
+            max_height: Some(1080),
+            eight_bit: false,
+            audio_channels: Some(2),
+            audio_codec: AudioCodec::Aac,
+            audio_bitrate_kbps: Some(160),
+            container: Some("mp4".to_string()),
+            subtitles: false,
+        },
+        DeviceProfile {
+            name: "Phone 720p".to_string(),
+            max_width: Some(1280),
+            max_height: Some(720),
+            eight_bit: true,
+            audio_channels: Some(2),
+            audio_codec: AudioCodec::Aac,
+            audio_bitrate_kbps: Some(128),
+            container: Some("mp4".to_string()),
+            subtitles: false,
+        },
+    ]
+}
+
 /// Encoding preset for a specific resolution tier
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncodingPreset {
@@ -51,6 +868,93 @@ pub struct EncodingPreset {
     pub amf_quality: u8,
 }
 
+impl EncodingPreset {
+    /// Quality value used by the given encoder
+    pub fn quality_for(&self, encoder: Encoder) -> u8 {
+        match encoder {
+            Encoder::SvtAv1 => self.crf,
+            Encoder::Nvenc => self.nvenc_cq,
+            Encoder::Qsv => self.qsv_quality,
+            Encoder::Amf => self.amf_quality,
+        }
+    }
+}
+
+/// Value of an encoding preset that can be edited from the TUI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresetField {
+    Crf,
+    FilmGrain,
+    NvencCq,
+    QsvQuality,
+    AmfQuality,
+}
+
+impl PresetField {
+    pub const ALL: [PresetField; 5] = [
+        PresetField::Crf,
+        PresetField::FilmGrain,
+        PresetField::NvencCq,
+        PresetField::QsvQuality,
+        PresetField::AmfQuality,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PresetField::Crf => "CRF",
+            PresetField::FilmGrain => "Film grain",
+            PresetField::NvencCq => "NVENC CQ",
+            PresetField::QsvQuality => "QSV quality",
+            PresetField::AmfQuality => "AMF quality",
+        }
+    }
+
+    /// Values the encoder accepts
+    pub fn range(&self) -> RangeInclusive<u8> {
+        match self {
+            PresetField::Crf => 0..=63,
+            PresetField::FilmGrain => 0..=50,
+            PresetField::NvencCq => 0..=51,
+            PresetField::QsvQuality => 1..=51,
+            PresetField::AmfQuality => 0..=51,
+        }
+    }
+}
+
+impl EncodingPreset {
+    pub fn field(&self, field: PresetField) -> u8 {
+        match field {
+            PresetField::Crf => self.crf,
+            PresetField::FilmGrain => self.film_grain,
+            PresetField::NvencCq => self.nvenc_cq,
+            PresetField::QsvQuality => self.qsv_quality,
+            PresetField::AmfQuality => self.amf_quality,
+        }
+    }
+
+    /// Set a field, refusing values outside its range
+    pub fn set_field(&mut self, field: PresetField, value: u8) -> Result<(), AppError> {
+        let range = field.range();
+        if !range.contains(&value) {
+            return Err(AppError::Config(format!(
+                "{} must be between {} and {}",
+                field.label(),
+                range.start(),
+                range.end()
+            )));
+        }
+        let slot = match field {
+            PresetField::Crf => &mut self.crf,
+            PresetField::FilmGrain => &mut self.film_grain,
+            PresetField::NvencCq => &mut self.nvenc_cq,
+            PresetField::QsvQuality => &mut self.qsv_quality,
+            PresetField::AmfQuality => &mut self.amf_quality,
+        };
+        *slot = value;
+        Ok(())
+    }
+}
+
 /// Encoding presets per resolution tier
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncodingPresetsConfig {
@@ -66,6 +970,65 @@ pub struct EncodingPresetsConfig {
     pub uhd_dv: EncodingPreset,
 }
 
+impl EncodingPresetsConfig {
+    /// Preset names as used in `[presets.<name>]`, in display order
+    pub const NAMES: [&'static str; 8] = [
+        "sd",
+        "hd",
+        "full_hd",
+        "full_hd_hdr",
+        "full_hd_dv",
+        "uhd",
+        "uhd_hdr",
+        "uhd_dv",
+    ];
+
+    /// Look up a preset by name
+    pub fn get(&self, name: &str) -> Option<&EncodingPreset> {
+        match name {
+            "sd" => Some(&self.sd),
+            "hd" => Some(&self.hd),
+            "full_hd" => Some(&self.full_hd),
+            "full_hd_hdr" => Some(&self.full_hd_hdr),
+            "full_hd_dv" => Some(&self.full_hd_dv),
+            "uhd" => Some(&self.uhd),
+            "uhd_hdr" => Some(&self.uhd_hdr),
+            "uhd_dv" => Some(&self.uhd_dv),
+            _ => None,
+        }
+    }
+
+    /// Look up a preset by name for editing
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut EncodingPreset> {
+        match name {
+            "sd" => Some(&mut self.sd),
+            "hd" => Some(&mut self.hd),
+            "full_hd" => Some(&mut self.full_hd),
+            "full_hd_hdr" => Some(&mut self.full_hd_hdr),
+            "full_hd_dv" => Some(&mut self.full_hd_dv),
+            "uhd" => Some(&mut self.uhd),
+            "uhd_hdr" => Some(&mut self.uhd_hdr),
+            "uhd_dv" => Some(&mut self.uhd_dv),
+            _ => None,
+        }
+    }
+
+    /// Display name of a preset, as in the README's preset table
+    pub fn display_name(name: &str) -> &'static str {
+        match name {
+            "sd" => "SD",
+            "hd" => "HD",
+            "full_hd" => "1080p SDR",
+            "full_hd_hdr" => "1080p HDR",
+            "full_hd_dv" => "1080p DV",
+            "uhd" => "4K SDR",
+            "uhd_hdr" => "4K HDR",
+            "uhd_dv" => "4K DV",
+            _ => "Unknown",
+        }
+    }
+}
+
 fn default_full_hd_dv() -> EncodingPreset {
     EncodingPreset {
         crf: 20,
@@ -137,6 +1100,51 @@ impl Default for EncodingPresetsConfig {
     }
 }
 
+/// Backend used for final container assembly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Muxer {
+    /// FFmpeg muxes while encoding
+    #[default]
+    Ffmpeg,
+    /// FFmpeg encodes video only, mkvmerge assembles the final MKV
+    Mkvmerge,
+}
+
+impl Muxer {
+    /// Display name for UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Muxer::Ffmpeg => "FFmpeg",
+            Muxer::Mkvmerge => "mkvmerge",
+        }
+    }
+}
+
+/// How output files are named
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputNaming {
+    /// From `name_template`
+    #[default]
+    Template,
+    /// `<folder> - 1080p AV1`, a version of the movie in its folder for Jellyfin
+    JellyfinVersions,
+}
+
+impl Cycle for OutputNaming {
+    const ALL: &'static [Self] = &[OutputNaming::Template, OutputNaming::JellyfinVersions];
+}
+
+impl OutputNaming {
+    /// Display name for UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            OutputNaming::Template => "Name template",
+            OutputNaming::JellyfinVersions => "Jellyfin versions",
+        }
+    }
+}
+
 /// Output configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputConfig {
@@ -146,8 +1154,114 @@ pub struct OutputConfig {
     pub container: String,
     /// Whether to place output in same directory as source
     pub same_directory: bool,
-    /// Custom output directory (if same_directory is false)
+    /// Custom output directory (if same_directory is false); folders below the
+    /// queued folder are recreated inside it
     pub output_directory: Option<String>,
+    /// Output file name, without extension; see [`crate::utils::naming::render_name`]
+    #[serde(default = "default_name_template")]
+    pub name_template: String,
+    /// How output files are named: by the template, or as Jellyfin versions
+    #[serde(default)]
+    pub naming: OutputNaming,
+    /// Remux the output with stream copy after encoding to rebuild container indexes
+    #[serde(default)]
+    pub remux_after_encode: bool,
+    /// Backend for final container assembly
+    #[serde(default)]
+    pub muxer: Muxer,
+    /// Player command for previewing results (system default if unset)
+    #[serde(default)]
+    pub player: Option<String>,
+    /// What a running instance locks against other instances
+    #[serde(default)]
+    pub instance_lock: LockScope,
+    /// Encode locally and copy with verification when writing to a network mount
+    #[serde(default)]
+    pub network_safe_mode: NetworkSafeMode,
+    /// Local folder for outputs bound for the network (data directory if unset)
+    #[serde(default)]
+    pub staging_directory: Option<String>,
+    /// Tag outputs with the app and tool versions and the FFmpeg command
+    #[serde(default = "default_true")]
+    pub embed_provenance: bool,
+    /// Claim each source with a file next to it, for several machines sharing a folder
+    #[serde(default)]
+    pub claim_files: bool,
+    /// Minutes without a heartbeat after which another machine's claim is taken over
+    #[serde(default = "default_claim_stale_mins")]
+    pub claim_stale_mins: u64,
+    /// Steps run in order once an output passed, e.g. extracting its subtitles, then moving it
+    #[serde(default)]
+    pub after_encode: Vec<PostStep>,
+    /// Folder outputs are moved to by the `move_to_library` step
+    #[serde(default)]
+    pub library_directory: Option<String>,
+    /// JSON summary written when a batch finishes (`last_run.json` in the data
+    /// directory if unset)
+    #[serde(default)]
+    pub summary_file: Option<String>,
+    /// JSON file kept up to date with the live queue, for dashboards and
+    /// status bars
+    #[serde(default)]
+    pub status_file: Option<String>,
+    /// What happens when the outputs of a queue likely won't fit their volume
+    #[serde(default)]
+    pub space_check: SpaceCheck,
+}
+
+fn default_claim_stale_mins() -> u64 {
+    10
+}
+
+/// A step run for a source after its output passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostStep {
+    /// Write the output's subtitles to files next to it
+    ExtractSubtitles,
+    /// Move the output, and files written next to it, into `library_directory`
+    MoveToLibrary,
+}
+
+/// When outputs are staged locally and copied to their folder with verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkSafeMode {
+    /// For output folders on NFS, SMB and other network mounts
+    #[default]
+    Auto,
+    /// For every output, e.g. for network mounts that aren't recognized
+    Always,
+    Never,
+}
+
+/// Check of free space at the output volumes before a queue starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpaceCheck {
+    /// Show the shortfall and ask before starting
+    #[default]
+    Warn,
+    /// Refuse to start until space is freed or files are removed
+    Block,
+    Off,
+}
+
+/// Scope of the lock that keeps two instances from encoding the same files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockScope {
+    /// Only one instance may run at a time
+    #[default]
+    Global,
+    /// Instances may run together as long as they write to different folders
+    OutputDirectory,
+    /// No locking
+    Off,
+}
+
+fn default_name_template() -> String {
+    DEFAULT_NAME_TEMPLATE.to_string()
 }
 
 impl Default for OutputConfig {
@@ -157,6 +1271,22 @@ impl Default for OutputConfig {
             container: "mkv".to_string(),
             same_directory: true,
             output_directory: None,
+            name_template: default_name_template(),
+            naming: OutputNaming::default(),
+            remux_after_encode: false,
+            muxer: Muxer::default(),
+            player: None,
+            instance_lock: LockScope::default(),
+            network_safe_mode: NetworkSafeMode::default(),
+            staging_directory: None,
+            embed_provenance: true,
+            claim_files: false,
+            claim_stale_mins: default_claim_stale_mins(),
+            after_encode: Vec::new(),
+            library_directory: None,
+            summary_file: None,
+            status_file: None,
+            space_check: SpaceCheck::default(),
         }
     }
 }
@@ -170,6 +1300,85 @@ pub struct TrackPresetConfig {
     pub preferred_subtitle_languages: Vec<String>,
     /// Whether to auto-select all tracks when no preference matches
     pub select_all_fallback: bool,
+    /// What a device profile's audio re-encode does with lossy tracks it would barely shrink
+    #[serde(default)]
+    pub lossy_audio: LossyAudio,
+    /// Whether tracks that look like commentary start deselected
+    #[serde(default = "default_true")]
+    pub exclude_commentary: bool,
+    /// Which subtitle tracks of a language start selected
+    #[serde(default)]
+    pub subtitle_rule: SubtitleRule,
+    /// Per-language rules, by language code, in place of `subtitle_rule`
+    #[serde(default)]
+    pub subtitle_rules: BTreeMap<String, SubtitleRule>,
+    /// Bitrates of audio re-encoded by a device profile without a fixed one,
+    /// by codec and channel count, replacing the built-in ones
+    #[serde(default)]
+    pub audio_bitrates: AudioBitrates,
+    /// Codec audio-only jobs re-encode the selected audio to
+    #[serde(default)]
+    pub audio_only_codec: AudioCodec,
+}
+
+impl TrackPresetConfig {
+    /// Subtitle rule for tracks in `language`
+    pub fn subtitle_rule_for(&self, language: Option<&str>) -> SubtitleRule {
+        language
+            .and_then(|lang| {
+                self.subtitle_rules
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(lang))
+            })
+            .map_or(self.subtitle_rule, |(_, rule)| *rule)
+    }
+}
+
+/// Subtitle tracks of a language to start with selected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtitleRule {
+    /// Every track
+    #[default]
+    All,
+    /// Forced tracks only, for foreign-language parts
+    ForcedOnly,
+    /// Everything but subtitles for the deaf and hard of hearing
+    ExcludeSdh,
+    /// SDH instead of the plain full subtitles when the language has both
+    PreferSdh,
+}
+
+impl Cycle for SubtitleRule {
+    const ALL: &'static [Self] = &[
+        SubtitleRule::All,
+        SubtitleRule::ForcedOnly,
+        SubtitleRule::ExcludeSdh,
+        SubtitleRule::PreferSdh,
+    ];
+}
+
+impl SubtitleRule {
+    /// Display name for UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SubtitleRule::All => "All",
+            SubtitleRule::ForcedOnly => "Forced only",
+            SubtitleRule::ExcludeSdh => "Exclude SDH",
+            SubtitleRule::PreferSdh => "Prefer SDH",
+        }
+    }
+}
+
+/// Handling of already-lossy audio tracks a re-encode would barely shrink
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LossyAudio {
+    /// Re-encode them anyway and flag them on the track screen and in the log
+    #[default]
+    Warn,
+    /// Copy them unchanged when they fit the profile's channel count
+    Copy,
 }
 
 impl Default for TrackPresetConfig {
@@ -178,6 +1387,12 @@ impl Default for TrackPresetConfig {
             preferred_audio_languages: vec!["eng".to_string(), "ita".to_string()],
             preferred_subtitle_languages: vec!["eng".to_string()],
             select_all_fallback: true,
+            lossy_audio: LossyAudio::default(),
+            exclude_commentary: true,
+            subtitle_rule: SubtitleRule::default(),
+            subtitle_rules: BTreeMap::new(),
+            audio_bitrates: AudioBitrates::new(),
+            audio_only_codec: AudioCodec::default(),
         }
     }
 }
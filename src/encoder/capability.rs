@@ -0,0 +1,102 @@
+//! Check that a hardware encoder can produce the wanted format before a full encode.
+//!
+//! Detection only tells which GPU is present; whether it encodes 10-bit AV1 at
+//! a given resolution depends on the generation and driver (e.g. NVENC size
+//! limits, QSV on older Arc drivers). A one-frame test encode answers that in
+//! well under a second instead of failing minutes into the queue.
+
+use crate::config::Encoder;
+use crate::utils::tools;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::sync::{LazyLock, Mutex};
+
+/// Encoder, width, height, 10-bit flag and encoder options of a probed format
+type FormatKey = (Encoder, u32, u32, bool, HardwareOptions);
+
+/// Encoder options that decide whether a hardware encode works at all
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct HardwareOptions {
+    /// Arguments choosing the device, placed before the input
+    pub device_args: Vec<String>,
+    /// Encode on the Quick Sync low-power path
+    pub low_power: bool,
+}
+
+/// Probe results, so a queue of same-sized files is only probed once
+static CACHE: LazyLock<Mutex<HashMap<FormatKey, Result<(), String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Check that `encoder` can encode AV1 at `width`x`height` and the given bit depth
+///
+/// The software encoder always can; hardware encoders run a one-frame test
+/// encode, whose error output is returned when it fails.
+pub fn check_support(
+    encoder: Encoder,
+    width: u32,
+    height: u32,
+    ten_bit: bool,
+    options: &HardwareOptions,
+    env: &[(String, String)],
+) -> Result<(), String> {
+    if encoder == Encoder::SvtAv1 || width == 0 || height == 0 {
+        return Ok(());
+    }
+    let key = (encoder, width, height, ten_bit, options.clone());
+    if let Some(result) = CACHE.lock().ok().and_then(|c| c.get(&key).cloned()) {
+        return result;
+    }
+
+    let result = probe(encoder, width, height, ten_bit, options, env);
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.insert(key, result.clone());
+    }
+    result
+}
+
+fn probe(
+    encoder: Encoder,
+    width: u32,
+    height: u32,
+    ten_bit: bool,
+    options: &HardwareOptions,
+    env: &[(String, String)],
+) -> Result<(), String> {
+    let pixel_format = if ten_bit { "yuv420p10le" } else { "yuv420p" };
+    let output = Command::new(tools::ffmpeg())
+        .args(["-hide_banner", "-nostdin", "-v", "error"])
+        .args(&options.device_args)
+        .args([
+            "-f",
+            "lavfi",
+            "-i",
+            &format!("color=c=black:s={}x{}:r=25", width, height),
+            "-frames:v",
+            "1",
+            "-vf",
+            &format!("format={}", pixel_format),
+            "-c:v",
+            encoder.ffmpeg_name(),
+        ])
+        .args(if options.low_power {
+            &["-low_power", "1"][..]
+        } else {
+            &[]
+        })
+        .args(["-f", "null", "-"])
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("could not run ffmpeg: {}", e))?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let reason = stderr
+        .lines()
+        .map(str::trim)
+        .rfind(|l| !l.is_empty())
+        .unwrap_or("test encode failed");
+    Err(reason.to_string())
+}
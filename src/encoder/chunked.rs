@@ -0,0 +1,226 @@
+use crate::encoder::command_builder::{EncodingParams, Segment, build_ffmpeg_args};
+use crate::encoder::ffmpeg::{EncodeProgress, EncodeResult, ProgressCallback, encode_video};
+use crate::error::AppError;
+use crate::utils::tools;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// File recording the encoder settings the chunks were produced with
+pub const SETTINGS_FILE: &str = "settings";
+
+/// Work directory holding the completed chunks of an output
+pub fn chunk_dir(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_owned();
+    name.push(".chunks");
+    PathBuf::from(name)
+}
+
+/// Encode the video stream in fixed-length chunks, reusing chunks left by an earlier run
+///
+/// Completed chunks survive cancellation and crashes; they are only discarded when the
+/// encoder settings or the source change, or after the final concatenation succeeds.
+pub async fn encode_chunked(
+    params: &EncodingParams,
+    work_dir: &Path,
+    chunk_secs: f64,
+    duration: f64,
+    progress_callback: Option<ProgressCallback>,
+    cancel: CancellationToken,
+) -> EncodeResult {
+    if duration <= 0.0 || chunk_secs <= 0.0 {
+        return encode_video(params, progress_callback, cancel, duration).await;
+    }
+
+    if let Err(e) = prepare_work_dir(work_dir, &settings_signature(params, chunk_secs)) {
+        return EncodeResult::Error(AppError::Io {
+            path: work_dir.to_path_buf(),
+            operation: "prepare chunk directory",
+            message: e.to_string(),
+        });
+    }
+
+    let chunk_count = (duration / chunk_secs).ceil() as usize;
+    let progress = Arc::new(Mutex::new(progress_callback));
+    let mut chunk_paths = Vec::with_capacity(chunk_count);
+
+    for i in 0..chunk_count {
+        let start = i as f64 * chunk_secs;
+        let is_last = i + 1 == chunk_count;
+        let length = if is_last {
+            duration - start
+        } else {
+            chunk_secs
+        };
+        let chunk_path = work_dir.join(format!("chunk_{:05}.mkv", i));
+        chunk_paths.push(chunk_path.clone());
+
+        if chunk_path.exists() {
+            info!("Reusing chunk {}/{}", i + 1, chunk_count);
+            report_progress(
+                &progress,
+                EncodeProgress {
+                    percent: ((start + length) / duration * 100.0) as f32,
+                    ..Default::default()
+                },
+            );
+            continue;
+        }
+
+        if cancel.is_cancelled() {
+            return EncodeResult::Cancelled;
+        }
+
+        let partial_path = work_dir.join(format!("chunk_{:05}.partial.mkv", i));
+        let mut chunk_params = params.clone();
+        chunk_params.output = partial_path.to_string_lossy().to_string();
+        chunk_params.segment = Some(Segment {
+            start,
+            // The last chunk runs to the end so no trailing frames are lost
+            duration: if is_last { None } else { Some(chunk_secs) },
+        });
+
+        let chunk_progress = progress.clone();
+        let callback: ProgressCallback = Box::new(move |p| {
+            let overall = (start + length * p.percent as f64 / 100.0) / duration * 100.0;
+            report_progress(
+                &chunk_progress,
+                EncodeProgress {
+                    percent: overall as f32,
+                    ..p
+                },
+            );
+        });
+
+        info!(
+            "Encoding chunk {}/{} (from {:.1}s)",
+            i + 1,
+            chunk_count,
+            start
+        );
+        match encode_video(&chunk_params, Some(callback), cancel.clone(), length).await {
+            EncodeResult::Success => {
+                if let Err(e) = std::fs::rename(&partial_path, &chunk_path) {
+                    return EncodeResult::Error(AppError::Io {
+                        path: chunk_path,
+                        operation: "store chunk",
+                        message: e.to_string(),
+                    });
+                }
+            }
+            other => return other,
+        }
+    }
+
+    if let Err(e) = concat_chunks(&chunk_paths, work_dir, Path::new(&params.output)).await {
+        return EncodeResult::Error(AppError::encode(e));
+    }
+
+    if let Err(e) = std::fs::remove_dir_all(work_dir) {
+        warn!(
+            "Failed to remove chunk directory {}: {}",
+            work_dir.display(),
+            e
+        );
+    }
+
+    EncodeResult::Success
+}
+
+fn report_progress(progress: &Mutex<Option<ProgressCallback>>, mut value: EncodeProgress) {
+    if let Ok(mut guard) = progress.lock()
+        && let Some(ref mut cb) = *guard
+    {
+        value.percent = value.percent.min(100.0);
+        cb(value);
+    }
+}
+
+/// Settings and source that must match for existing chunks to be reused
+fn settings_signature(params: &EncodingParams, chunk_secs: f64) -> String {
+    let mut reference = params.clone();
+    reference.input = String::new();
+    reference.output = String::new();
+    reference.segment = None;
+    format!(
+        "source={}\nchunk_secs={}\n{}",
+        source_identity(Path::new(&params.input)),
+        chunk_secs,
+        build_ffmpeg_args(&reference).join(" ")
+    )
+}
+
+/// Path, size and modification time of the source, so chunks of a replaced
+/// source are not joined into the new output
+fn source_identity(input: &Path) -> String {
+    let path = std::fs::canonicalize(input).unwrap_or_else(|_| input.to_path_buf());
+    let (size, modified) = match std::fs::metadata(input) {
+        Ok(meta) => {
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_nanos());
+            (meta.len(), modified)
+        }
+        Err(_) => (0, 0),
+    };
+    format!("{} size={} mtime={}", path.display(), size, modified)
+}
+
+/// Create the work directory, discarding chunks made with different settings
+fn prepare_work_dir(work_dir: &Path, signature: &str) -> std::io::Result<()> {
+    let settings_path = work_dir.join(SETTINGS_FILE);
+
+    if work_dir.exists() {
+        let previous = std::fs::read_to_string(&settings_path).unwrap_or_default();
+        if previous != signature {
+            info!("Encoder settings or source changed, discarding old chunks");
+            std::fs::remove_dir_all(work_dir)?;
+        } else {
+            // Partial chunks from an interrupted run are never reusable
+            for entry in std::fs::read_dir(work_dir)?.filter_map(|e| e.ok()) {
+                if entry.file_name().to_string_lossy().contains(".partial.") {
+                    let _ = std::fs::remove_file(entry.path());
+                }
+            }
+        }
+    }
+
+    std::fs::create_dir_all(work_dir)?;
+    std::fs::write(settings_path, signature)
+}
+
+/// Join the chunks with the concat demuxer
+async fn concat_chunks(chunks: &[PathBuf], work_dir: &Path, output: &Path) -> Result<(), String> {
+    let list_path = work_dir.join("concat.txt");
+    let list: String = chunks
+        .iter()
+        .map(|c| format!("file '{}'\n", c.to_string_lossy().replace('\'', "'\\''")))
+        .collect();
+    std::fs::write(&list_path, list).map_err(|e| format!("Failed to write concat list: {}", e))?;
+
+    let result = Command::new(tools::ffmpeg())
+        .args([
+            "-y", "-nostdin", "-v", "error", "-f", "concat", "-safe", "0", "-i",
+        ])
+        .arg(&list_path)
+        .args(["-c", "copy"])
+        .arg(output)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run ffmpeg concat: {}", e))?;
+
+    if !result.status.success() {
+        let _ = std::fs::remove_file(output);
+        return Err(format!(
+            "Chunk concatenation failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
@@ -1,6 +1,11 @@
-use crate::analyzer::{HdrType, ResolutionTier, VideoMetadata};
-use crate::config::{AppConfig, Encoder};
+use super::provenance::needs_metadata_flag;
+use crate::analyzer::{FilmGrainParams, HdrType, ResolutionTier, VideoMetadata};
+use crate::config::{
+    AmfPreset, AppConfig, AudioBitrates, AudioCodec, DeviceProfile, Encoder, audio_bitrate,
+};
 use crate::tracks::TrackSelection;
+use crate::utils::deps::{DependencyStatus, Feature};
+use serde::{Deserialize, Serialize};
 
 /// Parameters for encoding a video file
 #[derive(Debug, Clone)]
@@ -10,12 +15,303 @@ pub struct EncodingParams {
     pub encoder: Encoder,
     pub crf: u8,
     pub film_grain: u8,
+    /// Let SVT-AV1 denoise before synthesizing grain (needs a recent enough library)
+    pub film_grain_denoise: bool,
     pub hdr_type: HdrType,
     pub tracks: TrackSelection,
     pub frame_rate_num: u32,
     pub frame_rate_den: u32,
     pub svt_preset: u8,
     pub nvenc_preset: String,
+    /// Quick Sync device, e.g. `/dev/dri/renderD129`
+    pub qsv_device: Option<String>,
+    /// Use the Quick Sync low-power (VDENC) path
+    pub qsv_low_power: bool,
+    pub qsv_async_depth: Option<u32>,
+    pub amf_preset: AmfPreset,
+    pub amf_bframes: Option<u8>,
+    pub amf_preanalysis: bool,
+    /// Read the input at most this many times faster than it plays (`-readrate`)
+    pub read_rate: Option<f64>,
+    /// Encode only the video stream (for external muxing)
+    pub video_only: bool,
+    /// Largest output width; larger sources are scaled down
+    pub max_width: Option<u32>,
+    /// Largest output height; larger sources are scaled down
+    pub max_height: Option<u32>,
+    /// Encode SDR sources as 8-bit instead of 10-bit
+    pub eight_bit: bool,
+    /// Spend more bits on flat dark areas, where AV1 tends to band and block
+    pub dark_scene_aq: bool,
+    /// Smooth gradient steps of a banding-prone source before encoding
+    pub deband: bool,
+    /// Grain table reused from an AV1 source; the source is then decoded without grain
+    pub film_grain_table: Option<String>,
+    /// How audio and subtitle streams are written
+    pub streams: StreamHandling,
+    /// Encode only part of the input (chunked mode)
+    pub segment: Option<Segment>,
+    /// Frame rate a raw video stream is read at, since it has no timestamps
+    pub input_frame_rate: Option<(u32, u32)>,
+    /// Extra arguments inserted before the output path
+    pub extra_args: Vec<String>,
+    /// Environment variables for the FFmpeg process
+    pub env: Vec<(String, String)>,
+    /// Container tags of the output; a video-only encode leaves them to the mux
+    pub tags: Vec<(String, String)>,
+    /// Bitrates of re-encoded audio configured over the built-in ones
+    pub audio_bitrates: AudioBitrates,
+    /// Copy the video stream instead of encoding it
+    pub copy_video: bool,
+    /// Codec of audio re-encoded by an audio-only job
+    pub audio_only_codec: AudioCodec,
+}
+
+/// How audio and subtitle streams are written to the output
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamHandling {
+    /// Re-encode audio instead of copying it
+    pub audio: Option<AudioTranscode>,
+    /// Leave subtitle streams out
+    pub drop_subtitles: bool,
+}
+
+/// Audio re-encoding settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioTranscode {
+    /// FFmpeg audio encoder name
+    pub codec: String,
+    /// Downmix to this many channels
+    pub channels: Option<u8>,
+    /// Bitrate of streams without their own in `streams`
+    pub bitrate_kbps: u32,
+    /// Output audio positions copied unchanged instead
+    pub copy_streams: Vec<usize>,
+    /// Each output audio stream, when the source layouts are known; only
+    /// streams with more channels than `channels` are then downmixed
+    pub streams: Vec<AudioStream>,
+}
+
+/// Bitrate and downmix of one re-encoded audio stream
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioStream {
+    pub bitrate_kbps: u32,
+    pub downmix: bool,
+}
+
+impl StreamHandling {
+    /// Codec arguments for audio and subtitles
+    fn codec_args(&self) -> Vec<String> {
+        let mut args = match &self.audio {
+            None => vec!["-c:a".to_string(), "copy".to_string()],
+            Some(audio) => {
+                let mut args = vec![
+                    "-c:a".to_string(),
+                    audio.codec.clone(),
+                    "-b:a".to_string(),
+                    format!("{}k", audio.bitrate_kbps),
+                ];
+                if audio.streams.is_empty()
+                    && let Some(channels) = audio.channels
+                {
+                    args.extend(["-ac".to_string(), channels.to_string()]);
+                }
+                for (position, stream) in audio.streams.iter().enumerate() {
+                    if audio.copy_streams.contains(&position) {
+                        continue;
+                    }
+                    args.extend([
+                        format!("-b:a:{}", position),
+                        format!("{}k", stream.bitrate_kbps),
+                    ]);
+                    if let Some(channels) = audio.channels.filter(|_| stream.downmix) {
+                        args.extend([format!("-ac:a:{}", position), channels.to_string()]);
+                    }
+                }
+                // Per-stream codecs come after the general one so they win
+                for position in &audio.copy_streams {
+                    args.extend([format!("-c:a:{}", position), "copy".to_string()]);
+                }
+                args
+            }
+        };
+        if !self.drop_subtitles {
+            args.extend(["-c:s".to_string(), "copy".to_string()]);
+        }
+        args
+    }
+}
+
+/// Time range of the input to encode
+#[derive(Debug, Clone, Copy)]
+pub struct Segment {
+    /// Start offset in seconds
+    pub start: f64,
+    /// Length in seconds, or until the end of the input
+    pub duration: Option<f64>,
+}
+
+/// What a job does with its source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobMode {
+    /// Encode the video to AV1
+    #[default]
+    Encode,
+    /// Copy every stream, keeping only the selected audio and subtitle tracks
+    Remux,
+    /// Copy the video and re-encode the selected audio
+    AudioOnly,
+}
+
+impl JobMode {
+    pub const ALL: [JobMode; 3] = [JobMode::Encode, JobMode::Remux, JobMode::AudioOnly];
+
+    /// Display name for UI
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            JobMode::Encode => "Encode",
+            JobMode::Remux => "Remux tracks",
+            JobMode::AudioOnly => "Re-encode audio",
+        }
+    }
+
+    /// Whether the video stream is copied as it is
+    pub fn copies_video(&self) -> bool {
+        *self != JobMode::Encode
+    }
+
+    /// Next mode, wrapping around
+    pub fn next(self) -> Self {
+        let pos = Self::ALL.iter().position(|&m| m == self).unwrap_or(0);
+        Self::ALL[(pos + 1) % Self::ALL.len()]
+    }
+}
+
+/// Per-job adjustments applied on top of the preset-derived parameters
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOverrides {
+    /// Quality value replacing the preset CRF/CQ
+    pub crf: Option<u8>,
+    /// Output frame rate replacing the source frame rate
+    pub frame_rate: Option<(u32, u32)>,
+    /// Frame rate assumed for a raw video stream source
+    pub input_frame_rate: Option<(u32, u32)>,
+    /// Extra arguments appended after the global ones
+    pub extra_args: Vec<String>,
+    /// Environment variables overriding the global ones
+    pub env: Vec<(String, String)>,
+    /// Device profile of this output
+    pub profile: Option<DeviceProfile>,
+    /// Film grain signalled in an AV1 source, reused instead of re-estimated
+    pub source_grain: Option<FilmGrainParams>,
+    /// Film grain picked for the source's measured grain, replacing the preset's
+    pub film_grain: Option<u8>,
+    /// Leave the source in place even when verification passes, e.g. while
+    /// other outputs of it are still to be encoded
+    pub keep_source: bool,
+    /// Quality steps to lower the preset value by for dark content, which
+    /// also strengthens adaptive quantization in shadows
+    pub dark_scene_offset: Option<u8>,
+    /// Output audio positions copied although the profile re-encodes audio
+    pub copy_audio: Vec<usize>,
+    /// Channel count of each kept audio track, in output order
+    pub audio_channels: Vec<u16>,
+    /// Work out the encode without running it
+    pub simulate: bool,
+    /// Encode the video, or only rewrite the other streams
+    pub mode: JobMode,
+}
+
+impl EncodeOverrides {
+    /// Quality value to encode with, given the preset's
+    pub fn quality(&self, preset: u8) -> u8 {
+        match (self.crf, self.dark_scene_offset) {
+            (Some(crf), _) => crf,
+            (None, Some(offset)) => preset.saturating_sub(offset),
+            (None, None) => preset,
+        }
+    }
+
+    /// Parse a shell-like string where leading `NAME=value` words are environment variables
+    ///
+    /// e.g. `CUDA_VISIBLE_DEVICES=1 -g 240 -metadata "title=My Film"`
+    pub fn parse_extra(input: &str) -> (Vec<(String, String)>, Vec<String>) {
+        let mut env = Vec::new();
+        let mut args = Vec::new();
+
+        for word in split_words(input) {
+            if args.is_empty()
+                && let Some((name, value)) = word.split_once('=')
+                && is_env_name(name)
+            {
+                env.push((name.to_string(), value.to_string()));
+            } else {
+                args.push(word);
+            }
+        }
+
+        (env, args)
+    }
+}
+
+/// Split on whitespace, honouring single and double quotes
+fn split_words(input: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in input.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+
+    words
+}
+
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Film grain strength from which synthesized grain hides gradient steps
+const MASKING_FILM_GRAIN: u8 = 8;
+
+/// Whether a source is likely to show gradient banding after AV1 compression
+///
+/// 8-bit SDR sources have coarse gradient steps that AV1's smoothing turns
+/// into visible bands, unless enough film grain is synthesized on top.
+pub fn banding_prone(metadata: &VideoMetadata, film_grain: u8) -> bool {
+    metadata.bit_depth() == 8 && !metadata.hdr_type.is_hdr() && film_grain < MASKING_FILM_GRAIN
+}
+
+/// Get the preset quality value for the configured encoder
+pub fn preset_quality(metadata: &VideoMetadata, config: &AppConfig) -> u8 {
+    let tier = ResolutionTier::from_dimensions(metadata.width, metadata.height);
+    config
+        .preset_for(&tier, metadata.hdr_type)
+        .quality_for(config.encoder)
 }
 
 impl EncodingParams {
@@ -30,58 +326,283 @@ impl EncodingParams {
         let tier = ResolutionTier::from_dimensions(metadata.width, metadata.height);
         let preset = config.preset_for(&tier, metadata.hdr_type);
 
-        let crf = match config.encoder {
-            Encoder::SvtAv1 => preset.crf,
-            Encoder::Nvenc => preset.nvenc_cq,
-            Encoder::Qsv => preset.qsv_quality,
-            Encoder::Amf => preset.amf_quality,
-        };
-
         Self {
             input: input.to_string(),
             output: output.to_string(),
             encoder: config.encoder,
-            crf,
+            crf: preset_quality(metadata, config),
             film_grain: preset.film_grain,
+            film_grain_denoise: true,
             hdr_type: metadata.hdr_type,
             tracks,
             frame_rate_num: metadata.frame_rate_num,
             frame_rate_den: metadata.frame_rate_den,
             svt_preset: config.performance.svt_preset,
             nvenc_preset: config.performance.nvenc_preset.clone(),
+            qsv_device: config.performance.qsv_device.clone(),
+            qsv_low_power: config.performance.qsv_low_power,
+            qsv_async_depth: config.performance.qsv_async_depth,
+            amf_preset: config.performance.amf_preset,
+            amf_bframes: config.performance.amf_bframes,
+            amf_preanalysis: config.performance.amf_preanalysis,
+            read_rate: config.performance.read_rate,
+            video_only: false,
+            max_width: None,
+            max_height: None,
+            eight_bit: false,
+            dark_scene_aq: false,
+            deband: config.quality.deband && banding_prone(metadata, preset.film_grain),
+            film_grain_table: None,
+            streams: StreamHandling::default(),
+            segment: None,
+            input_frame_rate: None,
+            extra_args: config.ffmpeg.extra_args.clone(),
+            env: config
+                .ffmpeg
+                .env
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            tags: Vec::new(),
+            audio_bitrates: config.tracks.audio_bitrates.clone(),
+            copy_video: false,
+            audio_only_codec: config.tracks.audio_only_codec,
+        }
+    }
+
+    /// Frame size after scaling to the profile's bounding box (never up, even sizes)
+    pub fn output_dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+        fit_within(self.max_width, self.max_height, width, height)
+    }
+
+    /// Apply per-job overrides
+    pub fn apply_overrides(&mut self, overrides: &EncodeOverrides) {
+        self.crf = overrides.quality(self.crf);
+        self.dark_scene_aq = overrides.dark_scene_offset.is_some();
+        if let Some(film_grain) = overrides.film_grain {
+            self.film_grain = film_grain;
+            // Grain strong enough to hide gradient steps needs no debanding
+            self.deband &= film_grain < MASKING_FILM_GRAIN;
+        }
+        if let Some((num, den)) = overrides.frame_rate {
+            self.frame_rate_num = num;
+            self.frame_rate_den = den;
+        }
+        if overrides.input_frame_rate.is_some() {
+            self.input_frame_rate = overrides.input_frame_rate;
+        }
+        self.extra_args.extend(overrides.extra_args.iter().cloned());
+        self.env.extend(overrides.env.iter().cloned());
+        if let Some(ref profile) = overrides.profile {
+            self.apply_profile(profile);
+        }
+        if let Some(ref mut audio) = self.streams.audio {
+            audio.copy_streams = overrides.copy_audio.clone();
+        }
+        if let (Some(profile), Some(audio)) = (&overrides.profile, &mut self.streams.audio) {
+            let max = profile.audio_channels.map_or(u16::MAX, u16::from);
+            audio.streams = overrides
+                .audio_channels
+                .iter()
+                .map(|&channels| AudioStream {
+                    bitrate_kbps: profile.audio_bitrate(channels, &self.audio_bitrates),
+                    downmix: channels > max,
+                })
+                .collect();
+        }
+        if overrides.mode.copies_video() {
+            self.apply_mode(overrides);
+        }
+    }
+
+    /// Copy the video, leaving the selected audio copied or re-encoded by the job's mode
+    fn apply_mode(&mut self, overrides: &EncodeOverrides) {
+        self.copy_video = true;
+        // Nothing scales, converts or filters a copied stream
+        self.max_width = None;
+        self.max_height = None;
+        self.eight_bit = false;
+        self.deband = false;
+        let codec = self.audio_only_codec;
+        self.streams = StreamHandling {
+            audio: (overrides.mode == JobMode::AudioOnly).then(|| AudioTranscode {
+                codec: codec.ffmpeg_name().to_string(),
+                channels: None,
+                bitrate_kbps: audio_bitrate(codec, 2, &self.audio_bitrates),
+                copy_streams: overrides.copy_audio.clone(),
+                streams: overrides
+                    .audio_channels
+                    .iter()
+                    .map(|&channels| AudioStream {
+                        bitrate_kbps: audio_bitrate(codec, channels, &self.audio_bitrates),
+                        downmix: false,
+                    })
+                    .collect(),
+            }),
+            drop_subtitles: false,
+        };
+    }
+
+    /// Leave out encoder options the installed toolchain is too old for
+    pub fn apply_toolchain(&mut self, deps: &DependencyStatus) {
+        if !deps.supports(Feature::ReadRate) {
+            self.read_rate = None;
+        }
+        if self.encoder != Encoder::SvtAv1 {
+            return;
+        }
+        self.film_grain_denoise = deps.supports(Feature::FilmGrainDenoise);
+        self.dark_scene_aq &= deps.supports(Feature::VarianceBoost);
+    }
+
+    /// Constrain the output to a device profile
+    pub fn apply_profile(&mut self, profile: &DeviceProfile) {
+        self.max_width = profile.max_width;
+        self.max_height = profile.max_height;
+        // HDR needs 10-bit; 8-bit only applies to SDR sources
+        self.eight_bit = profile.eight_bit && !self.hdr_type.is_hdr();
+        self.streams = StreamHandling {
+            audio: profile.audio_channels.map(|channels| AudioTranscode {
+                codec: profile.audio_codec.ffmpeg_name().to_string(),
+                channels: Some(channels),
+                bitrate_kbps: profile.audio_bitrate(u16::from(channels), &self.audio_bitrates),
+                copy_streams: Vec::new(),
+                streams: Vec::new(),
+            }),
+            drop_subtitles: !profile.subtitles,
+        };
+    }
+}
+
+/// Named parameter sets covering every encoder × HDR type × track layout
+///
+/// Used by the golden-file tests and `--dump-commands`, so changes to the generated
+/// command lines show up as reviewable diffs.
+pub fn sample_variants(config: &AppConfig) -> Vec<(String, EncodingParams)> {
+    let encoders = [Encoder::SvtAv1, Encoder::Nvenc, Encoder::Qsv, Encoder::Amf];
+    let hdr_types = [
+        HdrType::Sdr,
+        HdrType::Pq,
+        HdrType::Hlg,
+        HdrType::DolbyVision,
+    ];
+    let selected = TrackSelection {
+        audio_indices: vec![0, 2],
+        subtitle_indices: vec![1],
+    };
+    let layouts = [
+        ("all", TrackSelection::default(), false),
+        ("selected", selected, false),
+        ("video_only", TrackSelection::default(), true),
+    ];
+
+    let mut variants = Vec::new();
+    for encoder in encoders {
+        let mut config = config.clone();
+        config.encoder = encoder;
+        for hdr_type in hdr_types {
+            let metadata = VideoMetadata {
+                width: 3840,
+                height: 2160,
+                hdr_type,
+                codec_name: "hevc".to_string(),
+                pixel_format: Some("yuv420p10le".to_string()),
+                frame_rate_num: 24000,
+                frame_rate_den: 1001,
+                duration_secs: 600.0,
+                bitrate: None,
+            };
+            for (layout, tracks, video_only) in &layouts {
+                let mut params = EncodingParams::from_metadata(
+                    "input.mkv",
+                    "output.mkv",
+                    &metadata,
+                    &config,
+                    tracks.clone(),
+                );
+                params.video_only = *video_only;
+                let name = format!("{:?}_{:?}_{}", encoder, hdr_type, layout).to_lowercase();
+                variants.push((name, params));
+            }
         }
     }
+    variants
+}
+
+/// Frame size scaled down into a bounding box, keeping the aspect ratio and even sizes
+pub fn fit_within(
+    max_width: Option<u32>,
+    max_height: Option<u32>,
+    width: u32,
+    height: u32,
+) -> (u32, u32) {
+    let scale = |max: Option<u32>, dim: u32| match max {
+        Some(max) if dim > max => max as f64 / dim as f64,
+        _ => 1.0,
+    };
+    let factor = scale(max_width, width).min(scale(max_height, height));
+    let even = |dim: u32| ((dim as f64 * factor) as u32) & !1;
+    (even(width), even(height))
 }
 
 /// Build FFmpeg arguments for encoding
 pub fn build_ffmpeg_args(params: &EncodingParams) -> Vec<String> {
-    let mut args = vec![
-        "-y".to_string(),
-        "-nostdin".to_string(),
+    let mut args = vec!["-y".to_string(), "-nostdin".to_string()];
+    if params.encoder == Encoder::Qsv && !params.copy_video {
+        args.extend(qsv_device_args(params.qsv_device.as_deref()));
+    }
+
+    // Input seeking (accurate when transcoding)
+    if let Some(segment) = params.segment {
+        args.extend(["-ss".to_string(), format!("{:.3}", segment.start)]);
+        if let Some(duration) = segment.duration {
+            args.extend(["-t".to_string(), format!("{:.3}", duration)]);
+        }
+    }
+    if let Some((num, den)) = params.input_frame_rate {
+        args.extend(["-framerate".to_string(), format!("{}/{}", num, den)]);
+    }
+    if let Some(rate) = params.read_rate.filter(|r| *r > 0.0) {
+        args.extend(["-readrate".to_string(), rate.to_string()]);
+    }
+    // Keep the source grain out of the picture; the encoder re-synthesizes it
+    if params.film_grain_table.is_some() {
+        args.extend(["-export_side_data".to_string(), "film_grain".to_string()]);
+    }
+
+    args.extend([
         "-i".to_string(),
         params.input.clone(),
         "-map".to_string(),
         "0:v:0".to_string(),
-    ];
+    ]);
 
-    // Track mapping
-    if params.tracks.audio_indices.is_empty() && params.tracks.subtitle_indices.is_empty() {
-        args.extend(["-map".to_string(), "0:a?".to_string()]);
-        args.extend(["-map".to_string(), "0:s?".to_string()]);
-    } else {
-        for idx in &params.tracks.audio_indices {
-            args.extend(["-map".to_string(), format!("0:a:{}", idx)]);
-        }
-        for idx in &params.tracks.subtitle_indices {
-            args.extend(["-map".to_string(), format!("0:s:{}", idx)]);
-        }
+    // Track mapping (video-only encodes take audio and subtitles from the source at mux time)
+    if !params.video_only {
+        args.extend(get_track_maps(0, &params.tracks, &params.streams));
+    }
+
+    // A copied video keeps its encode; only the other streams change
+    if params.copy_video {
+        args.extend(["-c:v".to_string(), "copy".to_string()]);
+        args.extend(params.streams.codec_args());
+        args.extend([
+            "-map_metadata".to_string(),
+            "0".to_string(),
+            "-map_chapters".to_string(),
+            "0".to_string(),
+        ]);
+        args.extend(tag_args(&params.tags, &params.output));
+        args.extend(params.extra_args.iter().cloned());
+        args.push(params.output.clone());
+        return args;
     }
 
     // Video encoder
     args.extend(["-c:v".to_string(), params.encoder.ffmpeg_name().to_string()]);
 
     // Build video filter chain (explicit filter graph is more robust than -pix_fmt auto-insertion)
-    let vf = build_video_filter(params.hdr_type);
+    let vf = build_video_filter(params);
     args.extend(["-vf".to_string(), vf]);
 
     // Explicit frame rate preservation
@@ -92,29 +613,112 @@ pub fn build_ffmpeg_args(params: &EncodingParams) -> Vec<String> {
         ]);
     }
 
-    // Copy audio and subtitles
-    args.extend([
-        "-c:a".to_string(),
-        "copy".to_string(),
-        "-c:s".to_string(),
-        "copy".to_string(),
-    ]);
+    // Copy (or re-encode) audio and subtitles
+    if !params.video_only {
+        args.extend(params.streams.codec_args());
+    }
 
     // Encoder-specific quality parameters
     args.extend(get_quality_params(params));
 
     // HDR/color parameters (metadata only, filter is handled above)
-    match params.hdr_type {
-        HdrType::DolbyVision => args.extend(get_dolby_vision_color_params()),
-        HdrType::Pq => args.extend(get_pq_params()),
-        HdrType::Hlg => args.extend(get_hlg_params()),
-        HdrType::Sdr => {}
+    args.extend(get_color_params(params.hdr_type));
+    if matches!(params.hdr_type, HdrType::Pq | HdrType::Hlg) {
+        args.extend(["-map_metadata".to_string(), "0".to_string()]);
     }
 
+    if !params.video_only {
+        args.extend(tag_args(&params.tags, &params.output));
+    }
+
+    // User-supplied arguments go last so they can override anything above
+    args.extend(params.extra_args.iter().cloned());
+
     args.push(params.output.clone());
     args
 }
 
+/// `-metadata` arguments writing `tags` to `output`
+fn tag_args(tags: &[(String, String)], output: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    for (name, value) in tags {
+        args.extend(["-metadata".to_string(), format!("{}={}", name, value)]);
+    }
+    if !tags.is_empty() && needs_metadata_flag(output) {
+        args.extend(["-movflags".to_string(), "+use_metadata_tags".to_string()]);
+    }
+    args
+}
+
+/// Build FFmpeg arguments to mux an encoded video stream with the source's selected tracks
+pub fn build_mux_args(
+    video: &str,
+    source: &str,
+    output: &str,
+    tracks: &TrackSelection,
+    streams: &StreamHandling,
+    hdr_type: HdrType,
+    tags: &[(String, String)],
+) -> Vec<String> {
+    let mut args = vec![
+        "-y".to_string(),
+        "-nostdin".to_string(),
+        "-v".to_string(),
+        "error".to_string(),
+        "-i".to_string(),
+        video.to_string(),
+        "-i".to_string(),
+        source.to_string(),
+        "-map".to_string(),
+        "0:v:0".to_string(),
+    ];
+    args.extend(get_track_maps(1, tracks, streams));
+    args.extend(["-c".to_string(), "copy".to_string()]);
+    if streams.audio.is_some() {
+        args.extend(streams.codec_args());
+    }
+    args.extend([
+        "-map_metadata".to_string(),
+        "1".to_string(),
+        "-map_chapters".to_string(),
+        "1".to_string(),
+    ]);
+
+    args.extend(get_color_params(hdr_type));
+    args.extend(tag_args(tags, output));
+
+    args.push(output.to_string());
+    args
+}
+
+/// Get -map arguments for the selected audio and subtitle tracks of an input
+fn get_track_maps(input: usize, tracks: &TrackSelection, streams: &StreamHandling) -> Vec<String> {
+    let mut args = Vec::new();
+    if tracks.keeps_all() {
+        args.extend(["-map".to_string(), format!("{}:a?", input)]);
+        if !streams.drop_subtitles {
+            args.extend(["-map".to_string(), format!("{}:s?", input)]);
+        }
+    } else {
+        for idx in &tracks.audio_indices {
+            args.extend(["-map".to_string(), format!("{}:a:{}", input, idx)]);
+        }
+        // The first track becomes the default, whatever the source flagged
+        if tracks.audio_reordered() {
+            for i in 0..tracks.audio_indices.len() {
+                let flag = if i == 0 { "default" } else { "0" };
+                args.extend([format!("-disposition:a:{}", i), flag.to_string()]);
+            }
+        }
+        if !streams.drop_subtitles {
+            for idx in &tracks.subtitle_indices {
+                args.extend(["-map".to_string(), format!("{}:s:{}", input, idx)]);
+            }
+        }
+    }
+    args
+}
+
 /// Get encoder-specific quality parameters
 fn get_quality_params(params: &EncodingParams) -> Vec<String> {
     match params.encoder {
@@ -126,14 +730,24 @@ fn get_quality_params(params: &EncodingParams) -> Vec<String> {
 }
 
 fn get_svtav1_params(params: &EncodingParams) -> Vec<String> {
-    let svt_params = if params.film_grain > 0 {
+    let mut svt_params = if let Some(ref table) = params.film_grain_table {
+        format!("tune=0:fgs-table={}:enable-overlays=1:scd=1", table)
+    } else if params.film_grain > 0 {
         format!(
-            "tune=0:film-grain={}:film-grain-denoise=1:enable-overlays=1:scd=1",
-            params.film_grain
+            "tune=0:film-grain={}{}:enable-overlays=1:scd=1",
+            params.film_grain,
+            if params.film_grain_denoise {
+                ":film-grain-denoise=1"
+            } else {
+                ""
+            }
         )
     } else {
         "tune=0:film-grain=0:enable-overlays=1:scd=1:enable-tf=1".to_string()
     };
+    if params.dark_scene_aq {
+        svt_params.push_str(":enable-variance-boost=1");
+    }
 
     vec![
         "-crf".to_string(),
@@ -148,7 +762,7 @@ fn get_svtav1_params(params: &EncodingParams) -> Vec<String> {
 fn get_nvenc_params(params: &EncodingParams) -> Vec<String> {
     let lookahead = if params.crf <= 23 { "48" } else { "32" };
 
-    vec![
+    let mut args = vec![
         "-cq".to_string(),
         params.crf.to_string(),
         "-preset".to_string(),
@@ -163,11 +777,15 @@ fn get_nvenc_params(params: &EncodingParams) -> Vec<String> {
         "1".to_string(),
         "-temporal-aq".to_string(),
         "1".to_string(),
-    ]
+    ];
+    if params.dark_scene_aq {
+        args.extend(["-aq-strength".to_string(), "12".to_string()]);
+    }
+    args
 }
 
 fn get_qsv_params(params: &EncodingParams) -> Vec<String> {
-    vec![
+    let mut args = vec![
         "-global_quality".to_string(),
         params.crf.to_string(),
         "-preset".to_string(),
@@ -176,51 +794,109 @@ fn get_qsv_params(params: &EncodingParams) -> Vec<String> {
         "1".to_string(),
         "-look_ahead_depth".to_string(),
         "40".to_string(),
-    ]
+    ];
+    if params.qsv_low_power {
+        args.extend(["-low_power".to_string(), "1".to_string()]);
+    }
+    if let Some(depth) = params.qsv_async_depth.filter(|d| *d > 0) {
+        args.extend(["-async_depth".to_string(), depth.to_string()]);
+    }
+    args
 }
 
+/// Global arguments opening Quick Sync on `device` instead of the first GPU
+///
+/// Multi-GPU systems often list a card without an AV1 encoder first, where
+/// the default device fails.
+pub fn qsv_device_args(device: Option<&str>) -> Vec<String> {
+    match device.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(device) => vec!["-qsv_device".to_string(), device.to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// AMF takes the preset as `-quality` and the quality value as constant QPs
+///
+/// AV1 QPs run from 0 to 255, so the 0–51 quality value of the presets is
+/// scaled by 5. B-frames get a slightly higher QP, like the other encoders do.
 fn get_amf_params(params: &EncodingParams) -> Vec<String> {
-    vec![
-        "-quality".to_string(),
-        params.crf.to_string(),
+    let qp = u32::from(params.crf) * 5;
+    let mut args = vec![
         "-usage".to_string(),
         "transcoding".to_string(),
+        "-quality".to_string(),
+        params.amf_preset.ffmpeg_name().to_string(),
         "-rc".to_string(),
         "cqp".to_string(),
-    ]
+        "-qp_i".to_string(),
+        qp.to_string(),
+        "-qp_p".to_string(),
+        qp.to_string(),
+    ];
+    if let Some(bframes) = params.amf_bframes.filter(|b| *b > 0) {
+        args.extend([
+            "-bf".to_string(),
+            bframes.to_string(),
+            "-qp_b".to_string(),
+            (qp + 10).min(255).to_string(),
+        ]);
+    }
+    if params.amf_preanalysis {
+        args.extend(["-preanalysis".to_string(), "1".to_string()]);
+    }
+    args
 }
 
-fn get_pq_params() -> Vec<String> {
-    vec![
-        "-color_primaries".to_string(),
-        "bt2020".to_string(),
-        "-color_trc".to_string(),
-        "smpte2084".to_string(),
-        "-colorspace".to_string(),
-        "bt2020nc".to_string(),
-        "-map_metadata".to_string(),
-        "0".to_string(),
-    ]
-}
+/// Color metadata parameters for HDR output (filter is handled in build_video_filter)
+///
+/// Dolby Vision is output as HDR10, so it shares the PQ parameters.
+fn get_color_params(hdr_type: HdrType) -> Vec<String> {
+    let transfer = match hdr_type {
+        HdrType::Sdr => return Vec::new(),
+        HdrType::Pq | HdrType::DolbyVision => "smpte2084",
+        HdrType::Hlg => "arib-std-b67",
+    };
 
-fn get_hlg_params() -> Vec<String> {
     vec![
         "-color_primaries".to_string(),
         "bt2020".to_string(),
         "-color_trc".to_string(),
-        "arib-std-b67".to_string(),
+        transfer.to_string(),
         "-colorspace".to_string(),
         "bt2020nc".to_string(),
-        "-map_metadata".to_string(),
-        "0".to_string(),
     ]
 }
 
-/// Build the video filter chain for format conversion and HDR metadata
-fn build_video_filter(hdr_type: HdrType) -> String {
-    let mut filters = vec!["format=yuv420p10le".to_string()];
+/// Build the video filter chain for scaling, format conversion and HDR metadata
+fn build_video_filter(params: &EncodingParams) -> String {
+    let mut filters = Vec::new();
+
+    // Scale down (never up) to fit the profile's bounding box
+    if params.max_width.is_some() || params.max_height.is_some() {
+        let bound = |max: Option<u32>, dim: &str| match max {
+            Some(max) => format!("'min({},{})'", dim, max),
+            None => dim.to_string(),
+        };
+        filters.push(format!(
+            "scale=w={}:h={}:force_original_aspect_ratio=decrease:force_divisible_by=2",
+            bound(params.max_width, "iw"),
+            bound(params.max_height, "ih")
+        ));
+    }
+
+    // Deband at the source bit depth, then let the 10-bit conversion keep the smooth gradients
+    if params.deband {
+        filters.push("deband=1thr=0.015:2thr=0.015:3thr=0.015:range=16:blur=1".to_string());
+    }
 
-    if hdr_type == HdrType::DolbyVision {
+    let pixel_format = if params.eight_bit {
+        "yuv420p"
+    } else {
+        "yuv420p10le"
+    };
+    filters.push(format!("format={}", pixel_format));
+
+    if params.hdr_type == HdrType::DolbyVision {
         filters.push(
             "setparams=colorspace=bt2020nc:color_primaries=bt2020:color_trc=smpte2084".to_string(),
         );
@@ -228,15 +904,3 @@ fn build_video_filter(hdr_type: HdrType) -> String {
 
     filters.join(",")
 }
-
-/// Dolby Vision color metadata parameters (filter is handled in build_video_filter)
-fn get_dolby_vision_color_params() -> Vec<String> {
-    vec![
-        "-color_primaries".to_string(),
-        "bt2020".to_string(),
-        "-color_trc".to_string(),
-        "smpte2084".to_string(),
-        "-colorspace".to_string(),
-        "bt2020nc".to_string(),
-    ]
-}
@@ -0,0 +1,151 @@
+//! Guard rails around deleting a source once its output passed.
+//!
+//! A source only goes when the output validated, has a plausible size and
+//! still reads back from its folder. It is moved to the trash unless
+//! configured otherwise, and every removal is appended to a log so a session
+//! can be traced and undone from the trash.
+
+use super::transfer;
+use crate::audit::CodecFamily;
+use crate::config::DeleteMode;
+use crate::error::AppError;
+use crate::utils::{data_dir, format_file_size, move_to_trash};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Source removed after a passing encode, appended to the deletion log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletionRecord {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub source: PathBuf,
+    pub output: PathBuf,
+    pub size: Option<u64>,
+    /// Moved to the trash rather than deleted for good
+    pub trashed: bool,
+    pub vmaf: f64,
+}
+
+/// Location of the deletion log
+pub fn deletion_log_path() -> PathBuf {
+    data_dir().join("deleted_sources.jsonl")
+}
+
+/// Why `source` has to stay although its output passed, or `None` when it may go
+///
+/// The output must have validated and be at least `min_size_ratio` of the size
+/// typical for an AV1 encode of `source_codec`. On another filesystem than the
+/// source it must also still read back, so a mount that dropped out or went
+/// stale never costs the only copy.
+pub fn deletion_blocker(
+    source: &Path,
+    output: &Path,
+    validated: bool,
+    source_codec: &str,
+    min_size_ratio: f64,
+) -> Option<String> {
+    if !validated {
+        return Some("the output could not be validated".to_string());
+    }
+    let output_size = match std::fs::metadata(output) {
+        Ok(m) => m.len(),
+        Err(e) => return Some(format!("the output can't be read ({})", e)),
+    };
+    if let Ok(source_meta) = std::fs::metadata(source) {
+        let expected =
+            source_meta.len() as f64 * CodecFamily::from_codec(source_codec).size_ratio();
+        if (output_size as f64) < expected * min_size_ratio {
+            return Some(format!(
+                "the output is only {} where ~{} was expected",
+                format_file_size(output_size),
+                format_file_size(expected as u64)
+            ));
+        }
+    }
+    if !same_filesystem(source, output)
+        && let Err(e) = transfer::read_tail(output)
+    {
+        return Some(format!("the output's mount is failing ({})", e));
+    }
+    None
+}
+
+/// Remove a source, to the trash or for good, and log it
+///
+/// Returns whether it went to the trash. Without a trash the source stays.
+pub fn remove_source(
+    source: &Path,
+    output: &Path,
+    mode: DeleteMode,
+    vmaf: f64,
+) -> Result<bool, AppError> {
+    let size = std::fs::metadata(source).ok().map(|m| m.len());
+    let trashed = match mode {
+        DeleteMode::Trash => {
+            move_to_trash(source)?;
+            true
+        }
+        DeleteMode::Permanent => {
+            std::fs::remove_file(source).map_err(|e| AppError::Io {
+                path: source.to_path_buf(),
+                operation: "delete source",
+                message: e.to_string(),
+            })?;
+            false
+        }
+    };
+
+    let record = DeletionRecord {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        source: source.to_path_buf(),
+        output: output.to_path_buf(),
+        size,
+        trashed,
+        vmaf,
+    };
+    if let Err(e) = append_record(&record) {
+        warn!("Failed to log deletion of {}: {}", source.display(), e);
+    }
+    Ok(trashed)
+}
+
+fn append_record(record: &DeletionRecord) -> Result<(), AppError> {
+    let path = deletion_log_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Check if two existing files are on the same filesystem
+fn same_filesystem(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::metadata(a), std::fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        // Same drive letter or UNC share
+        let root = |p: &Path| {
+            std::path::absolute(p)
+                .ok()
+                .and_then(|p| p.components().next().map(|c| c.as_os_str().to_os_string()))
+        };
+        root(a).is_some() && root(a) == root(b)
+    }
+}
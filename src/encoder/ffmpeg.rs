@@ -1,14 +1,26 @@
 use crate::encoder::command_builder::{EncodingParams, build_ffmpeg_args};
-use std::fs::File;
-use std::path::Path;
-use std::process::{Child, Command, Stdio};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::thread;
-use std::time::Duration;
+use crate::error::AppError;
+use crate::utils::tools::{self, shell_quote};
+use crate::utils::{crash, suspend};
+use std::collections::VecDeque;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{ChildStderr, Command};
+use tokio_util::sync::CancellationToken;
 
 /// Progress callback type
-pub type ProgressCallback = Box<dyn FnMut(f32) + Send>;
+pub type ProgressCallback = Box<dyn FnMut(EncodeProgress) + Send>;
+
+/// Progress snapshot reported while encoding
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EncodeProgress {
+    /// Completion percentage (0-100)
+    pub percent: f32,
+    /// Encoding speed relative to realtime (e.g. 1.5 = 1.5x)
+    pub speed: Option<f32>,
+    /// Current output bitrate in kbit/s
+    pub bitrate_kbps: Option<f32>,
+}
 
 /// Encoding result
 #[derive(Debug)]
@@ -18,143 +30,164 @@ pub enum EncodeResult {
     /// Encoding was cancelled
     Cancelled,
     /// Encoding failed
-    Error(String),
+    Error(AppError),
 }
 
+/// Number of trailing stderr lines included in error messages
+const STDERR_TAIL_LINES: usize = 5;
+
 /// Encode a video file using FFmpeg
-pub fn encode_video(
+pub async fn encode_video(
     params: &EncodingParams,
-    progress_callback: Option<ProgressCallback>,
-    cancel_flag: Arc<AtomicBool>,
+    mut progress_callback: Option<ProgressCallback>,
+    cancel: CancellationToken,
     duration: f64,
 ) -> EncodeResult {
     let args = build_ffmpeg_args(params);
-
-    // Create progress file
-    let progress_file =
-        std::env::temp_dir().join(format!("ffmpeg_progress_{}", std::process::id()));
-    if File::create(&progress_file).is_err() {
-        return EncodeResult::Error("Failed to create progress file".to_string());
-    }
-
-    // Insert progress args after -nostdin
+    let quoted: Vec<String> = args.iter().map(|a| shell_quote(a)).collect();
+    crash::record_command(format!(
+        "{} {}",
+        shell_quote(&tools::ffmpeg()),
+        quoted.join(" ")
+    ));
+
+    // Stream progress over stdout instead of polling a file
     let mut args = args;
     args.insert(2, "-progress".to_string());
-    args.insert(3, progress_file.to_string_lossy().to_string());
-
-    // Redirect stderr to a temp file to avoid pipe buffer deadlock
-    let stderr_path = std::env::temp_dir().join(format!("ffmpeg_stderr_{}", std::process::id()));
-    let stderr_file = match File::create(&stderr_path) {
-        Ok(f) => f,
-        Err(e) => {
-            let _ = std::fs::remove_file(&progress_file);
-            return EncodeResult::Error(format!("Failed to create stderr file: {}", e));
-        }
-    };
+    args.insert(3, "pipe:1".to_string());
 
     // Start FFmpeg
-    let mut child = match Command::new("ffmpeg")
+    let mut child = match Command::new(tools::ffmpeg())
         .args(&args)
-        .stdout(Stdio::null())
-        .stderr(Stdio::from(stderr_file))
+        .envs(params.env.iter().map(|(k, v)| (k, v)))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
         .spawn()
     {
         Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return EncodeResult::Error(AppError::DependencyMissing("ffmpeg".to_string()));
+        }
         Err(e) => {
-            let _ = std::fs::remove_file(&progress_file);
-            let _ = std::fs::remove_file(&stderr_path);
-            return EncodeResult::Error(format!("Failed to start ffmpeg: {}", e));
+            return EncodeResult::Error(AppError::encode(format!("Failed to start ffmpeg: {}", e)));
         }
     };
 
-    // Run encoding loop
-    let result = run_encode_loop(
-        &mut child,
-        &progress_file,
-        duration,
-        progress_callback,
-        cancel_flag,
-        &params.output,
-        &stderr_path,
-    );
-
-    // Cleanup
-    let _ = std::fs::remove_file(&progress_file);
-    let _ = std::fs::remove_file(&stderr_path);
-
-    result
-}
+    // Stopped while the queue is paused
+    let _suspendable = suspend::track(child.id());
 
-/// Run the encoding loop with progress updates
-fn run_encode_loop(
-    child: &mut Child,
-    progress_file: &Path,
-    duration: f64,
-    mut progress_callback: Option<ProgressCallback>,
-    cancel_flag: Arc<AtomicBool>,
-    output: &str,
-    stderr_path: &Path,
-) -> EncodeResult {
-    loop {
-        // Check cancellation
-        if cancel_flag.load(Ordering::Relaxed) {
-            let _ = child.kill();
-            let _ = child.wait();
-            let _ = std::fs::remove_file(output);
-            return EncodeResult::Cancelled;
-        }
+    // Drain stderr concurrently so a full pipe never blocks ffmpeg
+    let stderr_tail = child
+        .stderr
+        .take()
+        .map(|stderr| tokio::spawn(read_stderr_tail(stderr)));
+
+    let Some(stdout) = child.stdout.take() else {
+        return EncodeResult::Error(AppError::encode("Failed to capture ffmpeg output"));
+    };
+    let mut lines = BufReader::new(stdout).lines();
+    let mut block = ProgressBlock::default();
 
-        // Read progress
-        if let Ok(content) = std::fs::read_to_string(progress_file) {
-            let mut latest_time_us: Option<f64> = None;
-            for line in content.lines() {
-                if let Some(value) = line.strip_prefix("out_time_us=")
-                    && let Ok(time_us) = value.trim().parse::<f64>()
-                    && time_us > 0.0
+    // Report progress until ffmpeg closes its output or the job is cancelled
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else {
+                    break;
+                };
+                if parse_progress_line(&line, &mut block)
+                    && let Some(time_secs) = block.time_secs
+                    && duration > 0.0
+                    && let Some(ref mut cb) = progress_callback
                 {
-                    latest_time_us = Some(time_us);
+                    cb(EncodeProgress {
+                        percent: (time_secs / duration * 100.0).min(100.0) as f32,
+                        speed: block.speed,
+                        bitrate_kbps: block.bitrate_kbps,
+                    });
                 }
             }
-
-            if let Some(time_us) = latest_time_us {
-                let time_secs = time_us / 1_000_000.0;
-                if duration > 0.0 {
-                    let progress = (time_secs / duration * 100.0).min(100.0) as f32;
-                    if let Some(ref mut cb) = progress_callback {
-                        cb(progress);
-                    }
-                }
+            _ = cancel.cancelled() => {
+                let _ = child.kill().await;
+                let _ = tokio::fs::remove_file(&params.output).await;
+                return EncodeResult::Cancelled;
             }
         }
+    }
 
-        // Check if FFmpeg finished
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                if !status.success() {
-                    let stderr = std::fs::read_to_string(stderr_path).unwrap_or_default();
-
-                    let _ = std::fs::remove_file(output);
-
-                    let error_msg = if stderr.is_empty() {
-                        format!("ffmpeg failed with status: {}", status)
-                    } else {
-                        let last_lines: Vec<&str> = stderr.lines().rev().take(5).collect();
-                        format!(
-                            "ffmpeg failed: {}",
-                            last_lines.into_iter().rev().collect::<Vec<_>>().join("\n")
-                        )
-                    };
-
-                    return EncodeResult::Error(error_msg);
-                }
-                return EncodeResult::Success;
-            }
-            Ok(None) => {
-                thread::sleep(Duration::from_millis(250));
-            }
-            Err(e) => {
-                return EncodeResult::Error(format!("Failed to check ffmpeg status: {}", e));
+    let status = match child.wait().await {
+        Ok(status) => status,
+        Err(e) => {
+            return EncodeResult::Error(AppError::encode(format!(
+                "Failed to check ffmpeg status: {}",
+                e
+            )));
+        }
+    };
+    if status.success() {
+        return EncodeResult::Success;
+    }
+
+    let _ = tokio::fs::remove_file(&params.output).await;
+    let stderr = match stderr_tail {
+        Some(handle) => handle.await.unwrap_or_default(),
+        None => Vec::new(),
+    };
+    if stderr.is_empty() {
+        EncodeResult::Error(AppError::encode(format!(
+            "ffmpeg failed with status: {}",
+            status
+        )))
+    } else {
+        EncodeResult::Error(AppError::encode(format!(
+            "ffmpeg failed: {}",
+            stderr.join("\n")
+        )))
+    }
+}
+
+/// Values of one `-progress` block
+#[derive(Debug, Default)]
+struct ProgressBlock {
+    time_secs: Option<f64>,
+    speed: Option<f32>,
+    bitrate_kbps: Option<f32>,
+}
+
+/// Apply one `key=value` progress line, returning true at the end of a block
+fn parse_progress_line(line: &str, block: &mut ProgressBlock) -> bool {
+    let Some((key, value)) = line.split_once('=') else {
+        return false;
+    };
+    let value = value.trim();
+
+    match key {
+        "out_time_us" => {
+            if let Ok(time_us) = value.parse::<f64>()
+                && time_us > 0.0
+            {
+                block.time_secs = Some(time_us / 1_000_000.0);
             }
         }
+        // "N/A" until the first frames are out
+        "speed" => block.speed = value.trim_end_matches('x').parse().ok(),
+        "bitrate" => block.bitrate_kbps = value.trim_end_matches("kbits/s").parse().ok(),
+        "progress" => return true,
+        _ => {}
+    }
+    false
+}
+
+/// Keep the last lines ffmpeg writes to stderr
+async fn read_stderr_tail(stderr: ChildStderr) -> Vec<String> {
+    let mut tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+    let mut lines = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if tail.len() == STDERR_TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
     }
+    tail.into()
 }
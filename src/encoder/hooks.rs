@@ -0,0 +1,150 @@
+//! External programs hooked into the pipeline.
+//!
+//! Each `[[hooks]]` entry of the config runs at its point of every encode,
+//! e.g. to tag the output with mkvpropedit or to upscale it, without changes
+//! to this crate. A hook gets the job as one JSON object on stdin and answers
+//! on stdout with a verdict: nothing or `{"verdict": "continue"}` lets the job
+//! go on, `{"verdict": "keep_source", "reason": "…"}` keeps the source even if
+//! the output passes, and `{"verdict": "fail", "reason": "…"}` fails the job.
+//! A hook that exits non-zero or runs past its timeout fails the job too, and
+//! cancelling the job while a hook runs stops it there.
+
+use crate::config::{Encoder, HookConfig, HookPoint};
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// What a hook is told about the job
+#[derive(Debug, Clone, Serialize)]
+pub struct HookJob<'a> {
+    /// Point of the pipeline, e.g. `after_encode`
+    pub hook: &'static str,
+    pub input: &'a str,
+    /// Where the output is written; it exists from `after_encode` on
+    pub output: &'a str,
+    pub encoder: Encoder,
+    /// Quality value (CRF/CQ) of the encode
+    pub quality: u8,
+    /// e.g. `HDR10`
+    pub hdr: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub duration_secs: f64,
+    /// VMAF score, at `after_verify` when it was measured
+    pub vmaf: Option<f64>,
+}
+
+/// Answer of a hook
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "verdict", rename_all = "snake_case")]
+pub enum Verdict {
+    Continue,
+    KeepSource {
+        #[serde(default)]
+        reason: String,
+    },
+    Fail {
+        #[serde(default)]
+        reason: String,
+    },
+}
+
+impl Verdict {
+    /// Verdict printed by a hook; an empty output continues
+    pub fn parse(stdout: &str) -> Result<Self, AppError> {
+        let stdout = stdout.trim();
+        if stdout.is_empty() {
+            return Ok(Verdict::Continue);
+        }
+        serde_json::from_str(stdout).map_err(|e| AppError::Parse {
+            context: "hook verdict".to_string(),
+            message: e.to_string(),
+        })
+    }
+}
+
+/// Run the hooks configured for `job.hook`'s point, in order
+///
+/// Returns why the source has to stay if a hook asked for it, or the error of
+/// the first hook failing the job; `AppError::Cancelled` when the job was
+/// cancelled meanwhile.
+pub async fn run_hooks(
+    hooks: &[HookConfig],
+    at: HookPoint,
+    job: &HookJob<'_>,
+    cancel: &CancellationToken,
+) -> Result<Option<String>, AppError> {
+    let mut keep_source = None;
+    for hook in hooks.iter().filter(|h| h.at == at) {
+        match run_hook(hook, job, cancel).await? {
+            Verdict::Continue => {}
+            Verdict::KeepSource { reason } => {
+                let reason = if reason.is_empty() {
+                    format!("kept by hook {}", hook.command)
+                } else {
+                    reason
+                };
+                info!("Hook {} keeps the source: {}", hook.command, reason);
+                keep_source = Some(reason);
+            }
+            Verdict::Fail { reason } => {
+                return Err(AppError::CommandExecution(format!(
+                    "Hook {} failed the job: {}",
+                    hook.command,
+                    if reason.is_empty() {
+                        "no reason given"
+                    } else {
+                        &reason
+                    }
+                )));
+            }
+        }
+    }
+    Ok(keep_source)
+}
+
+async fn run_hook(
+    hook: &HookConfig,
+    job: &HookJob<'_>,
+    cancel: &CancellationToken,
+) -> Result<Verdict, AppError> {
+    let failed =
+        |message: String| AppError::CommandExecution(format!("Hook {} {}", hook.command, message));
+    info!("Running {} hook {}", job.hook, hook.command);
+    let mut child = Command::new(&hook.command)
+        .args(&hook.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| failed(format!("could not be started: {}", e)))?;
+
+    let body = serde_json::to_vec(job)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that doesn't read the job closes its end early
+        let _ = stdin.write_all(&body).await;
+    }
+
+    let timeout = Duration::from_secs(hook.timeout_secs);
+    let output = tokio::select! {
+        output = tokio::time::timeout(timeout, child.wait_with_output()) => match output {
+            Ok(output) => output.map_err(|e| failed(format!("failed: {}", e)))?,
+            Err(_) => return Err(failed(format!("timed out after {}s", hook.timeout_secs))),
+        },
+        _ = cancel.cancelled() => return Err(AppError::Cancelled),
+    };
+    if !output.status.success() {
+        return Err(failed(format!(
+            "exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Verdict::parse(&String::from_utf8_lossy(&output.stdout))
+}
@@ -0,0 +1,206 @@
+use crate::analyzer::HdrType;
+use crate::encoder::provenance::matroska_tags_xml;
+use crate::error::AppError;
+use crate::tracks::TrackSelection;
+use crate::utils::tools;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+/// Intermediate video-only file used when mkvmerge assembles the output
+pub fn intermediate_video_path(output: &Path) -> PathBuf {
+    output.with_extension("video.ivf")
+}
+
+/// Assemble the final MKV from an encoded video stream and the source's other tracks
+///
+/// Chapters, attachments and tags are carried over from the source by mkvmerge.
+pub fn mux_with_mkvmerge(
+    video: &Path,
+    source: &Path,
+    output: &Path,
+    tracks: &TrackSelection,
+    hdr_type: HdrType,
+    tags: &[(String, String)],
+) -> Result<(), AppError> {
+    let source_tracks = identify_tracks(source)?;
+
+    let audio_ids: Vec<u64> = source_tracks
+        .iter()
+        .filter(|t| t.track_type == "audio")
+        .map(|t| t.id)
+        .collect();
+    let subtitle_ids: Vec<u64> = source_tracks
+        .iter()
+        .filter(|t| t.track_type == "subtitles")
+        .map(|t| t.id)
+        .collect();
+
+    // Same semantics as the ffmpeg mapping: an empty selection keeps everything
+    let keep_all = tracks.keeps_all();
+    let (selected_audio, selected_subs): (Vec<u64>, Vec<u64>) = if keep_all {
+        (audio_ids.clone(), subtitle_ids.clone())
+    } else {
+        (
+            tracks
+                .audio_indices
+                .iter()
+                .filter_map(|&i| audio_ids.get(i).copied())
+                .collect(),
+            tracks
+                .subtitle_indices
+                .iter()
+                .filter_map(|&i| subtitle_ids.get(i).copied())
+                .collect(),
+        )
+    };
+
+    let mut args = vec!["-o".to_string(), output.to_string_lossy().to_string()];
+
+    // Tags go in through a Matroska tags file next to the output
+    let tags_file = output.with_extension("tags.xml");
+    if !tags.is_empty() {
+        std::fs::write(&tags_file, matroska_tags_xml(tags)).map_err(|e| AppError::Io {
+            path: tags_file.clone(),
+            operation: "write mkvmerge tags",
+            message: e.to_string(),
+        })?;
+        args.extend([
+            "--global-tags".to_string(),
+            tags_file.to_string_lossy().to_string(),
+        ]);
+    }
+
+    // Color metadata for the video track (file 0, track 0)
+    args.extend(get_colour_args(hdr_type));
+    args.push(video.to_string_lossy().to_string());
+
+    args.push("--no-video".to_string());
+    if selected_audio.is_empty() {
+        args.push("--no-audio".to_string());
+    } else {
+        args.extend(["--audio-tracks".to_string(), join_ids(&selected_audio)]);
+        // The first track becomes the default, whatever the source flagged
+        if tracks.audio_reordered() {
+            for (i, id) in selected_audio.iter().enumerate() {
+                let flag = if i == 0 { 1 } else { 0 };
+                args.extend([
+                    "--default-track-flag".to_string(),
+                    format!("{}:{}", id, flag),
+                ]);
+            }
+        }
+    }
+    if selected_subs.is_empty() {
+        args.push("--no-subtitles".to_string());
+    } else {
+        args.extend(["--subtitle-tracks".to_string(), join_ids(&selected_subs)]);
+    }
+    args.push(source.to_string_lossy().to_string());
+
+    let mut order = vec!["0:0".to_string()];
+    order.extend(
+        selected_audio
+            .iter()
+            .chain(selected_subs.iter())
+            .map(|id| format!("1:{}", id)),
+    );
+    args.extend(["--track-order".to_string(), order.join(",")]);
+
+    info!("Muxing with mkvmerge: {}", output.display());
+
+    let result = Command::new(tools::mkvmerge()).args(&args).output();
+    if !tags.is_empty() {
+        let _ = std::fs::remove_file(&tags_file);
+    }
+    let result =
+        result.map_err(|e| AppError::CommandExecution(format!("Failed to run mkvmerge: {}", e)))?;
+
+    // mkvmerge exits with 1 when it finished with warnings
+    match result.status.code() {
+        Some(0) => Ok(()),
+        Some(1) => {
+            warn!(
+                "mkvmerge finished with warnings: {}",
+                String::from_utf8_lossy(&result.stdout).trim()
+            );
+            Ok(())
+        }
+        _ => {
+            let _ = std::fs::remove_file(output);
+            Err(AppError::CommandExecution(format!(
+                "mkvmerge failed: {}",
+                last_lines(&String::from_utf8_lossy(&result.stdout), 5)
+            )))
+        }
+    }
+}
+
+/// List the source tracks with their mkvmerge track IDs
+fn identify_tracks(source: &Path) -> Result<Vec<MkvTrack>, AppError> {
+    let output = Command::new(tools::mkvmerge())
+        .arg("-J")
+        .arg(source)
+        .output()
+        .map_err(|e| AppError::CommandExecution(format!("Failed to run mkvmerge: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::CommandExecution(format!(
+            "mkvmerge could not identify {}",
+            source.display()
+        )));
+    }
+
+    let info: MkvIdentify =
+        serde_json::from_slice(&output.stdout).map_err(|e| AppError::Parse {
+            context: "mkvmerge identify".to_string(),
+            message: e.to_string(),
+        })?;
+
+    Ok(info.tracks)
+}
+
+fn get_colour_args(hdr_type: HdrType) -> Vec<String> {
+    // ITU-T H.273 code points: BT.2020 primaries/matrix, PQ (16) or HLG (18) transfer
+    let transfer = match hdr_type {
+        HdrType::Sdr => return Vec::new(),
+        HdrType::Hlg => 18,
+        HdrType::Pq | HdrType::DolbyVision => 16,
+    };
+
+    vec![
+        "--colour-primaries".to_string(),
+        "0:9".to_string(),
+        "--colour-transfer-characteristics".to_string(),
+        format!("0:{}", transfer),
+        "--colour-matrix-coefficients".to_string(),
+        "0:9".to_string(),
+    ]
+}
+
+fn join_ids(ids: &[u64]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn last_lines(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.lines().rev().take(count).collect();
+    lines.into_iter().rev().collect::<Vec<_>>().join("\n")
+}
+
+// JSON deserialization structures
+
+#[derive(Debug, Deserialize)]
+struct MkvIdentify {
+    tracks: Vec<MkvTrack>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MkvTrack {
+    id: u64,
+    #[serde(rename = "type")]
+    track_type: String,
+}
@@ -1,17 +1,59 @@
+pub mod capability;
+pub mod chunked;
 pub mod command_builder;
+pub mod deletion;
 pub mod ffmpeg;
+pub mod hooks;
+pub mod mkvmerge;
+pub mod provenance;
+pub mod remux;
+pub mod simulate;
+pub mod stage;
+pub mod transfer;
 
-pub use command_builder::EncodingParams;
-pub use ffmpeg::{EncodeResult, ProgressCallback, encode_video};
+pub use command_builder::{
+    AudioTranscode, EncodeOverrides, EncodingParams, JobMode, StreamHandling, banding_prone,
+    fit_within, preset_quality, qsv_device_args,
+};
+pub use ffmpeg::{EncodeProgress, EncodeResult, ProgressCallback, encode_video};
+pub use provenance::EncodeProvenance;
+pub use remux::{RemuxResult, mux_video_with_source, remux_output};
+pub use simulate::{SimulatedEncode, ffmpeg_command};
+pub use stage::{Stage, StageTimings, timed, timed_async, timed_blocking};
 
-use crate::analyzer::{HdrType, VideoMetadata};
-use crate::config::AppConfig;
+use crate::analyzer::{FilmGrainParams, HdrType, VideoMetadata};
+use crate::config::{AppConfig, Encoder, HookPoint, Muxer, SharedConfig};
+use crate::error::AppError;
 use crate::tracks::TrackSelection;
+use crate::utils::DependencyStatus;
+use crate::utils::deps::Feature;
 use crate::verifier;
-use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
+use capability::HardwareOptions;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
+/// Intermediate events reported by the pipeline alongside progress
+#[derive(Debug, Clone)]
+pub enum PipelineEvent {
+    /// Output was remuxed to rebuild container indexes
+    Remuxed(RemuxResult),
+    /// A pipeline stage finished after the given wall-clock time
+    StageFinished(Stage, Duration),
+    /// Output streams that drifted or do not match the selection; the source is kept
+    ValidationFailed(Vec<verifier::ValidationIssue>),
+    /// The hardware encoder can't encode this format, so SVT-AV1 is used instead
+    EncoderFallback { from: Encoder, reason: String },
+    /// The output passed but the source stays, and why
+    SourceKept(String),
+    /// How the output is produced, as embedded in its container
+    Provenance(EncodeProvenance),
+}
+
+/// Pipeline event callback type
+pub type EventCallback = Box<dyn FnMut(PipelineEvent) + Send>;
+
 /// Full encoding result including VMAF
 #[derive(Debug)]
 pub enum FullEncodeResult {
@@ -25,57 +67,448 @@ pub enum FullEncodeResult {
     /// Encoding was cancelled
     Cancelled,
     /// Encoding failed
-    Error(String),
+    Error(AppError),
     /// Quality below threshold
     QualityWarning {
         vmaf: verifier::VmafResult,
         threshold: f64,
     },
+    /// Nothing was encoded; what the encode would have done
+    Simulated(SimulatedEncode),
 }
 
 /// Orchestrate the full encoding pipeline: CRF search -> encode -> verify
+///
+/// Encoding uses the configuration as it is when the file starts; quality
+/// settings are read again right before verification.
 #[allow(clippy::too_many_arguments)]
-pub fn run_encoding_pipeline(
+pub async fn run_encoding_pipeline(
     input: &str,
     output: &str,
     metadata: &VideoMetadata,
     tracks: TrackSelection,
-    config: &AppConfig,
+    overrides: &EncodeOverrides,
+    shared: &SharedConfig,
     progress_callback: Option<ProgressCallback>,
-    cancel_flag: Arc<AtomicBool>,
+    mut event_callback: Option<EventCallback>,
+    cancel: CancellationToken,
 ) -> FullEncodeResult {
+    let snapshot = shared.snapshot();
+    let config = &snapshot;
+
+    if overrides.simulate {
+        let simulated = simulate::simulate_encode(
+            input,
+            output,
+            metadata,
+            tracks,
+            overrides,
+            config,
+            progress_callback,
+            cancel,
+        )
+        .await;
+        return simulated.map_or(FullEncodeResult::Cancelled, FullEncodeResult::Simulated);
+    }
+
+    // On a network mount, encode locally and copy the result over once verified
+    let destination = PathBuf::from(output);
+    let staged = transfer::staging_path(&destination, config);
+    let staged_output = staged.as_ref().map(|p| p.to_string_lossy().to_string());
+    let output = staged_output.as_deref().unwrap_or(output);
+    if let Some(ref staged) = staged {
+        info!(
+            "{} is on a network mount, encoding to {}",
+            destination.display(),
+            staged.display()
+        );
+    }
+    // Removes the staged output on every way out before the copy
+    let _staged_guard = staged.clone().map(transfer::StagedGuard::new);
+
+    // Outputs in another directory may go into folders that don't exist yet
+    for dir in [Some(destination.as_path()), staged.as_deref()]
+        .into_iter()
+        .flatten()
+        .filter_map(Path::parent)
+    {
+        if !dir.as_os_str().is_empty()
+            && let Err(e) = std::fs::create_dir_all(dir)
+        {
+            return FullEncodeResult::Error(AppError::Io {
+                path: dir.to_path_buf(),
+                operation: "create output folder",
+                message: e.to_string(),
+            });
+        }
+    }
+
     // Encoding parameters
-    let params = EncodingParams::from_metadata(input, output, metadata, config, tracks);
+    let mut params = EncodingParams::from_metadata(input, output, metadata, config, tracks);
+    params.apply_overrides(overrides);
     let duration = metadata.duration_secs;
 
+    // Make sure the hardware encoder handles this size and bit depth before committing to it
+    let prepare_start = Instant::now();
+    let hardware_checked = params.encoder != Encoder::SvtAv1 && !params.copy_video;
+    if hardware_checked {
+        let (width, height) = params.output_dimensions(metadata.width, metadata.height);
+        let (encoder, ten_bit, env) = (params.encoder, !params.eight_bit, params.env.clone());
+        let options = match encoder {
+            Encoder::Qsv => HardwareOptions {
+                device_args: qsv_device_args(params.qsv_device.as_deref()),
+                low_power: params.qsv_low_power,
+            },
+            _ => HardwareOptions::default(),
+        };
+        let supported = tokio::task::spawn_blocking(move || {
+            capability::check_support(encoder, width, height, ten_bit, &options, &env)
+        })
+        .await
+        .unwrap_or(Ok(()));
+        if let Err(reason) = supported {
+            let format = format!("{}x{} {}-bit", width, height, if ten_bit { 10 } else { 8 });
+            if !config.performance.hardware_fallback {
+                return FullEncodeResult::Error(AppError::encode(format!(
+                    "{} cannot encode {} AV1: {}",
+                    encoder.display_name(),
+                    format,
+                    reason
+                )));
+            }
+            warn!(
+                "{} cannot encode {} AV1 ({}), using SVT-AV1",
+                encoder.display_name(),
+                format,
+                reason
+            );
+            // The preset's software quality, since hardware scales differ
+            let software = AppConfig {
+                encoder: Encoder::SvtAv1,
+                ..config.clone()
+            };
+            params.encoder = Encoder::SvtAv1;
+            params.crf = overrides.quality(preset_quality(metadata, &software));
+            emit(
+                &mut event_callback,
+                PipelineEvent::EncoderFallback {
+                    from: encoder,
+                    reason: format!("{}: {}", format, reason),
+                },
+            );
+        }
+    }
+
+    // Options the installed SVT-AV1 doesn't know would fail the encode
+    let deps = DependencyStatus::current();
+    params.apply_toolchain(&deps);
+
+    // Reuse the grain an AV1 source signals instead of re-estimating it
+    let grain_table = match overrides.source_grain {
+        Some(ref grain)
+            if params.encoder == Encoder::SvtAv1
+                && !params.copy_video
+                && config.quality.film_grain_passthrough
+                && deps.supports(Feature::FilmGrainTable) =>
+        {
+            attach_grain_table(&mut params, grain, Path::new(output))
+        }
+        _ => None,
+    };
+    if hardware_checked || grain_table.is_some() {
+        emit(
+            &mut event_callback,
+            PipelineEvent::StageFinished(Stage::Prepare, prepare_start.elapsed()),
+        );
+    }
+
+    // With mkvmerge or chunking, ffmpeg first produces only the video stream;
+    // copying the video takes a single pass
+    let use_mkvmerge = !params.copy_video && wants_mkvmerge(config, output, &params.streams);
+    let chunk_secs = config.performance.chunk_duration_secs as f64;
+    let chunked =
+        !params.copy_video && config.performance.chunked_encoding && duration > chunk_secs;
+    if use_mkvmerge || chunked {
+        params.output = mkvmerge::intermediate_video_path(Path::new(output))
+            .to_string_lossy()
+            .to_string();
+        params.video_only = true;
+    }
+
+    // Record how the output is made, in the container and for the report
+    let provenance = EncodeProvenance::of(&params, &deps);
+    if config.output.embed_provenance {
+        params.tags = provenance.tags();
+    }
+    emit(&mut event_callback, PipelineEvent::Provenance(provenance));
+
+    let hook_cancel = cancel.clone();
+    let hook_job = |at: HookPoint, vmaf: Option<f64>| hooks::HookJob {
+        hook: at.name(),
+        input,
+        output,
+        encoder: params.encoder,
+        quality: params.crf,
+        hdr: metadata.hdr_type.display_string(),
+        width: metadata.width,
+        height: metadata.height,
+        duration_secs: duration,
+        vmaf,
+    };
+    let run_hooks = |at: HookPoint, vmaf: Option<f64>| {
+        let job = hook_job(at, vmaf);
+        let (hooks, cancel) = (&config.hooks, &hook_cancel);
+        async move { hooks::run_hooks(hooks, at, &job, cancel).await }
+    };
+    let mut kept_by_hook = match run_hooks(HookPoint::BeforeEncode, None).await {
+        Ok(kept) => kept,
+        Err(e) => return hook_failed(e, None),
+    };
+
     // Encode
-    let encode_result = encode_video(&params, progress_callback, cancel_flag, duration);
+    let (encode_result, elapsed) = timed_async(Stage::Encode, async {
+        if chunked {
+            chunked::encode_chunked(
+                &params,
+                &chunked::chunk_dir(Path::new(output)),
+                chunk_secs,
+                duration,
+                progress_callback,
+                cancel,
+            )
+            .await
+        } else {
+            encode_video(&params, progress_callback, cancel, duration).await
+        }
+    })
+    .await;
+    emit(
+        &mut event_callback,
+        PipelineEvent::StageFinished(Stage::Encode, elapsed),
+    );
+    if let Some(table) = grain_table {
+        let _ = std::fs::remove_file(table);
+    }
 
     match encode_result {
         EncodeResult::Success => {
+            // Assemble the final container and rebuild its indexes
+            if params.video_only || config.output.remux_after_encode {
+                let video_only = params.video_only;
+                let video = PathBuf::from(&params.output);
+                let (source, output_path) = (PathBuf::from(input), PathBuf::from(output));
+                let track_selection = params.tracks.clone();
+                let streams = params.streams.clone();
+                let (hdr_type, remux) = (metadata.hdr_type, config.output.remux_after_encode);
+                let tags = params.tags.clone();
+                let (muxed, elapsed) =
+                    timed_blocking(Stage::Mux, move || -> Result<_, AppError> {
+                        if video_only {
+                            let muxed = if use_mkvmerge {
+                                mkvmerge::mux_with_mkvmerge(
+                                    &video,
+                                    &source,
+                                    &output_path,
+                                    &track_selection,
+                                    hdr_type,
+                                    &tags,
+                                )
+                            } else {
+                                mux_video_with_source(
+                                    &video,
+                                    &source,
+                                    &output_path,
+                                    &track_selection,
+                                    &streams,
+                                    hdr_type,
+                                    &tags,
+                                )
+                            };
+                            let _ = std::fs::remove_file(&video);
+                            muxed?;
+                        }
+
+                        if !remux {
+                            return Ok(None);
+                        }
+                        match remux_output(&output_path) {
+                            Ok(remux) => Ok(Some(remux)),
+                            Err(e) => {
+                                warn!("Remux failed, keeping encoder output: {}", e);
+                                Ok(None)
+                            }
+                        }
+                    })
+                    .await;
+                emit(
+                    &mut event_callback,
+                    PipelineEvent::StageFinished(Stage::Mux, elapsed),
+                );
+
+                match muxed {
+                    Ok(Some(remux)) => emit(&mut event_callback, PipelineEvent::Remuxed(remux)),
+                    Ok(None) => {}
+                    Err(e) => return FullEncodeResult::Error(e),
+                }
+            }
+
+            match run_hooks(HookPoint::AfterEncode, None).await {
+                Ok(kept) => kept_by_hook = kept_by_hook.or(kept),
+                Err(e) => return hook_failed(e, Some(output)),
+            }
+
             // Verify
-            let vmaf_threshold = if config.quality.vmaf_enabled {
-                Some(config.quality.vmaf_threshold)
+            let frame_rate = if params.frame_rate_den > 0 {
+                params.frame_rate_num as f64 / params.frame_rate_den as f64
             } else {
-                None
+                0.0
             };
-            let result = run_vmaf_check(
-                input,
-                output,
-                vmaf_threshold,
-                metadata.hdr_type,
-                metadata.width,
-            );
+            let quality = shared.quality();
+            let verifying = quality.vmaf_enabled
+                || quality.sync_check
+                || quality.hdr_check
+                || quality.defect_check;
+            if verifying {
+                match run_hooks(HookPoint::BeforeVerify, None).await {
+                    Ok(kept) => kept_by_hook = kept_by_hook.or(kept),
+                    Err(e) => return hook_failed(e, Some(output)),
+                }
+            }
+            let (issues, validated, result) = if verifying {
+                let (input, output) = (input.to_string(), output.to_string());
+                let track_selection = params.tracks.clone();
+                let drop_subtitles = params.streams.drop_subtitles;
+                let input_frame_rate = params.input_frame_rate;
+                let copy_video = params.copy_video;
+                let (hdr_type, width, height) =
+                    (metadata.hdr_type, metadata.width, metadata.height);
+                let (verified, elapsed) = timed_blocking(Stage::Verify, move || {
+                    let (issues, validated) = match verifier::validate_output(
+                        Path::new(&input),
+                        Path::new(&output),
+                        &track_selection,
+                        drop_subtitles,
+                        hdr_type,
+                        duration,
+                        &quality,
+                    ) {
+                        Ok(issues) => (issues, true),
+                        Err(e) => {
+                            warn!("Output validation failed: {}", e);
+                            (Vec::new(), false)
+                        }
+                    };
+                    // A raw stream source has no timestamps to align the frames by
+                    let result = if quality.vmaf_enabled && input_frame_rate.is_some() {
+                        warn!("Skipping VMAF: the source is a raw stream without timestamps");
+                        FullEncodeResult::Success
+                    } else if quality.vmaf_enabled && copy_video {
+                        // The picture is the source's, and without a score the source stays
+                        info!("Skipping VMAF: the video is copied");
+                        FullEncodeResult::Success
+                    } else if quality.vmaf_enabled {
+                        run_vmaf_check(
+                            &input,
+                            &output,
+                            Some(quality.vmaf_threshold),
+                            hdr_type,
+                            width,
+                            height,
+                            frame_rate,
+                        )
+                    } else {
+                        FullEncodeResult::Success
+                    };
+                    (issues, validated, result)
+                })
+                .await;
+                emit(
+                    &mut event_callback,
+                    PipelineEvent::StageFinished(Stage::Verify, elapsed),
+                );
+                verified
+            } else {
+                (Vec::new(), false, FullEncodeResult::Success)
+            };
+            if verifying {
+                let vmaf = match result {
+                    FullEncodeResult::SuccessWithVmaf { ref vmaf, .. }
+                    | FullEncodeResult::QualityWarning { ref vmaf, .. } => Some(vmaf.score),
+                    _ => None,
+                };
+                match run_hooks(HookPoint::AfterVerify, vmaf).await {
+                    Ok(kept) => kept_by_hook = kept_by_hook.or(kept),
+                    Err(e) => return hook_failed(e, Some(output)),
+                }
+            }
+
+            // The output only counts once it arrived intact
+            if let Some(staged) = staged {
+                let target = destination.clone();
+                let (copied, elapsed) = timed_blocking(Stage::Post, move || {
+                    transfer::copy_verified(&staged, &target)
+                })
+                .await;
+                emit(
+                    &mut event_callback,
+                    PipelineEvent::StageFinished(Stage::Post, elapsed),
+                );
+                if let Err(e) = copied {
+                    return FullEncodeResult::Error(e);
+                }
+            }
+
+            // A drifted or dropped stream means the output is damaged even if the picture scores well
+            if !issues.is_empty() {
+                emit(&mut event_callback, PipelineEvent::ValidationFailed(issues));
+                return result;
+            }
 
-            // Delete source after VMAF passes
-            if let FullEncodeResult::SuccessWithVmaf { ref vmaf, .. } = result {
-                let source_deleted = match std::fs::remove_file(input) {
-                    Ok(()) => {
-                        info!("Deleted source file: {} (VMAF: {:.1})", input, vmaf.score);
+            // Delete source after VMAF passes, unless turned off meanwhile
+            if let FullEncodeResult::SuccessWithVmaf { ref vmaf, .. } = result
+                && !overrides.keep_source
+                && shared.quality().delete_source
+            {
+                let quality = shared.quality();
+                let source = Path::new(input);
+                let (removed, elapsed) = timed(Stage::Post, || {
+                    let blocker = kept_by_hook.or_else(|| {
+                        deletion::deletion_blocker(
+                            source,
+                            &destination,
+                            validated,
+                            &metadata.codec_name,
+                            quality.delete_min_size_ratio,
+                        )
+                    });
+                    match blocker {
+                        Some(reason) => Err(reason),
+                        None => deletion::remove_source(
+                            source,
+                            &destination,
+                            quality.delete_mode,
+                            vmaf.score,
+                        )
+                        .map_err(|e| e.to_string()),
+                    }
+                });
+                emit(
+                    &mut event_callback,
+                    PipelineEvent::StageFinished(Stage::Post, elapsed),
+                );
+                let source_deleted = match removed {
+                    Ok(trashed) => {
+                        info!(
+                            "{} source file: {} (VMAF: {:.1})",
+                            if trashed { "Trashed" } else { "Deleted" },
+                            input,
+                            vmaf.score
+                        );
                         true
                     }
-                    Err(e) => {
-                        warn!("Failed to delete source file {}: {}", input, e);
+                    Err(reason) => {
+                        warn!("Keeping source file {}: {}", input, reason);
+                        emit(&mut event_callback, PipelineEvent::SourceKept(reason));
                         false
                     }
                 };
@@ -92,6 +525,87 @@ pub fn run_encoding_pipeline(
     }
 }
 
+/// A job cancelled during a hook stops like a cancelled encode, without the
+/// output it already wrote
+fn hook_failed(e: AppError, output: Option<&str>) -> FullEncodeResult {
+    match e {
+        AppError::Cancelled => {
+            if let Some(output) = output {
+                let _ = std::fs::remove_file(output);
+            }
+            FullEncodeResult::Cancelled
+        }
+        e => FullEncodeResult::Error(e),
+    }
+}
+
+/// Grain table written next to the output while it is encoded
+pub fn grain_table_path(output: &Path) -> PathBuf {
+    output.with_extension("grain.tbl")
+}
+
+/// Write the source's grain table and point the encode at it
+///
+/// Returns the table to remove afterwards; `None` leaves the parameters alone,
+/// e.g. when the path can't be passed in `-svtav1-params`, which splits on `:`.
+fn attach_grain_table(
+    params: &mut EncodingParams,
+    grain: &FilmGrainParams,
+    output: &Path,
+) -> Option<PathBuf> {
+    let table = grain_table_path(output);
+    let table_str = table.to_string_lossy().to_string();
+    if table_str.contains(':') {
+        warn!(
+            "Cannot pass film grain table {} to SVT-AV1, estimating grain instead",
+            table_str
+        );
+        return None;
+    }
+    if let Err(e) = grain.write_table(&table) {
+        warn!("{}, estimating grain instead", e);
+        return None;
+    }
+    params.film_grain_table = Some(table_str);
+    Some(table)
+}
+
+fn emit(event_callback: &mut Option<EventCallback>, event: PipelineEvent) {
+    if let Some(cb) = event_callback {
+        cb(event);
+    }
+}
+
+/// Check if the final container should be assembled by mkvmerge
+fn wants_mkvmerge(config: &AppConfig, output: &str, streams: &StreamHandling) -> bool {
+    if config.output.muxer != Muxer::Mkvmerge {
+        return false;
+    }
+
+    if streams.audio.is_some() {
+        warn!(
+            "mkvmerge cannot re-encode audio, muxing {} with ffmpeg",
+            output
+        );
+        return false;
+    }
+
+    let is_mkv = Path::new(output)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("mkv"));
+    if !is_mkv {
+        warn!("mkvmerge only produces MKV, muxing {} with ffmpeg", output);
+        return false;
+    }
+
+    if !DependencyStatus::mkvmerge_available() {
+        warn!("mkvmerge not found, falling back to ffmpeg muxing");
+        return false;
+    }
+
+    true
+}
+
 /// Run VMAF quality check after encoding
 fn run_vmaf_check(
     input: &str,
@@ -99,6 +613,8 @@ fn run_vmaf_check(
     threshold: Option<f64>,
     hdr_type: HdrType,
     width: u32,
+    height: u32,
+    frame_rate: f64,
 ) -> FullEncodeResult {
     let threshold = match threshold {
         Some(t) => t,
@@ -110,7 +626,7 @@ fn run_vmaf_check(
     let input_path = std::path::Path::new(input);
     let output_path = std::path::Path::new(output);
 
-    match verifier::calculate_vmaf(input_path, output_path, hdr_type, width) {
+    match verifier::calculate_vmaf(input_path, output_path, hdr_type, width, height, frame_rate) {
         Ok(vmaf) => {
             info!("VMAF score: {:.2} ({})", vmaf.score, vmaf.quality_grade());
 
@@ -0,0 +1,96 @@
+//! Record of how an output was produced.
+//!
+//! The app version, tool versions and the FFmpeg command are written into the
+//! output container as tags and shown in the batch report, so a file can be
+//! traced back to its settings long after the run.
+
+use super::command_builder::{EncodingParams, build_ffmpeg_args};
+use crate::config::Encoder;
+use crate::utils::DependencyStatus;
+use crate::utils::tools::{self, shell_quote};
+
+/// Container tag holding the app version
+pub const VERSION_TAG: &str = "AV1CONVERTER_VERSION";
+
+/// Container tag holding the FFmpeg and encoder versions
+pub const TOOLS_TAG: &str = "AV1CONVERTER_TOOLS";
+
+/// Container tag holding the encode command
+pub const COMMAND_TAG: &str = "AV1CONVERTER_COMMAND";
+
+/// App, tools and command behind an output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeProvenance {
+    /// e.g. `av1converter 2.2.2`
+    pub app: String,
+    /// e.g. `FFmpeg 7.1.0, SVT-AV1 2.3.0`
+    pub tools: String,
+    /// FFmpeg command of the encode, ready to paste into a shell
+    pub command: String,
+}
+
+impl EncodeProvenance {
+    /// Provenance of encoding with `params` on the installed toolchain
+    ///
+    /// Read before the tags are added, so the command is the one that
+    /// produced the video stream.
+    pub fn of(params: &EncodingParams, deps: &DependencyStatus) -> Self {
+        let mut versions = vec![format!(
+            "FFmpeg {}",
+            deps.ffmpeg
+                .map_or_else(|| "unknown".to_string(), |v| v.to_string())
+        )];
+        match (params.encoder, deps.libsvtav1) {
+            // A copied video names no encoder
+            _ if params.copy_video => {}
+            (Encoder::SvtAv1, Some(version)) => versions.push(format!("SVT-AV1 {}", version)),
+            (Encoder::SvtAv1, None) => {}
+            (encoder, _) => versions.push(encoder.display_name().to_string()),
+        }
+        let args: Vec<String> = build_ffmpeg_args(params)
+            .iter()
+            .map(|a| shell_quote(a))
+            .collect();
+        Self {
+            app: format!("av1converter {}", env!("CARGO_PKG_VERSION")),
+            tools: versions.join(", "),
+            command: format!("{} {}", shell_quote(&tools::ffmpeg()), args.join(" ")),
+        }
+    }
+
+    /// Container tags recording the provenance
+    pub fn tags(&self) -> Vec<(String, String)> {
+        vec![
+            (VERSION_TAG.to_string(), self.app.clone()),
+            (TOOLS_TAG.to_string(), self.tools.clone()),
+            (COMMAND_TAG.to_string(), self.command.clone()),
+        ]
+    }
+}
+
+/// Whether `output` is in the MP4 family, which only keeps custom tags when asked to
+pub fn needs_metadata_flag(output: &str) -> bool {
+    let lower = output.to_lowercase();
+    [".mp4", ".m4v", ".mov"].iter().any(|e| lower.ends_with(e))
+}
+
+/// Tags as a Matroska tags file for mkvmerge's `--global-tags`
+pub fn matroska_tags_xml(tags: &[(String, String)]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Tags>\n  <Tag>\n");
+    for (name, value) in tags {
+        xml.push_str(&format!(
+            "    <Simple><Name>{}</Name><String>{}</String></Simple>\n",
+            xml_escape(name),
+            xml_escape(value)
+        ));
+    }
+    xml.push_str("  </Tag>\n</Tags>\n");
+    xml
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,143 @@
+use crate::analyzer::HdrType;
+use crate::encoder::command_builder::{StreamHandling, build_mux_args};
+use crate::error::AppError;
+use crate::tracks::TrackSelection;
+use crate::utils::tools;
+use std::path::Path;
+use std::process::Command;
+use tracing::info;
+
+/// Sizes recorded around a post-encode remux
+#[derive(Debug, Clone, Copy)]
+pub struct RemuxResult {
+    /// Size of the file written by the encoder
+    pub encoded_size: u64,
+    /// Size after remuxing
+    pub remuxed_size: u64,
+}
+
+/// Remux an encoded file with stream copy to rebuild clean container indexes
+///
+/// The remux is written next to the output and only replaces it on success.
+pub fn remux_output(output: &Path) -> Result<RemuxResult, AppError> {
+    let encoded_size = file_size(output)?;
+
+    let extension = output
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| "mkv".to_string());
+    let temp_path = output.with_extension(format!("remux.{}", extension));
+
+    let mut args = vec![
+        "-y".to_string(),
+        "-nostdin".to_string(),
+        "-v".to_string(),
+        "error".to_string(),
+        "-i".to_string(),
+        output.to_string_lossy().to_string(),
+        "-map".to_string(),
+        "0".to_string(),
+        "-c".to_string(),
+        "copy".to_string(),
+    ];
+
+    // Index at the front so players can seek without reading the whole file,
+    // keeping custom tags such as the encode provenance
+    if matches!(extension.to_lowercase().as_str(), "mp4" | "m4v" | "mov") {
+        args.extend([
+            "-movflags".to_string(),
+            "+faststart+use_metadata_tags".to_string(),
+        ]);
+    }
+
+    args.push(temp_path.to_string_lossy().to_string());
+
+    info!("Remuxing {}", output.display());
+
+    let result = Command::new(tools::ffmpeg())
+        .args(&args)
+        .output()
+        .map_err(|e| AppError::CommandExecution(format!("Failed to run ffmpeg remux: {}", e)));
+
+    let result = match result {
+        Ok(r) => r,
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e);
+        }
+    };
+
+    if !result.status.success() {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(AppError::CommandExecution(format!(
+            "Remux failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+
+    std::fs::rename(&temp_path, output).map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        AppError::Io {
+            path: output.to_path_buf(),
+            operation: "rename remuxed output",
+            message: e.to_string(),
+        }
+    })?;
+
+    let remuxed_size = file_size(output)?;
+
+    info!("Remux complete: {} -> {} bytes", encoded_size, remuxed_size);
+
+    Ok(RemuxResult {
+        encoded_size,
+        remuxed_size,
+    })
+}
+
+/// Mux an encoded video stream with the selected source tracks using ffmpeg
+pub fn mux_video_with_source(
+    video: &Path,
+    source: &Path,
+    output: &Path,
+    tracks: &TrackSelection,
+    streams: &StreamHandling,
+    hdr_type: HdrType,
+    tags: &[(String, String)],
+) -> Result<(), AppError> {
+    let args = build_mux_args(
+        &video.to_string_lossy(),
+        &source.to_string_lossy(),
+        &output.to_string_lossy(),
+        tracks,
+        streams,
+        hdr_type,
+        tags,
+    );
+
+    info!("Muxing {} with source tracks", output.display());
+
+    let result = Command::new(tools::ffmpeg())
+        .args(&args)
+        .output()
+        .map_err(|e| AppError::CommandExecution(format!("Failed to run ffmpeg mux: {}", e)))?;
+
+    if !result.status.success() {
+        let _ = std::fs::remove_file(output);
+        return Err(AppError::CommandExecution(format!(
+            "Mux failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+fn file_size(path: &Path) -> Result<u64, AppError> {
+    std::fs::metadata(path)
+        .map(|m| m.len())
+        .map_err(|e| AppError::Io {
+            path: path.to_path_buf(),
+            operation: "stat",
+            message: e.to_string(),
+        })
+}
@@ -0,0 +1,122 @@
+//! Dry run of the encoding pipeline.
+//!
+//! Parameters, quality value and output path are worked out as for a real
+//! encode, but nothing is run or written: the encode becomes a short pause
+//! with progress, so rules and profiles can be checked on a big library first.
+
+use super::command_builder::{EncodeOverrides, EncodingParams, build_ffmpeg_args};
+use super::ffmpeg::{EncodeProgress, ProgressCallback};
+use crate::analyzer::VideoMetadata;
+use crate::audit::CodecFamily;
+use crate::config::{AppConfig, Encoder};
+use crate::tracks::TrackSelection;
+use crate::utils::DependencyStatus;
+use crate::utils::tools::{self, shell_quote};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Time a simulated encode takes
+const SIMULATED_ENCODE: Duration = Duration::from_millis(800);
+
+/// Progress updates over a simulated encode
+const SIMULATED_STEPS: u32 = 8;
+
+/// What encoding a file would have done
+#[derive(Debug, Clone)]
+pub struct SimulatedEncode {
+    pub encoder: Encoder,
+    /// Quality value the encode would use
+    pub crf: u8,
+    /// FFmpeg command line, ready to paste into a shell
+    pub command: String,
+    /// Output size typical for the source codec
+    pub estimated_size: Option<u64>,
+    /// Whether the source would be removed once the output passed
+    pub deletes_source: bool,
+}
+
+/// Work out the encode of `input` and pretend to run it
+///
+/// The hardware encoder check and the mux, VMAF and deletion steps are left
+/// out since they need a real output. Returns `None` when cancelled.
+#[allow(clippy::too_many_arguments)]
+pub async fn simulate_encode(
+    input: &str,
+    output: &str,
+    metadata: &VideoMetadata,
+    tracks: TrackSelection,
+    overrides: &EncodeOverrides,
+    config: &AppConfig,
+    mut progress_callback: Option<ProgressCallback>,
+    cancel: CancellationToken,
+) -> Option<SimulatedEncode> {
+    let params = encode_params(input, output, metadata, tracks, overrides, config);
+
+    for step in 1..=SIMULATED_STEPS {
+        tokio::select! {
+            _ = tokio::time::sleep(SIMULATED_ENCODE / SIMULATED_STEPS) => {}
+            _ = cancel.cancelled() => return None,
+        }
+        if let Some(ref mut cb) = progress_callback {
+            cb(EncodeProgress {
+                percent: step as f32 / SIMULATED_STEPS as f32 * 100.0,
+                ..Default::default()
+            });
+        }
+    }
+
+    // A copied video keeps its size
+    let ratio = if params.copy_video {
+        1.0
+    } else {
+        CodecFamily::from_codec(&metadata.codec_name).size_ratio()
+    };
+    let estimated_size = std::fs::metadata(input)
+        .ok()
+        .map(|m| (m.len() as f64 * ratio) as u64);
+    Some(SimulatedEncode {
+        encoder: params.encoder,
+        crf: params.crf,
+        command: command_line(&params),
+        estimated_size,
+        deletes_source: config.quality.delete_source
+            && !overrides.keep_source
+            && !params.copy_video,
+    })
+}
+
+/// FFmpeg command line the encode of `input` would run, ready to paste into a shell
+pub fn ffmpeg_command(
+    input: &str,
+    output: &str,
+    metadata: &VideoMetadata,
+    tracks: TrackSelection,
+    overrides: &EncodeOverrides,
+    config: &AppConfig,
+) -> String {
+    command_line(&encode_params(
+        input, output, metadata, tracks, overrides, config,
+    ))
+}
+
+fn encode_params(
+    input: &str,
+    output: &str,
+    metadata: &VideoMetadata,
+    tracks: TrackSelection,
+    overrides: &EncodeOverrides,
+    config: &AppConfig,
+) -> EncodingParams {
+    let mut params = EncodingParams::from_metadata(input, output, metadata, config, tracks);
+    params.apply_overrides(overrides);
+    params.apply_toolchain(&DependencyStatus::current());
+    params
+}
+
+fn command_line(params: &EncodingParams) -> String {
+    let args: Vec<String> = build_ffmpeg_args(params)
+        .iter()
+        .map(|a| shell_quote(a))
+        .collect();
+    format!("{} {}", shell_quote(&tools::ffmpeg()), args.join(" "))
+}
@@ -0,0 +1,200 @@
+use crate::utils::format_duration;
+use std::time::{Duration, Instant};
+use tracing::{Instrument, Span, info, info_span};
+
+/// Pipeline stages traced and timed separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Analyze,
+    /// Hardware test encode and film grain table before the encode
+    Prepare,
+    Encode,
+    Mux,
+    Verify,
+    /// Source deletion after a passing verification
+    Post,
+}
+
+impl Stage {
+    /// All stages in pipeline order
+    pub const ALL: [Stage; 6] = [
+        Stage::Analyze,
+        Stage::Prepare,
+        Stage::Encode,
+        Stage::Mux,
+        Stage::Verify,
+        Stage::Post,
+    ];
+
+    /// Short lowercase name used in logs and reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Analyze => "analyze",
+            Stage::Prepare => "prepare",
+            Stage::Encode => "encode",
+            Stage::Mux => "mux",
+            Stage::Verify => "verify",
+            Stage::Post => "post",
+        }
+    }
+
+    /// Tracing span for the stage, a child of the current (job) span
+    fn span(&self) -> Span {
+        match self {
+            Stage::Analyze => info_span!("analyze"),
+            Stage::Prepare => info_span!("prepare"),
+            Stage::Encode => info_span!("encode"),
+            Stage::Mux => info_span!("mux"),
+            Stage::Verify => info_span!("verify"),
+            Stage::Post => info_span!("post"),
+        }
+    }
+}
+
+/// Run a stage inside its tracing span and measure how long it took
+pub fn timed<T>(stage: Stage, f: impl FnOnce() -> T) -> (T, Duration) {
+    let span = stage.span();
+    let _guard = span.enter();
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    info!(
+        elapsed_ms = elapsed.as_millis() as u64,
+        "{} stage finished",
+        stage.name()
+    );
+    (result, elapsed)
+}
+
+/// Await a stage inside its tracing span and measure how long it took
+pub async fn timed_async<F: Future>(stage: Stage, fut: F) -> (F::Output, Duration) {
+    let start = Instant::now();
+    let result = fut.instrument(stage.span()).await;
+    let elapsed = start.elapsed();
+    info!(
+        elapsed_ms = elapsed.as_millis() as u64,
+        "{} stage finished",
+        stage.name()
+    );
+    (result, elapsed)
+}
+
+/// Run a blocking stage on the blocking thread pool, keeping the current span as parent
+pub async fn timed_blocking<T: Send + 'static>(
+    stage: Stage,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> (T, Duration) {
+    let parent = Span::current();
+    tokio::task::spawn_blocking(move || parent.in_scope(|| timed(stage, f)))
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// Wall-clock time spent in each stage
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageTimings {
+    durations: [Option<Duration>; 6],
+}
+
+impl StageTimings {
+    /// Add time spent in a stage
+    pub fn record(&mut self, stage: Stage, elapsed: Duration) {
+        let slot = &mut self.durations[stage as usize];
+        *slot = Some(slot.unwrap_or_default() + elapsed);
+    }
+
+    /// Time spent in a stage, if it ran
+    pub fn get(&self, stage: Stage) -> Option<Duration> {
+        self.durations[stage as usize]
+    }
+
+    /// Sum with another set of timings
+    pub fn add(&mut self, other: &StageTimings) {
+        for stage in Stage::ALL {
+            if let Some(elapsed) = other.get(stage) {
+                self.record(stage, elapsed);
+            }
+        }
+    }
+
+    /// Check if no stage was timed
+    pub fn is_empty(&self) -> bool {
+        self.durations.iter().all(Option::is_none)
+    }
+
+    /// Breakdown such as "analyze 00:04 · encode 01:02:11 · verify 04:51"
+    pub fn summary(&self) -> String {
+        Stage::ALL
+            .iter()
+            .filter_map(|&stage| {
+                self.get(stage)
+                    .map(|d| format!("{} {}", stage.name(), format_duration(d)))
+            })
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+
+    /// Time spent between the start of the job and its encode
+    pub fn before_encode(&self) -> Duration {
+        self.get(Stage::Prepare).unwrap_or_default()
+    }
+
+    /// Time spent after the encode: muxing, verification and cleanup
+    pub fn after_encode(&self) -> Duration {
+        [Stage::Mux, Stage::Verify, Stage::Post]
+            .iter()
+            .filter_map(|&stage| self.get(stage))
+            .sum()
+    }
+
+    /// Time spent in all stages together
+    pub fn total(&self) -> Duration {
+        self.durations.iter().flatten().sum()
+    }
+
+    /// Stages that ran, in pipeline order, with their share of the total (0.0-1.0)
+    pub fn timeline(&self) -> Vec<(Stage, Duration, f64)> {
+        let total = self.total().as_secs_f64();
+        Stage::ALL
+            .iter()
+            .filter_map(|&stage| {
+                let elapsed = self.get(stage)?;
+                let share = if total > 0.0 {
+                    elapsed.as_secs_f64() / total
+                } else {
+                    0.0
+                };
+                Some((stage, elapsed, share))
+            })
+            .collect()
+    }
+
+    /// Stage that took the longest, with its share of the total
+    pub fn slowest(&self) -> Option<(Stage, f64)> {
+        self.timeline()
+            .into_iter()
+            .max_by_key(|&(_, elapsed, _)| elapsed)
+            .map(|(stage, _, share)| (stage, share))
+    }
+
+    /// One row per stage, e.g. `encode      54:02   82%  ████████░░`
+    ///
+    /// Bars are `bar_width` characters wide and filled by the stage's share, so
+    /// the rows line up.
+    pub fn timeline_lines(&self, bar_width: usize) -> Vec<String> {
+        self.timeline()
+            .into_iter()
+            .map(|(stage, elapsed, share)| {
+                let filled = ((share * bar_width as f64).round() as usize).clamp(1, bar_width);
+                format!(
+                    "{:<8} {:>9} {:>4.0}%  {}{}",
+                    stage.name(),
+                    format_duration(elapsed),
+                    share * 100.0,
+                    "█".repeat(filled),
+                    "░".repeat(bar_width - filled)
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,259 @@
+use crate::config::{AppConfig, NetworkSafeMode};
+use crate::error::AppError;
+use crate::utils::data_dir;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+/// Filesystem types of network mounts, as listed in `/proc/mounts` or by `mount`
+const NETWORK_FILESYSTEMS: [&str; 11] = [
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "afpfs",
+    "webdav",
+    "9p",
+    "ceph",
+    "fuse.sshfs",
+    "fuse.glusterfs",
+];
+
+/// Bytes compared at the end of a copied file
+const TAIL_BYTES: u64 = 1024 * 1024;
+
+/// Local file to encode into before copying to `output`, when the output is
+/// written with network safe mode
+///
+/// `None` writes directly, also when the staging folder is itself on the
+/// network.
+pub fn staging_path(output: &Path, config: &AppConfig) -> Option<PathBuf> {
+    let dir = output
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let staged = match config.output.network_safe_mode {
+        NetworkSafeMode::Never => return None,
+        NetworkSafeMode::Always => true,
+        NetworkSafeMode::Auto => is_network_path(dir),
+    };
+    if !staged {
+        return None;
+    }
+
+    let staging = staging_dir(config);
+    if is_network_path(&staging) {
+        warn!(
+            "Staging folder {} is on the network too, writing {} directly",
+            staging.display(),
+            output.display()
+        );
+        return None;
+    }
+    // Outputs of different folders may share a name
+    let mut hasher = DefaultHasher::new();
+    output.hash(&mut hasher);
+    let name = output.file_name()?.to_string_lossy();
+    Some(staging.join(format!("{:016x}_{}", hasher.finish(), name)))
+}
+
+/// Folder staged outputs are encoded into
+pub fn staging_dir(config: &AppConfig) -> PathBuf {
+    config
+        .output
+        .staging_directory
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| data_dir().join("staging"))
+}
+
+/// Temporary name a copy is written under before it replaces the output
+pub fn transfer_path(output: &Path) -> PathBuf {
+    let extension = output
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| "mkv".to_string());
+    output.with_extension(format!("transfer.{}", extension))
+}
+
+/// Check if `path` is on an NFS, SMB or other network mount
+pub fn is_network_path(path: &Path) -> bool {
+    let path = existing_ancestor(path);
+    if cfg!(windows) {
+        let text = path.to_string_lossy();
+        return text.starts_with(r"\\") && !text.starts_with(r"\\?\");
+    }
+
+    // Longest mount point containing the path decides
+    mount_points()
+        .into_iter()
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.as_os_str().len())
+        .is_some_and(|(_, fs_type)| NETWORK_FILESYSTEMS.contains(&fs_type.as_str()))
+}
+
+/// Deepest existing folder of `path`, made absolute
+fn existing_ancestor(path: &Path) -> PathBuf {
+    let mut current = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    loop {
+        if let Ok(canonical) = current.canonicalize() {
+            return canonical;
+        }
+        if !current.pop() {
+            return path.to_path_buf();
+        }
+    }
+}
+
+/// Mount points and their filesystem types
+fn mount_points() -> Vec<(PathBuf, String)> {
+    if let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") {
+        // `device mount-point type options ...`, spaces escaped as \040
+        return mounts
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let mount_point = fields.nth(1)?.replace("\\040", " ");
+                Some((PathBuf::from(mount_point), fields.next()?.to_string()))
+            })
+            .collect();
+    }
+
+    // macOS and BSD: `device on /mount/point (type, options)`
+    let Ok(output) = Command::new("mount").output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (mount_point, options) = rest.rsplit_once(" (")?;
+            let fs_type = options.split([',', ')']).next()?.trim();
+            Some((PathBuf::from(mount_point), fs_type.to_string()))
+        })
+        .collect()
+}
+
+/// Staged output removed when dropped, so an encode that stops before its
+/// copy leaves nothing in the staging folder
+#[derive(Debug)]
+pub struct StagedGuard {
+    path: PathBuf,
+}
+
+impl StagedGuard {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl Drop for StagedGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Copy a staged output to its destination and check what arrived
+///
+/// The copy is flushed and renamed into place, then the destination size and
+/// its last megabyte are compared against the staged file. A mismatch is
+/// copied again once before it fails, and a copy that still doesn't match is
+/// removed from the destination. The staged file is removed either way.
+pub fn copy_verified(staged: &Path, output: &Path) -> Result<(), AppError> {
+    let mut last_error = None;
+    // Whether the last attempt's copy reached the destination
+    let mut placed = false;
+    for attempt in 1..=2 {
+        let copied = copy_once(staged, output);
+        placed = copied.is_ok();
+        match copied.and_then(|()| verify_copy(staged, output)) {
+            Ok(()) => {
+                info!("Copied {} to {}", staged.display(), output.display());
+                let _ = std::fs::remove_file(staged);
+                return Ok(());
+            }
+            Err(e) => {
+                warn!(
+                    "Copy to {} failed (attempt {}): {}",
+                    output.display(),
+                    attempt,
+                    e
+                );
+                last_error = Some(e);
+            }
+        }
+    }
+    // The source is kept, so the file can simply be encoded again
+    let _ = std::fs::remove_file(staged);
+    if placed {
+        let _ = std::fs::remove_file(output);
+    }
+    Err(last_error.expect("at least one attempt"))
+}
+
+fn copy_once(staged: &Path, output: &Path) -> Result<(), AppError> {
+    let io_error = |path: &Path, operation: &'static str| {
+        let path = path.to_path_buf();
+        move |e: std::io::Error| AppError::Io {
+            path,
+            operation,
+            message: e.to_string(),
+        }
+    };
+    let temp = transfer_path(output);
+    let result = (|| {
+        std::fs::copy(staged, &temp).map_err(io_error(&temp, "copy output"))?;
+        File::open(&temp)
+            .and_then(|f| f.sync_all())
+            .map_err(io_error(&temp, "flush copied output"))?;
+        std::fs::rename(&temp, output).map_err(io_error(output, "rename copied output"))
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Compare the size and the tail of the copy with the staged file
+fn verify_copy(staged: &Path, output: &Path) -> Result<(), AppError> {
+    let mismatch = |message: String| AppError::Io {
+        path: output.to_path_buf(),
+        operation: "verify copied output",
+        message,
+    };
+    let (expected, expected_tail) = read_tail(staged)?;
+    let (actual, actual_tail) = read_tail(output)?;
+    if actual != expected {
+        return Err(mismatch(format!(
+            "only {} of {} bytes arrived",
+            actual, expected
+        )));
+    }
+    if actual_tail != expected_tail {
+        return Err(mismatch(
+            "last megabyte differs from the encoded file".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Size of a file and its last `TAIL_BYTES`
+pub(super) fn read_tail(path: &Path) -> Result<(u64, Vec<u8>), AppError> {
+    let io_error = |e: std::io::Error| AppError::Io {
+        path: path.to_path_buf(),
+        operation: "read output tail",
+        message: e.to_string(),
+    };
+    let mut file = File::open(path).map_err(io_error)?;
+    let size = file.metadata().map_err(io_error)?.len();
+    file.seek(SeekFrom::Start(size.saturating_sub(TAIL_BYTES)))
+        .map_err(io_error)?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).map_err(io_error)?;
+    Ok((size, tail))
+}
@@ -1,5 +1,111 @@
+use crate::analyzer::UnsupportedInput;
 use std::path::PathBuf;
 
+/// Broad class of a failure, deciding what the user can do about it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A required tool is not installed
+    DependencyMissing,
+    /// The hardware encoder could not open a session (busy GPU, driver limits)
+    EncoderSession,
+    /// The output disk ran out of space
+    DiskFull,
+    /// The source file is damaged or not a readable video
+    CorruptSource,
+    /// VMAF scoring is unavailable in the installed ffmpeg
+    VmafUnavailable,
+    /// The source is an image, audio file or other input that isn't a video
+    UnsupportedInput,
+    /// The source is a raw video stream that needs an assumed frame rate
+    ElementaryStream,
+    /// Another running instance holds the lock on the output
+    Locked,
+    /// Anything not recognized
+    Other,
+}
+
+impl ErrorKind {
+    /// Classify a failure from ffmpeg/ffprobe output or an I/O error message
+    pub fn classify(output: &str) -> Self {
+        let output = output.to_lowercase();
+        let matches = |needles: &[&str]| needles.iter().any(|n| output.contains(n));
+
+        if matches(&["no space left on device", "disk full", "os error 28"]) {
+            ErrorKind::DiskFull
+        } else if matches(&[
+            "openencodesessionex failed",
+            "no capable devices found",
+            "cannot load libcuda",
+            "cannot load nvcuda",
+            "error creating a mfx session",
+            "failed to initialise vaapi",
+            "failed to initialize amf",
+            "device creation failed",
+        ]) {
+            ErrorKind::EncoderSession
+        } else if matches(&["libvmaf", "vmaf model"]) {
+            ErrorKind::VmafUnavailable
+        } else if matches(&[
+            "invalid data found when processing input",
+            "moov atom not found",
+            "error while decoding",
+            "corrupt",
+            "truncat",
+        ]) {
+            ErrorKind::CorruptSource
+        } else {
+            ErrorKind::Other
+        }
+    }
+
+    /// Short name shown instead of raw tool output
+    pub fn label(&self) -> &'static str {
+        match self {
+            ErrorKind::DependencyMissing => "Missing dependency",
+            ErrorKind::EncoderSession => "Encoder unavailable",
+            ErrorKind::DiskFull => "Disk full",
+            ErrorKind::CorruptSource => "Corrupt source",
+            ErrorKind::VmafUnavailable => "VMAF unavailable",
+            ErrorKind::UnsupportedInput => "Unsupported input",
+            ErrorKind::ElementaryStream => "Raw video stream",
+            ErrorKind::Locked => "Another instance is running",
+            ErrorKind::Other => "Encoding failed",
+        }
+    }
+
+    /// Whether running the job again can succeed without changing the source
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::EncoderSession
+                | ErrorKind::DiskFull
+                | ErrorKind::ElementaryStream
+                | ErrorKind::Locked
+                | ErrorKind::Other
+        )
+    }
+
+    /// What the user can do about it
+    pub fn hint(&self) -> &'static str {
+        match self {
+            ErrorKind::DependencyMissing => "install it and restart",
+            ErrorKind::EncoderSession => "close other GPU encodes and press r to retry",
+            ErrorKind::DiskFull => "free space and press r to retry",
+            ErrorKind::CorruptSource => "check the source file",
+            ErrorKind::VmafUnavailable => "install ffmpeg with libvmaf or disable VMAF",
+            ErrorKind::UnsupportedInput => "only video files can be converted",
+            ErrorKind::ElementaryStream => "press r to encode it at the assumed frame rate",
+            ErrorKind::Locked => "wait for it to finish and press r, or start with --ignore-lock",
+            ErrorKind::Other => "see the log for details, press r to retry",
+        }
+    }
+
+    /// Label and hint, e.g. "Disk full — free space and press r to retry"
+    pub fn summary(&self) -> String {
+        format!("{} — {}", self.label(), self.hint())
+    }
+}
+
 /// AV1Converter application error
 #[derive(Debug)]
 pub enum AppError {
@@ -27,6 +133,62 @@ pub enum AppError {
 
     /// Command execution failed
     CommandExecution(String),
+
+    /// Encoding failed, classified from the encoder output
+    Encode { kind: ErrorKind, message: String },
+
+    /// The source is not a video this tool can convert
+    Unsupported(UnsupportedInput),
+
+    /// Another running instance holds this lock file
+    Locked {
+        path: PathBuf,
+        pid: u32,
+        /// Host of the instance, when it isn't this machine
+        host: Option<String>,
+    },
+
+    /// Another instance, possibly on another machine, claimed this source
+    Claimed { path: PathBuf, holder: String },
+
+    /// The job was cancelled while a step other than the encode ran
+    Cancelled,
+}
+
+impl AppError {
+    /// Encoding failure classified from its message
+    pub fn encode(message: impl Into<String>) -> Self {
+        let message = message.into();
+        AppError::Encode {
+            kind: ErrorKind::classify(&message),
+            message,
+        }
+    }
+
+    /// Broad class of the failure
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            AppError::DependencyMissing(_) => ErrorKind::DependencyMissing,
+            AppError::Vmaf(_) => ErrorKind::VmafUnavailable,
+            AppError::Analysis(msg) | AppError::CommandExecution(msg) => {
+                match ErrorKind::classify(msg) {
+                    ErrorKind::Other if matches!(self, AppError::Analysis(_)) => {
+                        ErrorKind::CorruptSource
+                    }
+                    kind => kind,
+                }
+            }
+            AppError::Parse { .. } => ErrorKind::CorruptSource,
+            AppError::Io { message, .. } => ErrorKind::classify(message),
+            AppError::Config(_) | AppError::Cancelled => ErrorKind::Other,
+            AppError::Encode { kind, .. } => *kind,
+            AppError::Unsupported(UnsupportedInput::ElementaryStream { .. }) => {
+                ErrorKind::ElementaryStream
+            }
+            AppError::Unsupported(_) => ErrorKind::UnsupportedInput,
+            AppError::Locked { .. } | AppError::Claimed { .. } => ErrorKind::Locked,
+        }
+    }
 }
 
 impl std::fmt::Display for AppError {
@@ -53,6 +215,21 @@ impl std::fmt::Display for AppError {
                 write!(f, "Parse error in {}: {}", context, message)
             }
             AppError::CommandExecution(msg) => write!(f, "Command execution failed: {}", msg),
+            AppError::Encode { message, .. } => write!(f, "{}", message),
+            AppError::Unsupported(input) => write!(f, "Unsupported input: {}", input.reason()),
+            AppError::Locked { path, pid, host } => write!(
+                f,
+                "Another instance is running (PID {}{}, lock {})",
+                pid,
+                host.as_deref()
+                    .map(|host| format!(" on {}", host))
+                    .unwrap_or_default(),
+                path.display()
+            ),
+            AppError::Claimed { path, holder } => {
+                write!(f, "Claimed by {} (claim {})", holder, path.display())
+            }
+            AppError::Cancelled => write!(f, "Cancelled"),
         }
     }
 }
@@ -0,0 +1,179 @@
+//! Running a queue without the terminal UI.
+//!
+//! `av1converter --json <files or folders>` analyzes and encodes what it is
+//! given with the saved configuration, keeping the tracks the track rules
+//! select, like starting the queue from the review screen. Progress goes to
+//! stdout as one [`JobEvent`] per line, for scripts and other tools to follow.
+//!
+//! `av1converter --print-commands <files or folders>` works the queue out the
+//! same way but runs nothing: it prints the FFmpeg command of each encode,
+//! with skipped and failed files as `#` comments, so the output is a script.
+
+use crate::app::{App, Background, Screen};
+use crate::encoder::preset_quality;
+use crate::queue::{EncodingJob, JobEvent, JobStatus, WorkerMessage};
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+/// Encode the queued jobs of `app`, writing their events to `out`
+pub async fn run(app: &mut App, out: &mut impl Write) -> io::Result<()> {
+    drive(app, out, EventStream::default()).await
+}
+
+/// Write the FFmpeg command of each queued job of `app` to `out`, without
+/// running any
+pub async fn print_commands(app: &mut App, out: &mut impl Write) -> io::Result<()> {
+    app.simulate = true;
+    let events = EventStream {
+        commands: true,
+        ..EventStream::default()
+    };
+    drive(app, out, events).await
+}
+
+async fn drive(app: &mut App, out: &mut impl Write, mut events: EventStream) -> io::Result<()> {
+    app.current_screen = Screen::FileConfirm;
+    app.start_queue();
+    events.sweep(app, out)?;
+
+    while app.progress_receiver.is_some() || app.analysis_receiver.is_some() {
+        let update = app.recv_background().await;
+        if let Background::Worker(Some(ref messages)) = update {
+            for message in messages {
+                events.message(message, app, out)?;
+            }
+        }
+        app.process_background(update);
+        events.sweep(app, out)?;
+    }
+
+    let queue = &app.queue;
+    events.emit(
+        app,
+        out,
+        &JobEvent::Finished {
+            converted: queue.converted_count,
+            skipped: queue.skipped_count,
+            user_skipped: queue.user_skipped_count,
+            cancelled: queue.cancelled_count,
+            errors: queue.error_count,
+        },
+    )
+}
+
+/// What has been reported of each job so far
+#[derive(Default)]
+struct EventStream {
+    /// Print commands instead of JSON events
+    commands: bool,
+    started: HashSet<usize>,
+    /// Jobs whose outcome was reported
+    finished: HashSet<usize>,
+    last_percent: Vec<(usize, f32)>,
+}
+
+impl EventStream {
+    /// Events of a worker message; a job's first progress also starts it
+    fn message(
+        &mut self,
+        message: &WorkerMessage,
+        app: &App,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let jobs = &app.queue.jobs;
+        let Some(event) = JobEvent::from_message(message, jobs) else {
+            return Ok(());
+        };
+        match &event {
+            JobEvent::Progress { job, percent, .. } => {
+                if self.started.insert(*job)
+                    && let Some(encoding) = jobs.get(*job)
+                {
+                    self.emit(app, out, &started(*job, encoding, app))?;
+                }
+                // ffmpeg reports more often than the rounded percentage moves
+                if self.last_percent.contains(&(*job, *percent)) {
+                    return Ok(());
+                }
+                self.last_percent.retain(|(j, _)| j != job);
+                self.last_percent.push((*job, *percent));
+            }
+            JobEvent::Done { job, .. }
+            | JobEvent::QualityWarning { job, .. }
+            | JobEvent::Skipped { job, .. }
+            | JobEvent::Error { job, .. } => {
+                self.finished.insert(*job);
+            }
+            _ => {}
+        }
+        self.emit(app, out, &event)
+    }
+
+    /// Report jobs that ended without reaching the worker: skipped or failed
+    /// during analysis, or left over when the queue was cancelled
+    fn sweep(&mut self, app: &App, out: &mut impl Write) -> io::Result<()> {
+        for (index, job) in app.queue.jobs.iter().enumerate() {
+            let event = match &job.status {
+                JobStatus::Skipped { kind, reason } => JobEvent::Skipped {
+                    job: index,
+                    file: job.display_name(),
+                    kind: *kind,
+                    reason: reason.clone(),
+                },
+                JobStatus::Error { message, kind } => JobEvent::Error {
+                    job: index,
+                    file: job.display_name(),
+                    kind: kind.label(),
+                    message: message.clone(),
+                },
+                _ => continue,
+            };
+            if self.finished.insert(index) {
+                self.emit(app, out, &event)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn emit(&self, app: &App, out: &mut impl Write, event: &JobEvent) -> io::Result<()> {
+        if !self.commands {
+            serde_json::to_writer(&mut *out, event)?;
+            writeln!(out)?;
+            return out.flush();
+        }
+        let path = |job: usize| {
+            app.queue
+                .jobs
+                .get(job)
+                .map(|j| j.path.display().to_string())
+                .unwrap_or_default()
+        };
+        match event {
+            JobEvent::Simulated { job, command } => {
+                writeln!(out, "# {}", path(*job))?;
+                writeln!(out, "{}", command)?;
+            }
+            JobEvent::Skipped { job, reason, .. } => {
+                writeln!(out, "# {}: skipped, {}", path(*job), reason)?;
+            }
+            JobEvent::Error { job, message, .. } => {
+                writeln!(out, "# {}: {}", path(*job), message)?;
+            }
+            _ => return Ok(()),
+        }
+        out.flush()
+    }
+}
+
+fn started(index: usize, job: &EncodingJob, app: &App) -> JobEvent {
+    JobEvent::Started {
+        job: index,
+        file: job.display_name(),
+        output: job.output_path.clone(),
+        quality: job.crf.or_else(|| {
+            job.metadata
+                .as_ref()
+                .map(|metadata| preset_quality(metadata, &app.config))
+        }),
+    }
+}
@@ -0,0 +1,109 @@
+use super::HistoryRecord;
+use crate::config::{AppConfig, Encoder, EncodingPresetsConfig};
+
+/// Results at the current setting needed before a change is suggested
+const MIN_SAMPLES: usize = 3;
+/// Only the most recent results per preset are considered
+const RECENT_RESULTS: usize = 30;
+/// VMAF the suggestion aims for above the configured threshold
+const TARGET_HEADROOM: f64 = 3.0;
+/// VMAF change per quality step when the history has a single setting to learn from;
+/// deliberately steep so early suggestions stay conservative
+const DEFAULT_SLOPE: f64 = -1.0;
+/// Largest change suggested at once
+const MAX_STEP: f64 = 4.0;
+
+/// Suggested quality value for one preset, based on measured VMAF
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommendation {
+    /// Preset name, as in `[presets.<name>]`
+    pub preset: &'static str,
+    pub encoder: Encoder,
+    pub current: u8,
+    pub suggested: u8,
+    /// Mean VMAF of the results at the current value
+    pub average_vmaf: f64,
+    pub samples: usize,
+}
+
+impl Recommendation {
+    /// One-line advice for the UI
+    pub fn message(&self) -> String {
+        let label = self.encoder.quality_label();
+        format!(
+            "{}: VMAF averages {:.1} over {} files at {} {} — consider {} {}",
+            EncodingPresetsConfig::display_name(self.preset),
+            self.average_vmaf,
+            self.samples,
+            label,
+            self.current,
+            label,
+            self.suggested
+        )
+    }
+}
+
+/// Suggest quality values for the configured encoder's presets from past VMAF results
+///
+/// Values are chosen to land a few points above the VMAF threshold: a preset that scores
+/// well above it can use a higher CRF and save space, one that scores close to or below it
+/// needs a lower CRF.
+pub fn recommend(history: &[HistoryRecord], config: &AppConfig) -> Vec<Recommendation> {
+    let encoder = config.encoder;
+    let target = (config.quality.vmaf_threshold + TARGET_HEADROOM).min(99.0);
+
+    EncodingPresetsConfig::NAMES
+        .iter()
+        .filter_map(|&preset| {
+            let current = config.presets.get(preset)?.quality_for(encoder);
+            let results: Vec<(f64, f64)> = history
+                .iter()
+                .filter(|r| r.encoder == encoder && r.preset == preset)
+                .filter_map(|r| Some((r.quality as f64, r.vmaf?)))
+                .collect();
+            let recent = &results[results.len().saturating_sub(RECENT_RESULTS)..];
+
+            let at_current: Vec<f64> = recent
+                .iter()
+                .filter(|(quality, _)| *quality == current as f64)
+                .map(|&(_, vmaf)| vmaf)
+                .collect();
+            if at_current.len() < MIN_SAMPLES {
+                return None;
+            }
+            let average_vmaf = at_current.iter().sum::<f64>() / at_current.len() as f64;
+
+            // Move along the VMAF/quality line to the target score
+            let change = ((target - average_vmaf) / slope(recent)).clamp(-MAX_STEP, MAX_STEP);
+            let suggested = (current as f64 + change)
+                .round()
+                .clamp(1.0, encoder.max_quality() as f64) as u8;
+            (suggested != current).then_some(Recommendation {
+                preset,
+                encoder,
+                current,
+                suggested,
+                average_vmaf,
+                samples: at_current.len(),
+            })
+        })
+        .collect()
+}
+
+/// Least-squares VMAF change per quality step, falling back to a typical value
+fn slope(results: &[(f64, f64)]) -> f64 {
+    let n = results.len() as f64;
+    let mean_q = results.iter().map(|(q, _)| q).sum::<f64>() / n;
+    let mean_v = results.iter().map(|(_, v)| v).sum::<f64>() / n;
+    let var_q: f64 = results.iter().map(|(q, _)| (q - mean_q).powi(2)).sum();
+    if var_q == 0.0 {
+        return DEFAULT_SLOPE;
+    }
+    let cov: f64 = results
+        .iter()
+        .map(|(q, v)| (q - mean_q) * (v - mean_v))
+        .sum();
+
+    // Higher values always mean lower quality; ignore noisy fits that say otherwise
+    (cov / var_q).clamp(-3.0, -0.25)
+}
@@ -0,0 +1,78 @@
+pub mod advisor;
+pub mod pacing;
+pub mod stats;
+
+pub use advisor::{Recommendation, recommend};
+pub use pacing::Pacing;
+pub use stats::StatsRecord;
+
+use crate::config::Encoder;
+use crate::error::AppError;
+use crate::utils::data_dir;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Outcome of one finished encode, appended to the history file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Source file name
+    pub file: String,
+    pub encoder: Encoder,
+    /// Preset name, as in `[presets.<name>]`
+    pub preset: String,
+    /// Quality value (CRF/CQ) the file was encoded with
+    pub quality: u8,
+    pub vmaf: Option<f64>,
+    pub source_size: Option<u64>,
+    pub output_size: Option<u64>,
+    /// Seconds spent encoding, before the encode and after it
+    #[serde(default)]
+    pub encode_secs: Option<f64>,
+    #[serde(default)]
+    pub before_encode_secs: Option<f64>,
+    #[serde(default)]
+    pub after_encode_secs: Option<f64>,
+}
+
+/// Location of the history file
+pub fn history_path() -> PathBuf {
+    data_dir().join("history.jsonl")
+}
+
+/// Append a record to the history file
+pub fn append(record: &HistoryRecord) -> Result<(), AppError> {
+    let path = history_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Read all records, skipping lines that fail to parse
+pub fn load() -> Vec<HistoryRecord> {
+    let Ok(content) = std::fs::read_to_string(history_path()) else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                warn!("Skipping malformed history entry: {}", e);
+                None
+            }
+        })
+        .collect()
+}
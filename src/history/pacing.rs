@@ -0,0 +1,90 @@
+use super::HistoryRecord;
+use crate::config::Encoder;
+use crate::encoder::{Stage, StageTimings};
+use std::time::Duration;
+
+/// Only the most recent timed results are considered
+const RECENT_RESULTS: usize = 30;
+
+/// Time spent around the encode, relative to the encode itself
+///
+/// The test encode and grain table before the encode and the mux, VMAF and
+/// checks after it do not report progress, so the ETA stretches the encode
+/// progress by these ratios instead of jumping when they start or end.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pacing {
+    encode_secs: f64,
+    before_secs: f64,
+    after_secs: f64,
+    /// Source bytes and encode seconds of the results with a known size
+    sized_bytes: f64,
+    sized_secs: f64,
+}
+
+impl Pacing {
+    /// Pacing learned from past results of `encoder`
+    pub fn from_history(history: &[HistoryRecord], encoder: Encoder) -> Self {
+        let mut pacing = Self::default();
+        for record in history
+            .iter()
+            .rev()
+            .filter(|r| r.encoder == encoder && r.encode_secs.is_some())
+            .take(RECENT_RESULTS)
+        {
+            pacing.add_secs(
+                record.encode_secs.unwrap_or_default(),
+                record.before_encode_secs.unwrap_or_default(),
+                record.after_encode_secs.unwrap_or_default(),
+                record.source_size,
+            );
+        }
+        pacing
+    }
+
+    /// Add the stage timings of a finished job
+    pub fn add(&mut self, timings: &StageTimings, source_size: Option<u64>) {
+        if let Some(encode) = timings.get(Stage::Encode) {
+            self.add_secs(
+                encode.as_secs_f64(),
+                timings.before_encode().as_secs_f64(),
+                timings.after_encode().as_secs_f64(),
+                source_size,
+            );
+        }
+    }
+
+    fn add_secs(&mut self, encode: f64, before: f64, after: f64, source_size: Option<u64>) {
+        if encode <= 0.0 || !encode.is_finite() {
+            return;
+        }
+        self.encode_secs += encode;
+        self.before_secs += before.max(0.0);
+        self.after_secs += after.max(0.0);
+        if let Some(size) = source_size {
+            self.sized_bytes += size as f64;
+            self.sized_secs += encode;
+        }
+    }
+
+    /// Time before the encode per second of encoding
+    pub fn before_ratio(&self) -> f64 {
+        ratio(self.before_secs, self.encode_secs)
+    }
+
+    /// Time after the encode per second of encoding
+    pub fn after_ratio(&self) -> f64 {
+        ratio(self.after_secs, self.encode_secs)
+    }
+
+    /// Expected encode time of a source of `size` bytes
+    pub fn encode_time(&self, size: u64) -> Option<Duration> {
+        if self.sized_bytes <= 0.0 || self.sized_secs <= 0.0 {
+            return None;
+        }
+        Duration::try_from_secs_f64(size as f64 * self.sized_secs / self.sized_bytes).ok()
+    }
+}
+
+fn ratio(part: f64, encode: f64) -> f64 {
+    if encode > 0.0 { part / encode } else { 0.0 }
+}
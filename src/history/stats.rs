@@ -0,0 +1,121 @@
+//! Anonymous encode statistics, off unless turned on.
+//!
+//! Each record holds only what tuning the default presets needs: the encoder,
+//! the preset tier, the quality value, the VMAF it reached and the encode
+//! speed. No file names, paths, sizes or times go into it. With
+//! `stats.mode = "local"` records are only appended to a file in the data
+//! directory; with `"submit"` they are appended there too, so what was sent
+//! can be read back, and each finished batch is posted to `stats.endpoint`
+//! through curl.
+
+use super::HistoryRecord;
+use crate::config::Encoder;
+use crate::error::AppError;
+use crate::utils::data_dir;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Version of the record layout, raised when fields change meaning
+pub const STATS_SCHEMA: u32 = 1;
+
+/// Give up on a submission after this many seconds
+const SUBMIT_TIMEOUT_SECS: &str = "10";
+
+/// One finished encode, without anything identifying the file or the user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatsRecord {
+    pub schema: u32,
+    pub app_version: String,
+    pub encoder: Encoder,
+    /// Preset the file was encoded with, e.g. `full_hd_hdr`
+    pub preset: String,
+    /// Quality value (CRF/CQ) chosen
+    pub quality: u8,
+    pub vmaf: Option<f64>,
+    /// Seconds of video encoded per second
+    pub speed: Option<f64>,
+}
+
+impl StatsRecord {
+    /// Anonymous part of a history record of a `duration_secs` long video
+    pub fn from_history(record: &HistoryRecord, duration_secs: f64) -> Self {
+        Self {
+            schema: STATS_SCHEMA,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            encoder: record.encoder,
+            preset: record.preset.clone(),
+            quality: record.quality,
+            vmaf: record.vmaf.map(|v| (v * 10.0).round() / 10.0),
+            speed: record
+                .encode_secs
+                .filter(|&secs| secs > 0.0 && duration_secs > 0.0)
+                .map(|secs| (duration_secs / secs * 100.0).round() / 100.0),
+        }
+    }
+}
+
+/// Location of the local statistics file
+pub fn stats_path() -> PathBuf {
+    data_dir().join("stats.jsonl")
+}
+
+/// Append a record to the local statistics file
+pub fn append(record: &StatsRecord) -> Result<(), AppError> {
+    let path = stats_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Body posted for a batch: `{"records": [...]}`
+pub fn submission_body(records: &[StatsRecord]) -> Result<String, AppError> {
+    Ok(serde_json::to_string(
+        &serde_json::json!({ "records": records }),
+    )?)
+}
+
+/// Post a batch of records to `endpoint`
+pub fn submit(endpoint: &str, records: &[StatsRecord]) -> Result<(), AppError> {
+    let body = submission_body(records)?;
+    let mut child = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--max-time",
+            SUBMIT_TIMEOUT_SECS,
+            "--header",
+            "Content-Type: application/json",
+            "--user-agent",
+            concat!("av1converter/", env!("CARGO_PKG_VERSION")),
+            "--data-binary",
+            "@-",
+            endpoint,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::CommandExecution(format!("Failed to run curl: {}", e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(body.as_bytes())?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| AppError::CommandExecution(format!("curl failed: {}", e)))?;
+    if !output.status.success() {
+        return Err(AppError::CommandExecution(format!(
+            "Submitting statistics failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
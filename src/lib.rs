@@ -0,0 +1,30 @@
+//! AV1 conversion pipeline: analysis, encoding, muxing and VMAF verification.
+//!
+//! The `av1converter` binary is a terminal UI on top of this library; the same
+//! modules can be embedded to drive conversions from other tools.
+
+pub mod analyzer;
+pub mod app;
+pub mod audit;
+pub mod cleanup;
+pub mod cli;
+pub mod config;
+pub mod encoder;
+pub mod error;
+pub mod headless;
+pub mod history;
+pub mod notify;
+pub mod palette;
+pub mod pipeline;
+pub mod queue;
+pub mod server;
+pub mod tracks;
+pub mod ui;
+pub mod utils;
+pub mod verifier;
+
+pub use analyzer::analyze;
+pub use config::AppConfig;
+pub use encoder::{FullEncodeResult, run_encoding_pipeline};
+pub use error::AppError;
+pub use pipeline::{ConversionHandle, ConversionPipeline};
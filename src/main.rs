@@ -1,125 +1,557 @@
-mod analyzer;
-mod app;
-mod config;
-mod encoder;
-mod error;
-mod queue;
-mod tracks;
-mod ui;
-mod utils;
-mod verifier;
-
-use app::{App, ConfirmAction, Screen, TrackFocus};
+use av1converter::app::{self, App, ConfirmAction, FolderTask, HOME_MENU, Screen, TrackFocus};
+use av1converter::cli::{self, Mode};
+use av1converter::config::{
+    ConfigRow, Cycle, EncodingPresetsConfig, LockScope, PresetField, Setting, SpaceCheck,
+};
+use av1converter::encoder::command_builder::{build_ffmpeg_args, sample_variants};
+use av1converter::queue::{LiveQueue, QueuePublisher};
+use av1converter::utils::{InstanceLock, crash};
+use av1converter::{AppConfig, AppError, audit, cleanup, headless, queue, server, ui, utils};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    cursor::Show,
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+    },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
-use std::io;
+use std::io::{self, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// Rows moved by PageUp/PageDown in scrollable lists
+const FINISH_PAGE_SIZE: usize = 10;
 
-use crate::app::HOME_MENU;
+/// Longest wait after a headless run for its email and statistics to go out
+const BATCH_END_TIMEOUT: Duration = Duration::from_secs(90);
 
-fn main() -> io::Result<()> {
+#[tokio::main]
+async fn main() -> io::Result<()> {
+    let cli = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            std::process::exit(2);
+        }
+    };
+    if let Some(dir) = cli.config_dir {
+        utils::paths::set_config_dir(dir);
+    }
+    if cli.ignore_lock {
+        utils::lock::set_ignore_locks(true);
+    }
+
+    if let Mode::Completions(shell) = cli.mode {
+        print!("{}", cli::completions(shell));
+        return Ok(());
+    }
     let _log_guard = utils::init_logging();
 
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    match cli.mode {
+        Mode::DumpCommands => {
+            dump_commands();
+            return Ok(());
+        }
+        Mode::Cleanup(dir) => return clean_up(&dir),
+        Mode::Serve(address) => return run_server(address).await,
+        Mode::Attach => return attach().await,
+        Mode::Json => return run_headless(input_paths(cli.paths, cli.stdin)?, false).await,
+        Mode::PrintCommands => {
+            return run_headless(input_paths(cli.paths, cli.stdin)?, true).await;
+        }
+        Mode::Tui | Mode::Completions(_) => {}
+    }
+    let inputs = input_paths(cli.paths, cli.stdin)?;
+    install_panic_hook();
 
-    // Create app and run
     let mut app = App::new();
-    let res = run_app(&mut terminal, &mut app);
+    let _instance_lock = match take_instance_lock(&mut app) {
+        Ok(lock) => lock,
+        // Without files to convert, watching the running queue is all that's left
+        Err(AppError::Locked { .. }) if inputs.is_empty() => return attach().await,
+        Err(e) => exit_locked(e),
+    };
+    app.restore_session();
+    if inputs.is_empty() {
+        app.offer_saved_queue();
+    } else {
+        app.open_paths(&inputs);
+    }
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    app.start_update_check();
+
+    let (_terminal_guard, mut terminal) = open_terminal()?;
+    let res = run_app(&mut terminal, &mut app).await;
+    app.save_session();
+
+    // Restore terminal before printing anything
+    drop(_terminal_guard);
 
     if let Err(err) = res {
         eprintln!("Error: {:?}", err);
+        if let Ok(path) = crash::write_bundle(&format!("{:?}", err)) {
+            eprintln!(
+                "A crash report was written to {}; please attach it to a bug report.",
+                path.display()
+            );
+        }
     }
 
     Ok(())
 }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> io::Result<()> {
+/// Take the global instance lock if configured, exiting when another instance holds it
+fn instance_lock(app: &mut App) -> Option<InstanceLock> {
+    take_instance_lock(app).unwrap_or_else(|e| exit_locked(e))
+}
+
+/// Take the global instance lock if configured, publishing the queue for
+/// `--attach` while holding it
+fn take_instance_lock(app: &mut App) -> Result<Option<InstanceLock>, AppError> {
+    if app.config.output.instance_lock != LockScope::Global {
+        return Ok(None);
+    }
+    let lock = InstanceLock::global()?;
+    app.publisher = Some(QueuePublisher::new(LiveQueue::default_path()));
+    Ok(Some(lock))
+}
+
+fn exit_locked(e: AppError) -> ! {
+    eprintln!("{}", e);
+    eprintln!(
+        "Start with --attach to watch its queue, with --ignore-lock to run anyway, or set \
+         instance_lock = \"output_directory\" under [output] to only keep instances out of \
+         each other's folders."
+    );
+    std::process::exit(1);
+}
+
+/// Switch to the alternate screen in raw mode; dropping the guard switches back
+fn open_terminal() -> io::Result<(TerminalGuard, Terminal<CrosstermBackend<io::Stdout>>)> {
+    enable_raw_mode()?;
+    let guard = TerminalGuard;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    Ok((guard, Terminal::new(CrosstermBackend::new(stdout))?))
+}
+
+/// Follow the queue published by the instance holding the lock, read-only,
+/// until the user detaches
+async fn attach() -> io::Result<()> {
+    let path = LiveQueue::default_path();
+    let (_terminal_guard, mut terminal) = open_terminal()?;
+    let mut events = spawn_event_reader();
+    // The last state stays on screen once the instance exits
+    let mut live = LiveQueue::read(&path);
     loop {
-        app.process_progress_messages();
-
-        terminal.draw(|f| {
-            match app.current_screen.clone() {
-                Screen::Home => ui::render_home(f, app),
-                Screen::FileExplorer { .. } => ui::render_explorer(f, app),
-                Screen::FileConfirm => ui::render_file_confirm(f, app),
-                Screen::TrackConfig => ui::render_track_config(f, app),
-                Screen::Queue => ui::render_queue(f, app),
-                Screen::Finish => ui::render_finish(f, app),
-                Screen::Configuration => ui::render_config_screen(f, app),
+        let running = live.as_ref().is_some_and(LiveQueue::is_running);
+        terminal.draw(|f| ui::render_viewer(f, live.as_ref(), running))?;
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(Ok(Event::Key(key)))
+                    if key.kind == KeyEventKind::Press
+                        && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) =>
+                {
+                    return Ok(());
+                }
+                Some(Ok(Event::Resize(..))) => {
+                    terminal.autoresize()?;
+                    terminal.clear()?;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+                None => return Ok(()),
+            },
+            _ = tokio::time::sleep(Duration::from_secs(1)) => {
+                if let Some(next) = LiveQueue::read(&path) {
+                    live = Some(next);
+                }
             }
-            if app.confirm_dialog.is_some() {
-                ui::render_confirm_dialog(f, app);
+        }
+    }
+}
+
+/// Files and folders given as arguments, then the lines of stdin with `--stdin`
+///
+/// Exits when a path does not exist.
+fn input_paths(mut paths: Vec<PathBuf>, read_stdin: bool) -> io::Result<Vec<PathBuf>> {
+    if read_stdin {
+        for line in io::stdin().lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                paths.push(PathBuf::from(line));
             }
-        })?;
+        }
+    }
+    if let Some(missing) = paths.iter().find(|p| !p.exists()) {
+        eprintln!("Error: {} does not exist", missing.display());
+        std::process::exit(2);
+    }
+    Ok(paths)
+}
 
-        if event::poll(Duration::from_millis(100))?
-            && let Event::Key(key) = event::read()?
-            && key.kind == KeyEventKind::Press
-        {
-            handle_key(app, key.code);
+/// Encode `paths` without the UI, printing JSON events, or only print the
+/// FFmpeg commands with `commands_only`
+async fn run_headless(paths: Vec<PathBuf>, commands_only: bool) -> io::Result<()> {
+    if paths.is_empty() {
+        eprintln!(
+            "Error: {} needs the files or folders to convert",
+            if commands_only {
+                "--print-commands"
+            } else {
+                "--json"
+            }
+        );
+        std::process::exit(2);
+    }
+
+    let mut app = App::new();
+    let lock = instance_lock(&mut app);
+    app.queue_paths(&paths);
+    if app.queue.jobs.is_empty() {
+        eprintln!("Error: no video files found");
+        std::process::exit(2);
+    }
+    let mut out = io::stdout().lock();
+    if commands_only {
+        return headless::print_commands(&mut app, &mut out).await;
+    }
+    if !check_space(&mut app) {
+        drop(app.publisher.take());
+        drop(lock);
+        std::process::exit(2);
+    }
+    headless::run(&mut app, &mut out).await?;
+    app.finish_batch_end_tasks(BATCH_END_TIMEOUT).await;
+    let outcome = queue::RunSummary::of(&app.queue).outcome;
+    // Exiting skips destructors, so the lock is released first
+    drop(app.publisher.take());
+    drop(lock);
+    std::process::exit(outcome.exit_code());
+}
+
+/// Report volumes the queued outputs likely won't fit on; false when that
+/// blocks the run
+fn check_space(app: &mut App) -> bool {
+    if app.config.output.space_check == SpaceCheck::Off {
+        return true;
+    }
+    app.file_probes = app
+        .queue
+        .jobs
+        .iter()
+        .enumerate()
+        .filter(|(_, j)| j.disc_title.is_none())
+        .map(|(i, j)| (i, audit::probe(&j.path)))
+        .collect();
+    app.check_space();
+    let shortfalls = app.space_shortfalls();
+    if shortfalls.is_empty() {
+        return true;
+    }
+    let block = app.config.output.space_check == SpaceCheck::Block;
+    eprintln!(
+        "{}: outputs likely won't fit on {} volume(s)",
+        if block { "Error" } else { "Warning" },
+        shortfalls.len()
+    );
+    for need in shortfalls {
+        eprintln!("  {}", need.describe());
+    }
+    !block
+}
+
+/// Serve the HTTP control API on `address`, or the configured one
+async fn run_server(address: Option<String>) -> io::Result<()> {
+    let mut app = App::new();
+    let _instance_lock = instance_lock(&mut app);
+    let server = app.config.server.clone();
+    let address = address.unwrap_or(server.address);
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Error: cannot listen on {}: {}", address, e);
+            std::process::exit(2);
+        }
+    };
+    let token = server.token.filter(|t| !t.is_empty());
+    if token.is_none() && !listener.local_addr()?.ip().is_loopback() {
+        eprintln!(
+            "Error: set a token under [server] to serve on {}, other machines could control \
+             the converter otherwise",
+            address
+        );
+        std::process::exit(2);
+    }
+    eprintln!("Serving on http://{}", listener.local_addr()?);
+    server::serve(&mut app, listener, token).await
+}
+
+/// Print the FFmpeg command line of every encoder × HDR type × track layout
+fn dump_commands() {
+    let config = AppConfig::load();
+    for (name, params) in sample_variants(&config) {
+        let args: Vec<String> = build_ffmpeg_args(&params)
+            .iter()
+            .map(|a| utils::tools::shell_quote(a))
+            .collect();
+        println!("# {}\nffmpeg {}\n", name, args.join(" "));
+    }
+}
+
+/// List leftovers of earlier runs under `dir` and delete the marked ones after confirmation
+fn clean_up(dir: &Path) -> io::Result<()> {
+    let config = AppConfig::load();
+    utils::humanize::configure(&config.ui);
+    queue::configure_video_extensions(&config.input);
+    let leftovers = cleanup::scan(dir, &config.output.suffix);
+    if leftovers.is_empty() {
+        println!("Nothing to clean up in {}", dir.display());
+        return Ok(());
+    }
+
+    for item in &leftovers {
+        let mark = match (item.kind.removable(), item.selected) {
+            (false, _) => "   ",
+            (true, true) => "[x]",
+            (true, false) => "[ ]",
+        };
+        println!(
+            "{} {:<14} {}  [{}]",
+            mark,
+            item.kind.label(),
+            item.path.display(),
+            utils::format_file_size(item.size)
+        );
+    }
+
+    let (count, size) = leftovers
+        .iter()
+        .filter(|l| l.selected && l.kind.removable())
+        .fold((0, 0), |(count, size), l| (count + 1, size + l.size));
+    if count == 0 {
+        println!(
+            "\nNothing marked for deletion; outputs without a source and chunks are only listed"
+        );
+        return Ok(());
+    }
+
+    print!(
+        "\nDelete {} marked files ({})? [y/N] ",
+        count,
+        utils::format_file_size(size)
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        println!("Nothing deleted");
+        return Ok(());
+    }
+
+    let result = cleanup::remove(&leftovers);
+    for (path, error) in &result.failed {
+        eprintln!("Failed to delete {}: {}", path.display(), error);
+    }
+    println!(
+        "Deleted {} files, freed {}",
+        result.removed,
+        utils::format_file_size(result.freed)
+    );
+    Ok(())
+}
+
+/// Restores the terminal when dropped, including while unwinding from a panic
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Leave raw mode and the alternate screen; safe to call more than once
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        Show
+    );
+}
+
+/// Restore the terminal before a main-thread panic message is printed
+///
+/// Panics on worker threads are only logged so the TUI stays usable; the app
+/// notices the dead worker through its closed channel.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let bundle = crash::write_bundle(&format!(
+            "{}\n\n{}",
+            info,
+            std::backtrace::Backtrace::force_capture()
+        ));
+        if std::thread::current().name() == Some("main") {
+            restore_terminal();
+            default_hook(info);
+            match bundle {
+                Ok(path) => eprintln!(
+                    "A crash report was written to {}; please attach it to a bug report.",
+                    path.display()
+                ),
+                Err(e) => eprintln!("Failed to write a crash report: {}", e),
+            }
+        } else {
+            tracing::error!("Worker thread panicked: {}", info);
+        }
+    }));
+}
+
+async fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+) -> io::Result<()> {
+    let mut events = spawn_event_reader();
+    let mut redraw = true;
+
+    loop {
+        if redraw {
+            terminal.draw(|f| ui::render(f, app))?;
         }
 
+        // Redraw after input or worker updates; ticks only redraw moving timers
+        let (poll_interval, ticking) = (app.poll_interval(), app.ticking());
+        redraw = tokio::select! {
+            event = events.recv() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    let palette = key.code == KeyCode::Char('p')
+                        && key.modifiers.contains(KeyModifiers::CONTROL);
+                    if palette && app.confirm_dialog.is_none() {
+                        app.open_palette();
+                    } else {
+                        handle_key(app, key.code);
+                    }
+                    true
+                }
+                Some(Ok(Event::Resize(..))) => {
+                    // Start from a blank screen so no cells of the old layout linger
+                    terminal.autoresize()?;
+                    terminal.clear()?;
+                    true
+                }
+                Some(Ok(_)) => false,
+                Some(Err(e)) => return Err(e),
+                None => return Ok(()),
+            },
+            update = app.recv_background() => {
+                app.process_background(update);
+                true
+            }
+            _ = tokio::time::sleep(poll_interval) => ticking,
+        };
+        app.save_queue();
+
         if app.should_quit {
             return Ok(());
         }
     }
 }
 
+/// Read terminal events on a dedicated thread, since crossterm's reader blocks
+fn spawn_event_reader() -> UnboundedReceiver<io::Result<Event>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        loop {
+            let event = event::read();
+            let failed = event.is_err();
+            if tx.send(event).is_err() || failed {
+                break;
+            }
+        }
+    });
+    rx
+}
+
 fn handle_key(app: &mut App, key: KeyCode) {
     if app.confirm_dialog.is_some() {
         handle_confirm_dialog_key(app, key);
         return;
     }
+    if app.palette.is_some() {
+        handle_palette_key(app, key);
+        return;
+    }
 
     match &app.current_screen {
         Screen::Home => handle_home_key(app, key),
         Screen::FileExplorer { .. } => handle_explorer_key(app, key),
         Screen::FileConfirm => handle_file_confirm_key(app, key),
+        Screen::DiscTitles => handle_disc_titles_key(app, key),
+        Screen::Audit => handle_audit_key(app, key),
+        Screen::Cleanup => handle_cleanup_key(app, key),
         Screen::TrackConfig => handle_track_config_key(app, key),
         Screen::Queue => handle_queue_key(app, key),
         Screen::Finish => handle_finish_key(app, key),
         Screen::Configuration => handle_config_key(app, key),
+        Screen::Presets => handle_presets_key(app, key),
+    }
+}
+
+fn handle_palette_key(app: &mut App, key: KeyCode) {
+    let count = app.palette_matches().len();
+    let Some(palette) = app.palette.as_mut() else {
+        return;
+    };
+    match key {
+        KeyCode::Esc => app.close_palette(),
+        KeyCode::Enter => app.run_palette_selection(),
+        KeyCode::Up => palette.selected = palette.selected.saturating_sub(1),
+        KeyCode::Down if palette.selected + 1 < count => palette.selected += 1,
+        KeyCode::Backspace => {
+            palette.query.pop();
+            palette.selected = 0;
+        }
+        KeyCode::Char(c) => {
+            palette.query.push(c);
+            palette.selected = 0;
+        }
+        _ => {}
     }
 }
 
 fn handle_confirm_dialog_key(app: &mut App, key: KeyCode) {
     match key {
         KeyCode::Char('y') | KeyCode::Char('Y') => {
-            if let Some(action) = app.confirm_dialog.take() {
+            if let Some(action) = app.accept_confirm() {
                 execute_confirm_action(app, action);
             }
         }
         KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-            app.confirm_dialog = None;
+            app.dismiss_confirm();
         }
         KeyCode::Left | KeyCode::Right | KeyCode::Char('h') | KeyCode::Char('l') => {
             app.confirm_selection = !app.confirm_selection;
         }
+        KeyCode::Char('a') | KeyCode::Char(' ')
+            if app
+                .confirm_dialog
+                .as_ref()
+                .is_some_and(ConfirmAction::can_apply_to_all) =>
+        {
+            app.confirm_apply_all = !app.confirm_apply_all;
+        }
         KeyCode::Enter => {
             if app.confirm_selection {
-                if let Some(action) = app.confirm_dialog.take() {
+                if let Some(action) = app.accept_confirm() {
                     execute_confirm_action(app, action);
                 }
             } else {
-                app.confirm_dialog = None;
+                app.dismiss_confirm();
             }
         }
         _ => {}
@@ -134,6 +566,21 @@ fn execute_confirm_action(app: &mut App, action: ConfirmAction) {
         ConfirmAction::ExitApp => {
             app.should_quit = true;
         }
+        ConfirmAction::DeleteLeftovers => {
+            app.delete_leftovers();
+        }
+        ConfirmAction::ResetConfig => {
+            app.reset_config();
+        }
+        ConfirmAction::ConvertEfficientSources => {
+            app.confirm_queued_files();
+        }
+        ConfirmAction::StartWithoutSpace => {
+            app.proceed_past_space_check();
+        }
+        ConfirmAction::RestoreQueue => {
+            app.restore_queue();
+        }
     }
 }
 
@@ -143,22 +590,20 @@ fn handle_home_key(app: &mut App, key: KeyCode) {
             app.confirm_dialog = Some(ConfirmAction::ExitApp);
             app.confirm_selection = false;
         }
-        KeyCode::Up | KeyCode::Char('k') => {
-            if app.home_index > 0 {
-                app.home_index -= 1;
-            }
+        KeyCode::Up | KeyCode::Char('k') if app.home_index > 0 => {
+            app.home_index -= 1;
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if app.home_index < HOME_MENU.len() - 1 {
-                app.home_index += 1;
-            }
+        KeyCode::Down | KeyCode::Char('j') if app.home_index < HOME_MENU.len() - 1 => {
+            app.home_index += 1;
         }
         KeyCode::Enter => match app.home_index {
             0 => app.navigate_to_explorer(false, false), // Open video file
             1 => app.navigate_to_explorer(true, false),  // Open folder
             2 => app.navigate_to_explorer(true, true),   // Open folder recursive
-            3 => app.navigate_to_configuration(),        // Configuration
-            4 => {
+            3 => app.navigate_to_folder_task(FolderTask::Audit), // Audit folder
+            4 => app.navigate_to_folder_task(FolderTask::Cleanup), // Clean up folder
+            5 => app.navigate_to_configuration(),        // Configuration
+            6 => {
                 app.confirm_dialog = Some(ConfirmAction::ExitApp);
                 app.confirm_selection = false;
             }
@@ -188,24 +633,98 @@ fn handle_explorer_key(app: &mut App, key: KeyCode) {
 }
 
 fn handle_file_confirm_key(app: &mut App, key: KeyCode) {
+    app.clear_message();
+
     match key {
         KeyCode::Esc => app.cancel_file_confirm(),
-        KeyCode::Enter => app.confirm_queued_files(),
-        KeyCode::Up | KeyCode::Char('k') => {
-            if app.file_confirm_scroll > 0 {
-                app.file_confirm_scroll -= 1;
-            }
+        KeyCode::Enter => app.proceed_from_file_confirm(),
+        KeyCode::Char('p') => app.cycle_profile(),
+        KeyCode::Char('s') => app.toggle_simulate(),
+        KeyCode::Char('o') => {
+            app.config.performance.queue_order = app.config.performance.queue_order.cycle(true);
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if app.file_confirm_scroll < app.queue.jobs.len().saturating_sub(1) {
-                app.file_confirm_scroll += 1;
-            }
+        KeyCode::Char(c @ '1'..='9') => app.toggle_profile(c as usize - '1' as usize),
+        KeyCode::Up | KeyCode::Char('k') if app.file_confirm_scroll > 0 => {
+            app.file_confirm_scroll -= 1;
+        }
+        KeyCode::Down | KeyCode::Char('j')
+            if app.file_confirm_scroll < app.queue.jobs.len().saturating_sub(1) =>
+        {
+            app.file_confirm_scroll += 1;
         }
         _ => {}
     }
 }
 
+fn handle_disc_titles_key(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc => app.cancel_disc_titles(),
+        KeyCode::Enter => app.select_disc_title(),
+        KeyCode::Up | KeyCode::Char('k') if app.disc_title_index > 0 => {
+            app.disc_title_index -= 1;
+        }
+        KeyCode::Down | KeyCode::Char('j')
+            if app.disc_title_index < app.disc_titles.len().saturating_sub(1) =>
+        {
+            app.disc_title_index += 1;
+        }
+        _ => {}
+    }
+}
+
+fn handle_audit_key(app: &mut App, key: KeyCode) {
+    app.clear_message();
+
+    match key {
+        KeyCode::Esc => app.close_audit(),
+        KeyCode::Up | KeyCode::Char('k') => app.audit_select(app.audit_index.saturating_sub(1)),
+        KeyCode::Down | KeyCode::Char('j') => app.audit_select(app.audit_index + 1),
+        KeyCode::PageUp => app.audit_select(app.audit_index.saturating_sub(FINISH_PAGE_SIZE)),
+        KeyCode::PageDown => app.audit_select(app.audit_index + FINISH_PAGE_SIZE),
+        KeyCode::Char('e') => app.export_audit(),
+        _ => {}
+    }
+}
+
+fn handle_cleanup_key(app: &mut App, key: KeyCode) {
+    app.clear_message();
+
+    match key {
+        KeyCode::Esc => app.close_cleanup(),
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.leftover_select(app.leftover_index.saturating_sub(1));
+        }
+        KeyCode::Down | KeyCode::Char('j') => app.leftover_select(app.leftover_index + 1),
+        KeyCode::Char(' ') => app.toggle_leftover(),
+        KeyCode::Char('a') => app.toggle_all_leftovers(),
+        KeyCode::Char('d') | KeyCode::Enter => app.request_cleanup(),
+        _ => {}
+    }
+}
+
 fn handle_track_config_key(app: &mut App, key: KeyCode) {
+    if app.extra_args_input.is_some() {
+        handle_extra_args_key(app, key);
+        return;
+    }
+    if app.command_preview.is_some() {
+        match key {
+            KeyCode::Char('y') => app.copy_command_preview(),
+            _ => app.command_preview = None,
+        }
+        return;
+    }
+
+    if key == KeyCode::Esc {
+        app.leave_track_config();
+        return;
+    }
+    if key == KeyCode::Char('v') && app.encoding_active {
+        app.navigate_to_queue();
+        return;
+    }
+
+    // Nothing to configure while the file is still analyzed
     let job = match app.current_config_job() {
         Some(j) => j,
         None => return,
@@ -215,7 +734,6 @@ fn handle_track_config_key(app: &mut App, key: KeyCode) {
     let subtitle_count = job.subtitle_tracks.len();
 
     match key {
-        KeyCode::Esc => app.navigate_to_home(),
         KeyCode::Tab => {
             app.track_focus = match app.track_focus {
                 TrackFocus::Audio if subtitle_count > 0 => TrackFocus::Subtitle,
@@ -239,6 +757,15 @@ fn handle_track_config_key(app: &mut App, key: KeyCode) {
             }
             _ => {}
         },
+        KeyCode::Char(c @ ('K' | 'J')) if app.track_focus == TrackFocus::Audio => {
+            let cursor = app.audio_cursor;
+            if let Some(job) = app.current_config_job_mut()
+                && let Some(track) = job.audio_tracks.get(cursor)
+            {
+                let idx = track.index;
+                job.track_selection.move_audio(idx, c == 'K');
+            }
+        }
         KeyCode::Char(' ') => match app.track_focus {
             TrackFocus::Audio => {
                 let cursor = app.audio_cursor;
@@ -280,11 +807,52 @@ fn handle_track_config_key(app: &mut App, key: KeyCode) {
                 }
             }
         }
+        KeyCode::Char('m') => {
+            if let Some(job) = app.current_config_job_mut() {
+                job.toggle_still_image_hint();
+            }
+        }
+        KeyCode::Char('c') => {
+            if let Some(job) = app.current_config_job_mut() {
+                job.toggle_commentary();
+            }
+        }
+        KeyCode::Char('e') => {
+            app.extra_args_input = app.current_config_job().map(|j| j.extra_args.clone());
+        }
+        KeyCode::Char('d') => app.preview_command(),
+        KeyCode::Char('p') => app.cycle_profile(),
+        KeyCode::Char('r') => app.cycle_job_mode(),
+        KeyCode::Char(c @ '1'..='9') => app.toggle_job_profile(c as usize - '1' as usize),
         KeyCode::Enter => app.confirm_track_config(),
         _ => {}
     }
 }
 
+fn handle_extra_args_key(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc => app.extra_args_input = None,
+        KeyCode::Enter => {
+            if let Some(input) = app.extra_args_input.take()
+                && let Some(job) = app.current_config_job_mut()
+            {
+                job.extra_args = input.trim().to_string();
+            }
+        }
+        KeyCode::Backspace => {
+            if let Some(input) = app.extra_args_input.as_mut() {
+                input.pop();
+            }
+        }
+        KeyCode::Char(c) => {
+            if let Some(input) = app.extra_args_input.as_mut() {
+                input.push(c);
+            }
+        }
+        _ => {}
+    }
+}
+
 fn handle_queue_key(app: &mut App, key: KeyCode) {
     match key {
         KeyCode::Esc if app.encoding_active => {
@@ -294,6 +862,18 @@ fn handle_queue_key(app: &mut App, key: KeyCode) {
         KeyCode::Enter if !app.encoding_active => {
             app.navigate_to_finish();
         }
+        KeyCode::Char('x') if app.encoding_active => app.cancel_current_job(),
+        KeyCode::Char('s') if app.encoding_active => app.skip_selected_job(),
+        KeyCode::Char('p') if app.encoding_active => app.toggle_pause(),
+        KeyCode::Char('+') | KeyCode::Char('=') if app.encoding_active => {
+            app.adjust_live_vmaf_threshold(1.0)
+        }
+        KeyCode::Char('-') if app.encoding_active => app.adjust_live_vmaf_threshold(-1.0),
+        KeyCode::Char('d') if app.encoding_active => app.toggle_live_delete_source(),
+        KeyCode::Up | KeyCode::Char('k') => app.queue_select(app.queue_index.saturating_sub(1)),
+        KeyCode::Down | KeyCode::Char('j') => app.queue_select(app.queue_index + 1),
+        KeyCode::Char('o') => app.play_output(app.last_finished_output()),
+        KeyCode::Char('t') if app.queue.configuring() => app.navigate_to_track_config(),
         _ => {}
     }
 }
@@ -305,30 +885,156 @@ fn handle_finish_key(app: &mut App, key: KeyCode) {
             app.confirm_selection = false;
         }
         KeyCode::Enter => app.reset(),
+        KeyCode::Up | KeyCode::Char('k') => app.finish_select(app.finish_index.saturating_sub(1)),
+        KeyCode::Down | KeyCode::Char('j') => app.finish_select(app.finish_index + 1),
+        KeyCode::PageUp => app.finish_select(app.finish_index.saturating_sub(FINISH_PAGE_SIZE)),
+        KeyCode::PageDown => app.finish_select(app.finish_index + FINISH_PAGE_SIZE),
+        KeyCode::Char('c') => app.copy_selected_output_path(),
+        KeyCode::Char('o') => app.play_selected_output(),
+        KeyCode::Char('f') => app.open_selected_output_folder(),
+        KeyCode::Char('r') => app.retry_selected_job(),
+        KeyCode::Home => app.finish_select(0),
+        KeyCode::End => app.finish_select(usize::MAX),
         _ => {}
     }
 }
 
 fn handle_config_key(app: &mut App, key: KeyCode) {
-    let config_item_count = 10; // Number of config items
+    if app.config_searching {
+        handle_config_search_key(app, key);
+        return;
+    }
+    if app.config_show_diff {
+        handle_config_diff_key(app, key);
+        return;
+    }
 
     match key {
+        KeyCode::Esc if !app.config_filter.is_empty() => app.set_config_filter(String::new()),
         KeyCode::Esc => app.navigate_to_home(),
-        KeyCode::Up | KeyCode::Char('k') => {
-            if app.config_selected > 0 {
-                app.config_selected -= 1;
+        KeyCode::Char('/') => app.config_searching = true,
+        KeyCode::Up | KeyCode::Char('k') => app.move_config_cursor(false),
+        KeyCode::Down | KeyCode::Char('j') => app.move_config_cursor(true),
+        KeyCode::Left | KeyCode::Char('h') | KeyCode::Right | KeyCode::Char('l') => {
+            let forward = matches!(key, KeyCode::Right | KeyCode::Char('l'));
+            match app.selected_config_row() {
+                Some(ConfigRow::Section(section)) => {
+                    app.set_config_section_collapsed(section, !forward);
+                }
+                Some(ConfigRow::Setting(setting)) => adjust_config_value(app, setting, forward),
+                None => {}
             }
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if app.config_selected < config_item_count - 1 {
-                app.config_selected += 1;
+        KeyCode::Enter | KeyCode::Char(' ') => match app.selected_config_row() {
+            Some(ConfigRow::Section(section)) => {
+                let collapsed = app.config_collapsed.contains(&section);
+                app.set_config_section_collapsed(section, !collapsed);
             }
+            Some(ConfigRow::Setting(Setting::Preset(name))) => app.navigate_to_presets(Some(name)),
+            _ => {}
+        },
+        KeyCode::Char('p') => app.navigate_to_presets(None),
+        KeyCode::Char('r') => match app.selected_config_row() {
+            Some(ConfigRow::Setting(setting)) => app.reset_setting(&setting.key()),
+            Some(ConfigRow::Section(section)) => {
+                for setting in Setting::ALL.iter().filter(|s| s.section() == section) {
+                    app.reset_setting(&setting.key());
+                }
+                app.set_message(&format!(
+                    "Reset the {} settings to defaults",
+                    section.label()
+                ));
+            }
+            None => {}
+        },
+        KeyCode::Char('R') => {
+            app.confirm_dialog = Some(ConfirmAction::ResetConfig);
+            app.confirm_selection = false;
         }
+        KeyCode::Char('d') => {
+            app.config_show_diff = true;
+            app.config_diff_selected = 0;
+        }
+        KeyCode::Char('s') => {
+            if let Err(e) = app.config.save() {
+                tracing::warn!("Failed to save config: {:?}", e);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_presets_key(app: &mut App, key: KeyCode) {
+    if let Some(input) = app.preset_input.as_mut() {
+        match key {
+            KeyCode::Esc => {
+                app.preset_input = None;
+                app.clear_message();
+            }
+            KeyCode::Enter => app.commit_preset_input(),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() && input.len() < 3 => input.push(c),
+            _ => {}
+        }
+        return;
+    }
+
+    let rows = EncodingPresetsConfig::NAMES.len();
+    let columns = PresetField::ALL.len();
+    match key {
+        KeyCode::Esc => {
+            app.clear_message();
+            app.current_screen = Screen::Configuration;
+        }
+        KeyCode::Up | KeyCode::Char('k') => app.preset_row = app.preset_row.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => app.preset_row = (app.preset_row + 1).min(rows - 1),
         KeyCode::Left | KeyCode::Char('h') => {
-            adjust_config_value(app, app.config_selected, false);
+            app.preset_column = app.preset_column.saturating_sub(1);
+        }
+        KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+            app.preset_column = (app.preset_column + 1).min(columns - 1);
         }
-        KeyCode::Right | KeyCode::Char('l') => {
-            adjust_config_value(app, app.config_selected, true);
+        KeyCode::Char('+') | KeyCode::Char('=') => app.adjust_preset(true),
+        KeyCode::Char('-') => app.adjust_preset(false),
+        KeyCode::Char(c) if c.is_ascii_digit() => app.preset_input = Some(c.to_string()),
+        KeyCode::Enter => {
+            let (name, field) = app.selected_preset_cell();
+            app.preset_input = app
+                .config
+                .presets
+                .get(name)
+                .map(|preset| preset.field(field).to_string());
+        }
+        KeyCode::Char('s') => match app.config.save() {
+            Ok(()) => app.set_message("Presets saved"),
+            Err(e) => app.set_message(&format!("Failed to save config: {}", e)),
+        },
+        _ => {}
+    }
+}
+
+/// The list of values that differ from the defaults
+fn handle_config_diff_key(app: &mut App, key: KeyCode) {
+    let differences = app.config_differences();
+    match key {
+        KeyCode::Esc | KeyCode::Char('d') => app.config_show_diff = false,
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.config_diff_selected = app.config_diff_selected.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.config_diff_selected =
+                (app.config_diff_selected + 1).min(differences.len().saturating_sub(1));
+        }
+        KeyCode::Char('r') => {
+            if let Some(difference) = differences.get(app.config_diff_selected) {
+                app.reset_setting(&difference.key);
+            }
+        }
+        KeyCode::Char('R') => {
+            app.confirm_dialog = Some(ConfirmAction::ResetConfig);
+            app.confirm_selection = false;
         }
         KeyCode::Char('s') => {
             if let Err(e) = app.config.save() {
@@ -339,41 +1045,51 @@ fn handle_config_key(app: &mut App, key: KeyCode) {
     }
 }
 
-fn adjust_config_value(app: &mut App, index: usize, increase: bool) {
-    match index {
-        0 => {
-            // Encoder - cycle through options
-            use crate::config::Encoder;
-            let encoders = [Encoder::SvtAv1, Encoder::Nvenc, Encoder::Qsv, Encoder::Amf];
-            let current = encoders
-                .iter()
-                .position(|e| *e == app.config.encoder)
-                .unwrap_or(0);
-            let next = if increase {
-                (current + 1) % encoders.len()
-            } else {
-                (current + encoders.len() - 1) % encoders.len()
-            };
-            app.config.encoder = encoders[next];
+/// Typing into the Configuration search box; the list filters as you type
+fn handle_config_search_key(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc => {
+            app.config_searching = false;
+            app.set_config_filter(String::new());
         }
-        1 => {
-            // VMAF Threshold
+        KeyCode::Enter => app.config_searching = false,
+        KeyCode::Backspace => {
+            let mut filter = app.config_filter.clone();
+            filter.pop();
+            app.set_config_filter(filter);
+        }
+        KeyCode::Char(c) => {
+            let filter = format!("{}{}", app.config_filter, c);
+            app.set_config_filter(filter);
+        }
+        KeyCode::Up => app.move_config_cursor(false),
+        KeyCode::Down => app.move_config_cursor(true),
+        _ => {}
+    }
+}
+
+fn adjust_config_value(app: &mut App, setting: Setting, increase: bool) {
+    match setting {
+        Setting::Encoder => {
+            // Cycle through the ones FFmpeg can drive
+            app.cycle_encoder(increase);
+        }
+        Setting::VmafThreshold => {
             let delta = if increase { 1.0 } else { -1.0 };
             app.config.quality.vmaf_threshold =
                 (app.config.quality.vmaf_threshold + delta).clamp(0.0, 100.0);
         }
-        2 => {
-            // VMAF Enabled
-            app.config.quality.vmaf_enabled = !app.config.quality.vmaf_enabled;
+        Setting::VmafEnabled => {
+            if let Err(reason) = app.toggle_vmaf() {
+                app.set_message(&format!("VMAF verification unavailable: {}", reason));
+            }
         }
-        3 => {
-            // SVT-AV1 Preset
+        Setting::SvtPreset => {
             let delta: i8 = if increase { 1 } else { -1 };
             let new_val = app.config.performance.svt_preset as i8 + delta;
             app.config.performance.svt_preset = new_val.clamp(0, 13) as u8;
         }
-        4 => {
-            // NVENC Preset - cycle
+        Setting::NvencPreset => {
             let presets = ["p1", "p2", "p3", "p4", "p5", "p6", "p7"];
             let current = presets
                 .iter()
@@ -386,10 +1102,34 @@ fn adjust_config_value(app: &mut App, index: usize, increase: bool) {
             };
             app.config.performance.nvenc_preset = presets[next].to_string();
         }
-        7 => {
-            // Same Directory Output
+        Setting::AmfPreset => {
+            app.config.performance.amf_preset = app.config.performance.amf_preset.cycle(increase);
+        }
+        Setting::QueueOrder => {
+            app.config.performance.queue_order = app.config.performance.queue_order.cycle(increase);
+        }
+        Setting::IoPriority => {
+            app.config.performance.io_priority = app.config.performance.io_priority.cycle(increase);
+        }
+        Setting::SameDirectory => {
             app.config.output.same_directory = !app.config.output.same_directory;
         }
-        _ => {} // String fields not adjustable via arrow keys
+        Setting::OutputNaming => {
+            app.config.output.naming = app.config.output.naming.cycle(increase);
+        }
+        Setting::ExcludeCommentary => {
+            app.config.tracks.exclude_commentary = !app.config.tracks.exclude_commentary;
+        }
+        Setting::SubtitleRule => {
+            app.config.tracks.subtitle_rule = app.config.tracks.subtitle_rule.cycle(increase);
+        }
+        Setting::Muxer => {
+            use av1converter::config::Muxer;
+            app.config.output.muxer = match app.config.output.muxer {
+                Muxer::Ffmpeg => Muxer::Mkvmerge,
+                Muxer::Mkvmerge => Muxer::Ffmpeg,
+            };
+        }
+        _ => {} // String fields and presets not adjustable via arrow keys
     }
 }
@@ -0,0 +1,125 @@
+use crate::config::NotificationConfig;
+use crate::error::AppError;
+use crate::utils::data_dir;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// Give up connecting to the SMTP server after this many seconds
+const CONNECT_TIMEOUT_SECS: &str = "20";
+
+/// Give up on sending after this many seconds, so a hung server can't stall the batch
+const SEND_TIMEOUT_SECS: &str = "60";
+
+/// Send a plain-text email through the configured SMTP server using curl
+///
+/// Credentials are passed to curl on stdin so they never show up in the process list.
+/// The message is written to a file in the data directory only this user can read.
+pub fn send_email(config: &NotificationConfig, subject: &str, body: &str) -> Result<(), AppError> {
+    if config.smtp_url.is_empty() || config.email_from.is_empty() || config.email_to.is_empty() {
+        return Err(AppError::Config(
+            "Email notifications need smtp_url, email_from and email_to".to_string(),
+        ));
+    }
+
+    let message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}",
+        config.email_from,
+        config.email_to.join(", "),
+        subject,
+        body.replace('\n', "\r\n")
+    );
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let message_path = data_dir().join(format!("mail_{}_{}.txt", std::process::id(), nanos));
+    write_private(&message_path, &message).map_err(|e| AppError::Io {
+        path: message_path.clone(),
+        operation: "write email message",
+        message: e.to_string(),
+    })?;
+
+    let mut args = vec![
+        "--silent".to_string(),
+        "--show-error".to_string(),
+        "--connect-timeout".to_string(),
+        CONNECT_TIMEOUT_SECS.to_string(),
+        "--max-time".to_string(),
+        SEND_TIMEOUT_SECS.to_string(),
+        "--url".to_string(),
+        config.smtp_url.clone(),
+        "--mail-from".to_string(),
+        config.email_from.clone(),
+        "--upload-file".to_string(),
+        message_path.to_string_lossy().to_string(),
+        "--config".to_string(),
+        "-".to_string(),
+    ];
+    // Upgrade plain SMTP connections with STARTTLS, failing rather than
+    // sending the credentials in the clear when the server doesn't offer it
+    if config.smtp_url.starts_with("smtp://") {
+        args.push("--ssl-reqd".to_string());
+    }
+    for rcpt in &config.email_to {
+        args.extend(["--mail-rcpt".to_string(), rcpt.clone()]);
+    }
+
+    let result = Command::new("curl")
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take()
+                && let Some(ref username) = config.smtp_username
+            {
+                let password = config.smtp_password.as_deref().unwrap_or("");
+                writeln!(
+                    stdin,
+                    "user = \"{}:{}\"",
+                    escape(username),
+                    escape(password)
+                )?;
+            }
+            child.wait_with_output()
+        });
+
+    let _ = std::fs::remove_file(&message_path);
+
+    let output =
+        result.map_err(|e| AppError::CommandExecution(format!("Failed to run curl: {}", e)))?;
+    if !output.status.success() {
+        return Err(AppError::CommandExecution(format!(
+            "Sending email failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    info!("Sent email notification to {}", config.email_to.join(", "));
+    Ok(())
+}
+
+/// Create `path` readable by this user only, refusing to follow or reuse an existing file
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+/// Escape a value for a quoted curl config string
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,5 @@
+pub mod email;
+pub mod report;
+
+pub use email::send_email;
+pub use report::{batch_report, batch_subject};
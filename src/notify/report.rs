@@ -0,0 +1,180 @@
+use crate::encoder::deletion::deletion_log_path;
+use crate::queue::{JobStatus, QueueState};
+use crate::utils::{format_duration, format_file_size};
+use std::fmt::Write;
+
+/// Width of the per-job stage timeline bars
+const REPORT_BAR_WIDTH: usize = 20;
+
+/// One-line subject summarising a finished batch
+pub fn batch_subject(queue: &QueueState) -> String {
+    let mut subject = format!(
+        "AV1 Converter: {} converted, {} skipped",
+        queue.converted_count, queue.skipped_count
+    );
+    if queue.cancelled_count > 0 {
+        let _ = write!(subject, ", {} cancelled", queue.cancelled_count);
+    }
+    let _ = write!(subject, ", {} failed", queue.error_count);
+    subject
+}
+
+/// Plain-text report of a finished batch: totals plus one line per file
+pub fn batch_report(queue: &QueueState) -> String {
+    let mut report = String::new();
+    let (total_saved, saved_str) = queue.total_space_saved();
+
+    let _ = writeln!(report, "AV1 Converter batch report");
+    let _ = writeln!(report);
+    if queue.jobs.iter().any(|job| job.simulation.is_some()) {
+        let _ = writeln!(
+            report,
+            "Simulation: nothing was encoded, written or deleted; output sizes are estimates"
+        );
+        let _ = writeln!(report);
+    }
+    let _ = writeln!(
+        report,
+        "Converted: {}   Skipped: {}   Skipped by you: {}   Cancelled: {}   Errors: {}",
+        queue.converted_count,
+        queue.skipped_count,
+        queue.user_skipped_count,
+        queue.cancelled_count,
+        queue.error_count
+    );
+    if total_saved > 0 {
+        let savings = queue.space_savings();
+        if savings.audio > 0 || savings.subtitles > 0 {
+            let _ = writeln!(
+                report,
+                "Total space saved: {} ({})",
+                saved_str,
+                savings.summary()
+            );
+        } else {
+            let _ = writeln!(report, "Total space saved: {}", saved_str);
+        }
+    }
+    if let Some(elapsed) = queue.elapsed_time() {
+        let _ = writeln!(report, "Total time: {}", format_duration(elapsed));
+    }
+    let stage_timings = queue.total_stage_timings();
+    if !stage_timings.is_empty() {
+        let _ = writeln!(report, "Time per stage: {}", stage_timings.summary());
+        if let Some((stage, share)) = stage_timings.slowest() {
+            let _ = writeln!(
+                report,
+                "Most time spent in: {} ({:.0}%)",
+                stage.name(),
+                share * 100.0
+            );
+        }
+    }
+
+    let _ = writeln!(report);
+    let _ = writeln!(report, "Files:");
+    for job in &queue.jobs {
+        let sizes = match (job.source_size, job.output_size, job.size_reduction()) {
+            (Some(source), Some(output), Some((_, percent))) => format!(
+                "  {} -> {} (-{:.1}%)",
+                format_file_size(source),
+                format_file_size(output),
+                percent
+            ),
+            _ => String::new(),
+        };
+
+        let line = match &job.status {
+            JobStatus::Done => format!("[OK]   {}{}", job.display_name(), sizes),
+            JobStatus::DoneWithVmaf { score } => {
+                format!("[OK]   {}{}  VMAF {:.1}", job.display_name(), sizes, score)
+            }
+            JobStatus::QualityWarning { vmaf, threshold } => format!(
+                "[WARN] {}{}  VMAF {:.1} < {:.0}",
+                job.display_name(),
+                sizes,
+                vmaf,
+                threshold
+            ),
+            JobStatus::Skipped { reason, .. } => {
+                format!("[SKIP] {} ({})", job.display_name(), reason)
+            }
+            JobStatus::Error { message, kind } => {
+                format!(
+                    "[FAIL] {}: {} ({})",
+                    job.display_name(),
+                    kind.label(),
+                    message
+                )
+            }
+            _ => format!("[----] {}", job.display_name()),
+        };
+        let _ = writeln!(report, "  {}", line);
+
+        let (audio, subtitles) = job.dropped_track_savings();
+        if job.size_reduction().is_some() && audio + subtitles > 0 {
+            let _ = writeln!(
+                report,
+                "         dropped tracks: ~{} audio, ~{} subtitles",
+                format_file_size(audio),
+                format_file_size(subtitles)
+            );
+        }
+        if let Some(ref simulated) = job.simulation {
+            let _ = writeln!(
+                report,
+                "         {} {} {}, source would be {}",
+                simulated.encoder.display_name(),
+                simulated.encoder.quality_label(),
+                simulated.crf,
+                if simulated.deletes_source {
+                    "deleted"
+                } else {
+                    "kept"
+                }
+            );
+            let _ = writeln!(report, "         {}", simulated.command);
+        } else if let Some(ref provenance) = job.provenance {
+            let _ = writeln!(
+                report,
+                "         made with {}, {}",
+                provenance.app, provenance.tools
+            );
+            let _ = writeln!(report, "         {}", provenance.command);
+        }
+        if job.source_deleted {
+            let _ = writeln!(report, "         source deleted");
+        } else if let Some(ref reason) = job.source_kept_reason {
+            let _ = writeln!(report, "         source kept: {}", reason);
+        }
+        for issue in &job.validation_issues {
+            let _ = writeln!(report, "         validation: {}", issue.describe());
+        }
+        for step in &job.steps.steps {
+            let _ = writeln!(
+                report,
+                "         {} {}{}",
+                step.status.symbol(),
+                step.action.display_name(),
+                step.status
+                    .note()
+                    .map(|note| format!(" ({})", note))
+                    .unwrap_or_default()
+            );
+        }
+        for row in job.stage_timings.timeline_lines(REPORT_BAR_WIDTH) {
+            let _ = writeln!(report, "         {}", row);
+        }
+    }
+
+    if queue.jobs.iter().any(|job| job.source_deleted) {
+        let _ = writeln!(report);
+        let _ = writeln!(
+            report,
+            "Removed sources are logged in {}",
+            deletion_log_path().display()
+        );
+    }
+
+    report
+}
@@ -0,0 +1,105 @@
+//! Command palette: quick actions picked by fuzzy name instead of per-screen keys.
+
+/// An action the command palette can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteAction {
+    OpenFile,
+    OpenFolder,
+    OpenFolderRecursive,
+    AuditFolder,
+    CleanUpFolder,
+    /// Encode the queued files, skipping the remaining track configuration
+    StartQueue,
+    ToggleVmaf,
+    SwitchEncoder,
+    OpenConfiguration,
+    SaveConfiguration,
+    /// Pause the running queue, or resume it when paused
+    PauseQueue,
+    CancelEncoding,
+    GoHome,
+    Quit,
+}
+
+impl PaletteAction {
+    pub const ALL: [PaletteAction; 14] = [
+        PaletteAction::OpenFile,
+        PaletteAction::OpenFolder,
+        PaletteAction::OpenFolderRecursive,
+        PaletteAction::AuditFolder,
+        PaletteAction::CleanUpFolder,
+        PaletteAction::StartQueue,
+        PaletteAction::ToggleVmaf,
+        PaletteAction::SwitchEncoder,
+        PaletteAction::OpenConfiguration,
+        PaletteAction::SaveConfiguration,
+        PaletteAction::PauseQueue,
+        PaletteAction::CancelEncoding,
+        PaletteAction::GoHome,
+        PaletteAction::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaletteAction::OpenFile => "Open video file",
+            PaletteAction::OpenFolder => "Open folder",
+            PaletteAction::OpenFolderRecursive => "Open folder (recursive)",
+            PaletteAction::AuditFolder => "Audit folder",
+            PaletteAction::CleanUpFolder => "Clean up folder",
+            PaletteAction::StartQueue => "Start queue",
+            PaletteAction::ToggleVmaf => "Toggle VMAF verification",
+            PaletteAction::SwitchEncoder => "Switch encoder",
+            PaletteAction::OpenConfiguration => "Open configuration",
+            PaletteAction::SaveConfiguration => "Save configuration",
+            PaletteAction::PauseQueue => "Pause or resume queue",
+            PaletteAction::CancelEncoding => "Cancel encoding",
+            PaletteAction::GoHome => "Go to home screen",
+            PaletteAction::Quit => "Quit",
+        }
+    }
+}
+
+/// Typed query and highlighted entry of the open palette
+#[derive(Debug, Clone, Default)]
+pub struct PaletteState {
+    pub query: String,
+    pub selected: usize,
+}
+
+/// Actions whose label fuzzy-matches `query`, best match first
+///
+/// Ties keep the order of `actions`, so an empty query lists them unchanged.
+pub fn filter(query: &str, actions: &[PaletteAction]) -> Vec<PaletteAction> {
+    let mut scored: Vec<(i32, usize, PaletteAction)> = actions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &action)| Some((fuzzy_score(query, action.label())?, i, action)))
+        .collect();
+    scored.sort_by_key(|&(score, i, _)| (std::cmp::Reverse(score), i));
+    scored.into_iter().map(|(_, _, action)| action).collect()
+}
+
+/// Score how well `query` matches `text` as a case-insensitive subsequence
+///
+/// Consecutive characters and matches at word starts score higher; `None`
+/// when some query character is missing.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+
+    for qc in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|&c| c == qc)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
@@ -0,0 +1,296 @@
+use crate::analyzer::{self, VideoMetadata};
+use crate::config::{AppConfig, OutputConfig, OutputNaming, SharedConfig};
+use crate::encoder::{
+    EncodeOverrides, EncodeProgress, EventCallback, FullEncodeResult, PipelineEvent,
+    ProgressCallback, run_encoding_pipeline,
+};
+use crate::error::AppError;
+use crate::queue::is_video_file;
+use crate::tracks::TrackSelection;
+use crate::utils::naming::{jellyfin_version_name, render_name, resolution_label};
+use crate::utils::tools;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Builder for converting a single file through the full pipeline
+///
+/// Conversions run on the tokio runtime, so `spawn` and `run` must be called from
+/// within one.
+///
+/// ```no_run
+/// use av1converter::{AppConfig, ConversionPipeline};
+///
+/// # async fn convert() {
+/// let handle = ConversionPipeline::new("movie.mkv")
+///     .config(AppConfig::load())
+///     .on_progress(|p| println!("{:.1}%", p.percent))
+///     .spawn();
+/// let result = handle.join().await;
+/// # }
+/// ```
+pub struct ConversionPipeline {
+    input: PathBuf,
+    output: Option<PathBuf>,
+    config: AppConfig,
+    tracks: TrackSelection,
+    overrides: EncodeOverrides,
+    metadata: Option<VideoMetadata>,
+    progress_callback: Option<ProgressCallback>,
+    event_callback: Option<EventCallback>,
+    cancel: CancellationToken,
+}
+
+impl ConversionPipeline {
+    /// Start a pipeline for an input file with the default configuration
+    pub fn new(input: impl Into<PathBuf>) -> Self {
+        Self {
+            input: input.into(),
+            output: None,
+            config: AppConfig::default(),
+            tracks: TrackSelection::default(),
+            overrides: EncodeOverrides::default(),
+            metadata: None,
+            progress_callback: None,
+            event_callback: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Output path (defaults to the input name with the configured suffix and container)
+    pub fn output(mut self, output: impl Into<PathBuf>) -> Self {
+        self.output = Some(output.into());
+        self
+    }
+
+    /// Configuration to encode with
+    pub fn config(mut self, config: AppConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Audio and subtitle tracks to keep (all tracks when empty)
+    pub fn tracks(mut self, tracks: TrackSelection) -> Self {
+        self.tracks = tracks;
+        self
+    }
+
+    /// Per-file adjustments on top of the preset parameters
+    pub fn overrides(mut self, overrides: EncodeOverrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Reuse metadata from an earlier analysis instead of probing again
+    pub fn metadata(mut self, metadata: VideoMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Called with encoding progress
+    pub fn on_progress(mut self, callback: impl FnMut(EncodeProgress) + Send + 'static) -> Self {
+        self.progress_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Called with intermediate pipeline events (stage timings, remux results)
+    pub fn on_event(mut self, callback: impl FnMut(PipelineEvent) + Send + 'static) -> Self {
+        self.event_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Cancel through an existing token, e.g. a child of a batch-wide token
+    pub fn cancel_token(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Jellyfin version name of the output, e.g. `1080p AV1`
+    fn version_label(&self) -> String {
+        match self.metadata {
+            Some(ref m) => format!("{} AV1", resolution_label(m.width, m.height)),
+            None => "AV1".to_string(),
+        }
+    }
+
+    /// Output path the pipeline will write
+    pub fn output_path(&self) -> PathBuf {
+        self.output.clone().unwrap_or_else(|| {
+            output_path_for(
+                &self.input,
+                None,
+                &self.config.output,
+                &self.config.output.suffix,
+                &self.version_label(),
+                &self.config.output.container,
+            )
+        })
+    }
+
+    /// Run the conversion on the current task
+    pub async fn run(self) -> Result<FullEncodeResult, AppError> {
+        tools::configure(&self.config.tools);
+        let output = self.output_path();
+        let input = self.input.to_string_lossy().to_string();
+
+        let metadata = match self.metadata {
+            Some(m) => m,
+            None => {
+                let path = input.clone();
+                tokio::task::spawn_blocking(move || analyzer::analyze(&path))
+                    .await
+                    .map_err(|e| AppError::Analysis(format!("Analysis task failed: {}", e)))??
+                    .metadata
+            }
+        };
+
+        Ok(run_encoding_pipeline(
+            &input,
+            &output.to_string_lossy(),
+            &metadata,
+            self.tracks,
+            &self.overrides,
+            &SharedConfig::new(self.config),
+            self.progress_callback,
+            self.event_callback,
+            self.cancel,
+        )
+        .await)
+    }
+
+    /// Run the conversion as a background task
+    pub fn spawn(mut self) -> ConversionHandle {
+        let percent = Arc::new(AtomicU32::new(0));
+        let latest = Arc::new(Mutex::new(EncodeProgress::default()));
+        let cancel = self.cancel.clone();
+
+        // Track progress for the handle, then forward to the user's callback
+        let mut user_callback = self.progress_callback.take();
+        let (percent_w, latest_w) = (percent.clone(), latest.clone());
+        self.progress_callback = Some(Box::new(move |progress: EncodeProgress| {
+            percent_w.store(progress.percent.to_bits(), Ordering::Relaxed);
+            if let Ok(mut l) = latest_w.lock() {
+                *l = progress;
+            }
+            if let Some(ref mut cb) = user_callback {
+                cb(progress);
+            }
+        }));
+
+        ConversionHandle {
+            task: tokio::spawn(self.run()),
+            percent,
+            latest,
+            cancel,
+        }
+    }
+}
+
+/// Handle to a conversion running as a background task
+///
+/// The handle is `Send`, so it can be moved to whichever task supervises the conversion.
+pub struct ConversionHandle {
+    task: JoinHandle<Result<FullEncodeResult, AppError>>,
+    percent: Arc<AtomicU32>,
+    latest: Arc<Mutex<EncodeProgress>>,
+    cancel: CancellationToken,
+}
+
+impl ConversionHandle {
+    /// Encoding progress in percent (0-100)
+    pub fn percent(&self) -> f32 {
+        f32::from_bits(self.percent.load(Ordering::Relaxed))
+    }
+
+    /// Latest progress snapshot including speed and bitrate
+    pub fn progress(&self) -> EncodeProgress {
+        self.latest.lock().map(|p| *p).unwrap_or_default()
+    }
+
+    /// Ask the conversion to stop; `join` then returns `FullEncodeResult::Cancelled`
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Check if the conversion has finished
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Wait for the conversion to finish
+    pub async fn join(self) -> Result<FullEncodeResult, AppError> {
+        self.task.await.unwrap_or_else(|e| {
+            Err(AppError::CommandExecution(format!(
+                "Conversion task failed: {}",
+                e
+            )))
+        })
+    }
+}
+
+/// Output path for `input` following the output settings
+///
+/// Outputs go next to the source, or with `same_directory = false` into
+/// `output_directory`, keeping the source's folder relative to `root` (the
+/// folder that was queued) so season packs keep their layout. The file name
+/// comes from the name template, or with Jellyfin naming from the folder and
+/// `version`, e.g. `1080p AV1`. A folder holding several movies isn't one
+/// Jellyfin movie, so its sources keep the name template.
+pub fn output_path_for(
+    input: &Path,
+    root: Option<&Path>,
+    output: &OutputConfig,
+    suffix: &str,
+    version: &str,
+    container: &str,
+) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let parent = input.parent().unwrap_or(Path::new("."));
+    let dir = match output.output_directory.as_deref() {
+        Some(target) if !output.same_directory && !target.is_empty() => {
+            let relative = root
+                .and_then(|root| parent.strip_prefix(root).ok())
+                .unwrap_or(Path::new(""));
+            Path::new(target).join(relative)
+        }
+        _ => parent.to_path_buf(),
+    };
+
+    let name = match (output.naming, dir.file_name()) {
+        (OutputNaming::JellyfinVersions, Some(folder)) if !shares_folder(input) => {
+            jellyfin_version_name(&folder.to_string_lossy(), version)
+        }
+        _ => render_name(&output.name_template, &stem, suffix),
+    };
+    let mut name = name.into_os_string();
+    name.push(".");
+    name.push(container);
+    dir.join(name)
+}
+
+/// Whether `input`'s folder holds other videos than versions of one movie
+fn shares_folder(input: &Path) -> bool {
+    let Some(parent) = input.parent() else {
+        return false;
+    };
+    let versions = format!(
+        "{} - ",
+        parent.file_name().unwrap_or_default().to_string_lossy()
+    );
+    std::fs::read_dir(parent).is_ok_and(|entries| {
+        entries.flatten().any(|entry| {
+            let path = entry.path();
+            path != input
+                && is_video_file(&path)
+                && !entry.file_name().to_string_lossy().starts_with(&versions)
+        })
+    })
+}
+
+pub fn default_output_path(input: &Path, suffix: &str, container: &str) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let parent = input.parent().unwrap_or(Path::new("."));
+    parent.join(format!("{}{}.{}", stem, suffix, container))
+}
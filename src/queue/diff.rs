@@ -0,0 +1,111 @@
+//! What sets a file apart from the one configured before it.
+//!
+//! Files of a batch usually come from the same release and have the same
+//! tracks, so the track configuration screen points out only what changed:
+//! an extra commentary track, a missing subtitle language, a different
+//! resolution or HDR format.
+
+use super::EncodingJob;
+use crate::tracks::{AudioTrack, SubtitleTrack};
+
+/// One difference between a job and the previous one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobDifference {
+    /// Resolution changed, as (previous, current)
+    Resolution(String, String),
+    /// HDR format changed, as (previous, current)
+    Hdr(String, String),
+    /// Audio track the previous file didn't have
+    AudioAdded(String),
+    /// Audio track of the previous file this one lacks
+    AudioMissing(String),
+    SubtitleAdded(String),
+    SubtitleMissing(String),
+}
+
+impl JobDifference {
+    /// Short description, e.g. `+ audio eng (AC3 Stereo) - Commentary`
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Resolution(was, now) => format!("{} → {}", was, now),
+            Self::Hdr(was, now) => format!("{} → {}", was, now),
+            Self::AudioAdded(track) => format!("+ audio {}", track),
+            Self::AudioMissing(track) => format!("− audio {}", track),
+            Self::SubtitleAdded(track) => format!("+ subtitle {}", track),
+            Self::SubtitleMissing(track) => format!("− subtitle {}", track),
+        }
+    }
+
+    /// Whether the current file has something the previous didn't
+    pub fn is_added(&self) -> bool {
+        matches!(self, Self::AudioAdded(_) | Self::SubtitleAdded(_))
+    }
+
+    /// Whether the current file lacks something the previous had
+    pub fn is_missing(&self) -> bool {
+        matches!(self, Self::AudioMissing(_) | Self::SubtitleMissing(_))
+    }
+}
+
+impl EncodingJob {
+    /// Differences in format and tracks from `previous`
+    ///
+    /// Tracks are matched by language, codec, layout and title rather than
+    /// stream index, so the same lineup in another order counts as equal.
+    pub fn differences_from(&self, previous: &EncodingJob) -> Vec<JobDifference> {
+        let mut differences = Vec::new();
+        if let (Some(was), Some(now)) = (&previous.metadata, &self.metadata) {
+            if (was.width, was.height) != (now.width, now.height) {
+                differences.push(JobDifference::Resolution(
+                    was.resolution_string(),
+                    now.resolution_string(),
+                ));
+            }
+            if was.hdr_type != now.hdr_type {
+                differences.push(JobDifference::Hdr(
+                    was.hdr_string().to_string(),
+                    now.hdr_string().to_string(),
+                ));
+            }
+        }
+
+        let audio = |job: &EncodingJob| job.audio_tracks.iter().map(audio_label).collect();
+        let (added, missing) = lineup_changes(audio(previous), audio(self));
+        differences.extend(added.into_iter().map(JobDifference::AudioAdded));
+        differences.extend(missing.into_iter().map(JobDifference::AudioMissing));
+
+        let subtitles =
+            |job: &EncodingJob| job.subtitle_tracks.iter().map(subtitle_label).collect();
+        let (added, missing) = lineup_changes(subtitles(previous), subtitles(self));
+        differences.extend(added.into_iter().map(JobDifference::SubtitleAdded));
+        differences.extend(missing.into_iter().map(JobDifference::SubtitleMissing));
+        differences
+    }
+}
+
+/// Labels only in `current`, then labels only in `previous`, counting duplicates
+fn lineup_changes(mut previous: Vec<String>, current: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let mut added = Vec::new();
+    for label in current {
+        match previous.iter().position(|p| *p == label) {
+            Some(pos) => {
+                previous.remove(pos);
+            }
+            None => added.push(label),
+        }
+    }
+    (added, previous)
+}
+
+/// Track name without its stream index, e.g. `eng (AC3 5.1) - Commentary`
+fn audio_label(track: &AudioTrack) -> String {
+    strip_index(&track.display_name()).to_string()
+}
+
+fn subtitle_label(track: &SubtitleTrack) -> String {
+    strip_index(&track.display_name()).to_string()
+}
+
+fn strip_index(name: &str) -> &str {
+    name.split_once(": ").map_or(name, |(_, rest)| rest)
+}
@@ -0,0 +1,215 @@
+//! Worker messages as JSON, for tools driving the app without its UI.
+//!
+//! Each [`JobEvent`] is one line of newline-delimited JSON tagged with its
+//! `event` name. Jobs are identified by their queue position and carry the
+//! source file name the first time they appear, in `started`, `skipped` or
+//! `error`. The layout only grows: fields are added, never renamed.
+
+use super::job::{EncodingJob, SkipKind};
+use super::worker::WorkerMessage;
+use crate::config::Encoder;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// A worker message, or a job outcome of the analysis, in serializable form
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JobEvent {
+    /// A job starts encoding with this quality value
+    Started {
+        job: usize,
+        file: String,
+        output: Option<PathBuf>,
+        quality: Option<u8>,
+    },
+    Progress {
+        job: usize,
+        percent: f32,
+        speed: Option<f32>,
+        bitrate_kbps: Option<f32>,
+    },
+    /// A pipeline stage finished
+    Stage {
+        job: usize,
+        stage: &'static str,
+        secs: f64,
+    },
+    Remuxed {
+        job: usize,
+        encoded_size: u64,
+        remuxed_size: u64,
+    },
+    EncoderFallback {
+        job: usize,
+        encoder: Encoder,
+        reason: String,
+    },
+    Validation {
+        job: usize,
+        issues: Vec<String>,
+    },
+    /// VMAF below the threshold; the source is kept
+    QualityWarning {
+        job: usize,
+        vmaf: f64,
+        threshold: f64,
+    },
+    SourceDeleted {
+        job: usize,
+    },
+    SourceKept {
+        job: usize,
+        reason: String,
+    },
+    Step {
+        job: usize,
+        step: String,
+        status: &'static str,
+        note: Option<String>,
+    },
+    OutputMoved {
+        job: usize,
+        output: PathBuf,
+    },
+    Simulated {
+        job: usize,
+        command: String,
+    },
+    Done {
+        job: usize,
+        vmaf: Option<f64>,
+    },
+    Skipped {
+        job: usize,
+        file: String,
+        kind: SkipKind,
+        reason: String,
+    },
+    Error {
+        job: usize,
+        file: String,
+        kind: &'static str,
+        message: String,
+    },
+    /// Outside the encode window; the next job starts once it opens
+    WaitingForSchedule {
+        window: String,
+    },
+    /// The encode window opened
+    ScheduleOpen,
+    /// The whole queue was cancelled
+    Cancelled,
+    /// Every job has finished
+    Finished {
+        converted: usize,
+        /// Jobs a rule left out
+        skipped: usize,
+        user_skipped: usize,
+        cancelled: usize,
+        errors: usize,
+    },
+}
+
+impl JobEvent {
+    /// Mirror of a worker message; `jobs` names the files
+    ///
+    /// Messages that only refine earlier ones, like the per-job provenance or
+    /// the worst VMAF segments, have no event.
+    pub fn from_message(message: &WorkerMessage, jobs: &[EncodingJob]) -> Option<Self> {
+        let file = |job: usize| jobs.get(job).map(|j| j.display_name()).unwrap_or_default();
+        Some(match message {
+            WorkerMessage::Progress(job, progress) => JobEvent::Progress {
+                job: *job,
+                percent: (progress.percent * 10.0).round() / 10.0,
+                speed: progress.speed,
+                bitrate_kbps: progress.bitrate_kbps,
+            },
+            WorkerMessage::Done(job) => JobEvent::Done {
+                job: *job,
+                vmaf: None,
+            },
+            WorkerMessage::DoneWithVmaf(job, score) => JobEvent::Done {
+                job: *job,
+                vmaf: Some(*score),
+            },
+            WorkerMessage::Error(job, e) => JobEvent::Error {
+                job: *job,
+                file: file(*job),
+                kind: e.kind().label(),
+                message: e.to_string(),
+            },
+            WorkerMessage::QualityWarning(job, vmaf, threshold) => JobEvent::QualityWarning {
+                job: *job,
+                vmaf: *vmaf,
+                threshold: *threshold,
+            },
+            WorkerMessage::Cancelled => JobEvent::Cancelled,
+            WorkerMessage::JobCancelled(job) => JobEvent::Skipped {
+                job: *job,
+                file: file(*job),
+                kind: SkipKind::Cancelled,
+                reason: "Cancelled".to_string(),
+            },
+            WorkerMessage::SourceDeleted(job) => JobEvent::SourceDeleted { job: *job },
+            WorkerMessage::SourceKeptLowVmaf(job, vmaf) => JobEvent::SourceKept {
+                job: *job,
+                reason: format!("VMAF {:.1}", vmaf),
+            },
+            WorkerMessage::SourceKept(job, reason) => JobEvent::SourceKept {
+                job: *job,
+                reason: reason.clone(),
+            },
+            WorkerMessage::Remuxed(job, encoded_size, remuxed_size) => JobEvent::Remuxed {
+                job: *job,
+                encoded_size: *encoded_size,
+                remuxed_size: *remuxed_size,
+            },
+            WorkerMessage::StageTime(job, stage, elapsed) => JobEvent::Stage {
+                job: *job,
+                stage: stage.name(),
+                secs: elapsed.as_secs_f64(),
+            },
+            WorkerMessage::ValidationIssues(job, issues) => JobEvent::Validation {
+                job: *job,
+                issues: issues.iter().map(|i| i.describe()).collect(),
+            },
+            WorkerMessage::EncoderFallback(job, encoder, reason) => JobEvent::EncoderFallback {
+                job: *job,
+                encoder: *encoder,
+                reason: reason.clone(),
+            },
+            WorkerMessage::Simulated(job, simulated) => JobEvent::Simulated {
+                job: *job,
+                command: simulated.command.clone(),
+            },
+            WorkerMessage::Claimed(job, holder) => JobEvent::Skipped {
+                job: *job,
+                file: file(*job),
+                kind: SkipKind::Rule,
+                reason: format!("Claimed by {}", holder),
+            },
+            WorkerMessage::Step(job, position, status) => {
+                let step = jobs
+                    .get(*job)
+                    .and_then(|j| j.steps.steps.get(*position))
+                    .map(|s| s.action.display_name())
+                    .unwrap_or_default();
+                JobEvent::Step {
+                    job: *job,
+                    step,
+                    status: status.name(),
+                    note: status.note().map(str::to_string),
+                }
+            }
+            WorkerMessage::OutputMoved(job, output) => JobEvent::OutputMoved {
+                job: *job,
+                output: output.clone(),
+            },
+            WorkerMessage::WaitingForSchedule(Some(window)) => JobEvent::WaitingForSchedule {
+                window: window.to_string(),
+            },
+            WorkerMessage::WaitingForSchedule(None) => JobEvent::ScheduleOpen,
+            WorkerMessage::VmafWorstSegments(..) | WorkerMessage::Provenance(..) => return None,
+        })
+    }
+}
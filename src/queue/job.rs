@@ -1,6 +1,32 @@
-use crate::analyzer::VideoMetadata;
-use crate::tracks::{AudioTrack, SubtitleTrack, TrackSelection};
+use crate::analyzer::{
+    DiscTitle, FilmGrainParams, LumaAnalysis, MotionAnalysis, NoiseAnalysis, ResolutionTier,
+    VideoMetadata, adaptive_film_grain, is_av1_codec,
+};
+use crate::config::{
+    AppConfig, AudioBitrates, DeviceProfile, Encoder, InputConfig, OutputConfig, SubtitleRule,
+    TrackPresetConfig,
+};
+use crate::encoder::{
+    EncodeOverrides, EncodeProvenance, JobMode, SimulatedEncode, Stage, StageTimings,
+    banding_prone, fit_within, preset_quality,
+};
+use crate::error::ErrorKind;
+use crate::pipeline::output_path_for;
+use crate::queue::saved::SavedJob;
+use crate::queue::steps::TaskGraph;
+use crate::tracks::{AudioTrack, SubtitleTrack, TrackSelection, commentary_tracks};
+use crate::utils::naming::resolution_label;
+use crate::verifier::{ValidationIssue, VmafSegment};
+use serde::Serialize;
 use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+use std::time::Instant;
+
+/// CRF increase suggested for low-motion content
+const STILL_IMAGE_CRF_BOOST: u8 = 10;
+
+/// Frame rate cap suggested for low-motion content
+const STILL_IMAGE_MAX_FPS: u32 = 10;
 
 /// Status of a job in the encoding queue
 #[derive(Debug, Clone)]
@@ -19,14 +45,160 @@ pub enum JobStatus {
     Done,
     /// Encoded with VMAF score
     DoneWithVmaf { score: f64 },
-    /// Skipped (e.g., already AV1, cancelled)
-    Skipped { reason: String },
+    /// Not encoded, and why
+    Skipped { kind: SkipKind, reason: String },
     /// Error occurred
-    Error { message: String },
+    Error { message: String, kind: ErrorKind },
     /// Encoded but quality below threshold
     QualityWarning { vmaf: f64, threshold: f64 },
 }
 
+/// Who or what left a job out, counted apart on the Finish screen and in reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipKind {
+    /// A rule, e.g. already AV1 or claimed by another machine
+    Rule,
+    /// Skipped by hand before it started
+    User,
+    /// Cancelled while waiting or encoding
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Name of the status in JSON output
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Analyzing => "analyzing",
+            JobStatus::AwaitingConfig => "awaiting_config",
+            JobStatus::Ready => "ready",
+            JobStatus::Encoding { .. } => "encoding",
+            JobStatus::Done | JobStatus::DoneWithVmaf { .. } => "done",
+            JobStatus::Skipped { .. } => "skipped",
+            JobStatus::Error { .. } => "error",
+            JobStatus::QualityWarning { .. } => "quality_warning",
+        }
+    }
+}
+
+/// Encoding suggestion for slideshow and still-image sources
+#[derive(Debug, Clone)]
+pub struct StillImageHint {
+    /// Fraction of the sampled window without motion
+    pub static_ratio: f64,
+    /// Suggested quality value for the configured encoder
+    pub suggested_crf: u8,
+    /// Suggested output frame rate
+    pub suggested_fps: (u32, u32),
+    /// Whether the suggestion is applied to the job
+    pub applied: bool,
+}
+
+/// Film grain picked for a source from its measured grain and bitrate
+#[derive(Debug, Clone)]
+pub struct GrainHint {
+    /// How grainy the source is, from 0 (clean) to 1 (heavy film grain)
+    pub score: f64,
+    /// Film grain encoded with
+    pub film_grain: u8,
+    /// Film grain the preset of its tier would have used
+    pub preset_grain: u8,
+}
+
+impl GrainHint {
+    /// Pick the film grain when adaptive grain is on
+    pub fn from_noise(
+        noise: &NoiseAnalysis,
+        metadata: &VideoMetadata,
+        config: &AppConfig,
+    ) -> Option<Self> {
+        let grain = &config.quality.adaptive_grain;
+        if !grain.enabled {
+            return None;
+        }
+        let tier = ResolutionTier::from_dimensions(metadata.width, metadata.height);
+        let bits_per_pixel = metadata
+            .bitrate
+            .filter(|&b| b > 0)
+            .and_then(|b| metadata.bits_per_pixel(b as f64));
+        let score = noise.grain_score(bits_per_pixel, grain);
+        Some(Self {
+            score,
+            film_grain: adaptive_film_grain(score, tier, grain),
+            preset_grain: config.preset_for(&tier, metadata.hdr_type).film_grain,
+        })
+    }
+}
+
+/// Quality boost for sources with many dark scenes, applied automatically
+#[derive(Debug, Clone)]
+pub struct DarkSceneHint {
+    /// Fraction of the sampled scenes that are dark
+    pub dark_ratio: f64,
+    /// Quality steps the preset value is lowered by
+    pub crf_offset: u8,
+}
+
+impl DarkSceneHint {
+    /// Build the boost if the luma analysis flags dark content
+    pub fn from_luma(luma: &LumaAnalysis, config: &AppConfig) -> Option<Self> {
+        let crf_offset = config.quality.dark_scene_crf_offset;
+        if !luma.is_dark() || crf_offset == 0 {
+            return None;
+        }
+        Some(Self {
+            dark_ratio: luma.dark_ratio(),
+            crf_offset,
+        })
+    }
+}
+
+impl StillImageHint {
+    /// Build a suggestion if the motion analysis flags low-motion content
+    pub fn from_motion(
+        motion: &MotionAnalysis,
+        metadata: &VideoMetadata,
+        config: &AppConfig,
+    ) -> Option<Self> {
+        if !motion.is_low_motion() {
+            return None;
+        }
+
+        let suggested_crf = preset_quality(metadata, config)
+            .saturating_add(STILL_IMAGE_CRF_BOOST)
+            .min(config.encoder.max_quality());
+
+        let source_fps = if metadata.frame_rate_den > 0 {
+            metadata.frame_rate_num as f64 / metadata.frame_rate_den as f64
+        } else {
+            0.0
+        };
+        let suggested_fps = if source_fps > STILL_IMAGE_MAX_FPS as f64 || source_fps == 0.0 {
+            (STILL_IMAGE_MAX_FPS, 1)
+        } else {
+            (metadata.frame_rate_num, metadata.frame_rate_den)
+        };
+
+        Some(Self {
+            static_ratio: motion.static_ratio(),
+            suggested_crf,
+            suggested_fps,
+            applied: false,
+        })
+    }
+
+    /// Get the frame rate as a display string
+    pub fn fps_string(&self) -> String {
+        let (num, den) = self.suggested_fps;
+        if den == 1 {
+            format!("{} fps", num)
+        } else {
+            format!("{:.2} fps", num as f64 / den as f64)
+        }
+    }
+}
+
 /// An encoding job in the queue
 #[derive(Debug, Clone)]
 pub struct EncodingJob {
@@ -40,8 +212,47 @@ pub struct EncodingJob {
     pub crf: Option<u8>,
     pub source_size: Option<u64>,
     pub output_size: Option<u64>,
+    pub encoded_size: Option<u64>,
     pub source_deleted: bool,
     pub source_kept_vmaf: Option<f64>,
+    /// Deletion guard that kept the source of a passing output
+    pub source_kept_reason: Option<String>,
+    pub vmaf_worst_segments: Vec<VmafSegment>,
+    /// Problems found by post-encode stream validation
+    pub validation_issues: Vec<ValidationIssue>,
+    /// Hardware encoder that couldn't encode the file and why; SVT-AV1 was used instead
+    pub encoder_fallback: Option<(Encoder, String)>,
+    pub stage_timings: StageTimings,
+    /// Start of the phase the running job is in: before, during or after the encode
+    pub phase_started: Option<Instant>,
+    pub still_image: Option<StillImageHint>,
+    /// Lower quality value and stronger AQ for dark content
+    pub dark_scenes: Option<DarkSceneHint>,
+    /// Film grain picked from the measured grain, replacing the preset's
+    pub adaptive_grain: Option<GrainHint>,
+    /// Film grain of an AV1 source that is re-encoded
+    pub source_grain: Option<FilmGrainParams>,
+    pub frame_rate_override: Option<(u32, u32)>,
+    /// Extra FFmpeg arguments for this job, with optional leading `NAME=value` env vars
+    pub extra_args: String,
+    /// Device profiles to produce; more than one is split into a job per output before encoding
+    pub profiles: Vec<DeviceProfile>,
+    /// Disc title this job reads from; `path` then holds the ffmpeg input
+    pub disc_title: Option<DiscTitle>,
+    /// Frame rate a raw video stream source is read at
+    pub assumed_frame_rate: Option<(u32, u32)>,
+    /// Folder the job was queued from; outputs in another directory keep their path below it
+    pub source_root: Option<PathBuf>,
+    /// What the encode would have done, for a simulated queue
+    pub simulation: Option<SimulatedEncode>,
+    /// App, tools and command the output was made with
+    pub provenance: Option<EncodeProvenance>,
+    /// Steps run after the encode, and where each is
+    pub steps: TaskGraph,
+    /// Encode the video, or only rewrite the other streams of an AV1 source
+    pub mode: JobMode,
+    /// Choices saved by an earlier run, applied once the source is analyzed again
+    pub restored: Option<Box<SavedJob>>,
 }
 
 impl EncodingJob {
@@ -59,19 +270,61 @@ impl EncodingJob {
             crf: None,
             source_size,
             output_size: None,
+            encoded_size: None,
             source_deleted: false,
             source_kept_vmaf: None,
+            source_kept_reason: None,
+            vmaf_worst_segments: Vec::new(),
+            validation_issues: Vec::new(),
+            encoder_fallback: None,
+            stage_timings: StageTimings::default(),
+            phase_started: None,
+            still_image: None,
+            dark_scenes: None,
+            adaptive_grain: None,
+            source_grain: None,
+            frame_rate_override: None,
+            extra_args: String::new(),
+            profiles: Vec::new(),
+            disc_title: None,
+            assumed_frame_rate: None,
+            source_root: None,
+            simulation: None,
+            provenance: None,
+            steps: TaskGraph::default(),
+            mode: JobMode::default(),
+            restored: None,
         }
     }
 
+    /// Create a job for a Blu-ray or DVD title
+    pub fn from_disc_title(title: DiscTitle) -> Self {
+        let mut job = Self::new(PathBuf::from(title.input()));
+        job.source_size = Some(title.size);
+        job.disc_title = Some(title);
+        job
+    }
+
     /// Get the filename
     pub fn filename(&self) -> String {
+        if let Some(ref title) = self.disc_title {
+            return title.source_name();
+        }
         self.path
             .file_name()
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_else(|| "Unknown".to_string())
     }
 
+    /// Filename with the device profile, telling outputs of the same source apart
+    pub fn display_name(&self) -> String {
+        match self.profiles.as_slice() {
+            [] => self.filename(),
+            [profile] => format!("{} → {}", self.filename(), profile.name),
+            profiles => format!("{} → {} outputs", self.filename(), profiles.len()),
+        }
+    }
+
     /// Get the resolution string
     pub fn resolution_string(&self) -> String {
         self.metadata
@@ -88,11 +341,117 @@ impl EncodingJob {
             .unwrap_or("Unknown")
     }
 
+    /// Whether the source is likely to band after encoding with its preset
+    pub fn banding_prone(&self, config: &AppConfig) -> bool {
+        self.metadata.as_ref().is_some_and(|m| {
+            let tier = ResolutionTier::from_dimensions(m.width, m.height);
+            let film_grain = match self.adaptive_grain {
+                Some(ref hint) => hint.film_grain,
+                None => config.preset_for(&tier, m.hdr_type).film_grain,
+            };
+            banding_prone(m, film_grain)
+        })
+    }
+
     /// Generate the output path based on config
-    pub fn generate_output_path(&mut self, suffix: &str, container: &str) {
-        let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
-        let parent = self.path.parent().unwrap_or(Path::new("."));
-        self.output_path = Some(parent.join(format!("{}{}.{}", stem, suffix, container)));
+    ///
+    /// A single device profile may replace the container.
+    pub fn generate_output_path(&mut self, output: &OutputConfig) {
+        let version = self.version_label();
+        self.output_path = Some(self.output_path_with_suffix(output, &output.suffix, &version));
+    }
+
+    /// Folder the output is written to, known before the source is analyzed
+    pub fn output_dir(&self, output: &OutputConfig) -> PathBuf {
+        let path = match self.output_path {
+            Some(ref path) => path.clone(),
+            None => self.output_path_with_suffix(output, &output.suffix, &self.version_label()),
+        };
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    }
+
+    /// Name of the output as a Jellyfin version, e.g. `1080p AV1`
+    ///
+    /// The resolution is the output's, after a device profile scaled it down.
+    pub fn version_label(&self) -> String {
+        let Some(ref metadata) = self.metadata else {
+            return "AV1".to_string();
+        };
+        let (width, height) = match self.profiles.as_slice() {
+            [profile] => fit_within(
+                profile.max_width,
+                profile.max_height,
+                metadata.width,
+                metadata.height,
+            ),
+            _ => (metadata.width, metadata.height),
+        };
+        format!("{} AV1", resolution_label(width, height))
+    }
+
+    fn output_path_with_suffix(
+        &self,
+        output: &OutputConfig,
+        suffix: &str,
+        version: &str,
+    ) -> PathBuf {
+        let container = match self.profiles.as_slice() {
+            [profile] => profile.container.as_deref().unwrap_or(&output.container),
+            _ => &output.container,
+        };
+        let source = match self.disc_title {
+            Some(ref title) => title.output_base(),
+            None => self.path.clone(),
+        };
+        output_path_for(
+            &source,
+            self.source_root.as_deref(),
+            output,
+            suffix,
+            version,
+            container,
+        )
+    }
+
+    /// Split into one job per device profile, each with its own output path
+    ///
+    /// Outputs are tagged with the profile name so two profiles sharing a
+    /// container don't write the same file.
+    pub fn split_outputs(&self, output: &OutputConfig) -> Vec<EncodingJob> {
+        // A copied video looks the same for every profile
+        if self.profiles.len() < 2 || self.mode.copies_video() {
+            return vec![self.clone()];
+        }
+        self.profiles
+            .iter()
+            .map(|profile| {
+                let mut job = self.clone();
+                job.profiles = vec![profile.clone()];
+                let suffix = format!("{}_{}", output.suffix, profile.slug());
+                let version = format!("{} ({})", job.version_label(), profile.name);
+                job.output_path = Some(job.output_path_with_suffix(output, &suffix, &version));
+                job
+            })
+            .collect()
+    }
+
+    /// Whether the video may be left as it is, since the source is already AV1
+    pub fn can_copy_video(&self) -> bool {
+        self.metadata
+            .as_ref()
+            .is_some_and(|m| is_av1_codec(&m.codec_name))
+    }
+
+    /// Switch to the next job mode; only AV1 sources may skip the video encode
+    pub fn cycle_mode(&mut self) -> bool {
+        if !self.can_copy_video() {
+            return false;
+        }
+        self.mode = self.mode.next();
+        true
     }
 
     /// Select all available tracks
@@ -101,6 +460,138 @@ impl EncodingJob {
             TrackSelection::select_all(&self.audio_tracks, &self.subtitle_tracks);
     }
 
+    /// Deselect the audio tracks that look like commentary
+    ///
+    /// Nothing changes when every audio track does, since an empty audio
+    /// selection would keep them all.
+    pub fn exclude_commentary(&mut self) {
+        let commentary = commentary_tracks(&self.audio_tracks);
+        if commentary.len() == self.audio_tracks.len() {
+            return;
+        }
+        self.track_selection
+            .audio_indices
+            .retain(|i| !commentary.contains(i));
+    }
+
+    /// Select the commentary tracks if any is left out, deselect them otherwise
+    pub fn toggle_commentary(&mut self) {
+        let commentary = commentary_tracks(&self.audio_tracks);
+        let left_out: Vec<usize> = commentary
+            .iter()
+            .copied()
+            .filter(|&i| self.track_selection.audio_position(i).is_none())
+            .collect();
+        if left_out.is_empty() {
+            self.exclude_commentary();
+        } else {
+            for index in left_out {
+                self.track_selection.toggle_audio(index);
+            }
+        }
+    }
+
+    /// Deselect the subtitles the language's rule leaves out
+    ///
+    /// Forced tracks stay under every rule. Nothing changes when no track at
+    /// all would be left, since an empty selection keeps everything.
+    pub fn apply_subtitle_rules(&mut self, tracks: &TrackPresetConfig) {
+        let has_sdh = |language: &Option<String>| {
+            self.subtitle_tracks
+                .iter()
+                .any(|t| t.language == *language && t.is_sdh())
+        };
+        let keep: Vec<usize> = self
+            .subtitle_tracks
+            .iter()
+            .filter(|t| {
+                t.is_forced()
+                    || match tracks.subtitle_rule_for(t.language.as_deref()) {
+                        SubtitleRule::All => true,
+                        SubtitleRule::ForcedOnly => false,
+                        SubtitleRule::ExcludeSdh => !t.is_sdh(),
+                        SubtitleRule::PreferSdh => t.is_sdh() || !has_sdh(&t.language),
+                    }
+            })
+            .map(|t| t.index)
+            .collect();
+        if keep.is_empty() && self.track_selection.audio_indices.is_empty() {
+            return;
+        }
+        self.track_selection
+            .subtitle_indices
+            .retain(|i| keep.contains(i));
+    }
+
+    /// Apply or revert the still-image suggestion
+    pub fn toggle_still_image_hint(&mut self) {
+        let Some(hint) = self.still_image.as_mut() else {
+            return;
+        };
+        hint.applied = !hint.applied;
+        if hint.applied {
+            self.crf = Some(hint.suggested_crf);
+            self.frame_rate_override = Some(hint.suggested_fps);
+        } else {
+            self.crf = None;
+            self.frame_rate_override = None;
+        }
+    }
+
+    /// Per-job encoder overrides
+    pub fn overrides(&self) -> EncodeOverrides {
+        let (env, extra_args) = EncodeOverrides::parse_extra(&self.extra_args);
+        EncodeOverrides {
+            crf: self.crf,
+            frame_rate: self.frame_rate_override,
+            input_frame_rate: self.assumed_frame_rate,
+            extra_args,
+            env,
+            profile: match self.profiles.as_slice() {
+                [profile] => Some(profile.clone()),
+                _ => None,
+            },
+            source_grain: self.source_grain.clone(),
+            film_grain: self.adaptive_grain.as_ref().map(|h| h.film_grain),
+            // Disc folders are never deleted
+            keep_source: self.disc_title.is_some(),
+            dark_scene_offset: self.dark_scenes.as_ref().map(|h| h.crf_offset),
+            copy_audio: Vec::new(),
+            audio_channels: self
+                .track_selection
+                .kept_audio(self.audio_tracks.len())
+                .into_iter()
+                .filter_map(|index| self.audio_tracks.iter().find(|t| t.index == index))
+                .map(|track| track.channels)
+                .collect(),
+            simulate: false,
+            mode: self.mode,
+        }
+    }
+
+    /// Kept lossy audio tracks that `profile`'s audio re-encode would barely
+    /// shrink, with their output position
+    pub fn pointless_audio_reencodes(
+        &self,
+        profile: &DeviceProfile,
+        bitrates: &AudioBitrates,
+    ) -> Vec<(usize, &AudioTrack)> {
+        if profile.audio_channels.is_none() {
+            return Vec::new();
+        }
+        self.track_selection
+            .kept_audio(self.audio_tracks.len())
+            .into_iter()
+            .enumerate()
+            .filter_map(|(position, index)| {
+                let track = self.audio_tracks.iter().find(|t| t.index == index)?;
+                track
+                    .reencode_pointless(profile.audio_bitrate(track.channels, bitrates))
+                    .then_some((position, track))
+            })
+            .collect()
+    }
+
     /// Calculate size reduction if both sizes are known
     pub fn size_reduction(&self) -> Option<(u64, f64)> {
         match (self.source_size, self.output_size) {
@@ -112,16 +603,69 @@ impl EncodingJob {
             _ => None,
         }
     }
+
+    /// Estimated bytes saved by leaving out the unselected audio and subtitle
+    /// tracks, as `(audio, subtitles)`
+    ///
+    /// Tracks without a known bitrate count as nothing.
+    pub fn dropped_track_savings(&self) -> (u64, u64) {
+        let duration = self.metadata.as_ref().map_or(0.0, |m| m.duration_secs);
+        let selection = &self.track_selection;
+        let audio = self
+            .audio_tracks
+            .iter()
+            .filter(|t| !selection.audio_indices.contains(&t.index))
+            .filter_map(|t| t.estimated_size(duration))
+            .fold(0u64, u64::saturating_add);
+        let subtitles = self
+            .subtitle_tracks
+            .iter()
+            .filter(|t| !selection.subtitle_indices.contains(&t.index))
+            .filter_map(|t| t.estimated_size(duration))
+            .fold(0u64, u64::saturating_add);
+        (audio, subtitles)
+    }
+
+    /// Source bytes processed per second while encoding at `speed` times realtime
+    pub fn source_rate(&self, speed: f64) -> Option<f64> {
+        let duration = self.metadata.as_ref()?.duration_secs;
+        let source = self.source_size?;
+        (duration > 0.0).then(|| source as f64 / duration * speed)
+    }
+
+    /// Average source bytes encoded per second, once the encode stage has run
+    pub fn encode_throughput(&self) -> Option<f64> {
+        let elapsed = self.stage_timings.get(Stage::Encode)?.as_secs_f64();
+        let source = self.source_size?;
+        (elapsed > 0.0).then(|| source as f64 / elapsed)
+    }
+}
+
+/// Extensions of the files treated as videos, lowercase and without the dot
+static VIDEO_EXTENSIONS: LazyLock<RwLock<Vec<String>>> =
+    LazyLock::new(|| RwLock::new(normalized(&InputConfig::default().video_extensions)));
+
+/// Use the video extensions from the configuration
+pub fn configure_video_extensions(input: &InputConfig) {
+    if let Ok(mut extensions) = VIDEO_EXTENSIONS.write() {
+        *extensions = normalized(&input.video_extensions);
+    }
+}
+
+fn normalized(extensions: &[String]) -> Vec<String> {
+    extensions
+        .iter()
+        .map(|e| e.trim().trim_start_matches('.').to_ascii_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect()
 }
 
-/// Check if a path is a video file
+/// Check if a path is a video file, by the configured extensions
 pub fn is_video_file(path: &Path) -> bool {
-    const VIDEO_EXTENSIONS: [&str; 10] = [
-        "mp4", "mkv", "avi", "mov", "webm", "m4v", "ts", "m2ts", "wmv", "flv",
-    ];
-
-    path.extension()
-        .and_then(|e| e.to_str())
-        .map(|e| VIDEO_EXTENSIONS.iter().any(|&ext| ext.eq_ignore_ascii_case(e)))
-        .unwrap_or(false)
+    let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    VIDEO_EXTENSIONS
+        .read()
+        .is_ok_and(|extensions| extensions.iter().any(|e| e.eq_ignore_ascii_case(extension)))
 }
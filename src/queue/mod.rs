@@ -1,7 +1,27 @@
+pub mod diff;
+pub mod events;
 pub mod job;
+pub mod preflight;
+pub mod publish;
+pub mod saved;
+pub mod schedule;
+pub mod snapshot;
 pub mod state;
+pub mod steps;
+pub mod summary;
 pub mod worker;
 
-pub use job::{EncodingJob, JobStatus, is_video_file};
-pub use state::QueueState;
-pub use worker::{WorkerJob, WorkerMessage, run_worker};
+pub use diff::JobDifference;
+pub use events::JobEvent;
+pub use job::{
+    DarkSceneHint, EncodingJob, GrainHint, JobStatus, SkipKind, StillImageHint,
+    configure_video_extensions, is_video_file,
+};
+pub use publish::{LiveQueue, QueuePublisher};
+pub use saved::{SavedJob, SavedQueue};
+pub use schedule::EncodeWindow;
+pub use snapshot::{JobSnapshot, QueueSnapshot};
+pub use state::{EtaEstimator, QueueState, SpaceSavings};
+pub use steps::{PostAction, StepStatus, TaskGraph, post_actions};
+pub use summary::{JobSummary, RunOutcome, RunSummary};
+pub use worker::{WorkerJob, WorkerMessage, run_streamed_worker, run_worker};
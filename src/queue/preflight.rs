@@ -0,0 +1,106 @@
+//! Check before a queue starts that its outputs fit where they are written.
+//!
+//! Outputs are grouped by the volume their folder is on, so a queue writing
+//! to two disks is judged per disk.
+
+use super::job::EncodingJob;
+use crate::config::OutputConfig;
+use crate::utils::format_file_size;
+use crate::utils::space::{self, Volume};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// What the queue writes to one volume
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeNeed {
+    /// Mount point, or the output folder when the volume is unknown
+    pub volume: PathBuf,
+    /// Bytes available, if the system could tell
+    pub free: Option<u64>,
+    /// Outputs written to this volume
+    pub files: usize,
+    /// Size of their sources
+    pub source_size: u64,
+    /// Likely size of the outputs
+    pub estimated_size: u64,
+}
+
+impl VolumeNeed {
+    /// Whether the outputs likely don't fit
+    pub fn is_short(&self) -> bool {
+        self.free.is_some_and(|free| self.estimated_size > free)
+    }
+
+    /// One line breakdown, e.g. `/mnt/media: 12 files, ~40 GB of 95 GB sources, 31 GB free`
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: {} {}, ~{} of {} sources, {}",
+            self.volume.display(),
+            self.files,
+            if self.files == 1 { "file" } else { "files" },
+            format_file_size(self.estimated_size),
+            format_file_size(self.source_size),
+            match self.free {
+                Some(free) => format!("{} free", format_file_size(free)),
+                None => "free space unknown".to_string(),
+            }
+        )
+    }
+}
+
+/// Space the queue needs on each output volume, largest need first
+///
+/// `estimate` gives the likely output size of a job, e.g. from a probe of its
+/// codec; the source size stands in without one. A job split across several
+/// device profiles writes one output per profile.
+pub fn space_needs(
+    jobs: &[EncodingJob],
+    output: &OutputConfig,
+    estimate: impl Fn(usize, &EncodingJob) -> Option<u64>,
+) -> Vec<VolumeNeed> {
+    space_needs_with(jobs, output, estimate, space::volume_of)
+}
+
+/// [`space_needs`] with the volume lookup given, so it can be checked
+/// without real disks
+pub fn space_needs_with(
+    jobs: &[EncodingJob],
+    output: &OutputConfig,
+    estimate: impl Fn(usize, &EncodingJob) -> Option<u64>,
+    volume_of: impl Fn(&Path) -> Option<Volume>,
+) -> Vec<VolumeNeed> {
+    let mut volumes: HashMap<PathBuf, Option<Volume>> = HashMap::new();
+    let mut needs: Vec<VolumeNeed> = Vec::new();
+    for (index, job) in jobs.iter().enumerate() {
+        let dir = job.output_dir(output);
+        let volume = volumes
+            .entry(dir.clone())
+            .or_insert_with(|| volume_of(&dir))
+            .clone();
+        let (key, free) = match volume {
+            Some(v) => (v.mount, Some(v.free)),
+            None => (dir, None),
+        };
+        let source_size = job.source_size.unwrap_or(0);
+        let outputs = job.profiles.len().max(1) as u64;
+        let estimated = estimate(index, job).unwrap_or(source_size) * outputs;
+        let need = match needs.iter_mut().find(|n| n.volume == key) {
+            Some(need) => need,
+            None => {
+                needs.push(VolumeNeed {
+                    volume: key,
+                    free,
+                    files: 0,
+                    source_size: 0,
+                    estimated_size: 0,
+                });
+                needs.last_mut().expect("just pushed")
+            }
+        };
+        need.files += outputs as usize;
+        need.source_size += source_size;
+        need.estimated_size += estimated;
+    }
+    needs.sort_by_key(|n| std::cmp::Reverse(n.estimated_size));
+    needs
+}
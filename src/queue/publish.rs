@@ -0,0 +1,110 @@
+//! Live queue state written to a file, so other processes can follow the
+//! queue without talking to the instance running it.
+//!
+//! The instance holding the global lock publishes; a second invocation
+//! attaches to the file as a read-only viewer. `output.status_file` names
+//! one more file for dashboards and status bars to read.
+
+use super::snapshot::QueueSnapshot;
+use super::state::QueueState;
+use crate::utils::{data_dir, lock};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Published at most this often while progress comes in
+const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The queue as published by a running instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveQueue {
+    /// Process running the queue
+    pub pid: u32,
+    /// Unix time of the last update
+    pub updated: u64,
+    #[serde(flatten)]
+    pub queue: QueueSnapshot,
+}
+
+impl LiveQueue {
+    /// File the instance holding the global lock publishes to
+    pub fn default_path() -> PathBuf {
+        data_dir().join("live_queue.json")
+    }
+
+    /// Queue published at `path`, if any
+    pub fn read(path: &Path) -> Option<Self> {
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+    }
+
+    /// Whether the publishing process still runs
+    pub fn is_running(&self) -> bool {
+        lock::process_running(self.pid)
+    }
+}
+
+/// Writes the queue to a file as it changes, removing it when dropped
+/// unless it is a status file
+#[derive(Debug)]
+pub struct QueuePublisher {
+    path: PathBuf,
+    last: Option<Instant>,
+    remove_on_drop: bool,
+}
+
+impl QueuePublisher {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last: None,
+            remove_on_drop: true,
+        }
+    }
+
+    /// Publisher of a status file, which keeps showing how the last run
+    /// ended after the instance exits
+    pub fn status_file(path: PathBuf) -> Self {
+        Self {
+            path,
+            last: None,
+            remove_on_drop: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write `queue`, at most once a second unless `force` is set
+    ///
+    /// The file is replaced by a rename, so readers never see half of it.
+    pub fn publish(&mut self, queue: &QueueState, force: bool) -> io::Result<()> {
+        if !force && self.last.is_some_and(|t| t.elapsed() < PUBLISH_INTERVAL) {
+            return Ok(());
+        }
+        self.last = Some(Instant::now());
+        let live = LiveQueue {
+            pid: std::process::id(),
+            updated: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            queue: QueueSnapshot::of(queue, 0),
+        };
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        std::fs::write(&temp, serde_json::to_vec_pretty(&live)?)?;
+        std::fs::rename(&temp, &self.path)
+    }
+}
+
+impl Drop for QueuePublisher {
+    fn drop(&mut self) {
+        if self.remove_on_drop {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! The unfinished part of the queue, saved as it changes so quitting or a
+//! crash doesn't lose a configured queue; the next start offers to restore it.
+//!
+//! Only what was decided for each job is kept. Sources are analyzed again on
+//! restore, since they may have changed in the meantime, and the saved
+//! choices are applied on top of the fresh analysis.
+
+use super::job::{EncodingJob, JobStatus};
+use super::state::QueueState;
+use crate::analyzer::DiscTitle;
+use crate::config::DeviceProfile;
+use crate::encoder::JobMode;
+use crate::error::AppError;
+use crate::tracks::TrackSelection;
+use crate::utils::data_dir;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Jobs of a queue that had not finished yet
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedQueue {
+    pub jobs: Vec<SavedJob>,
+}
+
+/// What was decided for a job, without what analysis finds out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedJob {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub source_root: Option<PathBuf>,
+    #[serde(default)]
+    pub disc_title: Option<DiscTitle>,
+    /// Whether its tracks were confirmed; it then encodes without asking again
+    pub configured: bool,
+    #[serde(default)]
+    pub track_selection: TrackSelection,
+    #[serde(default)]
+    pub output_path: Option<PathBuf>,
+    #[serde(default)]
+    pub crf: Option<u8>,
+    #[serde(default)]
+    pub frame_rate_override: Option<(u32, u32)>,
+    #[serde(default)]
+    pub assumed_frame_rate: Option<(u32, u32)>,
+    #[serde(default)]
+    pub extra_args: String,
+    #[serde(default)]
+    pub profiles: Vec<DeviceProfile>,
+    #[serde(default)]
+    pub mode: JobMode,
+}
+
+/// Location of the saved queue
+pub fn saved_queue_path() -> PathBuf {
+    data_dir().join("queue.json")
+}
+
+impl SavedJob {
+    /// Choices made for `job`, unless it finished or was never confirmed
+    ///
+    /// Jobs handed to the worker wait as `Pending` again; only those not yet
+    /// analyzed are still on the confirmation screen.
+    pub fn of(job: &EncodingJob) -> Option<Self> {
+        let configured = match job.status {
+            JobStatus::Analyzing | JobStatus::AwaitingConfig => false,
+            JobStatus::Pending if job.metadata.is_none() => return None,
+            JobStatus::Pending | JobStatus::Ready | JobStatus::Encoding { .. } => true,
+            _ => return None,
+        };
+        Some(Self {
+            path: job.path.clone(),
+            source_root: job.source_root.clone(),
+            disc_title: job.disc_title.clone(),
+            configured,
+            track_selection: job.track_selection.clone(),
+            output_path: job.output_path.clone(),
+            crf: job.crf,
+            frame_rate_override: job.frame_rate_override,
+            assumed_frame_rate: job.assumed_frame_rate,
+            extra_args: job.extra_args.clone(),
+            profiles: job.profiles.clone(),
+            mode: job.mode,
+        })
+    }
+
+    /// Whether the source can still be read
+    pub fn source_exists(&self) -> bool {
+        match self.disc_title {
+            Some(ref title) => title.parts.iter().all(|p| p.exists()),
+            None => self.path.exists(),
+        }
+    }
+
+    /// Job to analyze again, carrying these choices until its analysis is in
+    pub fn to_job(&self) -> EncodingJob {
+        let mut job = match self.disc_title {
+            Some(ref title) => EncodingJob::from_disc_title(title.clone()),
+            None => EncodingJob::new(self.path.clone()),
+        };
+        job.source_root = self.source_root.clone();
+        job.restored = Some(Box::new(self.clone()));
+        job
+    }
+
+    /// Apply the choices to a freshly analyzed `job`
+    ///
+    /// A job never configured keeps what analysis picked and is configured
+    /// as usual. A track selection no longer matching the source's tracks is
+    /// left at the defaults, and the job is then configured again.
+    pub fn apply(&self, job: &mut EncodingJob) {
+        if !self.configured {
+            return;
+        }
+        let tracks_match = self
+            .track_selection
+            .audio_indices
+            .iter()
+            .all(|i| job.audio_tracks.iter().any(|t| t.index == *i))
+            && self
+                .track_selection
+                .subtitle_indices
+                .iter()
+                .all(|i| job.subtitle_tracks.iter().any(|t| t.index == *i));
+        if tracks_match {
+            job.track_selection = self.track_selection.clone();
+        }
+        job.crf = self.crf;
+        job.frame_rate_override = self.frame_rate_override;
+        job.assumed_frame_rate = self.assumed_frame_rate;
+        job.extra_args = self.extra_args.clone();
+        job.profiles = self.profiles.clone();
+        if self.mode == JobMode::Encode || job.can_copy_video() {
+            job.mode = self.mode;
+        }
+        if self.output_path.is_some() {
+            job.output_path = self.output_path.clone();
+        }
+        job.status = if tracks_match {
+            JobStatus::Ready
+        } else {
+            JobStatus::AwaitingConfig
+        };
+    }
+}
+
+impl SavedQueue {
+    pub fn of(queue: &QueueState) -> Self {
+        Self {
+            jobs: queue.jobs.iter().filter_map(SavedJob::of).collect(),
+        }
+    }
+
+    /// Queue left unfinished by the last run, if any
+    pub fn load() -> Option<Self> {
+        let content = std::fs::read_to_string(saved_queue_path()).ok()?;
+        serde_json::from_str::<Self>(&content)
+            .ok()
+            .filter(|q| !q.jobs.is_empty())
+    }
+
+    /// Write the queue, replacing the file by a rename so a crash mid-write
+    /// keeps the previous one; an empty queue removes the file
+    pub fn save(&self) -> Result<(), AppError> {
+        let path = saved_queue_path();
+        if self.jobs.is_empty() {
+            match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => return Ok(()),
+            }
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+        std::fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temp, &path)?;
+        Ok(())
+    }
+}
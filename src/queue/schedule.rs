@@ -0,0 +1,95 @@
+//! Hours of the day encodes may start in.
+//!
+//! With `performance.encode_window` set, e.g. to `"23:00-07:00"`, the worker
+//! starts a job only inside the window and otherwise waits until it opens. A
+//! window ending before it starts runs past midnight. An encode already
+//! running when the window closes finishes.
+
+use crate::error::AppError;
+use std::fmt;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+/// Local times encodes may start between, as minutes after midnight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeWindow {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl EncodeWindow {
+    /// Whether `minute` of the day is inside; a window starting and ending at
+    /// the same time is always open
+    pub fn contains(&self, minute: u16) -> bool {
+        match self.start.cmp(&self.end) {
+            std::cmp::Ordering::Less => (self.start..self.end).contains(&minute),
+            std::cmp::Ordering::Greater => minute >= self.start || minute < self.end,
+            std::cmp::Ordering::Equal => true,
+        }
+    }
+
+    /// Whether the window is open now; when the local time can't be read,
+    /// it counts as open so a queue never waits forever
+    pub fn is_open(&self) -> bool {
+        local_minute_of_day().is_none_or(|minute| self.contains(minute))
+    }
+}
+
+impl FromStr for EncodeWindow {
+    type Err = AppError;
+
+    /// Parse `HH:MM-HH:MM`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            AppError::Config(format!(
+                "Encode window \"{}\" must look like 23:00-07:00",
+                s
+            ))
+        };
+        let (start, end) = s.split_once(['-', '–']).ok_or_else(invalid)?;
+        Ok(Self {
+            start: parse_time(start).ok_or_else(invalid)?,
+            end: parse_time(end).ok_or_else(invalid)?,
+        })
+    }
+}
+
+impl fmt::Display for EncodeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}–{:02}:{:02}",
+            self.start / 60,
+            self.start % 60,
+            self.end / 60,
+            self.end % 60
+        )
+    }
+}
+
+/// Minutes after midnight of `HH:MM`
+fn parse_time(s: &str) -> Option<u16> {
+    let (hours, minutes) = s.trim().split_once(':')?;
+    let (hours, minutes): (u16, u16) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Local time of day in minutes after midnight, asked from the system
+pub fn local_minute_of_day() -> Option<u16> {
+    let output = if cfg!(windows) {
+        Command::new("powershell")
+            .args(["-NoProfile", "-Command", "Get-Date -Format HH:mm"])
+            .stdin(Stdio::null())
+            .output()
+    } else {
+        Command::new("date")
+            .arg("+%H:%M")
+            .stdin(Stdio::null())
+            .output()
+    }
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_time(&String::from_utf8_lossy(&output.stdout))
+}
@@ -0,0 +1,86 @@
+//! Serializable view of the queue, for tools that watch it from outside.
+
+use super::job::{EncodingJob, JobStatus};
+use super::state::QueueState;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A job and where it is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobSnapshot {
+    pub id: usize,
+    pub file: String,
+    /// e.g. `encoding`, `done` or `error`
+    pub status: String,
+    /// Encode progress in percent, while encoding
+    pub progress: Option<f32>,
+    pub output: Option<PathBuf>,
+    pub vmaf: Option<f64>,
+    /// Why the job was skipped or failed
+    pub message: Option<String>,
+}
+
+impl JobSnapshot {
+    pub fn of(id: usize, job: &EncodingJob) -> Self {
+        let (progress, vmaf, message) = match &job.status {
+            JobStatus::Encoding { progress } => (Some(*progress), None, None),
+            JobStatus::DoneWithVmaf { score } => (None, Some(*score), None),
+            JobStatus::QualityWarning { vmaf, .. } => (None, Some(*vmaf), None),
+            JobStatus::Skipped { reason, .. } => (None, None, Some(reason.clone())),
+            JobStatus::Error { message, .. } => (None, None, Some(message.clone())),
+            _ => (None, None, None),
+        };
+        Self {
+            id,
+            file: job.display_name(),
+            status: job.status.name().to_string(),
+            progress,
+            output: job.output_path.clone(),
+            vmaf,
+            message,
+        }
+    }
+}
+
+/// The queue's counters, progress and jobs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueSnapshot {
+    pub converted: usize,
+    /// Jobs a rule left out
+    pub skipped: usize,
+    pub user_skipped: usize,
+    pub cancelled: usize,
+    pub errors: usize,
+    /// Overall progress in percent
+    pub progress: f32,
+    pub eta_secs: Option<u64>,
+    /// Encode window the queue waits for, e.g. `23:00–07:00`
+    pub waiting_for_schedule: Option<String>,
+    /// Whether the queue is paused by hand
+    #[serde(default)]
+    pub paused: bool,
+    pub jobs: Vec<JobSnapshot>,
+}
+
+impl QueueSnapshot {
+    /// Snapshot of `queue`, numbering its jobs from `first_id`
+    pub fn of(queue: &QueueState, first_id: usize) -> Self {
+        Self {
+            converted: queue.converted_count,
+            skipped: queue.skipped_count,
+            user_skipped: queue.user_skipped_count,
+            cancelled: queue.cancelled_count,
+            errors: queue.error_count,
+            progress: (queue.overall_progress() * 10.0).round() / 10.0,
+            eta_secs: queue.estimated_time_remaining().map(|d| d.as_secs()),
+            waiting_for_schedule: queue.waiting_for_schedule.map(|w| w.to_string()),
+            paused: queue.is_paused(),
+            jobs: queue
+                .jobs
+                .iter()
+                .enumerate()
+                .map(|(i, job)| JobSnapshot::of(first_id + i, job))
+                .collect(),
+        }
+    }
+}
@@ -1,19 +1,231 @@
-use super::job::{EncodingJob, JobStatus};
+use super::job::{EncodingJob, JobStatus, SkipKind};
+use super::schedule::EncodeWindow;
+use crate::config::{OutputConfig, QueueOrder};
+use crate::encoder::{EncodeProgress, Stage, StageTimings};
+use crate::history::Pacing;
 use crate::utils::format_file_size;
+use std::collections::{HashMap, VecDeque};
 use std::time::{Duration, Instant};
 
+/// Number of progress samples kept for the live graphs
+const STATS_HISTORY_LEN: usize = 240;
+
+/// Recent speed and bitrate samples of the active encode
+#[derive(Debug, Default)]
+pub struct EncodeStatsHistory {
+    pub job_index: Option<usize>,
+    /// Speed in hundredths of realtime
+    pub speed: VecDeque<u64>,
+    /// Bitrate in kbit/s
+    pub bitrate_kbps: VecDeque<u64>,
+    pub latest: EncodeProgress,
+}
+
+impl EncodeStatsHistory {
+    /// Record a progress sample, starting over when a new job begins
+    pub fn record(&mut self, index: usize, progress: &EncodeProgress) {
+        if self.job_index != Some(index) {
+            *self = Self {
+                job_index: Some(index),
+                ..Default::default()
+            };
+        }
+        self.latest = *progress;
+
+        if let Some(speed) = progress.speed {
+            push_capped(&mut self.speed, (speed * 100.0) as u64);
+        }
+        if let Some(bitrate) = progress.bitrate_kbps {
+            push_capped(&mut self.bitrate_kbps, bitrate as u64);
+        }
+    }
+}
+
+fn push_capped(samples: &mut VecDeque<u64>, value: u64) {
+    if samples.len() == STATS_HISTORY_LEN {
+        samples.pop_front();
+    }
+    samples.push_back(value);
+}
+
+/// Space saved by a batch
+///
+/// `audio` and `subtitles` are the estimated sizes of the dropped tracks;
+/// everything else the files lost counts as `video`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpaceSavings {
+    pub video: u64,
+    pub audio: u64,
+    pub subtitles: u64,
+}
+
+impl SpaceSavings {
+    pub fn total(&self) -> u64 {
+        self.video
+            .saturating_add(self.audio)
+            .saturating_add(self.subtitles)
+    }
+
+    /// e.g. `video 3.2 GB · audio 410 MB`, leaving out parts that saved nothing
+    pub fn summary(&self) -> String {
+        [
+            ("video", self.video),
+            ("audio", self.audio),
+            ("subtitles", self.subtitles),
+        ]
+        .iter()
+        .filter(|&&(_, bytes)| bytes > 0)
+        .map(|&(name, bytes)| format!("{} {}", name, format_file_size(bytes)))
+        .collect::<Vec<_>>()
+        .join(" · ")
+    }
+}
+
+/// Combine finished jobs and the active job's progress into a percentage of the run
+pub fn overall_progress(finished: usize, total: usize, current_progress: f32) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    let finished = finished.min(total);
+    if finished == total {
+        return 100.0;
+    }
+
+    // Encoder percentages can be NaN or overshoot when the duration is off
+    let current = if current_progress.is_finite() {
+        current_progress.clamp(0.0, 100.0)
+    } else {
+        0.0
+    };
+    let total_progress = (finished as f64 * 100.0 + current as f64) / total as f64;
+    total_progress.clamp(0.0, 100.0) as f32
+}
+
+/// [`overall_progress`] with several jobs running at once, each at its own percentage
+pub fn overall_progress_of(finished: usize, total: usize, running: &[f32]) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    let finished = finished.min(total);
+    if finished == total {
+        return 100.0;
+    }
+
+    let running: f64 = running
+        .iter()
+        .take(total - finished)
+        .map(|&p| {
+            if p.is_finite() {
+                p.clamp(0.0, 100.0) as f64
+            } else {
+                0.0
+            }
+        })
+        .sum();
+    let total_progress = (finished as f64 * 100.0 + running) / total as f64;
+    total_progress.clamp(0.0, 100.0) as f32
+}
+
+/// Share of a phase without progress reports that time alone may fill
+const PHASE_STRETCH_CAP: f64 = 0.95;
+
+/// Weight of the newest sample in the smoothed progress rate
+const ETA_SMOOTHING: f64 = 0.2;
+/// Minimum time between samples, so bursts of progress lines do not dominate the rate
+const ETA_MIN_SAMPLE_SECS: f64 = 1.0;
+
+/// Estimates time remaining from an exponentially smoothed progress rate
+///
+/// Falls back to the average rate since the start until the first rate sample.
+#[derive(Debug, Default, Clone)]
+pub struct EtaEstimator {
+    last: Option<(f64, f64)>,
+    /// Smoothed rate in percent per second
+    rate: Option<f64>,
+}
+
+impl EtaEstimator {
+    /// Record the overall progress reached after `elapsed`
+    pub fn record(&mut self, elapsed: Duration, progress: f32) {
+        let (now, progress) = (elapsed.as_secs_f64(), progress as f64);
+        if !progress.is_finite() {
+            return;
+        }
+
+        let Some((last_time, last_progress)) = self.last else {
+            self.last = Some((now, progress));
+            return;
+        };
+
+        // Progress going backwards means a new run; start over
+        if progress < last_progress || now < last_time {
+            *self = Self {
+                last: Some((now, progress)),
+                rate: None,
+            };
+            return;
+        }
+
+        let dt = now - last_time;
+        if dt < ETA_MIN_SAMPLE_SECS {
+            return;
+        }
+        let sample = (progress - last_progress) / dt;
+        self.rate = Some(match self.rate {
+            Some(rate) => ETA_SMOOTHING * sample + (1.0 - ETA_SMOOTHING) * rate,
+            None => sample,
+        });
+        self.last = Some((now, progress));
+    }
+
+    /// Time left to reach 100% from `progress`, or `None` if it cannot be estimated
+    pub fn remaining(&self, elapsed: Duration, progress: f32) -> Option<Duration> {
+        let progress = progress as f64;
+        if !(progress > 0.0 && progress < 100.0) {
+            return None;
+        }
+
+        let rate = match self.rate {
+            Some(rate) if rate > 0.0 => rate,
+            _ => progress / elapsed.as_secs_f64(),
+        };
+        let remaining_secs = (100.0 - progress) / rate;
+        if remaining_secs > 0.0 {
+            Duration::try_from_secs_f64(remaining_secs).ok()
+        } else {
+            None
+        }
+    }
+}
+
 /// Overall queue state
 pub struct QueueState {
     pub jobs: Vec<EncodingJob>,
     pub current_job_index: usize,
     pub config_job_index: usize,
+    /// Last file confirmed on the track configuration screen, to compare the next one with
+    pub previous_config_job: Option<EncodingJob>,
     pub start_time: Option<Instant>,
     pub end_time: Option<Instant>,
     pub total_jobs_to_encode: usize,
     pub converted_count: usize,
+    /// Jobs a rule left out, e.g. sources already in AV1
     pub skipped_count: usize,
+    /// Jobs skipped by hand before they started
+    pub user_skipped_count: usize,
+    pub cancelled_count: usize,
     pub error_count: usize,
     pub encoding_progress_done: usize,
+    pub stats: EncodeStatsHistory,
+    pub eta: EtaEstimator,
+    /// Time around the encode learned from earlier runs
+    pub pacing: Pacing,
+    /// Encode window the worker waits for before starting the next job
+    pub waiting_for_schedule: Option<EncodeWindow>,
+    /// When the queue was paused, while it is
+    pub paused_at: Option<Instant>,
+    /// Time spent paused before the current pause
+    pub paused_for: Duration,
 }
 
 impl QueueState {
@@ -22,75 +234,193 @@ impl QueueState {
             jobs: Vec::new(),
             current_job_index: 0,
             config_job_index: 0,
+            previous_config_job: None,
             start_time: None,
             end_time: None,
             total_jobs_to_encode: 0,
             converted_count: 0,
             skipped_count: 0,
+            user_skipped_count: 0,
+            cancelled_count: 0,
             error_count: 0,
             encoding_progress_done: 0,
+            stats: EncodeStatsHistory::default(),
+            eta: EtaEstimator::default(),
+            pacing: Pacing::default(),
+            waiting_for_schedule: None,
+            paused_at: None,
+            paused_for: Duration::ZERO,
         }
     }
 
+    /// Time the queue has been running, leaving out the time it was paused
     pub fn elapsed_time(&self) -> Option<Duration> {
         self.start_time.map(|start| {
-            self.end_time
-                .map(|end| end.duration_since(start))
-                .unwrap_or_else(|| start.elapsed())
+            let end = self.end_time.unwrap_or_else(Instant::now);
+            let paused = self.paused_for + self.paused_at.map_or(Duration::ZERO, |t| t.elapsed());
+            end.duration_since(start).saturating_sub(paused)
         })
     }
 
-    pub fn overall_progress(&self) -> f32 {
-        if self.total_jobs_to_encode == 0 {
-            return 0.0;
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    pub fn pause(&mut self) {
+        self.paused_at.get_or_insert_with(Instant::now);
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(at) = self.paused_at.take() {
+            self.paused_for += at.elapsed();
         }
+    }
 
-        let completed = self
-            .jobs
+    /// Progress across the whole run, in percent
+    ///
+    /// Every job that reached a terminal state (including skipped and failed ones)
+    /// counts as finished, so the total always ends at 100.
+    pub fn overall_progress(&self) -> f32 {
+        let running: Vec<f32> = self
+            .encoding_jobs()
+            .map(|i| self.job_progress(&self.jobs[i]))
+            .collect();
+
+        overall_progress_of(
+            self.encoding_progress_done,
+            self.total_jobs_to_encode,
+            &running,
+        )
+    }
+
+    /// Indices of the jobs encoding right now, in queue order
+    pub fn encoding_jobs(&self) -> impl Iterator<Item = usize> + '_ {
+        self.jobs
             .iter()
-            .filter(|j| {
-                matches!(
-                    j.status,
-                    JobStatus::Done
-                        | JobStatus::DoneWithVmaf { .. }
-                        | JobStatus::QualityWarning { .. }
-                )
-            })
-            .count();
+            .enumerate()
+            .filter(|(_, j)| matches!(j.status, JobStatus::Encoding { .. }))
+            .map(|(i, _)| i)
+    }
 
-        let current_progress = self
-            .jobs
-            .get(self.current_job_index)
-            .and_then(|j| {
-                if let JobStatus::Encoding { progress } = j.status {
-                    Some(progress)
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(0.0);
+    /// Earlier runs' pacing, refined by the jobs finished in this one
+    pub fn current_pacing(&self) -> Pacing {
+        let mut pacing = self.pacing;
+        let encoded = |j: &&EncodingJob| {
+            matches!(
+                j.status,
+                JobStatus::Done | JobStatus::DoneWithVmaf { .. } | JobStatus::QualityWarning { .. }
+            )
+        };
+        for job in self.jobs.iter().filter(encoded) {
+            pacing.add(&job.stage_timings, job.source_size);
+        }
+        pacing
+    }
+
+    /// Progress of a running job in percent, including the time before and after its encode
+    ///
+    /// The encode progress covers the encode's share of the job. The phases
+    /// around it report no progress and are filled by the time they usually
+    /// take, so the total moves steadily instead of resting at 0% or 100%.
+    pub fn job_progress(&self, job: &EncodingJob) -> f32 {
+        let JobStatus::Encoding { progress } = job.status else {
+            return 0.0;
+        };
+        let pacing = self.current_pacing();
+        let (before, after) = (pacing.before_ratio(), pacing.after_ratio());
+        let total = 1.0 + before + after;
+        let elapsed = job.phase_started.map(|t| t.elapsed()).unwrap_or_default();
+        let stretch = |expected: Option<Duration>| match expected {
+            Some(expected) if !expected.is_zero() => {
+                (elapsed.as_secs_f64() / expected.as_secs_f64()).min(PHASE_STRETCH_CAP)
+            }
+            _ => 0.0,
+        };
 
-        let total_progress =
-            (completed as f32 * 100.0 + current_progress) / self.total_jobs_to_encode as f32;
-        total_progress.min(100.0)
+        let done = if let Some(encode) = job.stage_timings.get(Stage::Encode) {
+            before + 1.0 + after * stretch(Some(encode.mul_f64(after)))
+        } else if progress > 0.0 || job.stage_timings.get(Stage::Prepare).is_some() {
+            before + progress as f64 / 100.0
+        } else {
+            let expected = self.expected_encode_time(job).map(|d| d.mul_f64(before));
+            before * stretch(expected)
+        };
+        (done / total * 100.0).clamp(0.0, 100.0) as f32
     }
 
-    pub fn estimated_time_remaining(&self) -> Option<Duration> {
-        let progress = self.overall_progress();
-        if progress <= 0.0 || progress >= 100.0 {
+    /// Time left for a running job, including verification after its encode
+    pub fn job_time_remaining(&self, job: &EncodingJob) -> Option<Duration> {
+        let JobStatus::Encoding { progress } = job.status else {
             return None;
+        };
+        let elapsed = job.phase_started?.elapsed();
+        let pacing = self.current_pacing();
+        let (before, after) = (pacing.before_ratio(), pacing.after_ratio());
+
+        let remaining = if let Some(encode) = job.stage_timings.get(Stage::Encode) {
+            encode.mul_f64(after).saturating_sub(elapsed)
+        } else if progress > 0.0 {
+            let encode = elapsed.div_f64(progress as f64 / 100.0);
+            encode.saturating_sub(elapsed) + encode.mul_f64(after)
+        } else {
+            let encode = self.expected_encode_time(job)?;
+            let before = if job.stage_timings.get(Stage::Prepare).is_some() {
+                Duration::ZERO
+            } else {
+                encode.mul_f64(before).saturating_sub(elapsed)
+            };
+            before + encode + encode.mul_f64(after)
+        };
+        (!remaining.is_zero()).then_some(remaining)
+    }
+
+    /// Expected encode time of a job from this run's throughput, or earlier runs'
+    fn expected_encode_time(&self, job: &EncodingJob) -> Option<Duration> {
+        let size = job.source_size?;
+        match self.encode_throughput() {
+            Some(throughput) => Duration::try_from_secs_f64(size as f64 / throughput).ok(),
+            None => self.pacing.encode_time(size),
         }
+    }
+
+    /// Feed the current overall progress into the ETA estimator
+    pub fn record_eta_sample(&mut self) {
+        if let Some(elapsed) = self.elapsed_time() {
+            let progress = self.overall_progress();
+            self.eta.record(elapsed, progress);
+        }
+    }
+
+    pub fn estimated_time_remaining(&self) -> Option<Duration> {
         let elapsed = self.elapsed_time()?;
-        let elapsed_secs = elapsed.as_secs_f64();
-        let total_estimated_secs = elapsed_secs / (progress as f64 / 100.0);
-        let remaining_secs = total_estimated_secs - elapsed_secs;
-        if remaining_secs > 0.0 {
-            Some(Duration::from_secs_f64(remaining_secs))
-        } else {
-            None
+        self.eta.remaining(elapsed, self.overall_progress())
+    }
+
+    /// Mark a job as not encoded, counting it under `kind`
+    pub fn skip(&mut self, index: usize, kind: SkipKind, reason: impl Into<String>) {
+        let Some(job) = self.jobs.get_mut(index) else {
+            return;
+        };
+        job.status = JobStatus::Skipped {
+            kind,
+            reason: reason.into(),
+        };
+        match kind {
+            SkipKind::Rule => self.skipped_count += 1,
+            SkipKind::User => self.user_skipped_count += 1,
+            SkipKind::Cancelled => self.cancelled_count += 1,
         }
     }
 
+    /// Jobs converted, skipped, cancelled or failed so far
+    pub fn finished_count(&self) -> usize {
+        self.converted_count
+            + self.skipped_count
+            + self.user_skipped_count
+            + self.cancelled_count
+            + self.error_count
+    }
+
     /// Check if all jobs are in a terminal state
     pub fn all_completed(&self) -> bool {
         self.jobs.iter().all(|j| {
@@ -107,26 +437,153 @@ impl QueueState {
 
     /// Get total space saved across all completed jobs
     pub fn total_space_saved(&self) -> (u64, String) {
-        let total_saved: u64 = self
+        let total_saved = self
             .jobs
             .iter()
             .filter_map(|j| j.size_reduction().map(|(saved, _)| saved))
-            .sum();
+            .fold(0u64, u64::saturating_add);
         (total_saved, format_file_size(total_saved))
     }
 
+    /// Space saved across all completed jobs, split into video and dropped tracks
+    pub fn space_savings(&self) -> SpaceSavings {
+        let mut savings = SpaceSavings::default();
+        for job in &self.jobs {
+            let Some((saved, _)) = job.size_reduction() else {
+                continue;
+            };
+            let (audio, subtitles) = job.dropped_track_savings();
+            // Track sizes are estimates and may exceed what the file lost
+            let audio = audio.min(saved);
+            let subtitles = subtitles.min(saved - audio);
+            savings.video = savings.video.saturating_add(saved - audio - subtitles);
+            savings.audio = savings.audio.saturating_add(audio);
+            savings.subtitles = savings.subtitles.saturating_add(subtitles);
+        }
+        savings
+    }
+
+    /// Average source bytes encoded per second across all encoded jobs
+    pub fn encode_throughput(&self) -> Option<f64> {
+        let (bytes, secs) = self
+            .jobs
+            .iter()
+            .filter(|j| !j.mode.copies_video())
+            .filter_map(|j| Some((j.source_size?, j.stage_timings.get(Stage::Encode)?)))
+            .fold((0u64, 0.0), |(bytes, secs), (size, elapsed)| {
+                (bytes.saturating_add(size), secs + elapsed.as_secs_f64())
+            });
+        (secs > 0.0).then(|| bytes as f64 / secs)
+    }
+
+    /// Time spent in each pipeline stage across all jobs
+    pub fn total_stage_timings(&self) -> StageTimings {
+        let mut total = StageTimings::default();
+        for job in &self.jobs {
+            total.add(&job.stage_timings);
+        }
+        total
+    }
+
+    /// Job of another source already writing, or done writing, the output of
+    /// job `index`
+    ///
+    /// Encoding it too would overwrite that output, and with sources deleted
+    /// after their encode lose the other source for good.
+    pub fn output_taken_by(&self, index: usize) -> Option<&EncodingJob> {
+        let job = self.jobs.get(index)?;
+        let output = job.output_path.as_ref()?;
+        self.jobs.iter().enumerate().find_map(|(i, other)| {
+            let writes = match other.status {
+                JobStatus::Pending => other.metadata.is_some(),
+                JobStatus::Encoding { .. }
+                | JobStatus::Done
+                | JobStatus::DoneWithVmaf { .. }
+                | JobStatus::QualityWarning { .. } => true,
+                _ => false,
+            };
+            (i != index
+                && writes
+                && other.path != job.path
+                && other.output_path.as_ref() == Some(output))
+            .then_some(other)
+        })
+    }
+
+    /// Split a job with several device profiles into one job per output
+    ///
+    /// The first output keeps the job's place and the others are appended, so
+    /// indices already handed to the worker stay valid. Returns the indices of
+    /// all outputs in encoding order.
+    pub fn split_job(&mut self, index: usize, output: &OutputConfig) -> Vec<usize> {
+        let Some(job) = self.jobs.get(index) else {
+            return Vec::new();
+        };
+        let mut outputs = job.split_outputs(output).into_iter();
+        let mut indices = vec![index];
+        if let Some(first) = outputs.next() {
+            self.jobs[index] = first;
+        }
+        for job in outputs {
+            indices.push(self.jobs.len());
+            self.jobs.push(job);
+        }
+        indices
+    }
+
+    /// Put the jobs in `order`, given the durations known by job index
+    ///
+    /// Jobs without a known size or duration go last, in their current order.
+    pub fn order_jobs(&mut self, order: QueueOrder, durations: &HashMap<usize, f64>) {
+        let mut keyed: Vec<(Option<f64>, EncodingJob)> = self
+            .jobs
+            .drain(..)
+            .enumerate()
+            .map(|(i, job)| {
+                let key = match order {
+                    QueueOrder::AsIs => Some(0.0),
+                    QueueOrder::SmallestFirst => job.source_size.map(|s| s as f64),
+                    QueueOrder::LargestFirst => job.source_size.map(|s| -(s as f64)),
+                    QueueOrder::ShortestFirst => durations.get(&i).copied(),
+                };
+                (key, job)
+            })
+            .collect();
+        keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+            (Some(a), Some(b)) => a.total_cmp(b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+        self.jobs = keyed.into_iter().map(|(_, job)| job).collect();
+    }
+
+    /// Check if some job still waits for analysis or track configuration
+    pub fn configuring(&self) -> bool {
+        self.jobs
+            .iter()
+            .any(|j| matches!(j.status, JobStatus::Analyzing | JobStatus::AwaitingConfig))
+    }
+
     /// Reset the queue for a new session
     pub fn reset(&mut self) {
         self.jobs.clear();
         self.current_job_index = 0;
         self.config_job_index = 0;
+        self.previous_config_job = None;
         self.start_time = None;
         self.end_time = None;
         self.total_jobs_to_encode = 0;
         self.converted_count = 0;
         self.skipped_count = 0;
+        self.user_skipped_count = 0;
+        self.cancelled_count = 0;
         self.error_count = 0;
         self.encoding_progress_done = 0;
+        self.stats = EncodeStatsHistory::default();
+        self.eta = EtaEstimator::default();
+        self.pacing = Pacing::default();
+        self.waiting_for_schedule = None;
+        self.paused_at = None;
+        self.paused_for = Duration::ZERO;
     }
 }
 
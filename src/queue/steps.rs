@@ -0,0 +1,366 @@
+//! Steps chained after the encode of a job.
+//!
+//! A source can go through more than its encode: its subtitles written next
+//! to the output, then everything moved into a library folder. The steps form
+//! a chain in the configured order. Each starts only once the one before it
+//! succeeded, and one that fails or is skipped skips all that follow. The
+//! worker runs the chain right after the encode, before the job counts as
+//! finished, so the library only ever receives outputs that passed.
+
+use super::job::JobStatus;
+use crate::config::{OutputConfig, PostStep};
+use crate::error::AppError;
+use crate::utils::tools;
+use crate::verifier::streams::{of_type, probe_streams};
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// A step run after the encode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PostAction {
+    /// Write the output's subtitles to files next to it
+    ExtractSubtitles,
+    /// Move the output, and files written next to it, into a folder
+    MoveTo(PathBuf),
+}
+
+impl PostAction {
+    pub fn display_name(&self) -> String {
+        match self {
+            PostAction::ExtractSubtitles => "Extract subtitles".to_string(),
+            PostAction::MoveTo(dir) => format!("Move to {}", dir.display()),
+        }
+    }
+}
+
+/// Steps configured for a source, with the library folder resolved for it
+///
+/// Like outputs in an output directory, a moved output keeps its source's
+/// folder below the queued `root`.
+pub fn post_actions(output: &OutputConfig, source: &Path, root: Option<&Path>) -> Vec<PostAction> {
+    output
+        .after_encode
+        .iter()
+        .filter_map(|step| match step {
+            PostStep::ExtractSubtitles => Some(PostAction::ExtractSubtitles),
+            PostStep::MoveToLibrary => {
+                let Some(library) = output
+                    .library_directory
+                    .as_deref()
+                    .filter(|d| !d.is_empty())
+                else {
+                    warn!("move_to_library needs library_directory; the step is left out");
+                    return None;
+                };
+                let relative = root
+                    .zip(source.parent())
+                    .and_then(|(root, parent)| parent.strip_prefix(root).ok())
+                    .unwrap_or(Path::new(""));
+                Some(PostAction::MoveTo(Path::new(library).join(relative)))
+            }
+        })
+        .collect()
+}
+
+/// Where a step of the chain is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepStatus {
+    Pending,
+    Running,
+    /// Finished, with a short note on what it did
+    Done(String),
+    Failed(String),
+    /// Not run, and why
+    Skipped(String),
+}
+
+impl StepStatus {
+    /// Marker shown before the step name
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "·",
+            StepStatus::Running => "»",
+            StepStatus::Done(_) => "✓",
+            StepStatus::Failed(_) => "✗",
+            StepStatus::Skipped(_) => "-",
+        }
+    }
+
+    /// Name of the status in event streams
+    pub fn name(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "pending",
+            StepStatus::Running => "running",
+            StepStatus::Done(_) => "done",
+            StepStatus::Failed(_) => "failed",
+            StepStatus::Skipped(_) => "skipped",
+        }
+    }
+
+    /// Note shown after the step name, if any
+    pub fn note(&self) -> Option<&str> {
+        match self {
+            StepStatus::Pending | StepStatus::Running => None,
+            StepStatus::Done(note) | StepStatus::Failed(note) | StepStatus::Skipped(note) => {
+                (!note.is_empty()).then_some(note.as_str())
+            }
+        }
+    }
+
+    /// The encode as the first step of the chain
+    pub fn of_encode(status: &JobStatus) -> Self {
+        match status {
+            JobStatus::Pending
+            | JobStatus::Analyzing
+            | JobStatus::AwaitingConfig
+            | JobStatus::Ready => StepStatus::Pending,
+            JobStatus::Encoding { .. } => StepStatus::Running,
+            JobStatus::Done | JobStatus::DoneWithVmaf { .. } => StepStatus::Done(String::new()),
+            JobStatus::QualityWarning { .. } => StepStatus::Failed("below threshold".to_string()),
+            JobStatus::Error { kind, .. } => StepStatus::Failed(kind.summary().to_string()),
+            JobStatus::Skipped { reason, .. } => StepStatus::Skipped(reason.clone()),
+        }
+    }
+}
+
+/// A step of a job and where it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobStep {
+    pub action: PostAction,
+    pub status: StepStatus,
+}
+
+/// Steps of a job after its encode, in the order they run
+#[derive(Debug, Clone, Default)]
+pub struct TaskGraph {
+    pub steps: Vec<JobStep>,
+}
+
+impl TaskGraph {
+    pub fn new(actions: Vec<PostAction>) -> Self {
+        Self {
+            steps: actions
+                .into_iter()
+                .map(|action| JobStep {
+                    action,
+                    status: StepStatus::Pending,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Step being run, if any
+    pub fn running(&self) -> Option<&JobStep> {
+        self.steps
+            .iter()
+            .find(|step| step.status == StepStatus::Running)
+    }
+
+    pub fn set_status(&mut self, position: usize, status: StepStatus) {
+        if let Some(step) = self.steps.get_mut(position) {
+            step.status = status;
+        }
+    }
+}
+
+/// Run the steps after an encode, reporting each status change
+///
+/// `blocked` is why the encode leaves nothing to work on, which skips every
+/// step. Returns where the output ended up.
+pub async fn run_chain(
+    actions: &[PostAction],
+    output: PathBuf,
+    blocked: Option<String>,
+    cancel: &CancellationToken,
+    mut on_status: impl FnMut(usize, StepStatus),
+) -> PathBuf {
+    let mut files = ChainFiles {
+        output,
+        sidecars: Vec::new(),
+    };
+    let mut blocked = blocked;
+    for (position, action) in actions.iter().enumerate() {
+        if blocked.is_none() && cancel.is_cancelled() {
+            blocked = Some("Cancelled".to_string());
+        }
+        if let Some(ref reason) = blocked {
+            on_status(position, StepStatus::Skipped(reason.clone()));
+            continue;
+        }
+
+        on_status(position, StepStatus::Running);
+        let step = action.clone();
+        let mut step_files = files.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let result = run_action(&step, &mut step_files);
+            (result, step_files)
+        })
+        .await;
+        match result {
+            Ok((Ok(note), step_files)) => {
+                files = step_files;
+                on_status(position, StepStatus::Done(note));
+            }
+            // Files a failed move got through with are where it left them
+            Ok((Err(e), step_files)) => {
+                files = step_files;
+                warn!("{} failed: {}", action.display_name(), e);
+                on_status(position, StepStatus::Failed(e.to_string()));
+                blocked = Some(format!("{} failed", action.display_name()));
+            }
+            Err(e) => {
+                on_status(position, StepStatus::Failed(e.to_string()));
+                blocked = Some(format!("{} failed", action.display_name()));
+            }
+        }
+    }
+    files.output
+}
+
+/// Output and the files steps wrote next to it
+#[derive(Debug, Clone)]
+struct ChainFiles {
+    output: PathBuf,
+    sidecars: Vec<PathBuf>,
+}
+
+fn run_action(action: &PostAction, files: &mut ChainFiles) -> Result<String, AppError> {
+    match action {
+        PostAction::ExtractSubtitles => {
+            let (written, left) = extract_subtitles(&files.output)?;
+            let note = match (written.len(), left) {
+                (1, 0) => "1 file".to_string(),
+                (n, 0) => format!("{} files", n),
+                (n, left) => format!("{} files, {} bitmap left in the output", n, left),
+            };
+            files.sidecars.extend(written);
+            Ok(note)
+        }
+        PostAction::MoveTo(dir) => {
+            std::fs::create_dir_all(dir).map_err(|e| AppError::Io {
+                path: dir.clone(),
+                operation: "create library folder",
+                message: e.to_string(),
+            })?;
+            files.output = move_file(&files.output, dir)?;
+            for sidecar in &mut files.sidecars {
+                *sidecar = move_file(sidecar, dir)?;
+            }
+            Ok(files.output.display().to_string())
+        }
+    }
+}
+
+/// Write each subtitle stream of `output` to a file next to it, as
+/// `<name>.<language>.<ext>`
+///
+/// Returns the files written and how many streams have no format of their own
+/// to go into (DVD and DVB bitmaps).
+pub fn extract_subtitles(output: &Path) -> Result<(Vec<PathBuf>, usize), AppError> {
+    let streams = probe_streams(output)?;
+    let mut written = Vec::new();
+    let mut taken = HashSet::new();
+    let mut left = 0;
+    for (index, stream) in of_type(&streams, "subtitle").into_iter().enumerate() {
+        let Some((extension, codec)) = sidecar_format(stream.codec_name.as_deref()) else {
+            left += 1;
+            continue;
+        };
+        let path = sidecar_path(output, stream.language.as_deref(), extension, &mut taken);
+        let result = Command::new(tools::ffmpeg())
+            .args(["-hide_banner", "-nostdin", "-y", "-i"])
+            .arg(output)
+            .args(["-map", &format!("0:s:{}", index), "-c:s", codec])
+            .arg(&path)
+            .output()
+            .map_err(|e| AppError::CommandExecution(format!("Failed to run ffmpeg: {}", e)))?;
+        if !result.status.success() {
+            return Err(AppError::CommandExecution(format!(
+                "Extracting subtitle {} failed: {}",
+                index + 1,
+                String::from_utf8_lossy(&result.stderr).trim()
+            )));
+        }
+        info!("Extracted subtitle {} to {}", index + 1, path.display());
+        written.push(path);
+    }
+    Ok((written, left))
+}
+
+/// File extension and codec a subtitle stream is written with
+fn sidecar_format(codec: Option<&str>) -> Option<(&'static str, &'static str)> {
+    match codec? {
+        "subrip" | "srt" => Some(("srt", "copy")),
+        "mov_text" => Some(("srt", "srt")),
+        "ass" | "ssa" => Some(("ass", "copy")),
+        "webvtt" => Some(("vtt", "copy")),
+        "hdmv_pgs_subtitle" => Some(("sup", "copy")),
+        _ => None,
+    }
+}
+
+/// `movie.eng.srt`, then `movie.eng.2.srt` for a second English track
+fn sidecar_path(
+    output: &Path,
+    language: Option<&str>,
+    extension: &str,
+    taken: &mut HashSet<PathBuf>,
+) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let base = match language {
+        Some(language) => format!("{}.{}", stem, language),
+        None => stem.to_string(),
+    };
+    let mut path = output.with_file_name(format!("{}.{}", base, extension));
+    let mut n = 2;
+    while taken.contains(&path) {
+        path = output.with_file_name(format!("{}.{}.{}", base, n, extension));
+        n += 1;
+    }
+    taken.insert(path.clone());
+    path
+}
+
+/// Move `file` into `dir`, copying across filesystems; never overwrites
+fn move_file(file: &Path, dir: &Path) -> Result<PathBuf, AppError> {
+    let target = dir.join(file.file_name().unwrap_or_default());
+    if target == file {
+        return Ok(target);
+    }
+    let io_error = |path: &Path, operation: &'static str| {
+        let path = path.to_path_buf();
+        move |e: std::io::Error| AppError::Io {
+            path,
+            operation,
+            message: e.to_string(),
+        }
+    };
+    if target.exists() {
+        return Err(AppError::Io {
+            path: target,
+            operation: "move to library",
+            message: "a file with this name already exists".to_string(),
+        });
+    }
+    if std::fs::rename(file, &target).is_err() {
+        // Another filesystem: the original goes only once the copy is on disk
+        let result = std::fs::copy(file, &target)
+            .and_then(|_| File::open(&target)?.sync_all())
+            .map_err(io_error(&target, "copy to library"));
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&target);
+            return Err(e);
+        }
+        std::fs::remove_file(file).map_err(io_error(file, "remove moved file"))?;
+    }
+    info!("Moved {} to {}", file.display(), target.display());
+    Ok(target)
+}
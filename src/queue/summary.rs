@@ -0,0 +1,142 @@
+//! Summary of a finished batch, for cron jobs and wrappers.
+//!
+//! Written as JSON when a batch finishes, to `output.summary_file` or
+//! `last_run.json` in the data directory. Headless runs also exit with the
+//! batch's [`RunOutcome`].
+
+use super::job::{EncodingJob, JobStatus, SkipKind};
+use super::state::QueueState;
+use crate::error::AppError;
+use crate::utils::paths::data_dir;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How a batch went, from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    /// Every job was converted or skipped
+    Ok,
+    /// Some outputs scored below the VMAF threshold
+    QualityWarnings,
+    /// Some jobs failed
+    Errors,
+}
+
+impl RunOutcome {
+    /// Exit code of a headless run; 1 and 2 are taken by startup failures
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunOutcome::Ok => 0,
+            RunOutcome::QualityWarnings => 3,
+            RunOutcome::Errors => 4,
+        }
+    }
+}
+
+/// What became of a job
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobSummary {
+    pub file: PathBuf,
+    /// e.g. `done`, `quality_warning`, `skipped` or `error`
+    pub status: &'static str,
+    pub output: Option<PathBuf>,
+    pub source_size: Option<u64>,
+    pub output_size: Option<u64>,
+    pub vmaf: Option<f64>,
+    /// Time spent on the job, from the start of its encode to its last step
+    pub elapsed_secs: Option<f64>,
+    /// Who or what skipped the job: `rule`, `user` or `cancelled`
+    pub skip: Option<SkipKind>,
+    /// Why the job was skipped or failed
+    pub message: Option<String>,
+}
+
+impl JobSummary {
+    pub fn of(job: &EncodingJob) -> Self {
+        let (vmaf, skip, message) = match &job.status {
+            JobStatus::DoneWithVmaf { score } => (Some(*score), None, None),
+            JobStatus::QualityWarning { vmaf, .. } => (Some(*vmaf), None, None),
+            JobStatus::Skipped { kind, reason } => (None, Some(*kind), Some(reason.clone())),
+            JobStatus::Error { message, .. } => (None, None, Some(message.clone())),
+            _ => (None, None, None),
+        };
+        let elapsed = job.stage_timings.total().as_secs_f64();
+        Self {
+            file: job.path.clone(),
+            status: job.status.name(),
+            output: job.output_path.clone(),
+            source_size: job.source_size,
+            output_size: job.output_size,
+            vmaf,
+            elapsed_secs: (!job.stage_timings.is_empty()).then_some(elapsed),
+            skip,
+            message,
+        }
+    }
+}
+
+/// The batch as a whole
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunSummary {
+    pub outcome: RunOutcome,
+    /// Unix time the summary was written at
+    pub finished_at: u64,
+    pub elapsed_secs: Option<f64>,
+    pub converted: usize,
+    pub quality_warnings: usize,
+    /// Jobs a rule left out
+    pub skipped: usize,
+    pub user_skipped: usize,
+    pub cancelled: usize,
+    pub errors: usize,
+    pub jobs: Vec<JobSummary>,
+}
+
+impl RunSummary {
+    pub fn of(queue: &QueueState) -> Self {
+        let jobs: Vec<JobSummary> = queue.jobs.iter().map(JobSummary::of).collect();
+        let quality_warnings = jobs
+            .iter()
+            .filter(|j| j.status == "quality_warning")
+            .count();
+        let outcome = if queue.error_count > 0 {
+            RunOutcome::Errors
+        } else if quality_warnings > 0 {
+            RunOutcome::QualityWarnings
+        } else {
+            RunOutcome::Ok
+        };
+        Self {
+            outcome,
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            elapsed_secs: queue.elapsed_time().map(|d| d.as_secs_f64()),
+            converted: queue.converted_count,
+            quality_warnings,
+            skipped: queue.skipped_count,
+            user_skipped: queue.user_skipped_count,
+            cancelled: queue.cancelled_count,
+            errors: queue.error_count,
+            jobs,
+        }
+    }
+
+    /// Write the summary to `path`, or `last_run.json` in the data directory
+    pub fn write(&self, path: Option<&Path>) -> Result<PathBuf, AppError> {
+        let path = path.map_or_else(|| data_dir().join("last_run.json"), Path::to_path_buf);
+        let io_error = |operation, e: std::io::Error| AppError::Io {
+            path: path.clone(),
+            operation,
+            message: e.to_string(),
+        };
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| io_error("create summary folder", e))?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, json + "\n").map_err(|e| io_error("write run summary", e))?;
+        Ok(path)
+    }
+}
@@ -1,34 +1,76 @@
 use crate::analyzer::VideoMetadata;
-use crate::config::AppConfig;
-use crate::encoder::{self, FullEncodeResult};
+use crate::config::{Encoder, SharedConfig};
+use crate::encoder::{
+    self, EncodeOverrides, EncodeProgress, EncodeProvenance, FullEncodeResult, PipelineEvent,
+    SimulatedEncode, Stage,
+};
+use crate::error::AppError;
+use crate::queue::schedule::EncodeWindow;
+use crate::queue::steps::{self, PostAction, StepStatus};
 use crate::tracks::TrackSelection;
+use crate::utils::claim::SourceClaim;
+use crate::utils::{priority, suspend};
+use crate::verifier::{ValidationIssue, VmafSegment};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
-use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
-use std::sync::mpsc::Sender;
-use tracing::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::{self, JoinSet};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, info, info_span, warn};
 
-/// Messages sent from the worker thread to the main thread
+/// How often a queue outside its encode window checks the time
+const SCHEDULE_POLL: Duration = Duration::from_secs(30);
+
+/// Messages sent from the worker task to the UI
 pub enum WorkerMessage {
     /// Progress update for a file
-    Progress(usize, f32),
+    Progress(usize, EncodeProgress),
     /// Encoding completed successfully
     Done(usize),
     /// Encoding completed with VMAF score
     DoneWithVmaf(usize, f64),
     /// Error occurred
-    Error(usize, String),
+    Error(usize, AppError),
     /// Quality below threshold
     QualityWarning(usize, f64, f64),
-    /// Encoding was cancelled
+    /// The whole queue was cancelled
     Cancelled,
+    /// A single job was cancelled; the queue carries on
+    JobCancelled(usize),
     /// Source file was deleted after successful encoding
     SourceDeleted(usize),
     /// Source file was kept because VMAF was below 90
     SourceKeptLowVmaf(usize, f64),
+    /// Source file was kept by a deletion guard, and why
+    SourceKept(usize, String),
+    /// Output was remuxed (encoded size, remuxed size)
+    Remuxed(usize, u64, u64),
+    /// A pipeline stage finished after the given time
+    StageTime(usize, Stage, Duration),
+    /// Lowest-scoring time ranges behind a quality warning
+    VmafWorstSegments(usize, Vec<VmafSegment>),
+    /// Output streams that drifted or do not match the selection
+    ValidationIssues(usize, Vec<ValidationIssue>),
+    /// The file was encoded with SVT-AV1 instead of the hardware encoder, and why
+    EncoderFallback(usize, Encoder, String),
+    /// Nothing was encoded; what the encode would have done
+    Simulated(usize, SimulatedEncode),
+    /// How the output is produced
+    Provenance(usize, EncodeProvenance),
+    /// Skipped because another instance claimed the source, and which
+    Claimed(usize, String),
+    /// A step after the encode changed status (job, position in the chain)
+    Step(usize, usize, StepStatus),
+    /// A step moved the output here
+    OutputMoved(usize, PathBuf),
+    /// Outside the encode window, waiting for it to open; `None` once it is
+    WaitingForSchedule(Option<EncodeWindow>),
 }
 
-/// Data needed by the worker thread for one job
+/// Data needed by the worker task for one job
 #[derive(Clone)]
 pub struct WorkerJob {
     pub index: usize,
@@ -36,73 +78,353 @@ pub struct WorkerJob {
     pub output: PathBuf,
     pub metadata: VideoMetadata,
     pub tracks: TrackSelection,
+    pub overrides: EncodeOverrides,
+    /// Steps run in order once the output passed
+    pub after: Vec<PostAction>,
+    /// Child of the queue token, cancels only this job
+    pub cancel: CancellationToken,
 }
 
-/// Run the encoding worker in a separate thread
-pub fn run_worker(
+/// Run the encoding worker as a task on the async runtime
+///
+/// `config` is read again for every file, so changes made while the queue runs
+/// apply to the files not yet encoded or verified.
+pub async fn run_worker(
     jobs: Vec<WorkerJob>,
-    config: AppConfig,
-    cancel_flag: Arc<AtomicBool>,
-    tx: Sender<WorkerMessage>,
+    config: SharedConfig,
+    cancel: CancellationToken,
+    tx: UnboundedSender<WorkerMessage>,
 ) {
-    for job in jobs {
-        if cancel_flag.load(std::sync::atomic::Ordering::Relaxed) {
-            let _ = tx.send(WorkerMessage::Cancelled);
-            break;
+    let (job_tx, job_rx) = mpsc::unbounded_channel();
+    for (position, mut job) in jobs.iter().cloned().enumerate() {
+        // With several outputs per source, only the last one may delete it
+        if jobs[position + 1..].iter().any(|j| j.input == job.input) {
+            job.overrides.keep_source = true;
         }
+        let _ = job_tx.send(job);
+    }
+    drop(job_tx);
+    run_streamed_worker(job_rx, config, cancel, tx).await;
+}
 
-        let _ = tx.send(WorkerMessage::Progress(job.index, 0.0));
-
-        let tx_progress = tx.clone();
-        let idx = job.index;
-
-        let input_str = job.input.to_str().unwrap_or("").to_string();
-        let output_str = job.output.to_str().unwrap_or("").to_string();
-
-        let result = encoder::run_encoding_pipeline(
-            &input_str,
-            &output_str,
-            &job.metadata,
-            job.tracks,
-            &config,
-            Some(Box::new(move |progress| {
-                let _ = tx_progress.send(WorkerMessage::Progress(idx, progress));
-            })),
-            cancel_flag.clone(),
-        );
-
-        match result {
-            FullEncodeResult::Success => {
-                let _ = tx.send(WorkerMessage::Done(job.index));
+/// Encode jobs as they arrive until the sender is dropped
+///
+/// Lets encoding start while later files are still being analyzed or
+/// configured. Up to `performance.parallel_jobs` jobs encode at once, read
+/// again before each start, and none starts while the queue is paused.
+/// Outputs of one source still encode one after another, so the last one to
+/// delete the source runs last; jobs of a source with more outputs to come
+/// must arrive with `keep_source` set.
+pub async fn run_streamed_worker(
+    mut jobs: UnboundedReceiver<WorkerJob>,
+    config: SharedConfig,
+    cancel: CancellationToken,
+    tx: UnboundedSender<WorkerMessage>,
+) {
+    // Sources with an output that did not pass; they are never deleted
+    let incomplete: Arc<Mutex<HashSet<PathBuf>>> = Arc::default();
+    let mut running: JoinSet<()> = JoinSet::new();
+    // Index and source of each running job
+    let mut running_jobs: HashMap<task::Id, (usize, PathBuf)> = HashMap::new();
+    // Jobs received, in order, that have not started
+    let mut waiting: VecDeque<WorkerJob> = VecDeque::new();
+    let mut closed = false;
+    let mut paused = suspend::subscribe();
+
+    loop {
+        let limit = config.snapshot().performance.parallel_jobs.max(1);
+        // A paused queue starts no new job
+        let held = *paused.borrow_and_update();
+        // The first job whose source isn't already encoding, so one source's
+        // outputs don't hold up the files behind them
+        let startable = if held || running.len() >= limit {
+            None
+        } else {
+            waiting
+                .iter()
+                .position(|j| !running_jobs.values().any(|(_, input)| *input == j.input))
+        };
+        if let Some(job) = startable.and_then(|position| waiting.remove(position)) {
+            wait_for_window(&config, &cancel, &job.cancel, &tx).await;
+            if cancel.is_cancelled() {
+                break;
             }
-            FullEncodeResult::SuccessWithVmaf {
-                vmaf,
-                source_deleted,
-            } => {
-                let score = vmaf.score;
-                if source_deleted {
-                    let _ = tx.send(WorkerMessage::SourceDeleted(job.index));
+            let running_job = (job.index, job.input.clone());
+            let task = running.spawn(encode_job(
+                job,
+                config.clone(),
+                cancel.clone(),
+                tx.clone(),
+                incomplete.clone(),
+            ));
+            running_jobs.insert(task.id(), running_job);
+            continue;
+        }
+        if closed && waiting.is_empty() && running.is_empty() {
+            return;
+        }
+        tokio::select! {
+            job = jobs.recv(), if !closed => match job {
+                Some(job) => waiting.push_back(job),
+                None => closed = true,
+            },
+            Some(finished) = running.join_next_with_id(), if !running.is_empty() => {
+                let (id, failure) = match finished {
+                    Ok((id, ())) => (id, None),
+                    Err(e) => (e.id(), Some(e)),
+                };
+                let job = running_jobs.remove(&id);
+                // The task never reported how the job ended
+                if let (Some(e), Some((index, _))) = (failure, job) {
+                    warn!("Encode task failed: {}", e);
+                    let _ = tx.send(WorkerMessage::Error(
+                        index,
+                        AppError::CommandExecution(format!("Encode task failed: {}", e)),
+                    ));
                 }
-                let _ = tx.send(WorkerMessage::DoneWithVmaf(job.index, score));
             }
-            FullEncodeResult::Cancelled => {
-                let _ = tx.send(WorkerMessage::Cancelled);
-                break;
+            Ok(()) = paused.changed() => {}
+            _ = cancel.cancelled() => break,
+        }
+    }
+
+    // Cancelled: the running jobs stop on their own
+    while running.join_next().await.is_some() {}
+    let _ = tx.send(WorkerMessage::Cancelled);
+}
+
+/// Encode one job and run its steps
+async fn encode_job(
+    mut job: WorkerJob,
+    config: SharedConfig,
+    cancel: CancellationToken,
+    tx: UnboundedSender<WorkerMessage>,
+    incomplete: Arc<Mutex<HashSet<PathBuf>>>,
+) {
+    let mark_incomplete = |input: &PathBuf| {
+        incomplete
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(input.clone());
+    };
+    if incomplete
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&job.input)
+    {
+        job.overrides.keep_source = true;
+    }
+    if cancel.is_cancelled() {
+        return;
+    }
+    if job.cancel.is_cancelled() {
+        mark_incomplete(&job.input);
+        let _ = tx.send(WorkerMessage::JobCancelled(job.index));
+        return;
+    }
+
+    let snapshot = config.snapshot();
+    priority::apply_io_priority(snapshot.performance.io_priority);
+
+    // On a shared folder, leave sources another machine is encoding to it
+    let claim = if snapshot.output.claim_files && !job.overrides.simulate {
+        let stale_after = Duration::from_secs(snapshot.output.claim_stale_mins * 60);
+        match SourceClaim::acquire(&job.input, stale_after) {
+            Ok(claim) => Some(claim),
+            Err(AppError::Claimed { holder, .. }) => {
+                info!("Skipping {}: claimed by {}", job.input.display(), holder);
+                mark_incomplete(&job.input);
+                let _ = tx.send(WorkerMessage::Claimed(job.index, holder));
+                return;
             }
-            FullEncodeResult::Error(e) => {
+            Err(e) => {
+                mark_incomplete(&job.input);
                 let _ = tx.send(WorkerMessage::Error(job.index, e));
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    let _ = tx.send(WorkerMessage::Progress(
+        job.index,
+        EncodeProgress::default(),
+    ));
+
+    let tx_progress = tx.clone();
+    let tx_events = tx.clone();
+    let idx = job.index;
+    let invalid = Arc::new(AtomicBool::new(false));
+    let invalid_events = invalid.clone();
+
+    let input_str = job.input.to_str().unwrap_or("").to_string();
+    let output_str = job.output.to_str().unwrap_or("").to_string();
+
+    let pipeline = encoder::run_encoding_pipeline(
+        &input_str,
+        &output_str,
+        &job.metadata,
+        job.tracks,
+        &job.overrides,
+        &config,
+        Some(Box::new(move |progress| {
+            let _ = tx_progress.send(WorkerMessage::Progress(idx, progress));
+        })),
+        Some(Box::new(move |event| match event {
+            PipelineEvent::Remuxed(remux) => {
+                let _ = tx_events.send(WorkerMessage::Remuxed(
+                    idx,
+                    remux.encoded_size,
+                    remux.remuxed_size,
+                ));
+            }
+            PipelineEvent::StageFinished(stage, elapsed) => {
+                let _ = tx_events.send(WorkerMessage::StageTime(idx, stage, elapsed));
+            }
+            PipelineEvent::ValidationFailed(issues) => {
+                invalid_events.store(true, Ordering::Relaxed);
+                let _ = tx_events.send(WorkerMessage::ValidationIssues(idx, issues));
             }
-            FullEncodeResult::QualityWarning { vmaf, threshold } => {
-                let score = vmaf.score;
-                info!(
-                    "Source file kept: {} (VMAF {:.1} < {:.0})",
-                    job.input.display(),
-                    score,
-                    threshold
-                );
-                let _ = tx.send(WorkerMessage::SourceKeptLowVmaf(job.index, score));
-                let _ = tx.send(WorkerMessage::QualityWarning(job.index, score, threshold));
+            PipelineEvent::EncoderFallback { from, reason } => {
+                let _ = tx_events.send(WorkerMessage::EncoderFallback(idx, from, reason));
             }
+            PipelineEvent::SourceKept(reason) => {
+                let _ = tx_events.send(WorkerMessage::SourceKept(idx, reason));
+            }
+            PipelineEvent::Provenance(provenance) => {
+                let _ = tx_events.send(WorkerMessage::Provenance(idx, provenance));
+            }
+        })),
+        job.cancel.clone(),
+    )
+    // Stage spans of the pipeline nest under the job span
+    .instrument(info_span!("job", file = %job.input.display()));
+    let result = match claim {
+        Some(ref claim) => claim.hold_while(pipeline).await,
+        None => pipeline.await,
+    };
+    drop(claim);
+
+    let passed = matches!(
+        result,
+        FullEncodeResult::Success
+            | FullEncodeResult::SuccessWithVmaf { .. }
+            | FullEncodeResult::Simulated(_)
+    );
+    if !passed || invalid.load(Ordering::Relaxed) {
+        mark_incomplete(&job.input);
+    }
+
+    // The job only counts as finished once its steps ran
+    if !job.after.is_empty() {
+        let blocked = match result {
+            FullEncodeResult::Simulated(_) => Some("Simulated".to_string()),
+            FullEncodeResult::Cancelled => Some("Cancelled".to_string()),
+            _ if !passed || invalid.load(Ordering::Relaxed) => {
+                Some("Output did not pass".to_string())
+            }
+            _ => None,
+        };
+        let tx_steps = tx.clone();
+        let moved = steps::run_chain(
+            &job.after,
+            job.output.clone(),
+            blocked,
+            &job.cancel,
+            |position, status| {
+                let _ = tx_steps.send(WorkerMessage::Step(idx, position, status));
+            },
+        )
+        .await;
+        if moved != job.output {
+            let _ = tx.send(WorkerMessage::OutputMoved(idx, moved));
+        }
+    }
+
+    match result {
+        FullEncodeResult::Success => {
+            let _ = tx.send(WorkerMessage::Done(job.index));
+        }
+        FullEncodeResult::SuccessWithVmaf {
+            vmaf,
+            source_deleted,
+        } => {
+            let score = vmaf.score;
+            if source_deleted {
+                let _ = tx.send(WorkerMessage::SourceDeleted(job.index));
+            }
+            let _ = tx.send(WorkerMessage::DoneWithVmaf(job.index, score));
+        }
+        FullEncodeResult::Cancelled => {
+            // A cancelled queue is reported once every job stopped
+            if !cancel.is_cancelled() {
+                let _ = tx.send(WorkerMessage::JobCancelled(job.index));
+            }
+        }
+        FullEncodeResult::Error(e) => {
+            let _ = tx.send(WorkerMessage::Error(job.index, e));
         }
+        FullEncodeResult::Simulated(simulated) => {
+            info!("Simulated: {}", simulated.command);
+            let _ = tx.send(WorkerMessage::Simulated(job.index, simulated));
+        }
+        FullEncodeResult::QualityWarning { vmaf, threshold } => {
+            let score = vmaf.score;
+            info!(
+                "Source file kept: {} (VMAF {:.1} < {:.0})",
+                job.input.display(),
+                score,
+                threshold
+            );
+            let _ = tx.send(WorkerMessage::SourceKeptLowVmaf(job.index, score));
+            let _ = tx.send(WorkerMessage::VmafWorstSegments(
+                job.index,
+                vmaf.worst_segments,
+            ));
+            let _ = tx.send(WorkerMessage::QualityWarning(job.index, score, threshold));
+        }
+    }
+}
+
+/// Wait until the encode window is open, or the queue or job is cancelled
+///
+/// The window is read from `config` on every check, so changing it takes
+/// effect without restarting the queue.
+async fn wait_for_window(
+    config: &SharedConfig,
+    cancel: &CancellationToken,
+    job_cancel: &CancellationToken,
+    tx: &UnboundedSender<WorkerMessage>,
+) {
+    let mut waiting = false;
+    loop {
+        let window = config.snapshot().performance.encode_window.and_then(|w| {
+            match w.parse::<EncodeWindow>() {
+                Ok(window) => Some(window),
+                Err(e) => {
+                    warn!("Ignoring the encode window: {}", e);
+                    None
+                }
+            }
+        });
+        match window {
+            Some(window) if !window.is_open() => {
+                if !waiting {
+                    info!("Waiting for the encode window {}", window);
+                    let _ = tx.send(WorkerMessage::WaitingForSchedule(Some(window)));
+                    waiting = true;
+                }
+            }
+            _ => break,
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(SCHEDULE_POLL) => {}
+            _ = cancel.cancelled() => break,
+            _ = job_cancel.cancelled() => break,
+        }
+    }
+    if waiting {
+        let _ = tx.send(WorkerMessage::WaitingForSchedule(None));
     }
 }
@@ -0,0 +1,479 @@
+//! HTTP control server, for running on a headless machine such as a NAS.
+//!
+//! `av1converter --serve` keeps running without the TUI and takes requests:
+//!
+//! - `GET /queue`: counters, progress and every job with its status
+//! - `POST /jobs` with `{"paths": ["/videos/movie.mkv", "/videos/show"]}`:
+//!   queue files, and folders recursively
+//! - `POST /jobs/<id>/cancel`: cancel a job of the running batch
+//! - `POST /cancel`: cancel the running batch and drop the queued files
+//!
+//! Submitted paths are read on the server. Files submitted while a batch runs
+//! form the next batch. Jobs keep their id across batches. Requests and
+//! answers are JSON; with `server.token` set, clients must send it as
+//! `Authorization: Bearer <token>`.
+//!
+//! Web pages open in a browser on the same machine can reach a loopback
+//! server too, so POSTs must be `Content-Type: application/json`, which a
+//! page can't send without the browser asking first, and without a token the
+//! `Host` must name the address the server listens on, which a rebound DNS
+//! name doesn't.
+
+use crate::app::{App, Screen};
+use crate::queue::{JobSnapshot, JobStatus, QueueSnapshot};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Longest request or header line accepted
+const MAX_LINE_BYTES: u64 = 8 * 1024;
+
+/// Most header lines accepted in a request
+const MAX_HEADERS: usize = 64;
+
+/// A client that sends nothing for this long is dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest wait for the rest of a request that was answered without reading it
+const LINGER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Connections handled at once; more wait for a free thread
+const CONNECTION_THREADS: usize = 4;
+
+/// Connections waiting for a thread; more are turned away
+const CONNECTION_BACKLOG: usize = 32;
+
+/// What a request asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    Queue,
+    Submit(Vec<PathBuf>),
+    CancelJob(usize),
+    CancelAll,
+}
+
+/// Status code and JSON body of an answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl Response {
+    fn ok(body: serde_json::Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Submission {
+    paths: Vec<PathBuf>,
+}
+
+/// Route of a request, or the answer to a malformed one
+pub fn route(method: &str, path: &str, body: &[u8]) -> Result<Route, Response> {
+    let segments: Vec<&str> = path
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    match (method, segments.as_slice()) {
+        ("GET", ["queue"]) => Ok(Route::Queue),
+        ("POST", ["jobs"]) => {
+            let submission: Submission = serde_json::from_slice(body).map_err(|e| {
+                Response::error(400, format!("Expected {{\"paths\": [...]}}: {}", e))
+            })?;
+            if submission.paths.is_empty() {
+                return Err(Response::error(400, "No paths given"));
+            }
+            Ok(Route::Submit(submission.paths))
+        }
+        ("POST", ["jobs", id, "cancel"]) => id
+            .parse()
+            .map(Route::CancelJob)
+            .map_err(|_| Response::error(404, format!("No job {}", id))),
+        ("POST", ["cancel"]) => Ok(Route::CancelAll),
+        (_, ["queue"] | ["jobs"] | ["jobs", _, "cancel"] | ["cancel"]) => {
+            Err(Response::error(405, format!("{} not allowed here", method)))
+        }
+        _ => Err(Response::error(404, format!("No route {}", path))),
+    }
+}
+
+/// Answer to `GET /queue`
+#[derive(Serialize)]
+struct Status {
+    /// Whether a batch is being analyzed or encoded
+    running: bool,
+    /// Counters and progress of the current batch, with its jobs after the
+    /// finished ones of earlier batches
+    #[serde(flatten)]
+    batch: QueueSnapshot,
+    /// Files waiting for the next batch
+    queued: Vec<PathBuf>,
+}
+
+struct Request {
+    route: Route,
+    reply: oneshot::Sender<Response>,
+}
+
+/// Jobs beyond the current batch
+#[derive(Default)]
+struct Batches {
+    /// Paths submitted while a batch runs
+    queued: Vec<PathBuf>,
+    /// Jobs of earlier batches
+    finished: Vec<JobSnapshot>,
+}
+
+impl Batches {
+    /// Id of the current batch's first job
+    fn first_id(&self) -> usize {
+        self.finished.len()
+    }
+}
+
+/// Run queues submitted through `listener` until the process is stopped
+pub async fn serve(app: &mut App, listener: TcpListener, token: Option<String>) -> io::Result<()> {
+    let mut requests = spawn_listener(listener, token)?;
+    let mut batches = Batches::default();
+    loop {
+        if idle(app) && !batches.queued.is_empty() {
+            start_batch(app, &mut batches);
+        }
+        tokio::select! {
+            update = app.recv_background() => app.process_background(update),
+            request = requests.recv() => {
+                let Some(request) = request else {
+                    return Ok(());
+                };
+                let response = answer(app, &mut batches, request.route);
+                let _ = request.reply.send(response);
+            }
+        }
+    }
+}
+
+fn idle(app: &App) -> bool {
+    app.progress_receiver.is_none() && app.analysis_receiver.is_none()
+}
+
+fn start_batch(app: &mut App, batches: &mut Batches) {
+    let first_id = batches.first_id();
+    batches.finished.extend(
+        app.queue
+            .jobs
+            .iter()
+            .enumerate()
+            .map(|(i, job)| JobSnapshot::of(first_id + i, job)),
+    );
+    let paths = std::mem::take(&mut batches.queued);
+    info!("Starting a batch of {} submitted paths", paths.len());
+    app.reset();
+    app.queue_paths(&paths);
+    app.current_screen = Screen::FileConfirm;
+    app.start_queue();
+}
+
+fn answer(app: &mut App, batches: &mut Batches, route: Route) -> Response {
+    let first_id = batches.first_id();
+    match route {
+        Route::Queue => {
+            let mut batch = QueueSnapshot::of(&app.queue, first_id);
+            batch.jobs.splice(0..0, batches.finished.iter().cloned());
+            let status = Status {
+                running: !idle(app),
+                batch,
+                queued: batches.queued.clone(),
+            };
+            serde_json::to_value(status)
+                .map_or_else(|e| Response::error(500, e.to_string()), Response::ok)
+        }
+        Route::Submit(paths) => {
+            if let Some(missing) = paths.iter().find(|p| !p.exists()) {
+                return Response::error(400, format!("{} does not exist", missing.display()));
+            }
+            let count = paths.len();
+            batches.queued.extend(paths);
+            Response {
+                status: 202,
+                body: json!({ "queued": count }),
+            }
+        }
+        Route::CancelJob(id) => {
+            let in_batch = app.queue.jobs.len();
+            let finished = id < first_id
+                || app.queue.jobs.get(id - first_id).is_some_and(|job| {
+                    matches!(
+                        job.status,
+                        JobStatus::Done
+                            | JobStatus::DoneWithVmaf { .. }
+                            | JobStatus::Skipped { .. }
+                            | JobStatus::Error { .. }
+                            | JobStatus::QualityWarning { .. }
+                    )
+                });
+            if finished {
+                Response::error(409, format!("Job {} already finished", id))
+            } else if id < first_id + in_batch {
+                if app.cancel_job(id - first_id) {
+                    Response::ok(json!({ "cancelled": id }))
+                } else {
+                    Response::error(409, format!("Job {} is not waiting or encoding", id))
+                }
+            } else {
+                Response::error(404, format!("No job {}", id))
+            }
+        }
+        Route::CancelAll => {
+            let removed = std::mem::take(&mut batches.queued).len();
+            let running = !idle(app);
+            if running {
+                app.cancel_encoding();
+            }
+            Response::ok(json!({ "cancelled": running, "removed": removed }))
+        }
+    }
+}
+
+/// Who may send requests: a token, or without one only clients naming the
+/// listening address
+#[derive(Debug, Clone)]
+struct Access {
+    token: Option<String>,
+    /// `Host` values accepted without a token, lowercase
+    hosts: Vec<String>,
+}
+
+impl Access {
+    fn new(listener: &TcpListener, token: Option<String>) -> io::Result<Self> {
+        let addr = listener.local_addr()?;
+        let mut hosts = vec![addr.to_string()];
+        if addr.ip().is_loopback() {
+            hosts.push(format!("localhost:{}", addr.port()));
+        }
+        Ok(Self { token, hosts })
+    }
+
+    /// Answer to a request not allowed through
+    fn check(&self, request: &RequestHead) -> Option<Response> {
+        match self.token {
+            Some(ref token) => {
+                let expected = format!("Bearer {}", token);
+                let sent = request.authorization.as_deref().unwrap_or_default();
+                (!constant_time_eq(sent.as_bytes(), expected.as_bytes()))
+                    .then(|| Response::error(401, "Missing or wrong token"))
+            }
+            None => {
+                let host = request.host.as_deref().unwrap_or_default();
+                (!self.hosts.iter().any(|h| h.eq_ignore_ascii_case(host)))
+                    .then(|| Response::error(403, format!("Host {:?} not served", host)))
+            }
+        }
+    }
+}
+
+/// Whether `a` and `b` are equal, taking as long whatever differs
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let len = a.len().max(b.len());
+    let differs = (0..len).fold(a.len() ^ b.len(), |acc, i| {
+        acc | usize::from(a.get(i).copied().unwrap_or(0) ^ b.get(i).copied().unwrap_or(0))
+    });
+    differs == 0
+}
+
+/// Request line and the headers the server looks at
+#[derive(Debug, Default)]
+struct RequestHead {
+    method: String,
+    path: String,
+    content_length: usize,
+    content_type: Option<String>,
+    authorization: Option<String>,
+    host: Option<String>,
+}
+
+/// Accept connections on a few threads, passing their requests to the serve loop
+fn spawn_listener(
+    listener: TcpListener,
+    token: Option<String>,
+) -> io::Result<UnboundedReceiver<Request>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let access = Access::new(&listener, token)?;
+    let (connections, waiting) = std::sync::mpsc::sync_channel::<TcpStream>(CONNECTION_BACKLOG);
+    let waiting = Arc::new(Mutex::new(waiting));
+    for i in 0..CONNECTION_THREADS {
+        let (tx, access, waiting) = (tx.clone(), access.clone(), waiting.clone());
+        std::thread::Builder::new()
+            .name(format!("server-{}", i))
+            .spawn(move || {
+                loop {
+                    let stream = waiting.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                    let Ok(stream) = stream else {
+                        break;
+                    };
+                    if let Err(e) = handle_connection(stream, &access, &tx) {
+                        warn!("Server connection failed: {}", e);
+                    }
+                }
+            })?;
+    }
+    std::thread::Builder::new()
+        .name("server".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                if connections.try_send(stream).is_err() {
+                    warn!("Server busy, dropping a connection");
+                }
+            }
+        })?;
+    Ok(rx)
+}
+
+/// Read a line of at most `MAX_LINE_BYTES`; false when it is longer
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<bool> {
+    line.clear();
+    reader.take(MAX_LINE_BYTES + 1).read_line(line)?;
+    Ok(line.len() as u64 <= MAX_LINE_BYTES)
+}
+
+/// Request line and headers, or the answer to an oversized head
+fn read_head(reader: &mut impl BufRead) -> io::Result<Result<RequestHead, Response>> {
+    let too_large = || Ok(Err(Response::error(431, "Request head too large")));
+    let mut line = String::new();
+    if !read_line(reader, &mut line)? {
+        return too_large();
+    }
+    let mut request_line = line.split_whitespace();
+    let mut head = RequestHead {
+        method: request_line.next().unwrap_or_default().to_string(),
+        path: request_line.next().unwrap_or_default().to_string(),
+        ..Default::default()
+    };
+
+    let mut headers = 0;
+    loop {
+        if !read_line(reader, &mut line)? {
+            return too_large();
+        }
+        if line.trim().is_empty() {
+            return Ok(Ok(head));
+        }
+        headers += 1;
+        if headers > MAX_HEADERS {
+            return too_large();
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim().to_string();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-length" => head.content_length = value.parse().unwrap_or(usize::MAX),
+                "content-type" => head.content_type = Some(value),
+                "authorization" => head.authorization = Some(value),
+                "host" => head.host = Some(value),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    access: &Access,
+    requests: &UnboundedSender<Request>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let head = match read_head(&mut reader)? {
+        Ok(head) => head,
+        Err(response) => return write_response(stream, &response),
+    };
+    let json = head.content_type.as_deref().is_some_and(|t| {
+        t.split(';')
+            .next()
+            .is_some_and(|t| t.trim().eq_ignore_ascii_case("application/json"))
+    });
+
+    let response = if let Some(denied) = access.check(&head) {
+        denied
+    } else if head.method == "POST" && !json {
+        Response::error(415, "POST requests must be Content-Type: application/json")
+    } else if head.content_length > MAX_BODY_BYTES {
+        Response::error(413, "Request body too large")
+    } else {
+        let (method, path, content_length) = (head.method, head.path, head.content_length);
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        match route(&method, &path, &body) {
+            Ok(route) => {
+                let (reply, answer) = oneshot::channel();
+                if requests.send(Request { route, reply }).is_err() {
+                    Response::error(503, "Shutting down")
+                } else {
+                    answer
+                        .blocking_recv()
+                        .unwrap_or_else(|_| Response::error(503, "Shutting down"))
+                }
+            }
+            Err(response) => response,
+        }
+    };
+    write_response(stream, &response)
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) -> io::Result<()> {
+    let body = response.body.to_string();
+    let reason = match response.status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    // Closing with request bytes still unread resets the connection, which
+    // can discard the response before the client has read it
+    let _ = stream.shutdown(Shutdown::Write);
+    stream.set_read_timeout(Some(LINGER_TIMEOUT))?;
+    let _ = io::copy(&mut (&stream).take(MAX_BODY_BYTES as u64), &mut io::sink());
+    Ok(())
+}
@@ -2,6 +2,57 @@ pub mod selection;
 
 pub use selection::TrackSelection;
 
+/// Audio codecs that already dropped information; re-encoding them stacks generation loss
+const LOSSY_AUDIO_CODECS: [&str; 9] = [
+    "aac", "mp3", "mp2", "ac3", "eac3", "dts", "opus", "vorbis", "wmav2",
+];
+
+/// Smallest bitrate saving that makes re-encoding a lossy track worth it
+const LOSSY_MIN_SAVING: f64 = 0.3;
+
+/// Title words of commentary tracks; `comment` also covers commentaire and commento
+const COMMENTARY_KEYWORDS: [&str; 4] = ["comment", "director", "kommentar", "comentario"];
+
+/// Highest bitrate of an untitled stereo track that passes for commentary
+const COMMENTARY_MAX_BITRATE: u64 = 128_000;
+
+/// Title words of subtitles for the deaf and hard of hearing
+const SDH_WORDS: [&str; 3] = ["sdh", "cc", "hi"];
+
+/// Spelled-out titles of subtitles for the deaf and hard of hearing
+const SDH_PHRASES: [&str; 3] = ["hearing impaired", "hard of hearing", "closed caption"];
+
+/// Disposition flags the container sets on a track
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrackDisposition {
+    /// Played or shown when the viewer picks nothing
+    pub default: bool,
+    /// Shown even with subtitles off, for foreign-language parts
+    pub forced: bool,
+    /// For the deaf and hard of hearing
+    pub hearing_impaired: bool,
+    /// Audio description for the blind and visually impaired
+    pub visual_impaired: bool,
+    /// Commentary
+    pub comment: bool,
+}
+
+impl TrackDisposition {
+    /// Names of the set flags, as ffprobe spells them
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            (self.default, "default"),
+            (self.forced, "forced"),
+            (self.hearing_impaired, "hearing_impaired"),
+            (self.visual_impaired, "visual_impaired"),
+            (self.comment, "comment"),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect()
+    }
+}
+
 /// Audio track information
 #[derive(Debug, Clone)]
 pub struct AudioTrack {
@@ -12,6 +63,7 @@ pub struct AudioTrack {
     pub title: Option<String>,
     pub bitrate: Option<u64>,
     pub sample_rate: Option<u32>,
+    pub disposition: TrackDisposition,
 }
 
 impl AudioTrack {
@@ -22,13 +74,7 @@ impl AudioTrack {
             .as_ref()
             .map(|t| format!(" - {}", t))
             .unwrap_or_default();
-        let channels_str = match self.channels {
-            1 => "Mono",
-            2 => "Stereo",
-            6 => "5.1",
-            8 => "7.1",
-            _ => "Multi",
-        };
+        let channels_str = self.channel_layout();
         format!(
             "{}: {} ({} {}){}",
             self.index,
@@ -39,6 +85,62 @@ impl AudioTrack {
         )
     }
 
+    /// Channel layout name, e.g. `5.1`
+    pub fn channel_layout(&self) -> &'static str {
+        match self.channels {
+            1 => "Mono",
+            2 => "Stereo",
+            6 => "5.1",
+            8 => "7.1",
+            _ => "Multi",
+        }
+    }
+
+    /// Bytes the track takes up over `duration_secs`, from its bitrate
+    pub fn estimated_size(&self, duration_secs: f64) -> Option<u64> {
+        estimated_size(self.bitrate, duration_secs)
+    }
+
+    /// Whether the track is in a lossy codec
+    pub fn is_lossy(&self) -> bool {
+        LOSSY_AUDIO_CODECS
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(&self.codec))
+    }
+
+    /// Whether re-encoding the track at `bitrate_kbps` would degrade it for little gain
+    ///
+    /// True for a lossy track whose bitrate is known and less than a third
+    /// above the target.
+    pub fn reencode_pointless(&self, bitrate_kbps: u32) -> bool {
+        self.is_lossy()
+            && self.bitrate.is_some_and(|source| {
+                bitrate_kbps as f64 * 1000.0 >= source as f64 * (1.0 - LOSSY_MIN_SAVING)
+            })
+    }
+
+    /// Why the track looks like a commentary, if it does, next to the `main` track
+    ///
+    /// Besides the container flag and the title, a low-bitrate stereo track
+    /// in the language of a multichannel main track is taken for one.
+    pub fn commentary_reason(&self, main: &AudioTrack) -> Option<&'static str> {
+        if self.disposition.comment {
+            return Some("flagged as commentary");
+        }
+        let title = self.title.as_deref().unwrap_or_default().to_lowercase();
+        if COMMENTARY_KEYWORDS.iter().any(|k| title.contains(k)) {
+            return Some("commentary title");
+        }
+        let untitled_stereo = self.index != main.index
+            && title.is_empty()
+            && self.channels <= 2
+            && main.channels > 2
+            && self.language.is_some()
+            && self.language == main.language
+            && self.bitrate.is_some_and(|b| b <= COMMENTARY_MAX_BITRATE);
+        untitled_stereo.then_some("low-bitrate stereo next to the main mix")
+    }
+
     /// Get bitrate display string
     pub fn bitrate_string(&self) -> String {
         self.bitrate
@@ -60,6 +162,20 @@ impl AudioTrack {
     }
 }
 
+/// Indices of the audio tracks that look like commentary
+///
+/// The first track is the main mix and never counts unless flagged or titled.
+pub fn commentary_tracks(tracks: &[AudioTrack]) -> Vec<usize> {
+    let Some(main) = tracks.first() else {
+        return Vec::new();
+    };
+    tracks
+        .iter()
+        .filter(|t| t.commentary_reason(main).is_some())
+        .map(|t| t.index)
+        .collect()
+}
+
 /// Subtitle track information
 #[derive(Debug, Clone)]
 pub struct SubtitleTrack {
@@ -67,7 +183,9 @@ pub struct SubtitleTrack {
     pub language: Option<String>,
     pub codec: String,
     pub title: Option<String>,
-    pub forced: bool,
+    pub disposition: TrackDisposition,
+    /// Average bitrate, from the container's statistics tags when present
+    pub bitrate: Option<u64>,
 }
 
 impl SubtitleTrack {
@@ -78,14 +196,55 @@ impl SubtitleTrack {
             .as_ref()
             .map(|t| format!(" - {}", t))
             .unwrap_or_default();
-        let forced_str = if self.forced { " [Forced]" } else { "" };
+        let forced_str = if self.is_forced() { " [Forced]" } else { "" };
+        let sdh_str = if self.is_sdh() { " [SDH]" } else { "" };
         format!(
-            "{}: {} ({}){}{}",
+            "{}: {} ({}){}{}{}",
             self.index,
             lang,
             self.codec.to_uppercase(),
             forced_str,
+            sdh_str,
             title
         )
     }
+
+    /// Whether the track only covers foreign-language parts, by flag or title
+    pub fn is_forced(&self) -> bool {
+        self.disposition.forced || self.title_words().any(|w| w == "forced")
+    }
+
+    /// Whether the track is for the deaf and hard of hearing, by flag or title
+    ///
+    /// Titles count with `SDH`, `CC` or `HI` as a word, or a spelled-out
+    /// "hearing impaired", "hard of hearing" or "closed captions".
+    pub fn is_sdh(&self) -> bool {
+        if self.disposition.hearing_impaired
+            || self.title_words().any(|w| SDH_WORDS.contains(&w.as_str()))
+        {
+            return true;
+        }
+        let title = self.title.as_deref().unwrap_or_default().to_lowercase();
+        SDH_PHRASES.iter().any(|p| title.contains(p))
+    }
+
+    /// Lowercase words of the title
+    fn title_words(&self) -> impl Iterator<Item = String> + '_ {
+        self.title
+            .as_deref()
+            .unwrap_or_default()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+    }
+
+    /// Bytes the track takes up over `duration_secs`, from its bitrate
+    pub fn estimated_size(&self, duration_secs: f64) -> Option<u64> {
+        estimated_size(self.bitrate, duration_secs)
+    }
+}
+
+fn estimated_size(bitrate: Option<u64>, duration_secs: f64) -> Option<u64> {
+    let bitrate = bitrate.filter(|&b| b > 0)?;
+    (duration_secs > 0.0).then(|| (bitrate as f64 * duration_secs / 8.0) as u64)
 }
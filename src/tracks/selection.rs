@@ -1,7 +1,8 @@
 use super::{AudioTrack, SubtitleTrack};
+use serde::{Deserialize, Serialize};
 
 /// Track selection for encoding
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct TrackSelection {
     pub audio_indices: Vec<usize>,
     pub subtitle_indices: Vec<usize>,
@@ -17,15 +18,46 @@ impl TrackSelection {
     }
 
     /// Toggle an audio track selection
+    ///
+    /// A newly selected track goes to its source position, or last once the
+    /// tracks have been reordered.
     pub fn toggle_audio(&mut self, index: usize) {
         if self.audio_indices.contains(&index) {
             self.audio_indices.retain(|&i| i != index);
         } else {
+            let reordered = self.audio_reordered();
             self.audio_indices.push(index);
-            self.audio_indices.sort();
+            if !reordered {
+                self.audio_indices.sort();
+            }
         }
     }
 
+    /// Move a selected audio track one place earlier or later in the output
+    pub fn move_audio(&mut self, index: usize, earlier: bool) {
+        let Some(pos) = self.audio_indices.iter().position(|&i| i == index) else {
+            return;
+        };
+        let target = if earlier {
+            pos.checked_sub(1)
+        } else {
+            Some(pos + 1).filter(|&t| t < self.audio_indices.len())
+        };
+        if let Some(target) = target {
+            self.audio_indices.swap(pos, target);
+        }
+    }
+
+    /// Output position of a selected audio track, starting at 0
+    pub fn audio_position(&self, index: usize) -> Option<usize> {
+        self.audio_indices.iter().position(|&i| i == index)
+    }
+
+    /// Whether the audio tracks are in a different order than in the source
+    pub fn audio_reordered(&self) -> bool {
+        !self.audio_indices.is_sorted()
+    }
+
     /// Toggle a subtitle track selection
     pub fn toggle_subtitle(&mut self, index: usize) {
         if self.subtitle_indices.contains(&index) {
@@ -35,4 +67,27 @@ impl TrackSelection {
             self.subtitle_indices.sort();
         }
     }
+
+    /// Whether nothing was picked, which keeps every track
+    pub fn keeps_all(&self) -> bool {
+        self.audio_indices.is_empty() && self.subtitle_indices.is_empty()
+    }
+
+    /// Source audio indices in output order, given the number of source audio tracks
+    pub fn kept_audio(&self, available: usize) -> Vec<usize> {
+        if self.keeps_all() {
+            (0..available).collect()
+        } else {
+            self.audio_indices.clone()
+        }
+    }
+
+    /// Source subtitle indices in output order, given the number of source subtitle tracks
+    pub fn kept_subtitles(&self, available: usize) -> Vec<usize> {
+        if self.keeps_all() {
+            (0..available).collect()
+        } else {
+            self.subtitle_indices.clone()
+        }
+    }
 }
@@ -0,0 +1,146 @@
+use crate::app::App;
+use crate::utils::{format_decimal, format_file_size};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+};
+
+pub fn render_audit(f: &mut Frame, app: &mut App) {
+    let Some(ref report) = app.audit else {
+        return;
+    };
+    let families = report.families();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(families.len() as u16 + 5),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .margin(1)
+        .split(f.area());
+
+    // Codec breakdown
+    let mut summary_lines = vec![Line::from(Span::styled(
+        report.root.display().to_string(),
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ))];
+    for family in &families {
+        summary_lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<6}", family.family.label()),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(format!(
+                " {:>5} files  {:>11}  →  ~{}",
+                family.count,
+                format_file_size(family.size),
+                format_file_size(family.estimated_size)
+            )),
+        ]));
+    }
+    let total = report.total_size();
+    let savings = report.total_savings();
+    let percent = if total > 0 {
+        savings as f64 / total as f64 * 100.0
+    } else {
+        0.0
+    };
+    summary_lines.push(Line::from(""));
+    summary_lines.push(Line::from(vec![
+        Span::styled("Estimated savings: ", Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            format!(
+                "{} of {} ({}%)",
+                format_file_size(savings),
+                format_file_size(total),
+                format_decimal(percent, 1)
+            ),
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            if report.unreadable.is_empty() {
+                String::new()
+            } else {
+                format!("   {} unreadable", report.unreadable.len())
+            },
+            Style::default().fg(Color::Red),
+        ),
+    ]));
+
+    let summary = Paragraph::new(summary_lines)
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(" Audit "),
+        );
+    f.render_widget(summary, chunks[0]);
+
+    // Files ranked by potential savings
+    let items: Vec<ListItem> = report
+        .entries
+        .iter()
+        .map(|entry| {
+            let path = entry.path.strip_prefix(&report.root).unwrap_or(&entry.path);
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:>11} ", format!("-{}", format_file_size(entry.savings()))),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::raw(path.display().to_string()),
+                Span::styled(
+                    format!(
+                        "  ({}, {}x{}, {})",
+                        entry.family.label(),
+                        entry.width,
+                        entry.height,
+                        format_file_size(entry.size)
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(" Biggest savings "),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray))
+        .highlight_symbol(">");
+    f.render_stateful_widget(list, chunks[1], &mut app.audit_list_state);
+
+    // Help
+    let mut help_text = vec![Line::from(vec![
+        Span::styled("↑↓", Style::default().fg(Color::Yellow)),
+        Span::raw(" Navigate  "),
+        Span::styled("e", Style::default().fg(Color::Yellow)),
+        Span::raw(" Export CSV  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(" Back"),
+    ])];
+    if let Some(ref msg) = app.message {
+        help_text.push(Line::from(Span::styled(
+            msg.clone(),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let help = Paragraph::new(help_text)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::NONE));
+    f.render_widget(help, chunks[2]);
+}
@@ -0,0 +1,117 @@
+use crate::app::App;
+use crate::cleanup::LeftoverKind;
+use crate::utils::format_file_size;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+};
+
+pub fn render_cleanup(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .margin(1)
+        .split(f.area());
+
+    // Header with what is marked for deletion
+    let (count, size) = app.selected_leftovers();
+    let header = Paragraph::new(format!(
+        "{}  —  {} of {} marked ({})",
+        app.cleanup_dir.display(),
+        count,
+        app.leftovers.len(),
+        format_file_size(size)
+    ))
+    .style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(" Clean Up "),
+    );
+    f.render_widget(header, chunks[0]);
+
+    let items: Vec<ListItem> = app
+        .leftovers
+        .iter()
+        .map(|item| {
+            let mark = if !item.kind.removable() {
+                "   "
+            } else if item.selected {
+                "[x]"
+            } else {
+                "[ ]"
+            };
+            let color = match item.kind {
+                LeftoverKind::TempFile | LeftoverKind::EmptyOutput => Color::Yellow,
+                LeftoverKind::Chunks => Color::Cyan,
+                LeftoverKind::OrphanOutput => Color::Red,
+                LeftoverKind::Unconverted => Color::DarkGray,
+            };
+            let path = item
+                .path
+                .strip_prefix(&app.cleanup_dir)
+                .unwrap_or(&item.path);
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{} ", mark)),
+                Span::styled(
+                    format!("{:<14}", item.kind.label()),
+                    Style::default().fg(color),
+                ),
+                Span::raw(format!(
+                    "{}  [{}]",
+                    path.display(),
+                    format_file_size(item.size)
+                )),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(" Leftovers "),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray))
+        .highlight_symbol(">");
+    f.render_stateful_widget(list, chunks[1], &mut app.leftover_list_state);
+
+    // Help
+    let mut help_text = vec![Line::from(vec![
+        Span::styled("↑↓", Style::default().fg(Color::Yellow)),
+        Span::raw(" Navigate  "),
+        Span::styled("Space", Style::default().fg(Color::Yellow)),
+        Span::raw(" Mark  "),
+        Span::styled("a", Style::default().fg(Color::Yellow)),
+        Span::raw(" Mark all  "),
+        Span::styled("d", Style::default().fg(Color::Yellow)),
+        Span::raw(" Delete marked  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(" Back"),
+    ])];
+    if let Some(ref msg) = app.message {
+        help_text.push(Line::from(Span::styled(
+            msg.clone(),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let help = Paragraph::new(help_text)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::NONE));
+    f.render_widget(help, chunks[2]);
+}
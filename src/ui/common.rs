@@ -25,6 +25,27 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
+/// Centered rectangle like [`centered_rect`], grown to at least `min_width` × `min_height`
+///
+/// Keeps popups usable in small terminals; never larger than `r`.
+pub fn centered_rect_min(
+    percent_x: u16,
+    percent_y: u16,
+    min_width: u16,
+    min_height: u16,
+    r: Rect,
+) -> Rect {
+    let rect = centered_rect(percent_x, percent_y, r);
+    let width = rect.width.max(min_width).min(r.width);
+    let height = rect.height.max(min_height).min(r.height);
+    Rect {
+        x: r.x + (r.width - width) / 2,
+        y: r.y + (r.height - height) / 2,
+        width,
+        height,
+    }
+}
+
 /// Get color for VMAF score/threshold
 pub fn get_vmaf_color(score: f64) -> Color {
     match score as u32 {
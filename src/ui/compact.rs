@@ -0,0 +1,121 @@
+use super::confirm_dialog::confirm_text;
+use crate::app::{App, Screen};
+use crate::queue::JobStatus;
+use crate::utils::{format_decimal, format_duration};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Wrap},
+};
+
+/// Smallest terminal a screen's layout fits in, as (columns, rows)
+pub fn min_size(screen: &Screen) -> (u16, u16) {
+    match screen {
+        Screen::Home => (50, 20),
+        Screen::Queue => (60, 16),
+        Screen::Finish => (60, 22),
+        Screen::TrackConfig => (70, 20),
+        Screen::Presets => (80, 16),
+        Screen::Configuration => (60, 18),
+        Screen::FileExplorer { .. }
+        | Screen::FileConfirm
+        | Screen::DiscTitles
+        | Screen::Audit
+        | Screen::Cleanup => (50, 14),
+    }
+}
+
+/// Check if the current screen fits in `area`
+pub fn fits(screen: &Screen, area: Rect) -> bool {
+    let (width, height) = min_size(screen);
+    area.width >= width && area.height >= height
+}
+
+/// Compact view for terminals smaller than the current screen's layout
+///
+/// A running queue keeps showing its progress; other screens show the size
+/// they need. A pending confirmation is shown with its keys so it can still be
+/// answered.
+pub fn render_too_small(f: &mut Frame, app: &App) {
+    let area = f.area();
+    let (min_width, min_height) = min_size(&app.current_screen);
+    let key = |text: &'static str| Span::styled(text, Style::default().fg(Color::Yellow));
+
+    let mut lines = Vec::new();
+    if let Some(ref action) = app.confirm_dialog {
+        let (title, message) = confirm_text(action, app);
+        lines.push(Line::from(Span::styled(
+            title.trim(),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(message));
+        lines.push(Line::from(vec![
+            key("y"),
+            Span::raw(" Yes  "),
+            key("n"),
+            Span::raw(" No"),
+        ]));
+    } else if matches!(app.current_screen, Screen::Queue) {
+        lines.extend(queue_lines(app));
+    }
+
+    if lines.is_empty() || area.height > lines.len() as u16 + 1 {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "Terminal too small: {}×{}, needs {}×{}",
+                area.width, area.height, min_width, min_height
+            ),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, area);
+}
+
+/// Current file, its progress and the ETAs of a running queue
+fn queue_lines(app: &App) -> Vec<Line<'static>> {
+    let queue = &app.queue;
+    let Some(job) = queue
+        .jobs
+        .get(queue.current_job_index)
+        .filter(|j| matches!(j.status, JobStatus::Encoding { .. }))
+    else {
+        return vec![Line::from(format!(
+            "Queue {}/{}",
+            queue.finished_count(),
+            queue.jobs.len()
+        ))];
+    };
+
+    let eta = |remaining: Option<std::time::Duration>| {
+        remaining
+            .map(format_duration)
+            .unwrap_or_else(|| "--:--".to_string())
+    };
+    vec![
+        Line::from(Span::styled(
+            format!(
+                "[{}/{}] {}",
+                (queue.encoding_progress_done + 1).min(queue.total_jobs_to_encode),
+                queue.total_jobs_to_encode,
+                job.display_name()
+            ),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!(
+            "{}%  ETA {} · queue {}",
+            format_decimal(queue.job_progress(job) as f64, 1),
+            eta(queue.job_time_remaining(job)),
+            eta(queue.estimated_time_remaining())
+        )),
+    ]
+}
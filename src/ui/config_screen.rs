@@ -1,19 +1,28 @@
 use crate::app::App;
-use crate::config::AppConfig;
+use crate::config::{AppConfig, ConfigRow, settings};
+use crate::utils::DependencyStatus;
+use crate::utils::deps::Feature;
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph},
 };
 
-pub fn render_config_screen(f: &mut Frame, app: &App) {
+pub fn render_config_screen(f: &mut Frame, app: &mut App) {
+    let advice_height = if app.recommendations.is_empty() {
+        0
+    } else {
+        app.recommendations.len() as u16 + 2
+    };
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
             Constraint::Min(5),
+            Constraint::Length(advice_height),
             Constraint::Length(3),
         ])
         .margin(1)
@@ -34,8 +43,100 @@ pub fn render_config_screen(f: &mut Frame, app: &App) {
         );
     f.render_widget(title, chunks[0]);
 
-    // Config items
-    let items = build_config_items(&app.config, app.config_selected);
+    if app.config_show_diff {
+        render_diff(f, app, chunks[1]);
+    } else {
+        render_settings(f, app, chunks[1]);
+    }
+
+    // Preset advice from past VMAF results
+    if !app.recommendations.is_empty() {
+        let lines: Vec<Line> = app
+            .recommendations
+            .iter()
+            .map(|r| {
+                Line::from(Span::styled(
+                    r.message(),
+                    Style::default().fg(Color::Yellow),
+                ))
+            })
+            .collect();
+        let advice = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(" Recommendations (from past results) "),
+        );
+        f.render_widget(advice, chunks[2]);
+    }
+
+    // Help
+    let key = |text: &'static str| Span::styled(text, Style::default().fg(Color::Yellow));
+    let mut help_text = if app.config_show_diff {
+        vec![Line::from(vec![
+            key("↑↓"),
+            Span::raw(" Navigate  "),
+            key("r"),
+            Span::raw(" Reset to default  "),
+            key("R"),
+            Span::raw(" Reset all  "),
+            key("s"),
+            Span::raw(" Save  "),
+            key("d/Esc"),
+            Span::raw(" Back to settings"),
+        ])]
+    } else {
+        vec![
+            Line::from(vec![
+                key("↑↓"),
+                Span::raw(" Navigate  "),
+                key("←→"),
+                Span::raw(" Adjust value  "),
+                key("Enter"),
+                Span::raw(" Collapse section  "),
+                key("/"),
+                Span::raw(" Search  "),
+                key("s"),
+                Span::raw(" Save  "),
+                key("Esc"),
+                Span::raw(" Back"),
+            ]),
+            Line::from(vec![
+                key("p"),
+                Span::raw(" Edit presets  "),
+                key("d"),
+                Span::raw(" Changes from defaults  "),
+                key("r"),
+                Span::raw(" Reset setting  "),
+                key("R"),
+                Span::raw(" Reset all"),
+            ]),
+        ]
+    };
+    if let Some(ref msg) = app.message {
+        help_text.push(Line::from(Span::styled(
+            msg.clone(),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let help = Paragraph::new(help_text)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::NONE));
+    f.render_widget(help, chunks[3]);
+}
+
+/// Settings grouped by section, customized ones marked
+fn render_settings(f: &mut Frame, app: &mut App, area: Rect) {
+    let rows = app.config_rows();
+    let items = if rows.is_empty() {
+        vec![ListItem::new(Span::styled(
+            format!("  No settings match \"{}\"", app.config_filter),
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        build_config_items(app, &rows)
+    };
 
     let list = List::new(items).block(
         Block::default()
@@ -44,70 +145,117 @@ pub fn render_config_screen(f: &mut Frame, app: &App) {
             .title(format!(
                 " Settings (config: {}) ",
                 AppConfig::config_path().display()
-            )),
+            ))
+            .title_top(search_line(app))
+            .title_bottom(toolchain_line(&app.deps)),
     );
-    f.render_widget(list, chunks[1]);
+    app.config_list_state.select(Some(app.config_selected));
+    f.render_stateful_widget(list, area, &mut app.config_list_state);
+}
 
-    // Help
-    let help_text = Line::from(vec![
-        Span::styled("↑↓", Style::default().fg(Color::Yellow)),
-        Span::raw(" Navigate  "),
-        Span::styled("←→", Style::default().fg(Color::Yellow)),
-        Span::raw(" Adjust value  "),
-        Span::styled("s", Style::default().fg(Color::Yellow)),
-        Span::raw(" Save  "),
-        Span::styled("Esc", Style::default().fg(Color::Yellow)),
-        Span::raw(" Back"),
-    ]);
+/// Every value that differs from the built-in defaults
+fn render_diff(f: &mut Frame, app: &mut App, area: Rect) {
+    let differences = app.config_differences();
+    let key_width = differences
+        .iter()
+        .map(|d| d.key.chars().count())
+        .max()
+        .unwrap_or(0);
+    let items: Vec<ListItem> = if differences.is_empty() {
+        vec![ListItem::new(Span::styled(
+            "  All settings are at their defaults",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        differences
+            .iter()
+            .enumerate()
+            .map(|(i, difference)| {
+                let is_selected = i == app.config_diff_selected;
+                let prefix = if is_selected { "> " } else { "  " };
+                let key_style = if is_selected {
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{}{:<width$}  ", prefix, difference.key, width = key_width),
+                        key_style,
+                    ),
+                    Span::styled(difference.value.clone(), Style::default().fg(Color::Yellow)),
+                    Span::styled(
+                        format!("  (default: {})", difference.default),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect()
+    };
 
-    let help = Paragraph::new(help_text)
-        .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::NONE));
-    f.render_widget(help, chunks[2]);
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(format!(" Changed from defaults ({}) ", differences.len())),
+    );
+    app.config_list_state.select(Some(app.config_diff_selected));
+    f.render_stateful_widget(list, area, &mut app.config_list_state);
 }
 
-fn build_config_items(config: &AppConfig, selected: usize) -> Vec<ListItem<'static>> {
-    let items = vec![
-        ("Encoder", config.encoder.display_name().to_string()),
-        (
-            "VMAF Threshold",
-            format!("{:.0}", config.quality.vmaf_threshold),
-        ),
-        (
-            "VMAF Enabled",
-            if config.quality.vmaf_enabled {
-                "Yes".to_string()
-            } else {
-                "No".to_string()
-            },
-        ),
-        ("SVT-AV1 Preset", config.performance.svt_preset.to_string()),
-        ("NVENC Preset", config.performance.nvenc_preset.clone()),
-        ("Output Suffix", config.output.suffix.clone()),
-        ("Output Container", config.output.container.clone()),
-        (
-            "Same Directory Output",
-            if config.output.same_directory {
-                "Yes".to_string()
-            } else {
-                "No".to_string()
-            },
-        ),
-        (
-            "Preferred Audio Languages",
-            config.tracks.preferred_audio_languages.join(", "),
-        ),
-        (
-            "Preferred Subtitle Languages",
-            config.tracks.preferred_subtitle_languages.join(", "),
-        ),
-    ];
+/// Detected versions and the features they rule out
+fn toolchain_line(deps: &DependencyStatus) -> Line<'static> {
+    let mut spans = vec![Span::styled(
+        format!(" {} ", deps.summary()),
+        Style::default().fg(Color::DarkGray),
+    )];
+    let disabled: Vec<String> = Feature::ALL
+        .iter()
+        .filter_map(|feature| {
+            let reason = deps.unsupported_reason(*feature)?;
+            Some(format!("{} {}", feature.label(), reason))
+        })
+        .collect();
+    if !disabled.is_empty() {
+        spans.push(Span::styled(
+            format!("Disabled: {} ", disabled.join(", ")),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    Line::from(spans)
+}
 
-    items
-        .into_iter()
+fn build_config_items(app: &App, rows: &[ConfigRow]) -> Vec<ListItem<'static>> {
+    let differences = app.config_differences();
+    rows.iter()
         .enumerate()
-        .map(|(i, (label, value))| {
-            let is_selected = i == selected;
+        .map(|(i, row)| {
+            let is_selected = i == app.config_selected;
+            let prefix = if is_selected { "> " } else { "  " };
+            let setting = match row {
+                ConfigRow::Section(section) => {
+                    let collapsed =
+                        app.config_filter.is_empty() && app.config_collapsed.contains(section);
+                    let marker = if collapsed { "▸" } else { "▾" };
+                    let style = if is_selected {
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default()
+                            .fg(Color::Magenta)
+                            .add_modifier(Modifier::BOLD)
+                    };
+                    return ListItem::new(Line::from(Span::styled(
+                        format!("{}{} {}", prefix, marker, section.label()),
+                        style,
+                    )));
+                }
+                ConfigRow::Setting(setting) => setting,
+            };
+
             let style = if is_selected {
                 Style::default()
                     .fg(Color::Cyan)
@@ -115,12 +263,18 @@ fn build_config_items(config: &AppConfig, selected: usize) -> Vec<ListItem<'stat
             } else {
                 Style::default().fg(Color::White)
             };
-
-            let prefix = if is_selected { "> " } else { "  " };
+            // Customized settings are marked, see the `d` view for what changed
+            let marker = if settings::is_customized(&setting.key(), &differences) {
+                Span::styled("* ", Style::default().fg(Color::Yellow))
+            } else {
+                Span::raw("  ")
+            };
             ListItem::new(Line::from(vec![
-                Span::styled(format!("{}{}: ", prefix, label), style),
+                Span::styled(format!("{}  ", prefix), style),
+                marker,
+                Span::styled(format!("{}: ", setting.label()), style),
                 Span::styled(
-                    value,
+                    setting.value(&app.config, &app.deps),
                     if is_selected {
                         Style::default()
                             .fg(Color::Yellow)
@@ -133,3 +287,16 @@ fn build_config_items(config: &AppConfig, selected: usize) -> Vec<ListItem<'stat
         })
         .collect()
 }
+
+/// Search box in the list's title, e.g. ` / vmaf_ `
+fn search_line(app: &App) -> Line<'static> {
+    if !app.config_searching && app.config_filter.is_empty() {
+        return Line::default();
+    }
+    let cursor = if app.config_searching { "_" } else { "" };
+    Line::from(Span::styled(
+        format!(" / {}{} ", app.config_filter, cursor),
+        Style::default().fg(Color::Yellow),
+    ))
+    .right_aligned()
+}
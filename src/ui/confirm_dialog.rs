@@ -1,5 +1,7 @@
-use super::common::centered_rect;
+use super::common::centered_rect_min;
+use super::file_confirm::efficient_sources_warning;
 use crate::app::{App, ConfirmAction};
+use crate::utils::format_file_size;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout},
@@ -8,22 +10,19 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph},
 };
 
+/// Smallest dialog that still shows the question and both buttons
+const DIALOG_MIN_WIDTH: u16 = 44;
+const DIALOG_MIN_HEIGHT: u16 = 9;
+
 pub fn render_confirm_dialog(f: &mut Frame, app: &App) {
     let action = match &app.confirm_dialog {
         Some(a) => a,
         None => return,
     };
+    let (title, message) = confirm_text(action, app);
 
-    let (title, message) = match action {
-        ConfirmAction::CancelEncoding => (
-            " Cancel Encoding ",
-            "Are you sure you want to cancel the current encoding?",
-        ),
-        ConfirmAction::ExitApp => (" Exit Application ", "Are you sure you want to exit?"),
-    };
-
-    // Calculate dialog area
-    let area = centered_rect(50, 30, f.area());
+    // Calculate dialog area, keeping room for the message and buttons
+    let area = centered_rect_min(50, 30, DIALOG_MIN_WIDTH, DIALOG_MIN_HEIGHT, f.area());
 
     // Clear area behind the dialog
     f.render_widget(Clear, area);
@@ -58,6 +57,17 @@ pub fn render_confirm_dialog(f: &mut Frame, app: &App) {
         .alignment(Alignment::Center);
     f.render_widget(msg, chunks[1]);
 
+    if action.can_apply_to_all() {
+        let mark = if app.confirm_apply_all { "x" } else { " " };
+        let apply_all = Paragraph::new(Line::from(vec![
+            Span::styled(format!("[{}] ", mark), Style::default().fg(Color::Yellow)),
+            Span::raw("Apply to all this session "),
+            Span::styled("(a)", Style::default().fg(Color::DarkGray)),
+        ]))
+        .alignment(Alignment::Center);
+        f.render_widget(apply_all, chunks[2]);
+    }
+
     // Buttons
     let yes_style = if app.confirm_selection {
         Style::default()
@@ -88,3 +98,67 @@ pub fn render_confirm_dialog(f: &mut Frame, app: &App) {
     let buttons_paragraph = Paragraph::new(buttons).alignment(Alignment::Center);
     f.render_widget(buttons_paragraph, chunks[3]);
 }
+
+/// Title and question of a confirmation dialog
+pub fn confirm_text(action: &ConfirmAction, app: &App) -> (&'static str, String) {
+    match action {
+        ConfirmAction::CancelEncoding => (
+            " Cancel Encoding ",
+            "Are you sure you want to cancel the current encoding?".to_string(),
+        ),
+        ConfirmAction::ExitApp => (
+            " Exit Application ",
+            "Are you sure you want to exit?".to_string(),
+        ),
+        ConfirmAction::ResetConfig => (
+            " Reset Configuration ",
+            format!(
+                "Reset all {} customized settings to their defaults?",
+                app.config_differences().len()
+            ),
+        ),
+        ConfirmAction::ConvertEfficientSources => (
+            " Efficient Sources ",
+            format!(
+                "{}. Convert them anyway?",
+                efficient_sources_warning(app).unwrap_or_default()
+            ),
+        ),
+        ConfirmAction::StartWithoutSpace => {
+            let shortfalls = app.space_shortfalls();
+            let missing: u64 = shortfalls
+                .iter()
+                .map(|n| n.estimated_size.saturating_sub(n.free.unwrap_or(0)))
+                .sum();
+            (
+                " Not Enough Space ",
+                format!(
+                    "Outputs likely need ~{} more than {} free. Start anyway?",
+                    format_file_size(missing),
+                    match shortfalls.as_slice() {
+                        [need] => format!("{} has", need.volume.display()),
+                        _ => format!("{} volumes have", shortfalls.len()),
+                    }
+                ),
+            )
+        }
+        ConfirmAction::RestoreQueue => (
+            " Unfinished Queue ",
+            format!(
+                "The last run left {} files unfinished. Continue where it stopped?",
+                app.restorable_queue.as_ref().map_or(0, |q| q.jobs.len())
+            ),
+        ),
+        ConfirmAction::DeleteLeftovers => {
+            let (count, size) = app.selected_leftovers();
+            (
+                " Delete Files ",
+                format!(
+                    "Permanently delete {} marked files ({})?",
+                    count,
+                    format_file_size(size)
+                ),
+            )
+        }
+    }
+}
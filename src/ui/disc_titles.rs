@@ -0,0 +1,119 @@
+use crate::app::App;
+use crate::utils::{format_duration, format_file_size};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+};
+use std::time::Duration;
+
+pub fn render_disc_titles(f: &mut Frame, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .margin(1)
+        .split(f.area());
+
+    // Header with the disc name and kind
+    let header_text = app
+        .disc_titles
+        .first()
+        .map(|t| {
+            let name = t
+                .disc
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            format!(
+                "{}  ({}, {} titles)",
+                name,
+                t.kind.label(),
+                app.disc_titles.len()
+            )
+        })
+        .unwrap_or_default();
+
+    let header = Paragraph::new(header_text)
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(" Disc "),
+        );
+    f.render_widget(header, chunks[0]);
+
+    // Titles, longest first
+    let items: Vec<ListItem> = app
+        .disc_titles
+        .iter()
+        .enumerate()
+        .map(|(i, title)| {
+            let duration = title
+                .duration_secs
+                .map(|d| format_duration(Duration::from_secs_f64(d.max(0.0))))
+                .unwrap_or_else(|| "--:--".to_string());
+            let parts = if title.parts.len() > 1 {
+                format!("  ({} parts)", title.parts.len())
+            } else {
+                String::new()
+            };
+            let main = if i == 0 { "  ★ main title" } else { "" };
+
+            let is_highlighted = i == app.disc_title_index;
+            let style = if is_highlighted {
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Green)
+            };
+
+            let prefix = if is_highlighted { "> " } else { "  " };
+            ListItem::new(format!(
+                "{}▷ {:<10} {:>9}  [{}]{}{}",
+                prefix,
+                title.name,
+                duration,
+                format_file_size(title.size),
+                parts,
+                main
+            ))
+            .style(style)
+        })
+        .collect();
+
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(" Titles "),
+    );
+    f.render_widget(list, chunks[1]);
+
+    // Help
+    let help_text = Line::from(vec![
+        Span::styled("↑↓", Style::default().fg(Color::Yellow)),
+        Span::raw(" Navigate  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)),
+        Span::raw(" Encode title  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(" Back"),
+    ]);
+
+    let help = Paragraph::new(help_text)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::NONE));
+    f.render_widget(help, chunks[2]);
+}
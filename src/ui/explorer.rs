@@ -1,4 +1,5 @@
-use crate::app::{App, SelectionMode};
+use crate::analyzer::detect_disc;
+use crate::app::{App, FolderTask, SelectionMode};
 use crate::queue::is_video_file;
 use crate::utils::format_file_size;
 use ratatui::{
@@ -72,7 +73,11 @@ pub fn render_explorer(f: &mut Frame, app: &mut App) {
 
     let title = match app.selection_mode {
         SelectionMode::File => " Select Video File ",
-        SelectionMode::Folder => " Select Folder ",
+        SelectionMode::Folder => match app.folder_task {
+            FolderTask::Encode => " Select Folder ",
+            FolderTask::Audit => " Select Folder to Audit ",
+            FolderTask::Cleanup => " Select Folder to Clean Up ",
+        },
     };
 
     let list = List::new(items)
@@ -151,8 +156,16 @@ fn create_entry_item(
             .unwrap_or_else(|| path.to_string_lossy().to_string())
     };
 
+    let disc = if is_dir && !is_parent {
+        detect_disc(path)
+    } else {
+        None
+    };
+
     // Add file metadata for video files
-    let metadata_str = if is_video && !is_parent {
+    let metadata_str = if let Some(kind) = disc {
+        format!("  [{}]", kind.label())
+    } else if is_video && !is_parent {
         path.metadata()
             .ok()
             .map(|m| format!("  [{}]", format_file_size(m.len())))
@@ -163,6 +176,8 @@ fn create_entry_item(
 
     let (icon, color) = if is_parent {
         ("↑ ", Color::Yellow)
+    } else if disc.is_some() {
+        ("◉ ", Color::Magenta)
     } else if is_dir {
         ("▶ ", Color::Blue)
     } else if is_toggled {
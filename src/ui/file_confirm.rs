@@ -1,5 +1,6 @@
 use crate::app::App;
-use crate::utils::format_file_size;
+use crate::audit::Verdict;
+use crate::utils::{format_decimal, format_file_size};
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout},
@@ -9,10 +10,13 @@ use ratatui::{
 };
 
 pub fn render_file_confirm(f: &mut Frame, app: &App) {
+    let warning = efficient_sources_warning(app);
+    let shortfalls = app.space_shortfalls();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
+            Constraint::Length(shortfalls.len() as u16 + warning.is_some() as u16),
             Constraint::Min(5),
             Constraint::Length(3),
         ])
@@ -22,10 +26,23 @@ pub fn render_file_confirm(f: &mut Frame, app: &App) {
     // Header with total count and size
     let total_size: u64 = app.queue.jobs.iter().filter_map(|j| j.source_size).sum();
 
+    let profiles: Vec<&str> = app
+        .device_profiles()
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect();
+    let profile = if profiles.is_empty() {
+        "none".to_string()
+    } else {
+        profiles.join(" + ")
+    };
     let title_text = format!(
-        "{} files selected  ({})",
+        "{} files selected  ({})  ·  Profile: {}  ·  Order: {}{}",
         app.queue.jobs.len(),
-        format_file_size(total_size)
+        format_file_size(total_size),
+        profile,
+        app.config.performance.queue_order.display_name(),
+        if app.simulate { "  ·  Simulation" } else { "" }
     );
 
     let title = Paragraph::new(title_text)
@@ -43,6 +60,33 @@ pub fn render_file_confirm(f: &mut Frame, app: &App) {
         );
     f.render_widget(title, chunks[0]);
 
+    // Space shortfalls first, they fail the run rather than waste it
+    let mut banner: Vec<Line> = shortfalls
+        .iter()
+        .map(|need| {
+            Line::styled(
+                format!("⚠ Not enough space on {} ⚠", need.describe()),
+                Style::default().bg(Color::Red),
+            )
+        })
+        .collect();
+    if let Some(warning) = warning {
+        banner.push(Line::styled(
+            format!("⚠ {} ⚠", warning),
+            Style::default().bg(Color::Yellow),
+        ));
+    }
+    if !banner.is_empty() {
+        let banner = Paragraph::new(banner)
+            .style(
+                Style::default()
+                    .fg(Color::Black)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .alignment(Alignment::Center);
+        f.render_widget(banner, chunks[1]);
+    }
+
     // File list
     let items: Vec<ListItem> = app
         .queue
@@ -65,7 +109,12 @@ pub fn render_file_confirm(f: &mut Frame, app: &App) {
             };
 
             let prefix = if is_highlighted { "> " } else { "  " };
-            ListItem::new(format!("{}▷ {}{}", prefix, job.filename(), size_str)).style(style)
+            let mut spans = vec![Span::styled(
+                format!("{}▷ {}{}", prefix, job.filename(), size_str),
+                style,
+            )];
+            spans.extend(verdict_spans(app, i));
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
@@ -73,20 +122,130 @@ pub fn render_file_confirm(f: &mut Frame, app: &App) {
         Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::DarkGray))
-            .title(" Files "),
+            .title(" Files ")
+            .title(verdict_summary(app).alignment(Alignment::Right)),
     );
-    f.render_widget(list, chunks[1]);
+    f.render_widget(list, chunks[2]);
 
     // Help
     let help_text = Line::from(vec![
         Span::styled("Enter", Style::default().fg(Color::Yellow)),
         Span::raw(" Proceed  "),
+        Span::styled("p", Style::default().fg(Color::Yellow)),
+        Span::raw(" Device profile  "),
+        Span::styled("1-9", Style::default().fg(Color::Yellow)),
+        Span::raw(" Add/remove output  "),
+        Span::styled("o", Style::default().fg(Color::Yellow)),
+        Span::raw(" Order  "),
+        Span::styled("s", Style::default().fg(Color::Yellow)),
+        Span::raw(if app.simulate {
+            " Encode for real  "
+        } else {
+            " Simulate  "
+        }),
         Span::styled("Esc", Style::default().fg(Color::Yellow)),
         Span::raw(" Back"),
     ]);
 
-    let help = Paragraph::new(help_text)
+    let mut help_lines = vec![help_text];
+    if let Some(ref msg) = app.message {
+        help_lines.push(Line::from(Span::styled(
+            msg.clone(),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    let help = Paragraph::new(help_lines)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::NONE));
-    f.render_widget(help, chunks[2]);
+    f.render_widget(help, chunks[3]);
+}
+
+fn verdict_color(verdict: Verdict) -> Color {
+    match verdict {
+        Verdict::Convert => Color::Green,
+        Verdict::Marginal => Color::Yellow,
+        Verdict::Skip => Color::Red,
+    }
+}
+
+/// Verdict of a file with the codec and bits per pixel behind it
+fn verdict_spans(app: &App, index: usize) -> Vec<Span<'static>> {
+    let dim = Style::default().fg(Color::DarkGray);
+    match app.file_probes.get(&index) {
+        Some(Some(entry)) => {
+            let verdict = entry.verdict(&app.config.triage);
+            let bpp = entry
+                .bits_per_pixel
+                .map(|bpp| format!(" · {} bpp", format_decimal(bpp, 3)))
+                .unwrap_or_default();
+            vec![
+                Span::raw("  "),
+                Span::styled(
+                    verdict.label(),
+                    Style::default()
+                        .fg(verdict_color(verdict))
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!("  {}{}", entry.family.label(), bpp), dim),
+            ]
+        }
+        Some(None) => vec![Span::styled("  unreadable", dim)],
+        None if app.probe_receiver.is_some() => vec![Span::styled("  probing…", dim)],
+        None => Vec::new(),
+    }
+}
+
+/// Count of files per verdict, e.g. " 12 Convert · 3 Marginal · 1 Skip "
+fn verdict_summary(app: &App) -> Line<'static> {
+    let verdicts: Vec<Verdict> = app
+        .file_probes
+        .values()
+        .flatten()
+        .map(|entry| entry.verdict(&app.config.triage))
+        .collect();
+    let mut spans = Vec::new();
+    for verdict in [Verdict::Convert, Verdict::Marginal, Verdict::Skip] {
+        let count = verdicts.iter().filter(|&&v| v == verdict).count();
+        if count == 0 {
+            continue;
+        }
+        spans.push(Span::raw(if spans.is_empty() { " " } else { " · " }));
+        spans.push(Span::styled(
+            format!("{} {}", count, verdict.label()),
+            Style::default().fg(verdict_color(verdict)),
+        ));
+    }
+    if !spans.is_empty() {
+        spans.push(Span::raw(" "));
+    }
+    Line::from(spans)
+}
+
+/// Warning about queued AV1 and VP9 sources and what converting them saves
+///
+/// e.g. "2 files are already AV1/VP9: converting saves ~1.2 GB of 8.0 GB (15%)"
+pub fn efficient_sources_warning(app: &App) -> Option<String> {
+    let sources = app.efficient_sources();
+    if sources.is_empty() {
+        return None;
+    }
+    let size: u64 = sources.iter().map(|e| e.size).sum();
+    let savings: u64 = sources.iter().map(|e| e.savings()).sum();
+    let percent = if size > 0 {
+        savings as f64 / size as f64 * 100.0
+    } else {
+        0.0
+    };
+    Some(format!(
+        "{} {} already AV1/VP9: converting saves ~{} of {} ({:.0}%)",
+        sources.len(),
+        if sources.len() == 1 {
+            "file is"
+        } else {
+            "files are"
+        },
+        format_file_size(savings),
+        format_file_size(size),
+        percent
+    ))
 }
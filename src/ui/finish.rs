@@ -1,16 +1,22 @@
 use super::common::{get_quality_description, get_vmaf_color};
 use crate::app::App;
-use crate::queue::JobStatus;
-use crate::utils::{format_duration, format_file_size};
+use crate::config::Encoder;
+use crate::encoder::StageTimings;
+use crate::queue::{EncodingJob, JobStatus, StepStatus};
+use crate::utils::{format_decimal, format_duration, format_file_size, format_speed};
+use crate::verifier::{ValidationIssue, VmafSegment};
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{
+        Block, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
+    },
 };
+use std::time::Duration;
 
-pub fn render_finish(f: &mut Frame, app: &App) {
+pub fn render_finish(f: &mut Frame, app: &mut App) {
     let is_single_file = app.queue.jobs.len() == 1;
 
     if is_single_file {
@@ -36,7 +42,7 @@ fn render_single_file_finish(f: &mut Frame, app: &App) {
 
     let mut lines = vec![
         Line::from(vec![Span::styled(
-            "Conversion Complete!",
+            complete_title(app),
             Style::default()
                 .fg(Color::Green)
                 .add_modifier(Modifier::BOLD),
@@ -45,7 +51,7 @@ fn render_single_file_finish(f: &mut Frame, app: &App) {
         Line::from(vec![
             Span::styled("File: ", Style::default().fg(Color::DarkGray)),
             Span::styled(
-                job.filename(),
+                job.display_name(),
                 Style::default()
                     .fg(Color::Cyan)
                     .add_modifier(Modifier::BOLD),
@@ -98,16 +104,17 @@ fn render_single_file_finish(f: &mut Frame, app: &App) {
                 ),
             ]));
         }
-        JobStatus::Error { message } => {
+        JobStatus::Error { message, kind } => {
             lines.push(Line::from(vec![
                 Span::styled("Status: ", Style::default().fg(Color::DarkGray)),
-                Span::styled(
-                    format!("Error: {}", message),
-                    Style::default().fg(Color::Red),
-                ),
+                Span::styled(kind.summary(), Style::default().fg(Color::Red)),
+            ]));
+            lines.push(Line::from(vec![
+                Span::styled("Details: ", Style::default().fg(Color::DarkGray)),
+                Span::styled(message.clone(), Style::default().fg(Color::DarkGray)),
             ]));
         }
-        JobStatus::Skipped { reason } => {
+        JobStatus::Skipped { reason, .. } => {
             lines.push(Line::from(vec![
                 Span::styled("Status: ", Style::default().fg(Color::DarkGray)),
                 Span::styled(
@@ -118,6 +125,7 @@ fn render_single_file_finish(f: &mut Frame, app: &App) {
         }
         _ => {}
     }
+    lines.extend(simulation_lines(job));
 
     // Size info
     if let Some(source) = job.source_size {
@@ -132,16 +140,55 @@ fn render_single_file_finish(f: &mut Frame, app: &App) {
             Span::raw(format_file_size(output)),
         ]));
     }
+    if let Some(encoded) = job.encoded_size {
+        lines.push(Line::from(vec![
+            Span::styled("Before remux: ", Style::default().fg(Color::DarkGray)),
+            Span::raw(format_file_size(encoded)),
+        ]));
+    }
     if let Some((saved, percent)) = job.size_reduction() {
         lines.push(Line::from(vec![
             Span::styled("Reduction: ", Style::default().fg(Color::DarkGray)),
             Span::styled(
-                format!("{} ({:.1}%)", format_file_size(saved), percent),
+                format!(
+                    "{} ({}%)",
+                    format_file_size(saved),
+                    format_decimal(percent, 1)
+                ),
                 Style::default()
                     .fg(Color::Green)
                     .add_modifier(Modifier::BOLD),
             ),
         ]));
+        let (audio, subtitles) = job.dropped_track_savings();
+        if audio + subtitles > 0 {
+            lines.push(Line::from(vec![
+                Span::styled("Dropped tracks: ", Style::default().fg(Color::DarkGray)),
+                Span::raw(format!(
+                    "~{} audio, ~{} subtitles",
+                    format_file_size(audio),
+                    format_file_size(subtitles)
+                )),
+            ]));
+        }
+    }
+
+    if !job.vmaf_worst_segments.is_empty() {
+        lines.push(Line::from(""));
+        lines.extend(worst_segment_lines(&job.vmaf_worst_segments));
+        lines.push(Line::from(""));
+    }
+    if !job.validation_issues.is_empty() {
+        lines.extend(validation_lines(&job.validation_issues));
+        lines.push(Line::from(""));
+    }
+    if job.encoder_fallback.is_some() {
+        lines.extend(fallback_lines(&job.encoder_fallback));
+        lines.push(Line::from(""));
+    }
+    if !job.steps.is_empty() {
+        lines.extend(step_lines(job));
+        lines.push(Line::from(""));
     }
 
     // Source deletion status
@@ -155,6 +202,16 @@ fn render_single_file_finish(f: &mut Frame, app: &App) {
             format!("Source kept (VMAF {:.1} < 90)", vmaf),
             Style::default().fg(Color::DarkGray),
         )]));
+    } else if let Some(ref reason) = job.source_kept_reason {
+        lines.push(Line::from(vec![Span::styled(
+            format!("Source kept ({})", reason),
+            Style::default().fg(Color::DarkGray),
+        )]));
+    } else if !job.validation_issues.is_empty() {
+        lines.push(Line::from(vec![Span::styled(
+            "Source kept (output differs from source)",
+            Style::default().fg(Color::DarkGray),
+        )]));
     }
 
     if !elapsed_str.is_empty() {
@@ -163,6 +220,16 @@ fn render_single_file_finish(f: &mut Frame, app: &App) {
             Span::raw(elapsed_str),
         ]));
     }
+    if !job.stage_timings.is_empty() {
+        lines.push(Line::from(""));
+        lines.extend(timeline_lines(&job.stage_timings));
+    }
+    if let Some(rate) = job.encode_throughput() {
+        lines.push(Line::from(vec![
+            Span::styled("Throughput: ", Style::default().fg(Color::DarkGray)),
+            Span::raw(format_speed(rate)),
+        ]));
+    }
 
     let summary = Paragraph::new(lines).alignment(Alignment::Center).block(
         Block::default()
@@ -174,24 +241,49 @@ fn render_single_file_finish(f: &mut Frame, app: &App) {
 
     // Help
     let help_text = Line::from(vec![
+        Span::styled("o", Style::default().fg(Color::Yellow)),
+        Span::raw(" Play  "),
+        Span::styled("c", Style::default().fg(Color::Yellow)),
+        Span::raw(" Copy path  "),
+        Span::styled("f", Style::default().fg(Color::Yellow)),
+        Span::raw(" Open folder  "),
         Span::styled("Enter", Style::default().fg(Color::Yellow)),
         Span::raw(" New conversion  "),
         Span::styled("q", Style::default().fg(Color::Yellow)),
         Span::raw(" Quit"),
     ]);
 
-    let help = Paragraph::new(help_text)
-        .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::NONE));
-    f.render_widget(help, chunks[1]);
+    render_help(f, app, help_text, chunks[1]);
 }
 
-fn render_multi_file_finish(f: &mut Frame, app: &App) {
+fn render_multi_file_finish(f: &mut Frame, app: &mut App) {
+    // Worst VMAF segments and stream drift of the selected result
+    let segment_lines = app
+        .queue
+        .jobs
+        .get(app.finish_index)
+        .map(|j| {
+            let mut lines = simulation_lines(j);
+            lines.extend(worst_segment_lines(&j.vmaf_worst_segments));
+            lines.extend(validation_lines(&j.validation_issues));
+            lines.extend(fallback_lines(&j.encoder_fallback));
+            lines.extend(step_lines(j));
+            lines.extend(timeline_lines(&j.stage_timings));
+            lines
+        })
+        .unwrap_or_default();
+    let detail_height = if segment_lines.is_empty() {
+        0
+    } else {
+        segment_lines.len() as u16 + 2
+    };
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(7),
+            Constraint::Length(9),
             Constraint::Min(5),
+            Constraint::Length(detail_height),
             Constraint::Length(3),
         ])
         .margin(1)
@@ -207,7 +299,7 @@ fn render_multi_file_finish(f: &mut Frame, app: &App) {
 
     let mut summary_lines = vec![
         Line::from(vec![Span::styled(
-            "Conversion Complete!",
+            complete_title(app),
             Style::default()
                 .fg(Color::Green)
                 .add_modifier(Modifier::BOLD),
@@ -224,8 +316,24 @@ fn render_multi_file_finish(f: &mut Frame, app: &App) {
             Span::raw(format!("Errors: {}", app.queue.error_count)),
         ]),
     ];
+    let (user_skipped, cancelled) = (app.queue.user_skipped_count, app.queue.cancelled_count);
+    if user_skipped > 0 || cancelled > 0 {
+        summary_lines.push(Line::from(vec![
+            Span::styled("⊘ ", Style::default().fg(Color::DarkGray)),
+            Span::raw(format!(
+                "Skipped by you: {}   Cancelled: {}",
+                user_skipped, cancelled
+            )),
+        ]));
+    }
 
     if total_saved > 0 {
+        let savings = app.queue.space_savings();
+        let breakdown = if savings.audio > 0 || savings.subtitles > 0 {
+            format!(" ({})", savings.summary())
+        } else {
+            String::new()
+        };
         summary_lines.push(Line::from(vec![
             Span::styled("Total space saved: ", Style::default().fg(Color::DarkGray)),
             Span::styled(
@@ -234,6 +342,7 @@ fn render_multi_file_finish(f: &mut Frame, app: &App) {
                     .fg(Color::Green)
                     .add_modifier(Modifier::BOLD),
             ),
+            Span::styled(breakdown, Style::default().fg(Color::DarkGray)),
         ]));
     }
 
@@ -244,6 +353,20 @@ fn render_multi_file_finish(f: &mut Frame, app: &App) {
         ]));
     }
 
+    let stage_timings = app.queue.total_stage_timings();
+    if !stage_timings.is_empty() {
+        summary_lines.push(Line::from(vec![
+            Span::styled("Stages: ", Style::default().fg(Color::DarkGray)),
+            Span::raw(stage_timings.summary()),
+        ]));
+    }
+    if let Some(rate) = app.queue.encode_throughput() {
+        summary_lines.push(Line::from(vec![
+            Span::styled("Throughput: ", Style::default().fg(Color::DarkGray)),
+            Span::raw(format_speed(rate)),
+        ]));
+    }
+
     let summary = Paragraph::new(summary_lines)
         .alignment(Alignment::Center)
         .block(
@@ -262,44 +385,260 @@ fn render_multi_file_finish(f: &mut Frame, app: &App) {
         .map(|job| create_result_item(job))
         .collect();
 
-    let list = List::new(items).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray))
-            .title(" Results "),
-    );
-    f.render_widget(list, chunks[1]);
+    let total = app.queue.jobs.len();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(format!(" Results ({}/{}) ", app.finish_index + 1, total)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_stateful_widget(list, chunks[1], &mut app.finish_list_state);
+
+    // Scrollbar only when the list overflows
+    if total > chunks[1].height.saturating_sub(2) as usize {
+        let mut scrollbar_state = ScrollbarState::new(total).position(app.finish_index);
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            chunks[1],
+            &mut scrollbar_state,
+        );
+    }
 
     // Help
     let help_text = Line::from(vec![
+        Span::styled("↑↓/PgUp/PgDn", Style::default().fg(Color::Yellow)),
+        Span::raw(" Scroll  "),
+        Span::styled("o", Style::default().fg(Color::Yellow)),
+        Span::raw(" Play  "),
+        Span::styled("c", Style::default().fg(Color::Yellow)),
+        Span::raw(" Copy path  "),
+        Span::styled("f", Style::default().fg(Color::Yellow)),
+        Span::raw(" Open folder  "),
         Span::styled("Enter", Style::default().fg(Color::Yellow)),
         Span::raw(" New conversion  "),
         Span::styled("q", Style::default().fg(Color::Yellow)),
         Span::raw(" Quit"),
     ]);
 
-    let help = Paragraph::new(help_text)
+    if !segment_lines.is_empty() {
+        let detail = Paragraph::new(segment_lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(" Quality Details "),
+        );
+        f.render_widget(detail, chunks[2]);
+    }
+
+    render_help(f, app, help_text, chunks[3]);
+}
+
+/// List the lowest-scoring time ranges so they can be checked in a player
+fn worst_segment_lines(segments: &[VmafSegment]) -> Vec<Line<'static>> {
+    if segments.is_empty() {
+        return Vec::new();
+    }
+
+    let mut lines = vec![Line::from(Span::styled(
+        "Worst segments:",
+        Style::default().fg(Color::DarkGray),
+    ))];
+    for segment in segments {
+        lines.push(Line::from(vec![
+            Span::raw(format!(
+                "{} – {}  ",
+                format_duration(Duration::from_secs_f64(segment.start_secs)),
+                format_duration(Duration::from_secs_f64(segment.end_secs))
+            )),
+            Span::styled(
+                format!("VMAF {:.1}", segment.score),
+                Style::default().fg(get_vmaf_color(segment.score)),
+            ),
+        ]));
+    }
+    lines
+}
+
+fn validation_lines(issues: &[ValidationIssue]) -> Vec<Line<'static>> {
+    if issues.is_empty() {
+        return Vec::new();
+    }
+
+    let mut lines = vec![Line::from(Span::styled(
+        "Output differs from source:",
+        Style::default().fg(Color::DarkGray),
+    ))];
+    for issue in issues {
+        lines.push(Line::from(Span::styled(
+            issue.describe(),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    lines
+}
+
+/// Heading of the Finish screen
+fn complete_title(app: &App) -> &'static str {
+    if app.queue.jobs.iter().any(|j| j.simulation.is_some()) {
+        "Simulation Complete!"
+    } else {
+        "Conversion Complete!"
+    }
+}
+
+/// Encoder, quality, output and source handling a simulated job would have had
+fn simulation_lines(job: &EncodingJob) -> Vec<Line<'static>> {
+    let Some(ref simulated) = job.simulation else {
+        return Vec::new();
+    };
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "Simulated: {} {} {}, source would be {}",
+            simulated.encoder.display_name(),
+            simulated.encoder.quality_label(),
+            simulated.crf,
+            if simulated.deletes_source {
+                "deleted"
+            } else {
+                "kept"
+            }
+        ),
+        Style::default().fg(Color::Cyan),
+    ))];
+    if let Some(ref output) = job.output_path {
+        lines.push(Line::from(Span::styled(
+            format!("Would write {}", output.display()),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines
+}
+
+fn fallback_lines(fallback: &Option<(Encoder, String)>) -> Vec<Line<'static>> {
+    let Some((encoder, reason)) = fallback else {
+        return Vec::new();
+    };
+    vec![Line::from(Span::styled(
+        format!(
+            "Encoded with SVT-AV1, {} can't encode {}",
+            encoder.display_name(),
+            reason
+        ),
+        Style::default().fg(Color::Yellow),
+    ))]
+}
+
+/// The encode and the steps chained after it, each with its status
+fn step_lines(job: &EncodingJob) -> Vec<Line<'static>> {
+    if job.steps.is_empty() {
+        return Vec::new();
+    }
+    let encode = StepStatus::of_encode(&job.status);
+    let mut lines = vec![Line::from(Span::styled(
+        "Steps",
+        Style::default().fg(Color::DarkGray),
+    ))];
+    for (name, status) in std::iter::once(("Encode".to_string(), &encode)).chain(
+        job.steps
+            .steps
+            .iter()
+            .map(|step| (step.action.display_name(), &step.status)),
+    ) {
+        let color = match status {
+            StepStatus::Done(_) => Color::Green,
+            StepStatus::Failed(_) => Color::Red,
+            StepStatus::Running => Color::Cyan,
+            StepStatus::Pending | StepStatus::Skipped(_) => Color::DarkGray,
+        };
+        let mut spans = vec![
+            Span::styled(format!("{} ", status.symbol()), Style::default().fg(color)),
+            Span::raw(name),
+        ];
+        if let Some(note) = status.note() {
+            spans.push(Span::styled(
+                format!(" ({})", note),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+/// Width of the bars in the stage timeline
+const TIMELINE_BAR_WIDTH: usize = 20;
+
+/// Time per stage of a job, the slowest highlighted as where optimizing pays off
+fn timeline_lines(timings: &StageTimings) -> Vec<Line<'static>> {
+    if timings.is_empty() {
+        return Vec::new();
+    }
+    let slowest = timings.slowest().map(|(stage, _)| stage);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("Timeline ({} total)", format_duration(timings.total())),
+        Style::default().fg(Color::DarkGray),
+    ))];
+    for ((stage, _, _), row) in timings
+        .timeline()
+        .into_iter()
+        .zip(timings.timeline_lines(TIMELINE_BAR_WIDTH))
+    {
+        let color = if Some(stage) == slowest {
+            Color::Yellow
+        } else {
+            Color::Gray
+        };
+        lines.push(Line::from(Span::styled(row, Style::default().fg(color))));
+    }
+    lines
+}
+
+/// Help line with the last action's message below it
+fn render_help(f: &mut Frame, app: &App, help_text: Line<'static>, area: Rect) {
+    let mut lines = vec![help_text];
+    if let Some(ref msg) = app.message {
+        lines.push(Line::from(Span::styled(
+            msg.clone(),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let help = Paragraph::new(lines)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::NONE));
-    f.render_widget(help, chunks[2]);
+    f.render_widget(help, area);
 }
 
-fn create_result_item(job: &crate::queue::EncodingJob) -> ListItem<'static> {
-    let name = job.filename();
+fn create_result_item(job: &EncodingJob) -> ListItem<'static> {
+    let name = job.display_name();
 
     // Output size and compression ratio
     let output_info = match (job.output_size, job.size_reduction()) {
         (Some(output), Some((_, percent))) => {
-            format!(" → {} (-{:.1}%)", format_file_size(output), percent)
+            format!(
+                " → {} (-{}%)",
+                format_file_size(output),
+                format_decimal(percent, 1)
+            )
         }
         (Some(output), None) => format!(" → {}", format_file_size(output)),
         _ => String::new(),
     };
 
     // Source deletion info
-    let source_info = if job.source_deleted {
+    let source_info = if job.simulation.is_some() {
+        " [simulated]"
+    } else if job.source_deleted {
         " [source deleted]"
-    } else if job.source_kept_vmaf.is_some() {
+    } else if !job.validation_issues.is_empty() {
+        " [output differs, source kept]"
+    } else if job.source_kept_vmaf.is_some() || job.source_kept_reason.is_some() {
         " [source kept]"
     } else {
         ""
@@ -345,9 +684,9 @@ fn create_result_item(job: &crate::queue::EncodingJob) -> ListItem<'static> {
             }
             ListItem::new(Line::from(spans))
         }
-        JobStatus::Skipped { reason } => ListItem::new(format!("  ⊘ {} ({})", name, reason))
+        JobStatus::Skipped { reason, .. } => ListItem::new(format!("  ⊘ {} ({})", name, reason))
             .style(Style::default().fg(Color::Yellow)),
-        JobStatus::Error { message } => ListItem::new(format!("  ✗ {}: {}", name, message))
+        JobStatus::Error { kind, .. } => ListItem::new(format!("  ✗ {}: {}", name, kind.summary()))
             .style(Style::default().fg(Color::Red)),
         JobStatus::QualityWarning { vmaf, threshold } => {
             let vmaf_color = get_vmaf_color(*vmaf);
@@ -1,5 +1,6 @@
 use super::common::create_menu_item;
 use crate::app::App;
+use crate::utils::update;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -32,14 +33,26 @@ pub fn render_home(f: &mut Frame, app: &App) {
         .block(Block::default().borders(Borders::NONE));
     f.render_widget(title, chunks[0]);
 
+    // Version in the top right corner, with the newer release if there is one
+    let version = Paragraph::new(version_line(app)).alignment(Alignment::Right);
+    f.render_widget(
+        version,
+        Rect {
+            height: 1,
+            ..f.area()
+        },
+    );
+
     // Menu
     let menu_area = centered_menu_area(chunks[1]);
     let menu_items: Vec<ListItem> = vec![
         create_menu_item("Open video file", 0, app.home_index),
         create_menu_item("Open folder", 1, app.home_index),
         create_menu_item("Open folder (recursive)", 2, app.home_index),
-        create_menu_item("Configuration", 3, app.home_index),
-        create_menu_item("Quit", 4, app.home_index),
+        create_menu_item("Audit folder", 3, app.home_index),
+        create_menu_item("Clean up folder", 4, app.home_index),
+        create_menu_item("Configuration", 5, app.home_index),
+        create_menu_item("Quit", 6, app.home_index),
     ];
 
     let menu = List::new(menu_items)
@@ -83,17 +96,44 @@ pub fn render_home(f: &mut Frame, app: &App) {
     f.render_widget(help, chunks[4]);
 }
 
+fn version_line(app: &App) -> Line<'static> {
+    let current = format!("v{} ", update::current_version());
+    match app.available_update {
+        Some(latest) => Line::from(vec![
+            Span::styled("Update available: ", Style::default().fg(Color::Yellow)),
+            Span::styled(current, Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!("→ v{} ", latest),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        None => Line::from(Span::styled(current, Style::default().fg(Color::DarkGray))),
+    }
+}
+
 fn render_status_info(app: &App) -> Line<'static> {
     let encoder_span = Span::styled(
         format!("Encoder: {}", app.config.encoder),
         Style::default().fg(Color::Cyan),
     );
 
-    Line::from(vec![encoder_span])
+    let toolchain = app.deps.summary();
+    if toolchain.is_empty() {
+        return Line::from(vec![encoder_span]);
+    }
+    Line::from(vec![
+        encoder_span,
+        Span::styled(
+            format!("  ({})", toolchain),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ])
 }
 
 fn render_vmaf_info(app: &App) -> Line<'static> {
-    if app.deps {
+    if app.deps.is_ready() {
         Line::from(vec![
             Span::styled("✓ ", Style::default().fg(Color::Green)),
             Span::raw("VMAF quality validation enabled (threshold: "),
@@ -1,18 +1,64 @@
+mod audit;
+mod cleanup;
 pub mod common;
+mod compact;
 mod config_screen;
 mod confirm_dialog;
+mod disc_titles;
 mod explorer;
 mod file_confirm;
 mod finish;
 mod home;
+mod palette;
+mod presets;
 mod queue;
 mod track_config;
+mod viewer;
 
+pub use audit::render_audit;
+pub use cleanup::render_cleanup;
+pub use compact::{fits, render_too_small};
 pub use config_screen::render_config_screen;
 pub use confirm_dialog::render_confirm_dialog;
+pub use disc_titles::render_disc_titles;
 pub use explorer::render_explorer;
 pub use file_confirm::render_file_confirm;
 pub use finish::render_finish;
 pub use home::render_home;
+pub use palette::render_palette;
+pub use presets::render_presets;
 pub use queue::render_queue;
 pub use track_config::render_track_config;
+pub use viewer::render_viewer;
+
+use crate::app::{App, Screen};
+use ratatui::Frame;
+
+/// Draw the current screen with its open palette or dialog
+///
+/// Terminals too small for the screen get a compact view instead.
+pub fn render(f: &mut Frame, app: &mut App) {
+    if !fits(&app.current_screen, f.area()) {
+        render_too_small(f, app);
+        return;
+    }
+    match app.current_screen {
+        Screen::Home => render_home(f, app),
+        Screen::FileExplorer { .. } => render_explorer(f, app),
+        Screen::FileConfirm => render_file_confirm(f, app),
+        Screen::DiscTitles => render_disc_titles(f, app),
+        Screen::Audit => render_audit(f, app),
+        Screen::Cleanup => render_cleanup(f, app),
+        Screen::TrackConfig => render_track_config(f, app),
+        Screen::Queue => render_queue(f, app),
+        Screen::Finish => render_finish(f, app),
+        Screen::Configuration => render_config_screen(f, app),
+        Screen::Presets => render_presets(f, app),
+    }
+    if app.palette.is_some() {
+        render_palette(f, app);
+    }
+    if app.confirm_dialog.is_some() {
+        render_confirm_dialog(f, app);
+    }
+}
@@ -0,0 +1,86 @@
+use super::common::centered_rect_min;
+use crate::app::App;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+};
+
+pub fn render_palette(f: &mut Frame, app: &App) {
+    let Some(ref palette) = app.palette else {
+        return;
+    };
+    let matches = app.palette_matches();
+
+    let area = centered_rect_min(50, 50, 40, 10, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(" Commands ")
+        .title_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(inner);
+
+    // Query line
+    let query = Paragraph::new(Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Yellow)),
+        Span::styled(
+            format!("{}_", palette.query),
+            Style::default().fg(Color::White),
+        ),
+    ]));
+    f.render_widget(query, chunks[0]);
+
+    // Matching actions
+    let items: Vec<ListItem> = if matches.is_empty() {
+        vec![ListItem::new(Span::styled(
+            "No matching command",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        matches
+            .iter()
+            .map(|action| ListItem::new(action.label()))
+            .collect()
+    };
+    let list = List::new(items)
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    let mut state = ListState::default();
+    if !matches.is_empty() {
+        state.select(Some(palette.selected.min(matches.len() - 1)));
+    }
+    f.render_stateful_widget(list, chunks[1], &mut state);
+
+    let help = Paragraph::new(Line::from(vec![
+        Span::styled("↑↓", Style::default().fg(Color::Yellow)),
+        Span::raw(" Select  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)),
+        Span::raw(" Run  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(" Close"),
+    ]));
+    f.render_widget(help, chunks[2]);
+}
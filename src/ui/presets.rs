@@ -0,0 +1,133 @@
+use crate::app::App;
+use crate::config::{EncodingPresetsConfig, PresetField};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+};
+
+/// Width of the tier name column
+const TIER_WIDTH: u16 = 12;
+
+pub fn render_presets(f: &mut Frame, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(4),
+        ])
+        .margin(1)
+        .split(f.area());
+
+    let title = Paragraph::new("Encoding Presets")
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+    f.render_widget(title, chunks[0]);
+
+    // Tiers as rows, preset values as columns
+    let header = Row::new(
+        std::iter::once(Cell::from("Tier")).chain(PresetField::ALL.iter().map(|field| {
+            let range = field.range();
+            Cell::from(format!(
+                "{} ({}-{})",
+                field.label(),
+                range.start(),
+                range.end()
+            ))
+        })),
+    )
+    .style(
+        Style::default()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let (_, selected_field) = app.selected_preset_cell();
+    let rows: Vec<Row> = EncodingPresetsConfig::NAMES
+        .iter()
+        .enumerate()
+        .map(|(row, name)| {
+            let Some(preset) = app.config.presets.get(name) else {
+                return Row::default();
+            };
+            let tier_style = if row == app.preset_row {
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let cells = PresetField::ALL.iter().map(|&field| {
+                if row != app.preset_row || field != selected_field {
+                    return Cell::from(preset.field(field).to_string())
+                        .style(Style::default().fg(Color::Gray));
+                }
+                match app.preset_input {
+                    Some(ref input) => Cell::from(format!("{}_", input))
+                        .style(Style::default().fg(Color::Black).bg(Color::Yellow)),
+                    None => Cell::from(preset.field(field).to_string()).style(
+                        Style::default()
+                            .fg(Color::Black)
+                            .bg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                }
+            });
+            Row::new(
+                std::iter::once(
+                    Cell::from(EncodingPresetsConfig::display_name(name)).style(tier_style),
+                )
+                .chain(cells),
+            )
+        })
+        .collect();
+
+    let widths = std::iter::once(Constraint::Length(TIER_WIDTH))
+        .chain(PresetField::ALL.iter().map(|_| Constraint::Fill(1)));
+    let table = Table::new(rows, widths).header(header).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(" Presets per tier "),
+    );
+    f.render_widget(table, chunks[1]);
+
+    // Help
+    let mut help_text = vec![Line::from(vec![
+        Span::styled("↑↓←→", Style::default().fg(Color::Yellow)),
+        Span::raw(" Move  "),
+        Span::styled("+/-", Style::default().fg(Color::Yellow)),
+        Span::raw(" Adjust  "),
+        Span::styled("0-9", Style::default().fg(Color::Yellow)),
+        Span::raw(" Type a value  "),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)),
+        Span::raw(" Apply  "),
+        Span::styled("s", Style::default().fg(Color::Yellow)),
+        Span::raw(" Save  "),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(" Back"),
+    ])];
+    if let Some(ref msg) = app.message {
+        help_text.push(Line::from(Span::styled(
+            msg.clone(),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let help = Paragraph::new(help_text)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::NONE));
+    f.render_widget(help, chunks[2]);
+}
@@ -1,22 +1,33 @@
 use super::common::get_vmaf_color;
 use crate::app::App;
 use crate::queue::JobStatus;
-use crate::utils::format_duration;
+use crate::utils::{format_decimal, format_duration, format_speed};
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Sparkline},
 };
 
-pub fn render_queue(f: &mut Frame, app: &App) {
+pub fn render_queue(f: &mut Frame, app: &mut App) {
+    let show_stats = app.encoding_active
+        && app.queue.stats.job_index == Some(app.queue.current_job_index)
+        && app
+            .queue
+            .jobs
+            .get(app.queue.current_job_index)
+            .is_some_and(|j| matches!(j.status, JobStatus::Encoding { .. }));
+
+    let encoding: Vec<usize> = app.queue.encoding_jobs().collect();
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
             Constraint::Min(5),
-            Constraint::Length(3),
+            Constraint::Length(if show_stats { 5 } else { 0 }),
+            Constraint::Length(3 * encoding.len().max(1) as u16),
             Constraint::Length(3),
         ])
         .margin(1)
@@ -29,11 +40,16 @@ pub fn render_queue(f: &mut Frame, app: &App) {
         if let Some(job) = app.queue.jobs.get(app.queue.current_job_index) {
             if matches!(job.status, JobStatus::Encoding { .. }) {
                 let current_number = (app.queue.encoding_progress_done + 1).min(total_to_encode);
+                let others = match encoding.len() {
+                    0 | 1 => String::new(),
+                    n => format!(" + {} more", n - 1),
+                };
                 format!(
-                    "[{}/{}] Encoding: {}",
+                    "[{}/{}] Encoding: {}{}",
                     current_number,
                     total_to_encode,
-                    job.filename()
+                    job.display_name(),
+                    others
                 )
             } else {
                 format!(
@@ -45,9 +61,27 @@ pub fn render_queue(f: &mut Frame, app: &App) {
             format!("Conversion Queue (0/{})", total_to_encode)
         }
     } else {
-        let done = app.queue.converted_count + app.queue.skipped_count + app.queue.error_count;
-        let total = app.queue.jobs.len();
-        format!("Conversion Queue ({}/{})", done, total)
+        format!(
+            "Conversion Queue ({}/{})",
+            app.queue.finished_count(),
+            app.queue.jobs.len()
+        )
+    };
+    let title_text = match app.queue.waiting_for_schedule {
+        Some(window) if app.encoding_active => {
+            format!("Waiting for schedule ({}) · {}", window, title_text)
+        }
+        _ => title_text,
+    };
+    let title_text = if app.encoding_active && app.queue.is_paused() {
+        format!("⏸ Paused · {}", title_text)
+    } else {
+        title_text
+    };
+    let title_text = if app.simulate {
+        format!("Simulation · {}", title_text)
+    } else {
+        title_text
     };
 
     let title = Paragraph::new(title_text)
@@ -71,61 +105,59 @@ pub fn render_queue(f: &mut Frame, app: &App) {
         .iter()
         .enumerate()
         .map(|(i, job)| {
-            let is_current = i == app.queue.current_job_index && app.encoding_active;
-            create_queue_item(&job.filename(), &job.status, is_current, job.crf)
+            let is_current =
+                app.encoding_active && (i == app.queue.current_job_index || encoding.contains(&i));
+            // An encoded job stays at 100% while its steps run
+            let name = match job.steps.running() {
+                Some(step) => format!("{} → {}", job.display_name(), step.action.display_name()),
+                None => job.display_name(),
+            };
+            let name = if job.mode.copies_video() {
+                format!("{} ({})", name, job.mode.display_name().to_lowercase())
+            } else {
+                name
+            };
+            create_queue_item(&name, &job.status, is_current, job.crf)
         })
         .collect();
 
-    let list = List::new(items).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::DarkGray))
-            .title(" Files "),
-    );
-    f.render_widget(list, chunks[1]);
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(" Files "),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray))
+        .highlight_symbol(">");
+    f.render_stateful_widget(list, chunks[1], &mut app.queue_list_state);
 
-    // Current file progress
-    if let Some(job) = app.queue.jobs.get(app.queue.current_job_index) {
+    // Live speed/bitrate graphs for the active encode
+    if show_stats {
+        render_stats(f, app, chunks[2]);
+    }
+
+    // Progress of each running encode, or the current file's status
+    if encoding.len() > 1 {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(3); encoding.len()])
+            .split(chunks[3]);
+        for (&index, &area) in encoding.iter().zip(rows.iter()) {
+            render_job_gauge(f, app, index, area);
+        }
+    } else if let Some(job) = app.queue.jobs.get(app.queue.current_job_index) {
         match &job.status {
-            JobStatus::Encoding { progress } => {
-                let elapsed_str = app
-                    .queue
-                    .elapsed_time()
-                    .map(format_duration)
-                    .unwrap_or_else(|| "--:--".to_string());
-
-                let eta_str = app
-                    .queue
-                    .estimated_time_remaining()
-                    .map(format_duration)
-                    .unwrap_or_else(|| "--:--".to_string());
-
-                let crf_str = job.crf.map(|c| format!("  CRF: {}", c)).unwrap_or_default();
-
-                let label = format!(
-                    "{:.1}%  |  Elapsed: {}  |  ETA: {}{}",
-                    progress, elapsed_str, eta_str, crf_str
-                );
-
-                let gauge = Gauge::default()
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .border_style(Style::default().fg(Color::DarkGray))
-                            .title(format!(" {} ", job.filename())),
-                    )
-                    .gauge_style(Style::default().fg(Color::Cyan).bg(Color::DarkGray))
-                    .percent(*progress as u16)
-                    .label(label);
-                f.render_widget(gauge, chunks[2]);
+            JobStatus::Encoding { .. } => {
+                render_job_gauge(f, app, app.queue.current_job_index, chunks[3]);
             }
             _ => {
                 let status_text = match &job.status {
-                    JobStatus::Pending => "Waiting...",
-                    JobStatus::Done => "Complete!",
-                    JobStatus::Skipped { reason } => reason.as_str(),
-                    JobStatus::Error { message } => message.as_str(),
-                    _ => "",
+                    JobStatus::Pending => "Waiting...".to_string(),
+                    JobStatus::Done => "Complete!".to_string(),
+                    JobStatus::Skipped { reason, .. } => reason.clone(),
+                    JobStatus::Error { kind, .. } => kind.summary(),
+                    _ => String::new(),
                 };
                 let status = Paragraph::new(status_text)
                     .alignment(Alignment::Center)
@@ -135,28 +167,184 @@ pub fn render_queue(f: &mut Frame, app: &App) {
                             .border_style(Style::default().fg(Color::DarkGray))
                             .title(" Status "),
                     );
-                f.render_widget(status, chunks[2]);
+                f.render_widget(status, chunks[3]);
             }
         }
     }
 
     // Help
-    let help_text = if app.encoding_active {
-        Line::from(vec![
+    let mut help_spans = if app.encoding_active {
+        vec![
             Span::styled("Esc", Style::default().fg(Color::Yellow)),
-            Span::raw(" Cancel"),
-        ])
+            Span::raw(" Cancel all  "),
+            Span::styled("x", Style::default().fg(Color::Yellow)),
+            Span::raw(" Cancel current  "),
+            Span::styled("↑↓", Style::default().fg(Color::Yellow)),
+            Span::raw(" Select  "),
+            Span::styled("s", Style::default().fg(Color::Yellow)),
+            Span::raw(" Skip selected  "),
+            Span::styled("p", Style::default().fg(Color::Yellow)),
+            Span::raw(if app.queue.is_paused() {
+                " Resume  "
+            } else {
+                " Pause  "
+            }),
+            Span::styled("+/-", Style::default().fg(Color::Yellow)),
+            Span::raw(format!(
+                " VMAF ≥ {:.0}  ",
+                app.config.quality.vmaf_threshold
+            )),
+            Span::styled("d", Style::default().fg(Color::Yellow)),
+            Span::raw(if app.config.quality.delete_source {
+                " Delete source: on"
+            } else {
+                " Delete source: off"
+            }),
+        ]
     } else {
-        Line::from(vec![
+        vec![
             Span::styled("Enter", Style::default().fg(Color::Yellow)),
             Span::raw(" Continue"),
-        ])
+        ]
     };
+    if app.queue.configuring() {
+        help_spans.push(Span::raw("  "));
+        help_spans.push(Span::styled("t", Style::default().fg(Color::Yellow)));
+        help_spans.push(Span::raw(" Configure tracks"));
+    }
+    if app.last_finished_output().is_some() {
+        help_spans.push(Span::raw("  "));
+        help_spans.push(Span::styled("o", Style::default().fg(Color::Yellow)));
+        help_spans.push(Span::raw(" Play last result"));
+    }
+    let mut help_text = vec![Line::from(help_spans)];
+    if let Some(ref msg) = app.message {
+        help_text.push(Line::from(Span::styled(
+            msg.clone(),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
 
     let help = Paragraph::new(help_text)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::NONE));
-    f.render_widget(help, chunks[3]);
+    f.render_widget(help, chunks[4]);
+}
+
+/// Progress gauge of the encoding job at `index`
+fn render_job_gauge(f: &mut Frame, app: &App, index: usize, area: Rect) {
+    let job = &app.queue.jobs[index];
+    let progress = app.queue.job_progress(job);
+    let elapsed_str = app
+        .queue
+        .elapsed_time()
+        .map(format_duration)
+        .unwrap_or_else(|| "--:--".to_string());
+
+    let eta_str = app
+        .queue
+        .estimated_time_remaining()
+        .map(format_duration)
+        .unwrap_or_else(|| "--:--".to_string());
+    let job_eta_str = app
+        .queue
+        .job_time_remaining(job)
+        .map(format_duration)
+        .unwrap_or_else(|| "--:--".to_string());
+
+    let crf_str = job.crf.map(|c| format!("  CRF: {}", c)).unwrap_or_default();
+
+    let label = format!(
+        "{}%  |  Elapsed: {}  |  ETA: file {} · queue {}{}",
+        format_decimal(progress as f64, 1),
+        elapsed_str,
+        job_eta_str,
+        eta_str,
+        crf_str
+    );
+
+    let gauge = Gauge::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(format!(" {} ", job.display_name())),
+        )
+        .gauge_style(
+            Style::default()
+                .fg(if app.queue.is_paused() {
+                    Color::Yellow
+                } else {
+                    Color::Cyan
+                })
+                .bg(Color::DarkGray),
+        )
+        .percent(progress as u16)
+        .label(label);
+    f.render_widget(gauge, area);
+}
+
+fn render_stats(f: &mut Frame, app: &App, area: Rect) {
+    let stats = &app.queue.stats;
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    // Encode speed, plus how much of the source it gets through per second
+    let source_rate = |speed: f64| {
+        app.queue
+            .jobs
+            .get(app.queue.current_job_index)
+            .and_then(|j| j.source_rate(speed))
+            .map(|rate| format!(" · {}", format_speed(rate)))
+            .unwrap_or_default()
+    };
+    let speed_title = stats
+        .latest
+        .speed
+        .map(|s| {
+            format!(
+                " Speed {}x{} ",
+                format_decimal(s as f64, 2),
+                source_rate(s as f64)
+            )
+        })
+        .unwrap_or_else(|| " Speed -- ".to_string());
+    let bitrate_title = stats
+        .latest
+        .bitrate_kbps
+        .map(|b| format!(" Bitrate {:.0} kb/s ", b))
+        .unwrap_or_else(|| " Bitrate -- ".to_string());
+
+    for (area, samples, title, color) in [
+        (halves[0], &stats.speed, speed_title, Color::Green),
+        (
+            halves[1],
+            &stats.bitrate_kbps,
+            bitrate_title,
+            Color::Magenta,
+        ),
+    ] {
+        // Only the most recent samples that fit in the graph
+        let width = area.width.saturating_sub(2) as usize;
+        let data: Vec<u64> = samples
+            .iter()
+            .skip(samples.len().saturating_sub(width))
+            .copied()
+            .collect();
+
+        let sparkline = Sparkline::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::DarkGray))
+                    .title(title),
+            )
+            .data(&data)
+            .style(Style::default().fg(color));
+        f.render_widget(sparkline, area);
+    }
 }
 
 fn create_queue_item(
@@ -201,9 +389,9 @@ fn create_queue_item(
                 ),
             ]))
         }
-        JobStatus::Skipped { reason } => ListItem::new(format!("  ⊘ {} ({})", name, reason))
+        JobStatus::Skipped { reason, .. } => ListItem::new(format!("  ⊘ {} ({})", name, reason))
             .style(Style::default().fg(Color::Yellow).add_modifier(bold_mod)),
-        JobStatus::Error { message } => ListItem::new(format!("  ✗ {} Error: {}", name, message))
+        JobStatus::Error { kind, .. } => ListItem::new(format!("  ✗ {} {}", name, kind.summary()))
             .style(Style::default().fg(Color::Red).add_modifier(bold_mod)),
         JobStatus::QualityWarning { vmaf, threshold } => {
             let vmaf_color = get_vmaf_color(*vmaf);
@@ -1,40 +1,79 @@
+use super::common::centered_rect_min;
 use crate::app::{App, TrackFocus};
+use crate::config::{AudioBitrates, Encoder, LossyAudio};
+use crate::encoder::JobMode;
+use crate::queue::{EncodingJob, JobDifference};
+use crate::tracks::{AudioTrack, TrackDisposition, commentary_tracks};
+use crate::utils::format_file_size;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
 };
 
 pub fn render_track_config(f: &mut Frame, app: &mut App) {
-    let (filename, resolution_string, hdr_string, audio_data, subtitle_data) = {
+    let (
+        filename,
+        resolution_string,
+        hdr_string,
+        audio_data,
+        subtitle_data,
+        still_image,
+        dark_scenes,
+        adaptive_grain,
+        source_grain,
+        banding,
+        extra_args,
+        profiles,
+        details,
+        dropped,
+        has_commentary,
+        changes,
+        mode,
+    ) = {
         let job = match app.current_config_job() {
             Some(j) => j,
-            None => return,
+            None => {
+                render_waiting(f, app);
+                return;
+            }
         };
 
-        let audio_data: Vec<(String, String, String, bool)> = job
+        let duration = job.metadata.as_ref().map_or(0.0, |m| m.duration_secs);
+        let commentary = commentary_tracks(&job.audio_tracks);
+        let audio_data: Vec<AudioRow> = job
             .audio_tracks
             .iter()
             .map(|track| {
+                let mut tags = String::new();
+                if commentary.contains(&track.index) {
+                    tags.push_str(" [Commentary]");
+                }
+                if lossy_reencode(job, track, &app.config.tracks.audio_bitrates).is_some() {
+                    tags.push_str(" ⚠ lossy");
+                }
                 (
                     track.display_name(),
                     track.bitrate_string(),
                     track.sample_rate_string(),
-                    job.track_selection.audio_indices.contains(&track.index),
+                    job.track_selection.audio_position(track.index),
+                    track.estimated_size(duration),
+                    tags,
                 )
             })
             .collect();
+        let has_commentary = !commentary.is_empty();
 
-        let subtitle_data: Vec<(String, bool, bool)> = job
+        let subtitle_data: Vec<(String, bool, Option<u64>)> = job
             .subtitle_tracks
             .iter()
             .map(|track| {
                 (
                     track.display_name(),
-                    track.forced,
                     job.track_selection.subtitle_indices.contains(&track.index),
+                    track.estimated_size(duration),
                 )
             })
             .collect();
@@ -45,21 +84,48 @@ pub fn render_track_config(f: &mut Frame, app: &mut App) {
             job.hdr_string().to_string(),
             audio_data,
             subtitle_data,
+            job.still_image.clone(),
+            job.dark_scenes.clone(),
+            job.adaptive_grain.clone(),
+            job.source_grain.is_some() && !job.mode.copies_video(),
+            job.banding_prone(&app.config),
+            job.extra_args.clone(),
+            job.profiles.clone(),
+            track_details(app, job),
+            job.dropped_track_savings(),
+            has_commentary,
+            app.queue
+                .previous_config_job
+                .as_ref()
+                .map(|previous| job.differences_from(previous)),
+            job.can_copy_video().then_some(job.mode),
         )
     };
 
+    let editing = app.extra_args_input.clone();
+    let info_height = (5
+        + u16::from(still_image.is_some())
+        + u16::from(dark_scenes.is_some())
+        + u16::from(adaptive_grain.is_some())
+        + u16::from(source_grain)
+        + u16::from(mode.is_some())
+        + u16::from(changes.is_some())
+        + u16::from(editing.is_some() || !extra_args.is_empty()))
+    .max(5);
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(5),
+            Constraint::Length(info_height),
             Constraint::Min(5),
             Constraint::Length(3),
+            Constraint::Length(3),
         ])
         .margin(1)
         .split(f.area());
 
     // File info header
-    let info_lines = vec![
+    let mut info_lines = vec![
         Line::from(vec![
             Span::styled("File: ", Style::default().fg(Color::DarkGray)),
             Span::styled(
@@ -86,14 +152,137 @@ pub fn render_track_config(f: &mut Frame, app: &mut App) {
                     _ => Color::White,
                 }),
             ),
+            Span::styled(
+                match (banding, app.config.quality.deband) {
+                    (false, _) => "",
+                    (true, true) => "  8-bit, debanded",
+                    (true, false) => "  8-bit, may band (set deband = true)",
+                },
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Profile: ", Style::default().fg(Color::DarkGray)),
+            match profiles.as_slice() {
+                [] => Span::styled(
+                    "none (p to pick, 1-9 to add outputs)",
+                    Style::default().fg(Color::DarkGray),
+                ),
+                [profile] => Span::styled(profile.summary(), Style::default().fg(Color::Cyan)),
+                profiles => Span::styled(
+                    format!(
+                        "{} outputs: {}",
+                        profiles.len(),
+                        profiles
+                            .iter()
+                            .map(|p| p.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                    Style::default().fg(Color::Cyan),
+                ),
+            },
         ]),
     ];
 
+    if let Some(ref hint) = still_image {
+        let (status, status_color) = if hint.applied {
+            ("applied", Color::Green)
+        } else {
+            ("press m to apply", Color::DarkGray)
+        };
+        info_lines.push(Line::from(vec![
+            Span::styled("Low motion: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!(
+                    "{:.0}% static — suggest CRF {} at {}",
+                    hint.static_ratio * 100.0,
+                    hint.suggested_crf,
+                    hint.fps_string()
+                ),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(format!("  ({})", status), Style::default().fg(status_color)),
+        ]));
+    }
+
+    if let Some(ref hint) = dark_scenes {
+        info_lines.push(Line::from(vec![
+            Span::styled("Dark scenes: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!(
+                    "{:.0}% — quality raised by {} with stronger AQ in shadows",
+                    hint.dark_ratio * 100.0,
+                    hint.crf_offset
+                ),
+                Style::default().fg(Color::Yellow),
+            ),
+        ]));
+    }
+
+    if let Some(ref hint) = adaptive_grain {
+        info_lines.push(Line::from(vec![
+            Span::styled("Film grain: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                format!(
+                    "{} for a grain score of {:.2} (preset {})",
+                    hint.film_grain, hint.score, hint.preset_grain
+                ),
+                Style::default().fg(Color::Cyan),
+            ),
+        ]));
+    }
+
+    if source_grain {
+        let note = if app.config.encoder == Encoder::SvtAv1 {
+            "reused from the AV1 source"
+        } else {
+            "decoded into the picture (passthrough needs SVT-AV1)"
+        };
+        info_lines.push(Line::from(vec![
+            Span::styled("Film grain: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(note, Style::default().fg(Color::Cyan)),
+        ]));
+    }
+
+    if let Some(mode) = mode {
+        let note = match mode {
+            JobMode::Encode => "the AV1 video is encoded again",
+            JobMode::Remux => "video and audio copied, only the selected tracks kept",
+            JobMode::AudioOnly => "video copied, selected audio re-encoded",
+        };
+        info_lines.push(Line::from(vec![
+            Span::styled("Mode: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(mode.display_name(), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!(" — {} (r to change)", note),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+
+    if let Some(ref changes) = changes {
+        info_lines.push(changes_line(changes));
+    }
+
+    if let Some(ref input) = editing {
+        info_lines.push(Line::from(vec![
+            Span::styled("FFmpeg args: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("{}_", input), Style::default().fg(Color::Yellow)),
+        ]));
+    } else if !extra_args.is_empty() {
+        info_lines.push(Line::from(vec![
+            Span::styled("FFmpeg args: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(extra_args, Style::default().fg(Color::White)),
+        ]));
+    }
+
     let info = Paragraph::new(info_lines).block(
         Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::DarkGray))
-            .title(" Video Info "),
+            .title(" Video Info ")
+            .title_top(Line::from(queue_status(app)).right_aligned()),
     );
     f.render_widget(info, chunks[0]);
 
@@ -107,9 +296,17 @@ pub fn render_track_config(f: &mut Frame, app: &mut App) {
     let audio_items: Vec<ListItem> = audio_data
         .iter()
         .enumerate()
-        .map(|(i, (name, bitrate, sample_rate, selected))| {
+        .map(|(i, (name, bitrate, sample_rate, selected, size, tags))| {
             let is_cursor = app.track_focus == TrackFocus::Audio && i == app.audio_cursor;
-            create_audio_track_item(name, bitrate, sample_rate, *selected, is_cursor)
+            create_audio_track_item(
+                name,
+                bitrate,
+                sample_rate,
+                *selected,
+                *size,
+                tags,
+                is_cursor,
+            )
         })
         .collect();
 
@@ -124,7 +321,8 @@ pub fn render_track_config(f: &mut Frame, app: &mut App) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(audio_border_color))
-                .title(" Audio Tracks [Space to toggle] "),
+                .title(" Audio Tracks [Space to toggle] ")
+                .title_top(savings_title(dropped.0)),
         )
         .highlight_style(Style::default());
 
@@ -135,9 +333,9 @@ pub fn render_track_config(f: &mut Frame, app: &mut App) {
     let subtitle_items: Vec<ListItem> = subtitle_data
         .iter()
         .enumerate()
-        .map(|(i, (name, forced, selected))| {
+        .map(|(i, (name, selected, size))| {
             let is_cursor = app.track_focus == TrackFocus::Subtitle && i == app.subtitle_cursor;
-            create_subtitle_track_item(name, *forced, *selected, is_cursor)
+            create_subtitle_track_item(name, *selected, *size, is_cursor)
         })
         .collect();
 
@@ -152,13 +350,23 @@ pub fn render_track_config(f: &mut Frame, app: &mut App) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(Style::default().fg(subtitle_border_color))
-                .title(" Subtitle Tracks [Space to toggle] "),
+                .title(" Subtitle Tracks [Space to toggle] ")
+                .title_top(savings_title(dropped.1)),
         )
         .highlight_style(Style::default());
 
     app.subtitle_list_state.select(Some(app.subtitle_cursor));
     f.render_stateful_widget(subtitle_list, track_chunks[1], &mut app.subtitle_list_state);
 
+    // Highlighted track
+    let details = Paragraph::new(details).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(" Track Details "),
+    );
+    f.render_widget(details, chunks[2]);
+
     // Help / Confirm button
     let confirm_style = if app.track_focus == TrackFocus::Confirm {
         Style::default()
@@ -169,38 +377,328 @@ pub fn render_track_config(f: &mut Frame, app: &mut App) {
         Style::default().fg(Color::Cyan)
     };
 
-    let help_text = Line::from(vec![
-        Span::styled("Tab", Style::default().fg(Color::Yellow)),
-        Span::raw(" Switch panel  "),
-        Span::styled("↑↓", Style::default().fg(Color::Yellow)),
-        Span::raw(" Navigate  "),
-        Span::styled("Space", Style::default().fg(Color::Yellow)),
-        Span::raw(" Toggle  "),
-        Span::styled("a", Style::default().fg(Color::Yellow)),
-        Span::raw(" All audio  "),
-        Span::styled("s", Style::default().fg(Color::Yellow)),
-        Span::raw(" All subs  "),
-        Span::styled(" [", Style::default().fg(Color::DarkGray)),
-        Span::styled(" Continue ", confirm_style),
-        Span::styled("]", Style::default().fg(Color::DarkGray)),
-    ]);
+    let mut help_spans = if editing.is_some() {
+        vec![
+            Span::styled("Enter", Style::default().fg(Color::Yellow)),
+            Span::raw(" Apply  "),
+            Span::styled("Esc", Style::default().fg(Color::Yellow)),
+            Span::raw(" Cancel  "),
+            Span::styled("NAME=value", Style::default().fg(Color::Yellow)),
+            Span::raw(" prefix sets env vars"),
+        ]
+    } else {
+        vec![
+            Span::styled("Tab", Style::default().fg(Color::Yellow)),
+            Span::raw(" Switch panel  "),
+            Span::styled("↑↓", Style::default().fg(Color::Yellow)),
+            Span::raw(" Navigate  "),
+            Span::styled("Space", Style::default().fg(Color::Yellow)),
+            Span::raw(" Toggle  "),
+            Span::styled("K/J", Style::default().fg(Color::Yellow)),
+            Span::raw(" Reorder audio  "),
+            Span::styled("a", Style::default().fg(Color::Yellow)),
+            Span::raw(" All audio  "),
+            Span::styled("s", Style::default().fg(Color::Yellow)),
+            Span::raw(" All subs  "),
+            Span::styled("e", Style::default().fg(Color::Yellow)),
+            Span::raw(" FFmpeg args  "),
+            Span::styled("d", Style::default().fg(Color::Yellow)),
+            Span::raw(" Show command  "),
+            Span::styled("p", Style::default().fg(Color::Yellow)),
+            Span::raw(" Profile  "),
+            Span::styled("1-9", Style::default().fg(Color::Yellow)),
+            Span::raw(" Outputs  "),
+        ]
+    };
+    if editing.is_none() && app.encoding_active {
+        help_spans.push(Span::styled("v", Style::default().fg(Color::Yellow)));
+        help_spans.push(Span::raw(" View queue  "));
+    }
+    if editing.is_none() {
+        if still_image.is_some() {
+            help_spans.push(Span::styled("m", Style::default().fg(Color::Yellow)));
+            help_spans.push(Span::raw(" Low-motion preset  "));
+        }
+        if has_commentary {
+            help_spans.push(Span::styled("c", Style::default().fg(Color::Yellow)));
+            help_spans.push(Span::raw(" Commentary  "));
+        }
+        if mode.is_some() {
+            help_spans.push(Span::styled("r", Style::default().fg(Color::Yellow)));
+            help_spans.push(Span::raw(" Mode  "));
+        }
+        help_spans.extend([
+            Span::styled(" [", Style::default().fg(Color::DarkGray)),
+            Span::styled(" Continue ", confirm_style),
+            Span::styled("]", Style::default().fg(Color::DarkGray)),
+        ]);
+    }
+    let help_text = Line::from(help_spans);
 
     let help = Paragraph::new(help_text)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::NONE));
-    f.render_widget(help, chunks[2]);
+    f.render_widget(help, chunks[3]);
+
+    if let Some(command) = &app.command_preview {
+        render_command_preview(f, command);
+    }
+}
+
+/// The FFmpeg command of the job, over the track lists
+fn render_command_preview(f: &mut Frame, command: &str) {
+    let area = centered_rect_min(80, 50, 50, 8, f.area());
+    f.render_widget(Clear, area);
+    let preview = Paragraph::new(vec![
+        Line::from(Span::styled(
+            command.to_string(),
+            Style::default().fg(Color::White),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("y", Style::default().fg(Color::Yellow)),
+            Span::raw(" Copy  "),
+            Span::styled("any key", Style::default().fg(Color::Yellow)),
+            Span::raw(" Close"),
+        ]),
+    ])
+    .wrap(Wrap { trim: false })
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(" FFmpeg command (not run) "),
+    );
+    f.render_widget(preview, area);
+}
+
+/// Name, bitrate, sample rate, output position, estimated size and
+/// commentary and lossy tags of an audio track
+type AudioRow = (String, String, String, Option<usize>, Option<u64>, String);
+
+/// Audio bitrate of the first profile whose re-encode would barely shrink `track`
+fn lossy_reencode(job: &EncodingJob, track: &AudioTrack, bitrates: &AudioBitrates) -> Option<u32> {
+    job.profiles
+        .iter()
+        .filter(|p| p.audio_channels.is_some())
+        .map(|p| p.audio_bitrate(track.channels, bitrates))
+        .find(|&kbps| track.reencode_pointless(kbps))
+}
+
+/// Codec, layout, bitrate and estimated size of the highlighted track
+fn track_details(app: &App, job: &EncodingJob) -> Line<'static> {
+    let label = |text: &str| Span::styled(text.to_string(), Style::default().fg(Color::DarkGray));
+    let value = |text: String| Span::styled(text, Style::default().fg(Color::White));
+    let duration = job.metadata.as_ref().map_or(0.0, |m| m.duration_secs);
+
+    let (mut spans, size) = match app.track_focus {
+        TrackFocus::Audio | TrackFocus::Confirm => {
+            let Some(track) = job.audio_tracks.get(app.audio_cursor) else {
+                return Line::from(label("No audio tracks"));
+            };
+            (
+                vec![
+                    label("Codec: "),
+                    value(track.codec.to_uppercase()),
+                    label("  Channels: "),
+                    value(format!("{} ({})", track.channel_layout(), track.channels)),
+                    label("  Bitrate: "),
+                    value(track.bitrate_string()),
+                    label("  Sample rate: "),
+                    value(track.sample_rate_string()),
+                ]
+                .into_iter()
+                .chain(flag_spans(&track.disposition))
+                .chain(
+                    job.audio_tracks
+                        .first()
+                        .and_then(|main| track.commentary_reason(main))
+                        .map(|reason| {
+                            Span::styled(
+                                format!("  Commentary: {}", reason),
+                                Style::default().fg(Color::Yellow),
+                            )
+                        }),
+                )
+                .chain(
+                    lossy_reencode(job, track, &app.config.tracks.audio_bitrates).map(|kbps| {
+                        let action = match app.config.tracks.lossy_audio {
+                            LossyAudio::Copy => "copied if it fits the profile",
+                            LossyAudio::Warn => "re-encoding saves little",
+                        };
+                        Span::styled(
+                            format!("  ⚠ already lossy, re-encode at {}k: {}", kbps, action),
+                            Style::default().fg(Color::Yellow),
+                        )
+                    }),
+                )
+                .collect::<Vec<_>>(),
+                track.estimated_size(duration),
+            )
+        }
+        TrackFocus::Subtitle => {
+            let Some(track) = job.subtitle_tracks.get(app.subtitle_cursor) else {
+                return Line::from(label("No subtitle tracks"));
+            };
+            (
+                vec![
+                    label("Codec: "),
+                    value(track.codec.to_uppercase()),
+                    label("  Language: "),
+                    value(
+                        track
+                            .language
+                            .clone()
+                            .unwrap_or_else(|| "Unknown".to_string()),
+                    ),
+                ]
+                .into_iter()
+                .chain(flag_spans(&track.disposition))
+                .collect(),
+                track.estimated_size(duration),
+            )
+        }
+    };
+
+    spans.push(label("  Size: "));
+    spans.push(value(match size {
+        Some(size) => match job.source_size.filter(|&s| s > 0) {
+            Some(source) => format!(
+                "~{} ({:.1}% of the file)",
+                format_file_size(size),
+                size as f64 / source as f64 * 100.0
+            ),
+            None => format!("~{}", format_file_size(size)),
+        },
+        None => "unknown".to_string(),
+    }));
+    Line::from(spans)
+}
+
+/// What differs from the file configured before, or that nothing does
+fn changes_line(changes: &[JobDifference]) -> Line<'static> {
+    let label = Span::styled("Vs previous: ", Style::default().fg(Color::DarkGray));
+    if changes.is_empty() {
+        return Line::from(vec![
+            label,
+            Span::styled(
+                "same format and tracks",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+    }
+    let mut spans = vec![label];
+    for (i, change) in changes.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(", ", Style::default().fg(Color::DarkGray)));
+        }
+        let color = if change.is_added() {
+            Color::Green
+        } else if change.is_missing() {
+            Color::Red
+        } else {
+            Color::Yellow
+        };
+        spans.push(Span::styled(
+            change.describe(),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Disposition flags of a track, e.g. `  Flags: default, forced`
+fn flag_spans(disposition: &TrackDisposition) -> Vec<Span<'static>> {
+    let names = disposition.names();
+    if names.is_empty() {
+        return Vec::new();
+    }
+    vec![
+        Span::styled("  Flags: ", Style::default().fg(Color::DarkGray)),
+        Span::styled(names.join(", "), Style::default().fg(Color::White)),
+    ]
+}
+
+/// Files being encoded while the rest are configured, e.g. ` Encoding 2/5 · 34% `
+fn queue_status(app: &App) -> Vec<Span<'static>> {
+    if !app.encoding_active {
+        return Vec::new();
+    }
+    let queue = &app.queue;
+    vec![Span::styled(
+        format!(
+            " Encoding {}/{} · {:.0}% ",
+            (queue.encoding_progress_done + 1).min(queue.total_jobs_to_encode),
+            queue.total_jobs_to_encode,
+            queue.overall_progress()
+        ),
+        Style::default().fg(Color::Green),
+    )]
+}
+
+/// Shown while the next file to configure is still being analyzed
+fn render_waiting(f: &mut Frame, app: &App) {
+    let analyzing = app.files_analyzing();
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!(
+                "Analyzing {} file{}...",
+                analyzing,
+                if analyzing == 1 { "" } else { "s" }
+            ),
+            Style::default().fg(Color::Cyan),
+        )),
+    ];
+    if app.encoding_active {
+        lines.push(Line::from(Span::styled(
+            "Configured files are already encoding (v to view the queue)",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::raw(if app.encoding_active {
+            " Cancel all"
+        } else {
+            " Back"
+        }),
+    ]));
+
+    let waiting = Paragraph::new(lines).alignment(Alignment::Center).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(" Track Configuration ")
+            .title_top(Line::from(queue_status(app)).right_aligned()),
+    );
+    f.render_widget(waiting, f.area());
 }
 
 fn create_audio_track_item(
     name: &str,
     bitrate: &str,
     sample_rate: &str,
-    selected: bool,
+    position: Option<usize>,
+    size: Option<u64>,
+    tags: &str,
     is_cursor: bool,
 ) -> ListItem<'static> {
-    let checkbox = if selected { "[x]" } else { "[ ]" };
+    let selected = position.is_some();
+    // Selected tracks show their place in the output
+    let checkbox = match position {
+        Some(pos) => format!("[{}]", pos + 1),
+        None => "[ ]".to_string(),
+    };
     let prefix = if is_cursor { "> " } else { "  " };
-    let extra = format!(" ({}, {})", bitrate, sample_rate);
+    let extra = format!(
+        " ({}, {}){}{}",
+        bitrate,
+        sample_rate,
+        tags,
+        dropped_note(selected, size)
+    );
 
     let style = if is_cursor {
         Style::default().add_modifier(Modifier::BOLD)
@@ -215,13 +713,12 @@ fn create_audio_track_item(
 
 fn create_subtitle_track_item(
     name: &str,
-    forced: bool,
     selected: bool,
+    size: Option<u64>,
     is_cursor: bool,
 ) -> ListItem<'static> {
     let checkbox = if selected { "[x]" } else { "[ ]" };
     let prefix = if is_cursor { "> " } else { "  " };
-    let forced_str = if forced { " [Forced]" } else { "" };
 
     let style = if is_cursor {
         Style::default().add_modifier(Modifier::BOLD)
@@ -231,5 +728,32 @@ fn create_subtitle_track_item(
         Style::default().fg(Color::DarkGray)
     };
 
-    ListItem::new(format!("{}{} {}{}", prefix, checkbox, name, forced_str)).style(style)
+    ListItem::new(format!(
+        "{}{} {}{}",
+        prefix,
+        checkbox,
+        name,
+        dropped_note(selected, size)
+    ))
+    .style(style)
+}
+
+/// Space an unselected track saves, e.g. ` −120 MB`
+fn dropped_note(selected: bool, size: Option<u64>) -> String {
+    match size {
+        Some(size) if !selected => format!(" −{}", format_file_size(size)),
+        _ => String::new(),
+    }
+}
+
+/// Right-aligned total saved by the dropped tracks of a list
+fn savings_title(saved: u64) -> Line<'static> {
+    if saved == 0 {
+        return Line::default();
+    }
+    Line::from(Span::styled(
+        format!(" Dropping saves ~{} ", format_file_size(saved)),
+        Style::default().fg(Color::Green),
+    ))
+    .right_aligned()
 }
@@ -0,0 +1,141 @@
+use super::common::get_vmaf_color;
+use crate::queue::publish::LiveQueue;
+use crate::utils::{format_decimal, format_duration};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Gauge, List, ListItem, Paragraph},
+};
+use std::time::Duration;
+
+/// Draw the queue another instance publishes, read-only
+///
+/// `live` is the last state read; `running` tells whether its process still
+/// runs, so a finished or crashed instance is shown as such.
+pub fn render_viewer(f: &mut Frame, live: Option<&LiveQueue>, running: bool) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .margin(1)
+        .split(f.area());
+
+    let title_text = match live {
+        None => "Waiting for the running instance to publish its queue".to_string(),
+        Some(live) if !running => format!("Instance {} has exited", live.pid),
+        Some(live) => {
+            let queue = &live.queue;
+            let mut title = format!(
+                "Attached to instance {} · {} converted · {} skipped · {} errors",
+                live.pid,
+                queue.converted,
+                queue.skipped + queue.user_skipped + queue.cancelled,
+                queue.errors
+            );
+            if let Some(ref window) = queue.waiting_for_schedule {
+                title = format!("Waiting for schedule ({}) · {}", window, title);
+            }
+            if queue.paused {
+                title = format!("⏸ Paused · {}", title);
+            }
+            title
+        }
+    };
+    let title = Paragraph::new(title_text)
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(" Read-only "),
+        );
+    f.render_widget(title, chunks[0]);
+
+    let items: Vec<ListItem> = live
+        .map(|live| live.queue.jobs.iter().map(job_item).collect())
+        .unwrap_or_default();
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(" Files "),
+    );
+    f.render_widget(list, chunks[1]);
+
+    if let Some(live) = live {
+        let eta = live
+            .queue
+            .eta_secs
+            .map(|s| format_duration(Duration::from_secs(s)))
+            .unwrap_or_else(|| "--:--".to_string());
+        let gauge = Gauge::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::DarkGray))
+                    .title(" Queue "),
+            )
+            .gauge_style(Style::default().fg(Color::Cyan).bg(Color::DarkGray))
+            .percent(live.queue.progress.clamp(0.0, 100.0) as u16)
+            .label(format!(
+                "{}%  |  ETA: {}",
+                format_decimal(live.queue.progress as f64, 1),
+                eta
+            ));
+        f.render_widget(gauge, chunks[2]);
+    }
+
+    let help = Paragraph::new(Line::from(vec![
+        Span::styled("q", Style::default().fg(Color::Yellow)),
+        Span::raw(" Detach"),
+    ]))
+    .alignment(Alignment::Center);
+    f.render_widget(help, chunks[3]);
+}
+
+fn job_item(job: &crate::queue::JobSnapshot) -> ListItem<'static> {
+    let (symbol, color) = match job.status.as_str() {
+        "pending" => ("○", Color::DarkGray),
+        "analyzing" => ("◐", Color::Yellow),
+        "awaiting_config" | "ready" => ("●", Color::Blue),
+        "encoding" => ("▶", Color::Cyan),
+        "done" => ("✓", Color::Green),
+        "skipped" => ("⊘", Color::Yellow),
+        "quality_warning" => ("⚠", Color::Yellow),
+        _ => ("✗", Color::Red),
+    };
+    let mut spans = vec![Span::styled(
+        format!("  {} {}", symbol, job.file),
+        Style::default().fg(color),
+    )];
+    if let Some(progress) = job.progress {
+        spans.push(Span::styled(
+            format!(" {:.1}%", progress),
+            Style::default().fg(color),
+        ));
+    }
+    if let Some(vmaf) = job.vmaf {
+        spans.push(Span::styled(
+            format!(" VMAF: {:.1}", vmaf),
+            Style::default().fg(get_vmaf_color(vmaf)),
+        ));
+    }
+    if let Some(ref message) = job.message {
+        spans.push(Span::styled(
+            format!(" ({})", message),
+            Style::default().fg(color),
+        ));
+    }
+    ListItem::new(Line::from(spans))
+}
@@ -0,0 +1,236 @@
+//! Claim files that let several machines work through one shared folder.
+//!
+//! Before a source is encoded, a `<name>.av1claim` file is created next to
+//! it. Creating it fails if it already exists, so of two instances reaching
+//! the same file only one gets it; the other skips the file. The claim names
+//! the host and process holding it and is rewritten every minute while the
+//! encode runs. A claim whose process is gone (on this host) or that has not
+//! been rewritten for the configured time (on any host) is stale and taken
+//! over, e.g. after a crash or a machine losing power.
+
+use super::lock::process_running;
+use crate::error::AppError;
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Extension added to the source name for its claim file
+pub const CLAIM_EXTENSION: &str = "av1claim";
+
+/// Time between rewrites of a held claim
+const HEARTBEAT: Duration = Duration::from_secs(60);
+
+/// Claim file of `source`, e.g. `movie.mkv.av1claim`
+pub fn claim_path(source: &Path) -> PathBuf {
+    let mut name = source.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(CLAIM_EXTENSION);
+    source.with_file_name(name)
+}
+
+/// Name of this machine, as written into claims
+pub fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            std::process::Command::new("hostname")
+                .output()
+                .ok()
+                .map(|out| String::from_utf8_lossy(&out.stdout).into_owned())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown-host".to_string())
+}
+
+/// Instance named in a claim file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClaimHolder {
+    pub host: String,
+    pub pid: u32,
+    /// Unique per claim, so a claim taken over is never removed by its old owner
+    pub token: String,
+}
+
+impl ClaimHolder {
+    fn current() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+        let host = host_name();
+        let pid = std::process::id();
+        Self {
+            token: format!("{}-{}-{}", host, pid, nanos),
+            host,
+            pid,
+        }
+    }
+
+    fn parse(content: &str) -> Option<Self> {
+        let mut lines = content.lines();
+        Some(Self {
+            host: lines.next()?.to_string(),
+            pid: lines.next()?.parse().ok()?,
+            token: lines.next()?.to_string(),
+        })
+    }
+
+    fn contents(&self) -> String {
+        format!("{}\n{}\n{}\n", self.host, self.pid, self.token)
+    }
+
+    /// e.g. `nas-box (PID 4242)`
+    pub fn describe(&self) -> String {
+        format!("{} (PID {})", self.host, self.pid)
+    }
+}
+
+/// Instance holding the claim on `source`, if any
+pub fn claim_holder(source: &Path) -> Option<ClaimHolder> {
+    ClaimHolder::parse(&std::fs::read_to_string(claim_path(source)).ok()?)
+}
+
+/// Whether `source` is claimed by an instance that may still be running
+///
+/// Claims of other hosts count regardless of their age, as do claims that
+/// can't be read yet.
+pub fn claim_active(source: &Path) -> bool {
+    let path = claim_path(source);
+    match claim_holder(source) {
+        Some(holder) => holder.host != host_name() || process_running(holder.pid),
+        None => path.exists(),
+    }
+}
+
+/// Claim on a source held by this process, released when dropped
+#[derive(Debug)]
+pub struct SourceClaim {
+    path: PathBuf,
+    holder: ClaimHolder,
+}
+
+impl SourceClaim {
+    /// Claim `source`, taking over a claim untouched for `stale_after`
+    ///
+    /// Fails with `AppError::Claimed` while another live instance holds it.
+    pub fn acquire(source: &Path, stale_after: Duration) -> Result<Self, AppError> {
+        let path = claim_path(source);
+        let io_error = |e: std::io::Error| AppError::Io {
+            path: path.clone(),
+            operation: "write claim file",
+            message: e.to_string(),
+        };
+        let holder = ClaimHolder::current();
+
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(holder.contents().as_bytes())
+                        .map_err(io_error)?;
+                    return Ok(Self { path, holder });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(io_error(e)),
+            }
+
+            // Unreadable while its owner is still writing it, so only its age counts
+            let other = claim_holder(source);
+            if !is_stale(&path, other.as_ref(), stale_after) {
+                return Err(AppError::Claimed {
+                    path,
+                    holder: describe(other.as_ref()),
+                });
+            }
+
+            // Moving it aside succeeds for one instance only
+            let aside = path.with_extension(format!("{}.{}", CLAIM_EXTENSION, holder.token));
+            if std::fs::rename(&path, &aside).is_ok() {
+                // Another instance may have replaced it with a live claim since
+                // it was read; that one goes back in place
+                let moved =
+                    ClaimHolder::parse(&std::fs::read_to_string(&aside).unwrap_or_default());
+                if moved != other {
+                    let _ = std::fs::rename(&aside, &path);
+                    return Err(AppError::Claimed {
+                        path,
+                        holder: describe(moved.as_ref()),
+                    });
+                }
+                info!(
+                    "Taking over stale claim of {} from {}",
+                    source.display(),
+                    describe(other.as_ref())
+                );
+                let _ = std::fs::remove_file(&aside);
+            }
+        }
+        Err(AppError::Claimed {
+            holder: describe(claim_holder(source).as_ref()),
+            path,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rewrite the claim so other instances see it is still alive
+    pub fn refresh(&self) {
+        if !self.still_ours() {
+            warn!("Claim {} was taken over", self.path.display());
+            return;
+        }
+        if let Err(e) = std::fs::write(&self.path, self.holder.contents()) {
+            warn!("Failed to refresh claim {}: {}", self.path.display(), e);
+        }
+    }
+
+    /// Run `work`, refreshing the claim until it completes
+    pub async fn hold_while<F: Future>(&self, work: F) -> F::Output {
+        tokio::pin!(work);
+        loop {
+            tokio::select! {
+                output = &mut work => return output,
+                _ = tokio::time::sleep(HEARTBEAT) => self.refresh(),
+            }
+        }
+    }
+
+    fn still_ours(&self) -> bool {
+        ClaimHolder::parse(&std::fs::read_to_string(&self.path).unwrap_or_default())
+            .is_some_and(|h| h.token == self.holder.token)
+    }
+}
+
+impl Drop for SourceClaim {
+    fn drop(&mut self) {
+        if self.still_ours() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn describe(holder: Option<&ClaimHolder>) -> String {
+    holder.map_or_else(|| "another instance".to_string(), ClaimHolder::describe)
+}
+
+/// Whether a claim was left behind by an instance that is gone
+fn is_stale(path: &Path, holder: Option<&ClaimHolder>, stale_after: Duration) -> bool {
+    if let Some(holder) = holder
+        && holder.host == host_name()
+        && !process_running(holder.pid)
+    {
+        return true;
+    }
+    // Another host's process can't be checked; its heartbeat can
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > stale_after)
+}
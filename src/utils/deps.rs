@@ -1,43 +1,310 @@
-use crate::error::AppError;
-use std::process::Command;
+use crate::utils::tools;
+use regex::Regex;
+use std::process::{Command, Stdio};
+use std::sync::{LazyLock, RwLock};
 
-/// Status of required and optional dependencies
-#[derive(Debug, Clone)]
-pub struct DependencyStatus;
+/// Encoders the app can drive, as named by `ffmpeg -encoders`
+const AV1_ENCODERS: [&str; 4] = ["libsvtav1", "av1_nvenc", "av1_qsv", "av1_amf"];
+
+/// ab-av1 executable, looked up on the PATH
+const AB_AV1: &str = "ab-av1";
+
+/// First dotted version number in a line, e.g. `7.1`, `n6.1` or `v2.3.0`
+static VERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[^\w.])[nv]?(\d+)\.(\d+)(?:\.(\d+))?").expect("valid version pattern")
+});
+
+/// Toolchain found by the last detection, read by the encoder
+static DETECTED: LazyLock<RwLock<DependencyStatus>> =
+    LazyLock::new(|| RwLock::new(DependencyStatus::default()));
+
+/// Version number of a tool or library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse the first version number in `text`
+    ///
+    /// Git builds like `N-112345-gabcdef` carry no number and give `None`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let caps = VERSION.captures(text)?;
+        let number = |i: usize| caps.get(i).and_then(|m| m.as_str().parse().ok());
+        Some(Self::new(number(1)?, number(2)?, number(3).unwrap_or(0)))
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Part of the toolchain a feature depends on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    Ffmpeg,
+    SvtAv1,
+    Libvmaf,
+}
+
+impl Component {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Component::Ffmpeg => "FFmpeg",
+            Component::SvtAv1 => "SVT-AV1",
+            Component::Libvmaf => "libvmaf",
+        }
+    }
+}
+
+/// Optional capability that needs a minimum toolchain version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// av1_nvenc, av1_qsv and av1_amf
+    HardwareAv1,
+    /// `film-grain-denoise`, which removes the grain the synthesis adds back
+    FilmGrainDenoise,
+    /// `enable-variance-boost`, used by the dark-scene guard
+    VarianceBoost,
+    /// The libvmaf filter options used by verification
+    VmafVerification,
+    /// `fgs-table`, which reuses the film grain of an AV1 source
+    FilmGrainTable,
+    /// FFmpeg's `-readrate`, which throttles reading the source
+    ReadRate,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 6] = [
+        Feature::HardwareAv1,
+        Feature::FilmGrainDenoise,
+        Feature::VarianceBoost,
+        Feature::VmafVerification,
+        Feature::FilmGrainTable,
+        Feature::ReadRate,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Feature::HardwareAv1 => "Hardware AV1 encoding",
+            Feature::FilmGrainDenoise => "Film grain denoising",
+            Feature::VarianceBoost => "Dark-scene variance boost",
+            Feature::VmafVerification => "VMAF verification",
+            Feature::FilmGrainTable => "AV1 film grain passthrough",
+            Feature::ReadRate => "Read rate limit",
+        }
+    }
+
+    /// Component and the oldest version of it that has the feature
+    pub fn requirement(&self) -> (Component, Version) {
+        match self {
+            Feature::HardwareAv1 => (Component::Ffmpeg, Version::new(6, 0, 0)),
+            Feature::FilmGrainDenoise => (Component::SvtAv1, Version::new(1, 1, 0)),
+            Feature::VarianceBoost => (Component::SvtAv1, Version::new(2, 0, 0)),
+            Feature::VmafVerification => (Component::Libvmaf, Version::new(2, 0, 0)),
+            Feature::FilmGrainTable => (Component::SvtAv1, Version::new(1, 5, 0)),
+            Feature::ReadRate => (Component::Ffmpeg, Version::new(5, 0, 0)),
+        }
+    }
+}
+
+/// Status of required and optional dependencies, with the versions found
+///
+/// A version that could not be read is `None` and assumed recent enough, so
+/// git builds and unusual version strings never lose features.
+#[derive(Debug, Clone, Default)]
+pub struct DependencyStatus {
+    pub ffmpeg_available: bool,
+    pub ffprobe_available: bool,
+    pub ffmpeg: Option<Version>,
+    /// AV1 encoders compiled into FFmpeg
+    pub encoders: Vec<String>,
+    /// Whether FFmpeg has the libvmaf filter
+    pub libvmaf_available: bool,
+    pub libsvtav1: Option<Version>,
+    pub libvmaf: Option<Version>,
+    /// ab-av1, when installed
+    pub ab_av1: Option<Version>,
+}
 
 impl DependencyStatus {
-    /// Check all dependencies
-    pub fn check() -> Result<bool, AppError> {
-        if check_command("ffmpeg", &["-version"])  // Check ffmpeg
-            && check_command("ffprobe", &["-version"])  // Check ffprobe
-            && check_vmaf_available()
-        // Check libvmaf
+    /// Probe the installed tools and remember the result for `current`
+    pub fn detect() -> Self {
+        let ffmpeg = tools::ffmpeg();
+        let version_output = command_output(&ffmpeg, &["-version"]);
+        let encoders: Vec<String> = command_output(&ffmpeg, &["-hide_banner", "-encoders"])
+            .map(|out| {
+                AV1_ENCODERS
+                    .iter()
+                    .filter(|name| out.split_whitespace().any(|word| word == **name))
+                    .map(|name| name.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let libvmaf_available = command_output(&ffmpeg, &["-hide_banner", "-filters"])
+            .is_some_and(|out| out.contains("libvmaf"));
+
+        let status = Self {
+            ffmpeg_available: version_output.is_some(),
+            ffprobe_available: command_output(&tools::ffprobe(), &["-version"]).is_some(),
+            ffmpeg: version_output
+                .as_deref()
+                .and_then(|out| out.lines().next())
+                .and_then(|line| line.strip_prefix("ffmpeg version "))
+                .and_then(Version::parse),
+            libsvtav1: if encoders.iter().any(|e| e == "libsvtav1") {
+                svtav1_version(&ffmpeg)
+            } else {
+                None
+            },
+            encoders,
+            libvmaf: if libvmaf_available {
+                command_output("vmaf", &["--version"]).and_then(|out| Version::parse(&out))
+            } else {
+                None
+            },
+            libvmaf_available,
+            ab_av1: command_output(AB_AV1, &["--version"]).and_then(|out| Version::parse(&out)),
+        };
+
+        if let Ok(mut detected) = DETECTED.write() {
+            *detected = status.clone();
+        }
+        status
+    }
+
+    /// Result of the last `detect`, or an all-permissive status before the first one
+    pub fn current() -> Self {
+        DETECTED.read().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Check if the tools the app cannot work without are installed
+    pub fn is_ready(&self) -> bool {
+        self.ffmpeg_available && self.ffprobe_available && self.libvmaf_available
+    }
+
+    /// Detected version of a component
+    pub fn version(&self, component: Component) -> Option<Version> {
+        match component {
+            Component::Ffmpeg => self.ffmpeg,
+            Component::SvtAv1 => self.libsvtav1,
+            Component::Libvmaf => self.libvmaf,
+        }
+    }
+
+    /// Check if the toolchain supports a feature
+    ///
+    /// VMAF also needs the libvmaf filter itself. Before detection ran every
+    /// feature counts as supported.
+    pub fn supports(&self, feature: Feature) -> bool {
+        if feature == Feature::VmafVerification && self.ffmpeg_available && !self.libvmaf_available
         {
-            Ok(true)
-        } else {
-            Err(AppError::DependencyMissing(
-                "Dependencies missing".to_string(),
-            ))
+            return false;
         }
+        let (component, minimum) = feature.requirement();
+        self.version(component).is_none_or(|v| v >= minimum)
+    }
+
+    /// Why a feature is unavailable, e.g. `needs SVT-AV1 2.0.0 (found 1.7.0)`
+    pub fn unsupported_reason(&self, feature: Feature) -> Option<String> {
+        if self.supports(feature) {
+            return None;
+        }
+        let (component, minimum) = feature.requirement();
+        Some(match self.version(component) {
+            Some(found) => format!("needs {} {} (found {})", component.name(), minimum, found),
+            None => format!("{} not available", component.name()),
+        })
+    }
+
+    /// Check if FFmpeg can drive an encoder
+    ///
+    /// Hardware encoders need a recent enough FFmpeg built with them; when the
+    /// encoder list could not be read every encoder is allowed.
+    pub fn encoder_usable(&self, ffmpeg_name: &str) -> bool {
+        if ffmpeg_name != "libsvtav1" && !self.supports(Feature::HardwareAv1) {
+            return false;
+        }
+        self.encoders.is_empty() || self.encoders.iter().any(|e| e == ffmpeg_name)
+    }
+
+    /// One-line summary of the detected versions, e.g. `FFmpeg 7.1.0 · SVT-AV1 2.3.0`
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        for (name, version, present) in [
+            ("FFmpeg", self.ffmpeg, self.ffmpeg_available),
+            ("SVT-AV1", self.libsvtav1, self.libsvtav1.is_some()),
+            ("libvmaf", self.libvmaf, self.libvmaf_available),
+            ("ab-av1", self.ab_av1, self.ab_av1.is_some()),
+        ] {
+            match (present, version) {
+                (true, Some(version)) => parts.push(format!("{} {}", name, version)),
+                (true, None) => parts.push(name.to_string()),
+                (false, _) => {}
+            }
+        }
+        parts.join(" · ")
+    }
+
+    /// Check if mkvmerge is installed for the optional muxing backend
+    pub fn mkvmerge_available() -> bool {
+        command_output(&tools::mkvmerge(), &["--version"]).is_some()
     }
 }
 
-/// Check if a command is available
-fn check_command(cmd: &str, args: &[&str]) -> bool {
+/// Stdout of a successful command, or `None` when it is missing or fails
+fn command_output(cmd: &str, args: &[&str]) -> Option<String> {
     Command::new(cmd)
         .args(args)
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .status()
-        .is_ok_and(|s| s.success())
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
 }
 
-/// Check if VMAF is available in FFmpeg
-fn check_vmaf_available() -> bool {
-    Command::new("ffmpeg")
-        .args(["-filters"])
+/// SVT-AV1 library version, which it logs when an encoder opens
+///
+/// FFmpeg doesn't report it otherwise, so this encodes one tiny frame.
+fn svtav1_version(ffmpeg: &str) -> Option<Version> {
+    let output = Command::new(ffmpeg)
+        .args([
+            "-hide_banner",
+            "-nostdin",
+            "-v",
+            "info",
+            "-f",
+            "lavfi",
+            "-i",
+            "color=c=black:s=64x64:r=25",
+            "-frames:v",
+            "1",
+            "-c:v",
+            "libsvtav1",
+            "-f",
+            "null",
+            "-",
+        ])
+        .stdin(Stdio::null())
         .output()
-        .ok()
-        .map(|o| String::from_utf8_lossy(&o.stdout).contains("libvmaf"))
-        .unwrap_or(false)
+        .ok()?;
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .find_map(|line| {
+            let pos = line.find("SVT-AV1 Encoder Lib")?;
+            Version::parse(&line[pos..])
+        })
 }
@@ -0,0 +1,167 @@
+use crate::error::AppError;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Copy text to the system clipboard using the platform's clipboard tool
+pub fn copy_to_clipboard(text: &str) -> Result<(), AppError> {
+    let candidates: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else if cfg!(windows) {
+        &[("clip", &[])]
+    } else {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    };
+
+    for (cmd, args) in candidates {
+        let Ok(mut child) = Command::new(cmd)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(text.as_bytes());
+        }
+        if child.wait().is_ok_and(|s| s.success()) {
+            return Ok(());
+        }
+    }
+
+    Err(AppError::DependencyMissing(
+        "No clipboard tool found (install wl-clipboard, xclip or xsel)".to_string(),
+    ))
+}
+
+/// Play a media file in the configured player, or the system default application
+///
+/// The player setting may include arguments, e.g. `mpv --fs`.
+pub fn open_in_player(path: &Path, player: Option<&str>) -> Result<(), AppError> {
+    let words: Vec<&str> = player
+        .map(|p| p.split_whitespace().collect())
+        .unwrap_or_default();
+
+    let mut command = if let Some((program, args)) = words.split_first() {
+        let mut c = Command::new(program);
+        c.args(args);
+        c
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(path);
+
+    spawn_detached(command)
+        .map_err(|e| AppError::CommandExecution(format!("Failed to launch player: {}", e)))
+}
+
+/// Open the folder containing a file in the system file manager, selecting the file where supported
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), AppError> {
+    let command = if cfg!(target_os = "macos") {
+        let mut c = Command::new("open");
+        c.arg("-R").arg(path);
+        c
+    } else if cfg!(windows) {
+        let mut c = Command::new("explorer");
+        c.arg(format!("/select,{}", path.display()));
+        c
+    } else {
+        let mut c = Command::new("xdg-open");
+        c.arg(path.parent().unwrap_or(Path::new(".")));
+        c
+    };
+
+    spawn_detached(command)
+        .map_err(|e| AppError::CommandExecution(format!("Failed to open file manager: {}", e)))
+}
+
+/// Move a file to the system trash (the recycle bin on Windows)
+///
+/// Fails when no trash tool is available, so the caller can keep the file.
+pub fn move_to_trash(path: &Path) -> Result<(), AppError> {
+    let path_str = path.to_string_lossy();
+    let candidates: Vec<(&str, Vec<String>)> = if cfg!(target_os = "macos") {
+        let escaped = path_str.replace('\\', "\\\\").replace('"', "\\\"");
+        vec![(
+            "osascript",
+            vec![
+                "-e".to_string(),
+                format!(
+                    "tell application \"Finder\" to delete POSIX file \"{}\"",
+                    escaped
+                ),
+            ],
+        )]
+    } else if cfg!(windows) {
+        vec![(
+            "powershell",
+            vec![
+                "-NoProfile".to_string(),
+                "-Command".to_string(),
+                format!(
+                    "Add-Type -AssemblyName Microsoft.VisualBasic; \
+                     [Microsoft.VisualBasic.FileIO.FileSystem]::DeleteFile('{}', \
+                     'OnlyErrorDialogs', 'SendToRecycleBin')",
+                    path_str.replace('\'', "''")
+                ),
+            ],
+        )]
+    } else {
+        vec![
+            ("gio", vec!["trash".to_string(), path_str.to_string()]),
+            ("trash-put", vec![path_str.to_string()]),
+            (
+                "kioclient5",
+                vec![
+                    "move".to_string(),
+                    path_str.to_string(),
+                    "trash:/".to_string(),
+                ],
+            ),
+        ]
+    };
+
+    for (cmd, args) in candidates {
+        let moved = Command::new(cmd)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success());
+        if moved && !path.exists() {
+            return Ok(());
+        }
+    }
+
+    Err(AppError::DependencyMissing(
+        "No trash available (install gio or trash-cli, or set delete_mode = \"permanent\")"
+            .to_string(),
+    ))
+}
+
+/// Start a GUI program without tying it to the terminal
+fn spawn_detached(mut command: Command) -> std::io::Result<()> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // Reap the child in the background so it doesn't linger as a zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
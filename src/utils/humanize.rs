@@ -1,5 +1,67 @@
+use crate::config::{SizeUnits, UiConfig};
+use std::sync::RwLock;
 use std::time::Duration;
 
+/// Language codes whose locales write decimals with a comma
+const COMMA_LANGUAGES: &[&str] = &[
+    "bg", "ca", "cs", "da", "de", "el", "es", "et", "eu", "fi", "fr", "gl", "hr", "hu", "id", "is",
+    "it", "lt", "lv", "nb", "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sr", "sv", "tr",
+    "uk", "vi",
+];
+
+/// How numbers with units are written
+#[derive(Debug, Clone, Copy)]
+struct NumberFormat {
+    units: SizeUnits,
+    separator: char,
+}
+
+static FORMAT: RwLock<NumberFormat> = RwLock::new(NumberFormat {
+    units: SizeUnits::Binary,
+    separator: '.',
+});
+
+/// Use the size units and decimal separator from the configuration
+pub fn configure(ui: &UiConfig) {
+    let separator = match ui.decimal_separator.as_str() {
+        "," => ',',
+        "." => '.',
+        _ => locale_separator(),
+    };
+    if let Ok(mut format) = FORMAT.write() {
+        *format = NumberFormat {
+            units: ui.size_units,
+            separator,
+        };
+    }
+}
+
+fn current() -> NumberFormat {
+    FORMAT.read().map(|f| *f).unwrap_or(NumberFormat {
+        units: SizeUnits::Binary,
+        separator: '.',
+    })
+}
+
+/// Decimal separator of the locale numbers are formatted in (LC_ALL, LC_NUMERIC, LANG)
+fn locale_separator() -> char {
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    let language = locale
+        .split(['_', '.', '@', '-'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if COMMA_LANGUAGES.contains(&language.as_str()) {
+        ','
+    } else {
+        '.'
+    }
+}
+
 /// Format a duration as HH:MM:SS or MM:SS
 pub fn format_duration(duration: Duration) -> String {
     let total_secs = duration.as_secs();
@@ -14,19 +76,42 @@ pub fn format_duration(duration: Duration) -> String {
     }
 }
 
+/// Format a number with a fixed number of decimals and the configured separator
+pub fn format_decimal(value: f64, precision: usize) -> String {
+    let text = format!("{:.*}", precision, value);
+    match current().separator {
+        '.' => text,
+        separator => text.replace('.', &separator.to_string()),
+    }
+}
+
 /// Format a file size in human-readable form
 pub fn format_file_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = 1024 * KB;
-    const GB: u64 = 1024 * MB;
-
-    if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.1} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.0} KB", bytes as f64 / KB as f64)
+    let (value, unit, precision) = scale(bytes as f64, current().units);
+    format!("{} {}", format_decimal(value, precision), unit)
+}
+
+/// Format a transfer rate such as "48.2 MiB/s"
+pub fn format_speed(bytes_per_sec: f64) -> String {
+    let (value, unit, precision) = scale(bytes_per_sec.max(0.0), current().units);
+    format!("{} {}/s", format_decimal(value, precision), unit)
+}
+
+/// Scaled value, unit label and decimals shown for an amount of bytes
+fn scale(bytes: f64, units: SizeUnits) -> (f64, &'static str, usize) {
+    let (base, labels) = match units {
+        SizeUnits::Binary => (1024.0, ["KiB", "MiB", "GiB"]),
+        SizeUnits::Decimal => (1000.0, ["kB", "MB", "GB"]),
+    };
+    let (kb, mb, gb) = (base, base * base, base * base * base);
+
+    if bytes >= gb {
+        (bytes / gb, labels[2], 2)
+    } else if bytes >= mb {
+        (bytes / mb, labels[1], 1)
+    } else if bytes >= kb {
+        (bytes / kb, labels[0], 0)
     } else {
-        format!("{} B", bytes)
+        (bytes, "B", 0)
     }
 }
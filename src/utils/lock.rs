@@ -0,0 +1,223 @@
+//! Lock files that keep two running copies of the app from encoding into the
+//! same place, where they would convert the same files twice and overwrite
+//! each other's chunks and temporaries.
+//!
+//! A lock names the process and host holding it. Only a lock of this host
+//! whose process is gone counts as stale; on a shared folder another
+//! machine's process can't be checked, so its lock stays in force.
+
+use super::claim::host_name;
+use crate::error::AppError;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Lock file written into each output directory
+pub const DIR_LOCK_FILE: &str = ".av1converter.lock";
+
+/// Set by `--ignore-lock`: run even when another instance holds a lock
+static IGNORE_LOCKS: AtomicBool = AtomicBool::new(false);
+
+/// Run alongside other instances instead of refusing to (the `--ignore-lock` flag)
+pub fn set_ignore_locks(ignore: bool) {
+    IGNORE_LOCKS.store(ignore, Ordering::Relaxed);
+}
+
+/// Instance named in a lock file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockHolder {
+    pub pid: u32,
+    /// Host of the process; locks written by older versions name none and
+    /// count as this machine's
+    pub host: Option<String>,
+}
+
+impl LockHolder {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            host: Some(host_name()),
+        }
+    }
+
+    fn parse(content: &str) -> Option<Self> {
+        let mut lines = content.lines();
+        Some(Self {
+            pid: lines.next()?.trim().parse().ok()?,
+            host: lines
+                .next()
+                .map(|host| host.trim().to_string())
+                .filter(|host| !host.is_empty()),
+        })
+    }
+
+    fn contents(&self) -> String {
+        format!(
+            "{}\n{}\n",
+            self.pid,
+            self.host.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// Host of the process, when it isn't this machine
+    pub fn other_host(&self) -> Option<&str> {
+        self.host.as_deref().filter(|host| *host != host_name())
+    }
+
+    /// Whether the process may still run; another host's can't be checked
+    pub fn alive(&self) -> bool {
+        self.other_host().is_some() || process_running(self.pid)
+    }
+
+    fn is_this_process(&self) -> bool {
+        self.pid == std::process::id() && self.other_host().is_none()
+    }
+}
+
+/// Lock held by this process, removed again when dropped
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+    /// False when another instance's lock was ignored, which stays in place
+    owned: bool,
+}
+
+impl InstanceLock {
+    /// Lock for the whole app, in the data directory
+    pub fn global() -> Result<Self, AppError> {
+        Self::acquire(&super::data_dir().join("instance.lock"))
+    }
+
+    /// Lock for everything written into `dir`
+    pub fn output_dir(dir: &Path) -> Result<Self, AppError> {
+        Self::acquire(&dir.join(DIR_LOCK_FILE))
+    }
+
+    /// Take the lock file at `path`
+    ///
+    /// A lock left behind by a process of this host that no longer runs
+    /// (e.g. after a crash) is taken over. One held by a running process, or
+    /// by any process of another host, fails with `AppError::Locked`, unless
+    /// locks are ignored.
+    pub fn acquire(path: &Path) -> Result<Self, AppError> {
+        let io_error = |e: std::io::Error| AppError::Io {
+            path: path.to_path_buf(),
+            operation: "write lock file",
+            message: e.to_string(),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+
+        let current = LockHolder::current();
+
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(mut file) => {
+                    file.write_all(current.contents().as_bytes())
+                        .map_err(io_error)?;
+                    return Ok(Self {
+                        path: path.to_path_buf(),
+                        owned: true,
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(io_error(e)),
+            }
+
+            let holder = lock_holder(path);
+            match holder {
+                Some(ref holder) if holder.is_this_process() => {
+                    return Ok(Self {
+                        path: path.to_path_buf(),
+                        owned: false,
+                    });
+                }
+                Some(ref holder) if holder.alive() => {
+                    if IGNORE_LOCKS.load(Ordering::Relaxed) {
+                        return Ok(Self {
+                            path: path.to_path_buf(),
+                            owned: false,
+                        });
+                    }
+                    return Err(AppError::Locked {
+                        path: path.to_path_buf(),
+                        pid: holder.pid,
+                        host: holder.other_host().map(str::to_string),
+                    });
+                }
+                // Stale or unreadable: moving it aside succeeds for one instance only
+                _ => {
+                    let nanos = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_nanos());
+                    let mut aside = path.as_os_str().to_owned();
+                    aside.push(format!(".{}-{}", current.pid, nanos));
+                    let aside = PathBuf::from(aside);
+                    if std::fs::rename(path, &aside).is_ok() {
+                        // Another instance may have replaced it with a live lock
+                        // since it was read; that one goes back in place
+                        if lock_holder(&aside) != holder {
+                            let _ = std::fs::rename(&aside, path);
+                        } else {
+                            let _ = std::fs::remove_file(&aside);
+                        }
+                    }
+                }
+            }
+        }
+        Err(io_error(std::io::Error::other(
+            "lock file keeps reappearing",
+        )))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        if self.owned && lock_holder(&self.path).is_some_and(|h| h.is_this_process()) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Instance named in a lock file
+pub fn lock_holder(path: &Path) -> Option<LockHolder> {
+    LockHolder::parse(&std::fs::read_to_string(path).ok()?)
+}
+
+/// Whether the lock at `path` may be held by a running instance
+///
+/// A lock that can't be read yet counts as held.
+pub fn lock_active(path: &Path) -> bool {
+    match lock_holder(path) {
+        Some(holder) => holder.alive(),
+        None => path.exists(),
+    }
+}
+
+/// Check if a process with this ID is running
+pub(crate) fn process_running(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        return Path::new("/proc").join(pid.to_string()).exists();
+    }
+    if cfg!(windows) {
+        return Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .stdin(Stdio::null())
+            .output()
+            .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).contains(&pid.to_string()));
+    }
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
@@ -1,34 +1,80 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
 use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
+
+/// Log lines kept in memory for crash reports
+const RECENT_LINES: usize = 200;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Writer appending to the in-memory log
+struct RecentLines;
+
+impl std::io::Write for RecentLines {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Ok(mut recent) = RECENT.lock() {
+            for line in String::from_utf8_lossy(buf).lines() {
+                if recent.len() == RECENT_LINES {
+                    recent.pop_front();
+                }
+                recent.push_back(line.to_string());
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Latest info-level log lines, oldest first, whether or not AV1_DEBUG is set
+pub fn recent_log_lines() -> Vec<String> {
+    RECENT
+        .lock()
+        .map(|recent| recent.iter().cloned().collect())
+        .unwrap_or_default()
+}
 
 /// Initialize logging based on AV1_DEBUG environment variable
+///
+/// Info-level lines are always kept in memory for crash reports; the log
+/// file is only written with AV1_DEBUG.
 pub fn init_logging() -> Option<WorkerGuard> {
+    let recent = tracing_subscriber::fmt::layer()
+        .with_writer(|| RecentLines)
+        .with_ansi(false)
+        .with_filter(LevelFilter::INFO);
+
     if std::env::var("AV1_DEBUG").is_ok() {
-        let log_dir = std::env::var_os("XDG_DATA_HOME")
-            .map(std::path::PathBuf::from)
-            .or_else(|| {
-                std::env::var_os("HOME").map(|h| std::path::PathBuf::from(h).join(".local/share"))
-            })
-            .or_else(|| std::env::var_os("LOCALAPPDATA").map(std::path::PathBuf::from))
-            .unwrap_or_else(|| std::path::PathBuf::from("."))
-            .join("av1converter");
+        let log_dir = super::data_dir();
 
         let _ = std::fs::create_dir_all(&log_dir);
 
         let file_appender = tracing_appender::rolling::daily(&log_dir, "av1converter.log");
         let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
 
-        tracing_subscriber::fmt()
+        let file = tracing_subscriber::fmt::layer()
             .with_writer(non_blocking)
             .with_ansi(false)
-            .with_env_filter(
+            // Log each span's busy/idle time when it closes
+            .with_span_events(FmtSpan::CLOSE)
+            .with_filter(
                 tracing_subscriber::EnvFilter::from_default_env()
                     .add_directive(tracing::Level::DEBUG.into()),
-            )
+            );
+        tracing_subscriber::registry()
+            .with(recent)
+            .with(file)
             .init();
 
         tracing::info!("AV1 Converter logging initialized");
         Some(guard)
     } else {
+        tracing_subscriber::registry().with(recent).init();
         None
     }
 }
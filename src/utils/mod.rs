@@ -1,7 +1,23 @@
+pub mod claim;
+pub mod crash;
 pub mod deps;
+pub mod desktop;
 pub mod humanize;
+pub mod lock;
 pub mod logger;
+pub mod naming;
+pub mod paths;
+pub mod priority;
+pub mod session;
+pub mod space;
+pub mod suspend;
+pub mod tools;
+pub mod update;
 
 pub use deps::DependencyStatus;
-pub use humanize::{format_duration, format_file_size};
+pub use desktop::{copy_to_clipboard, move_to_trash, open_in_player, reveal_in_file_manager};
+pub use humanize::{format_decimal, format_duration, format_file_size, format_speed};
+pub use lock::InstanceLock;
 pub use logger::init_logging;
+pub use paths::{config_dir, data_dir};
+pub use session::SessionState;
@@ -0,0 +1,136 @@
+use regex::Regex;
+use std::path::{Component, PathBuf};
+use std::sync::LazyLock;
+
+/// Default output name: the source name followed by the suffix
+pub const DEFAULT_NAME_TEMPLATE: &str = "{name}{suffix}";
+
+/// Placeholders only known for files named after an episode
+const EPISODE_PLACEHOLDERS: [&str; 4] = ["{show}", "{season}", "{episode}", "{title}"];
+
+/// `Show.Name.S01E02.Title`, `Show - S01E02-E03 - Title` or `Show 1x02 Title`
+static EPISODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^(?P<show>.*?)[\s._-]*(?:s(?P<season>\d{1,2})[\s._-]?e(?P<episode>\d{1,3})(?:[\s._-]?-?e(?P<last>\d{1,3}))?|(?:^|[\s._-])(?P<season_x>\d{1,2})x(?P<episode_x>\d{2,3})\b)(?P<rest>.*)$",
+    )
+    .expect("valid episode pattern")
+});
+
+/// Release tags that end an episode title, e.g. `1080p` or `WEB-DL`
+static RELEASE_TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)[\s._-](?:\d{3,4}p|web(?:-?dl|rip)?|bluray|bdrip|hdtv|remux|x26[45]|h\.?26[45]|hevc|proper|repack)\b")
+        .expect("valid release tag pattern")
+});
+
+/// Show, season and episode parsed from a file name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpisodeInfo {
+    pub show: String,
+    pub season: u32,
+    pub episode: u32,
+    /// Last episode of a multi-episode file
+    pub last_episode: Option<u32>,
+    /// Episode title following the SxxEyy token, without release tags
+    pub title: String,
+}
+
+/// Parse an `SxxEyy` (or `1x02`) episode token out of a file stem
+pub fn parse_episode(stem: &str) -> Option<EpisodeInfo> {
+    let caps = EPISODE.captures(stem)?;
+    let number = |name: &str| caps.name(name).and_then(|m| m.as_str().parse().ok());
+    let (season, episode) = match (number("season"), number("episode")) {
+        (Some(season), Some(episode)) => (season, episode),
+        _ => (number("season_x")?, number("episode_x")?),
+    };
+
+    let rest = caps.name("rest").map_or("", |m| m.as_str());
+    let title = match RELEASE_TAG.find(rest) {
+        Some(tag) => &rest[..tag.start()],
+        None => rest,
+    };
+    Some(EpisodeInfo {
+        show: tidy(caps.name("show").map_or("", |m| m.as_str())),
+        season,
+        episode,
+        last_episode: number("last"),
+        title: tidy(title),
+    })
+}
+
+/// Output file name (without extension) for a source stem
+///
+/// `{name}` is the source name and `{suffix}` the output suffix. `{show}`,
+/// `{season}`, `{episode}` and `{title}` come from an `SxxEyy` token; a source
+/// without one falls back to the default template. `/` in the template creates
+/// sub-folders, e.g. `{show}/Season {season}/{show} - S{season}E{episode}{suffix}`.
+pub fn render_name(template: &str, stem: &str, suffix: &str) -> PathBuf {
+    let uses_episode = EPISODE_PLACEHOLDERS.iter().any(|p| template.contains(p));
+    let episode = if uses_episode {
+        parse_episode(stem)
+    } else {
+        None
+    };
+    let template = match (&episode, uses_episode) {
+        (None, true) => DEFAULT_NAME_TEMPLATE,
+        _ if template.trim().is_empty() => DEFAULT_NAME_TEMPLATE,
+        _ => template,
+    };
+
+    let mut name = template.replace("{name}", stem).replace("{suffix}", suffix);
+    if let Some(info) = episode {
+        let episodes = match info.last_episode {
+            Some(last) => format!("{:02}-E{:02}", info.episode, last),
+            None => format!("{:02}", info.episode),
+        };
+        name = name
+            .replace("{show}", &info.show)
+            .replace("{season}", &format!("{:02}", info.season))
+            .replace("{episode}", &episodes)
+            .replace("{title}", &info.title);
+    }
+
+    // Only plain sub-folders; never escape the output directory
+    PathBuf::from(name)
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Usual name of a resolution, e.g. `1080p` for 1920x800 scope video
+///
+/// Goes by width as well as height, so letterboxed and cropped sources get
+/// the name of the format they were mastered in.
+pub fn resolution_label(width: u32, height: u32) -> &'static str {
+    let height = height.max(width * 9 / 16);
+    match height {
+        h if h >= 2000 => "2160p",
+        h if h >= 1300 => "1440p",
+        h if h >= 1000 => "1080p",
+        h if h >= 700 => "720p",
+        h if h >= 560 => "576p",
+        _ => "480p",
+    }
+}
+
+/// Jellyfin version of the movie in `folder`, e.g. `Heat (1995) - 1080p AV1`
+///
+/// Jellyfin groups the files of a movie folder whose names start with the
+/// folder name and a ` - ` into one library entry, offering the rest of the
+/// name as the version to play.
+pub fn jellyfin_version_name(folder: &str, version: &str) -> PathBuf {
+    PathBuf::from(format!("{} - {}", folder, version).replace(['/', '\\'], "-"))
+}
+
+/// Turn dots and underscores into spaces and trim separators
+fn tidy(text: &str) -> String {
+    let spaced = text.replace(['.', '_'], " ");
+    spaced
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c == '-' || c.is_whitespace())
+        .to_string()
+}
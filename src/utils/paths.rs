@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// Environment variable pointing at a portable directory
+const CONFIG_DIR_ENV: &str = "AV1CONVERTER_CONFIG_DIR";
+
+/// Directory given with `--config-dir`, which wins over the environment variable
+static CONFIG_DIR_OVERRIDE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Keep configuration, history and logs in `dir` (the `--config-dir` flag)
+pub fn set_config_dir(dir: PathBuf) {
+    if let Ok(mut current) = CONFIG_DIR_OVERRIDE.write() {
+        *current = Some(dir);
+    }
+}
+
+/// Directory holding everything when running portable, from `--config-dir` or
+/// `AV1CONVERTER_CONFIG_DIR`
+pub fn portable_dir() -> Option<PathBuf> {
+    CONFIG_DIR_OVERRIDE
+        .read()
+        .ok()
+        .and_then(|dir| dir.clone())
+        .or_else(|| {
+            std::env::var_os(CONFIG_DIR_ENV)
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+        })
+}
+
+/// Per-user configuration directory, e.g. `~/.config/av1converter`
+pub fn config_dir() -> PathBuf {
+    if let Some(dir) = portable_dir() {
+        return dir;
+    }
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("av1converter")
+}
+
+/// Per-user data directory for logs and history, e.g. `~/.local/share/av1converter`
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = portable_dir() {
+        return dir;
+    }
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("av1converter")
+}
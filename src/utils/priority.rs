@@ -0,0 +1,59 @@
+//! Disk priority of the app and the tools it runs.
+//!
+//! The priority is set on the app's own process, and FFmpeg, ffprobe and
+//! mkvmerge inherit it when they start, so every read and write of an encode
+//! yields to a media server streaming from the same disk. Linux uses
+//! `ionice`, macOS `taskpolicy`; elsewhere the setting has no effect.
+
+use crate::config::IoPriority;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Priority last set on this process
+static APPLIED: Mutex<IoPriority> = Mutex::new(IoPriority::Normal);
+
+/// Give this process, and the tools it starts from now on, `priority`
+pub fn apply_io_priority(priority: IoPriority) {
+    let Ok(mut applied) = APPLIED.lock() else {
+        return;
+    };
+    if *applied == priority {
+        return;
+    }
+    let pid = std::process::id().to_string();
+    let (program, args): (&str, Vec<&str>) = if cfg!(target_os = "linux") {
+        let class = match priority {
+            IoPriority::Normal => vec!["-c", "2", "-n", "4"],
+            IoPriority::Low => vec!["-c", "2", "-n", "7"],
+            IoPriority::Idle => vec!["-c", "3"],
+        };
+        ("ionice", [class, vec!["-p", &pid]].concat())
+    } else if cfg!(target_os = "macos") {
+        let policy = match priority {
+            IoPriority::Normal => "important",
+            IoPriority::Low => "utility",
+            IoPriority::Idle => "throttle",
+        };
+        ("taskpolicy", vec!["-d", policy, "-p", &pid])
+    } else {
+        warn!("Disk priority is not supported on this platform");
+        *applied = priority;
+        return;
+    };
+
+    let status = Command::new(program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => {
+            info!("Disk priority set to {}", priority.display_name());
+            *applied = priority;
+        }
+        Ok(status) => warn!("{} exited with {}", program, status),
+        Err(e) => warn!("Failed to run {}: {}", program, e),
+    }
+}
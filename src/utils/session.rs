@@ -0,0 +1,46 @@
+//! Where the last session left off, restored when the TUI starts.
+//!
+//! Kept in `session.json` in the data directory rather than in the config:
+//! it changes on every run and is nothing to edit by hand. A missing or
+//! unreadable file starts a fresh session.
+
+use super::data_dir;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    /// Folder the file explorer showed last
+    pub last_dir: Option<PathBuf>,
+    /// Whether folders rather than files were opened
+    pub select_folder: bool,
+    /// Whether folders were opened with their sub-folders
+    pub recursive: bool,
+    /// Names of the device profiles selected for the queue run
+    pub profiles: Vec<String>,
+}
+
+/// Location of the session file
+pub fn session_path() -> PathBuf {
+    data_dir().join("session.json")
+}
+
+impl SessionState {
+    pub fn load() -> Self {
+        std::fs::read_to_string(session_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), AppError> {
+        let path = session_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
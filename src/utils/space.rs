@@ -0,0 +1,77 @@
+//! Free space of the volume a path is on, asked from the system.
+//!
+//! `df` answers on Unix and PowerShell on Windows, like the other system
+//! queries of this crate, so no platform bindings are needed.
+
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// A mounted volume and its free space
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Volume {
+    /// Mount point, or the drive on Windows, e.g. `/mnt/media` or `D:`
+    pub mount: PathBuf,
+    /// Bytes available to this user
+    pub free: u64,
+}
+
+/// Volume holding `path`; a path not created yet counts as on its nearest
+/// existing parent's volume
+pub fn volume_of(path: &Path) -> Option<Volume> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    if cfg!(windows) {
+        return windows_volume(existing);
+    }
+    let output = Command::new("df")
+        .arg("-Pk")
+        .arg(existing)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_df(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Volume in the POSIX output of `df -Pk`
+///
+/// Mount points may contain spaces, so everything after the capacity column
+/// is the mount point.
+pub fn parse_df(stdout: &str) -> Option<Volume> {
+    let line = stdout.lines().nth(1)?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let available_kib: u64 = fields.get(3)?.parse().ok()?;
+    let mount = fields.get(5..).filter(|f| !f.is_empty())?.join(" ");
+    Some(Volume {
+        mount: PathBuf::from(mount),
+        free: available_kib * 1024,
+    })
+}
+
+fn windows_volume(path: &Path) -> Option<Volume> {
+    let path = std::fs::canonicalize(path).ok()?;
+    let Some(Component::Prefix(prefix)) = path.components().next() else {
+        return None;
+    };
+    let drive = prefix.as_os_str().to_string_lossy().replace(r"\\?\", "");
+    let letter = drive.strip_suffix(':').filter(|l| l.len() == 1)?;
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            &format!("(Get-PSDrive -Name {}).Free", letter),
+        ])
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    let free = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Volume {
+        mount: PathBuf::from(drive),
+        free,
+    })
+}
@@ -0,0 +1,102 @@
+//! Pausing the queue: the long-running ffmpeg processes are suspended and
+//! the worker starts no new job until the queue resumes.
+//!
+//! Processes are suspended with `SIGSTOP`/`SIGCONT` through `kill` on Unix
+//! and through PowerShell on Windows, like the other system calls of this
+//! crate.
+
+use std::process::{Command, Stdio};
+use std::sync::{LazyLock, Mutex};
+use tokio::sync::watch;
+use tracing::warn;
+
+/// Whether the queue is paused; receivers wake up when it changes
+static PAUSED: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::Sender::new(false));
+
+/// Processes suspended along with the queue
+static TRACKED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// A process suspended with the queue until dropped
+#[derive(Debug)]
+pub struct Suspendable {
+    pid: Option<u32>,
+}
+
+impl Drop for Suspendable {
+    fn drop(&mut self) {
+        let Some(pid) = self.pid else {
+            return;
+        };
+        let mut tracked = TRACKED.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(pos) = tracked.iter().position(|&p| p == pid) {
+            tracked.swap_remove(pos);
+        }
+    }
+}
+
+/// Suspend the process `pid` whenever the queue is paused, including right
+/// away if it already is
+pub fn track(pid: Option<u32>) -> Suspendable {
+    if let Some(pid) = pid {
+        let mut tracked = TRACKED.lock().unwrap_or_else(|e| e.into_inner());
+        tracked.push(pid);
+        if is_paused() {
+            signal(pid, true);
+        }
+    }
+    Suspendable { pid }
+}
+
+/// Pause or resume the queue, suspending or resuming its processes
+pub fn set_paused(paused: bool) {
+    let tracked = TRACKED.lock().unwrap_or_else(|e| e.into_inner());
+    if PAUSED.send_replace(paused) == paused {
+        return;
+    }
+    for &pid in tracked.iter() {
+        signal(pid, paused);
+    }
+}
+
+pub fn is_paused() -> bool {
+    *PAUSED.borrow()
+}
+
+/// Receiver woken whenever the queue pauses or resumes
+pub fn subscribe() -> watch::Receiver<bool> {
+    PAUSED.subscribe()
+}
+
+fn signal(pid: u32, stop: bool) {
+    let status = if cfg!(windows) {
+        let call = if stop {
+            "NtSuspendProcess"
+        } else {
+            "NtResumeProcess"
+        };
+        let script = format!(
+            "Add-Type -Name P -Namespace N -MemberDefinition \
+             '[DllImport(\"ntdll.dll\")] public static extern int {call}(IntPtr h);'; \
+             [N.P]::{call}((Get-Process -Id {pid}).Handle)"
+        );
+        Command::new("powershell")
+            .args(["-NoProfile", "-Command", &script])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+    } else {
+        Command::new("kill")
+            .args([if stop { "-STOP" } else { "-CONT" }, &pid.to_string()])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+    };
+    if !status.is_ok_and(|s| s.success()) {
+        warn!(
+            "Failed to {} process {}",
+            if stop { "suspend" } else { "resume" },
+            pid
+        );
+    }
+}
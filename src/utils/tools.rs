@@ -0,0 +1,67 @@
+use crate::config::ToolsConfig;
+use std::sync::{LazyLock, RwLock};
+
+/// Environment variables overriding the configured tool paths
+const FFMPEG_ENV: &str = "AV1CONVERTER_FFMPEG";
+const FFPROBE_ENV: &str = "AV1CONVERTER_FFPROBE";
+const MKVMERGE_ENV: &str = "AV1CONVERTER_MKVMERGE";
+
+static TOOLS: LazyLock<RwLock<ToolsConfig>> =
+    LazyLock::new(|| RwLock::new(with_env_overrides(ToolsConfig::default())));
+
+/// Use the tool paths from the configuration; environment variables still take precedence
+pub fn configure(tools: &ToolsConfig) {
+    if let Ok(mut current) = TOOLS.write() {
+        *current = with_env_overrides(tools.clone());
+    }
+}
+
+/// ffmpeg executable to run
+pub fn ffmpeg() -> String {
+    get(|t| &t.ffmpeg)
+}
+
+/// ffprobe executable to run
+pub fn ffprobe() -> String {
+    get(|t| &t.ffprobe)
+}
+
+/// mkvmerge executable to run
+pub fn mkvmerge() -> String {
+    get(|t| &t.mkvmerge)
+}
+
+fn get(field: impl Fn(&ToolsConfig) -> &String) -> String {
+    match TOOLS.read() {
+        Ok(tools) => field(&tools).clone(),
+        Err(_) => field(&ToolsConfig::default()).clone(),
+    }
+}
+
+fn with_env_overrides(mut tools: ToolsConfig) -> ToolsConfig {
+    for (var, field) in [
+        (FFMPEG_ENV, &mut tools.ffmpeg),
+        (FFPROBE_ENV, &mut tools.ffprobe),
+        (MKVMERGE_ENV, &mut tools.mkvmerge),
+    ] {
+        if let Ok(path) = std::env::var(var)
+            && !path.is_empty()
+        {
+            *field = path;
+        }
+    }
+    tools
+}
+
+/// Quote an argument for copy-pasting into a POSIX shell
+pub fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
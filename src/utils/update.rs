@@ -0,0 +1,121 @@
+//! Checking GitHub for a newer release.
+//!
+//! At most once a day, in the background at startup. The answer is cached in
+//! the data directory, so later starts that day cost nothing. The request goes
+//! through curl like the email notifications, and a failed check is only
+//! logged; it never gets in the way of encoding.
+
+use super::data_dir;
+use super::deps::Version;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// GitHub API endpoint of the newest release
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/framicheli/av1converter/releases/latest";
+
+/// Time a cached answer stays valid
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Give up on the request after this many seconds
+const REQUEST_TIMEOUT_SECS: &str = "10";
+
+/// Outcome of the last successful check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedCheck {
+    /// Seconds since the Unix epoch
+    checked_at: u64,
+    /// Tag of the latest release, e.g. `v2.3.0`
+    latest: String,
+}
+
+/// Location of the update check cache
+pub fn update_cache_path() -> PathBuf {
+    data_dir().join("update_check.json")
+}
+
+/// Version of the running build
+pub fn current_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).unwrap_or(Version::new(0, 0, 0))
+}
+
+/// Latest release if it is newer than the running build
+pub fn available_update() -> Option<Version> {
+    latest_release(fetch_latest_tag).filter(|latest| *latest > current_version())
+}
+
+/// Latest released version, asking `fetch` for its tag unless the cache is fresh
+pub fn latest_release(fetch: impl FnOnce() -> Option<String>) -> Option<Version> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    if let Some(cached) = read_cache()
+        && now.saturating_sub(cached.checked_at) < CHECK_INTERVAL.as_secs()
+    {
+        return Version::parse(&cached.latest);
+    }
+
+    // Failures are not cached, so the next start tries again
+    let latest = fetch()?;
+    let check = CachedCheck {
+        checked_at: now,
+        latest,
+    };
+    if let Err(e) = write_cache(&check) {
+        warn!("Failed to save the update check: {}", e);
+    }
+    Version::parse(&check.latest)
+}
+
+fn read_cache() -> Option<CachedCheck> {
+    let content = std::fs::read_to_string(update_cache_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_cache(check: &CachedCheck) -> Result<(), AppError> {
+    let path = update_cache_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string(check)?)?;
+    Ok(())
+}
+
+/// Tag name of the latest GitHub release
+fn fetch_latest_tag() -> Option<String> {
+    let output = Command::new("curl")
+        .args([
+            "--silent",
+            "--fail",
+            "--location",
+            "--max-time",
+            REQUEST_TIMEOUT_SECS,
+            "--header",
+            "Accept: application/vnd.github+json",
+            "--user-agent",
+            concat!("av1converter/", env!("CARGO_PKG_VERSION")),
+            LATEST_RELEASE_URL,
+        ])
+        .output();
+    match output {
+        Ok(out) if out.status.success() => release_tag(&String::from_utf8_lossy(&out.stdout)),
+        Ok(out) => {
+            debug!("Update check failed: curl exited with {}", out.status);
+            None
+        }
+        Err(e) => {
+            debug!("Update check failed: {}", e);
+            None
+        }
+    }
+}
+
+/// `tag_name` of a GitHub release response
+pub fn release_tag(json: &str) -> Option<String> {
+    let release: serde_json::Value = serde_json::from_str(json).ok()?;
+    release["tag_name"].as_str().map(str::to_string)
+}
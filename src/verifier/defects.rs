@@ -0,0 +1,186 @@
+//! Spot checks of the output for black and frozen segments.
+//!
+//! Some hardware driver versions emit stretches of black or repeated frames
+//! that neither VMAF averages nor the stream checks notice. A few windows
+//! spread over the output are scanned with `blackdetect` and `freezedetect`.
+//! A segment counts only if the same window of the source has no such segment
+//! overlapping it, so fades, credits and still shots are left alone.
+
+use crate::error::AppError;
+use crate::utils::{format_duration, tools};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+/// Windows scanned in each file, spread evenly over its duration
+const WINDOW_COUNT: usize = 6;
+
+/// Seconds of video scanned per window
+const WINDOW_SECS: f64 = 10.0;
+
+/// Shortest black segment reported, in seconds
+const MIN_BLACK_SECS: f64 = 1.0;
+
+/// Shortest frozen segment reported, in seconds
+const MIN_FREEZE_SECS: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefectKind {
+    Black,
+    Frozen,
+}
+
+/// A black or frozen segment of the output the source does not have
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputDefect {
+    pub kind: DefectKind,
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
+impl OutputDefect {
+    /// Short description for the UI and reports
+    pub fn describe(&self) -> String {
+        let what = match self.kind {
+            DefectKind::Black => "Black frames",
+            DefectKind::Frozen => "Frozen picture",
+        };
+        format!(
+            "{} at {}–{} not in the source",
+            what,
+            format_duration(Duration::from_secs_f64(self.start_secs.max(0.0))),
+            format_duration(Duration::from_secs_f64(self.end_secs.max(0.0)))
+        )
+    }
+
+    fn overlaps(&self, other: &OutputDefect) -> bool {
+        self.kind == other.kind
+            && self.start_secs < other.end_secs
+            && other.start_secs < self.end_secs
+    }
+}
+
+/// Scan windows of the output and keep the segments the source lacks
+pub fn check_defects(
+    source: &Path,
+    output: &Path,
+    duration_secs: f64,
+) -> Result<Vec<OutputDefect>, AppError> {
+    let mut defects = Vec::new();
+    for (start, length) in windows(duration_secs) {
+        let found = scan_window(output, start, length)?;
+        if found.is_empty() {
+            continue;
+        }
+        let in_source = scan_window(source, start, length)?;
+        defects.extend(
+            found
+                .into_iter()
+                .filter(|defect| !in_source.iter().any(|s| s.overlaps(defect))),
+        );
+    }
+    Ok(defects)
+}
+
+/// Start and length of each scanned window; short files are scanned whole
+fn windows(duration_secs: f64) -> Vec<(f64, f64)> {
+    if duration_secs <= WINDOW_SECS * WINDOW_COUNT as f64 {
+        return vec![(0.0, duration_secs.max(WINDOW_SECS))];
+    }
+    (0..WINDOW_COUNT)
+        .map(|i| {
+            let middle = duration_secs * (i + 1) as f64 / (WINDOW_COUNT + 1) as f64;
+            (middle - WINDOW_SECS / 2.0, WINDOW_SECS)
+        })
+        .collect()
+}
+
+/// Black and frozen segments of `path` between `start` and `start + length`
+pub fn scan_window(path: &Path, start: f64, length: f64) -> Result<Vec<OutputDefect>, AppError> {
+    let output = Command::new(tools::ffmpeg())
+        .args([
+            "-hide_banner",
+            "-nostdin",
+            "-ss",
+            &format!("{:.3}", start),
+            "-t",
+            &format!("{:.3}", length),
+            "-i",
+        ])
+        .arg(path)
+        .args([
+            "-map",
+            "0:v:0",
+            "-vf",
+            &format!(
+                "blackdetect=d={}:pix_th=0.10,freezedetect=n=-60dB:d={}",
+                MIN_BLACK_SECS, MIN_FREEZE_SECS
+            ),
+            "-an",
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .map_err(|e| AppError::Analysis(format!("Failed to scan for black frames: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::Analysis(format!(
+            "Black frame scan failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(parse_defects(
+        &String::from_utf8_lossy(&output.stderr),
+        start,
+        start + length,
+    ))
+}
+
+/// Segments logged by `blackdetect` and `freezedetect`, shifted by `offset`
+///
+/// A freeze still running when the window ends is cut off at `end`.
+pub fn parse_defects(stderr: &str, offset: f64, end: f64) -> Vec<OutputDefect> {
+    let mut defects = Vec::new();
+    let mut freeze_start = None;
+    for line in stderr.lines() {
+        if line.contains("black_start:") {
+            let start = value(line, "black_start:");
+            let stop = value(line, "black_end:");
+            if let (Some(start), Some(stop)) = (start, stop) {
+                defects.push(OutputDefect {
+                    kind: DefectKind::Black,
+                    start_secs: offset + start,
+                    end_secs: offset + stop,
+                });
+            }
+        } else if let Some(start) = value(line, "lavfi.freezedetect.freeze_start:") {
+            freeze_start = Some(start);
+        } else if let Some(stop) = value(line, "lavfi.freezedetect.freeze_end:")
+            && let Some(start) = freeze_start.take()
+        {
+            defects.push(OutputDefect {
+                kind: DefectKind::Frozen,
+                start_secs: offset + start,
+                end_secs: offset + stop,
+            });
+        }
+    }
+    if let Some(start) = freeze_start {
+        defects.push(OutputDefect {
+            kind: DefectKind::Frozen,
+            start_secs: offset + start,
+            end_secs: end,
+        });
+    }
+    defects
+}
+
+fn value(line: &str, key: &str) -> Option<f64> {
+    let pos = line.find(key)?;
+    line[pos + key.len()..]
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
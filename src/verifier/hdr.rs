@@ -0,0 +1,132 @@
+//! Brightness check of HDR outputs.
+//!
+//! Wrong color flags or an accidental conversion leave an HDR output that
+//! plays but looks washed out or dim, and VMAF, which compares luma code
+//! values, rarely notices. A few frames at the same timestamps of source and
+//! output are scaled to full range, so a range mislabel shows up, and their
+//! average and highlight luma compared.
+
+use crate::error::AppError;
+use crate::utils::tools;
+use std::path::Path;
+use std::process::Command;
+
+/// Frames sampled from each file, spread evenly over its duration
+const SAMPLE_COUNT: usize = 5;
+
+/// Largest allowed relative change of the average or highlight luma
+const LUMA_TOLERANCE: f64 = 0.1;
+
+/// Luma of the sampled frames of a file, in 10-bit full range (0-1023)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LumaLevels {
+    /// Mean of the frames' average luma
+    pub average: f64,
+    /// Mean of the frames' 90th percentile luma
+    pub highlights: f64,
+}
+
+/// An HDR output whose brightness differs from its source
+#[derive(Debug, Clone, PartialEq)]
+pub struct HdrMismatch {
+    pub source: LumaLevels,
+    pub output: LumaLevels,
+}
+
+impl HdrMismatch {
+    /// Short description for the UI and reports
+    pub fn describe(&self) -> String {
+        format!(
+            "HDR mismatch: average luma {:.0} → {:.0}, highlights {:.0} → {:.0}",
+            self.source.average,
+            self.output.average,
+            self.source.highlights,
+            self.output.highlights
+        )
+    }
+}
+
+/// Sample source and output and compare their brightness
+pub fn check_hdr_brightness(
+    source: &Path,
+    output: &Path,
+    duration_secs: f64,
+) -> Result<Option<HdrMismatch>, AppError> {
+    let source_levels = sample_luma(source, duration_secs)?;
+    let output_levels = sample_luma(output, duration_secs)?;
+    Ok(compare_luma(source_levels, output_levels))
+}
+
+/// Mismatch if the average or highlight luma moved by more than the tolerance
+pub fn compare_luma(source: LumaLevels, output: LumaLevels) -> Option<HdrMismatch> {
+    let changed = |from: f64, to: f64| from > 0.0 && ((to - from) / from).abs() > LUMA_TOLERANCE;
+    (changed(source.average, output.average) || changed(source.highlights, output.highlights))
+        .then_some(HdrMismatch { source, output })
+}
+
+/// Average and highlight luma of frames sampled across `path`
+pub fn sample_luma(path: &Path, duration_secs: f64) -> Result<LumaLevels, AppError> {
+    let mut averages = Vec::new();
+    let mut highlights = Vec::new();
+    for i in 0..SAMPLE_COUNT {
+        let at = duration_secs * (i + 1) as f64 / (SAMPLE_COUNT + 1) as f64;
+        let output = Command::new(tools::ffmpeg())
+            .args([
+                "-hide_banner",
+                "-nostdin",
+                "-ss",
+                &format!("{:.3}", at),
+                "-i",
+            ])
+            .arg(path)
+            .args([
+                "-map",
+                "0:v:0",
+                "-frames:v",
+                "1",
+                "-vf",
+                "scale=160:-2:out_range=full,format=yuv420p10le,signalstats,\
+                 metadata=print:key=lavfi.signalstats.YAVG,\
+                 metadata=print:key=lavfi.signalstats.YHIGH",
+                "-an",
+                "-f",
+                "null",
+                "-",
+            ])
+            .output()
+            .map_err(|e| AppError::Analysis(format!("Failed to sample HDR brightness: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(AppError::Analysis(format!(
+                "HDR brightness sampling failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        averages.extend(values(&stderr, "lavfi.signalstats.YAVG="));
+        highlights.extend(values(&stderr, "lavfi.signalstats.YHIGH="));
+    }
+
+    match (mean(&averages), mean(&highlights)) {
+        (Some(average), Some(highlights)) => Ok(LumaLevels {
+            average,
+            highlights,
+        }),
+        _ => Err(AppError::Analysis(format!(
+            "No frames sampled from {}",
+            path.display()
+        ))),
+    }
+}
+
+fn values<'a>(stderr: &'a str, key: &'a str) -> impl Iterator<Item = f64> + 'a {
+    stderr.lines().filter_map(move |line| {
+        let pos = line.find(key)?;
+        line[pos + key.len()..].trim().parse::<f64>().ok()
+    })
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
@@ -1,3 +1,14 @@
+pub mod defects;
+pub mod hdr;
+pub mod parity;
+pub mod streams;
+pub mod sync;
+pub mod validation;
 pub mod vmaf;
 
-pub use vmaf::{VmafResult, calculate_vmaf};
+pub use defects::OutputDefect;
+pub use hdr::HdrMismatch;
+pub use parity::StreamMismatch;
+pub use sync::StreamDrift;
+pub use validation::{ValidationIssue, validate_output};
+pub use vmaf::{VmafResult, VmafSegment, calculate_vmaf};
@@ -0,0 +1,92 @@
+use super::streams::{ProbedStream, of_type};
+use crate::tracks::TrackSelection;
+
+/// An audio or subtitle stream that does not line up with the requested selection
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamMismatch {
+    /// A selected stream is absent from the output
+    Missing { stream: String, expected: String },
+    /// The output has a stream that was not selected
+    Unexpected { stream: String, found: String },
+    /// The stream at this position has a different language than requested
+    Reordered {
+        stream: String,
+        expected: String,
+        found: String,
+    },
+}
+
+impl StreamMismatch {
+    /// Short description for the UI and reports
+    pub fn describe(&self) -> String {
+        match self {
+            StreamMismatch::Missing { stream, expected } => {
+                format!("{} ({}) missing from output", stream, expected)
+            }
+            StreamMismatch::Unexpected { stream, found } => {
+                format!("{} ({}) was not selected", stream, found)
+            }
+            StreamMismatch::Reordered {
+                stream,
+                expected,
+                found,
+            } => format!("{} is {}, expected {}", stream, found, expected),
+        }
+    }
+}
+
+/// Check that output audio and subtitle streams match the selection in number and order
+///
+/// Subtitles may be converted for the target container, so streams are matched by
+/// position and language rather than codec. With `drop_subtitles` the output is
+/// expected to have none.
+pub fn check_parity(
+    source: &[ProbedStream],
+    output: &[ProbedStream],
+    tracks: &TrackSelection,
+    drop_subtitles: bool,
+) -> Vec<StreamMismatch> {
+    let mut mismatches = Vec::new();
+    for (codec_type, name) in [("audio", "audio"), ("subtitle", "subtitle")] {
+        let source_streams = of_type(source, codec_type);
+        let output_streams = of_type(output, codec_type);
+        let kept = if codec_type == "audio" {
+            tracks.kept_audio(source_streams.len())
+        } else if drop_subtitles {
+            Vec::new()
+        } else {
+            tracks.kept_subtitles(source_streams.len())
+        };
+        let expected: Vec<&ProbedStream> = kept
+            .iter()
+            .filter_map(|&i| source_streams.get(i).copied())
+            .collect();
+
+        for position in 0..expected.len().max(output_streams.len()) {
+            let stream = format!("{} {}", name, position + 1);
+            match (expected.get(position), output_streams.get(position)) {
+                (Some(expected), None) => mismatches.push(StreamMismatch::Missing {
+                    stream,
+                    expected: expected.describe(),
+                }),
+                (None, Some(found)) => mismatches.push(StreamMismatch::Unexpected {
+                    stream,
+                    found: found.describe(),
+                }),
+                (Some(expected), Some(found))
+                    if expected.language.is_some()
+                        && found.language.is_some()
+                        && expected.language != found.language =>
+                {
+                    mismatches.push(StreamMismatch::Reordered {
+                        stream,
+                        expected: expected.describe(),
+                        found: found.describe(),
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+    mismatches
+}
@@ -0,0 +1,103 @@
+use crate::error::AppError;
+use crate::utils::tools;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+
+/// A stream of a finished file as reported by ffprobe
+#[derive(Debug, Clone)]
+pub struct ProbedStream {
+    pub codec_type: String,
+    pub codec_name: Option<String>,
+    pub language: Option<String>,
+    pub duration_secs: Option<f64>,
+}
+
+impl ProbedStream {
+    /// Codec and language, e.g. "truehd, eng"
+    pub fn describe(&self) -> String {
+        let codec = self.codec_name.as_deref().unwrap_or("unknown");
+        match &self.language {
+            Some(language) => format!("{}, {}", codec, language),
+            None => codec.to_string(),
+        }
+    }
+}
+
+/// Probe type, codec, language and duration of every stream in file order
+pub fn probe_streams(path: &Path) -> Result<Vec<ProbedStream>, AppError> {
+    let output = Command::new(tools::ffprobe())
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "stream=codec_type,codec_name,duration:stream_tags=DURATION,language",
+            "-of",
+            "json",
+        ])
+        .arg(path)
+        .output()
+        .map_err(|e| AppError::Analysis(format!("Failed to execute ffprobe: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Analysis(format!("ffprobe failed: {}", stderr)));
+    }
+
+    let data: StreamsOutput = serde_json::from_slice(&output.stdout)?;
+    Ok(data
+        .streams
+        .into_iter()
+        .map(|stream| ProbedStream {
+            duration_secs: stream.duration_secs(),
+            codec_type: stream.codec_type.unwrap_or_default(),
+            codec_name: stream.codec_name,
+            language: stream.tags.and_then(|t| t.language),
+        })
+        .collect())
+}
+
+/// Streams of one type, in file order
+pub fn of_type<'a>(streams: &'a [ProbedStream], codec_type: &str) -> Vec<&'a ProbedStream> {
+    streams
+        .iter()
+        .filter(|s| s.codec_type == codec_type)
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamsOutput {
+    streams: Vec<RawStream>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    duration: Option<String>,
+    tags: Option<RawTags>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTags {
+    #[serde(rename = "DURATION")]
+    duration: Option<String>,
+    language: Option<String>,
+}
+
+impl RawStream {
+    /// Stream duration, falling back to the Matroska `DURATION` tag (`HH:MM:SS.fffffffff`)
+    fn duration_secs(&self) -> Option<f64> {
+        if let Some(secs) = self.duration.as_deref().and_then(|d| d.parse().ok()) {
+            return Some(secs);
+        }
+        let tag = self.tags.as_ref()?.duration.as_deref()?;
+        let mut parts = tag.split(':');
+        let (h, m, s) = (parts.next()?, parts.next()?, parts.next()?);
+        Some(
+            h.parse::<f64>().ok()? * 3600.0
+                + m.parse::<f64>().ok()? * 60.0
+                + s.parse::<f64>().ok()?,
+        )
+    }
+}
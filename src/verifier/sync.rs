@@ -0,0 +1,77 @@
+use super::streams::{ProbedStream, of_type};
+use crate::tracks::TrackSelection;
+
+/// A stream whose duration in the output differs from the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamDrift {
+    /// Human-readable stream name, e.g. "video" or "audio 2"
+    pub stream: String,
+    pub source_secs: f64,
+    pub output_secs: f64,
+}
+
+impl StreamDrift {
+    /// Output minus source duration; negative when the output stream ends early
+    pub fn drift_secs(&self) -> f64 {
+        self.output_secs - self.source_secs
+    }
+
+    /// Short description for the UI and reports
+    pub fn describe(&self) -> String {
+        let drift = self.drift_secs();
+        let direction = if drift < 0.0 { "early" } else { "late" };
+        format!(
+            "{} ends {:.2}s {} ({:.2}s → {:.2}s)",
+            self.stream,
+            drift.abs(),
+            direction,
+            self.source_secs,
+            self.output_secs
+        )
+    }
+}
+
+/// Compare video and audio stream durations between source and output
+///
+/// Returns every kept stream whose duration drifted by more than `tolerance_secs`.
+pub fn check_sync(
+    source: &[ProbedStream],
+    output: &[ProbedStream],
+    tracks: &TrackSelection,
+    tolerance_secs: f64,
+) -> Vec<StreamDrift> {
+    // Only the first video stream; later ones are cover art
+    let duration =
+        |streams: &[&ProbedStream], i: usize| streams.get(i).and_then(|s| s.duration_secs);
+    let (source_video, output_video) = (of_type(source, "video"), of_type(output, "video"));
+    let (source_audio, output_audio) = (of_type(source, "audio"), of_type(output, "audio"));
+
+    let mut pairs = vec![(
+        "video".to_string(),
+        duration(&source_video, 0),
+        duration(&output_video, 0),
+    )];
+    for (output_index, source_index) in tracks
+        .kept_audio(source_audio.len())
+        .into_iter()
+        .enumerate()
+    {
+        pairs.push((
+            format!("audio {}", output_index + 1),
+            duration(&source_audio, source_index),
+            duration(&output_audio, output_index),
+        ));
+    }
+
+    pairs
+        .into_iter()
+        .filter_map(|(stream, source_secs, output_secs)| {
+            Some(StreamDrift {
+                stream,
+                source_secs: source_secs?,
+                output_secs: output_secs?,
+            })
+        })
+        .filter(|d| d.drift_secs().abs() > tolerance_secs)
+        .collect()
+}
@@ -0,0 +1,99 @@
+use super::defects::{OutputDefect, check_defects};
+use super::hdr::{HdrMismatch, check_hdr_brightness};
+use super::parity::{StreamMismatch, check_parity};
+use super::streams::probe_streams;
+use super::sync::{StreamDrift, check_sync};
+use crate::analyzer::HdrType;
+use crate::config::QualityConfig;
+use crate::error::AppError;
+use crate::tracks::TrackSelection;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// A problem with the output found by post-encode validation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    /// A stream's duration differs from the source
+    Drift(StreamDrift),
+    /// Audio or subtitle streams do not match the selection
+    Stream(StreamMismatch),
+    /// An HDR output is brighter or dimmer than the source
+    Hdr(HdrMismatch),
+    /// The output has a black or frozen segment the source does not
+    Defect(OutputDefect),
+}
+
+impl ValidationIssue {
+    /// Short description for the UI and reports
+    pub fn describe(&self) -> String {
+        match self {
+            ValidationIssue::Drift(drift) => drift.describe(),
+            ValidationIssue::Stream(mismatch) => mismatch.describe(),
+            ValidationIssue::Hdr(mismatch) => mismatch.describe(),
+            ValidationIssue::Defect(defect) => defect.describe(),
+        }
+    }
+}
+
+/// Probe source and output once and run the enabled stream checks
+///
+/// HDR sources also get their brightness compared, and the output is spot
+/// checked for black and frozen segments; a failure to sample either is
+/// logged rather than discarding the stream checks.
+pub fn validate_output(
+    source: &Path,
+    output: &Path,
+    tracks: &TrackSelection,
+    drop_subtitles: bool,
+    hdr_type: HdrType,
+    duration_secs: f64,
+    quality: &QualityConfig,
+) -> Result<Vec<ValidationIssue>, AppError> {
+    let mut issues = Vec::new();
+    if quality.sync_check || quality.stream_parity_check {
+        info!("Validating output streams...");
+        let source_streams = probe_streams(source)?;
+        let output_streams = probe_streams(output)?;
+
+        if quality.sync_check {
+            issues.extend(
+                check_sync(
+                    &source_streams,
+                    &output_streams,
+                    tracks,
+                    quality.sync_tolerance_secs,
+                )
+                .into_iter()
+                .map(ValidationIssue::Drift),
+            );
+        }
+        if quality.stream_parity_check {
+            issues.extend(
+                check_parity(&source_streams, &output_streams, tracks, drop_subtitles)
+                    .into_iter()
+                    .map(ValidationIssue::Stream),
+            );
+        }
+    }
+
+    if quality.hdr_check && hdr_type.is_hdr() {
+        info!("Comparing HDR brightness...");
+        match check_hdr_brightness(source, output, duration_secs) {
+            Ok(mismatch) => issues.extend(mismatch.map(ValidationIssue::Hdr)),
+            Err(e) => warn!("HDR brightness check failed: {}", e),
+        }
+    }
+
+    if quality.defect_check {
+        info!("Scanning output for black and frozen segments...");
+        match check_defects(source, output, duration_secs) {
+            Ok(defects) => issues.extend(defects.into_iter().map(ValidationIssue::Defect)),
+            Err(e) => warn!("Black frame scan failed: {}", e),
+        }
+    }
+
+    for issue in &issues {
+        warn!("Output validation: {}", issue.describe());
+    }
+    Ok(issues)
+}
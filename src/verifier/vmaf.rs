@@ -1,8 +1,11 @@
 use crate::analyzer::HdrType;
 use crate::error::AppError;
+use crate::utils::{suspend, tools};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
 use tracing::info;
 
 /// VMAF quality result
@@ -14,8 +17,27 @@ pub struct VmafResult {
     pub min_score: f64,
     /// Maximum frame score
     pub max_score: f64,
+    /// Lowest-scoring time ranges, in playback order
+    pub worst_segments: Vec<VmafSegment>,
 }
 
+/// Mean VMAF over a time range of the output
+#[derive(Debug, Clone, Copy)]
+pub struct VmafSegment {
+    /// Start of the range in seconds
+    pub start_secs: f64,
+    /// End of the range in seconds
+    pub end_secs: f64,
+    /// Mean score of the sampled frames in the range
+    pub score: f64,
+}
+
+/// Length of the windows frame scores are averaged over
+const SEGMENT_WINDOW_SECS: f64 = 2.0;
+
+/// Number of worst windows reported
+const WORST_SEGMENT_COUNT: usize = 5;
+
 impl VmafResult {
     /// Check if quality meets threshold
     pub fn meets_threshold(&self, threshold: f64) -> bool {
@@ -54,8 +76,16 @@ pub fn calculate_vmaf(
     encoded: &Path,
     hdr_type: HdrType,
     width: u32,
+    height: u32,
+    frame_rate: f64,
 ) -> Result<VmafResult, AppError> {
-    let json_output = std::env::temp_dir().join(format!("vmaf_result_{}.json", std::process::id()));
+    // Unique per call so concurrent checks don't share a log file
+    static RUN: AtomicU64 = AtomicU64::new(0);
+    let json_output = std::env::temp_dir().join(format!(
+        "vmaf_result_{}_{}.json",
+        std::process::id(),
+        RUN.fetch_add(1, Ordering::Relaxed)
+    ));
 
     let (model_suffix, model_name) = if width >= 3840 {
         (":model='version=vmaf_4k_v0.6.1'", "vmaf_4k_v0.6.1")
@@ -65,11 +95,14 @@ pub fn calculate_vmaf(
         ("", "vmaf_v0.6.1 (default)")
     };
 
-    // VMAF filter with quick settings (subsample=10 for speed)
+    // VMAF filter with quick settings (subsample=10 for speed); a downscaled
+    // output is scaled back up since libvmaf compares equal-sized frames
     let filter = format!(
         "[0:v]format=yuv420p10le,setpts=PTS-STARTPTS[ref];\
-         [1:v]format=yuv420p10le,setpts=PTS-STARTPTS[dist];\
+         [1:v]scale={}:{}:flags=bicubic,format=yuv420p10le,setpts=PTS-STARTPTS[dist];\
          [ref][dist]libvmaf=log_path={}:log_fmt=json:n_threads=4:n_subsample=10{}",
+        width,
+        height,
         json_output.to_string_lossy(),
         model_suffix
     );
@@ -82,7 +115,7 @@ pub fn calculate_vmaf(
         hdr_type.display_string()
     );
 
-    let output = Command::new("ffmpeg")
+    let output = Command::new(tools::ffmpeg())
         .args([
             "-i",
             original.to_str().unwrap_or(""),
@@ -94,7 +127,15 @@ pub fn calculate_vmaf(
             "null",
             "-",
         ])
-        .output()
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|child| {
+            // Stopped while the queue is paused
+            let _suspendable = suspend::track(Some(child.id()));
+            child.wait_with_output()
+        })
         .map_err(|e| AppError::CommandExecution(format!("Failed to run ffmpeg for VMAF: {}", e)))?;
 
     if !output.status.success() {
@@ -126,6 +167,7 @@ pub fn calculate_vmaf(
         score: vmaf_data.pooled_metrics.vmaf.mean,
         min_score: vmaf_data.pooled_metrics.vmaf.min,
         max_score: vmaf_data.pooled_metrics.vmaf.max,
+        worst_segments: worst_segments(&vmaf_data.frames, frame_rate),
     };
 
     info!("VMAF result: {}", result);
@@ -133,11 +175,56 @@ pub fn calculate_vmaf(
     Ok(result)
 }
 
+/// Average per-frame scores over fixed windows and pick the lowest ones
+fn worst_segments(frames: &[FrameJson], frame_rate: f64) -> Vec<VmafSegment> {
+    if frame_rate <= 0.0 {
+        return Vec::new();
+    }
+
+    // window index -> (sum, count)
+    let mut windows: BTreeMap<u64, (f64, u32)> = BTreeMap::new();
+    for frame in frames {
+        let time = frame.frame_num as f64 / frame_rate;
+        let window = (time / SEGMENT_WINDOW_SECS) as u64;
+        let entry = windows.entry(window).or_default();
+        entry.0 += frame.metrics.vmaf;
+        entry.1 += 1;
+    }
+
+    let mut segments: Vec<VmafSegment> = windows
+        .into_iter()
+        .map(|(window, (sum, count))| VmafSegment {
+            start_secs: window as f64 * SEGMENT_WINDOW_SECS,
+            end_secs: (window + 1) as f64 * SEGMENT_WINDOW_SECS,
+            score: sum / count as f64,
+        })
+        .collect();
+
+    segments.sort_by(|a, b| a.score.total_cmp(&b.score));
+    segments.truncate(WORST_SEGMENT_COUNT);
+    segments.sort_by(|a, b| a.start_secs.total_cmp(&b.start_secs));
+    segments
+}
+
 // JSON deserialization structures
 
 #[derive(Debug, Deserialize)]
 struct VmafJson {
     pooled_metrics: PooledMetrics,
+    #[serde(default)]
+    frames: Vec<FrameJson>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FrameJson {
+    #[serde(rename = "frameNum")]
+    frame_num: u64,
+    metrics: FrameMetrics,
+}
+
+#[derive(Debug, Deserialize)]
+struct FrameMetrics {
+    vmaf: f64,
 }
 
 #[derive(Debug, Deserialize)]
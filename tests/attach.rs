@@ -0,0 +1,74 @@
+//! The live queue a running instance publishes for attached viewers.
+
+use av1converter::cli;
+use av1converter::queue::{EncodingJob, JobStatus, LiveQueue, QueuePublisher, QueueState};
+use tempfile::TempDir;
+
+fn queue() -> QueueState {
+    let mut queue = QueueState::default();
+    let mut job = EncodingJob::new("/videos/a.mkv".into());
+    job.status = JobStatus::Encoding { progress: 40.0 };
+    queue.jobs.push(job);
+    queue.jobs.push(EncodingJob::new("/videos/b.mkv".into()));
+    queue
+}
+
+#[test]
+fn viewers_read_what_the_running_instance_publishes() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("live_queue.json");
+    let mut queue = queue();
+    let mut publisher = QueuePublisher::new(path.clone());
+
+    publisher.publish(&queue, false).unwrap();
+    let live = LiveQueue::read(&path).unwrap();
+    assert_eq!(live.pid, std::process::id());
+    assert!(live.is_running());
+    assert_eq!(live.queue.jobs.len(), 2);
+    assert_eq!(live.queue.jobs[0].status, "encoding");
+    assert_eq!(live.queue.jobs[0].progress, Some(40.0));
+
+    // Progress within a second waits for the next write, a forced one doesn't
+    queue.jobs[0].status = JobStatus::Done;
+    publisher.publish(&queue, false).unwrap();
+    assert_eq!(
+        LiveQueue::read(&path).unwrap().queue.jobs[0].status,
+        "encoding"
+    );
+    publisher.publish(&queue, true).unwrap();
+    assert_eq!(LiveQueue::read(&path).unwrap().queue.jobs[0].status, "done");
+
+    drop(publisher);
+    assert!(!path.exists());
+    assert_eq!(LiveQueue::read(&path), None);
+}
+
+#[test]
+fn status_file_outlives_the_run() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("status/av1.json");
+    let mut queue = queue();
+    let mut publisher = QueuePublisher::status_file(path.clone());
+    publisher.publish(&queue, true).unwrap();
+
+    queue.jobs[0].status = JobStatus::Done;
+    queue.converted_count = 1;
+    publisher.publish(&queue, true).unwrap();
+    drop(publisher);
+
+    // Dashboards keep showing how the run ended, not a missing file
+    let text = std::fs::read_to_string(&path).unwrap();
+    let status: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(status["converted"], 1);
+    assert_eq!(status["jobs"][0]["status"], "done");
+    assert_eq!(status["jobs"][1]["file"], "b.mkv");
+    assert!(status.get("eta_secs").is_some());
+    assert!(!dir.path().join("status/av1.json.tmp").exists());
+}
+
+#[test]
+fn attach_takes_no_files() {
+    let parse = |args: &[&str]| cli::parse(args.iter().map(|a| a.to_string()));
+    assert_eq!(parse(&["--attach"]).unwrap().mode, cli::Mode::Attach);
+    assert!(parse(&["--attach", "a.mkv"]).is_err());
+}
@@ -0,0 +1,78 @@
+//! Track differences between files of a batch, pointed out on the track configuration screen.
+
+use av1converter::queue::{EncodingJob, JobDifference, JobStatus};
+use av1converter::tracks::AudioTrack;
+use proptest::prelude::*;
+use std::path::PathBuf;
+
+fn job(status: JobStatus) -> EncodingJob {
+    let mut job = EncodingJob::new(PathBuf::from("/nonexistent/movie.mkv"));
+    job.status = status;
+    job
+}
+
+fn audio_track(index: usize, (language, channels): (u8, u16)) -> AudioTrack {
+    AudioTrack {
+        index,
+        language: Some(["eng", "fre", "ger"][language as usize].to_string()),
+        codec: "ac3".to_string(),
+        channels,
+        title: None,
+        bitrate: None,
+        sample_rate: None,
+        disposition: Default::default(),
+    }
+}
+
+fn job_with_audio(tracks: &[(u8, u16)]) -> EncodingJob {
+    let mut job = job(JobStatus::AwaitingConfig);
+    job.audio_tracks = tracks
+        .iter()
+        .enumerate()
+        .map(|(index, &track)| audio_track(index, track))
+        .collect();
+    job
+}
+
+proptest! {
+    #[test]
+    fn batch_differences_ignore_order_and_mirror_each_other(
+        previous in prop::collection::vec((0u8..3, prop::sample::select(vec![2u16, 6])), 0..5),
+        current in prop::collection::vec((0u8..3, prop::sample::select(vec![2u16, 6])), 0..5),
+    ) {
+        let (previous, current) = (job_with_audio(&previous), job_with_audio(&current));
+        let mut reversed = current.clone();
+        reversed.audio_tracks.reverse();
+        prop_assert!(current.differences_from(&reversed).is_empty());
+
+        let forward = current.differences_from(&previous);
+        let backward = previous.differences_from(&current);
+        let count = |changes: &[JobDifference], added: bool| {
+            changes
+                .iter()
+                .filter(|c| if added { c.is_added() } else { c.is_missing() })
+                .count()
+        };
+        prop_assert_eq!(count(&forward, true), count(&backward, false));
+        prop_assert_eq!(count(&forward, false), count(&backward, true));
+        prop_assert_eq!(
+            current.audio_tracks.len() + count(&forward, false),
+            previous.audio_tracks.len() + count(&forward, true)
+        );
+    }
+}
+
+#[test]
+fn an_extra_commentary_track_is_pointed_out() {
+    let previous = job_with_audio(&[(0, 6)]);
+    let mut current = job_with_audio(&[(0, 6), (0, 2)]);
+    current.audio_tracks[1].title = Some("Commentary".to_string());
+    assert_eq!(
+        current
+            .differences_from(&previous)
+            .iter()
+            .map(JobDifference::describe)
+            .collect::<Vec<_>>(),
+        ["+ audio eng (AC3 Stereo) - Commentary"]
+    );
+}
@@ -0,0 +1,122 @@
+//! Leftover detection of the library cleanup scan.
+
+use av1converter::cleanup::{self, LeftoverKind};
+use av1converter::utils::claim::{claim_path, host_name};
+use av1converter::utils::lock::DIR_LOCK_FILE;
+use std::fs;
+use std::path::Path;
+
+fn touch(path: &Path, size: usize) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, vec![0u8; size]).unwrap();
+}
+
+#[test]
+fn leftovers_are_classified_and_only_safe_ones_preselected() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    touch(&root.join("done.mkv"), 10);
+    touch(&root.join("done_av1.mkv"), 5);
+    touch(&root.join("todo.mp4"), 10);
+    touch(&root.join("gone_av1.mkv"), 5);
+    touch(&root.join("failed_av1.mkv"), 0);
+    touch(&root.join("tv/show_av1_phone-720p.mp4"), 5);
+    touch(&root.join("tv/show.mkv"), 10);
+    touch(&root.join("tv/show_av1.remux.mkv"), 3);
+    touch(&root.join("big_av1.mkv.chunks/chunk_00000.mkv"), 7);
+    touch(&root.join("big_av1.mkv.chunks/settings"), 1);
+    // Names merely looking like temporaries
+    touch(&root.join("holiday.remux.mkv"), 4);
+    touch(&root.join("notes.partial.txt"), 4);
+    touch(&root.join("photos.chunks/img.jpg"), 4);
+    touch(&root.join("Disc/VIDEO_TS/VTS_01_1.VOB"), 10);
+    touch(&root.join("Disc_title-01_av1.mkv"), 5);
+
+    let found = cleanup::scan(root, "_av1");
+    let kind_of = |name: &str| {
+        found
+            .iter()
+            .find(|l| l.path.ends_with(name))
+            .map(|l| (l.kind, l.selected))
+    };
+
+    assert_eq!(kind_of("done.mkv"), None);
+    assert_eq!(kind_of("done_av1.mkv"), None);
+    assert_eq!(kind_of("tv/show.mkv"), None);
+    assert_eq!(kind_of("tv/show_av1_phone-720p.mp4"), None);
+    assert_eq!(kind_of("Disc_title-01_av1.mkv"), None);
+    assert_eq!(
+        kind_of("todo.mp4"),
+        Some((LeftoverKind::Unconverted, false))
+    );
+    assert_eq!(
+        kind_of("gone_av1.mkv"),
+        Some((LeftoverKind::OrphanOutput, false))
+    );
+    assert_eq!(
+        kind_of("failed_av1.mkv"),
+        Some((LeftoverKind::EmptyOutput, true))
+    );
+    assert_eq!(
+        kind_of("tv/show_av1.remux.mkv"),
+        Some((LeftoverKind::TempFile, true))
+    );
+    assert_eq!(
+        kind_of("big_av1.mkv.chunks"),
+        Some((LeftoverKind::Chunks, false))
+    );
+    assert_eq!(
+        kind_of("holiday.remux.mkv"),
+        Some((LeftoverKind::Unconverted, false))
+    );
+    assert_eq!(kind_of("notes.partial.txt"), None);
+    assert_eq!(kind_of("photos.chunks"), None);
+    assert!(
+        !found
+            .iter()
+            .any(|l| l.path.to_string_lossy().contains("VTS_"))
+    );
+
+    let result = cleanup::remove(&found);
+    assert_eq!(result.removed, 2);
+    assert_eq!(result.freed, 3);
+    assert!(result.failed.is_empty());
+    assert!(root.join("big_av1.mkv.chunks").exists());
+    assert!(root.join("notes.partial.txt").exists());
+    assert!(root.join("gone_av1.mkv").exists());
+    assert!(root.join("todo.mp4").exists());
+}
+
+#[test]
+fn files_of_a_running_encode_are_left_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let pid = std::process::id();
+
+    // A folder locked by a running instance
+    touch(&root.join("locked/movie.mkv"), 10);
+    touch(&root.join("locked/movie_av1.mkv"), 0);
+    touch(&root.join("locked/movie_av1.remux.mkv"), 3);
+    fs::write(
+        root.join("locked").join(DIR_LOCK_FILE),
+        format!("{}\n", pid),
+    )
+    .unwrap();
+
+    // A source claimed by a running instance
+    touch(&root.join("claimed/show.mkv"), 10);
+    touch(&root.join("claimed/show_av1.video.ivf"), 3);
+    touch(&root.join("claimed/other_av1.video.ivf"), 3);
+    fs::write(
+        claim_path(&root.join("claimed/show.mkv")),
+        format!("{}\n{}\ntoken\n", host_name(), pid),
+    )
+    .unwrap();
+
+    let found: Vec<_> = cleanup::scan(root, "_av1")
+        .into_iter()
+        .filter(|l| l.kind != LeftoverKind::Unconverted)
+        .map(|l| l.path.strip_prefix(root).unwrap().to_path_buf())
+        .collect();
+    assert_eq!(found, [Path::new("claimed/other_av1.video.ivf")]);
+}
@@ -0,0 +1,104 @@
+//! Command-line parsing and the completion scripts generated from it.
+
+use av1converter::cli::{self, Cli, Mode, OPTIONS, Shell};
+use std::path::PathBuf;
+
+fn parse(args: &[&str]) -> Result<Cli, String> {
+    cli::parse(args.iter().map(|a| a.to_string()))
+}
+
+#[test]
+fn options_and_paths_are_read_in_any_order() {
+    let cli = parse(&[
+        "a.mkv",
+        "--config-dir",
+        "./av1",
+        "--json",
+        "--stdin",
+        "show",
+    ])
+    .unwrap();
+    assert_eq!(cli.mode, Mode::Json);
+    assert_eq!(cli.config_dir, Some(PathBuf::from("./av1")));
+    assert!(cli.stdin);
+    assert_eq!(
+        cli.paths,
+        vec![PathBuf::from("a.mkv"), PathBuf::from("show")]
+    );
+
+    let cli = parse(&["--config-dir=/tmp/c", "--ignore-lock"]).unwrap();
+    assert_eq!(cli.mode, Mode::Tui);
+    assert_eq!(cli.config_dir, Some(PathBuf::from("/tmp/c")));
+    assert!(cli.ignore_lock);
+}
+
+#[test]
+fn optional_values_stop_at_the_next_option() {
+    assert_eq!(
+        parse(&["--serve", "0.0.0.0:9000"]).unwrap().mode,
+        Mode::Serve(Some("0.0.0.0:9000".to_string()))
+    );
+    assert_eq!(
+        parse(&["--serve", "--ignore-lock"]).unwrap().mode,
+        Mode::Serve(None)
+    );
+    assert_eq!(
+        parse(&["--cleanup"]).unwrap().mode,
+        Mode::Cleanup(PathBuf::from("."))
+    );
+    assert_eq!(
+        parse(&["--cleanup", "/videos"]).unwrap().mode,
+        Mode::Cleanup(PathBuf::from("/videos"))
+    );
+}
+
+#[test]
+fn bad_command_lines_are_refused() {
+    for args in [
+        &["--frobnicate"][..],
+        &["--config-dir"],
+        &["--config-dir="],
+        &["--json=yes"],
+        &["--json", "--print-commands"],
+        &["--dump-commands", "a.mkv"],
+        &["completions"],
+        &["completions", "powershell"],
+    ] {
+        assert!(parse(args).is_err(), "{:?}", args);
+    }
+    assert_eq!(
+        parse(&["completions", "zsh"]).unwrap().mode,
+        Mode::Completions(Shell::Zsh)
+    );
+}
+
+#[test]
+fn every_script_completes_every_option() {
+    for shell in Shell::ALL {
+        let script = cli::completions(shell);
+        for option in OPTIONS {
+            let name = match shell {
+                Shell::Fish => format!("-l {}", option.name.trim_start_matches("--")),
+                _ => option.name.to_string(),
+            };
+            assert!(script.contains(&name), "{:?} lacks {}", shell, option.name);
+        }
+        assert!(script.contains("completions"), "{:?}", shell);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn bash_script_parses() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("av1converter.bash");
+    std::fs::write(&path, cli::completions(Shell::Bash)).unwrap();
+    let Ok(status) = std::process::Command::new("bash")
+        .arg("-n")
+        .arg(&path)
+        .status()
+    else {
+        return;
+    };
+    assert!(status.success());
+}
@@ -0,0 +1,635 @@
+use av1converter::AppConfig;
+use av1converter::analyzer::{HdrType, VideoMetadata};
+use av1converter::config::{
+    AmfPreset, AudioBitrates, AudioCodec, DeviceProfile, Encoder, audio_bitrate,
+};
+use av1converter::encoder::command_builder::{
+    EncodeOverrides, EncodingParams, JobMode, Segment, StreamHandling, build_ffmpeg_args,
+    build_mux_args, sample_variants,
+};
+use av1converter::tracks::{AudioTrack, TrackSelection};
+use av1converter::utils::deps::{DependencyStatus, Version};
+use std::path::{Path, PathBuf};
+
+/// Set to regenerate the golden files after an intended change
+const UPDATE_ENV: &str = "UPDATE_GOLDEN";
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/command_builder")
+}
+
+/// Config with fixed values so the snapshots don't depend on the machine
+fn test_config() -> AppConfig {
+    let mut config = AppConfig {
+        encoder: Encoder::SvtAv1,
+        ..AppConfig::default()
+    };
+    config.performance.svt_preset = 4;
+    config.performance.nvenc_preset = "p7".to_string();
+    config.ffmpeg.extra_args.clear();
+    config.ffmpeg.env.clear();
+    config
+}
+
+fn params(encoder: Encoder, hdr_type: HdrType) -> EncodingParams {
+    sample_variants(&test_config())
+        .into_iter()
+        .map(|(_, p)| p)
+        .find(|p| p.encoder == encoder && p.hdr_type == hdr_type && !p.video_only)
+        .expect("variant exists")
+}
+
+fn has_pair(args: &[String], flag: &str, value: &str) -> bool {
+    args.windows(2).any(|w| w[0] == flag && w[1] == value)
+}
+
+#[test]
+fn ffmpeg_args_match_golden_files() {
+    let update = std::env::var_os(UPDATE_ENV).is_some();
+    let dir = golden_dir();
+    let mut mismatches = Vec::new();
+
+    for (name, params) in sample_variants(&test_config()) {
+        let actual = build_ffmpeg_args(&params).join("\n") + "\n";
+        let path = dir.join(format!("{}.txt", name));
+
+        if update {
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(&path, &actual).unwrap();
+            continue;
+        }
+
+        match std::fs::read_to_string(&path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => mismatches.push(format!(
+                "{}:\n--- expected\n{}--- actual\n{}",
+                name, expected, actual
+            )),
+            Err(_) => mismatches.push(format!("{}: missing golden file {}", name, path.display())),
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "command lines changed (rerun with {}=1 if intended):\n{}",
+        UPDATE_ENV,
+        mismatches.join("\n")
+    );
+}
+
+#[test]
+fn variants_cover_every_combination() {
+    let variants = sample_variants(&test_config());
+    assert_eq!(variants.len(), 4 * 4 * 3);
+
+    let mut names: Vec<_> = variants.iter().map(|(n, _)| n.clone()).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), variants.len(), "variant names must be unique");
+}
+
+#[test]
+fn output_path_is_last_and_input_follows_flag() {
+    for (name, params) in sample_variants(&test_config()) {
+        let args = build_ffmpeg_args(&params);
+        assert_eq!(args.last().unwrap(), "output.mkv", "{}", name);
+        assert!(has_pair(&args, "-i", "input.mkv"), "{}", name);
+        assert_eq!(&args[..2], ["-y", "-nostdin"], "{}", name);
+    }
+}
+
+#[test]
+fn encoder_name_and_quality_flag() {
+    let cases = [
+        (Encoder::SvtAv1, "libsvtav1", "-crf"),
+        (Encoder::Nvenc, "av1_nvenc", "-cq"),
+        (Encoder::Qsv, "av1_qsv", "-global_quality"),
+    ];
+    for (encoder, name, quality_flag) in cases {
+        let p = params(encoder, HdrType::Sdr);
+        let args = build_ffmpeg_args(&p);
+        assert!(has_pair(&args, "-c:v", name));
+        assert!(has_pair(&args, quality_flag, &p.crf.to_string()));
+    }
+}
+
+#[test]
+fn amf_takes_a_preset_and_constant_qps() {
+    let mut p = params(Encoder::Amf, HdrType::Sdr);
+    p.crf = 22;
+    let args = build_ffmpeg_args(&p);
+    assert!(has_pair(&args, "-c:v", "av1_amf"));
+    assert!(has_pair(&args, "-quality", "quality"));
+    assert!(has_pair(&args, "-rc", "cqp"));
+    assert!(has_pair(&args, "-qp_i", "110"));
+    assert!(has_pair(&args, "-qp_p", "110"));
+    assert!(!args.iter().any(|a| a == "-bf" || a == "-preanalysis"));
+
+    p.amf_preset = AmfPreset::Speed;
+    p.amf_bframes = Some(2);
+    p.amf_preanalysis = true;
+    let args = build_ffmpeg_args(&p);
+    assert!(has_pair(&args, "-quality", "speed"));
+    assert!(has_pair(&args, "-bf", "2"));
+    assert!(has_pair(&args, "-qp_b", "120"));
+    assert!(has_pair(&args, "-preanalysis", "1"));
+}
+
+#[test]
+fn sdr_has_no_color_metadata() {
+    let args = build_ffmpeg_args(&params(Encoder::SvtAv1, HdrType::Sdr));
+    assert!(!args.iter().any(|a| a == "-color_primaries"));
+    assert!(!args.iter().any(|a| a == "-map_metadata"));
+    assert!(has_pair(&args, "-vf", "format=yuv420p10le"));
+}
+
+#[test]
+fn hdr_color_metadata_per_transfer() {
+    for (hdr_type, transfer) in [
+        (HdrType::Pq, "smpte2084"),
+        (HdrType::Hlg, "arib-std-b67"),
+        (HdrType::DolbyVision, "smpte2084"),
+    ] {
+        let args = build_ffmpeg_args(&params(Encoder::Nvenc, hdr_type));
+        assert!(has_pair(&args, "-color_primaries", "bt2020"));
+        assert!(has_pair(&args, "-color_trc", transfer));
+        assert!(has_pair(&args, "-colorspace", "bt2020nc"));
+    }
+}
+
+#[test]
+fn dolby_vision_is_tagged_as_hdr10_in_filter() {
+    let args = build_ffmpeg_args(&params(Encoder::SvtAv1, HdrType::DolbyVision));
+    let vf = args.iter().skip_while(|a| *a != "-vf").nth(1).unwrap();
+    assert!(vf.contains("color_trc=smpte2084"));
+    // Dolby Vision metadata is not carried over
+    assert!(!has_pair(&args, "-map_metadata", "0"));
+}
+
+#[test]
+fn selected_tracks_are_mapped_individually() {
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.tracks = TrackSelection {
+        audio_indices: vec![1, 3],
+        subtitle_indices: vec![0],
+    };
+    let args = build_ffmpeg_args(&p);
+    assert!(has_pair(&args, "-map", "0:a:1"));
+    assert!(has_pair(&args, "-map", "0:a:3"));
+    assert!(has_pair(&args, "-map", "0:s:0"));
+    assert!(!has_pair(&args, "-map", "0:a?"));
+}
+
+#[test]
+fn reordered_audio_follows_selection_order() {
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.tracks = TrackSelection {
+        audio_indices: vec![2, 0],
+        subtitle_indices: Vec::new(),
+    };
+    let args = build_ffmpeg_args(&p);
+    let first = args.iter().position(|a| a == "0:a:2").unwrap();
+    let second = args.iter().position(|a| a == "0:a:0").unwrap();
+    assert!(first < second);
+    assert!(has_pair(&args, "-disposition:a:0", "default"));
+    assert!(has_pair(&args, "-disposition:a:1", "0"));
+}
+
+#[test]
+fn dark_scenes_lower_quality_and_strengthen_aq() {
+    let overrides = EncodeOverrides {
+        dark_scene_offset: Some(4),
+        ..Default::default()
+    };
+    for encoder in [Encoder::SvtAv1, Encoder::Nvenc] {
+        let mut p = params(encoder, HdrType::Sdr);
+        let preset = p.crf;
+        p.apply_overrides(&overrides);
+        assert_eq!(p.crf, preset - 4);
+        let args = build_ffmpeg_args(&p);
+        match encoder {
+            Encoder::SvtAv1 => assert!(args.iter().any(|a| a.contains("enable-variance-boost=1"))),
+            _ => assert!(has_pair(&args, "-aq-strength", "12")),
+        }
+    }
+
+    // An explicit quality value wins
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.apply_overrides(&EncodeOverrides {
+        crf: Some(30),
+        ..overrides
+    });
+    assert_eq!(p.crf, 30);
+}
+
+#[test]
+fn options_the_installed_svtav1_lacks_are_left_out() {
+    let overrides = EncodeOverrides {
+        dark_scene_offset: Some(4),
+        ..Default::default()
+    };
+    let svt_params = |deps: &DependencyStatus| {
+        let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+        p.film_grain = 8;
+        p.apply_overrides(&overrides);
+        p.apply_toolchain(deps);
+        let args = build_ffmpeg_args(&p);
+        let pos = args.iter().position(|a| a == "-svtav1-params").unwrap();
+        args[pos + 1].clone()
+    };
+    let with_svt = |version| DependencyStatus {
+        libsvtav1: Some(version),
+        ..Default::default()
+    };
+
+    // Unknown versions keep every option
+    let unknown = svt_params(&DependencyStatus::default());
+    assert!(unknown.contains("film-grain-denoise=1"));
+    assert!(unknown.contains("enable-variance-boost=1"));
+
+    let old = svt_params(&with_svt(Version::new(1, 7, 0)));
+    assert!(old.contains("film-grain=8"));
+    assert!(old.contains("film-grain-denoise=1"));
+    assert!(!old.contains("enable-variance-boost"));
+
+    let ancient = svt_params(&with_svt(Version::new(1, 0, 0)));
+    assert!(!ancient.contains("film-grain-denoise"));
+}
+
+#[test]
+fn reused_grain_table_replaces_grain_estimation() {
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.film_grain = 8;
+    p.film_grain_table = Some("/tmp/movie.grain.tbl".to_string());
+    let args = build_ffmpeg_args(&p);
+
+    // The decoder leaves the grain out so it isn't encoded twice
+    let export = args.iter().position(|a| a == "-export_side_data").unwrap();
+    let input = args.iter().position(|a| a == "-i").unwrap();
+    assert!(export < input);
+    assert_eq!(args[export + 1], "film_grain");
+
+    let pos = args.iter().position(|a| a == "-svtav1-params").unwrap();
+    assert!(args[pos + 1].contains("fgs-table=/tmp/movie.grain.tbl"));
+    assert!(!args[pos + 1].contains("film-grain"));
+}
+
+#[test]
+fn deband_applies_to_8bit_sdr_sources_only() {
+    let mut config = test_config();
+    config.quality.deband = true;
+    let metadata = |pixel_format: &str, hdr_type| VideoMetadata {
+        width: 1920,
+        height: 1080,
+        hdr_type,
+        codec_name: "h264".to_string(),
+        pixel_format: Some(pixel_format.to_string()),
+        frame_rate_num: 24,
+        frame_rate_den: 1,
+        duration_secs: 60.0,
+        bitrate: None,
+    };
+    let filter = |m: &VideoMetadata, config: &AppConfig| {
+        let p = EncodingParams::from_metadata("in.mkv", "out.mkv", m, config, Default::default());
+        let args = build_ffmpeg_args(&p);
+        let vf = args.iter().position(|a| a == "-vf").unwrap();
+        args[vf + 1].clone()
+    };
+
+    let vf = filter(&metadata("yuv420p", HdrType::Sdr), &config);
+    assert!(vf.starts_with("deband="), "{}", vf);
+    assert!(vf.ends_with("format=yuv420p10le"), "{}", vf);
+    assert!(!filter(&metadata("yuv420p10le", HdrType::Sdr), &config).contains("deband"));
+    assert!(!filter(&metadata("yuv420p", HdrType::Pq), &config).contains("deband"));
+
+    config.quality.deband = false;
+    assert!(!filter(&metadata("yuv420p", HdrType::Sdr), &config).contains("deband"));
+}
+
+#[test]
+fn empty_selection_keeps_all_tracks() {
+    let args = build_ffmpeg_args(&params(Encoder::SvtAv1, HdrType::Sdr));
+    assert!(has_pair(&args, "-map", "0:a?"));
+    assert!(has_pair(&args, "-map", "0:s?"));
+    assert!(has_pair(&args, "-c:a", "copy"));
+}
+
+#[test]
+fn video_only_drops_audio_and_subtitles() {
+    let mut p = params(Encoder::Qsv, HdrType::Pq);
+    p.video_only = true;
+    let args = build_ffmpeg_args(&p);
+    assert!(has_pair(&args, "-map", "0:v:0"));
+    assert!(
+        !args
+            .iter()
+            .any(|a| a.starts_with("0:a") || a.starts_with("0:s"))
+    );
+    assert!(!args.iter().any(|a| a == "-c:a"));
+}
+
+#[test]
+fn qsv_options_pick_the_device_and_low_power_path() {
+    let mut p = params(Encoder::Qsv, HdrType::Sdr);
+    p.qsv_device = Some("/dev/dri/renderD129".to_string());
+    p.qsv_low_power = true;
+    p.qsv_async_depth = Some(4);
+    let args = build_ffmpeg_args(&p);
+    let device = args.iter().position(|a| a == "-qsv_device").unwrap();
+    let input = args.iter().position(|a| a == "-i").unwrap();
+    assert!(device < input);
+    assert!(has_pair(&args, "-qsv_device", "/dev/dri/renderD129"));
+    assert!(has_pair(&args, "-low_power", "1"));
+    assert!(has_pair(&args, "-async_depth", "4"));
+
+    // Other encoders never get them
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.qsv_device = Some("/dev/dri/renderD129".to_string());
+    p.qsv_low_power = true;
+    let args = build_ffmpeg_args(&p);
+    assert!(!args.iter().any(|a| a == "-qsv_device" || a == "-low_power"));
+}
+
+#[test]
+fn segment_seeks_before_input() {
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.segment = Some(Segment {
+        start: 300.0,
+        duration: Some(150.5),
+    });
+    let args = build_ffmpeg_args(&p);
+    let ss = args.iter().position(|a| a == "-ss").unwrap();
+    let input = args.iter().position(|a| a == "-i").unwrap();
+    assert!(ss < input);
+    assert!(has_pair(&args, "-ss", "300.000"));
+    assert!(has_pair(&args, "-t", "150.500"));
+
+    p.segment = Some(Segment {
+        start: 0.0,
+        duration: None,
+    });
+    assert!(!build_ffmpeg_args(&p).iter().any(|a| a == "-t"));
+}
+
+#[test]
+fn read_rate_throttles_the_input_on_ffmpeg_5_and_later() {
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.read_rate = Some(2.5);
+    let args = build_ffmpeg_args(&p);
+    let rate = args.iter().position(|a| a == "-readrate").unwrap();
+    let input = args.iter().position(|a| a == "-i").unwrap();
+    assert!(rate < input);
+    assert!(has_pair(&args, "-readrate", "2.5"));
+
+    p.apply_toolchain(&DependencyStatus {
+        ffmpeg: Some(Version::new(4, 4, 2)),
+        ..Default::default()
+    });
+    assert!(!build_ffmpeg_args(&p).iter().any(|a| a == "-readrate"));
+}
+
+#[test]
+fn film_grain_switches_svt_params() {
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.film_grain = 8;
+    let args = build_ffmpeg_args(&p);
+    assert!(
+        args.iter()
+            .any(|a| a.contains("film-grain=8:film-grain-denoise=1"))
+    );
+
+    p.film_grain = 0;
+    let args = build_ffmpeg_args(&p);
+    assert!(args.iter().any(|a| a.contains("film-grain=0")));
+}
+
+#[test]
+fn overrides_replace_quality_and_frame_rate_and_append_args() {
+    let mut p = params(Encoder::Nvenc, HdrType::Sdr);
+    p.extra_args = vec!["-g".to_string(), "240".to_string()];
+    p.apply_overrides(&EncodeOverrides {
+        crf: Some(40),
+        frame_rate: Some((10, 1)),
+        extra_args: vec!["-bf".to_string(), "3".to_string()],
+        env: vec![("CUDA_VISIBLE_DEVICES".to_string(), "1".to_string())],
+        ..Default::default()
+    });
+    let args = build_ffmpeg_args(&p);
+    assert!(has_pair(&args, "-cq", "40"));
+    assert!(has_pair(&args, "-r", "10/1"));
+
+    // Global then per-job extra args, just before the output
+    let n = args.len();
+    assert_eq!(&args[n - 5..n - 1], ["-g", "240", "-bf", "3"]);
+    assert_eq!(
+        p.env,
+        [("CUDA_VISIBLE_DEVICES".to_string(), "1".to_string())]
+    );
+}
+
+#[test]
+fn parse_extra_splits_env_and_quoted_args() {
+    let (env, args) =
+        EncodeOverrides::parse_extra("CUDA_VISIBLE_DEVICES=1 -g 240 -metadata \"title=My Film\"");
+    assert_eq!(env, [("CUDA_VISIBLE_DEVICES".to_string(), "1".to_string())]);
+    assert_eq!(args, ["-g", "240", "-metadata", "title=My Film"]);
+
+    // NAME=value after the first argument is an argument
+    let (env, args) = EncodeOverrides::parse_extra("-metadata A=b");
+    assert!(env.is_empty());
+    assert_eq!(args, ["-metadata", "A=b"]);
+}
+
+#[test]
+fn mux_args_take_tracks_from_source() {
+    let tracks = TrackSelection {
+        audio_indices: vec![2],
+        subtitle_indices: vec![],
+    };
+    let tags = [(
+        "AV1CONVERTER_VERSION".to_string(),
+        "av1converter 1.0".to_string(),
+    )];
+    let args = build_mux_args(
+        "video.mkv",
+        "source.mkv",
+        "out.mkv",
+        &tracks,
+        &StreamHandling::default(),
+        HdrType::Hlg,
+        &tags,
+    );
+    assert!(has_pair(&args, "-map", "0:v:0"));
+    assert!(has_pair(&args, "-map", "1:a:2"));
+    assert!(has_pair(&args, "-c", "copy"));
+    assert!(has_pair(&args, "-color_trc", "arib-std-b67"));
+    assert!(has_pair(
+        &args,
+        "-metadata",
+        "AV1CONVERTER_VERSION=av1converter 1.0"
+    ));
+    assert!(!args.iter().any(|a| a == "-movflags"));
+    assert_eq!(args.last().unwrap(), "out.mkv");
+}
+
+#[test]
+fn device_profile_scales_downmixes_and_drops_subtitles() {
+    let config = test_config();
+    let phone = config
+        .profiles
+        .iter()
+        .find(|p| p.name == "Phone 720p")
+        .unwrap();
+
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.apply_profile(phone);
+    let args = build_ffmpeg_args(&p);
+    let vf = &args[args.iter().position(|a| a == "-vf").unwrap() + 1];
+    assert!(vf.starts_with("scale=w='min(iw,1280)':h='min(ih,720)'"));
+    assert!(vf.split(',').any(|f| f == "format=yuv420p"));
+    assert!(has_pair(&args, "-c:a", "aac"));
+    assert!(has_pair(&args, "-ac", "2"));
+    assert!(has_pair(&args, "-b:a", "128k"));
+    assert!(!args.iter().any(|a| a == "0:s?" || a == "-c:s"));
+
+    // HDR stays 10-bit even when the profile asks for 8-bit
+    let mut p = params(Encoder::SvtAv1, HdrType::Pq);
+    p.apply_profile(phone);
+    let args = build_ffmpeg_args(&p);
+    assert!(args.iter().any(|a| a.contains("format=yuv420p10le")));
+}
+
+#[test]
+fn lossy_audio_is_copied_past_the_profile_reencode() {
+    let track = |codec: &str, kbps: u64| AudioTrack {
+        index: 0,
+        language: None,
+        codec: codec.to_string(),
+        channels: 2,
+        title: None,
+        bitrate: Some(kbps * 1000),
+        sample_rate: None,
+        disposition: Default::default(),
+    };
+    assert!(track("aac", 160).reencode_pointless(128));
+    assert!(track("ac3", 128).reencode_pointless(128));
+    assert!(!track("ac3", 640).reencode_pointless(128));
+    assert!(!track("flac", 900).reencode_pointless(128));
+    assert!(!track("pcm_s16le", 128).reencode_pointless(128));
+
+    let config = test_config();
+    let phone = config
+        .profiles
+        .iter()
+        .find(|p| p.name == "Phone 720p")
+        .unwrap();
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.apply_overrides(&EncodeOverrides {
+        profile: Some(phone.clone()),
+        copy_audio: vec![1],
+        ..Default::default()
+    });
+    let args = build_ffmpeg_args(&p);
+    let general = args.iter().position(|a| a == "-c:a").unwrap();
+    let copied = args.iter().position(|a| a == "-c:a:1").unwrap();
+    assert_eq!(args[general + 1], "aac");
+    assert_eq!(args[copied + 1], "copy");
+    assert!(general < copied);
+    assert!(!args.iter().any(|a| a == "-c:a:0"));
+}
+
+#[test]
+fn assumed_frame_rate_is_an_input_option() {
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.apply_overrides(&EncodeOverrides {
+        input_frame_rate: Some((25, 1)),
+        ..Default::default()
+    });
+    let args = build_ffmpeg_args(&p);
+    let rate = args.iter().position(|a| a == "-framerate").unwrap();
+    let input = args.iter().position(|a| a == "-i").unwrap();
+    assert_eq!(args[rate + 1], "25/1");
+    assert!(rate < input);
+}
+
+#[test]
+fn audio_bitrate_follows_codec_and_channel_count() {
+    let none = AudioBitrates::new();
+    assert_eq!(audio_bitrate(AudioCodec::Opus, 2, &none), 96);
+    assert_eq!(audio_bitrate(AudioCodec::Opus, 6, &none), 256);
+    assert_eq!(audio_bitrate(AudioCodec::Opus, 8, &none), 450);
+    assert_eq!(audio_bitrate(AudioCodec::Aac, 2, &none), 160);
+    // 3.0 gets stereo's rate per channel
+    assert_eq!(audio_bitrate(AudioCodec::Aac, 3, &none), 240);
+
+    // As written under [tracks.audio_bitrates.opus]
+    let custom: AudioBitrates = toml::from_str("[opus]\n2 = 128\n").unwrap();
+    assert_eq!(audio_bitrate(AudioCodec::Opus, 2, &custom), 128);
+    assert_eq!(audio_bitrate(AudioCodec::Opus, 6, &custom), 256);
+}
+
+#[test]
+fn profile_reencode_sets_a_bitrate_per_stream() {
+    let profile = DeviceProfile {
+        name: "Opus".to_string(),
+        max_width: None,
+        max_height: None,
+        eight_bit: false,
+        audio_channels: Some(6),
+        audio_codec: AudioCodec::Opus,
+        audio_bitrate_kbps: None,
+        container: None,
+        subtitles: true,
+    };
+    let mut p = params(Encoder::SvtAv1, HdrType::Sdr);
+    p.apply_overrides(&EncodeOverrides {
+        profile: Some(profile),
+        audio_channels: vec![8, 2],
+        ..Default::default()
+    });
+    let args = build_ffmpeg_args(&p);
+    assert!(has_pair(&args, "-c:a", "libopus"));
+    // 7.1 is downmixed to 5.1, stereo stays stereo
+    assert!(has_pair(&args, "-b:a:0", "256k"));
+    assert!(has_pair(&args, "-ac:a:0", "6"));
+    assert!(has_pair(&args, "-b:a:1", "96k"));
+    assert!(!args.iter().any(|a| a == "-ac:a:1" || a == "-ac"));
+}
+
+#[test]
+fn remux_mode_copies_every_stream() {
+    let mut p = params(Encoder::SvtAv1, HdrType::Pq);
+    p.apply_overrides(&EncodeOverrides {
+        mode: JobMode::Remux,
+        ..Default::default()
+    });
+    let args = build_ffmpeg_args(&p);
+    assert!(has_pair(&args, "-c:v", "copy"));
+    assert!(has_pair(&args, "-c:a", "copy"));
+    assert!(has_pair(&args, "-map_chapters", "0"));
+    assert!(
+        !args
+            .iter()
+            .any(|a| a == "-vf" || a == "-crf" || a == "-svtav1-params")
+    );
+    assert_eq!(args.last().unwrap(), &p.output);
+}
+
+#[test]
+fn audio_only_mode_reencodes_audio_beside_the_copied_video() {
+    let mut p = params(Encoder::Nvenc, HdrType::Sdr);
+    p.apply_overrides(&EncodeOverrides {
+        mode: JobMode::AudioOnly,
+        audio_channels: vec![6, 2],
+        copy_audio: vec![1],
+        ..Default::default()
+    });
+    let args = build_ffmpeg_args(&p);
+    assert!(has_pair(&args, "-c:v", "copy"));
+    assert!(has_pair(&args, "-c:a", "aac"));
+    assert!(has_pair(&args, "-b:a:0", "384k"));
+    assert!(has_pair(&args, "-c:a:1", "copy"));
+    // Surround stays surround
+    assert!(!args.iter().any(|a| a.starts_with("-ac")));
+    assert!(!args.iter().any(|a| a == "-hwaccel" || a == "-cq"));
+}
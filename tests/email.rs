@@ -0,0 +1,64 @@
+//! Batch report emails, sent through a stand-in for curl.
+#![cfg(unix)]
+
+use av1converter::config::NotificationConfig;
+use av1converter::notify::send_email;
+use av1converter::utils::paths;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+#[test]
+fn mail_is_sent_over_tls_with_a_private_message_file() {
+    let data = TempDir::new().unwrap();
+    paths::set_config_dir(data.path().to_path_buf());
+    let bin = TempDir::new().unwrap();
+    let log = bin.path().join("curl.log");
+    let curl = bin.path().join("curl");
+    std::fs::write(
+        &curl,
+        format!(
+            "#!/bin/sh\n\
+             log={}\n\
+             for arg in \"$@\"; do echo \"$arg\" >> \"$log\"; done\n\
+             while [ $# -gt 0 ]; do\n\
+               if [ \"$1\" = --upload-file ]; then stat -c 'mode %a' \"$2\" >> \"$log\"; fi\n\
+               shift\n\
+             done\n\
+             sed 's/^/stdin /' >> \"$log\"\n",
+            log.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&curl, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        bin.path().display(),
+        std::env::var("PATH").unwrap()
+    );
+    // The only test in this binary, so nothing else reads the environment meanwhile
+    unsafe { std::env::set_var("PATH", path) };
+
+    let config = NotificationConfig {
+        email_enabled: true,
+        smtp_url: "smtp://mail.example.com:587".to_string(),
+        smtp_username: Some("user".to_string()),
+        smtp_password: Some("secret".to_string()),
+        email_from: "av1converter@example.com".to_string(),
+        email_to: vec!["me@example.com".to_string()],
+    };
+    send_email(&config, "Done", "All files converted").unwrap();
+
+    let log = std::fs::read_to_string(&log).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert!(lines.contains(&"--ssl-reqd"), "{}", log);
+    assert!(!lines.contains(&"--ssl"), "{}", log);
+    assert!(lines.contains(&"--max-time"), "{}", log);
+    assert!(lines.contains(&"--connect-timeout"), "{}", log);
+    assert!(lines.contains(&"mode 600"), "{}", log);
+    assert!(lines.contains(&"stdin user = \"user:secret\""), "{}", log);
+
+    // The message is gone again and was never in the shared temp folder
+    let upload = lines[lines.iter().position(|l| *l == "--upload-file").unwrap() + 1];
+    assert!(upload.starts_with(&data.path().to_string_lossy().to_string()));
+    assert!(!std::path::Path::new(upload).exists());
+}
@@ -0,0 +1,29 @@
+//! Configurable video file extensions.
+
+use av1converter::config::InputConfig;
+use av1converter::queue::{configure_video_extensions, is_video_file};
+use std::path::Path;
+
+#[test]
+fn video_extensions_come_from_the_config() {
+    // Camcorder, DVD and older container formats are picked up by default
+    for name in [
+        "clip.MTS",
+        "VTS_01_1.vob",
+        "tape.mpg",
+        "phone.3gp",
+        "old.ogv",
+    ] {
+        assert!(is_video_file(Path::new(name)), "{}", name);
+    }
+    assert!(!is_video_file(Path::new("notes.txt")));
+    assert!(!is_video_file(Path::new("mkv")));
+
+    configure_video_extensions(&InputConfig {
+        video_extensions: vec![".MXF".to_string(), " dv ".to_string(), String::new()],
+        ..Default::default()
+    });
+    assert!(is_video_file(Path::new("camera/A001.mxf")));
+    assert!(is_video_file(Path::new("tape.DV")));
+    assert!(!is_video_file(Path::new("movie.mkv")));
+}
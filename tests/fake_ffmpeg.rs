@@ -0,0 +1,1723 @@
+//! End-to-end tests of the analyze → encode → mux → verify pipeline against the mock
+//! executables in `tests/fixtures/bin`, so no real encodes are needed.
+#![cfg(unix)]
+
+use av1converter::analyzer::detect::sniff_container;
+use av1converter::analyzer::{
+    DiscKind, HdrType, UnsupportedInput, VideoDetector, analyze_elementary, analyze_luma,
+    detect_disc, list_titles, probe_film_grain,
+};
+use av1converter::audit::{CodecFamily, Verdict, audit};
+use av1converter::config::{
+    DeleteMode, Encoder, HookConfig, HookPoint, InputConfig, NetworkSafeMode, OutputConfig,
+    PostStep, SharedConfig, SubtitleRule, ToolsConfig, TrackPresetConfig, TriageConfig,
+    VideoDetection,
+};
+use av1converter::encoder::chunked::{chunk_dir, encode_chunked};
+use av1converter::encoder::command_builder::EncodingParams;
+use av1converter::encoder::ffmpeg::EncodeResult;
+use av1converter::encoder::transfer::transfer_path;
+use av1converter::encoder::{EncodeOverrides, PipelineEvent, Stage, grain_table_path};
+use av1converter::error::{AppError, ErrorKind};
+use av1converter::queue::{
+    EncodingJob, PostAction, StepStatus, WorkerJob, WorkerMessage, post_actions,
+    run_streamed_worker, run_worker,
+};
+use av1converter::tracks::{TrackSelection, commentary_tracks};
+use av1converter::utils::claim::claim_path;
+use av1converter::utils::deps::{Feature, Version};
+use av1converter::utils::{DependencyStatus, paths};
+use av1converter::verifier::defects::DefectKind;
+use av1converter::verifier::{StreamMismatch, ValidationIssue};
+use av1converter::{AppConfig, ConversionPipeline, FullEncodeResult, analyze};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
+
+fn fixture_tools() -> ToolsConfig {
+    let bin = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bin");
+    let path = |name: &str| bin.join(name).to_string_lossy().to_string();
+    ToolsConfig {
+        ffmpeg: path("ffmpeg"),
+        ffprobe: path("ffprobe"),
+        mkvmerge: path("mkvmerge"),
+    }
+}
+
+fn test_config(vmaf_enabled: bool) -> AppConfig {
+    let mut config = AppConfig {
+        encoder: Encoder::SvtAv1,
+        tools: fixture_tools(),
+        ..AppConfig::default()
+    };
+    config.quality.vmaf_enabled = vmaf_enabled;
+    config.quality.vmaf_threshold = 90.0;
+    config.output.remux_after_encode = false;
+    config.performance.chunked_encoding = false;
+    // No trash in CI; removals are logged next to the test files
+    config.quality.delete_mode = DeleteMode::Permanent;
+    paths::set_config_dir(std::env::temp_dir().join("av1converter-tests"));
+    config
+}
+
+/// Write a source file whose contents steer the mock tools
+fn source(dir: &TempDir, name: &str, keywords: &str) -> PathBuf {
+    let path = dir.path().join(name);
+    std::fs::write(&path, format!("fake video\n{}\n", keywords)).unwrap();
+    path
+}
+
+fn output(dir: &TempDir, name: &str) -> PathBuf {
+    dir.path().join(name)
+}
+
+fn validation_issues(events: &[PipelineEvent]) -> Vec<ValidationIssue> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            PipelineEvent::ValidationFailed(issues) => Some(issues.clone()),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+#[test]
+fn analyze_reads_mock_ffprobe() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+
+    let result = analyze(source(&dir, "movie.mkv", "HDR10").to_str().unwrap()).unwrap();
+    assert_eq!(result.metadata.width, 1920);
+    assert_eq!(result.metadata.codec_name, "hevc");
+    assert!(result.metadata.hdr_type.is_hdr());
+    assert_eq!(result.metadata.duration_secs, 10.0);
+    assert_eq!(result.audio_tracks.len(), 2);
+    assert_eq!(result.subtitle_tracks.len(), 1);
+
+    // 640 kbps for 10 s, and a subtitle bitrate from the Matroska BPS tag
+    assert_eq!(result.audio_tracks[0].estimated_size(10.0), Some(800_000));
+    assert_eq!(result.subtitle_tracks[0].bitrate, Some(80));
+    assert_eq!(result.subtitle_tracks[0].estimated_size(10.0), Some(100));
+}
+
+#[test]
+fn corrupt_source_is_classified() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+
+    let err = analyze(source(&dir, "broken.mkv", "CORRUPT").to_str().unwrap()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::CorruptSource);
+    assert!(!err.kind().is_retryable());
+}
+
+#[test]
+fn luma_analysis_flags_dark_content() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+
+    let dark = source(&dir, "night.mkv", "DARK");
+    let luma = analyze_luma(dark.to_str().unwrap(), HdrType::Sdr).unwrap();
+    assert_eq!(luma.scene_luma.len(), 4);
+    assert_eq!(luma.dark_ratio(), 0.75);
+    assert!(luma.is_dark());
+
+    let bright = source(&dir, "day.mkv", "");
+    assert!(
+        !analyze_luma(bright.to_str().unwrap(), HdrType::Sdr)
+            .unwrap()
+            .is_dark()
+    );
+}
+
+#[test]
+fn toolchain_versions_are_detected() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let deps = DependencyStatus::detect();
+
+    assert!(deps.is_ready());
+    assert_eq!(deps.ffmpeg, Some(Version::new(7, 1, 0)));
+    assert_eq!(deps.libsvtav1, Some(Version::new(2, 3, 0)));
+    assert!(deps.supports(Feature::VarianceBoost));
+    // The mock FFmpeg is built without hardware encoders
+    assert!(deps.encoder_usable("libsvtav1"));
+    assert!(!deps.encoder_usable("av1_nvenc"));
+}
+
+#[test]
+fn av1_film_grain_becomes_a_grain_table() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+
+    let grainy = source(&dir, "grainy.mkv", "AV1 GRAIN");
+    let grain = probe_film_grain(grainy.to_str().unwrap())
+        .unwrap()
+        .expect("grain parameters");
+    assert_eq!(grain.seed, 4242);
+    assert_eq!(grain.y_points, vec![(0, 20), (64, 32), (255, 24)]);
+    assert_eq!(grain.ar_coeffs_cb, vec![1, 0, -1, 3, 5]);
+    assert!(grain.cr_points.is_empty());
+
+    let table = grain.to_table();
+    let lines: Vec<&str> = table.lines().map(str::trim).collect();
+    assert_eq!(lines[0], "filmgrn1");
+    assert_eq!(lines[1], format!("E 0 {} 1 4242 1", i64::MAX));
+    // Chroma multipliers and offsets are written as coded, not centred
+    assert_eq!(lines[2], "p 1 7 0 10 0 1 128 192 256 128 128 256");
+    assert_eq!(lines[3], "sY 3 0 20 64 32 255 24");
+    assert_eq!(lines[7], "cCb 1 0 -1 3 5");
+
+    let clean = source(&dir, "clean.mkv", "AV1");
+    assert_eq!(probe_film_grain(clean.to_str().unwrap()).unwrap(), None);
+}
+
+#[tokio::test]
+async fn reused_grain_table_is_removed_after_the_encode() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "grainy.mkv", "AV1 GRAIN");
+    let out = output(&dir, "grainy_av1.mkv");
+    let grain = probe_film_grain(input.to_str().unwrap()).unwrap();
+
+    let result = ConversionPipeline::new(&input)
+        .output(&out)
+        .config(test_config(false))
+        .overrides(EncodeOverrides {
+            source_grain: grain,
+            ..Default::default()
+        })
+        .run()
+        .await
+        .unwrap();
+
+    assert!(matches!(result, FullEncodeResult::Success), "{:?}", result);
+    assert!(out.exists());
+    assert!(!grain_table_path(&out).exists());
+}
+
+#[tokio::test]
+async fn chunks_of_a_changed_source_are_discarded() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "");
+    let out = output(&dir, "movie_av1.mkv");
+    let work_dir = chunk_dir(&out);
+    let metadata = analyze(input.to_str().unwrap()).unwrap().metadata;
+    let params = EncodingParams::from_metadata(
+        input.to_str().unwrap(),
+        out.to_str().unwrap(),
+        &metadata,
+        &test_config(false),
+        Default::default(),
+    );
+    // Already cancelled, so a run only prepares the chunk folder
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let run = || encode_chunked(&params, &work_dir, 4.0, 10.0, None, cancel.clone());
+
+    assert!(matches!(run().await, EncodeResult::Cancelled));
+    let chunk = work_dir.join("chunk_00000.mkv");
+    std::fs::write(&chunk, "chunk").unwrap();
+    assert!(matches!(run().await, EncodeResult::Cancelled));
+    assert!(chunk.exists());
+
+    // The same name with other contents starts over
+    std::fs::write(&input, "fake video\nreplaced with another cut\n").unwrap();
+    assert!(matches!(run().await, EncodeResult::Cancelled));
+    assert!(!chunk.exists());
+}
+
+#[tokio::test]
+async fn encode_reports_progress_and_stages() {
+    let dir = TempDir::new().unwrap();
+    let out = output(&dir, "movie_av1.mkv");
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let (p, e) = (progress.clone(), events.clone());
+    let result = ConversionPipeline::new(source(&dir, "movie.mkv", ""))
+        .output(&out)
+        .config(test_config(false))
+        .on_progress(move |update| p.lock().unwrap().push(update.percent))
+        .on_event(move |event| e.lock().unwrap().push(event))
+        .run()
+        .await
+        .unwrap();
+
+    assert!(matches!(result, FullEncodeResult::Success), "{:?}", result);
+    assert!(out.exists());
+
+    let progress = progress.lock().unwrap();
+    assert!(progress.windows(2).all(|w| w[0] <= w[1]), "{:?}", progress);
+    assert_eq!(progress.first(), Some(&25.0));
+    assert_eq!(progress.last(), Some(&100.0));
+
+    let events = events.lock().unwrap();
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, PipelineEvent::StageFinished(Stage::Encode, _)))
+    );
+    let provenance = events
+        .iter()
+        .find_map(|e| match e {
+            PipelineEvent::Provenance(provenance) => Some(provenance),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        provenance.app,
+        format!("av1converter {}", env!("CARGO_PKG_VERSION"))
+    );
+    assert!(
+        provenance.tools.starts_with("FFmpeg "),
+        "{}",
+        provenance.tools
+    );
+    assert!(provenance.command.contains("libsvtav1"));
+    assert!(
+        provenance.command.ends_with("movie_av1.mkv"),
+        "{}",
+        provenance.command
+    );
+}
+
+#[tokio::test]
+async fn simulation_writes_nothing_and_reports_the_encode() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "VMAF=96.5");
+    let out = output(&dir, "movie_av1.mkv");
+    let progress = Arc::new(Mutex::new(Vec::new()));
+
+    let p = progress.clone();
+    let result = ConversionPipeline::new(&input)
+        .output(&out)
+        .config(test_config(true))
+        .overrides(EncodeOverrides {
+            crf: Some(33),
+            simulate: true,
+            ..Default::default()
+        })
+        .on_progress(move |update| p.lock().unwrap().push(update.percent))
+        .run()
+        .await
+        .unwrap();
+
+    let FullEncodeResult::Simulated(simulated) = result else {
+        panic!("{:?}", result);
+    };
+    assert_eq!(simulated.crf, 33);
+    assert!(simulated.deletes_source);
+    assert!(simulated.command.ends_with(out.to_str().unwrap()));
+    assert!(simulated.estimated_size.is_some());
+    assert_eq!(progress.lock().unwrap().last(), Some(&100.0));
+    assert!(input.exists());
+    assert!(!out.exists());
+}
+
+#[tokio::test]
+async fn passing_vmaf_deletes_source() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "VMAF=96.5");
+    let out = output(&dir, "movie_av1.mkv");
+
+    let result = ConversionPipeline::new(&input)
+        .output(&out)
+        .config(test_config(true))
+        .run()
+        .await
+        .unwrap();
+
+    match result {
+        FullEncodeResult::SuccessWithVmaf {
+            vmaf,
+            source_deleted,
+        } => {
+            assert_eq!(vmaf.score, 96.5);
+            assert!(source_deleted);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(!input.exists());
+    assert!(out.exists());
+}
+
+#[tokio::test]
+async fn hooks_get_the_job_and_decide_its_outcome() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "VMAF=96.5");
+    let out = output(&dir, "movie_av1.mkv");
+    let received = dir.path().join("job.json");
+    let hook = |at: HookPoint, script: &str| HookConfig {
+        at,
+        command: "sh".to_string(),
+        args: vec![
+            "-c".to_string(),
+            script.to_string(),
+            received.to_string_lossy().to_string(),
+        ],
+        timeout_secs: 10,
+    };
+
+    let mut config = test_config(true);
+    config.hooks = vec![hook(
+        HookPoint::AfterVerify,
+        r#"cat > "$0"; echo '{"verdict": "keep_source", "reason": "tags pending"}'"#,
+    )];
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    let result = ConversionPipeline::new(&input)
+        .output(&out)
+        .config(config)
+        .on_event(move |e| sink.lock().unwrap().push(e))
+        .run()
+        .await
+        .unwrap();
+    assert!(matches!(
+        result,
+        FullEncodeResult::SuccessWithVmaf {
+            source_deleted: false,
+            ..
+        }
+    ));
+    assert!(input.exists());
+    assert!(
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| matches!(e, PipelineEvent::SourceKept(reason) if reason == "tags pending"))
+    );
+    let job: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&received).unwrap()).unwrap();
+    assert_eq!(job["hook"], "after_verify");
+    assert_eq!(job["vmaf"], 96.5);
+    assert_eq!(job["output"], out.to_string_lossy().as_ref());
+
+    // A failing verdict before the encode stops the job there
+    std::fs::remove_file(&out).unwrap();
+    let mut config = test_config(true);
+    config.hooks = vec![hook(
+        HookPoint::BeforeEncode,
+        r#"echo '{"verdict": "fail", "reason": "no upscaler"}'"#,
+    )];
+    let result = ConversionPipeline::new(&input)
+        .output(&out)
+        .config(config)
+        .run()
+        .await
+        .unwrap();
+    match result {
+        FullEncodeResult::Error(e) => assert!(e.to_string().contains("no upscaler"), "{}", e),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(!out.exists());
+}
+
+#[tokio::test]
+async fn cancelling_during_a_hook_stops_the_job() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "VMAF=96.5");
+    let out = output(&dir, "movie_av1.mkv");
+    let mut config = test_config(true);
+    config.hooks = vec![HookConfig {
+        at: HookPoint::AfterEncode,
+        command: "sh".to_string(),
+        args: vec!["-c".to_string(), "sleep 30".to_string()],
+        timeout_secs: 60,
+    }];
+
+    let cancel = CancellationToken::new();
+    let stop = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        stop.cancel();
+    });
+    let started = std::time::Instant::now();
+    let result = ConversionPipeline::new(&input)
+        .output(&out)
+        .config(config)
+        .cancel_token(cancel)
+        .run()
+        .await
+        .unwrap();
+    assert!(
+        matches!(result, FullEncodeResult::Cancelled),
+        "{:?}",
+        result
+    );
+    assert!(started.elapsed() < std::time::Duration::from_secs(20));
+    // Neither the verification nor the source deletion ran
+    assert!(!out.exists());
+    assert!(input.exists());
+}
+
+#[tokio::test]
+async fn undersized_output_keeps_source() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "VMAF=96.5\nTINY_OUTPUT");
+    // Pad the source so the two-line output is far below a plausible AV1 size
+    let mut contents = std::fs::read_to_string(&input).unwrap();
+    contents.push_str(&"padding\n".repeat(8192));
+    std::fs::write(&input, contents).unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let e = events.clone();
+    let result = ConversionPipeline::new(&input)
+        .output(output(&dir, "movie_av1.mkv"))
+        .config(test_config(true))
+        .on_event(move |event| e.lock().unwrap().push(event))
+        .run()
+        .await
+        .unwrap();
+
+    assert!(
+        matches!(
+            result,
+            FullEncodeResult::SuccessWithVmaf {
+                source_deleted: false,
+                ..
+            }
+        ),
+        "{:?}",
+        result
+    );
+    assert!(input.exists());
+    let events = events.lock().unwrap();
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, PipelineEvent::SourceKept(reason) if reason.contains("expected")))
+    );
+}
+
+#[tokio::test]
+async fn network_output_is_staged_and_copied_before_the_source_goes() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "VMAF=96.5");
+    let out = output(&dir, "share/movie_av1.mkv");
+    let staging = dir.path().join("staging");
+    let mut config = test_config(true);
+    config.output.network_safe_mode = NetworkSafeMode::Always;
+    config.output.staging_directory = Some(staging.to_string_lossy().to_string());
+
+    let result = ConversionPipeline::new(&input)
+        .output(&out)
+        .config(config)
+        .run()
+        .await
+        .unwrap();
+
+    assert!(
+        matches!(
+            result,
+            FullEncodeResult::SuccessWithVmaf {
+                source_deleted: true,
+                ..
+            }
+        ),
+        "{:?}",
+        result
+    );
+    assert!(out.exists());
+    assert!(!transfer_path(&out).exists());
+    assert_eq!(std::fs::read_dir(&staging).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn a_job_failing_after_a_staged_encode_leaves_no_staged_file() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "VMAF=96.5");
+    let out = output(&dir, "share/movie_av1.mkv");
+    let staging = dir.path().join("staging");
+    let mut config = test_config(true);
+    config.output.network_safe_mode = NetworkSafeMode::Always;
+    config.output.staging_directory = Some(staging.to_string_lossy().to_string());
+    config.hooks = vec![HookConfig {
+        at: HookPoint::AfterEncode,
+        command: "sh".to_string(),
+        args: vec![
+            "-c".to_string(),
+            r#"echo '{"verdict": "fail", "reason": "no tags"}'"#.to_string(),
+        ],
+        timeout_secs: 10,
+    }];
+
+    let result = ConversionPipeline::new(&input)
+        .output(&out)
+        .config(config)
+        .run()
+        .await
+        .unwrap();
+
+    assert!(matches!(result, FullEncodeResult::Error(_)), "{:?}", result);
+    assert!(!out.exists());
+    assert_eq!(std::fs::read_dir(&staging).unwrap().count(), 0);
+    assert!(input.exists());
+}
+
+#[tokio::test]
+async fn audio_drift_keeps_source() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "DRIFT");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let e = events.clone();
+    let result = ConversionPipeline::new(&input)
+        .output(output(&dir, "movie_av1.mkv"))
+        .config(test_config(true))
+        .on_event(move |event| e.lock().unwrap().push(event))
+        .run()
+        .await
+        .unwrap();
+
+    assert!(
+        matches!(
+            result,
+            FullEncodeResult::SuccessWithVmaf {
+                source_deleted: false,
+                ..
+            }
+        ),
+        "{:?}",
+        result
+    );
+    assert!(input.exists());
+
+    let events = events.lock().unwrap();
+    match validation_issues(&events).as_slice() {
+        [ValidationIssue::Drift(drift)] => {
+            assert_eq!(drift.stream, "audio 1");
+            assert_eq!(drift.drift_secs(), -2.0);
+        }
+        other => panic!("unexpected issues: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn dropped_subtitle_keeps_source() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "DROP_SUBS");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let e = events.clone();
+    ConversionPipeline::new(&input)
+        .output(output(&dir, "movie_av1.mkv"))
+        .config(test_config(true))
+        .on_event(move |event| e.lock().unwrap().push(event))
+        .run()
+        .await
+        .unwrap();
+    assert!(input.exists());
+
+    let events = events.lock().unwrap();
+    match validation_issues(&events).as_slice() {
+        [ValidationIssue::Stream(StreamMismatch::Missing { stream, expected })] => {
+            assert_eq!(stream, "subtitle 1");
+            assert_eq!(expected, "subrip, eng");
+        }
+        other => panic!("unexpected issues: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn selected_tracks_pass_validation() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    // The mock output always carries every stream, so only a full selection lines up
+    let e = events.clone();
+    let result = ConversionPipeline::new(&input)
+        .output(output(&dir, "movie_av1.mkv"))
+        .config(test_config(true))
+        .tracks(TrackSelection {
+            audio_indices: vec![0, 1],
+            subtitle_indices: vec![0],
+        })
+        .on_event(move |event| e.lock().unwrap().push(event))
+        .run()
+        .await
+        .unwrap();
+
+    assert!(validation_issues(&events.lock().unwrap()).is_empty());
+    assert!(matches!(
+        result,
+        FullEncodeResult::SuccessWithVmaf {
+            source_deleted: true,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn washed_out_hdr_output_keeps_source() {
+    let dir = TempDir::new().unwrap();
+    let full_selection = TrackSelection {
+        audio_indices: vec![0, 1],
+        subtitle_indices: vec![0],
+    };
+
+    for (name, keywords, washed_out) in [
+        ("movie.mkv", "HDR10\nWASHED_OUT", true),
+        ("show.mkv", "HDR10", false),
+    ] {
+        let input = source(&dir, name, keywords);
+        let out_name = name.replace(".mkv", "_av1.mkv");
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let e = events.clone();
+        ConversionPipeline::new(&input)
+            .output(output(&dir, &out_name))
+            .config(test_config(true))
+            .tracks(full_selection.clone())
+            .on_event(move |event| e.lock().unwrap().push(event))
+            .run()
+            .await
+            .unwrap();
+
+        let issues = validation_issues(&events.lock().unwrap());
+        if !washed_out {
+            assert!(issues.is_empty(), "{:?}", issues);
+            assert!(!input.exists());
+            continue;
+        }
+        assert!(input.exists());
+        match issues.as_slice() {
+            [ValidationIssue::Hdr(mismatch)] => {
+                assert!(mismatch.output.average > mismatch.source.average);
+                assert_eq!(mismatch.source.highlights, 640.0);
+                assert_eq!(mismatch.output.highlights, 470.0);
+                assert!(mismatch.describe().starts_with("HDR mismatch"));
+            }
+            other => panic!("unexpected issues: {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn frozen_output_segment_keeps_source() {
+    let dir = TempDir::new().unwrap();
+    let full_selection = TrackSelection {
+        audio_indices: vec![0, 1],
+        subtitle_indices: vec![0],
+    };
+
+    // A still shot the source has too is not a defect
+    for (name, keywords, frozen) in [
+        ("movie.mkv", "FROZEN", true),
+        ("still.mkv", "STILL_SHOT", false),
+    ] {
+        let input = source(&dir, name, keywords);
+        let out_name = name.replace(".mkv", "_av1.mkv");
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let e = events.clone();
+        ConversionPipeline::new(&input)
+            .output(output(&dir, &out_name))
+            .config(test_config(true))
+            .tracks(full_selection.clone())
+            .on_event(move |event| e.lock().unwrap().push(event))
+            .run()
+            .await
+            .unwrap();
+
+        let issues = validation_issues(&events.lock().unwrap());
+        if !frozen {
+            assert!(issues.is_empty(), "{:?}", issues);
+            assert!(!input.exists());
+            continue;
+        }
+        assert!(input.exists());
+        match issues.as_slice() {
+            [ValidationIssue::Defect(defect)] => {
+                assert_eq!(defect.kind, DefectKind::Frozen);
+                assert_eq!(
+                    defect.describe(),
+                    "Frozen picture at 00:03–00:07 not in the source"
+                );
+            }
+            other => panic!("unexpected issues: {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn low_vmaf_keeps_source() {
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "VMAF=80");
+
+    let result = ConversionPipeline::new(&input)
+        .output(output(&dir, "movie_av1.mkv"))
+        .config(test_config(true))
+        .run()
+        .await
+        .unwrap();
+
+    match result {
+        FullEncodeResult::QualityWarning { vmaf, threshold } => {
+            assert_eq!(vmaf.score, 80.0);
+            assert_eq!(threshold, 90.0);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(input.exists());
+}
+
+#[tokio::test]
+async fn encoder_failures_are_classified() {
+    for (keyword, kind) in [
+        ("FAIL_DISKFULL", ErrorKind::DiskFull),
+        ("FAIL_SESSION", ErrorKind::EncoderSession),
+    ] {
+        let dir = TempDir::new().unwrap();
+        let out = output(&dir, "movie_av1.mkv");
+
+        let result = ConversionPipeline::new(source(&dir, "movie.mkv", keyword))
+            .output(&out)
+            .config(test_config(false))
+            .run()
+            .await
+            .unwrap();
+
+        match result {
+            FullEncodeResult::Error(e) => {
+                assert_eq!(e.kind(), kind, "{}", e);
+                assert!(e.kind().is_retryable());
+            }
+            other => panic!("unexpected result for {}: {:?}", keyword, other),
+        }
+        assert!(!out.exists());
+    }
+}
+
+#[tokio::test]
+async fn unsupported_hardware_format_falls_back_to_software() {
+    let dir = TempDir::new().unwrap();
+    let out = output(&dir, "movie_av1.mkv");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let mut config = test_config(false);
+    config.encoder = Encoder::Nvenc;
+    let e = events.clone();
+    let result = ConversionPipeline::new(source(&dir, "movie.mkv", "HDR10"))
+        .output(&out)
+        .config(config.clone())
+        .on_event(move |event| e.lock().unwrap().push(event))
+        .run()
+        .await
+        .unwrap();
+    assert!(matches!(result, FullEncodeResult::Success), "{:?}", result);
+    assert!(events.lock().unwrap().iter().any(|e| matches!(
+        e,
+        PipelineEvent::EncoderFallback { from: Encoder::Nvenc, reason } if reason.contains("10-bit")
+    )));
+
+    // Without the fallback the file fails before encoding
+    std::fs::remove_file(&out).unwrap();
+    config.performance.hardware_fallback = false;
+    let result = ConversionPipeline::new(source(&dir, "movie.mkv", "HDR10"))
+        .output(&out)
+        .config(config)
+        .run()
+        .await
+        .unwrap();
+    assert!(matches!(result, FullEncodeResult::Error(_)), "{:?}", result);
+    assert!(!out.exists());
+}
+
+#[tokio::test]
+async fn cancelling_stops_the_encode() {
+    let dir = TempDir::new().unwrap();
+    let out = output(&dir, "movie_av1.mkv");
+
+    let handle = ConversionPipeline::new(source(&dir, "movie.mkv", "SLOW"))
+        .output(&out)
+        .config(test_config(false))
+        .spawn();
+
+    // Wait for the first progress block, then cancel
+    while handle.percent() == 0.0 && !handle.is_finished() {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    handle.cancel();
+
+    let result = handle.join().await.unwrap();
+    assert!(
+        matches!(result, FullEncodeResult::Cancelled),
+        "{:?}",
+        result
+    );
+    assert!(!out.exists());
+}
+
+#[tokio::test]
+async fn worker_continues_after_a_failed_job() {
+    let config = test_config(false);
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let queue_token = CancellationToken::new();
+
+    let jobs: Vec<WorkerJob> = ["FAIL_DISKFULL", ""]
+        .iter()
+        .enumerate()
+        .map(|(index, keywords)| {
+            let input = source(&dir, &format!("movie{}.mkv", index), keywords);
+            WorkerJob {
+                index,
+                metadata: analyze(input.to_str().unwrap()).unwrap().metadata,
+                output: output(&dir, &format!("movie{}_av1.mkv", index)),
+                input,
+                tracks: TrackSelection::default(),
+                overrides: Default::default(),
+                after: Vec::new(),
+                cancel: queue_token.child_token(),
+            }
+        })
+        .collect();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    run_worker(jobs, config.into(), queue_token, tx).await;
+
+    let mut failed = Vec::new();
+    let mut done = Vec::new();
+    while let Ok(msg) = rx.try_recv() {
+        match msg {
+            WorkerMessage::Error(idx, e) => failed.push((idx, e.kind())),
+            WorkerMessage::Done(idx) => done.push(idx),
+            WorkerMessage::Cancelled => panic!("queue was not cancelled"),
+            _ => {}
+        }
+    }
+    assert_eq!(failed, [(0, ErrorKind::DiskFull)]);
+    assert_eq!(done, [1]);
+}
+
+#[tokio::test]
+async fn streamed_worker_encodes_jobs_as_they_arrive() {
+    let config = test_config(false);
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let queue_token = CancellationToken::new();
+    let job = |index: usize| {
+        let input = source(&dir, &format!("movie{}.mkv", index), "");
+        WorkerJob {
+            index,
+            metadata: analyze(input.to_str().unwrap()).unwrap().metadata,
+            output: output(&dir, &format!("movie{}_av1.mkv", index)),
+            input,
+            tracks: TrackSelection::default(),
+            overrides: Default::default(),
+            after: Vec::new(),
+            cancel: queue_token.child_token(),
+        }
+    };
+
+    let (job_tx, job_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let worker = tokio::spawn(run_streamed_worker(
+        job_rx,
+        config.into(),
+        queue_token.clone(),
+        tx,
+    ));
+
+    // The first file finishes before the second one is even configured
+    job_tx.send(job(0)).unwrap();
+    loop {
+        match rx.recv().await {
+            Some(WorkerMessage::Done(0)) => break,
+            Some(WorkerMessage::Error(_, e)) => panic!("{}", e),
+            Some(_) => {}
+            None => panic!("worker stopped early"),
+        }
+    }
+    assert!(!worker.is_finished());
+
+    job_tx.send(job(1)).unwrap();
+    drop(job_tx);
+    worker.await.unwrap();
+
+    let mut done = Vec::new();
+    while let Ok(msg) = rx.try_recv() {
+        match msg {
+            WorkerMessage::Done(idx) => done.push(idx),
+            WorkerMessage::Cancelled => panic!("queue was not cancelled"),
+            _ => {}
+        }
+    }
+    assert_eq!(done, [1]);
+}
+
+#[tokio::test]
+async fn idle_streamed_worker_stops_on_cancel() {
+    let config = test_config(false);
+    let queue_token = CancellationToken::new();
+    let (_job_tx, job_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let worker = tokio::spawn(run_streamed_worker(
+        job_rx,
+        config.into(),
+        queue_token.clone(),
+        tx,
+    ));
+
+    queue_token.cancel();
+    worker.await.unwrap();
+    assert!(matches!(rx.try_recv(), Ok(WorkerMessage::Cancelled)));
+}
+
+#[tokio::test]
+async fn quality_changed_mid_queue_applies_to_the_running_job() {
+    let config = test_config(true);
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "SLOW\nVMAF=96.5");
+    let job = WorkerJob {
+        index: 0,
+        metadata: analyze(input.to_str().unwrap()).unwrap().metadata,
+        output: output(&dir, "movie_av1.mkv"),
+        input: input.clone(),
+        tracks: TrackSelection::default(),
+        overrides: Default::default(),
+        after: Vec::new(),
+        cancel: CancellationToken::new(),
+    };
+
+    let shared = SharedConfig::new(config.clone());
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let worker = tokio::spawn(run_worker(
+        vec![job],
+        shared.clone(),
+        CancellationToken::new(),
+        tx,
+    ));
+
+    // Raise the threshold above the score while the file is still encoding
+    assert!(matches!(
+        rx.recv().await,
+        Some(WorkerMessage::Progress(0, _))
+    ));
+    let mut stricter = config;
+    stricter.quality.vmaf_threshold = 99.0;
+    shared.set(stricter);
+    worker.await.unwrap();
+
+    let mut warned = false;
+    while let Ok(msg) = rx.try_recv() {
+        if let WorkerMessage::QualityWarning(0, _, threshold) = msg {
+            warned = threshold == 99.0;
+        }
+    }
+    assert!(warned);
+    assert!(input.exists());
+}
+
+#[tokio::test]
+async fn steps_after_the_encode_run_in_order_once_it_passed() {
+    let config = test_config(false);
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let queue_token = CancellationToken::new();
+
+    // The library keeps the folder below the queued one
+    let output_config = OutputConfig {
+        after_encode: vec![PostStep::ExtractSubtitles, PostStep::MoveToLibrary],
+        library_directory: Some(dir.path().join("library").to_string_lossy().to_string()),
+        ..OutputConfig::default()
+    };
+    std::fs::create_dir(dir.path().join("Show")).unwrap();
+    let after = post_actions(
+        &output_config,
+        &dir.path().join("Show/movie0.mkv"),
+        Some(dir.path()),
+    );
+    let library = dir.path().join("library/Show");
+    assert_eq!(
+        after,
+        [
+            PostAction::ExtractSubtitles,
+            PostAction::MoveTo(library.clone())
+        ]
+    );
+
+    let jobs: Vec<WorkerJob> = ["", "FAIL_DISKFULL"]
+        .iter()
+        .enumerate()
+        .map(|(index, keywords)| {
+            let input = source(&dir, &format!("Show/movie{}.mkv", index), keywords);
+            WorkerJob {
+                index,
+                metadata: analyze(input.to_str().unwrap()).unwrap().metadata,
+                output: output(&dir, &format!("Show/movie{}_av1.mkv", index)),
+                input,
+                tracks: TrackSelection::default(),
+                overrides: Default::default(),
+                after: after.clone(),
+                cancel: queue_token.child_token(),
+            }
+        })
+        .collect();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    run_worker(jobs, config.into(), queue_token, tx).await;
+
+    let mut steps = Vec::new();
+    let mut moved = None;
+    let mut done_after_steps = false;
+    while let Ok(msg) = rx.try_recv() {
+        match msg {
+            WorkerMessage::Step(idx, position, status) => steps.push((idx, position, status)),
+            WorkerMessage::OutputMoved(0, path) => moved = Some(path),
+            WorkerMessage::Done(0) => done_after_steps = steps.len() == 4,
+            _ => {}
+        }
+    }
+    assert!(done_after_steps, "the job finished before its steps");
+    assert_eq!(
+        steps,
+        [
+            (0, 0, StepStatus::Running),
+            (0, 0, StepStatus::Done("1 file".to_string())),
+            (0, 1, StepStatus::Running),
+            (
+                0,
+                1,
+                StepStatus::Done(library.join("movie0_av1.mkv").display().to_string())
+            ),
+            (1, 0, StepStatus::Skipped("Output did not pass".to_string())),
+            (1, 1, StepStatus::Skipped("Output did not pass".to_string())),
+        ]
+    );
+    assert_eq!(moved, Some(library.join("movie0_av1.mkv")));
+    assert!(library.join("movie0_av1.eng.srt").exists());
+    assert!(!dir.path().join("Show/movie0_av1.mkv").exists());
+    assert!(!dir.path().join("Show/movie0_av1.eng.srt").exists());
+}
+
+#[tokio::test]
+async fn cancelled_job_is_skipped_without_stopping_the_queue() {
+    let config = test_config(false);
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let queue_token = CancellationToken::new();
+
+    let jobs: Vec<WorkerJob> = (0..2)
+        .map(|index| {
+            let input = source(&dir, &format!("movie{}.mkv", index), "");
+            WorkerJob {
+                index,
+                metadata: analyze(input.to_str().unwrap()).unwrap().metadata,
+                output: output(&dir, &format!("movie{}_av1.mkv", index)),
+                input,
+                tracks: TrackSelection::default(),
+                overrides: Default::default(),
+                after: Vec::new(),
+                cancel: queue_token.child_token(),
+            }
+        })
+        .collect();
+    jobs[0].cancel.cancel();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    run_worker(jobs, config.into(), queue_token, tx).await;
+
+    let mut cancelled = Vec::new();
+    let mut done = Vec::new();
+    while let Ok(msg) = rx.try_recv() {
+        match msg {
+            WorkerMessage::JobCancelled(idx) => cancelled.push(idx),
+            WorkerMessage::Done(idx) => done.push(idx),
+            _ => {}
+        }
+    }
+    assert_eq!(cancelled, [0]);
+    assert_eq!(done, [1]);
+}
+
+/// Worker jobs writing one output each for the same source
+fn sibling_jobs(
+    dir: &TempDir,
+    input: &Path,
+    profiles: &[Option<&str>],
+    config: &AppConfig,
+    queue_token: &CancellationToken,
+) -> Vec<WorkerJob> {
+    profiles
+        .iter()
+        .enumerate()
+        .map(|(index, name)| WorkerJob {
+            index,
+            input: input.to_path_buf(),
+            output: output(dir, &format!("movie_av1_{}.mkv", index)),
+            metadata: analyze(input.to_str().unwrap()).unwrap().metadata,
+            tracks: TrackSelection::default(),
+            overrides: EncodeOverrides {
+                profile: name.map(|name| {
+                    config
+                        .profiles
+                        .iter()
+                        .find(|p| p.name == name)
+                        .unwrap()
+                        .clone()
+                }),
+                ..Default::default()
+            },
+            after: Vec::new(),
+            cancel: queue_token.child_token(),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn only_the_last_output_deletes_the_source() {
+    let config = test_config(true);
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "");
+    let queue_token = CancellationToken::new();
+    let jobs = sibling_jobs(&dir, &input, &[None, None], &config, &queue_token);
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    run_worker(jobs, config.into(), queue_token, tx).await;
+
+    let mut done = Vec::new();
+    let mut deleted = Vec::new();
+    while let Ok(msg) = rx.try_recv() {
+        match msg {
+            WorkerMessage::DoneWithVmaf(idx, _) => done.push(idx),
+            WorkerMessage::SourceDeleted(idx) => deleted.push(idx),
+            _ => {}
+        }
+    }
+    assert_eq!(done, [0, 1]);
+    assert_eq!(deleted, [1]);
+    assert!(!input.exists());
+}
+
+#[tokio::test]
+async fn parallel_jobs_encode_side_by_side() {
+    let mut config = test_config(false);
+    config.performance.parallel_jobs = 2;
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let queue_token = CancellationToken::new();
+
+    let jobs: Vec<WorkerJob> = (0..3)
+        .map(|index| {
+            let input = source(&dir, &format!("movie{}.mkv", index), "");
+            WorkerJob {
+                index,
+                metadata: analyze(input.to_str().unwrap()).unwrap().metadata,
+                output: output(&dir, &format!("movie{}_av1.mkv", index)),
+                input,
+                tracks: TrackSelection::default(),
+                overrides: Default::default(),
+                after: Vec::new(),
+                cancel: queue_token.child_token(),
+            }
+        })
+        .collect();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    run_worker(jobs, config.into(), queue_token, tx).await;
+
+    // The second job starts before the first one finishes
+    let mut started = Vec::new();
+    let mut done = Vec::new();
+    let mut started_before_first_done = 0;
+    while let Ok(msg) = rx.try_recv() {
+        match msg {
+            WorkerMessage::Progress(idx, _) if !started.contains(&idx) => {
+                started.push(idx);
+                if done.is_empty() {
+                    started_before_first_done += 1;
+                }
+            }
+            WorkerMessage::Done(idx) => done.push(idx),
+            WorkerMessage::Cancelled => panic!("queue was not cancelled"),
+            _ => {}
+        }
+    }
+    assert_eq!(started_before_first_done, 2);
+    done.sort();
+    assert_eq!(done, [0, 1, 2]);
+}
+
+#[tokio::test]
+async fn parallel_outputs_of_one_source_still_run_in_turn() {
+    let mut config = test_config(true);
+    config.performance.parallel_jobs = 2;
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "");
+    let queue_token = CancellationToken::new();
+    let jobs = sibling_jobs(&dir, &input, &[None, None], &config, &queue_token);
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    run_worker(jobs, config.into(), queue_token, tx).await;
+
+    let mut events = Vec::new();
+    while let Ok(msg) = rx.try_recv() {
+        match msg {
+            WorkerMessage::Progress(idx, _) if !events.contains(&("start", idx)) => {
+                events.push(("start", idx))
+            }
+            WorkerMessage::DoneWithVmaf(idx, _) => events.push(("done", idx)),
+            WorkerMessage::SourceDeleted(idx) => events.push(("deleted", idx)),
+            _ => {}
+        }
+    }
+    assert_eq!(
+        events,
+        [
+            ("start", 0),
+            ("done", 0),
+            ("start", 1),
+            ("deleted", 1),
+            ("done", 1)
+        ]
+    );
+    assert!(!input.exists());
+}
+
+#[tokio::test]
+async fn a_source_waiting_for_its_sibling_holds_up_no_other_file() {
+    let mut config = test_config(false);
+    config.performance.parallel_jobs = 2;
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "");
+    let queue_token = CancellationToken::new();
+    let mut jobs = sibling_jobs(&dir, &input, &[None, None], &config, &queue_token);
+    let other = source(&dir, "other.mkv", "");
+    jobs.push(WorkerJob {
+        index: 2,
+        metadata: analyze(other.to_str().unwrap()).unwrap().metadata,
+        output: output(&dir, "other_av1.mkv"),
+        input: other,
+        tracks: TrackSelection::default(),
+        overrides: Default::default(),
+        after: Vec::new(),
+        cancel: queue_token.child_token(),
+    });
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    run_worker(jobs, config.into(), queue_token, tx).await;
+
+    // The other file starts beside the first output, ahead of the second
+    let mut started = Vec::new();
+    let mut done = Vec::new();
+    while let Ok(msg) = rx.try_recv() {
+        match msg {
+            WorkerMessage::Progress(idx, _) if !started.contains(&idx) => started.push(idx),
+            WorkerMessage::Done(idx) => done.push(idx),
+            _ => {}
+        }
+        if done.is_empty() && started.len() == 2 {
+            break;
+        }
+    }
+    assert_eq!(started, [0, 2]);
+}
+
+#[tokio::test]
+async fn failed_output_keeps_the_source_for_its_siblings() {
+    let config = test_config(true);
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "");
+    let queue_token = CancellationToken::new();
+    // The mock keeps subtitles, which the phone profile drops, so its output fails validation
+    let jobs = sibling_jobs(
+        &dir,
+        &input,
+        &[Some("Phone 720p"), None],
+        &config,
+        &queue_token,
+    );
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    run_worker(jobs, config.into(), queue_token, tx).await;
+
+    let mut invalid = Vec::new();
+    while let Ok(msg) = rx.try_recv() {
+        match msg {
+            WorkerMessage::ValidationIssues(idx, _) => invalid.push(idx),
+            WorkerMessage::SourceDeleted(_) => panic!("source was deleted"),
+            _ => {}
+        }
+    }
+    assert_eq!(invalid, [0]);
+    assert!(input.exists());
+}
+
+#[tokio::test]
+async fn an_output_skipped_for_a_claim_keeps_the_source_for_its_siblings() {
+    let mut config = test_config(true);
+    config.output.claim_files = true;
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "movie.mkv", "");
+    let queue_token = CancellationToken::new();
+    let mut jobs = sibling_jobs(&dir, &input, &[None, None], &config, &queue_token).into_iter();
+
+    let (job_tx, job_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let worker = tokio::spawn(run_streamed_worker(
+        job_rx,
+        config.into(),
+        queue_token.clone(),
+        tx,
+    ));
+
+    // Another machine holds the source for the first output, then lets go
+    let claim = claim_path(&input);
+    std::fs::write(&claim, "render-box\n4242\nrender-box-4242-1\n").unwrap();
+    job_tx.send(jobs.next().unwrap()).unwrap();
+    loop {
+        match rx.recv().await {
+            Some(WorkerMessage::Claimed(0, _)) => break,
+            Some(WorkerMessage::Error(_, e)) => panic!("{}", e),
+            Some(_) => {}
+            None => panic!("worker stopped early"),
+        }
+    }
+    std::fs::remove_file(&claim).unwrap();
+    job_tx.send(jobs.next().unwrap()).unwrap();
+    drop(job_tx);
+    worker.await.unwrap();
+
+    let mut done = Vec::new();
+    while let Ok(msg) = rx.try_recv() {
+        match msg {
+            WorkerMessage::DoneWithVmaf(idx, _) => done.push(idx),
+            WorkerMessage::SourceDeleted(_) => panic!("source was deleted"),
+            _ => {}
+        }
+    }
+    assert_eq!(done, [1]);
+    assert!(input.exists());
+}
+
+#[test]
+fn bluray_titles_are_listed_longest_first() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+    let stream = dir.path().join("Movie").join("BDMV").join("STREAM");
+    std::fs::create_dir_all(&stream).unwrap();
+    std::fs::write(stream.join("00001.m2ts"), "fake video\nDURATION=95.5\n").unwrap();
+    std::fs::write(stream.join("00800.m2ts"), "fake video\nDURATION=6400\n").unwrap();
+    std::fs::write(stream.join("00001.clpi"), "clip info").unwrap();
+
+    let disc = dir.path().join("Movie");
+    assert_eq!(detect_disc(&disc), Some(DiscKind::BluRay));
+    assert_eq!(detect_disc(&disc.join("BDMV")), Some(DiscKind::BluRay));
+
+    let titles = list_titles(&disc).unwrap();
+    let names: Vec<&str> = titles.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["00800", "00001"]);
+    assert_eq!(titles[0].duration_secs, Some(6400.0));
+    assert_eq!(titles[0].output_base(), dir.path().join("Movie_00800.m2ts"));
+}
+
+#[tokio::test]
+async fn dvd_title_is_encoded_from_its_vob_parts_and_kept() {
+    let config = test_config(true);
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let video_ts = dir.path().join("Film").join("VIDEO_TS");
+    std::fs::create_dir_all(&video_ts).unwrap();
+    for name in [
+        "VTS_01_0.VOB",
+        "VTS_01_1.VOB",
+        "VTS_01_2.VOB",
+        "VIDEO_TS.VOB",
+    ] {
+        std::fs::write(video_ts.join(name), "fake video\n").unwrap();
+    }
+
+    let titles = list_titles(&video_ts).unwrap();
+    assert_eq!(titles.len(), 1);
+    let title = titles[0].clone();
+    assert_eq!(title.name, "title 01");
+    assert_eq!(title.parts.len(), 2);
+    assert!(title.input().starts_with("concat:"));
+
+    let mut job = EncodingJob::from_disc_title(title);
+    job.generate_output_path(&OutputConfig::default());
+    assert_eq!(job.filename(), "Film_title-01.vob");
+    let out = job.output_path.clone().unwrap();
+    assert_eq!(out, dir.path().join("Film_title-01_av1.mkv"));
+
+    let input = job.path.to_string_lossy().to_string();
+    let metadata = analyze(&input).unwrap().metadata;
+    let result = av1converter::encoder::run_encoding_pipeline(
+        &input,
+        out.to_str().unwrap(),
+        &metadata,
+        TrackSelection::default(),
+        &job.overrides(),
+        &config.into(),
+        None,
+        None,
+        CancellationToken::new(),
+    )
+    .await;
+    assert!(matches!(
+        result,
+        FullEncodeResult::SuccessWithVmaf {
+            source_deleted: false,
+            ..
+        }
+    ));
+    assert!(out.exists());
+    assert!(video_ts.join("VTS_01_1.VOB").exists());
+}
+
+#[test]
+fn audit_ranks_files_by_estimated_savings() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+    source(&dir, "movie.mkv", &format!("AVC\n{}", "x".repeat(4000)));
+    source(&dir, "show.mkv", &"x".repeat(2000));
+    source(&dir, "done.mkv", &format!("AV1\n{}", "x".repeat(8000)));
+    source(&dir, "song.mkv", "AUDIO_ONLY");
+
+    let report = audit(dir.path(), &InputConfig::default());
+    let names: Vec<_> = report
+        .entries
+        .iter()
+        .map(|e| (e.path.file_name().unwrap().to_str().unwrap(), e.family))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            ("movie.mkv", CodecFamily::H264),
+            ("show.mkv", CodecFamily::Hevc),
+            ("done.mkv", CodecFamily::Av1),
+        ]
+    );
+    assert_eq!(report.entries[2].savings(), 0);
+    assert_eq!(report.unreadable, [dir.path().join("song.mkv")]);
+    assert_eq!(report.families().len(), 3);
+
+    let csv = report.to_csv();
+    assert_eq!(csv.lines().count(), 4);
+    assert!(csv.lines().nth(1).unwrap().contains(",h264,1920x1080,"));
+
+    // 8 Mbit/s of 1080p24 is ~0.16 bits per pixel
+    let mut triage = TriageConfig::default();
+    let verdicts = |triage: &TriageConfig| {
+        report
+            .entries
+            .iter()
+            .map(|e| e.verdict(triage))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        verdicts(&triage),
+        [Verdict::Convert, Verdict::Convert, Verdict::Skip]
+    );
+    triage.hevc.convert_bpp = 0.2;
+    triage.h264.skip_bpp = 0.2;
+    triage.h264.convert_bpp = 0.3;
+    assert_eq!(
+        verdicts(&triage),
+        [Verdict::Skip, Verdict::Marginal, Verdict::Skip]
+    );
+}
+
+#[test]
+fn probe_detection_goes_by_content_not_extension() {
+    let config = test_config(false);
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let write = |name: &str, bytes: &[u8]| {
+        let path = dir.path().join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    };
+    let mut ts = vec![0u8; 188 * 3];
+    for packet in ts.chunks_mut(188) {
+        packet[0] = 0x47;
+    }
+    let misnamed = write("holiday.dat", b"\0\0\0\x18ftypisom\0\0\0\0");
+    let camcorder = write("00001.bin", &ts);
+    let probed = write("named.mkv", b"fake video\n");
+    let fake = write("fake.mkv", b"AUDIO_ONLY\n");
+    let song = write("song.m4a", b"\0\0\0\x18ftypM4A \0\0\0\0");
+    let notes = write("notes.txt", b"shopping list");
+
+    assert_eq!(sniff_container(&ts), Some("mpegts"));
+    let input = InputConfig {
+        detection: VideoDetection::Probe,
+        ..Default::default()
+    };
+    let mut detector = VideoDetector::new(&input);
+    for (path, video) in [
+        (&misnamed, true),
+        (&camcorder, true),
+        (&probed, true),
+        (&fake, false),
+        (&song, false),
+        (&notes, false),
+    ] {
+        assert_eq!(detector.is_video(path), video, "{}", path.display());
+    }
+    detector.save();
+
+    // Cached verdicts hold until the file changes
+    let mut cached = VideoDetector::new(&input);
+    assert!(cached.is_video(&misnamed));
+    std::fs::write(&misnamed, b"no longer a video").unwrap();
+    assert!(!cached.is_video(&misnamed));
+
+    // Extension mode keeps the names
+    let mut by_name = VideoDetector::new(&InputConfig::default());
+    assert!(by_name.is_video(&fake));
+    assert!(!by_name.is_video(&camcorder));
+}
+
+#[test]
+fn commentary_tracks_start_deselected_and_can_be_toggled_back() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "extras.mkv", "COMMENTARY");
+    let analysis = analyze(input.to_str().unwrap()).unwrap();
+    assert!(analysis.audio_tracks[0].disposition.default);
+    assert_eq!(analysis.audio_tracks[2].disposition.names(), ["comment"]);
+    assert_eq!(commentary_tracks(&analysis.audio_tracks), [2, 3]);
+
+    let mut job = EncodingJob::new(input);
+    job.audio_tracks = analysis.audio_tracks;
+    job.select_all_tracks();
+    job.exclude_commentary();
+    assert_eq!(job.track_selection.audio_indices, [0, 1]);
+    job.toggle_commentary();
+    assert_eq!(job.track_selection.audio_indices, [0, 1, 2, 3]);
+    job.toggle_commentary();
+    assert_eq!(job.track_selection.audio_indices, [0, 1]);
+
+    // A file of only commentary keeps its audio
+    job.audio_tracks.truncate(3);
+    job.audio_tracks.drain(..2);
+    job.select_all_tracks();
+    job.exclude_commentary();
+    assert_eq!(job.track_selection.audio_indices, [2]);
+}
+
+#[test]
+fn subtitle_rules_pick_forced_and_sdh_tracks_per_language() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "subs.mkv", "SDH_SUBS");
+    let analysis = analyze(input.to_str().unwrap()).unwrap();
+    let flags: Vec<(bool, bool)> = analysis
+        .subtitle_tracks
+        .iter()
+        .map(|t| (t.is_forced(), t.is_sdh()))
+        .collect();
+    assert_eq!(
+        flags,
+        [
+            (false, false),
+            (false, true),
+            (true, false),
+            (false, false),
+            (false, true)
+        ]
+    );
+    assert_eq!(
+        analysis.subtitle_tracks[1].disposition.names(),
+        ["default", "hearing_impaired"]
+    );
+
+    let mut job = EncodingJob::new(input);
+    job.audio_tracks = analysis.audio_tracks;
+    job.subtitle_tracks = analysis.subtitle_tracks;
+    let selected = |job: &mut EncodingJob, tracks: &TrackPresetConfig| {
+        job.select_all_tracks();
+        job.apply_subtitle_rules(tracks);
+        job.track_selection.subtitle_indices.clone()
+    };
+
+    let mut tracks = TrackPresetConfig::default();
+    assert_eq!(selected(&mut job, &tracks), [0, 1, 2, 3, 4]);
+    tracks.subtitle_rule = SubtitleRule::ExcludeSdh;
+    assert_eq!(selected(&mut job, &tracks), [0, 2, 3]);
+    tracks.subtitle_rule = SubtitleRule::PreferSdh;
+    assert_eq!(selected(&mut job, &tracks), [1, 2, 4]);
+    tracks
+        .subtitle_rules
+        .insert("fre".to_string(), SubtitleRule::ForcedOnly);
+    assert_eq!(selected(&mut job, &tracks), [1, 2]);
+    tracks.subtitle_rule = SubtitleRule::ForcedOnly;
+    assert_eq!(selected(&mut job, &tracks), [2]);
+}
+
+#[test]
+fn non_video_inputs_are_rejected_with_a_reason() {
+    av1converter::utils::tools::configure(&fixture_tools());
+    let dir = TempDir::new().unwrap();
+    let unsupported = |keywords: &str| {
+        let input = source(&dir, &format!("{}.bin", keywords), keywords);
+        match analyze(input.to_str().unwrap()) {
+            Err(AppError::Unsupported(input)) => input,
+            other => panic!("{}: expected unsupported input, got {:?}", keywords, other),
+        }
+    };
+
+    assert_eq!(unsupported("AUDIO_ONLY"), UnsupportedInput::AudioOnly);
+    assert_eq!(unsupported("COVER_ART"), UnsupportedInput::AudioOnly);
+    assert_eq!(
+        unsupported("IMAGE"),
+        UnsupportedInput::ImageSequence {
+            codec: "png".to_string()
+        }
+    );
+    let error = AppError::Unsupported(unsupported("RAW_H264"));
+    assert_eq!(error.kind(), ErrorKind::ElementaryStream);
+    assert!(error.kind().is_retryable());
+    assert!(
+        !AppError::Unsupported(UnsupportedInput::AudioOnly)
+            .kind()
+            .is_retryable()
+    );
+}
+
+#[tokio::test]
+async fn raw_stream_encodes_at_the_assumed_frame_rate() {
+    let config = test_config(true);
+    av1converter::utils::tools::configure(&config.tools);
+    let dir = TempDir::new().unwrap();
+    let input = source(&dir, "capture.h264", "RAW_H264");
+    let out = output(&dir, "capture_av1.mkv");
+
+    let frame_rate = config.ffmpeg.assumed_frame_rate().unwrap();
+    assert_eq!(frame_rate, (24000, 1001));
+    let analysis = analyze_elementary(input.to_str().unwrap(), (25, 1)).unwrap();
+    assert_eq!(analysis.metadata.frame_rate_num, 25);
+    assert_eq!(analysis.metadata.duration_secs, 10.0);
+
+    let overrides = EncodeOverrides {
+        input_frame_rate: Some((25, 1)),
+        ..Default::default()
+    };
+    let result = av1converter::encoder::run_encoding_pipeline(
+        input.to_str().unwrap(),
+        out.to_str().unwrap(),
+        &analysis.metadata,
+        TrackSelection::default(),
+        &overrides,
+        &config.into(),
+        None,
+        None,
+        CancellationToken::new(),
+    )
+    .await;
+
+    // No timestamps to align VMAF frames by, so no score and the source stays
+    assert!(matches!(result, FullEncodeResult::Success));
+    assert!(out.exists());
+    assert!(input.exists());
+}
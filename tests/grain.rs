@@ -0,0 +1,44 @@
+//! Film grain picked per source from its measured grain and bitrate.
+
+use av1converter::analyzer::noise::parse_psnr_y;
+use av1converter::analyzer::{NoiseAnalysis, ResolutionTier, adaptive_film_grain};
+use av1converter::config::AdaptiveGrainConfig;
+
+#[test]
+fn psnr_summary_gives_the_luma_value() {
+    let stderr = "frame=   40 fps=0.0 q=-0.0 size=N/A\n\
+        [Parsed_psnr_3 @ 0x55d] PSNR y:41.23 u:47.10 v:46.85 average:42.61 min:39.01 max:44.90\n";
+    assert_eq!(parse_psnr_y(stderr), Some(41.23));
+    assert_eq!(
+        parse_psnr_y("[Parsed_psnr_3 @ 0x1] PSNR y:inf u:inf v:inf average:inf"),
+        Some(f64::INFINITY)
+    );
+    assert_eq!(parse_psnr_y("no summary"), None);
+}
+
+#[test]
+fn clean_sources_get_no_grain_and_grainy_ones_the_most() {
+    let config = AdaptiveGrainConfig::default();
+    let noise = |denoised_psnr| NoiseAnalysis { denoised_psnr };
+
+    let clean = noise(49.0).grain_score(Some(0.03), &config);
+    assert_eq!(clean, 0.0);
+    assert_eq!(adaptive_film_grain(clean, ResolutionTier::Uhd, &config), 0);
+    assert_eq!(noise(f64::INFINITY).grain_score(None, &config), 0.0);
+
+    let grainy = noise(34.0).grain_score(Some(0.4), &config);
+    assert_eq!(grainy, 1.0);
+    assert_eq!(
+        adaptive_film_grain(grainy, ResolutionTier::FullHD, &config),
+        config.max_film_grain
+    );
+    // Smaller pictures get less of the same grain
+    assert_eq!(adaptive_film_grain(grainy, ResolutionTier::SD, &config), 6);
+    assert_eq!(adaptive_film_grain(grainy, ResolutionTier::HD, &config), 9);
+
+    // Halfway noise scores half without a bitrate; a high bitrate lifts it
+    let halfway = noise(41.0);
+    assert!((halfway.grain_score(None, &config) - 0.5).abs() < 1e-9);
+    assert!(halfway.grain_score(Some(0.25), &config) > 0.5);
+    assert!(halfway.grain_score(Some(0.05), &config) < 0.5);
+}
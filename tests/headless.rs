@@ -0,0 +1,228 @@
+//! Headless runs and their JSON event stream, against the mock executables.
+#![cfg(unix)]
+
+use av1converter::app::{App, Screen};
+use av1converter::config::{DeleteMode, Encoder, ToolsConfig};
+use av1converter::queue::{EncodingJob, JobStatus, QueueState, RunOutcome, RunSummary, SkipKind};
+use av1converter::utils::paths;
+use av1converter::{AppConfig, headless};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use tokio::sync::Mutex;
+
+/// The configuration directory is process-wide, so runs take turns
+static CONFIG_DIR: Mutex<()> = Mutex::const_new(());
+
+fn fixture_tools() -> ToolsConfig {
+    let bin = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bin");
+    let path = |name: &str| bin.join(name).to_string_lossy().to_string();
+    ToolsConfig {
+        ffmpeg: path("ffmpeg"),
+        ffprobe: path("ffprobe"),
+        mkvmerge: path("mkvmerge"),
+    }
+}
+
+#[tokio::test]
+async fn json_events_follow_each_job_to_its_outcome() {
+    let _turn = CONFIG_DIR.lock().await;
+    let config_dir = TempDir::new().unwrap();
+    paths::set_config_dir(config_dir.path().to_path_buf());
+    let mut config = AppConfig {
+        encoder: Encoder::SvtAv1,
+        tools: fixture_tools(),
+        ..AppConfig::default()
+    };
+    config.quality.vmaf_enabled = false;
+    config.output.remux_after_encode = false;
+    config.performance.chunked_encoding = false;
+    config.quality.delete_mode = DeleteMode::Permanent;
+    config.save().unwrap();
+
+    let videos = TempDir::new().unwrap();
+    std::fs::write(videos.path().join("a.mkv"), "fake video\n").unwrap();
+    std::fs::write(videos.path().join("b.mkv"), "fake video\nAV1\n").unwrap();
+
+    let mut app = App::new();
+    app.queue_paths(&[videos.path().to_path_buf()]);
+    let mut out = Vec::new();
+    headless::run(&mut app, &mut out).await.unwrap();
+
+    let events: Vec<Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let of_job = |job: u64| -> Vec<&str> {
+        events
+            .iter()
+            .filter(|e| e["job"] == job)
+            .map(|e| e["event"].as_str().unwrap())
+            .collect()
+    };
+
+    let a = of_job(0);
+    assert_eq!(a.first(), Some(&"started"), "{:?}", events);
+    assert!(a.contains(&"progress"));
+    assert_eq!(a.last(), Some(&"done"), "{:?}", events);
+    let started = &events[events.iter().position(|e| e["event"] == "started").unwrap()];
+    assert_eq!(started["file"], "a.mkv");
+    assert!(started["quality"].is_u64());
+
+    let skipped = events.iter().find(|e| e["job"] == 1).unwrap();
+    assert_eq!(skipped["event"], "skipped");
+    assert_eq!(skipped["kind"], "rule");
+    assert_eq!(skipped["reason"], "Already AV1");
+
+    let finished = events.last().unwrap();
+    assert_eq!(finished["event"], "finished");
+    assert_eq!(finished["converted"], 1);
+    assert_eq!(finished["skipped"], 1);
+
+    let summary: Value = serde_json::from_str(
+        &std::fs::read_to_string(config_dir.path().join("last_run.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(summary["outcome"], "ok");
+    assert_eq!(summary["converted"], 1);
+    let jobs = summary["jobs"].as_array().unwrap();
+    assert_eq!(jobs[0]["status"], "done");
+    assert!(jobs[0]["output_size"].is_u64());
+    assert!(jobs[0]["elapsed_secs"].is_f64());
+    assert_eq!(jobs[1]["message"], "Already AV1");
+    assert_eq!(jobs[1]["skip"], "rule");
+}
+
+#[test]
+fn run_outcome_is_the_worst_job_outcome() {
+    let mut queue = QueueState::new();
+    let job = |status| {
+        let mut job = EncodingJob::new("a.mkv".into());
+        job.status = status;
+        job
+    };
+    queue.jobs.push(job(JobStatus::Done));
+    assert_eq!(RunSummary::of(&queue).outcome.exit_code(), 0);
+    queue.jobs.push(job(JobStatus::QualityWarning {
+        vmaf: 88.0,
+        threshold: 90.0,
+    }));
+    let summary = RunSummary::of(&queue);
+    assert_eq!(summary.outcome, RunOutcome::QualityWarnings);
+    assert_eq!(summary.quality_warnings, 1);
+    queue.error_count = 1;
+    assert_eq!(RunSummary::of(&queue).outcome.exit_code(), 4);
+}
+
+#[test]
+fn skips_are_counted_by_who_made_them() {
+    let mut queue = QueueState::new();
+    for name in ["a.mkv", "b.mkv", "c.mkv", "d.mkv"] {
+        queue.jobs.push(EncodingJob::new(name.into()));
+    }
+    queue.skip(0, SkipKind::Rule, "Already AV1");
+    queue.skip(1, SkipKind::User, "Skipped");
+    queue.skip(2, SkipKind::Cancelled, "Cancelled");
+    queue.skip(3, SkipKind::Cancelled, "Cancelled");
+    assert_eq!(
+        (
+            queue.skipped_count,
+            queue.user_skipped_count,
+            queue.cancelled_count
+        ),
+        (1, 1, 2)
+    );
+    assert_eq!(queue.finished_count(), 4);
+
+    let summary = RunSummary::of(&queue);
+    assert_eq!(summary.outcome, RunOutcome::Ok);
+    assert_eq!((summary.skipped, summary.cancelled), (1, 2));
+    assert_eq!(summary.jobs[1].skip, Some(SkipKind::User));
+}
+
+#[tokio::test]
+async fn print_commands_runs_nothing() {
+    let _turn = CONFIG_DIR.lock().await;
+    let config_dir = TempDir::new().unwrap();
+    paths::set_config_dir(config_dir.path().to_path_buf());
+    let config = AppConfig {
+        encoder: Encoder::SvtAv1,
+        tools: fixture_tools(),
+        ..AppConfig::default()
+    };
+    config.save().unwrap();
+
+    let videos = TempDir::new().unwrap();
+    let source = videos.path().join("a.mkv");
+    std::fs::write(&source, "fake video\n").unwrap();
+    std::fs::write(videos.path().join("b.mkv"), "fake video\nAV1\n").unwrap();
+
+    let mut app = App::new();
+    app.queue_paths(&[videos.path().to_path_buf()]);
+    let mut out = Vec::new();
+    headless::print_commands(&mut app, &mut out).await.unwrap();
+
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    let header = lines
+        .iter()
+        .position(|l| *l == format!("# {}", source.display()))
+        .unwrap_or_else(|| panic!("{}", out));
+    let command = lines[header + 1];
+    assert!(command.contains("libsvtav1"), "{}", command);
+    assert!(command.contains("a_av1.mkv"), "{}", command);
+    assert!(out.contains("b.mkv: skipped, Already AV1"), "{}", out);
+    assert!(source.exists());
+    assert!(!videos.path().join("a_av1.mkv").exists());
+}
+
+#[tokio::test]
+async fn command_line_paths_open_the_confirmation_screen() {
+    let _turn = CONFIG_DIR.lock().await;
+    let config_dir = TempDir::new().unwrap();
+    paths::set_config_dir(config_dir.path().to_path_buf());
+    let videos = TempDir::new().unwrap();
+    let file = videos.path().join("a.mkv");
+    std::fs::write(&file, "fake video\n").unwrap();
+    std::fs::create_dir(videos.path().join("show")).unwrap();
+    std::fs::write(videos.path().join("show/e1.mkv"), "fake video\n").unwrap();
+
+    let mut app = App::new();
+    app.open_paths(&[file.clone(), videos.path().join("show")]);
+    assert_eq!(app.current_screen, Screen::FileConfirm);
+    assert_eq!(app.queue.jobs.len(), 2);
+    assert_eq!(app.queue.jobs[0].path, file);
+
+    let mut app = App::new();
+    app.open_paths(&[config_dir.path().to_path_buf()]);
+    assert_eq!(app.current_screen, Screen::Home);
+}
+
+#[tokio::test]
+async fn the_batch_email_and_statistics_are_awaited_before_exiting() {
+    let _turn = CONFIG_DIR.lock().await;
+    let config_dir = TempDir::new().unwrap();
+    paths::set_config_dir(config_dir.path().to_path_buf());
+    let mut app = App::new();
+    let sent = Arc::new(AtomicBool::new(false));
+    let flag = sent.clone();
+    app.batch_end_tasks
+        .push(tokio::task::spawn_blocking(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            flag.store(true, Ordering::SeqCst);
+        }));
+    app.finish_batch_end_tasks(Duration::from_secs(10)).await;
+    assert!(sent.load(Ordering::SeqCst));
+    assert!(app.batch_end_tasks.is_empty());
+
+    // A hung server doesn't hold the exit up past the limit
+    app.batch_end_tasks
+        .push(tokio::spawn(std::future::pending()));
+    let started = Instant::now();
+    app.finish_batch_end_tasks(Duration::from_millis(100)).await;
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
@@ -0,0 +1,35 @@
+//! Size, speed and decimal formatting under each unit and separator setting.
+
+use av1converter::config::{SizeUnits, UiConfig};
+use av1converter::utils::humanize::{configure, format_decimal, format_file_size, format_speed};
+
+fn ui(size_units: SizeUnits, decimal_separator: &str) -> UiConfig {
+    UiConfig {
+        size_units,
+        decimal_separator: decimal_separator.to_string(),
+        ..Default::default()
+    }
+}
+
+// One test: the format is process-wide, so settings must not change under a parallel test
+#[test]
+fn sizes_and_speeds_follow_the_configured_format() {
+    configure(&ui(SizeUnits::Binary, "."));
+    assert_eq!(format_file_size(512), "512 B");
+    assert_eq!(format_file_size(2048), "2 KiB");
+    assert_eq!(format_file_size(5 * 1024 * 1024 + 512 * 1024), "5.5 MiB");
+    assert_eq!(format_file_size(3 * 1024 * 1024 * 1024), "3.00 GiB");
+    assert_eq!(format_speed(48.0 * 1024.0 * 1024.0), "48.0 MiB/s");
+
+    configure(&ui(SizeUnits::Decimal, "."));
+    assert_eq!(format_file_size(2048), "2 kB");
+    assert_eq!(format_file_size(1_500_000_000), "1.50 GB");
+    assert_eq!(format_speed(12_300_000.0), "12.3 MB/s");
+    assert_eq!(format_speed(-1.0), "0 B/s");
+
+    configure(&ui(SizeUnits::Decimal, ","));
+    assert_eq!(format_file_size(1_500_000_000), "1,50 GB");
+    assert_eq!(format_speed(12_300_000.0), "12,3 MB/s");
+    assert_eq!(format_decimal(42.26, 1), "42,3");
+    assert_eq!(format_decimal(3.0, 0), "3");
+}
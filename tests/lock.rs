@@ -0,0 +1,92 @@
+//! Instance locks and source claims between copies of the app.
+
+use av1converter::error::{AppError, ErrorKind};
+use av1converter::utils::InstanceLock;
+use av1converter::utils::claim::{SourceClaim, claim_holder, claim_path, host_name};
+use av1converter::utils::lock::{DIR_LOCK_FILE, lock_holder};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+#[test]
+fn running_instance_blocks_the_folder_until_it_exits() {
+    let dir = TempDir::new().unwrap();
+    let lock_path = dir.path().join(DIR_LOCK_FILE);
+
+    // Another instance, alive for now
+    let mut other = Command::new("sleep").arg("30").spawn().unwrap();
+    std::fs::write(&lock_path, format!("{}\n", other.id())).unwrap();
+    match InstanceLock::output_dir(dir.path()) {
+        Err(e @ AppError::Locked { .. }) => assert_eq!(e.kind(), ErrorKind::Locked),
+        other => panic!("expected a held lock, got {:?}", other),
+    }
+
+    // Its lock goes stale once it exits
+    other.kill().unwrap();
+    other.wait().unwrap();
+    let lock = InstanceLock::output_dir(dir.path()).unwrap();
+    let holder = lock_holder(&lock_path).unwrap();
+    assert_eq!(holder.pid, std::process::id());
+    assert_eq!(holder.host, Some(host_name()));
+    assert_eq!(lock.path(), lock_path);
+
+    drop(lock);
+    assert!(!lock_path.exists());
+
+    // A process of another machine can't be checked, so its lock holds
+    std::fs::write(&lock_path, format!("{}\nrender-box\n", other.id())).unwrap();
+    match InstanceLock::output_dir(dir.path()) {
+        Err(e @ AppError::Locked { .. }) => assert!(e.to_string().contains("on render-box")),
+        other => panic!("expected a held lock, got {:?}", other),
+    }
+    assert!(lock_path.exists());
+}
+
+#[test]
+fn claimed_sources_are_left_to_their_holder_until_it_goes_quiet() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("movie.mkv");
+    std::fs::write(&source, "video").unwrap();
+    let stale_after = Duration::from_secs(600);
+
+    let claim = SourceClaim::acquire(&source, stale_after).unwrap();
+    assert_eq!(claim.path(), dir.path().join("movie.mkv.av1claim"));
+    let holder = claim_holder(&source).unwrap();
+    assert_eq!(holder.host, host_name());
+    assert!(matches!(
+        SourceClaim::acquire(&source, stale_after),
+        Err(AppError::Claimed { .. })
+    ));
+    drop(claim);
+    assert!(!claim_path(&source).exists());
+
+    // Another machine working on it
+    let claim_file = claim_path(&source);
+    std::fs::write(&claim_file, "render-box\n4242\nrender-box-4242-1\n").unwrap();
+    match SourceClaim::acquire(&source, stale_after) {
+        Err(AppError::Claimed { holder, .. }) => assert_eq!(holder, "render-box (PID 4242)"),
+        other => panic!("expected a claimed source, got {:?}", other),
+    }
+
+    // which stopped refreshing its claim
+    let quiet_since = SystemTime::now() - Duration::from_secs(3600);
+    std::fs::File::options()
+        .write(true)
+        .open(&claim_file)
+        .unwrap()
+        .set_modified(quiet_since)
+        .unwrap();
+    let claim = SourceClaim::acquire(&source, stale_after).unwrap();
+    assert_eq!(claim_holder(&source).unwrap().host, host_name());
+    drop(claim);
+
+    // A process of this machine that exited leaves a stale claim at once
+    let mut other = Command::new("true").spawn().unwrap();
+    other.wait().unwrap();
+    std::fs::write(
+        &claim_file,
+        format!("{}\n{}\nold-token\n", host_name(), other.id()),
+    )
+    .unwrap();
+    assert!(SourceClaim::acquire(&source, stale_after).is_ok());
+}
@@ -0,0 +1,158 @@
+//! Episode parsing, name templates and output placement.
+
+use av1converter::config::{OutputConfig, OutputNaming};
+use av1converter::pipeline::output_path_for;
+use av1converter::queue::{EncodingJob, JobStatus, QueueState};
+use av1converter::utils::naming::{EpisodeInfo, parse_episode, render_name, resolution_label};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+#[test]
+fn episode_tokens_are_parsed_from_common_release_names() {
+    assert_eq!(
+        parse_episode("The.Expanse.S02E05.Home.1080p.WEB-DL.x264"),
+        Some(EpisodeInfo {
+            show: "The Expanse".to_string(),
+            season: 2,
+            episode: 5,
+            last_episode: None,
+            title: "Home".to_string(),
+        })
+    );
+    let double = parse_episode("Show Name - s01e01-e02 - Pilot").unwrap();
+    assert_eq!((double.season, double.episode), (1, 1));
+    assert_eq!(double.last_episode, Some(2));
+    assert_eq!(double.title, "Pilot");
+    assert_eq!(
+        parse_episode("Show_3x07").map(|e| (e.season, e.episode)),
+        Some((3, 7))
+    );
+    assert_eq!(parse_episode("Movie.2019.1920x1080"), None);
+    assert_eq!(parse_episode("Holiday video"), None);
+}
+
+#[test]
+fn templates_fall_back_for_files_without_an_episode_token() {
+    let template = "{show}/Season {season}/{show} - S{season}E{episode}{suffix}";
+    assert_eq!(
+        render_name(template, "the.office.s03e10.720p", "_av1"),
+        PathBuf::from("the office/Season 03/the office - S03E10_av1")
+    );
+    assert_eq!(
+        render_name(template, "Holiday video", "_av1"),
+        PathBuf::from("Holiday video_av1")
+    );
+    assert_eq!(
+        render_name("../{name}{suffix}", "clip", "_av1"),
+        PathBuf::from("clip_av1")
+    );
+}
+
+#[test]
+fn outputs_in_another_directory_keep_the_season_pack_layout() {
+    let output = OutputConfig {
+        same_directory: false,
+        output_directory: Some("/media/av1".to_string()),
+        ..OutputConfig::default()
+    };
+    let root = Path::new("/media/tv");
+    let input = Path::new("/media/tv/Show/Season 01/Show.S01E02.mkv");
+
+    assert_eq!(
+        output_path_for(input, Some(root), &output, "_av1", "1080p AV1", "mkv"),
+        PathBuf::from("/media/av1/Show/Season 01/Show.S01E02_av1.mkv")
+    );
+    assert_eq!(
+        output_path_for(input, None, &output, "_av1", "1080p AV1", "mkv"),
+        PathBuf::from("/media/av1/Show.S01E02_av1.mkv")
+    );
+    assert_eq!(
+        output_path_for(
+            input,
+            Some(root),
+            &OutputConfig::default(),
+            "_av1",
+            "1080p AV1",
+            "mkv"
+        ),
+        PathBuf::from("/media/tv/Show/Season 01/Show.S01E02_av1.mkv")
+    );
+}
+
+#[test]
+fn jellyfin_versions_are_named_after_the_movie_folder() {
+    let output = OutputConfig {
+        naming: OutputNaming::JellyfinVersions,
+        ..OutputConfig::default()
+    };
+    let input = Path::new("/media/movies/Heat (1995)/Heat (1995).mkv");
+    assert_eq!(
+        output_path_for(input, None, &output, "_av1", "1080p AV1", "mkv"),
+        PathBuf::from("/media/movies/Heat (1995)/Heat (1995) - 1080p AV1.mkv")
+    );
+
+    // Scope and cropped sources are named after their format
+    assert_eq!(resolution_label(1920, 800), "1080p");
+    assert_eq!(resolution_label(3840, 1600), "2160p");
+    assert_eq!(resolution_label(1280, 720), "720p");
+    assert_eq!(resolution_label(720, 576), "576p");
+    assert_eq!(resolution_label(640, 360), "480p");
+}
+
+#[test]
+fn movies_sharing_a_folder_keep_their_own_names() {
+    let output = OutputConfig {
+        naming: OutputNaming::JellyfinVersions,
+        ..OutputConfig::default()
+    };
+    let dir = TempDir::new().unwrap();
+    let folder = dir.path().join("Heat (1995)");
+    std::fs::create_dir(&folder).unwrap();
+    let movie = folder.join("Heat (1995).mkv");
+    std::fs::write(&movie, "").unwrap();
+
+    // Versions already in the folder belong to the same movie
+    std::fs::write(folder.join("Heat (1995) - 2160p HDR.mkv"), "").unwrap();
+    assert_eq!(
+        output_path_for(&movie, None, &output, "_av1", "1080p AV1", "mkv"),
+        folder.join("Heat (1995) - 1080p AV1.mkv")
+    );
+
+    let sequel = folder.join("Heat 2 (2026).mkv");
+    std::fs::write(&sequel, "").unwrap();
+    let outputs: Vec<PathBuf> = [&movie, &sequel]
+        .iter()
+        .map(|input| output_path_for(input, None, &output, "_av1", "1080p AV1", "mkv"))
+        .collect();
+    assert_eq!(
+        outputs,
+        [
+            folder.join("Heat (1995)_av1.mkv"),
+            folder.join("Heat 2 (2026)_av1.mkv")
+        ]
+    );
+}
+
+#[test]
+fn a_second_source_writing_the_same_output_is_caught() {
+    let mut queue = QueueState::new();
+    for (name, status) in [
+        ("a.mkv", JobStatus::Encoding { progress: 10.0 }),
+        ("b.mkv", JobStatus::Ready),
+    ] {
+        let mut job = EncodingJob::new(PathBuf::from("/movies").join(name));
+        job.status = status;
+        job.output_path = Some(PathBuf::from("/movies/Movies - 1080p AV1.mkv"));
+        queue.jobs.push(job);
+    }
+    assert_eq!(
+        queue.output_taken_by(1).map(|j| j.filename()),
+        Some("a.mkv".to_string())
+    );
+    // The job writing it first is not held back by one still waiting
+    assert!(queue.output_taken_by(0).is_none());
+
+    // Another output of the same source is no collision
+    queue.jobs[1].path = PathBuf::from("/movies/a.mkv");
+    assert!(queue.output_taken_by(1).is_none());
+}
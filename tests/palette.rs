@@ -0,0 +1,21 @@
+use av1converter::palette::{PaletteAction, filter, fuzzy_score};
+
+#[test]
+fn palette_ranks_fuzzy_matches() {
+    let all = PaletteAction::ALL;
+
+    // An empty query keeps every action in order
+    assert_eq!(filter("", &all), all.to_vec());
+
+    // Word starts and consecutive letters beat scattered ones
+    let matches = filter("of", &all);
+    assert_eq!(matches.first(), Some(&PaletteAction::OpenFile));
+    assert_eq!(
+        filter("vmaf", &all).first(),
+        Some(&PaletteAction::ToggleVmaf)
+    );
+    assert!(filter("xyz", &all).is_empty());
+
+    assert!(fuzzy_score("sw enc", "Switch encoder") > fuzzy_score("sw enc", "Save configuration"));
+    assert_eq!(fuzzy_score("ABC", "abc"), fuzzy_score("abc", "ABC"));
+}
@@ -0,0 +1,22 @@
+//! Portable configuration and data directory.
+
+use av1converter::AppConfig;
+use av1converter::history;
+use av1converter::utils::{config_dir, data_dir, paths};
+use tempfile::TempDir;
+
+#[test]
+fn config_dir_flag_keeps_everything_in_one_place() {
+    let dir = TempDir::new().unwrap();
+    paths::set_config_dir(dir.path().to_path_buf());
+
+    assert_eq!(paths::portable_dir().as_deref(), Some(dir.path()));
+    assert_eq!(config_dir(), dir.path());
+    assert_eq!(data_dir(), dir.path());
+    assert_eq!(AppConfig::config_path(), dir.path().join("config.toml"));
+    assert_eq!(history::history_path(), dir.path().join("history.jsonl"));
+
+    // A first run writes its default configuration there
+    AppConfig::load();
+    assert!(dir.path().join("config.toml").exists());
+}
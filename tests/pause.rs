@@ -0,0 +1,134 @@
+//! Pausing the queue: held jobs and suspended processes.
+#![cfg(target_os = "linux")]
+
+use av1converter::config::{Encoder, ToolsConfig};
+use av1converter::queue::{WorkerJob, WorkerMessage, run_worker};
+use av1converter::tracks::TrackSelection;
+use av1converter::utils::{paths, suspend};
+use av1converter::{AppConfig, analyze};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// The pause is process-wide, so the tests take turns
+static SERIAL: Mutex<()> = Mutex::const_new(());
+
+/// A child process killed when dropped, so a failed check leaves none behind stopped
+struct Sleeper(std::process::Child);
+
+impl Sleeper {
+    fn spawn() -> Self {
+        let child = std::process::Command::new("sleep")
+            .arg("30")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        Self(child)
+    }
+
+    fn id(&self) -> u32 {
+        self.0.id()
+    }
+
+    /// Whether the process is stopped, once the signal got through
+    async fn stopped(&self, expected: bool) -> bool {
+        for _ in 0..50 {
+            // State letter after the command name, e.g. `S` sleeping or `T` stopped
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", self.id())).unwrap();
+            let state = stat
+                .rsplit_once(')')
+                .unwrap()
+                .1
+                .trim_start()
+                .starts_with('T');
+            if state == expected {
+                return state;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        !expected
+    }
+}
+
+impl Drop for Sleeper {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[tokio::test]
+async fn paused_processes_are_stopped_until_resumed() {
+    let _serial = SERIAL.lock().await;
+    let child = Sleeper::spawn();
+    let tracked = suspend::track(Some(child.id()));
+
+    suspend::set_paused(true);
+    assert!(child.stopped(true).await);
+    suspend::set_paused(false);
+    assert!(!child.stopped(false).await);
+
+    // A process started during a pause is stopped right away
+    drop(tracked);
+    suspend::set_paused(true);
+    let late = Sleeper::spawn();
+    let _late = suspend::track(Some(late.id()));
+    assert!(late.stopped(true).await);
+    assert!(!child.stopped(false).await);
+    suspend::set_paused(false);
+}
+
+#[tokio::test]
+async fn paused_queue_starts_no_job_until_resumed() {
+    let _serial = SERIAL.lock().await;
+    let bin = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bin");
+    let tool = |name: &str| bin.join(name).to_string_lossy().to_string();
+    let mut config = AppConfig {
+        encoder: Encoder::SvtAv1,
+        tools: ToolsConfig {
+            ffmpeg: tool("ffmpeg"),
+            ffprobe: tool("ffprobe"),
+            mkvmerge: tool("mkvmerge"),
+        },
+        ..AppConfig::default()
+    };
+    config.quality.vmaf_enabled = false;
+    config.output.remux_after_encode = false;
+    config.performance.chunked_encoding = false;
+    paths::set_config_dir(std::env::temp_dir().join("av1converter-tests"));
+    av1converter::utils::tools::configure(&config.tools);
+
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("movie.mkv");
+    std::fs::write(&input, "fake video\n\n").unwrap();
+    let queue_token = CancellationToken::new();
+    let job = WorkerJob {
+        index: 0,
+        metadata: analyze(input.to_str().unwrap()).unwrap().metadata,
+        output: dir.path().join("movie_av1.mkv"),
+        input,
+        tracks: TrackSelection::default(),
+        overrides: Default::default(),
+        after: Vec::new(),
+        cancel: queue_token.child_token(),
+    };
+
+    suspend::set_paused(true);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let worker = tokio::spawn(run_worker(vec![job], config.into(), queue_token, tx));
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(rx.try_recv().is_err(), "a job started while paused");
+
+    suspend::set_paused(false);
+    worker.await.unwrap();
+    let mut done = false;
+    while let Ok(msg) = rx.try_recv() {
+        done |= matches!(msg, WorkerMessage::Done(0));
+    }
+    assert!(done);
+}
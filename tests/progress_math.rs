@@ -0,0 +1,272 @@
+//! Property tests for queue progress, ETA and size-reduction math.
+
+use av1converter::encoder::{Stage, StageTimings};
+use av1converter::queue::state::{overall_progress, overall_progress_of};
+use av1converter::queue::{EncodingJob, EtaEstimator, JobStatus, QueueState, SkipKind};
+use proptest::prelude::*;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn job(status: JobStatus) -> EncodingJob {
+    let mut job = EncodingJob::new(PathBuf::from("/nonexistent/movie.mkv"));
+    job.status = status;
+    job
+}
+
+fn finished_status() -> impl Strategy<Value = JobStatus> {
+    prop_oneof![
+        Just(JobStatus::Done),
+        (0.0f64..100.0).prop_map(|score| JobStatus::DoneWithVmaf { score }),
+        Just(JobStatus::Skipped {
+            kind: SkipKind::Rule,
+            reason: "Skipped".to_string()
+        }),
+        Just(JobStatus::Error {
+            message: "failed".to_string(),
+            kind: av1converter::error::ErrorKind::Other,
+        }),
+        (0.0f64..100.0).prop_map(|vmaf| JobStatus::QualityWarning {
+            vmaf,
+            threshold: 90.0
+        }),
+    ]
+}
+
+fn any_duration() -> impl Strategy<Value = Duration> {
+    (any::<u64>(), 0u32..1_000_000_000).prop_map(|(secs, nanos)| Duration::new(secs, nanos))
+}
+
+proptest! {
+    #[test]
+    fn progress_stays_within_bounds(
+        finished in any::<usize>(),
+        total in any::<usize>(),
+        current in any::<f32>(),
+    ) {
+        let progress = overall_progress(finished, total, current);
+        prop_assert!((0.0..=100.0).contains(&progress), "{}", progress);
+    }
+
+    #[test]
+    fn progress_is_monotonic(
+        total in 1usize..1000,
+        finished in 0usize..1000,
+        a in 0.0f32..=100.0,
+        b in 0.0f32..=100.0,
+    ) {
+        let finished = finished % total;
+        let (low, high) = (a.min(b), a.max(b));
+        prop_assert!(
+            overall_progress(finished, total, low) <= overall_progress(finished, total, high)
+        );
+        prop_assert!(
+            overall_progress(finished, total, high) <= overall_progress(finished + 1, total, 0.0)
+        );
+    }
+
+    #[test]
+    fn finished_run_is_complete(total in 1usize..10_000, current in any::<f32>()) {
+        prop_assert_eq!(overall_progress(total, total, current), 100.0);
+    }
+
+    #[test]
+    fn parallel_progress_stays_within_bounds(
+        finished in any::<usize>(),
+        total in any::<usize>(),
+        running in prop::collection::vec(any::<f32>(), 0..8),
+    ) {
+        let progress = overall_progress_of(finished, total, &running);
+        prop_assert!((0.0..=100.0).contains(&progress), "{}", progress);
+    }
+
+    #[test]
+    fn one_running_job_matches_serial_progress(
+        total in 1usize..1000,
+        finished in 0usize..1000,
+        current in 0.0f32..=100.0,
+    ) {
+        let finished = finished % total;
+        let serial = overall_progress(finished, total, current);
+        let parallel = overall_progress_of(finished, total, &[current]);
+        prop_assert!((serial - parallel).abs() < 1e-3, "{} vs {}", serial, parallel);
+    }
+
+    #[test]
+    fn parallel_progress_stays_below_the_next_finish(
+        total in 2usize..1000,
+        finished in 0usize..1000,
+        running in prop::collection::vec(0.0f32..100.0, 1..8),
+    ) {
+        // Fewer jobs run than are left, and none of them finished yet
+        let finished = finished % (total - 1);
+        let left = total - finished;
+        let running = &running[..running.len().min(left - 1).max(1)];
+        let progress = overall_progress_of(finished, total, running);
+        prop_assert!(progress <= overall_progress(finished + running.len(), total, 0.0));
+    }
+
+    #[test]
+    fn queue_progress_counts_every_terminal_job(
+        statuses in prop::collection::vec(finished_status(), 1..20),
+    ) {
+        let mut queue = QueueState::new();
+        queue.total_jobs_to_encode = statuses.len();
+        queue.jobs = statuses.into_iter().map(job).collect();
+
+        // Mid-run the last job is still encoding
+        let last = queue.jobs.len() - 1;
+        queue.current_job_index = last;
+        queue.encoding_progress_done = last;
+        queue.jobs[last].status = JobStatus::Encoding { progress: 50.0 };
+        prop_assert!(queue.overall_progress() < 100.0);
+
+        queue.jobs[last].status = JobStatus::Done;
+        queue.encoding_progress_done += 1;
+        prop_assert_eq!(queue.overall_progress(), 100.0);
+    }
+
+    #[test]
+    fn earlier_results_do_not_inflate_a_rerun(
+        earlier in prop::collection::vec(finished_status(), 0..20),
+        progress in 0.0f32..100.0,
+    ) {
+        // A retry re-queues one job next to results of the previous run
+        let mut queue = QueueState::new();
+        queue.jobs = earlier.into_iter().map(job).collect();
+        queue.jobs.push(job(JobStatus::Encoding { progress }));
+        queue.current_job_index = queue.jobs.len() - 1;
+        queue.total_jobs_to_encode = 1;
+
+        let overall = queue.overall_progress();
+        prop_assert!((overall - progress).abs() < 1e-3, "{} vs {}", overall, progress);
+    }
+
+    #[test]
+    fn eta_never_panics(
+        samples in prop::collection::vec((any_duration(), any::<f32>()), 0..20),
+        elapsed in any_duration(),
+        progress in any::<f32>(),
+    ) {
+        let mut eta = EtaEstimator::default();
+        for (elapsed, progress) in samples {
+            eta.record(elapsed, progress);
+        }
+        let remaining = eta.remaining(elapsed, progress);
+        if !(progress > 0.0 && progress < 100.0) {
+            prop_assert!(remaining.is_none());
+        }
+    }
+
+    #[test]
+    fn eta_matches_a_steady_rate(rate in 0.01f64..10.0, steps in 2u32..50) {
+        // Progress advances `rate` percent per second, sampled every 2 seconds
+        let mut eta = EtaEstimator::default();
+        let mut last = 0.0;
+        for step in 1..=steps {
+            let secs = step as f64 * 2.0;
+            let progress = (rate * secs).min(99.0);
+            last = progress;
+            eta.record(Duration::from_secs_f64(secs), progress as f32);
+        }
+
+        let elapsed = Duration::from_secs_f64(steps as f64 * 2.0);
+        let expected = (100.0 - last) / rate;
+        if last < 99.0 {
+            let remaining = eta.remaining(elapsed, last as f32).unwrap().as_secs_f64();
+            prop_assert!((remaining - expected).abs() <= expected * 0.01 + 0.01,
+                "{} vs {}", remaining, expected);
+        }
+    }
+
+    #[test]
+    fn size_reduction_is_a_valid_percentage(
+        source in prop::option::of(any::<u64>()),
+        output in prop::option::of(any::<u64>()),
+    ) {
+        let mut job = job(JobStatus::Done);
+        job.source_size = source;
+        job.output_size = output;
+
+        match job.size_reduction() {
+            Some((saved, percent)) => {
+                let source = source.unwrap();
+                prop_assert!(source > 0);
+                prop_assert!(saved <= source);
+                prop_assert!((0.0..=100.0).contains(&percent), "{}", percent);
+            }
+            None => prop_assert!(source.is_none() || output.is_none() || source == Some(0)),
+        }
+    }
+
+    #[test]
+    fn total_space_saved_does_not_overflow(
+        sizes in prop::collection::vec((any::<u64>(), any::<u64>()), 0..20),
+    ) {
+        let mut queue = QueueState::new();
+        queue.jobs = sizes
+            .iter()
+            .map(|&(source, output)| {
+                let mut job = job(JobStatus::Done);
+                job.source_size = Some(source);
+                job.output_size = Some(output);
+                job
+            })
+            .collect();
+
+        let (total, label) = queue.total_space_saved();
+        let expected = sizes
+            .iter()
+            .map(|&(source, output)| source.saturating_sub(output) as u128)
+            .sum::<u128>()
+            .min(u64::MAX as u128) as u64;
+        prop_assert_eq!(total, expected);
+        prop_assert!(!label.is_empty());
+    }
+
+}
+
+#[test]
+fn empty_run_has_no_progress_or_eta() {
+    let queue = QueueState::new();
+    assert_eq!(queue.overall_progress(), 0.0);
+    assert_eq!(queue.estimated_time_remaining(), None);
+    assert_eq!(overall_progress(0, 0, f32::NAN), 0.0);
+}
+
+#[test]
+fn verification_after_the_encode_is_stretched_by_past_pacing() {
+    let mut earlier = StageTimings::default();
+    earlier.record(Stage::Prepare, Duration::from_secs(10));
+    earlier.record(Stage::Encode, Duration::from_secs(100));
+    earlier.record(Stage::Verify, Duration::from_secs(50));
+    let mut queue = QueueState::new();
+    queue.pacing.add(&earlier, Some(1_000_000));
+
+    // Encode finished 25 of the 50 seconds verification usually takes ago
+    let mut verifying = job(JobStatus::Encoding { progress: 100.0 });
+    verifying.stage_timings = earlier;
+    verifying.phase_started = Instant::now().checked_sub(Duration::from_secs(25));
+    let progress = queue.job_progress(&verifying);
+    assert!((progress - 1.35 / 1.6 * 100.0).abs() < 0.5, "{}", progress);
+    let remaining = queue.job_time_remaining(&verifying).unwrap();
+    assert!(
+        remaining.as_secs() <= 25 && remaining.as_secs() >= 23,
+        "{:?}",
+        remaining
+    );
+
+    // Halfway through the encode, verification is still ahead
+    let mut encoding = job(JobStatus::Encoding { progress: 50.0 });
+    encoding
+        .stage_timings
+        .record(Stage::Prepare, Duration::from_secs(10));
+    encoding.phase_started = Instant::now().checked_sub(Duration::from_secs(50));
+    let progress = queue.job_progress(&encoding);
+    assert!((progress - 0.6 / 1.6 * 100.0).abs() < 0.5, "{}", progress);
+    let remaining = queue.job_time_remaining(&encoding).unwrap();
+    assert!(
+        remaining.as_secs() >= 99 && remaining.as_secs() <= 101,
+        "{:?}",
+        remaining
+    );
+}
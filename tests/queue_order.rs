@@ -0,0 +1,73 @@
+//! Ordering the queue by size or duration before a run.
+
+use av1converter::config::{Cycle, QueueOrder};
+use av1converter::queue::{EncodingJob, JobStatus, QueueState};
+use proptest::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+fn job(status: JobStatus) -> EncodingJob {
+    let mut job = EncodingJob::new(PathBuf::from("/nonexistent/movie.mkv"));
+    job.status = status;
+    job
+}
+
+proptest! {
+    #[test]
+    fn ordering_keeps_every_job_and_puts_unknown_sizes_last(
+        sizes in prop::collection::vec(prop::option::of(0u64..1 << 40), 0..20),
+        order in prop::sample::select(QueueOrder::ALL.to_vec()),
+    ) {
+        let mut queue = QueueState::new();
+        queue.jobs = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &size)| {
+                let mut job = job(JobStatus::Pending);
+                job.path = PathBuf::from(format!("/nonexistent/{}.mkv", i));
+                job.source_size = size;
+                job
+            })
+            .collect();
+        // Every other file was probed, its duration following its size
+        let durations: HashMap<usize, f64> = sizes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % 2 == 0)
+            .map(|(i, size)| (i, size.unwrap_or(0) as f64 + 1.0))
+            .collect();
+
+        queue.order_jobs(order, &durations);
+
+        let mut names: Vec<PathBuf> = queue.jobs.iter().map(|j| j.path.clone()).collect();
+        names.sort();
+        let mut expected: Vec<PathBuf> = (0..sizes.len())
+            .map(|i| PathBuf::from(format!("/nonexistent/{}.mkv", i)))
+            .collect();
+        expected.sort();
+        prop_assert_eq!(names, expected);
+
+        let ordered: Vec<Option<u64>> = queue.jobs.iter().map(|j| j.source_size).collect();
+        match order {
+            QueueOrder::AsIs => prop_assert_eq!(ordered, sizes),
+            QueueOrder::SmallestFirst | QueueOrder::LargestFirst => {
+                let known = ordered.iter().take_while(|s| s.is_some()).count();
+                prop_assert!(ordered[known..].iter().all(Option::is_none));
+                let known: Vec<u64> = ordered.iter().flatten().copied().collect();
+                let sorted = |w: &[u64]| match order {
+                    QueueOrder::SmallestFirst => w[0] <= w[1],
+                    _ => w[0] >= w[1],
+                };
+                prop_assert!(known.windows(2).all(sorted), "{:?}", known);
+            }
+            QueueOrder::ShortestFirst => {
+                let probed = durations.len();
+                let probed_sizes: Vec<u64> = ordered[..probed]
+                    .iter()
+                    .map(|s| s.unwrap_or(0))
+                    .collect();
+                prop_assert!(probed_sizes.windows(2).all(|w| w[0] <= w[1]));
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Every screen renders at any terminal size, falling back to a compact view.
+
+use av1converter::app::{App, ConfirmAction, Screen};
+use av1converter::queue::{EncodingJob, JobStatus};
+use av1converter::ui;
+use av1converter::utils::paths;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn draw(app: &mut App, width: u16, height: u16) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| ui::render(f, app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    buffer.content().iter().map(|cell| cell.symbol()).collect()
+}
+
+#[test]
+fn screens_survive_tiny_terminals() {
+    let dir = TempDir::new().unwrap();
+    paths::set_config_dir(dir.path().to_path_buf());
+    let mut app = App::new();
+    let mut job = EncodingJob::new(PathBuf::from("/nonexistent/movie.mkv"));
+    job.status = JobStatus::Encoding { progress: 42.0 };
+    app.queue.jobs.push(job);
+    app.queue.total_jobs_to_encode = 1;
+    app.encoding_active = true;
+
+    let screens = [
+        Screen::Home,
+        Screen::FileExplorer {
+            select_folder: false,
+        },
+        Screen::FileConfirm,
+        Screen::DiscTitles,
+        Screen::Audit,
+        Screen::Cleanup,
+        Screen::Queue,
+        Screen::Finish,
+        Screen::Configuration,
+        Screen::Presets,
+    ];
+    for screen in screens {
+        app.current_screen = screen;
+        for (width, height) in [(1, 1), (20, 5), (45, 12), (80, 24), (200, 60)] {
+            draw(&mut app, width, height);
+        }
+    }
+
+    // A narrow pane still shows the running encode
+    app.current_screen = Screen::Queue;
+    let text = draw(&mut app, 40, 6);
+    assert!(text.contains("movie.mkv"), "{}", text);
+    assert!(text.contains("ETA"), "{}", text);
+
+    // and a pending question can still be answered
+    app.confirm_dialog = Some(ConfirmAction::CancelEncoding);
+    let text = draw(&mut app, 40, 6);
+    assert!(text.contains("Cancel Encoding"), "{}", text);
+}
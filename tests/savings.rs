@@ -0,0 +1,69 @@
+//! Space saved by a batch, split into video and dropped tracks.
+
+use av1converter::analyzer::{HdrType, VideoMetadata};
+use av1converter::queue::{EncodingJob, JobStatus, QueueState};
+use av1converter::tracks::{AudioTrack, TrackSelection};
+use proptest::prelude::*;
+use std::path::PathBuf;
+
+fn job(status: JobStatus) -> EncodingJob {
+    let mut job = EncodingJob::new(PathBuf::from("/nonexistent/movie.mkv"));
+    job.status = status;
+    job
+}
+
+proptest! {
+    #[test]
+    fn space_savings_add_up_to_the_size_reduction(
+        files in prop::collection::vec(
+            (1u64..1 << 40, 0u64..1 << 40, prop::collection::vec((0u64..2_000_000, any::<bool>()), 0..4)),
+            0..6,
+        ),
+        duration in 0.0f64..20_000.0,
+    ) {
+        let mut queue = QueueState::new();
+        let mut dropped_audio = 0u64;
+        for (source, output, tracks) in files {
+            let mut job = job(JobStatus::Done);
+            job.source_size = Some(source);
+            job.output_size = Some(output);
+            job.metadata = Some(VideoMetadata {
+                width: 1920,
+                height: 1080,
+                hdr_type: HdrType::Sdr,
+                codec_name: "h264".to_string(),
+                pixel_format: None,
+                frame_rate_num: 24,
+                frame_rate_den: 1,
+                duration_secs: duration,
+                bitrate: None,
+            });
+            job.audio_tracks = tracks
+                .iter()
+                .enumerate()
+                .map(|(index, &(bitrate, _))| AudioTrack {
+                    index,
+                    language: None,
+                    codec: "ac3".to_string(),
+                    channels: 6,
+                    title: None,
+                    bitrate: Some(bitrate),
+                    sample_rate: None,
+                    disposition: Default::default(),
+                })
+                .collect();
+            job.track_selection = TrackSelection {
+                audio_indices: (0..tracks.len()).filter(|&i| tracks[i].1).collect(),
+                subtitle_indices: Vec::new(),
+            };
+            dropped_audio += job.dropped_track_savings().0;
+            queue.jobs.push(job);
+        }
+
+        let (total, _) = queue.total_space_saved();
+        let savings = queue.space_savings();
+        prop_assert_eq!(savings.total(), total);
+        prop_assert!(savings.audio <= dropped_audio);
+        prop_assert_eq!(savings.subtitles, 0);
+    }
+}
@@ -0,0 +1,58 @@
+//! Encode window parsing and the times it lets encodes start at.
+
+use av1converter::queue::EncodeWindow;
+use proptest::prelude::*;
+
+fn window(s: &str) -> EncodeWindow {
+    s.parse().unwrap()
+}
+
+#[test]
+fn windows_parse_and_print() {
+    assert_eq!(
+        window("23:00-07:30"),
+        EncodeWindow {
+            start: 23 * 60,
+            end: 7 * 60 + 30
+        }
+    );
+    assert_eq!(window(" 9:05 – 17:00 ").to_string(), "09:05–17:00");
+    for bad in ["23:00", "24:00-07:00", "23:60-07:00", "late-early", ""] {
+        assert!(bad.parse::<EncodeWindow>().is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn overnight_window_wraps_past_midnight() {
+    let night = window("23:00-07:00");
+    assert!(night.contains(23 * 60));
+    assert!(night.contains(0));
+    assert!(night.contains(6 * 60 + 59));
+    assert!(!night.contains(7 * 60));
+    assert!(!night.contains(12 * 60));
+
+    let day = window("09:00-17:00");
+    assert!(day.contains(9 * 60));
+    assert!(!day.contains(17 * 60));
+    assert!(!day.contains(23 * 60));
+}
+
+proptest! {
+    #[test]
+    fn a_minute_is_in_a_window_or_its_complement(
+        start in 0u16..1440,
+        end in 0u16..1440,
+        minute in 0u16..1440,
+    ) {
+        prop_assume!(start != end);
+        let window = EncodeWindow { start, end };
+        let complement = EncodeWindow { start: end, end: start };
+        prop_assert!(window.contains(minute) != complement.contains(minute));
+    }
+
+    #[test]
+    fn equal_start_and_end_is_always_open(start in 0u16..1440, minute in 0u16..1440) {
+        let always = EncodeWindow { start, end: start };
+        prop_assert!(always.contains(minute));
+    }
+}
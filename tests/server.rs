@@ -0,0 +1,177 @@
+//! The HTTP control server, against the mock executables.
+#![cfg(unix)]
+
+use av1converter::AppConfig;
+use av1converter::app::App;
+use av1converter::config::{DeleteMode, Encoder, ToolsConfig};
+use av1converter::server::{self, Route};
+use av1converter::utils::paths;
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn fixture_tools() -> ToolsConfig {
+    let bin = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bin");
+    let path = |name: &str| bin.join(name).to_string_lossy().to_string();
+    ToolsConfig {
+        ffmpeg: path("ffmpeg"),
+        ffprobe: path("ffprobe"),
+        mkvmerge: path("mkvmerge"),
+    }
+}
+
+/// Send a request and return the status code and JSON body
+fn request(addr: SocketAddr, method: &str, path: &str, body: &str, token: &str) -> (u16, Value) {
+    let head = format!(
+        "{} {} HTTP/1.1\r\nHost: test\r\nAuthorization: Bearer {}\r\n\
+         Content-Type: application/json\r\nContent-Length: {}\r\n",
+        method,
+        path,
+        token,
+        body.len()
+    );
+    send(addr, &head, body)
+}
+
+/// Send a request with the head lines given, and return the status code and JSON body
+fn send(addr: SocketAddr, head: &str, body: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "{}\r\n{}", head, body).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn requests_map_to_routes() {
+    assert_eq!(server::route("GET", "/queue", b""), Ok(Route::Queue));
+    assert_eq!(
+        server::route("POST", "/jobs", br#"{"paths": ["/videos/a.mkv"]}"#),
+        Ok(Route::Submit(vec![PathBuf::from("/videos/a.mkv")]))
+    );
+    assert_eq!(
+        server::route("POST", "/jobs/3/cancel", b""),
+        Ok(Route::CancelJob(3))
+    );
+    assert_eq!(server::route("POST", "/cancel", b""), Ok(Route::CancelAll));
+    assert_eq!(
+        server::route("DELETE", "/queue", b"").unwrap_err().status,
+        405
+    );
+    assert_eq!(
+        server::route("POST", "/jobs", b"[]").unwrap_err().status,
+        400
+    );
+    assert_eq!(server::route("GET", "/other", b"").unwrap_err().status, 404);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn submitted_files_are_encoded_and_reported() {
+    let config_dir = TempDir::new().unwrap();
+    paths::set_config_dir(config_dir.path().to_path_buf());
+    let mut config = AppConfig {
+        encoder: Encoder::SvtAv1,
+        tools: fixture_tools(),
+        ..AppConfig::default()
+    };
+    config.quality.vmaf_enabled = false;
+    config.output.remux_after_encode = false;
+    config.performance.chunked_encoding = false;
+    config.quality.delete_mode = DeleteMode::Permanent;
+    config.save().unwrap();
+
+    let videos = TempDir::new().unwrap();
+    let movie = videos.path().join("movie.mkv");
+    std::fs::write(&movie, "fake video\n").unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut app = App::new();
+        server::serve(&mut app, listener, Some("secret".to_string())).await
+    });
+
+    let body = serde_json::json!({ "paths": [movie] }).to_string();
+    let status = tokio::task::spawn_blocking(move || {
+        assert_eq!(request(addr, "GET", "/queue", "", "wrong").0, 401);
+        let (status, answer) = request(addr, "POST", "/jobs", &body, "secret");
+        assert_eq!(answer["queued"], 1);
+        assert_eq!(status, 202);
+
+        for _ in 0..100 {
+            let (_, queue) = request(addr, "GET", "/queue", "", "secret");
+            if queue["jobs"][0]["status"] == "done" && queue["running"] == false {
+                return queue;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        panic!("the job did not finish");
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(status["converted"], 1);
+    assert_eq!(status["jobs"][0]["id"], 0);
+    assert_eq!(status["jobs"][0]["file"], "movie.mkv");
+    let cancel = tokio::task::spawn_blocking(move || {
+        request(addr, "POST", "/jobs/0/cancel", "", "secret").0
+    });
+    assert_eq!(cancel.await.unwrap(), 409);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_server_without_token_turns_away_what_browsers_could_send() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut app = App::new();
+        server::serve(&mut app, listener, None).await
+    });
+
+    let statuses = tokio::task::spawn_blocking(move || {
+        let host = format!("Host: localhost:{}\r\n", addr.port());
+        let body = r#"{"paths": ["/nonexistent"]}"#;
+        let post = |extra: &str| {
+            let head = format!(
+                "POST /jobs HTTP/1.1\r\n{}{}Content-Length: {}\r\n",
+                host,
+                extra,
+                body.len()
+            );
+            send(addr, &head, body).0
+        };
+        let long_header = format!("{}X-Filler: {}\r\n", host, "a".repeat(50_000));
+        let many_headers = format!("{}{}", host, "X-Filler: a\r\n".repeat(100));
+        [
+            // A form posted by a web page
+            post("Content-Type: text/plain\r\n"),
+            post(""),
+            // The same JSON from a real client gets through to the route
+            post("Content-Type: application/json; charset=utf-8\r\n"),
+            // A page whose DNS name was rebound to this machine
+            send(addr, "GET /queue HTTP/1.1\r\nHost: evil.example:80\r\n", "").0,
+            send(addr, &format!("GET /queue HTTP/1.1\r\n{}", host), "").0,
+            send(
+                addr,
+                &format!("GET /queue HTTP/1.1\r\nHost: {}\r\n", addr),
+                "",
+            )
+            .0,
+            send(addr, &format!("GET /queue HTTP/1.1\r\n{}", long_header), "").0,
+            send(
+                addr,
+                &format!("GET /queue HTTP/1.1\r\n{}", many_headers),
+                "",
+            )
+            .0,
+        ]
+    })
+    .await
+    .unwrap();
+    assert_eq!(statuses, [415, 415, 400, 403, 200, 200, 431, 431]);
+}
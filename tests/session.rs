@@ -0,0 +1,157 @@
+//! The session state saved on quit and restored at the next start.
+
+use av1converter::AppConfig;
+use av1converter::app::{App, ConfirmAction, SelectionMode};
+use av1converter::config::ToolsConfig;
+use av1converter::encoder::JobMode;
+use av1converter::queue::{EncodingJob, JobStatus, SavedJob, SavedQueue};
+use av1converter::tracks::TrackSelection;
+use av1converter::utils::{SessionState, paths};
+use std::path::Path;
+use tempfile::TempDir;
+use tokio::sync::Mutex;
+
+/// The configuration directory is process-wide, so the tests take turns
+static CONFIG_DIR: Mutex<()> = Mutex::const_new(());
+
+#[tokio::test]
+async fn a_new_session_starts_where_the_last_one_left_off() {
+    let _turn = CONFIG_DIR.lock().await;
+    let config_dir = TempDir::new().unwrap();
+    paths::set_config_dir(config_dir.path().to_path_buf());
+    AppConfig::default().save().unwrap();
+    let videos = TempDir::new().unwrap();
+
+    // Nothing saved yet starts a fresh session
+    let mut app = App::new();
+    app.restore_session();
+    assert_eq!(app.selection_mode, SelectionMode::File);
+    assert!(app.active_profiles.is_empty());
+    assert_eq!(app.home_index, 0);
+
+    app.current_dir = videos.path().to_path_buf();
+    app.navigate_to_explorer(true, true);
+    app.toggle_profile(app.config.profiles.len() - 1);
+    app.save_session();
+    let profile = app.config.profiles.last().unwrap().name.clone();
+    assert_eq!(SessionState::load().profiles, vec![profile]);
+
+    let mut app = App::new();
+    app.restore_session();
+    assert_eq!(app.current_dir, videos.path());
+    assert_eq!(app.selection_mode, SelectionMode::Folder);
+    assert!(app.recursive_scan);
+    assert_eq!(app.active_profiles, vec![app.config.profiles.len() - 1]);
+    assert_eq!(app.home_index, 2);
+
+    // A folder deleted since is not restored
+    let gone = videos.path().to_path_buf();
+    drop(videos);
+    let mut app = App::new();
+    app.restore_session();
+    assert_ne!(app.current_dir, gone);
+}
+
+/// Feed background updates to `app` until every file is analyzed
+async fn finish_analysis(app: &mut App) {
+    while app.files_analyzing() > 0 {
+        let update = app.recv_background().await;
+        app.process_background(update);
+    }
+}
+
+#[tokio::test]
+async fn an_unfinished_queue_is_offered_at_the_next_start() {
+    let _turn = CONFIG_DIR.lock().await;
+    let config_dir = TempDir::new().unwrap();
+    paths::set_config_dir(config_dir.path().to_path_buf());
+    let bin = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/bin");
+    let tool = |name: &str| bin.join(name).to_string_lossy().to_string();
+    let config = AppConfig {
+        tools: ToolsConfig {
+            ffmpeg: tool("ffmpeg"),
+            ffprobe: tool("ffprobe"),
+            mkvmerge: tool("mkvmerge"),
+        },
+        ..AppConfig::default()
+    };
+    config.save().unwrap();
+    let videos = TempDir::new().unwrap();
+    let files: Vec<_> = ["a.mkv", "b.mkv"]
+        .iter()
+        .map(|name| videos.path().join(name))
+        .collect();
+    for file in &files {
+        std::fs::write(file, "fake video\n").unwrap();
+    }
+
+    // Files only picked, not confirmed, are nothing to restore
+    let mut app = App::new();
+    app.open_paths(&files);
+    app.save_queue();
+    assert_eq!(SavedQueue::load(), None);
+
+    app.confirm_queued_files();
+    finish_analysis(&mut app).await;
+    app.current_config_job_mut().unwrap().crf = Some(31);
+    app.confirm_track_config();
+    // Handed to the worker while the other file is configured, not yet started
+    assert!(matches!(app.queue.jobs[0].status, JobStatus::Pending));
+    app.save_queue();
+    let saved = SavedQueue::load().unwrap();
+    assert_eq!(saved.jobs.len(), 2);
+    assert!(saved.jobs[0].configured);
+    app.cancel_encoding();
+    drop(app);
+
+    let mut app = App::new();
+    app.offer_saved_queue();
+    assert_eq!(app.confirm_dialog, Some(ConfirmAction::RestoreQueue));
+    assert_eq!(app.accept_confirm(), Some(ConfirmAction::RestoreQueue));
+    app.restore_queue();
+    finish_analysis(&mut app).await;
+    let jobs = &app.queue.jobs;
+    assert_eq!(jobs.len(), 2);
+    // Back with the worker right away, while the other file is configured
+    assert!(!matches!(
+        jobs[0].status,
+        JobStatus::AwaitingConfig | JobStatus::Ready
+    ));
+    assert_eq!(jobs[0].crf, Some(31));
+    assert!(matches!(jobs[1].status, JobStatus::AwaitingConfig));
+    assert_eq!(jobs[1].crf, None);
+    app.cancel_encoding();
+
+    // Declining starts afresh
+    let mut app = App::new();
+    app.offer_saved_queue();
+    app.dismiss_confirm();
+    app.save_queue();
+    assert_eq!(SavedQueue::load(), None);
+    let mut app = App::new();
+    app.offer_saved_queue();
+    assert_eq!(app.confirm_dialog, None);
+}
+
+#[test]
+fn a_restored_job_never_configured_keeps_what_analysis_picked() {
+    let mut analyzing = EncodingJob::new("/videos/a.mkv".into());
+    analyzing.status = JobStatus::Analyzing;
+    let saved = SavedJob::of(&analyzing).unwrap();
+    assert!(!saved.configured);
+
+    let mut job = saved.to_job();
+    job.track_selection = TrackSelection {
+        audio_indices: vec![1, 2],
+        subtitle_indices: vec![3],
+    };
+    job.profiles = AppConfig::default().profiles[..1].to_vec();
+    job.mode = JobMode::Remux;
+    job.status = JobStatus::AwaitingConfig;
+    saved.apply(&mut job);
+    assert_eq!(job.track_selection.audio_indices, vec![1, 2]);
+    assert_eq!(job.track_selection.subtitle_indices, vec![3]);
+    assert_eq!(job.profiles.len(), 1);
+    assert_eq!(job.mode, JobMode::Remux);
+    assert!(matches!(job.status, JobStatus::AwaitingConfig));
+}
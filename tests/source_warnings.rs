@@ -0,0 +1,143 @@
+//! Sources already in an efficient codec, flagged before they are analyzed.
+
+use av1converter::app::{App, ConfirmAction, Screen};
+use av1converter::audit::{AuditEntry, CodecFamily};
+use av1converter::queue::EncodingJob;
+use av1converter::ui;
+use av1converter::utils::paths;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use tokio::sync::Mutex;
+
+/// The configuration directory is process-wide, so the tests take turns
+static CONFIG_DIR: Mutex<()> = Mutex::const_new(());
+
+fn draw(app: &mut App, width: u16, height: u16) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| ui::render(f, app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    buffer.content().iter().map(|cell| cell.symbol()).collect()
+}
+
+#[test]
+fn efficient_sources_are_flagged_and_need_confirming() {
+    let _turn = CONFIG_DIR.blocking_lock();
+    let dir = TempDir::new().unwrap();
+    paths::set_config_dir(dir.path().to_path_buf());
+    let mut app = App::new();
+    app.current_screen = Screen::FileConfirm;
+    for (i, (name, family)) in [
+        ("movie.mkv", CodecFamily::H264),
+        ("clip.webm", CodecFamily::Vp9),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        app.queue.jobs.push(EncodingJob::new(PathBuf::from(name)));
+        let size = 1_000_000_000;
+        let entry = AuditEntry {
+            path: PathBuf::from(name),
+            codec: family.label().to_string(),
+            family,
+            width: 1920,
+            height: 1080,
+            duration_secs: 3600.0,
+            size,
+            estimated_size: (size as f64 * family.size_ratio()) as u64,
+            bits_per_pixel: None,
+        };
+        app.file_probes.insert(i, Some(entry));
+    }
+
+    let text = draw(&mut app, 120, 30);
+    assert!(text.contains("1 file is already AV1/VP9"), "{}", text);
+    assert!(text.contains("(15%)"), "{}", text);
+
+    // Enter asks first instead of starting the analysis
+    app.proceed_from_file_confirm();
+    assert_eq!(
+        app.confirm_dialog,
+        Some(ConfirmAction::ConvertEfficientSources)
+    );
+    assert!(!app.confirm_selection);
+    assert_eq!(app.current_screen, Screen::FileConfirm);
+
+    // AV1 sources are skipped after analysis unless re-encoding them is on
+    app.confirm_dialog = None;
+    app.file_probes.remove(&1);
+    app.queue.jobs.truncate(1);
+    let mut av1 = app.file_probes[&0].clone().unwrap();
+    av1.family = CodecFamily::Av1;
+    app.file_probes.insert(0, Some(av1));
+    assert!(app.efficient_sources().is_empty());
+    app.config.quality.reencode_av1 = true;
+    assert_eq!(app.efficient_sources().len(), 1);
+}
+
+#[tokio::test]
+async fn an_answer_applied_to_all_lasts_for_the_session() {
+    let _turn = CONFIG_DIR.lock().await;
+    let dir = TempDir::new().unwrap();
+    paths::set_config_dir(dir.path().to_path_buf());
+    let batch = |app: &mut App| {
+        app.current_screen = Screen::FileConfirm;
+        app.queue.jobs = vec![EncodingJob::new(PathBuf::from("clip.webm"))];
+        let entry = AuditEntry {
+            path: PathBuf::from("clip.webm"),
+            codec: CodecFamily::Vp9.label().to_string(),
+            family: CodecFamily::Vp9,
+            width: 1920,
+            height: 1080,
+            duration_secs: 60.0,
+            size: 1_000_000,
+            estimated_size: 150_000,
+            bits_per_pixel: None,
+        };
+        app.file_probes.insert(0, Some(entry));
+        app.proceed_from_file_confirm();
+    };
+
+    // A plain yes is asked again for the next batch
+    let mut app = App::new();
+    batch(&mut app);
+    assert_eq!(
+        app.accept_confirm(),
+        Some(ConfirmAction::ConvertEfficientSources)
+    );
+    assert!(app.session_answers.is_empty());
+    batch(&mut app);
+    assert_eq!(
+        app.confirm_dialog,
+        Some(ConfirmAction::ConvertEfficientSources)
+    );
+
+    // With "apply to all" it is not asked again
+    app.confirm_apply_all = true;
+    assert_eq!(
+        app.accept_confirm(),
+        Some(ConfirmAction::ConvertEfficientSources)
+    );
+    assert!(!app.confirm_apply_all);
+    batch(&mut app);
+    assert_eq!(app.confirm_dialog, None);
+
+    // One-off questions are never remembered
+    app.confirm_dialog = Some(ConfirmAction::ExitApp);
+    app.confirm_apply_all = true;
+    assert_eq!(app.accept_confirm(), Some(ConfirmAction::ExitApp));
+    assert!(!app.session_answers.contains(&ConfirmAction::ExitApp));
+    assert!(ConfirmAction::StartWithoutSpace.can_apply_to_all());
+
+    // The next start asks again
+    app.save_session();
+    let mut app = App::new();
+    app.restore_session();
+    assert!(app.session_answers.is_empty());
+    batch(&mut app);
+    assert_eq!(
+        app.confirm_dialog,
+        Some(ConfirmAction::ConvertEfficientSources)
+    );
+}
@@ -0,0 +1,102 @@
+//! Free space per output volume, checked before a queue starts.
+
+use av1converter::config::OutputConfig;
+use av1converter::queue::EncodingJob;
+use av1converter::queue::preflight::space_needs_with;
+use av1converter::utils::space::{Volume, parse_df};
+use std::path::{Path, PathBuf};
+
+const GB: u64 = 1_000_000_000;
+
+#[test]
+fn df_output_gives_the_mount_point_and_available_bytes() {
+    let stdout = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                  /dev/sdb1       1921724676 123456789 1000000 7% /mnt/My Videos\n";
+    assert_eq!(
+        parse_df(stdout),
+        Some(Volume {
+            mount: PathBuf::from("/mnt/My Videos"),
+            free: 1_024_000_000,
+        })
+    );
+    assert_eq!(parse_df("Filesystem 1024-blocks Used Available\n"), None);
+    assert_eq!(parse_df(""), None);
+}
+
+fn job(path: &str, size: u64) -> EncodingJob {
+    let mut job = EncodingJob::new(path.into());
+    job.source_size = Some(size);
+    job
+}
+
+/// `/media` and `/backup` are separate disks, anything else is unknown
+fn volume_of(dir: &Path) -> Option<Volume> {
+    let (mount, free) = if dir.starts_with("/media") {
+        ("/media", 10 * GB)
+    } else if dir.starts_with("/backup") {
+        ("/backup", 100 * GB)
+    } else {
+        return None;
+    };
+    Some(Volume {
+        mount: mount.into(),
+        free,
+    })
+}
+
+#[test]
+fn outputs_are_summed_per_volume() {
+    let jobs = vec![
+        job("/media/movies/a.mkv", 8 * GB),
+        job("/media/shows/b.mkv", 6 * GB),
+        job("/backup/c.mkv", 20 * GB),
+        job("/elsewhere/d.mkv", GB),
+    ];
+    // The first file is probed as H.264 and shrinks to half
+    let needs = space_needs_with(
+        &jobs,
+        &OutputConfig::default(),
+        |i, _| (i == 0).then_some(4 * GB),
+        volume_of,
+    );
+
+    assert_eq!(needs.len(), 3);
+    let backup = &needs[0];
+    assert_eq!(backup.volume, Path::new("/backup"));
+    assert_eq!((backup.files, backup.estimated_size), (1, 20 * GB));
+    assert!(!backup.is_short());
+
+    let media = &needs[1];
+    assert_eq!(media.volume, Path::new("/media"));
+    assert_eq!(media.files, 2);
+    assert_eq!(media.source_size, 14 * GB);
+    assert_eq!(media.estimated_size, 10 * GB);
+    assert!(!media.is_short(), "an exact fit still fits");
+
+    // An unknown volume is listed by folder and never judged short
+    let unknown = &needs[2];
+    assert_eq!(unknown.volume, Path::new("/elsewhere"));
+    assert_eq!(unknown.free, None);
+    assert!(!unknown.is_short());
+    assert!(unknown.describe().contains("free space unknown"));
+}
+
+#[test]
+fn a_custom_output_folder_moves_the_need_to_its_volume() {
+    let output = OutputConfig {
+        same_directory: false,
+        output_directory: Some("/media/av1".to_string()),
+        ..OutputConfig::default()
+    };
+    let jobs = vec![job("/backup/a.mkv", 7 * GB), job("/backup/b.mkv", 7 * GB)];
+    let needs = space_needs_with(&jobs, &output, |_, _| None, volume_of);
+
+    assert_eq!(needs.len(), 1);
+    assert_eq!(needs[0].volume, Path::new("/media"));
+    assert!(needs[0].is_short());
+    assert!(
+        needs[0].describe().starts_with("/media: 2 files"),
+        "{}",
+        needs[0].describe()
+    );
+}
@@ -0,0 +1,47 @@
+//! Opt-in anonymous encode statistics.
+
+use av1converter::AppConfig;
+use av1converter::config::{Encoder, StatsMode};
+use av1converter::history::HistoryRecord;
+use av1converter::history::stats::{StatsRecord, append, stats_path, submission_body};
+use av1converter::utils::paths;
+use tempfile::TempDir;
+
+#[test]
+fn statistics_leave_out_everything_about_the_file() {
+    let dir = TempDir::new().unwrap();
+    paths::set_config_dir(dir.path().to_path_buf());
+    assert_eq!(AppConfig::default().stats.mode, StatsMode::Off);
+
+    let history = HistoryRecord {
+        timestamp: 1_760_000_000,
+        file: "Family Holiday 2024.mkv".to_string(),
+        encoder: Encoder::SvtAv1,
+        preset: "full_hd_hdr".to_string(),
+        quality: 28,
+        vmaf: Some(94.437),
+        source_size: Some(8_000_000_000),
+        output_size: Some(2_000_000_000),
+        encode_secs: Some(1200.0),
+        before_encode_secs: Some(20.0),
+        after_encode_secs: Some(300.0),
+    };
+    let record = StatsRecord::from_history(&history, 3000.0);
+    assert_eq!(record.preset, "full_hd_hdr");
+    assert_eq!(record.quality, 28);
+    assert_eq!(record.vmaf, Some(94.4));
+    assert_eq!(record.speed, Some(2.5));
+
+    // The local file holds the same schema that is submitted
+    append(&record).unwrap();
+    let local = std::fs::read_to_string(stats_path()).unwrap();
+    assert_eq!(stats_path(), dir.path().join("stats.jsonl"));
+    let body = submission_body(std::slice::from_ref(&record)).unwrap();
+    let submitted: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let written: serde_json::Value = serde_json::from_str(local.trim()).unwrap();
+    assert_eq!(submitted["records"][0], written);
+
+    for leak in ["Family", "8000000000", "1760000000"] {
+        assert!(!body.contains(leak), "{} in {}", leak, body);
+    }
+}
@@ -0,0 +1,31 @@
+//! Per-stage timings and the timeline drawn from them.
+
+use av1converter::encoder::{Stage, StageTimings};
+use proptest::prelude::*;
+use std::time::Duration;
+
+proptest! {
+    #[test]
+    fn timeline_shares_add_up_and_rows_line_up(
+        secs in prop::collection::vec(prop::option::of(0u64..200_000), Stage::ALL.len()),
+    ) {
+        let mut timings = StageTimings::default();
+        for (&stage, secs) in Stage::ALL.iter().zip(&secs) {
+            if let Some(secs) = secs {
+                timings.record(stage, Duration::from_secs(*secs));
+            }
+        }
+
+        let timeline = timings.timeline();
+        prop_assert_eq!(timeline.len(), secs.iter().flatten().count());
+        let shares: f64 = timeline.iter().map(|&(_, _, share)| share).sum();
+        if timings.total() > Duration::ZERO {
+            prop_assert!((shares - 1.0).abs() < 1e-9, "{}", shares);
+            let (slowest, _) = timings.slowest().unwrap();
+            prop_assert!(timeline.iter().all(|&(_, d, _)| d <= timings.get(slowest).unwrap()));
+        }
+
+        let rows = timings.timeline_lines(20);
+        prop_assert!(rows.windows(2).all(|w| w[0].chars().count() == w[1].chars().count()));
+    }
+}
@@ -0,0 +1,46 @@
+//! Daily update check against GitHub releases.
+
+use av1converter::utils::deps::Version;
+use av1converter::utils::paths;
+use av1converter::utils::update::{latest_release, release_tag, update_cache_path};
+use std::cell::Cell;
+use tempfile::TempDir;
+
+/// Fetch answering `tag` and counting its calls
+fn counting<'a>(
+    fetches: &'a Cell<u32>,
+    tag: Option<&'a str>,
+) -> impl FnOnce() -> Option<String> + 'a {
+    move || {
+        fetches.set(fetches.get() + 1);
+        tag.map(str::to_string)
+    }
+}
+
+#[test]
+fn release_is_looked_up_once_a_day() {
+    let dir = TempDir::new().unwrap();
+    paths::set_config_dir(dir.path().to_path_buf());
+    let fetches = Cell::new(0);
+    let fetch = |tag| counting(&fetches, tag);
+
+    // A failed request is not remembered
+    assert_eq!(latest_release(fetch(None)), None);
+    assert!(!update_cache_path().exists());
+
+    let response = r#"{"tag_name": "v2.3.0", "name": "2.3.0", "draft": false}"#;
+    let tag = release_tag(response);
+    assert_eq!(tag.as_deref(), Some("v2.3.0"));
+    assert_eq!(
+        latest_release(fetch(tag.as_deref())),
+        Some(Version::new(2, 3, 0))
+    );
+
+    // The same day answers from the cache
+    assert_eq!(
+        latest_release(fetch(Some("v9.9.9"))),
+        Some(Version::new(2, 3, 0))
+    );
+    assert_eq!(fetches.get(), 2);
+    assert_eq!(release_tag("rate limit exceeded"), None);
+}